@@ -0,0 +1,304 @@
+use crate::error::{error_kind, ErrorKind};
+use crate::ipfs::IpfsClient;
+use crate::package_files::tree_sha256;
+use crate::resolver::MetadataSource;
+use crate::types::{LockFile, LockedPackage, PackageAccount, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// Outcome of one check on one locked package
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Present but different from what the lockfile recorded
+    Mismatch(String),
+    /// The directory, account or content is gone
+    Missing(String),
+    /// The check itself failed (RPC or gateway error)
+    Error(String),
+    /// Nothing to compare against, or not requested
+    Skipped(String),
+}
+
+impl CheckStatus {
+    pub fn failed(&self) -> bool {
+        matches!(self, CheckStatus::Mismatch(_) | CheckStatus::Missing(_) | CheckStatus::Error(_))
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "OK",
+            CheckStatus::Mismatch(_) => "MISMATCH",
+            CheckStatus::Missing(_) => "MISSING",
+            CheckStatus::Error(_) => "ERROR",
+            CheckStatus::Skipped(_) => "-",
+        }
+    }
+
+    fn detail(&self) -> Option<&str> {
+        match self {
+            CheckStatus::Ok => None,
+            CheckStatus::Mismatch(detail)
+            | CheckStatus::Missing(detail)
+            | CheckStatus::Error(detail)
+            | CheckStatus::Skipped(detail) => Some(detail),
+        }
+    }
+}
+
+/// Downloads a locked package's tarball again for `--deep`
+pub trait ContentSource {
+    /// Download `locked` and extract it into `dir`; returns the archive sha256
+    async fn fetch(&self, locked: &LockedPackage, dir: &Path) -> Result<String>;
+}
+
+impl ContentSource for IpfsClient {
+    async fn fetch(&self, locked: &LockedPackage, dir: &Path) -> Result<String> {
+        // No expected sha256: a different archive is reported, not retried
+        self.download_package(&locked.ipfs_cid, dir, None).await
+    }
+}
+
+/// Every check for one `antsol.lock` entry
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub name: String,
+    pub version: String,
+    /// Installed files against the lockfile's file hash
+    pub disk: CheckStatus,
+    /// On-chain account against the locked CID and authority
+    pub chain: CheckStatus,
+    /// Fresh IPFS download against the locked tarball hash (`--deep` only)
+    pub ipfs: CheckStatus,
+}
+
+impl AuditEntry {
+    pub fn failed(&self) -> bool {
+        self.disk.failed() || self.chain.failed() || self.ipfs.failed()
+    }
+}
+
+/// Result of `antsol verify` without a package, suitable for CI artifacts
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditReport {
+    pub ok: bool,
+    pub failed: usize,
+    pub packages: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    /// Table of per-check results, with the reason under each failing row
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![format!("{:<28} {:<12} {:<10} {:<10} {}", "PACKAGE", "VERSION", "DISK", "CHAIN", "IPFS")];
+        for entry in &self.packages {
+            lines.push(format!(
+                "{:<28} {:<12} {:<10} {:<10} {}",
+                entry.name,
+                entry.version,
+                entry.disk.label(),
+                entry.chain.label(),
+                entry.ipfs.label()
+            ));
+            for (check, status) in [("disk", &entry.disk), ("chain", &entry.chain), ("ipfs", &entry.ipfs)] {
+                if let (true, Some(detail)) = (status.failed(), status.detail()) {
+                    lines.push(format!("  ✗ {}: {}", check, detail));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Check every lockfile entry against `packages_dir`, the chain, and with
+/// `deep` a fresh download of its tarball
+pub async fn audit<S: MetadataSource, C: ContentSource>(
+    lock: &LockFile,
+    packages_dir: &Path,
+    chain: &S,
+    deep: Option<&C>,
+) -> AuditReport {
+    let mut packages = Vec::with_capacity(lock.packages.len());
+    for locked in &lock.packages {
+        let disk = check_disk(locked, &packages_dir.join(&locked.name));
+        let chain = check_chain(locked, chain.fetch_package(&locked.name, &locked.version).await);
+        let ipfs = match deep {
+            Some(content) => check_content(locked, content).await,
+            None => CheckStatus::Skipped("not downloaded (use --deep)".to_string()),
+        };
+        packages.push(AuditEntry {
+            name: locked.name.clone(),
+            version: locked.version.clone(),
+            disk,
+            chain,
+            ipfs,
+        });
+    }
+    let failed = packages.iter().filter(|entry| entry.failed()).count();
+    AuditReport { ok: failed == 0, failed, packages }
+}
+
+fn check_disk(locked: &LockedPackage, dir: &Path) -> CheckStatus {
+    if !dir.is_dir() {
+        return CheckStatus::Missing(format!("{} does not exist", dir.display()));
+    }
+    let Some(expected) = &locked.files_sha256 else {
+        return CheckStatus::Skipped("no file hash in the lockfile; reinstall to record one".to_string());
+    };
+    match tree_sha256(dir) {
+        Ok(actual) if actual == *expected => CheckStatus::Ok,
+        Ok(_) => CheckStatus::Mismatch(format!("files in {} changed since install", dir.display())),
+        Err(e) => CheckStatus::Error(format!("could not read {}: {}", dir.display(), e)),
+    }
+}
+
+fn check_chain(locked: &LockedPackage, fetched: Result<Option<PackageAccount>>) -> CheckStatus {
+    let account = match fetched {
+        Ok(Some(account)) => account,
+        Ok(None) => return CheckStatus::Missing(format!("no on-chain account for {}@{}", locked.name, locked.version)),
+        Err(e) => return CheckStatus::Error(e.to_string()),
+    };
+    if account.ipfs_cid != locked.ipfs_cid {
+        return CheckStatus::Mismatch(format!("on-chain CID is {}, locked {}", account.ipfs_cid, locked.ipfs_cid));
+    }
+    match &locked.authority {
+        Some(authority) if *authority != account.authority.to_string() => {
+            CheckStatus::Mismatch(format!("on-chain authority is {}, locked {}", account.authority, authority))
+        }
+        _ => CheckStatus::Ok,
+    }
+}
+
+async fn check_content<C: ContentSource>(locked: &LockedPackage, content: &C) -> CheckStatus {
+    let dir = std::env::temp_dir().join(format!("antsol_audit_{}", uuid::Uuid::new_v4()));
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        return CheckStatus::Error(format!("could not create {}: {}", dir.display(), e));
+    }
+    let status = match content.fetch(locked, &dir).await {
+        Ok(sha256) if sha256 != locked.sha256 => {
+            CheckStatus::Mismatch(format!("downloaded tarball sha256 is {}, locked {}", sha256, locked.sha256))
+        }
+        Ok(_) => match (&locked.files_sha256, tree_sha256(&dir)) {
+            (Some(expected), Ok(actual)) if actual != *expected => {
+                CheckStatus::Mismatch("downloaded files differ from the lockfile's file hash".to_string())
+            }
+            _ => CheckStatus::Ok,
+        },
+        Err(e) if error_kind(e.as_ref()) == ErrorKind::Verification => CheckStatus::Mismatch(e.to_string()),
+        Err(e) => CheckStatus::Error(e.to_string()),
+    };
+    let _ = std::fs::remove_dir_all(&dir);
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::{client_with, sample_package};
+    use std::collections::HashMap;
+
+    /// Serves fixed file trees; packages it doesn't know fail like an unreachable gateway
+    struct FakeContent(HashMap<String, Vec<(&'static str, &'static str)>>);
+
+    impl ContentSource for FakeContent {
+        async fn fetch(&self, locked: &LockedPackage, dir: &Path) -> Result<String> {
+            let files = self.0.get(&locked.name).ok_or("all gateways failed")?;
+            for (path, content) in files {
+                std::fs::write(dir.join(path), content)?;
+            }
+            Ok(locked.sha256.clone())
+        }
+    }
+
+    fn install(packages_dir: &Path, account: &PackageAccount, files: &[(&str, &str)]) -> LockedPackage {
+        let dir = packages_dir.join(&account.name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (path, content) in files {
+            std::fs::write(dir.join(path), content).unwrap();
+        }
+        LockedPackage {
+            name: account.name.clone(),
+            version: account.version.clone(),
+            ipfs_cid: account.ipfs_cid.clone(),
+            sha256: "ab".repeat(32),
+            files_sha256: Some(tree_sha256(&dir).unwrap()),
+            authority: Some(account.authority.to_string()),
+            dependencies: Vec::new(),
+            requirement: None,
+            from_cache: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_each_kind_of_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let accounts: Vec<PackageAccount> = ["clean", "edited", "gone", "moved", "unpublished"]
+            .iter()
+            .map(|name| sample_package(name, "1.0.0", &[]))
+            .collect();
+        let mut lock = LockFile::new();
+        for account in &accounts {
+            lock.upsert(install(dir.path(), account, &[("lib.rs", "fn main() {}")]));
+        }
+        std::fs::write(dir.path().join("edited/lib.rs"), "tampered").unwrap();
+        std::fs::remove_dir_all(dir.path().join("gone")).unwrap();
+        lock.packages[3].ipfs_cid = "QmOld".to_string();
+
+        // Everything but "unpublished" is on-chain
+        let client = client_with(&accounts[..4], None);
+        let report = audit::<_, FakeContent>(&lock, dir.path(), &client, None).await;
+
+        let statuses: Vec<(&str, &str, &str)> = report.packages.iter()
+            .map(|e| (e.name.as_str(), e.disk.label(), e.chain.label()))
+            .collect();
+        assert_eq!(statuses, [
+            ("clean", "OK", "OK"),
+            ("edited", "MISMATCH", "OK"),
+            ("gone", "MISSING", "OK"),
+            ("moved", "OK", "MISMATCH"),
+            ("unpublished", "OK", "MISSING"),
+        ]);
+        assert!(report.packages.iter().all(|e| e.ipfs.label() == "-"));
+        assert_eq!((report.ok, report.failed), (false, 4));
+        assert!(report.render().iter().any(|line| line.contains("chain: on-chain CID is")));
+    }
+
+    #[tokio::test]
+    async fn old_lockfiles_skip_the_disk_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let account = sample_package("app", "1.0.0", &[]);
+        let mut locked = install(dir.path(), &account, &[("lib.rs", "")]);
+        locked.files_sha256 = None;
+        locked.authority = None;
+        let mut lock = LockFile::new();
+        lock.upsert(locked);
+
+        let client = client_with(&[account], None);
+        let report = audit::<_, FakeContent>(&lock, dir.path(), &client, None).await;
+        assert!(report.ok);
+        assert!(matches!(report.packages[0].disk, CheckStatus::Skipped(_)));
+    }
+
+    #[tokio::test]
+    async fn deep_compares_a_fresh_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let accounts: Vec<PackageAccount> = ["same", "changed", "unavailable"]
+            .iter()
+            .map(|name| sample_package(name, "1.0.0", &[]))
+            .collect();
+        let mut lock = LockFile::new();
+        for account in &accounts {
+            lock.upsert(install(dir.path(), account, &[("lib.rs", "original")]));
+        }
+        let content = FakeContent(HashMap::from([
+            ("same".to_string(), vec![("lib.rs", "original")]),
+            ("changed".to_string(), vec![("lib.rs", "different")]),
+        ]));
+
+        let client = client_with(&accounts, None);
+        let report = audit(&lock, dir.path(), &client, Some(&content)).await;
+        let ipfs: Vec<&str> = report.packages.iter().map(|e| e.ipfs.label()).collect();
+        assert_eq!(ipfs, ["MISMATCH", "OK", "ERROR"]);
+        assert_eq!(report.failed, 2);
+    }
+}
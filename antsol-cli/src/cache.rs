@@ -0,0 +1,262 @@
+use crate::config::Config;
+use crate::integrity;
+use crate::types::Result;
+use crate::utils::sha256_hex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const INDEX_NAME: &str = "index.json";
+const ARCHIVE_SUFFIX: &str = ".tar.gz";
+
+/// One archive in the download cache
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub cid: String,
+    /// sha256 of the archive when it was stored; `None` for files the index doesn't know
+    pub sha256: Option<String>,
+    pub size: u64,
+    /// Unix time of the last install that stored or used it
+    pub last_used: i64,
+}
+
+/// What `index.json` records per CID
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+/// Content-addressed store of downloaded archives shared by every project.
+///
+/// Layout: `<root>/<cid>.tar.gz`, plus `index.json` with each archive's
+/// sha256 and last use. Files the index doesn't know fall back to their
+/// access time for eviction.
+pub struct PackageCache {
+    root: PathBuf,
+}
+
+impl PackageCache {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// `~/.antsol/cache`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(Config::config_dir()?.join("cache")))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where the archive for `cid` lives; `None` for strings that can't be a CID
+    pub fn archive_path(&self, cid: &str) -> Option<PathBuf> {
+        let valid = !cid.is_empty() && cid.chars().all(|c| c.is_ascii_alphanumeric());
+        valid.then(|| self.root.join(format!("{}{}", cid, ARCHIVE_SUFFIX)))
+    }
+
+    /// Look up `cid`, returning the cached archive's path and sha256 on a hit.
+    ///
+    /// The bytes are re-hashed and checked against `expected_sha256`, the
+    /// sha256 recorded when they were stored and, for single-block CIDs,
+    /// the CID itself. A copy that fails any check is deleted and reported
+    /// as a miss.
+    pub fn fetch(&self, cid: &str, expected_sha256: Option<&str>, now: i64) -> Result<Option<(PathBuf, String)>> {
+        let Some(path) = self.archive_path(cid) else {
+            return Ok(None);
+        };
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(&path)?;
+        let sha256 = sha256_hex(&bytes);
+        let mut index = self.load_index();
+        let recorded = index.entries.get(cid).and_then(|e| e.sha256.clone());
+        let valid = expected_sha256.is_none_or(|expected| expected == sha256)
+            && recorded.is_none_or(|recorded| recorded == sha256)
+            && integrity::check_single_block(&bytes, cid).is_ok();
+        if !valid {
+            std::fs::remove_file(&path)?;
+            if index.entries.remove(cid).is_some() {
+                self.save_index(&index)?;
+            }
+            return Ok(None);
+        }
+        index.entries.insert(cid.to_string(), CacheEntry {
+            cid: cid.to_string(),
+            sha256: Some(sha256.clone()),
+            size: bytes.len() as u64,
+            last_used: now,
+        });
+        self.save_index(&index)?;
+        Ok(Some((path, sha256)))
+    }
+
+    /// Copy a verified `archive` into the cache under `cid`
+    pub fn store(&self, cid: &str, archive: &Path, sha256: &str, now: i64) -> Result<()> {
+        let Some(path) = self.archive_path(cid) else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(&self.root)?;
+        // Copy then rename so a concurrent install never reads half an archive
+        let partial = path.with_extension("partial");
+        let size = std::fs::copy(archive, &partial)?;
+        std::fs::rename(&partial, &path)?;
+        let mut index = self.load_index();
+        index.entries.insert(cid.to_string(), CacheEntry {
+            cid: cid.to_string(),
+            sha256: Some(sha256.to_string()),
+            size,
+            last_used: now,
+        });
+        self.save_index(&index)
+    }
+
+    /// Every cached archive, most recently used first
+    pub fn list(&self) -> Result<Vec<CacheEntry>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let index = self.load_index();
+        let mut entries = Vec::new();
+        for dir_entry in std::fs::read_dir(&self.root)? {
+            let dir_entry = dir_entry?;
+            let file_name = dir_entry.file_name().to_string_lossy().into_owned();
+            let Some(cid) = file_name.strip_suffix(ARCHIVE_SUFFIX) else {
+                continue;
+            };
+            let metadata = dir_entry.metadata()?;
+            let entry = match index.entries.get(cid) {
+                Some(entry) => CacheEntry { size: metadata.len(), ..entry.clone() },
+                None => CacheEntry {
+                    cid: cid.to_string(),
+                    sha256: None,
+                    size: metadata.len(),
+                    last_used: last_access(&metadata),
+                },
+            };
+            entries.push(entry);
+        }
+        entries.sort_by(|a, b| b.last_used.cmp(&a.last_used).then_with(|| a.cid.cmp(&b.cid)));
+        Ok(entries)
+    }
+
+    /// Total bytes of cached archives
+    pub fn size(&self) -> Result<u64> {
+        Ok(self.list()?.iter().map(|e| e.size).sum())
+    }
+
+    /// Evict least recently used archives until at most `max_size` bytes
+    /// remain; without a limit everything goes. Returns what was removed.
+    pub fn clean(&self, max_size: Option<u64>) -> Result<Vec<CacheEntry>> {
+        let mut entries = self.list()?;
+        let mut total: u64 = entries.iter().map(|e| e.size).sum();
+        let limit = max_size.unwrap_or(0);
+        let mut index = self.load_index();
+        let mut removed = Vec::new();
+        // Oldest last in `entries`
+        while total > limit {
+            let Some(entry) = entries.pop() else {
+                break;
+            };
+            if let Some(path) = self.archive_path(&entry.cid) {
+                std::fs::remove_file(path)?;
+            }
+            index.entries.remove(&entry.cid);
+            total -= entry.size;
+            removed.push(entry);
+        }
+        // Drop index records whose archive was deleted by hand
+        index.entries.retain(|cid, _| entries.iter().any(|e| &e.cid == cid));
+        if self.root.is_dir() {
+            self.save_index(&index)?;
+        }
+        Ok(removed)
+    }
+
+    fn load_index(&self) -> CacheIndex {
+        std::fs::read_to_string(self.root.join(INDEX_NAME))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_index(&self, index: &CacheIndex) -> Result<()> {
+        std::fs::create_dir_all(&self.root)?;
+        std::fs::write(self.root.join(INDEX_NAME), serde_json::to_string_pretty(index)?)?;
+        Ok(())
+    }
+}
+
+/// Access time (or failing that modification time) as Unix seconds
+fn last_access(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .accessed()
+        .or_else(|_| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/hello-pkg.tar.gz");
+    const FIXTURE_CID: &str = "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu";
+
+    fn store_bytes(cache: &PackageCache, cid: &str, bytes: &[u8], now: i64) {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("package.tar.gz");
+        std::fs::write(&archive, bytes).unwrap();
+        cache.store(cid, &archive, &sha256_hex(bytes), now).unwrap();
+    }
+
+    #[test]
+    fn hits_are_verified_and_refresh_last_use() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        let expected = sha256_hex(FIXTURE);
+        assert_eq!(cache.fetch(FIXTURE_CID, None, 1).unwrap(), None);
+
+        store_bytes(&cache, FIXTURE_CID, FIXTURE, 10);
+        let (path, sha256) = cache.fetch(FIXTURE_CID, Some(&expected), 20).unwrap().unwrap();
+        assert_eq!((path, sha256), (cache.root().join(format!("{}.tar.gz", FIXTURE_CID)), expected));
+        assert_eq!(cache.list().unwrap()[0].last_used, 20);
+
+        // An on-chain hash that disagrees is a miss, and the copy is dropped
+        assert_eq!(cache.fetch(FIXTURE_CID, Some(&"00".repeat(32)), 30).unwrap(), None);
+        assert!(cache.list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn corrupted_archives_are_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        store_bytes(&cache, FIXTURE_CID, FIXTURE, 10);
+        std::fs::write(cache.archive_path(FIXTURE_CID).unwrap(), b"tampered").unwrap();
+
+        assert_eq!(cache.fetch(FIXTURE_CID, None, 20).unwrap(), None);
+        assert!(!cache.archive_path(FIXTURE_CID).unwrap().exists());
+        assert_eq!(cache.archive_path("../escape"), None);
+    }
+
+    #[test]
+    fn clean_evicts_least_recently_used_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        store_bytes(&cache, "QmOld", &[0; 100], 1);
+        store_bytes(&cache, "QmNew", &[1; 100], 3);
+        store_bytes(&cache, "QmMid", &[2; 100], 2);
+        assert_eq!(cache.size().unwrap(), 300);
+
+        let removed = cache.clean(Some(150)).unwrap();
+        let removed: Vec<&str> = removed.iter().map(|e| e.cid.as_str()).collect();
+        assert_eq!(removed, ["QmOld", "QmMid"]);
+        let kept: Vec<String> = cache.list().unwrap().into_iter().map(|e| e.cid).collect();
+        assert_eq!(kept, ["QmNew"]);
+
+        assert_eq!(cache.clean(None).unwrap().len(), 1);
+        assert_eq!(cache.size().unwrap(), 0);
+    }
+}
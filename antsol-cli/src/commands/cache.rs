@@ -0,0 +1,46 @@
+use crate::cache::PackageCache;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+use std::io::Write;
+
+/// List the archives in the download cache
+pub async fn handle_ls(json: bool) -> Result<()> {
+    let cache = PackageCache::open_default()?;
+    let entries = cache.list()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!("\n{} {}", "🗄️  Download Cache".cyan().bold(), cache.root().display().to_string().dimmed());
+    if entries.is_empty() {
+        print_info("The cache is empty. Installs fill it as they download packages.");
+        return Ok(());
+    }
+    let mut out = PagedOutput::new();
+    for entry in &entries {
+        let last_used = chrono::DateTime::<chrono::Utc>::from_timestamp(entry.last_used, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        writeln!(out, "  {:<60} {:>10} {}", entry.cid.green(), format_size(entry.size), last_used.dimmed())?;
+    }
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    writeln!(out, "\n  {} archive(s), {}", entries.len(), format_size(total).cyan())?;
+    out.finish()
+}
+
+/// Evict least recently used archives down to `max_size`, or everything
+pub async fn handle_clean(max_size: Option<u64>) -> Result<()> {
+    let cache = PackageCache::open_default()?;
+    let removed = cache.clean(max_size)?;
+    let freed: u64 = removed.iter().map(|e| e.size).sum();
+    if removed.is_empty() {
+        print_info("Nothing to remove");
+    } else {
+        print_success(&format!("Removed {} archive(s), freeing {}", removed.len(), format_size(freed)));
+    }
+    print_info(&format!("Cache size: {}", format_size(cache.size()?)));
+    Ok(())
+}
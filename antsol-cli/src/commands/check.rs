@@ -0,0 +1,40 @@
+use crate::manifest_check::{self, Diagnostic};
+use crate::types::Result;
+use crate::utils::*;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct CheckReport<'a> {
+    manifest: PathBuf,
+    errors: usize,
+    warnings: usize,
+    diagnostics: &'a [Diagnostic],
+}
+
+/// Check the manifest in `path` and report every problem; fails if any is an error
+pub async fn handle_check(path: PathBuf, json: bool) -> Result<()> {
+    let manifest_path = path.join("antsol.toml");
+    let report = manifest_check::check_dir(&path)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&CheckReport {
+            manifest: manifest_path.clone(),
+            errors: report.errors(),
+            warnings: report.warnings(),
+            diagnostics: &report.diagnostics,
+        })?);
+    } else {
+        print_heading(format!("🔎 Checking {}", manifest_path.display()));
+        report.print(&manifest_path);
+    }
+    let warnings = report.warnings();
+    let manifest = report.into_manifest(&manifest_path)?;
+    if !json {
+        let summary = format!("{}@{} is ready to publish", manifest.package.name, manifest.package.version);
+        match warnings {
+            0 => print_success(&summary),
+            n => print_success(&format!("{} ({} warning(s))", summary, n)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::config::Config;
+use crate::indexer_client::{IndexerClient, SearchFilters};
+use crate::types::Result;
+use clap::Command;
+use clap_complete::Shell;
+use std::io::Write;
+use std::time::Duration;
+
+/// Longest a completion waits on the indexer; a slow indexer means no
+/// suggestions rather than a hung shell
+const COMPLETION_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Most package names offered for one completion
+const MAX_SUGGESTIONS: u32 = 50;
+
+/// Bash: complete the first argument of `install` and `info` with package names
+const BASH_PACKAGE_HOOK: &str = r#"
+_antsol_packages() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ ${COMP_CWORD} -eq 2 && "${cur}" != -* ]]; then
+        case "${COMP_WORDS[1]}" in
+            install|info)
+                COMPREPLY=($(antsol __complete-packages "${cur}" 2>/dev/null))
+                return 0
+                ;;
+        esac
+    fi
+    _antsol "$@"
+}
+complete -F _antsol_packages -o nosort -o bashdefault -o default antsol
+"#;
+
+/// Fish: the same, evaluated each time a package argument is completed
+const FISH_PACKAGE_HOOK: &str = r#"
+complete -c antsol -n "__fish_seen_subcommand_from install info" -f -a "(antsol __complete-packages (commandline -ct) 2>/dev/null)"
+"#;
+
+/// Print the completion script for `shell`. Bash and fish scripts also
+/// complete package names for `install` and `info` via `antsol __complete-packages`.
+pub async fn handle_completions(shell: Shell, mut command: Command) -> Result<()> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, "antsol", &mut script);
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&script)?;
+    if let Some(hook) = package_hook(shell) {
+        stdout.write_all(hook.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Print the roff man page for the whole CLI
+pub async fn handle_man(command: Command) -> Result<()> {
+    clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
+    Ok(())
+}
+
+/// Print indexed package names starting with `prefix`, one per line. Never
+/// fails: without a config or a reachable indexer there are just no suggestions.
+pub async fn handle_complete_packages(prefix: String) -> Result<()> {
+    let indexer_url = Config::load().map(|config| config.indexer_url).unwrap_or_else(|_| Config::default().indexer_url);
+    for name in package_names(&indexer_url, &prefix, COMPLETION_TIMEOUT).await {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn package_hook(shell: Shell) -> Option<&'static str> {
+    match shell {
+        Shell::Bash => Some(BASH_PACKAGE_HOOK),
+        Shell::Fish => Some(FISH_PACKAGE_HOOK),
+        _ => None,
+    }
+}
+
+async fn package_names(indexer_url: &str, prefix: &str, timeout: Duration) -> Vec<String> {
+    let indexer = IndexerClient::new(indexer_url);
+    let rows = match tokio::time::timeout(timeout, indexer.search(prefix, MAX_SUGGESTIONS, 0, &SearchFilters::default())).await {
+        Ok(Ok(page)) => page.rows,
+        _ => return Vec::new(),
+    };
+    // Search matches descriptions too; only names extending what was typed are useful
+    let mut names: Vec<String> = rows.into_iter().map(|row| row.name).filter(|name| name.starts_with(prefix)).collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn row(name: &str) -> serde_json::Value {
+        json!({ "id": 1, "name": name, "author": null, "description": null, "repository": null, "homepage": null, "total_downloads": 0 })
+    }
+
+    #[tokio::test]
+    async fn suggests_names_with_the_typed_prefix() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/search"))
+            .and(query_param("q", "json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "data": [row("json-parser"), row("yaml"), row("json-fmt")],
+            })))
+            .mount(&server)
+            .await;
+        let names = package_names(&server.uri(), "json", COMPLETION_TIMEOUT).await;
+        assert_eq!(names, ["json-fmt", "json-parser"]);
+    }
+
+    #[tokio::test]
+    async fn a_slow_or_missing_indexer_gives_no_suggestions() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)).set_body_json(json!({
+                "success": true,
+                "data": [row("json-parser")],
+            })))
+            .mount(&server)
+            .await;
+        let started = std::time::Instant::now();
+        assert!(package_names(&server.uri(), "json", Duration::from_millis(50)).await.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(package_names("http://127.0.0.1:1", "json", COMPLETION_TIMEOUT).await.is_empty());
+    }
+
+    #[test]
+    fn bash_and_fish_scripts_complete_package_names() {
+        let mut command = clap::Command::new("antsol").subcommand(clap::Command::new("install"));
+        let mut script = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut command, "antsol", &mut script);
+        // The hook wraps the function clap generates
+        assert!(String::from_utf8(script).unwrap().contains("_antsol()"));
+        assert!(package_hook(Shell::Bash).unwrap().contains("__complete-packages"));
+        assert!(package_hook(Shell::Fish).unwrap().contains("__complete-packages"));
+        assert!(package_hook(Shell::Zsh).is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::config::{is_secret_key, mask_secret, Config, ConfigSource, CONFIG_KEYS, ENV_OVERRIDES};
+use crate::credentials;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+
+/// Print the effective value of one config key; unset optional keys are reported on stderr
+pub async fn handle_get(key: String) -> Result<()> {
+    let config = Config::load()?;
+    match config.get(&key)? {
+        Some(value) => println!("{}", value),
+        None => eprintln!("{} is not set", key),
+    }
+    Ok(())
+}
+
+/// Validate and store one config key in the file (environment overrides are not saved).
+/// The Pinata JWT goes to the OS keyring unless `--no-keyring`.
+pub async fn handle_set(key: String, value: String) -> Result<()> {
+    let mut config = Config::load_file()?;
+    config.set(&key, &value)?;
+    if key == "pinata_jwt" {
+        let jwt = config.pinata_jwt.take();
+        config.store_pinata_jwt(credentials::keyring().as_ref(), jwt)?;
+    }
+    config.save()?;
+    
+    let shown = match config.get(&key)? {
+        None if key == "pinata_jwt" && config.pinata_jwt_in_keyring => "(stored in the OS keyring)".to_string(),
+        Some(value) if is_secret_key(&key) => mask_secret(&value),
+        Some(value) => value,
+        None => "(unset)".to_string(),
+    };
+    print_success(&format!("{} = {}", key, shown.cyan()));
+    
+    let overridden = ENV_OVERRIDES.iter()
+        .find(|(k, var)| *k == key && std::env::var(var).is_ok_and(|v| !v.trim().is_empty()));
+    if let Some((_, var)) = overridden {
+        print_warning(&format!("{} is set in the environment and takes precedence over this value", var));
+    }
+    Ok(())
+}
+
+/// Print every effective config key with secrets masked, and with `sources`
+/// where each value came from (default, file or env)
+pub async fn handle_list(sources: bool) -> Result<()> {
+    let (config, origins) = Config::load_with_sources()?;
+    let path = Config::config_dir()?.join("config.toml");
+    
+    println!("\n{} {}", "⚙️  Configuration".cyan().bold(), path.display().to_string().dimmed());
+    for key in CONFIG_KEYS {
+        let value = match config.get(key)? {
+            Some(value) if is_secret_key(key) => mask_secret(&value).yellow(),
+            Some(value) => value.normal(),
+            None => "(unset)".dimmed(),
+        };
+        if sources {
+            let origin = origins.get(key).copied().unwrap_or(ConfigSource::Default);
+            println!("  {:<16} {:<26} {}", key, origin.to_string().dimmed(), value);
+        } else {
+            println!("  {:<16} {}", key, value);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::idl::{self, INSTRUCTIONS, TYPES};
+use crate::solana_client::AntSolClient;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+
+/// Fetch the registry program's on-chain IDL and check that the instructions
+/// and accounts the CLI encodes match it. Fails on any difference.
+pub async fn handle_verify_idl() -> Result<()> {
+    let config = Config::load()?;
+    let client = AntSolClient::new(&config)?;
+    let program_id: solana_sdk::pubkey::Pubkey = config.program_id.parse()?;
+    
+    print_heading("🔎 Checking the CLI against the on-chain IDL".cyan().bold());
+    println!("  Program: {}", config.program_id.cyan());
+    println!("  IDL account: {}", idl::idl_address(&program_id).to_string().cyan());
+    
+    let problems = idl::check_idl(&client.fetch_idl()?);
+    println!();
+    // Problems start with the subject they concern, e.g. "instruction publish_package: ..."
+    let mark = |subject: String| {
+        let failed = problems
+            .iter()
+            .any(|p| p.strip_prefix(&subject).is_some_and(|rest| rest.starts_with([' ', ':'])));
+        if failed { "✗".red() } else { "✓".green() }
+    };
+    for layout in INSTRUCTIONS {
+        let discriminator = idl::instruction_discriminator(layout.name);
+        println!("  {} instruction {} {:?}", mark(format!("instruction {}", layout.name)), layout.name, discriminator);
+    }
+    for (name, _) in TYPES {
+        println!("  {} type {}", mark(format!("type {}", name)), name);
+    }
+    
+    if problems.is_empty() {
+        println!();
+        print_success("Instruction and account layouts match the deployed program");
+        return Ok(());
+    }
+    println!();
+    for problem in &problems {
+        println!("  {} {}", "✗".red(), problem);
+    }
+    Err(AntSolError::Program(format!(
+        "{} mismatch(es) between the CLI and the program's IDL; publishing with this CLI would send malformed instructions",
+        problems.len()
+    )).into())
+}
@@ -0,0 +1,406 @@
+use crate::config::{Config, IpfsBackend};
+use crate::error::AntSolError;
+use crate::indexer_client::IndexerClient;
+use crate::ipfs::IpfsClient;
+use crate::pinning::Pinata;
+use crate::rpc::RpcApi;
+use crate::signer::known_pubkey;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::pubkey::Pubkey;
+use std::time::Duration;
+
+/// Longest any one check may take, so a run finishes in a few seconds
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Genesis hashes of the public clusters
+const GENESIS_HASHES: [(Cluster, &str); 3] = [
+    (Cluster::Mainnet, "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d"),
+    (Cluster::Devnet, "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG"),
+    (Cluster::Testnet, "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Pass,
+    /// Something only some commands need is broken or missing
+    Warn,
+    /// A requirement of every command is broken; `doctor` exits non-zero
+    Fail,
+    /// Not checked, because the backend doesn't support it or an earlier check failed
+    Skip,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    /// What to do about a failure or warning
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Pass, detail: detail.into(), hint: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    fn skip(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Skip, detail: detail.into(), hint: None }
+    }
+}
+
+#[derive(Serialize)]
+struct DoctorReport<'a> {
+    ok: bool,
+    checks: &'a [Check],
+}
+
+/// Check the config, wallet, RPC endpoint, program, pinning credential and
+/// indexer, printing a remediation hint for each problem. Fails when a
+/// requirement of every command (config, RPC, cluster, program, a configured
+/// wallet) is broken; a missing wallet, an unfunded one, a rejected pinning
+/// credential or an unhealthy indexer only warn.
+pub async fn handle_doctor() -> Result<()> {
+    let checks = match Config::load() {
+        Ok(config) => {
+            let rpc = Box::new(RpcClient::new_with_timeout(config.rpc_url.clone(), CHECK_TIMEOUT));
+            let mut checks = vec![Check::pass("config", config_detail(&config))];
+            checks.extend(run_checks(&config, rpc).await);
+            checks
+        }
+        Err(e) => vec![Check::fail(
+            "config",
+            e.to_string(),
+            "Fix or remove ~/.antsol/config.toml and the ANTSOL_* variables it names, then run 'antsol setup'",
+        )],
+    };
+    report(checks)
+}
+
+fn config_detail(config: &Config) -> String {
+    match &config.selected_profile {
+        Some(profile) => format!("~/.antsol/config.toml (profile '{}')", profile),
+        None => "~/.antsol/config.toml".to_string(),
+    }
+}
+
+/// Every check after the config, RPC ones alongside the HTTP ones
+async fn run_checks(config: &Config, rpc: Box<dyn RpcApi>) -> Vec<Check> {
+    let (wallet_check, wallet) = check_wallet(config);
+    let rpc_url = config.rpc_url.clone();
+    let expected = expected_cluster(config);
+    let program_id = config.program_id.clone();
+    let min_balance = config.min_balance_sol.map(sol_to_lamports);
+    let rpc_checks = tokio::task::spawn_blocking(move || {
+        check_rpc(rpc.as_ref(), &rpc_url, expected, &program_id, wallet, min_balance)
+    });
+    let (pinning, indexer) = tokio::join!(check_pinning(config), check_indexer(&config.indexer_url));
+
+    let mut checks = vec![wallet_check];
+    checks.extend(rpc_checks.await.unwrap_or_else(|e| {
+        vec![Check::fail("rpc", format!("RPC checks did not finish: {}", e), "Re-run 'antsol doctor'")]
+    }));
+    checks.push(pinning);
+    checks.push(indexer);
+    checks
+}
+
+/// The cluster the profile is meant for: its name when that names a public
+/// cluster (profiles are created named after theirs), else what `rpc_url` says
+fn expected_cluster(config: &Config) -> Cluster {
+    let named = config.selected_profile.as_deref().and_then(|name| {
+        GENESIS_HASHES.iter().map(|(cluster, _)| *cluster).find(|cluster| cluster.as_str() == name)
+    });
+    named.unwrap_or_else(|| Cluster::detect(&config.rpc_url))
+}
+
+fn cluster_of_genesis(hash: &str) -> Option<Cluster> {
+    GENESIS_HASHES.iter().find(|(_, genesis)| *genesis == hash).map(|(cluster, _)| *cluster)
+}
+
+fn check_wallet(config: &Config) -> (Check, Option<Pubkey>) {
+    let Some(path) = &config.wallet_path else {
+        let check = Check::warn(
+            "wallet",
+            "No wallet configured; publishing and namespace commands need one",
+            "Run 'antsol wallet new' or 'antsol wallet connect <keypair.json|prompt://|usb://ledger>'",
+        );
+        return (check, None);
+    };
+    match known_pubkey(path) {
+        Ok(Some(pubkey)) => (Check::pass("wallet", format!("{} ({})", pubkey, path.display())), Some(pubkey)),
+        Ok(None) => (Check::pass("wallet", format!("{} (address known once the seed phrase is entered)", path.display())), None),
+        Err(e) => (
+            Check::fail(
+                "wallet",
+                e.to_string(),
+                "Point wallet_path at a working wallet with 'antsol wallet connect <keypair.json|prompt://|usb://ledger>'",
+            ),
+            None,
+        ),
+    }
+}
+
+/// RPC reachability, the cluster behind it, the program and the wallet's
+/// balance. Only the first needs an answer for the others to run.
+fn check_rpc(
+    rpc: &dyn RpcApi,
+    rpc_url: &str,
+    expected: Cluster,
+    program_id: &str,
+    wallet: Option<Pubkey>,
+    min_balance: Option<u64>,
+) -> Vec<Check> {
+    let genesis = match rpc.get_genesis_hash() {
+        Ok(hash) => hash.to_string(),
+        Err(e) => {
+            return vec![
+                Check::fail(
+                    "rpc",
+                    format!("{} did not answer: {}", rpc_url, e),
+                    "Check your network, or set another endpoint with 'antsol config set rpc_url <url>'",
+                ),
+                Check::skip("cluster", "RPC unreachable"),
+                Check::skip("program", "RPC unreachable"),
+                Check::skip("balance", "RPC unreachable"),
+            ];
+        }
+    };
+    let mut checks = vec![Check::pass("rpc", rpc_url)];
+
+    let actual = cluster_of_genesis(&genesis);
+    checks.push(match GENESIS_HASHES.iter().find(|(cluster, _)| *cluster == expected) {
+        Some((_, hash)) if *hash == genesis => Check::pass("cluster", format!("{} (genesis {})", expected.as_str(), genesis)),
+        Some(_) => Check::fail(
+            "cluster",
+            format!(
+                "Expected {} but {} serves {} (genesis {})",
+                expected.as_str(),
+                rpc_url,
+                actual.map_or("an unknown cluster", Cluster::as_str),
+                genesis
+            ),
+            format!(
+                "Point this profile at a {} endpoint ('antsol config set rpc_url <url>') or switch with 'antsol profile use <name>'",
+                expected.as_str()
+            ),
+        ),
+        None => Check::pass("cluster", format!("{} (genesis {})", actual.map_or(expected.as_str(), Cluster::as_str), genesis)),
+    });
+
+    checks.push(check_program(rpc, program_id));
+
+    checks.push(match wallet {
+        None => Check::skip("balance", "No wallet loaded"),
+        Some(pubkey) => match rpc.get_balance(&pubkey) {
+            Ok(lamports) if lamports < min_balance.unwrap_or(1) => Check::warn(
+                "balance",
+                format!("{} SOL", lamports_to_sol(lamports)),
+                match actual {
+                    Some(Cluster::Mainnet) => "Fund the wallet before publishing".to_string(),
+                    _ => "Fund the wallet before publishing, e.g. with 'antsol wallet airdrop'".to_string(),
+                },
+            ),
+            Ok(lamports) => Check::pass("balance", format!("{} SOL", lamports_to_sol(lamports))),
+            Err(e) => Check::warn("balance", format!("Could not fetch the balance: {}", e), "Re-run 'antsol doctor'; the endpoint may be rate limiting"),
+        },
+    });
+    checks
+}
+
+fn check_program(rpc: &dyn RpcApi, program_id: &str) -> Check {
+    let hint = "Set the registry's address for this cluster with 'antsol config set program_id <address>'";
+    let pubkey: Pubkey = match program_id.parse() {
+        Ok(pubkey) => pubkey,
+        Err(_) => return Check::fail("program", format!("program_id '{}' is not a valid address", program_id), hint),
+    };
+    match rpc.get_account(&pubkey) {
+        Ok(Some(account)) if account.executable => Check::pass("program", program_id),
+        Ok(Some(_)) => Check::fail("program", format!("{} exists but is not a program", program_id), hint),
+        Ok(None) => Check::fail("program", format!("No account at {} on this cluster", program_id), hint),
+        Err(e) => Check::fail("program", format!("Could not fetch {}: {}", program_id, e), "Re-run 'antsol doctor'; the endpoint may be rate limiting"),
+    }
+}
+
+/// Whether uploads would be accepted, without uploading anything
+async fn check_pinning(config: &Config) -> Check {
+    match config.ipfs_backend() {
+        IpfsBackend::Pinata if config.pinata_jwt.is_none() => Check::warn(
+            "pinning",
+            "No Pinata JWT configured; publishing needs one",
+            "Run 'antsol setup' or set PINATA_JWT",
+        ),
+        IpfsBackend::Pinata => match Pinata::new(&config.ipfs_url, config.pinata_jwt.clone()).test_authentication(CHECK_TIMEOUT).await {
+            Ok(()) => Check::pass("pinning", "Pinata accepted the JWT"),
+            Err(e) => Check::warn("pinning", e.to_string(), "Create a new JWT in the Pinata dashboard and run 'antsol setup'"),
+        },
+        IpfsBackend::Kubo => match IpfsClient::kubo_version(&config.ipfs_url).await {
+            Some(version) => Check::pass("pinning", format!("Kubo {} at {}", version, config.ipfs_url)),
+            None => Check::warn(
+                "pinning",
+                format!("No Kubo node answered at {}", config.ipfs_url),
+                "Start it with 'ipfs daemon', or point ipfs_url at its RPC API port (5001 by default)",
+            ),
+        },
+        backend => Check::skip("pinning", format!("{:?} credentials are only checked by an upload", backend)),
+    }
+}
+
+async fn check_indexer(indexer_url: &str) -> Check {
+    let hint = "Search and install fall back to on-chain lookups; set another indexer with 'antsol config set indexer_url <url>'";
+    match tokio::time::timeout(CHECK_TIMEOUT, IndexerClient::new(indexer_url).health()).await {
+        Ok(Ok(health)) if health.status == "ok" => Check::pass("indexer", indexer_url),
+        Ok(Ok(health)) => Check::warn(
+            "indexer",
+            match (health.indexer.lag_slots, health.indexer.last_error) {
+                (_, Some(error)) => format!("{} reports {}: {}", indexer_url, health.status, error),
+                (Some(lag), None) => format!("{} reports {}, {} slots behind", indexer_url, health.status, lag),
+                (None, None) => format!("{} reports {}", indexer_url, health.status),
+            },
+            hint,
+        ),
+        Ok(Err(e)) => Check::warn("indexer", e.to_string(), hint),
+        Err(_) => Check::warn("indexer", format!("{} did not answer within {}s", indexer_url, CHECK_TIMEOUT.as_secs()), hint),
+    }
+}
+
+fn report(checks: Vec<Check>) -> Result<()> {
+    let failed: Vec<&str> = checks.iter().filter(|c| c.status == Status::Fail).map(|c| c.name).collect();
+
+    if json_output() {
+        emit_json(&DoctorReport { ok: failed.is_empty(), checks: &checks })?;
+    } else {
+        print_heading("🩺 Checking the AntSol environment".cyan().bold());
+        println!();
+        for check in &checks {
+            let mark = match check.status {
+                Status::Pass => "✓".green(),
+                Status::Warn => "⚠".yellow(),
+                Status::Fail => "✗".red(),
+                Status::Skip => "-".dimmed(),
+            };
+            println!("  {} {:<9} {}", mark, check.name, check.detail);
+            if let Some(hint) = &check.hint {
+                println!("    {} {}", "→".dimmed(), hint.dimmed());
+            }
+        }
+        println!();
+    }
+
+    if failed.is_empty() {
+        print_success("Everything the CLI needs is in place");
+        return Ok(());
+    }
+    Err(AntSolError::Config(format!("{} check(s) failed: {}", failed.len(), failed.join(", "))).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::mock::MockRpc;
+    use solana_sdk::account::Account;
+    use solana_sdk::hash::Hash;
+    use std::str::FromStr;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn mock_on(cluster: Cluster) -> MockRpc {
+        let (_, genesis) = GENESIS_HASHES.iter().find(|(c, _)| *c == cluster).unwrap();
+        MockRpc { genesis_hash: Hash::from_str(genesis).unwrap(), ..MockRpc::default() }
+    }
+
+    fn statuses(checks: &[Check]) -> Vec<(&str, Status)> {
+        checks.iter().map(|c| (c.name, c.status)).collect()
+    }
+
+    #[test]
+    fn profile_names_pick_the_expected_cluster() {
+        let mut config = Config { rpc_url: "https://rpc.example.com".into(), ..Config::default() };
+        assert_eq!(expected_cluster(&config), Cluster::Custom);
+        config.selected_profile = Some("mainnet".into());
+        assert_eq!(expected_cluster(&config), Cluster::Mainnet);
+        config.selected_profile = Some("staging".into());
+        config.rpc_url = "https://api.devnet.solana.com".into();
+        assert_eq!(expected_cluster(&config), Cluster::Devnet);
+    }
+
+    #[test]
+    fn rpc_checks_pass_against_the_expected_cluster() {
+        let rpc = mock_on(Cluster::Devnet);
+        let program_id = Pubkey::new_unique();
+        rpc.insert_account(program_id, Account { executable: true, lamports: 1, ..Account::default() });
+        let wallet = Pubkey::new_unique();
+        rpc.insert_account(wallet, Account { lamports: 2_000_000_000, ..Account::default() });
+
+        let checks = check_rpc(&rpc, "https://api.devnet.solana.com", Cluster::Devnet, &program_id.to_string(), Some(wallet), None);
+        assert_eq!(
+            statuses(&checks),
+            [("rpc", Status::Pass), ("cluster", Status::Pass), ("program", Status::Pass), ("balance", Status::Pass)]
+        );
+        assert_eq!(checks[3].detail, "2 SOL");
+    }
+
+    #[test]
+    fn rpc_checks_flag_the_wrong_cluster_and_a_missing_program() {
+        let rpc = mock_on(Cluster::Mainnet);
+        let wallet = Pubkey::new_unique();
+
+        let checks = check_rpc(&rpc, "https://rpc.example.com", Cluster::Devnet, &Pubkey::new_unique().to_string(), Some(wallet), None);
+        assert_eq!(
+            statuses(&checks),
+            [("rpc", Status::Pass), ("cluster", Status::Fail), ("program", Status::Fail), ("balance", Status::Warn)]
+        );
+        assert!(checks[1].detail.starts_with("Expected devnet but https://rpc.example.com serves mainnet"), "{}", checks[1].detail);
+        assert_eq!(checks[3].hint.as_deref(), Some("Fund the wallet before publishing"));
+
+        let checks = check_rpc(&rpc, "http://localhost:8899", Cluster::Localnet, "not-a-pubkey", None, None);
+        assert_eq!(checks[1].status, Status::Pass);
+        assert!(checks[2].detail.contains("not a valid address"));
+        assert_eq!(checks[3].status, Status::Skip);
+    }
+
+    #[tokio::test]
+    async fn indexer_check_reports_lag_as_a_warning() {
+        let server = MockServer::start().await;
+        Mock::given(path("/health"))
+            .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
+                "success": false,
+                "data": {
+                    "status": "degraded",
+                    "indexer": { "last_processed_slot": 100, "lag_slots": 800, "last_error": null },
+                },
+            })))
+            .mount(&server)
+            .await;
+
+        let check = check_indexer(&server.uri()).await;
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.detail.ends_with("reports degraded, 800 slots behind"), "{}", check.detail);
+
+        let check = check_indexer("http://127.0.0.1:9").await;
+        assert_eq!(check.status, Status::Warn);
+        assert!(check.hint.unwrap().contains("fall back to on-chain lookups"));
+    }
+
+    #[test]
+    fn only_failures_fail_the_report() {
+        assert!(report(vec![Check::pass("config", "ok"), Check::warn("indexer", "down", "later")]).is_ok());
+        let err = report(vec![Check::fail("rpc", "down", "fix it"), Check::fail("program", "missing", "fix it")]).unwrap_err();
+        assert_eq!(err.to_string(), "2 check(s) failed: rpc, program");
+    }
+}
@@ -0,0 +1,24 @@
+use crate::error::catalog;
+use crate::types::Result;
+use colored::*;
+
+/// List the exit code catalog, optionally as JSON for scripts
+pub async fn handle_list(json: bool) -> Result<()> {
+    let entries = catalog();
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    
+    println!("\n{}", "🚦 Exit Codes".cyan().bold());
+    println!("{}", "─".repeat(80));
+    println!("  {:<6} {:<18} {}", "CODE".bold(), "NAME".bold(), "DESCRIPTION".bold());
+    println!("  {:<6} {:<18} Command completed successfully", "0".green(), "success");
+    for entry in entries {
+        println!("  {:<6} {:<18} {}", entry.code.to_string().yellow(), entry.name, entry.description);
+    }
+    println!("{}", "─".repeat(80));
+    
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::cache::PackageCache;
+use crate::commands::install::require_lockfile;
+use crate::config::Config;
+use crate::ipfs::IpfsClient;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::resolver;
+use crate::solana_client::AntSolClient;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+
+/// Download every package `antsol.lock` pins into the download cache
+/// without installing anything, so `antsol install --offline` can run later
+/// without a network. The locked CIDs are checked against the chain first,
+/// as `antsol install --locked` does.
+pub async fn handle_fetch() -> Result<()> {
+    if !json_output() {
+        print_heading("📦 Fetching antsol.lock into the download cache".cyan().bold());
+    }
+    let lockfile = require_lockfile()?;
+    let config = Config::load()?;
+    let solana_client = AntSolClient::new(&config)?;
+
+    let spinner = create_spinner("🔍 Checking locked packages against the blockchain...");
+    // Nothing is extracted there; the plan only needs somewhere to point
+    let plan = resolver::plan_locked(&solana_client, &lockfile, &config.install_dir).await;
+    spinner.finish_and_clear();
+    let plan = plan?;
+    print_success(&format!("All {} locked CIDs match on-chain metadata", plan.packages.len()));
+
+    let cache = PackageCache::open_default()?;
+    let cache_root = cache.root().to_path_buf();
+    let ipfs_client = IpfsClient::from_config(&config).with_cache(cache);
+    let mut downloaded = 0;
+    for package in &plan.packages {
+        let id = format!("{}@{}", package.name, package.version);
+        let progress = create_progress_bar(0, &format!("⬇️  {}", id));
+        let fetched = ipfs_client.fetch_to_cache(&package.ipfs_cid, package.sha256.as_deref(), &progress).await;
+        progress.finish_and_clear();
+        if fetched?.from_cache {
+            print_info(&format!("{} is already cached", id));
+        } else {
+            downloaded += 1;
+            print_success(&format!("Cached {} ({})", id.green(), package.ipfs_cid.dimmed()));
+        }
+    }
+
+    print_success(&format!(
+        "{} package(s) from {} are in {} ({} downloaded)",
+        plan.packages.len(),
+        LOCKFILE_NAME,
+        cache_root.display(),
+        downloaded
+    ));
+    print_info("Copy that directory to the offline machine and run 'antsol install --offline'");
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::indexer_client::{IndexerClient, VersionDependents};
+use crate::signer::load_signer;
+use crate::solana_client::AntSolClient;
+use crate::tx::SendOptions;
+use crate::types::{PackageAccount, Result};
+use crate::utils::*;
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+use std::io::{IsTerminal, Write};
+
+/// Freeze `name@version` for good. The program burns its authority, so the
+/// version can never be updated or transferred again. The version must be
+/// given explicitly, and the configured wallet must be its authority.
+pub async fn handle_freeze(package: String, yes: bool, send: SendOptions) -> Result<()> {
+    let (name, version) = parse_package_spec(&package);
+    let version = version.ok_or_else(|| {
+        AntSolError::Usage(format!("Name the version to freeze, e.g. {}@1.0.0; freezing can't be undone", name))
+    })?;
+    if !yes && (json_output() || !std::io::stdin().is_terminal()) {
+        return Err(AntSolError::Usage(
+            "Freezing can't be undone and needs confirmation, but stdin is not a terminal. Pass --yes to freeze without asking".into()
+        ).into());
+    }
+
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    let signer = load_signer(&config)?;
+    let solana_client = AntSolClient::new(&config)?;
+
+    let package = solana_client.require_package(&name, &version)?;
+    check_freeze(&package, &signer.pubkey())?;
+    if !yes {
+        let impact = version_dependents(&config.indexer_url, &name, &version).await;
+        confirm_freeze(&name, &version, impact.as_ref())?;
+    }
+
+    let spinner = create_spinner(&format!("Freezing {}@{}...", name, version));
+    let signature = solana_client.freeze_package(signer.as_ref(), &name, &version).await;
+    spinner.finish_and_clear();
+    let signature = signature?;
+
+    print_success(&format!("{}@{} is frozen {}", name.green().bold(), version.green(), "🔒".yellow()));
+    println!("  Transaction: {}", signature.cyan());
+    Ok(())
+}
+
+/// Refuse what the program would reject, before signing anything
+fn check_freeze(package: &PackageAccount, wallet: &Pubkey) -> Result<()> {
+    if package.frozen {
+        return Err(AntSolError::Usage(format!("{}@{} is already frozen", package.name, package.version)).into());
+    }
+    if package.authority != *wallet {
+        return Err(AntSolError::Wallet(format!(
+            "{}@{} is owned by {}, not the configured wallet {}",
+            package.name, package.version, package.authority, wallet
+        )).into());
+    }
+    Ok(())
+}
+
+/// Dependents shown before confirming; `None` when the indexer can't say,
+/// which shouldn't hold up a freeze
+async fn version_dependents(indexer_url: &str, name: &str, version: &str) -> Option<VersionDependents> {
+    match IndexerClient::new(indexer_url).get_version_dependents(name, version).await {
+        Ok(impact) => impact,
+        Err(e) => {
+            tracing::debug!("Could not ask the indexer for dependents of {}@{}: {}", name, version, e);
+            None
+        }
+    }
+}
+
+/// Dependents named in the impact line before the rest are counted
+const IMPACT_NAMES_SHOWN: usize = 5;
+
+/// "N packages depend on name@version, including: ..." for the confirmation prompt
+fn impact_line(name: &str, version: &str, impact: &VersionDependents) -> String {
+    let total = impact.dependents.len();
+    if total == 0 {
+        return format!("No indexed package depends on {}@{}.", name, version);
+    }
+    let mut names: Vec<String> = impact.dependents.iter()
+        .take(IMPACT_NAMES_SHOWN)
+        .map(|d| format!("{}@{}", d.name, d.version))
+        .collect();
+    if total > names.len() {
+        names.push(format!("and {} more", total - names.len()));
+    }
+    format!(
+        "{} {} on {}@{} ({} directly, {} through other packages), including: {}",
+        total,
+        if total == 1 { "package depends" } else { "packages depend" },
+        name,
+        version,
+        impact.direct_count,
+        impact.transitive_count,
+        names.join(", ")
+    )
+}
+
+fn confirm_freeze(name: &str, version: &str, impact: Option<&VersionDependents>) -> Result<()> {
+    println!("{}", format!(
+        "Freezing burns the authority of {}@{}: nobody, you included, can update or transfer it afterwards.",
+        name, version
+    ).yellow().bold());
+    if let Some(impact) = impact {
+        println!("{}", impact_line(name, version, impact));
+    }
+    print!("Type \"yes\" to freeze {}@{}: ", name, version);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("yes") {
+        Ok(())
+    } else {
+        Err(AntSolError::Usage("Cancelled; nothing was sent".into()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer_client::VersionDependent;
+    use crate::solana_client::tests::sample_package;
+
+    #[test]
+    fn only_the_authority_freezes_an_unfrozen_version() {
+        let mut package = sample_package("foo", "1.0.0", &[]);
+        let authority = package.authority;
+        check_freeze(&package, &authority).unwrap();
+        assert!(check_freeze(&package, &Pubkey::new_unique()).unwrap_err().to_string().contains("is owned by"));
+
+        package.frozen = true;
+        assert!(check_freeze(&package, &authority).unwrap_err().to_string().contains("already frozen"));
+    }
+
+    #[test]
+    fn impact_line_names_the_nearest_dependents() {
+        let dependent = |name: &&str| VersionDependent { name: name.to_string(), version: "1.0.0".to_string() };
+        let mut impact = VersionDependents { direct_count: 0, transitive_count: 0, dependents: vec![] };
+        assert_eq!(impact_line("core", "1.0.0", &impact), "No indexed package depends on core@1.0.0.");
+
+        impact.direct_count = 2;
+        impact.transitive_count = 5;
+        impact.dependents = ["lib", "util", "app", "tool", "cli", "web", "docs"].iter().map(dependent).collect();
+        assert_eq!(
+            impact_line("core", "1.0.0", &impact),
+            "7 packages depend on core@1.0.0 (2 directly, 5 through other packages), including: lib@1.0.0, util@1.0.0, app@1.0.0, tool@1.0.0, cli@1.0.0, and 2 more"
+        );
+    }
+}
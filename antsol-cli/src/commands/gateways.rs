@@ -0,0 +1,54 @@
+use crate::config::Config;
+use crate::gateway_stats::GatewayStats;
+use crate::types::Result;
+use colored::*;
+
+/// Show per-gateway download success rates and latency
+pub async fn handle_stats(json: bool) -> Result<()> {
+    let path = GatewayStats::default_path()?;
+    let stats = GatewayStats::load(&path);
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    
+    println!("\n{}", "🌐 IPFS Gateway Stats".cyan().bold());
+    println!("{}", "─".repeat(80));
+    println!(
+        "  {:<32} {:>8} {:>8} {:>10} {:>12}",
+        "GATEWAY".bold(), "OK".bold(), "FAILED".bold(), "SUCCESS".bold(), "AVG LATENCY".bold()
+    );
+    
+    let configured = Config::load()?.ipfs_gateways;
+    let known: Vec<&str> = configured.iter().map(String::as_str)
+        .chain(stats.gateways.keys().map(String::as_str).filter(|g| !configured.iter().any(|c| c == *g)))
+        .collect();
+    for gateway in stats.rank(&known) {
+        let record = stats.gateways.get(&gateway).cloned().unwrap_or_default();
+        let rate = record.success_rate()
+            .map(|r| format!("{:.0}%", r * 100.0))
+            .unwrap_or_else(|| "-".to_string());
+        let latency = record.average_latency_ms()
+            .map(|ms| format!("{:.0} ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+        let rate = match record.success_rate() {
+            Some(r) if r >= 0.9 => rate.green(),
+            Some(r) if r >= 0.5 => rate.yellow(),
+            Some(_) => rate.red(),
+            None => rate.dimmed(),
+        };
+        println!(
+            "  {:<32} {:>8} {:>8} {:>10} {:>12}",
+            gateway, record.successes, record.failures, rate, latency
+        );
+    }
+    println!("{}", "─".repeat(80));
+    
+    if let Some(preferred) = stats.preferred(&known) {
+        println!("  Preferred gateway: {}", preferred.green());
+    }
+    println!("  Stats file: {}", path.display().to_string().dimmed());
+    
+    Ok(())
+}
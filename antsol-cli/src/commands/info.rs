@@ -1,30 +1,137 @@
 use crate::config::Config;
+use crate::error::AntSolError;
+use crate::indexer_client::{IndexerClient, VersionRow};
+use crate::ipfs::IpfsClient;
+use crate::local_registry::{LocalPackage, LocalRegistry};
+use crate::markdown;
 use crate::solana_client::AntSolClient;
-use crate::types::Result;
+use crate::types::{Dependency, ExternalDependency, PackageAccount, Result};
 use crate::utils::*;
 use colored::*;
 use chrono::{DateTime, Utc};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::io::{IsTerminal, Write};
 
-/// Show detailed package information from the blockchain
-pub async fn handle_info(package: String) -> Result<()> {
+/// Characters of the CID shown in the versions table
+const CID_PREFIX_LEN: usize = 12;
+
+/// One row of the versions table
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VersionSummary {
+    version: String,
+    published: String,
+    /// Only the indexer counts downloads
+    downloads: Option<u64>,
+    cid: String,
+}
+
+/// Result of `antsol info --output json`
+#[derive(Debug, Serialize)]
+pub struct InfoOutput {
+    pub name: String,
+    pub version: String,
+    /// `"chain"` or `"local"`
+    pub source: &'static str,
+    pub description: String,
+    pub published_at: i64,
+    /// On-chain fields; `None` for local registry packages
+    pub authority: Option<String>,
+    pub account: Option<String>,
+    /// Frozen with `antsol freeze`: no further updates or transfers
+    pub frozen: bool,
+    pub ipfs_cid: Option<String>,
+    /// sha256 of the tarball (for local packages, of the stored archive)
+    pub content_sha256: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub keywords: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+    pub external_dependencies: Vec<ExternalDependency>,
+    pub readme_cid: Option<String>,
+    /// README text, with `--readme`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readme: Option<String>,
+    /// Every published version, newest first, when no version was asked for
+    pub versions: Option<Vec<VersionSummary>>,
+}
+
+/// Show detailed package information from the blockchain.
+///
+/// `name@version` reads that version's account directly. A bare name lists
+/// every published version (from the indexer, or by scanning the program's
+/// accounts when the indexer is down) and shows the latest one.
+///
+/// With `local` (or `local_registry = true`) a matching local registry
+/// package is shown instead, clearly marked as local.
+///
+/// With `readme` the README pinned with the version is downloaded and
+/// rendered after the details (as plain text when stdout isn't a terminal).
+pub async fn handle_info(package: String, local: bool, readme: bool) -> Result<()> {
     let (name, version) = parse_package_spec(&package);
     
-    println!("\n{}", "📋 Fetching Package Info from Blockchain".cyan().bold());
-    let spinner = create_spinner(&format!("Querying on-chain data for {}...", name));
-    
     let config = Config::load()?;
-    let solana_client = AntSolClient::new(&config)?;
+    if local || config.local_registry {
+        let registry = LocalRegistry::open_default()?;
+        let local_version = match &version {
+            Some(version) => Some(version.clone()),
+            None => registry.latest_version(&name)?,
+        };
+        if let Some(local_version) = local_version {
+            if let Some(package) = registry.get(&name, &local_version)? {
+                if readme {
+                    print_warning("Local registry packages have no pinned README");
+                }
+                if json_output() {
+                    return emit_json(&local_output(package));
+                }
+                print_local_info(&registry, &package);
+                return Ok(());
+            }
+        }
+        print_info(&format!("{} is not in the local registry; checking the blockchain", name));
+    }
     
-    // If version not specified, use a default (in production, query latest)
-    let version = version.unwrap_or_else(|| {
-        print_warning("No version specified. Specify version like: package@1.0.0");
-        "1.0.0".to_string()
-    });
+    if !json_output() {
+        print_heading("📋 Fetching Package Info from Blockchain".cyan().bold());
+    }
+    let solana_client = AntSolClient::new(&config)?;
     
-    let package_info = solana_client.get_package(&name, &version)?
-        .ok_or_else(|| format!("Package {}@{} not found on blockchain", name, version))?;
+    // Without a version, list what has been published and show the latest
+    let mut versions = None;
+    let version = match version {
+        Some(version) => version,
+        None => {
+            let spinner = create_spinner(&format!("Looking up published versions of {}...", name));
+            let history = version_history(&config, &solana_client, &name).await;
+            spinner.finish_and_clear();
+            let history = history?;
+            let latest = latest_version(history.iter().map(|row| row.version.as_str()))
+                .ok_or_else(|| AntSolError::PackageNotFound { name: name.clone(), version: None })?;
+            if !json_output() {
+                print_versions(&name, &history, &latest);
+            }
+            versions = Some(history);
+            latest
+        }
+    };
     
+    let spinner = create_spinner(&format!("Querying on-chain data for {}@{}...", name, version));
+    let package_info = solana_client.require_package(&name, &version);
     spinner.finish_and_clear();
+    let package_info = package_info?;
+    let (pda, _) = solana_client.derive_package_pda(&name, &version);
+    let readme = match (readme, &package_info.readme_cid) {
+        (false, _) => None,
+        (true, None) => {
+            print_warning(&format!("{}@{} was published without a README", name, version));
+            None
+        }
+        (true, Some(cid)) => Some(fetch_readme(&config, cid).await?),
+    };
+    if json_output() {
+        return emit_json(&chain_output(package_info, &pda, versions, readme));
+    }
     
     // Display comprehensive package information
     println!("\n{} {}", "📦".cyan(), name.green().bold());
@@ -32,22 +139,46 @@ pub async fn handle_info(package: String) -> Result<()> {
     
     println!("\n{}", "📋 Package Information".cyan().bold());
     println!("  Name: {}", package_info.name.green());
-    println!("  Version: {}", package_info.version.cyan());
+    if package_info.frozen {
+        println!("  Version: {} {}", package_info.version.cyan(), "🔒 frozen".yellow().bold());
+    } else {
+        println!("  Version: {}", package_info.version.cyan());
+    }
     println!("  Description: {}", package_info.description);
     
     let datetime = DateTime::<Utc>::from_timestamp(package_info.published_at, 0)
         .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
         .unwrap_or_else(|| "Unknown".to_string());
     println!("  Published: {}", datetime.yellow());
+    let metadata = &package_info.metadata;
+    if !metadata.repository.is_empty() {
+        println!("  Repository: {}", metadata.repository.blue());
+    }
+    if !metadata.homepage.is_empty() {
+        println!("  Homepage: {}", metadata.homepage.blue());
+    }
+    if !metadata.keywords.is_empty() {
+        println!("  Keywords: {}", metadata.keywords.join(", "));
+    }
     
     println!("\n{}", "⛓️  Blockchain Details".cyan().bold());
-    println!("  Authority (Publisher): {}", package_info.authority.to_string().cyan());
-    let (pda, _) = solana_client.derive_package_pda(&name, &version);
+    if package_info.frozen {
+        println!("  Authority (Publisher): {}", "none, burned when the version was frozen".dimmed());
+    } else {
+        println!("  Authority (Publisher): {}", package_info.authority.to_string().cyan());
+    }
     println!("  On-chain Account: {}", pda.to_string().cyan());
     println!("  Program ID: {}", config.program_id.cyan());
     
     println!("\n{}", "💾 Storage Details".cyan().bold());
     println!("  IPFS CID: {}", package_info.ipfs_cid.yellow());
+    match &package_info.content_sha256 {
+        Some(sha256) => println!("  Content sha256: {}", sha256.yellow()),
+        None => println!("  Content sha256: {}", "not recorded (published before checksums)".dimmed()),
+    }
+    if let Some(readme_cid) = &package_info.readme_cid {
+        println!("  README CID: {}", readme_cid.yellow());
+    }
     println!("  Storage Type: {}", "IPFS (Immutable)".green());
     
     if !package_info.dependencies.is_empty() {
@@ -77,15 +208,14 @@ pub async fn handle_info(package: String) -> Result<()> {
     
     println!("\n{}", "═".repeat(80).cyan());
     
-    println!("\n{}", "🚀 Quick Actions:".yellow().bold());
-    println!("  Install: {}", format!("antsol install {}@{}", name, version).green());
+    print_tips("🚀 Quick Actions:".yellow().bold(), [format!("Install: {}", format!("antsol install {}@{}", name, version).green())]);
     
     println!("\n{}", "🔗 Explorer Links:".blue().bold());
     println!("  Package Account: {}", 
-        format!("https://explorer.solana.com/address/{}?cluster=devnet", pda).blue()
+        explorer_url(&config.rpc_url, &format!("address/{}", pda)).blue()
     );
     println!("  Authority: {}", 
-        format!("https://explorer.solana.com/address/{}?cluster=devnet", package_info.authority).blue()
+        explorer_url(&config.rpc_url, &format!("address/{}", package_info.authority)).blue()
     );
     println!("  IPFS Gateway: {}", 
         format!("https://gateway.pinata.cloud/ipfs/{}", package_info.ipfs_cid).blue()
@@ -94,7 +224,258 @@ pub async fn handle_info(package: String) -> Result<()> {
     println!("\n{}", "🔐 Verification:".green().bold());
     println!("  ✓ Package registered on Solana blockchain");
     println!("  ✓ Content stored immutably on IPFS");
-    println!("  ✓ Ownership verified by on-chain authority");
+    if package_info.frozen {
+        println!("  ✓ Frozen: this version can never be updated or transferred");
+    } else {
+        println!("  ✓ Ownership verified by on-chain authority");
+    }
+    
+    if let Some(readme) = readme {
+        println!("\n{}", "📖 README".cyan().bold());
+        println!("{}", "═".repeat(80).cyan());
+        print_readme(&readme)?;
+    }
     
     Ok(())
 }
+
+/// Download a README by CID through the configured gateways
+async fn fetch_readme(config: &Config, cid: &str) -> Result<String> {
+    let spinner = create_spinner("Downloading README from IPFS...");
+    let bytes = IpfsClient::from_config(config).download_file(cid).await;
+    spinner.finish_and_clear();
+    Ok(String::from_utf8_lossy(&bytes?).into_owned())
+}
+
+/// Rendered markdown on a terminal, the text as written otherwise; long
+/// READMEs go through the pager
+fn print_readme(readme: &str) -> Result<()> {
+    let text = if std::io::stdout().is_terminal() { markdown::render(readme) } else { readme.to_string() };
+    let mut out = PagedOutput::new();
+    writeln!(out, "{}", text)?;
+    out.finish()
+}
+
+fn chain_output(package: PackageAccount, account: &Pubkey, versions: Option<Vec<VersionSummary>>, readme: Option<String>) -> InfoOutput {
+    let non_empty = |value: String| (!value.is_empty()).then_some(value);
+    InfoOutput {
+        name: package.name,
+        version: package.version,
+        source: "chain",
+        description: package.description,
+        published_at: package.published_at,
+        authority: Some(package.authority.to_string()),
+        account: Some(account.to_string()),
+        frozen: package.frozen,
+        ipfs_cid: Some(package.ipfs_cid),
+        content_sha256: package.content_sha256,
+        repository: non_empty(package.metadata.repository),
+        homepage: non_empty(package.metadata.homepage),
+        keywords: package.metadata.keywords,
+        dependencies: package.dependencies,
+        external_dependencies: package.external_dependencies,
+        readme_cid: package.readme_cid,
+        readme,
+        versions,
+    }
+}
+
+fn local_output(package: LocalPackage) -> InfoOutput {
+    InfoOutput {
+        name: package.name,
+        version: package.version,
+        source: "local",
+        description: package.description,
+        published_at: package.published_at,
+        authority: None,
+        account: None,
+        frozen: false,
+        ipfs_cid: None,
+        content_sha256: Some(package.sha256),
+        repository: None,
+        homepage: None,
+        keywords: Vec::new(),
+        dependencies: package.dependencies,
+        external_dependencies: package.external_dependencies,
+        readme_cid: None,
+        readme: None,
+        versions: None,
+    }
+}
+
+fn print_local_info(registry: &LocalRegistry, package: &LocalPackage) {
+    println!("\n{} {} {}", "📦".cyan(), package.name.green().bold(), "[local]".yellow().bold());
+    println!("{}", "═".repeat(80).cyan());
+    
+    println!("\n{}", "📋 Package Information".cyan().bold());
+    println!("  Name: {}", package.name.green());
+    println!("  Version: {}", package.version.cyan());
+    println!("  Description: {}", package.description);
+    
+    let datetime = DateTime::<Utc>::from_timestamp(package.published_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+    println!("  Published locally: {}", datetime.yellow());
+    
+    println!("\n{}", "🏠 Local Registry".cyan().bold());
+    println!("  Archive: {}", registry.archive_path(&package.name, &package.version).display().to_string().yellow());
+    println!("  sha256: {}", package.sha256.cyan());
+    println!("  {}", "Not published on-chain or to IPFS".yellow());
+    
+    println!("\n{}", "🔗 Dependencies".cyan().bold());
+    if package.dependencies.is_empty() {
+        println!("  No dependencies");
+    }
+    for dep in &package.dependencies {
+        println!("  • {}@{}", dep.name.green(), dep.version.yellow());
+    }
+    
+    print_tips("🚀 Quick Actions:".yellow().bold(), [format!("Install: {}", format!("antsol install {} --local", package.id()).green())]);
+}
+
+/// Published versions of `name`, newest first. The indexer is asked first;
+/// if it can't be reached the registry program's accounts are scanned instead.
+async fn version_history(config: &Config, client: &AntSolClient, name: &str) -> Result<Vec<VersionSummary>> {
+    let indexer = IndexerClient::new(&config.indexer_url);
+    let mut rows = match indexer.get_package(name).await {
+        Ok(Some(details)) => details.versions.iter().map(from_indexer).collect(),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            print_warning(&format!("Indexer unavailable ({}); scanning on-chain accounts instead, which is slower", e));
+            client.package_versions(name)?.iter().map(from_account).collect()
+        }
+    };
+    rows.sort_by(|a: &VersionSummary, b| compare_versions(&b.version, &a.version));
+    Ok(rows)
+}
+
+fn from_indexer(row: &VersionRow) -> VersionSummary {
+    VersionSummary {
+        version: row.version.clone(),
+        // Timestamps come back as `YYYY-MM-DD HH:MM:SS` or RFC 3339; the date is enough here
+        published: row.published_at.as_deref()
+            .and_then(|published| published.split(['T', ' ']).next())
+            .unwrap_or("unknown")
+            .to_string(),
+        downloads: row.downloads,
+        cid: row.ipfs_hash.clone().unwrap_or_default(),
+    }
+}
+
+fn from_account(package: &PackageAccount) -> VersionSummary {
+    VersionSummary {
+        version: package.version.clone(),
+        published: DateTime::<Utc>::from_timestamp(package.published_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        downloads: None,
+        cid: package.ipfs_cid.clone(),
+    }
+}
+
+fn cid_prefix(cid: &str) -> String {
+    match cid.char_indices().nth(CID_PREFIX_LEN) {
+        Some((end, _)) => format!("{}…", &cid[..end]),
+        None => cid.to_string(),
+    }
+}
+
+fn print_versions(name: &str, rows: &[VersionSummary], latest: &str) {
+    println!("\n{} {}", "📚 Versions of".cyan().bold(), name.green().bold());
+    println!("  {:<16} {:<12} {:>10}  {}", "VERSION".bold(), "PUBLISHED".bold(), "DOWNLOADS".bold(), "CID".bold());
+    for row in rows {
+        let downloads = row.downloads.map_or_else(|| "-".to_string(), |d| d.to_string());
+        let marker = if row.version == latest { " (latest)".green().to_string() } else { String::new() };
+        println!(
+            "  {:<16} {:<12} {:>10}  {}{}",
+            row.version.cyan(),
+            row.published,
+            downloads,
+            cid_prefix(&row.cid).dimmed(),
+            marker
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::{client_with, sample_package};
+
+    fn version_row(version: &str, published_at: Option<&str>, downloads: Option<u64>) -> VersionRow {
+        VersionRow {
+            version: version.to_string(),
+            ipfs_hash: Some("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi".to_string()),
+            downloads,
+            published_at: published_at.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn indexer_rows_keep_the_date_and_downloads() {
+        let row = from_indexer(&version_row("1.2.0", Some("2024-03-01T10:00:00Z"), Some(42)));
+        assert_eq!(row.published, "2024-03-01");
+        assert_eq!(row.downloads, Some(42));
+        assert_eq!(from_indexer(&version_row("1.0.0", Some("2024-01-02 03:04:05"), None)).published, "2024-01-02");
+        assert_eq!(from_indexer(&version_row("1.0.0", None, None)).published, "unknown");
+    }
+
+    #[test]
+    fn cid_prefix_shortens_long_cids_only() {
+        assert_eq!(cid_prefix("bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi"), "bafybeigdyrz…");
+        assert_eq!(cid_prefix("QmShort"), "QmShort");
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_an_account_scan_without_an_indexer() {
+        let client = client_with(&[
+            sample_package("foo", "1.2.0", &[]),
+            sample_package("foo", "1.10.0", &[]),
+            sample_package("foo", "2.0.0-rc.1", &[]),
+        ], None);
+        let config = Config { indexer_url: "http://127.0.0.1:1".to_string(), ..Config::default() };
+
+        let history = version_history(&config, &client, "foo").await.unwrap();
+        let versions: Vec<&str> = history.iter().map(|row| row.version.as_str()).collect();
+        assert_eq!(versions, ["2.0.0-rc.1", "1.10.0", "1.2.0"]);
+        assert!(history.iter().all(|row| row.downloads.is_none() && row.published == "2023-11-14"));
+        assert_eq!(latest_version(versions).as_deref(), Some("1.10.0"));
+        assert_eq!(client.rpc_usage().count("getProgramAccounts"), 1);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let mut package = sample_package("foo", "1.0.0", &[("bar", "2.0.0")]);
+        package.metadata.homepage = "https://foo.dev".to_string();
+        package.metadata.keywords = vec!["parsing".to_string()];
+        let cid = package.ipfs_cid.clone();
+        let authority = package.authority.to_string();
+        let account = Pubkey::new_unique();
+        let versions = vec![from_account(&package)];
+        let value = serde_json::to_value(chain_output(package, &account, Some(versions), None)).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "name": "foo",
+            "version": "1.0.0",
+            "source": "chain",
+            "description": "foo test package",
+            "published_at": 1_700_000_000,
+            "authority": authority,
+            "account": account.to_string(),
+            "frozen": false,
+            "ipfs_cid": cid,
+            "content_sha256": null,
+            "repository": null,
+            "homepage": "https://foo.dev",
+            "keywords": ["parsing"],
+            "dependencies": [{ "name": "bar", "version": "2.0.0" }],
+            "external_dependencies": [],
+            "readme_cid": null,
+            "versions": [{
+                "version": "1.0.0",
+                "published": "2023-11-14",
+                "downloads": null,
+                "cid": cid
+            }]
+        }));
+    }
+}
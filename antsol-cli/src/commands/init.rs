@@ -1,60 +1,94 @@
+use crate::error::AntSolError;
+use crate::foreign_manifest::{self, ForeignManifest, ManifestKind};
 use crate::types::{AntSolManifest, PackageInfo, Result};
 use crate::utils::*;
 use colored::*;
-use std::io::Write;
+use std::io::{IsTerminal, Write};
+
+/// Values passed on the command line; anything missing is prompted for,
+/// or defaulted with `yes`
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub license: Option<String>,
+    pub yes: bool,
+    /// Prefill from this manifest in the current directory without asking
+    pub from: Option<ManifestKind>,
+}
+
+impl InitOptions {
+    /// No flags at all: the classic interactive flow
+    fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.version.is_none()
+            && self.description.is_none()
+            && self.author.is_none()
+            && self.license.is_none()
+            && !self.yes
+            && self.from.is_none()
+    }
+}
 
 /// Initialize a new package for the decentralized registry
-pub async fn handle_init() -> Result<()> {
+pub async fn handle_init(mut options: InitOptions) -> Result<()> {
     print_info("Initializing package for AntSol decentralized registry...");
     
     // Check if antsol.toml already exists
     if std::path::Path::new("antsol.toml").exists() {
-        return Err("antsol.toml already exists!".into());
+        return Err(AntSolError::Usage("antsol.toml already exists!".into()).into());
     }
     
-    // Interactive prompts
-    print!("\n📦 Package name: ");
-    std::io::stdout().flush()?;
-    let mut name = String::new();
-    std::io::stdin().read_line(&mut name)?;
-    let name = name.trim().to_string();
-    
-    if !validate_package_name(&name) {
-        return Err("Invalid package name. Use only lowercase letters, numbers, and hyphens.".into());
+    let can_prompt = std::io::stdin().is_terminal();
+    if options.is_empty() && !can_prompt {
+        return Err(AntSolError::Usage(
+            "stdin is not a terminal. Pass --name (and optionally --version, --description), or --yes to accept defaults".into()
+        ).into());
     }
     
-    print!("📌 Version (default: 0.1.0): ");
-    std::io::stdout().flush()?;
-    let mut version = String::new();
-    std::io::stdin().read_line(&mut version)?;
-    let version = version.trim();
-    let version = if version.is_empty() {
-        "0.1.0".to_string()
-    } else {
-        version.to_string()
+    let cwd = std::env::current_dir()?;
+    let dir_name = cwd.file_name().map(|n| n.to_string_lossy().to_string());
+    let ask = |label: &str| -> Result<String> {
+        print!("{}", label);
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(answer.trim().to_string())
     };
     
-    if !validate_version(&version) {
-        return Err("Invalid version. Use semantic versioning (e.g., 1.0.0)".into());
-    }
-    
-    print!("📝 Description: ");
-    std::io::stdout().flush()?;
-    let mut description = String::new();
-    std::io::stdin().read_line(&mut description)?;
-    let description = description.trim().to_string();
+    let imported = match options.from {
+        Some(kind) => {
+            // --from-cargo / --from-npm never prompt
+            options.yes = true;
+            Some((kind, foreign_manifest::load(kind, &cwd)?))
+        }
+        None if can_prompt && !options.yes => match foreign_manifest::detect(&cwd) {
+            Some(kind) if confirm_import(kind, ask)? => Some((kind, foreign_manifest::load(kind, &cwd)?)),
+            _ => None,
+        },
+        None => None,
+    };
     
-    // Create manifest
-    let manifest = AntSolManifest {
-        package: PackageInfo {
-            name,
-            version,
-            description,
-            authors: None,
-            license: None,
+    let manifest = match imported {
+        Some((kind, imported)) => {
+            if !imported.dependencies.is_empty() {
+                print_info(&format!("Adding {} external dependencies from {}", imported.dependencies.len(), kind.file_name()));
+            }
+            if !imported.skipped.is_empty() {
+                print_warning(&format!(
+                    "Skipped dependencies without a registry version: {}",
+                    imported.skipped.join(", ")
+                ));
+            }
+            scaffold(options, imported, can_prompt, dir_name.as_deref(), ask)?
+        }
+        None => AntSolManifest {
+            package: collect_package_info(options, can_prompt, dir_name.as_deref(), ask)?,
+            dependencies: None,
+            external_dependencies: None,
         },
-        dependencies: None,
-        external_dependencies: None,
     };
     
     // Write to file
@@ -63,14 +97,223 @@ pub async fn handle_init() -> Result<()> {
     
     print_success("Created antsol.toml manifest");
     
-    println!("\n{}", "🚀 Next Steps:".cyan().bold());
-    println!("  1. Add your package files to this directory");
-    println!("  2. Connect your Solana wallet: {}", "antsol wallet connect <keypair.json>".cyan());
-    println!("  3. Publish to blockchain: {}", "antsol publish".cyan());
-    println!("\n{}", "💡 Your package will be:".yellow());
-    println!("  • Stored immutably on IPFS");
-    println!("  • Registered on-chain on Solana");
-    println!("  • Verifiable and censorship-resistant");
+    print_tips("🚀 Next Steps:".cyan().bold(), [
+        "1. Add your package files to this directory".to_string(),
+        format!("2. Connect your Solana wallet: {}", "antsol wallet connect <keypair.json>".cyan()),
+        format!("3. Publish to blockchain: {}", "antsol publish".cyan()),
+    ]);
+    print_tips("💡 Your package will be:".yellow(), [
+        "• Stored immutably on IPFS",
+        "• Registered on-chain on Solana",
+        "• Verifiable and censorship-resistant",
+    ]);
     
     Ok(())
 }
+
+fn confirm_import<F>(kind: ManifestKind, mut ask: F) -> Result<bool>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let answer = ask(&format!(
+        "\n📄 Found {}. Prefill name, version, description, authors, license and dependencies from it? [Y/n]: ",
+        kind.file_name()
+    ))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "" | "y" | "yes"))
+}
+
+/// Build the manifest with values from another ecosystem's manifest standing
+/// in for missing flags. Flags still win; values the registry would reject
+/// (a name like `My_Crate`, a non-semver version) are adjusted or dropped.
+fn scaffold<F>(
+    mut options: InitOptions,
+    imported: ForeignManifest,
+    can_prompt: bool,
+    dir_name: Option<&str>,
+    ask: F,
+) -> Result<AntSolManifest>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    options.name = options.name.or_else(|| imported.name.as_deref().and_then(imported_name));
+    options.version = options.version.or(imported.version.filter(|v| validate_version(v)));
+    options.description = options.description.or(imported.description);
+    options.license = options.license.or(imported.license);
+    
+    let mut package = collect_package_info(options, can_prompt, dir_name, ask)?;
+    if package.authors.is_none() && !imported.authors.is_empty() {
+        package.authors = Some(imported.authors);
+    }
+    Ok(AntSolManifest {
+        package,
+        dependencies: None,
+        external_dependencies: (!imported.dependencies.is_empty()).then_some(imported.dependencies),
+    })
+}
+
+/// Registry name for a crate or npm package name, e.g. `token_utils` ->
+/// `token-utils` and `@acme/wallet-kit` -> `acme/wallet-kit`
+fn imported_name(name: &str) -> Option<String> {
+    let segments = name
+        .trim_start_matches('@')
+        .split('/')
+        .map(default_name)
+        .collect::<Option<Vec<_>>>()?;
+    let name = segments.join("/");
+    validate_package_name(&name).then_some(name)
+}
+
+/// Fill in the manifest's package table from flags, prompts and defaults.
+///
+/// A flag always wins. Otherwise `--yes` takes the default, an interactive
+/// session asks, and a non-interactive one takes the default; only the name
+/// has no default without `--yes`, so it is an error there.
+fn collect_package_info<F>(options: InitOptions, can_prompt: bool, dir_name: Option<&str>, mut ask: F) -> Result<PackageInfo>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let prompt = can_prompt && !options.yes;
+    
+    let name = match options.name {
+        Some(name) => name,
+        None if prompt => ask("\n📦 Package name: ")?,
+        None if options.yes => dir_name.and_then(default_name).ok_or_else(|| AntSolError::Usage(
+            "Could not derive a package name from the current directory. Pass --name".into()
+        ))?,
+        None => return Err(AntSolError::Usage("Missing package name: pass --name, or --yes to use the directory name".into()).into()),
+    };
+    if !validate_package_name(&name) {
+        return Err(AntSolError::Usage("Invalid package name. Use only lowercase letters, numbers, and hyphens, optionally as scope/name.".into()).into());
+    }
+    
+    let version = match options.version {
+        Some(version) => version,
+        None if prompt => ask("📌 Version (default: 0.1.0): ")?,
+        None => String::new(),
+    };
+    let version = if version.is_empty() { "0.1.0".to_string() } else { version };
+    if !validate_version(&version) {
+        return Err(AntSolError::Usage("Invalid version. Use semantic versioning (e.g., 1.0.0)".into()).into());
+    }
+    
+    let description = match options.description {
+        Some(description) => description,
+        None if prompt => ask("📝 Description: ")?,
+        None => String::new(),
+    };
+    
+    Ok(PackageInfo {
+        name,
+        version,
+        description,
+        authors: options.author.map(|a| vec![a]),
+        license: options.license,
+        repository: None,
+        homepage: None,
+        keywords: None,
+    })
+}
+
+/// Registry-safe package name derived from a directory name, e.g. `My_Project` -> `my-project`
+fn default_name(dir_name: &str) -> Option<String> {
+    let mapped: String = dir_name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_lowercase() || c.is_ascii_digit() { c } else { '-' })
+        .collect();
+    let name = mapped.split('-').filter(|s| !s.is_empty()).collect::<Vec<_>>().join("-");
+    (!name.is_empty() && validate_package_name(&name)).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+
+    fn no_prompt(label: &str) -> Result<String> {
+        panic!("unexpected prompt: {}", label)
+    }
+
+    #[test]
+    fn yes_fills_defaults_without_prompting() {
+        let options = InitOptions { yes: true, ..Default::default() };
+        let package = collect_package_info(options, true, Some("My_Cool Project"), no_prompt).unwrap();
+        assert_eq!(package.name, "my-cool-project");
+        assert_eq!(package.version, "0.1.0");
+        assert_eq!(package.description, "");
+        assert!(package.authors.is_none());
+    }
+
+    #[test]
+    fn flags_win_and_only_missing_values_are_prompted() {
+        let options = InitOptions {
+            name: Some("foo".to_string()),
+            author: Some("Ada <ada@example.com>".to_string()),
+            license: Some("MIT".to_string()),
+            ..Default::default()
+        };
+        let mut asked = Vec::new();
+        let package = collect_package_info(options, true, None, |label| {
+            asked.push(label.to_string());
+            Ok(if label.contains("Version") { "2.0.0".to_string() } else { "A thing".to_string() })
+        }).unwrap();
+        assert_eq!(asked.len(), 2);
+        assert_eq!(package.name, "foo");
+        assert_eq!(package.version, "2.0.0");
+        assert_eq!(package.description, "A thing");
+        assert_eq!(package.authors, Some(vec!["Ada <ada@example.com>".to_string()]));
+        assert_eq!(package.license.as_deref(), Some("MIT"));
+    }
+
+    #[test]
+    fn non_interactive_requires_a_name_and_validates_flags() {
+        let options = InitOptions { description: Some("x".to_string()), ..Default::default() };
+        let err = collect_package_info(options, false, Some("dir"), no_prompt).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Usage);
+        assert!(err.to_string().contains("--name"));
+
+        let options = InitOptions { name: Some("foo".to_string()), ..Default::default() };
+        assert_eq!(collect_package_info(options, false, None, no_prompt).unwrap().version, "0.1.0");
+
+        let options = InitOptions { name: Some("Foo".to_string()), yes: true, ..Default::default() };
+        assert!(collect_package_info(options, false, None, no_prompt).is_err());
+    }
+
+    #[test]
+    fn scaffolds_from_a_workspace_member_with_flags_winning() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/manifests/cargo-workspace");
+        let imported = foreign_manifest::load(ManifestKind::Cargo, &dir).unwrap();
+        let options = InitOptions { license: Some("Apache-2.0".to_string()), yes: true, ..Default::default() };
+        let manifest = scaffold(options, imported, false, Some("cargo-workspace"), no_prompt).unwrap();
+        assert_eq!(manifest.package.name, "vault");
+        assert_eq!(manifest.package.version, "0.4.0");
+        assert_eq!(manifest.package.description, "Vault helpers");
+        assert_eq!(manifest.package.authors, Some(vec!["Ada <ada@example.com>".to_string()]));
+        assert_eq!(manifest.package.license.as_deref(), Some("Apache-2.0"));
+
+        let written = toml::to_string_pretty(&manifest).unwrap();
+        assert!(written.contains("[[external_dependencies]]\nname = \"sha2\"\nversion = \"0.10\"\ntype = \"rust\"\nregistry = \"crates.io\""), "{}", written);
+    }
+
+    #[test]
+    fn imported_names_are_made_registry_safe() {
+        assert_eq!(imported_name("token_utils").as_deref(), Some("token-utils"));
+        assert_eq!(imported_name("@acme/wallet-kit").as_deref(), Some("acme/wallet-kit"));
+        assert_eq!(imported_name("a/b/c"), None);
+
+        let imported = ForeignManifest { name: Some("___".to_string()), version: Some("1.0".to_string()), ..Default::default() };
+        let manifest = scaffold(InitOptions { yes: true, ..Default::default() }, imported, false, Some("proj"), no_prompt).unwrap();
+        assert_eq!(manifest.package.name, "proj");
+        assert_eq!(manifest.package.version, "0.1.0");
+        assert!(manifest.external_dependencies.is_none());
+    }
+
+    #[test]
+    fn accepts_scoped_names() {
+        let options = InitOptions { name: Some("alice/token-utils".to_string()), yes: true, ..Default::default() };
+        assert_eq!(collect_package_info(options, false, None, no_prompt).unwrap().name, "alice/token-utils");
+
+        let options = InitOptions { name: Some("alice/team/utils".to_string()), yes: true, ..Default::default() };
+        assert!(collect_package_info(options, false, None, no_prompt).is_err());
+    }
+}
@@ -1,73 +1,285 @@
+use crate::cache::PackageCache;
 use crate::config::Config;
-use crate::ipfs::IpfsClient;
+use crate::error::AntSolError;
+use crate::indexer_client::IndexerClient;
+use crate::extract::{extract_archive, ExtractLimits};
+use crate::ipfs::{FetchedArchive, IpfsClient};
+use crate::local_registry::{is_local_cid, LocalRegistry};
+use crate::lockfile::LOCKFILE_NAME;
+use crate::package_files::tree_sha256;
+use crate::resolver::{self, LocalFirst, PlannedPackage, ResolutionPlan, VersionReason, VersionSpec};
+use crate::signer::load_signer;
 use crate::solana_client::AntSolClient;
-use crate::types::Result;
+use crate::types::{LockFile, LockedPackage, Result};
 use crate::utils::*;
 use colored::*;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
-/// Install a package from the decentralized registry
-pub async fn handle_install(package_spec: String) -> Result<()> {
+/// Result of `antsol install --output json` (`--plan` prints the plan itself)
+#[derive(Debug, Serialize)]
+pub struct InstallOutput {
+    /// `name@version` that was asked for; `None` with `--locked`
+    pub requested: Option<String>,
+    /// The requested package first, then its dependencies
+    pub packages: Vec<InstalledEntry>,
+    /// Whether `antsol.lock` was written
+    pub lockfile_updated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstalledEntry {
+    pub name: String,
+    pub version: String,
+    pub ipfs_cid: String,
+    /// sha256 of the downloaded archive
+    pub sha256: String,
+    pub path: String,
+    /// Copied from the local development registry
+    pub local: bool,
+    /// Extracted from the download cache instead of a gateway
+    pub from_cache: bool,
+}
+
+fn install_output(requested: Option<String>, packages: &[PlannedPackage], fetched: &[FetchedArchive], lockfile_updated: bool) -> InstallOutput {
+    InstallOutput {
+        requested,
+        packages: packages.iter().zip(fetched).map(|(package, archive)| InstalledEntry {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            ipfs_cid: package.ipfs_cid.clone(),
+            sha256: archive.sha256.clone(),
+            path: package.install_dir.clone(),
+            local: package.local,
+            from_cache: archive.from_cache,
+        }).collect(),
+        lockfile_updated,
+    }
+}
+
+/// Install a package from the decentralized registry.
+///
+/// With `plan_only` the resolved plan is printed (as JSON with `json`) and
+/// nothing is downloaded or written. With `locked` (and no package) exactly
+/// the packages pinned in `antsol.lock` are installed. With `local` (or
+/// `local_registry = true`) the local development registry is consulted
+/// before the chain, and `antsol.lock` is left untouched.
+///
+/// Packages are extracted under `target_dir`, or the `install_dir` config
+/// (`antsol_packages` by default). Archives already in the download cache
+/// (`~/.antsol/cache`) are verified and used without contacting a gateway.
+///
+/// After a successful install each package fetched from the registry is
+/// reported to the indexer's download counter, unless `no_telemetry` or
+/// `telemetry = false`. Reporting runs in the background and never fails the install.
+/// With `record_download` each is instead counted on-chain by a
+/// `record_download` transaction from the configured wallet, falling back to
+/// the indexer report when the transaction fails.
+///
+/// With `offline` (and no package) the lockfile is installed from the
+/// download cache alone; see [`handle_install_offline`].
+///
+/// Symlinks in an archive are rejected unless `allow_symlinks`, and then
+/// only links that stay inside the package are created.
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_install(
+    package_spec: Option<String>,
+    plan_only: bool,
+    json: bool,
+    locked: bool,
+    offline: bool,
+    local: bool,
+    no_telemetry: bool,
+    record_download: bool,
+    target_dir: Option<PathBuf>,
+    allow_symlinks: bool,
+) -> Result<()> {
+    let package_spec = match package_spec {
+        Some(spec) => spec,
+        None if offline => return handle_install_offline(target_dir, allow_symlinks).await,
+        None if locked => return handle_install_locked(no_telemetry, record_download, target_dir, allow_symlinks).await,
+        None => return Err(AntSolError::Usage("Specify a package to install, or pass --locked or --offline".into()).into()),
+    };
     let (name, version) = parse_package_spec(&package_spec);
     
-    println!("\n{}", "📥 Installing from Decentralized Registry".cyan().bold());
-    print_info(&format!("Package: {}...", name.cyan()));
+    if !json {
+        print_heading("📥 Installing from Decentralized Registry".cyan().bold());
+        print_info(&format!("Package: {}...", name.cyan()));
+    }
     
     // Load config
     let config = Config::load()?;
     let solana_client = AntSolClient::new(&config)?;
+    let registry = if local || config.local_registry {
+        Some(LocalRegistry::open_default()?)
+    } else {
+        None
+    };
     
-    // Determine version
-    let version = version.unwrap_or_else(|| {
-        print_warning("No version specified, attempting to find latest");
-        "latest".to_string()
-    });
+    // Determine version (local registry first, then the indexer)
+    let local_latest = match &registry {
+        Some(registry) => registry.latest_version(&name)?,
+        None => None,
+    };
+    let (version, reason) = match version {
+        Some(spec) => match VersionSpec::parse(&spec)? {
+            VersionSpec::Exact(version) => (version, VersionReason::Requested),
+            range => {
+                let local_match = match &registry {
+                    Some(registry) => range.select(registry.versions(&name)?.iter().map(String::as_str)),
+                    None => None,
+                };
+                let pinned = match local_match {
+                    Some(pinned) => pinned,
+                    None => resolve_range(&config.indexer_url, &name, &spec, &range).await?,
+                };
+                if !json {
+                    print_info(&format!("Resolved {} to {}", spec.cyan(), pinned.cyan()));
+                }
+                (pinned, VersionReason::Range { requirement: spec })
+            }
+        },
+        None => {
+            let latest = match local_latest {
+                Some(latest) => latest,
+                None => resolve_latest_version(&config.indexer_url, &name).await?,
+            };
+            if !json {
+                print_info(&format!("Resolved latest version: {}", latest.cyan()));
+            }
+            (latest, VersionReason::Latest)
+        }
+    };
     
-    // Step 1: Fetch package metadata from blockchain
-    let spinner = create_spinner("🔍 Fetching package metadata from blockchain...");
-    let package = match solana_client.get_package(&name, &version) {
-        Ok(Some(pkg)) => pkg,
-        Ok(None) => {
-            spinner.finish_and_clear();
-            return Err(format!("Package {}@{} not found on-chain", name, version).into());
+    // Step 1: Resolve the full dependency graph from the blockchain into a plan
+    let spinner = create_spinner("🔍 Resolving dependency graph from blockchain...");
+    let packages_dir = target_dir.unwrap_or_else(|| config.install_dir.clone());
+    let lockfile_path = Path::new(LOCKFILE_NAME);
+    let lockfile = LockFile::load(lockfile_path)?;
+    let plan = match &registry {
+        Some(registry) => {
+            let source = LocalFirst { local: registry, fallback: &solana_client };
+            resolver::plan(&source, &name, &version, reason, &packages_dir, None).await
         }
+        None => resolver::plan(&solana_client, &name, &version, reason, &packages_dir, lockfile.as_ref()).await,
+    };
+    spinner.finish_and_clear();
+    let mut plan = match plan {
+        Ok(plan) => plan,
         Err(e) => {
-            spinner.finish_and_clear();
-            return Err(format!("Error fetching package from blockchain: {}", e).into());
+            if e.is::<AntSolError>() {
+                return Err(e);
+            }
+            return Err(AntSolError::Network(format!("Error fetching package from blockchain: {}", e)).into());
         }
     };
-    spinner.finish_and_clear();
     
-    print_success(&format!("Found {}@{} on blockchain", name.green(), version.green()));
+    let ipfs_client = IpfsClient::from_config(&config)
+        .with_cache(PackageCache::open_default()?)
+        .with_symlinks_allowed(allow_symlinks);
     
-    // Step 2: Verify dependencies
-    if !package.dependencies.is_empty() {
-        let spinner = create_spinner("🔗 Verifying dependencies on-chain...");
-        for dep in &package.dependencies {
-            let dep_exists = solana_client.get_package(&dep.name, &dep.version)?.is_some();
-            if !dep_exists {
-                spinner.finish_and_clear();
-                return Err(format!("❌ Dependency {}@{} not found on-chain", dep.name, dep.version).into());
-            }
+    if plan_only {
+        let spinner = create_spinner("📏 Looking up archive sizes...");
+        let targets: Vec<(String, String, String)> = plan.packages.iter()
+            .map(|p| (p.name.clone(), p.version.clone(), p.ipfs_cid.clone()))
+            .collect();
+        for (name, version, cid) in targets {
+            let size = match &registry {
+                Some(registry) if is_local_cid(&cid) => {
+                    std::fs::metadata(registry.archive_path(&name, &version)).ok().map(|m| m.len())
+                }
+                _ => ipfs_client.content_size(&cid).await,
+            };
+            plan.record_size(&name, size);
         }
         spinner.finish_and_clear();
-        print_success(&format!("All {} dependencies verified on blockchain", package.dependencies.len()));
+        
+        if json {
+            emit_json(&plan)?;
+        } else {
+            println!();
+            for line in plan.render() {
+                println!("{}", line);
+            }
+            println!("\n{}", "No changes made (--plan).".dimmed());
+        }
+        return match plan.failure {
+            Some(failure) => Err(failure.into_error()),
+            None => Ok(()),
+        };
     }
     
-    // Step 3: Download from IPFS
-    let spinner = create_spinner("⬇️  Downloading package from IPFS (verifying integrity)...");
-    let ipfs_client = IpfsClient::new(config.ipfs_url);
+    if let Some(failure) = plan.failure {
+        return Err(failure.into_error());
+    }
     
-    let packages_dir = PathBuf::from("antsol_packages");
-    std::fs::create_dir_all(&packages_dir)?;
+    if plan.packages[0].local {
+        print_success(&format!("Found {}@{} in the local registry", name.green(), version.green()));
+    } else {
+        print_success(&format!("Found {}@{} on blockchain", name.green(), version.green()));
+    }
+    if plan.packages.len() > 1 {
+        print_success(&format!("Resolved {} dependencies on blockchain", plan.packages.len() - 1));
+    }
+    for warning in &plan.warnings {
+        print_warning(warning);
+    }
     
-    let package_dir = packages_dir.join(&name);
-    std::fs::create_dir_all(&package_dir)?;
+    // Step 2: Download every planned package from IPFS (or copy it from the local registry)
+    let fetched = execute_plan(&plan, &ipfs_client, registry.as_ref()).await?;
+    let mut downloaded: Vec<(String, String)> =
+        plan.packages.iter().filter(|p| !p.local).map(|p| (p.name.clone(), p.version.clone())).collect();
+    if record_download {
+        downloaded = record_downloads_on_chain(&config, &solana_client, downloaded).await;
+    }
+    let reports = DownloadReports::spawn(&config.indexer_url, config.telemetry && !no_telemetry, downloaded.into_iter());
     
-    ipfs_client.download_package(&package.ipfs_cid, &package_dir).await?;
-    spinner.finish_and_clear();
+    // Step 3: Record exactly what was installed; local installs stay out of the lockfile
+    if registry.is_some() {
+        print_info(&format!("Local registry install: {} left untouched", LOCKFILE_NAME));
+    } else {
+        let mut lockfile = lockfile.unwrap_or_else(LockFile::new);
+        for (package, archive) in plan.packages.iter().zip(&fetched) {
+            // Keep a dependency's recorded range as long as it stays at the version the range picked
+            let requirement = match &package.reason {
+                VersionReason::Range { requirement } => Some(requirement.clone()),
+                VersionReason::Dependency { .. } => lockfile.get(&package.name)
+                    .filter(|l| l.version == package.version)
+                    .and_then(|l| l.requirement.clone()),
+                _ => None,
+            };
+            lockfile.upsert(LockedPackage {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                ipfs_cid: package.ipfs_cid.clone(),
+                sha256: archive.sha256.clone(),
+                files_sha256: Some(tree_sha256(Path::new(&package.install_dir))?),
+                authority: Some(package.account.authority.to_string()),
+                dependencies: package.account.dependencies.iter()
+                    .map(|d| format!("{}@{}", d.name, d.version))
+                    .collect(),
+                requirement,
+                from_cache: archive.from_cache,
+            });
+        }
+        lockfile.save(lockfile_path)?;
+        print_success(&format!("Updated {}", LOCKFILE_NAME));
+    }
+    
+    if json {
+        reports.finish().await;
+        let requested = format!("{}@{}", name, version);
+        return emit_json(&install_output(Some(requested), &plan.packages, &fetched, registry.is_none()));
+    }
+    
+    let package = &plan.packages[0].account;
+    let package_dir = packages_dir.join(&name);
     
-    print_success(&format!("Installed {}@{} with cryptographic verification", name.green().bold(), version.green()));
+    if quiet() {
+        print_success(&format!("Installed {}@{} to {}", package.name, package.version, package_dir.display()));
+        reports.finish().await;
+        return Ok(());
+    }
     
     println!("\n{}", "✨ Package Installed Successfully!".green().bold());
     println!("{}", "═".repeat(80).cyan());
@@ -79,9 +291,9 @@ pub async fn handle_install(package_spec: String) -> Result<()> {
     println!("  IPFS CID: {}", package.ipfs_cid.cyan());
     
     if !package.dependencies.is_empty() {
-        println!("\n{}", "🔗 Dependencies:".blue().bold());
-        for dep in &package.dependencies {
-            println!("  • {}@{}", dep.name.green(), dep.version.yellow());
+        println!("\n{}", "🌳 Installed Tree:".blue().bold());
+        for line in &plan.tree {
+            println!("  {}", line);
         }
     }
     
@@ -100,13 +312,377 @@ pub async fn handle_install(package_spec: String) -> Result<()> {
         }
     }
     
-    println!("\n{}", "💡 Import in your code:".yellow());
-    println!("  {}", format!("use antsol_packages::{};", name.replace("-", "_")).cyan());
+    print_tips("💡 Import in your code:".yellow(), [format!("use antsol_packages::{};", name.replace("-", "_")).cyan()]);
     
     println!("\n{}", "🔐 Security:".green().bold());
-    println!("  ✓ On-chain verification passed");
-    println!("  ✓ IPFS content integrity verified");
-    println!("  ✓ Dependencies checked on blockchain");
+    let local_count = plan.packages.iter().filter(|p| p.local).count();
+    if local_count > 0 {
+        println!("  {} {} package(s) installed from the local registry (not on-chain)", "⚠".yellow(), local_count);
+    }
+    if local_count < plan.packages.len() {
+        println!("  ✓ On-chain verification passed");
+        println!("  ✓ IPFS content integrity verified");
+    }
+    if registry.is_none() {
+        println!("  ✓ Dependency graph resolved on blockchain");
+        println!("  ✓ Pinned in {}", LOCKFILE_NAME);
+    }
     
+    reports.finish().await;
     Ok(())
 }
+
+/// Install exactly what `antsol.lock` pins, failing if the chain disagrees
+async fn handle_install_locked(no_telemetry: bool, record_download: bool, target_dir: Option<PathBuf>, allow_symlinks: bool) -> Result<()> {
+    if !json_output() {
+        print_heading("🔒 Installing from antsol.lock".cyan().bold());
+    }
+    
+    let lockfile = require_lockfile()?;
+    
+    let config = Config::load()?;
+    let solana_client = AntSolClient::new(&config)?;
+    // One account lookup per locked package; abort up front rather than halfway through
+    solana_client.rpc_usage().ensure_budget(lockfile.packages.len() as u64)?;
+    
+    let spinner = create_spinner("🔍 Checking locked packages against the blockchain...");
+    let packages_dir = target_dir.unwrap_or_else(|| config.install_dir.clone());
+    let plan = resolver::plan_locked(&solana_client, &lockfile, &packages_dir).await;
+    spinner.finish_and_clear();
+    let plan = plan?;
+    print_success(&format!("All {} locked CIDs match on-chain metadata", plan.packages.len()));
+    
+    let ipfs_client = IpfsClient::from_config(&config)
+        .with_cache(PackageCache::open_default()?)
+        .with_symlinks_allowed(allow_symlinks);
+    let fetched = execute_plan(&plan, &ipfs_client, None).await?;
+    let mut downloaded: Vec<(String, String)> = plan.packages.iter().map(|p| (p.name.clone(), p.version.clone())).collect();
+    if record_download {
+        downloaded = record_downloads_on_chain(&config, &solana_client, downloaded).await;
+    }
+    let reports = DownloadReports::spawn(&config.indexer_url, config.telemetry && !no_telemetry, downloaded.into_iter());
+    if json_output() {
+        reports.finish().await;
+        return emit_json(&install_output(None, &plan.packages, &fetched, false));
+    }
+    
+    if quiet() {
+        print_success(&format!("Installed {} package(s) from {}", plan.packages.len(), LOCKFILE_NAME));
+    } else {
+        println!("\n{}", "✨ Locked Install Complete!".green().bold());
+        for line in &plan.tree {
+            println!("  {}", line);
+        }
+    }
+    reports.finish().await;
+    Ok(())
+}
+
+/// Install what `antsol.lock` pins from the download cache alone. The RPC,
+/// the indexer and IPFS gateways are never contacted, so nothing is checked
+/// against the chain: the cached archives are only verified against the
+/// CIDs and checksums the lockfile recorded. Nothing is reported to the
+/// download counter.
+async fn handle_install_offline(target_dir: Option<PathBuf>, allow_symlinks: bool) -> Result<()> {
+    if !json_output() {
+        print_heading("✈️  Installing offline from antsol.lock".cyan().bold());
+    }
+    
+    let lockfile = require_lockfile()?;
+    let config = Config::load()?;
+    print_warning(&format!(
+        "Offline: skipping on-chain verification. Packages are checked against the CIDs and checksums in {} only",
+        LOCKFILE_NAME
+    ));
+    
+    let packages_dir = target_dir.unwrap_or_else(|| config.install_dir.clone());
+    let cache = PackageCache::open_default()?;
+    let plan = plan_offline(&lockfile, &packages_dir, &cache).await?;
+    let ipfs_client = IpfsClient::from_config(&config)
+        .with_cache(cache)
+        .with_offline(true)
+        .with_symlinks_allowed(allow_symlinks);
+    let fetched = execute_plan(&plan, &ipfs_client, None).await?;
+    if json_output() {
+        return emit_json(&install_output(None, &plan.packages, &fetched, false));
+    }
+    
+    if quiet() {
+        print_success(&format!("Installed {} package(s) offline from {}", plan.packages.len(), LOCKFILE_NAME));
+    } else {
+        println!("\n{}", "✨ Offline Install Complete!".green().bold());
+        for line in &plan.tree {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Plan an install of everything `lockfile` pins, trusting the lockfile in
+/// place of the chain. Every package the cache can't serve is named at once,
+/// before anything is extracted.
+async fn plan_offline(lockfile: &LockFile, packages_dir: &Path, cache: &PackageCache) -> Result<ResolutionPlan> {
+    let plan = resolver::plan_locked(lockfile, lockfile, packages_dir).await?;
+    let now = chrono::Utc::now().timestamp();
+    let mut missing = Vec::new();
+    for package in &plan.packages {
+        if cache.fetch(&package.ipfs_cid, package.sha256.as_deref(), now)?.is_none() {
+            missing.push(format!("  {}@{} (CID {})", package.name, package.version, package.ipfs_cid));
+        }
+    }
+    if !missing.is_empty() {
+        return Err(AntSolError::NotFound(format!(
+            "{} locked package(s) are not in the download cache at {}:\n{}\nRun 'antsol fetch' where the network is reachable, then copy the cache over",
+            missing.len(),
+            cache.root().display(),
+            missing.join("\n")
+        )).into());
+    }
+    Ok(plan)
+}
+
+/// `antsol.lock` in the current directory, which locked and offline installs require
+pub(crate) fn require_lockfile() -> Result<LockFile> {
+    LockFile::load(Path::new(LOCKFILE_NAME))?
+        .ok_or_else(|| AntSolError::Config(format!(
+            "No {} found in the current directory. Run 'antsol install <package>' first.",
+            LOCKFILE_NAME
+        )).into())
+}
+
+/// How long an install waits at exit for outstanding download reports
+const DOWNLOAD_REPORT_GRACE: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Background `record_download` calls for the packages just installed
+struct DownloadReports(Vec<tokio::task::JoinHandle<()>>);
+
+impl DownloadReports {
+    /// Start one report per package; nothing is sent when `enabled` is false
+    fn spawn(indexer_url: &str, enabled: bool, packages: impl Iterator<Item = (String, String)>) -> Self {
+        if !enabled {
+            return Self(Vec::new());
+        }
+        let handles = packages
+            .map(|(name, version)| {
+                let indexer = IndexerClient::new(indexer_url);
+                tokio::spawn(async move {
+                    // Best-effort: a missed count is not worth a warning
+                    let _ = indexer.record_download(&name, &version).await;
+                })
+            })
+            .collect();
+        Self(handles)
+    }
+    
+    /// Give reports still in flight a moment to land before the process exits
+    async fn finish(self) {
+        let _ = tokio::time::timeout(DOWNLOAD_REPORT_GRACE, async {
+            for handle in self.0 {
+                let _ = handle.await;
+            }
+        }).await;
+    }
+}
+
+/// Count `packages` on-chain with one `record_download` transaction each,
+/// paid by the configured wallet. Returns the packages that weren't
+/// recorded, for the indexer's HTTP counter; failures are only warnings.
+async fn record_downloads_on_chain(config: &Config, solana_client: &AntSolClient, packages: Vec<(String, String)>) -> Vec<(String, String)> {
+    let signer = match load_signer(config) {
+        Ok(signer) => signer,
+        Err(e) => {
+            print_warning(&format!("Not recording downloads on-chain: {}", e));
+            return packages;
+        }
+    };
+    let mut unrecorded = Vec::new();
+    for (name, version) in packages {
+        match solana_client.record_download(signer.as_ref(), &name, &version).await {
+            Ok(signature) => tracing::debug!("Recorded the download of {}@{} on-chain: {}", name, version, signature),
+            Err(e) => {
+                print_warning(&format!("Could not record the download of {}@{} on-chain: {}", name, version, e));
+                unrecorded.push((name, version));
+            }
+        }
+    }
+    unrecorded
+}
+
+/// Carry out a resolved plan: download and extract every planned package.
+/// Local registry packages are copied from `registry` instead.
+/// Returns each package's archive, in plan order.
+async fn execute_plan(plan: &ResolutionPlan, ipfs_client: &IpfsClient, registry: Option<&LocalRegistry>) -> Result<Vec<FetchedArchive>> {
+    let mut fetched = Vec::with_capacity(plan.packages.len());
+    for package in &plan.packages {
+        let id = format!("{}@{}", package.name, package.version);
+        if let (true, Some(registry)) = (package.local, registry) {
+            let package_dir = PathBuf::from(&package.install_dir);
+            let sha256 = install_local(registry, &package.name, &package.version, &package_dir, ipfs_client.extract_limits())?;
+            fetched.push(FetchedArchive { sha256, from_cache: false });
+            print_success(&format!("Installed {} from the local registry", id.green()));
+            continue;
+        }
+        let progress = create_progress_bar(0, &format!("⬇️  {}", id));
+        let package_dir = PathBuf::from(&package.install_dir);
+        std::fs::create_dir_all(&package_dir)?;
+        let archive = ipfs_client
+            .download_package_with_progress(&package.ipfs_cid, &package_dir, package.sha256.as_deref(), &progress)
+            .await;
+        progress.finish_and_clear();
+        let archive = archive?;
+        if archive.from_cache {
+            print_success(&format!("Installed {} from the download cache (sha256 verified)", id.green()));
+        } else {
+            print_success(&format!("Installed {} with cryptographic verification", id.green()));
+        }
+        fetched.push(archive);
+    }
+    Ok(fetched)
+}
+
+/// Extract a local registry archive into `package_dir`, returning its sha256
+fn install_local(registry: &LocalRegistry, name: &str, version: &str, package_dir: &Path, limits: &ExtractLimits) -> Result<String> {
+    let (bytes, sha256) = registry.read_archive(name, version)?;
+    std::fs::create_dir_all(package_dir)?;
+    let temp_file = package_dir.join("package.tar.gz");
+    std::fs::write(&temp_file, bytes)?;
+    extract_archive(&temp_file, package_dir, limits)?;
+    std::fs::remove_file(temp_file)?;
+    Ok(sha256)
+}
+
+/// Ask the indexer for every known version of `name` and pick the highest one
+pub(crate) async fn resolve_latest_version(indexer_url: &str, name: &str) -> Result<String> {
+    let spinner = create_spinner(&format!("🔎 Resolving latest version of {} via indexer...", name));
+    let versions = indexed_versions(indexer_url, name).await;
+    spinner.finish_and_clear();
+    
+    let latest = latest_version(versions?.iter().map(String::as_str))
+        .ok_or_else(|| AntSolError::NotFound(format!(
+            "No published versions of {} found. Pass an explicit version: antsol install {}@<version>",
+            name, name
+        )))?;
+    Ok(latest)
+}
+
+/// Ask the indexer for every known version of `name` and pick the highest one `spec` accepts
+pub(crate) async fn resolve_range(indexer_url: &str, name: &str, requirement: &str, spec: &VersionSpec) -> Result<String> {
+    let spinner = create_spinner(&format!("🔎 Resolving {}@{} via indexer...", name, requirement));
+    let versions = indexed_versions(indexer_url, name).await;
+    spinner.finish_and_clear();
+    let versions = versions?;
+    
+    spec.select(versions.iter().map(String::as_str))
+        .ok_or_else(|| AntSolError::NotFound(format!(
+            "No published version of {} matches {} (known: {})",
+            name,
+            requirement,
+            if versions.is_empty() { "none".to_string() } else { versions.join(", ") }
+        )).into())
+}
+
+/// Every version of `name` the indexer knows about
+async fn indexed_versions(indexer_url: &str, name: &str) -> Result<Vec<String>> {
+    let indexer = IndexerClient::new(indexer_url);
+    match indexer.get_package(name).await {
+        Ok(Some(details)) => Ok(details.versions.into_iter().map(|v| v.version).collect()),
+        Ok(None) => Err(AntSolError::NotFound(format!("Package {} not found in the indexer", name)).into()),
+        Err(e) => Err(AntSolError::Network(format!(
+            "Could not look up versions of {} ({}). Pass an explicit version: antsol install {}@<version>",
+            name, e, name
+        )).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::sample_package;
+    use crate::utils::sha256_hex;
+
+    const FIXTURE: &[u8] = include_bytes!("../../tests/fixtures/hello-pkg.tar.gz");
+    const FIXTURE_CID: &str = "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu";
+
+    fn locked(name: &str, ipfs_cid: &str, deps: &[&str]) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            ipfs_cid: ipfs_cid.to_string(),
+            sha256: sha256_hex(FIXTURE),
+            files_sha256: None,
+            authority: None,
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            requirement: None,
+            from_cache: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn offline_install_uses_only_the_seeded_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        let seed = dir.path().join("seed.tar.gz");
+        std::fs::write(&seed, FIXTURE).unwrap();
+        cache.store(FIXTURE_CID, &seed, &sha256_hex(FIXTURE), 1).unwrap();
+        let packages_dir = dir.path().join("antsol_packages");
+
+        let mut lockfile = LockFile::new();
+        lockfile.upsert(locked("hello", FIXTURE_CID, &[]));
+        let plan = plan_offline(&lockfile, &packages_dir, &cache).await.unwrap();
+        let client = IpfsClient::new(String::new()).with_cache(cache).with_offline(true);
+        let fetched = execute_plan(&plan, &client, None).await.unwrap();
+        assert!(fetched[0].from_cache);
+        assert!(packages_dir.join("hello/antsol.toml").is_file());
+    }
+
+    #[tokio::test]
+    async fn offline_install_names_every_missing_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        let seed = dir.path().join("seed.tar.gz");
+        std::fs::write(&seed, FIXTURE).unwrap();
+        cache.store(FIXTURE_CID, &seed, &sha256_hex(FIXTURE), 1).unwrap();
+
+        let missing_cid = format!("Qm{}", "m".repeat(44));
+        let mut lockfile = LockFile::new();
+        lockfile.upsert(locked("app", &missing_cid, &["hello@1.0.0"]));
+        lockfile.upsert(locked("hello", FIXTURE_CID, &[]));
+        let err = plan_offline(&lockfile, dir.path(), &cache).await.unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::NotFound);
+        let message = err.to_string();
+        assert!(message.contains(&format!("app@1.0.0 (CID {})", missing_cid)), "{}", message);
+        assert!(!message.contains("hello@1.0.0"), "{}", message);
+        assert!(message.contains("antsol fetch"), "{}", message);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let account = sample_package("app", "1.0.0", &[]);
+        let package = PlannedPackage {
+            name: "app".to_string(),
+            version: "1.0.0".to_string(),
+            ipfs_cid: "QmApp".to_string(),
+            reason: VersionReason::Requested,
+            size: None,
+            install_dir: "antsol_packages/app".to_string(),
+            sha256: None,
+            local: false,
+            account,
+        };
+        let archive = FetchedArchive { sha256: "ab".repeat(32), from_cache: true };
+        let output = install_output(Some("app@1.0.0".to_string()), &[package], &[archive], true);
+        assert_eq!(serde_json::to_value(output).unwrap(), serde_json::json!({
+            "requested": "app@1.0.0",
+            "packages": [{
+                "name": "app",
+                "version": "1.0.0",
+                "ipfs_cid": "QmApp",
+                "sha256": "ab".repeat(32),
+                "path": "antsol_packages/app",
+                "local": false,
+                "from_cache": true
+            }],
+            "lockfile_updated": true
+        }));
+    }
+}
@@ -0,0 +1,396 @@
+use crate::config::Config;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::solana_client::AntSolClient;
+use crate::types::{AntSolManifest, LockFile, PackageAccount, Result};
+use crate::utils::*;
+use crate::workspace;
+use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+
+/// How an installed package compares with its on-chain record
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ChainStatus {
+    /// On-chain CID equals the locked CID
+    Match,
+    /// The on-chain record now points at different content
+    Mismatch { on_chain_cid: String },
+    /// No on-chain record for this name@version
+    NotFound,
+    /// Could not be compared (no version or CID known locally, or RPC failed)
+    Unknown { reason: String },
+}
+
+/// One package present in `antsol_packages/` or `antsol.lock`
+#[derive(Debug, Clone, Serialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub ipfs_cid: Option<String>,
+    /// Bytes on disk under `antsol_packages/<name>`
+    pub size: u64,
+    /// Whether the package directory exists
+    pub present: bool,
+    /// Recorded in `antsol.lock` (otherwise found only by scanning the directory)
+    pub locked: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_chain: Option<ChainStatus>,
+}
+
+/// How a workspace member's manifest version compares with the chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberStatus {
+    /// No version of the package is on-chain
+    Unpublished,
+    /// The manifest version is the latest on-chain
+    Published,
+    /// The manifest version is newer than any on-chain; ready to publish
+    Ahead,
+    /// A newer version than the manifest's is already on-chain
+    Behind,
+}
+
+/// One member of the workspace, as `antsol list --workspace` shows it
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceMemberStatus {
+    pub name: String,
+    pub path: String,
+    pub manifest_version: String,
+    pub latest_on_chain: Option<String>,
+    pub status: MemberStatus,
+}
+
+/// List installed packages, optionally checking each against the chain
+pub async fn handle_list(json: bool, verify: bool, workspace: bool) -> Result<()> {
+    if workspace {
+        return list_workspace(json);
+    }
+    let config = Config::load()?;
+    let lockfile = LockFile::load(Path::new(LOCKFILE_NAME))?;
+    let mut packages = collect_installed(lockfile.as_ref(), &config.install_dir)?;
+
+    if verify && !packages.is_empty() {
+        let solana_client = AntSolClient::new(&config)?;
+        let spinner = (!json).then(|| create_spinner("🔍 Checking installed packages against the blockchain..."));
+        for package in &mut packages {
+            package.on_chain = Some(check_on_chain(&solana_client, package));
+        }
+        if let Some(spinner) = spinner {
+            spinner.finish_and_clear();
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packages)?);
+        return Ok(());
+    }
+
+    println!("\n{}", "📦 Installed Packages".cyan().bold());
+    if packages.is_empty() {
+        print_info("Nothing installed. Try: antsol install <package>");
+        return Ok(());
+    }
+    if lockfile.is_none() {
+        print_warning(&format!("No {} found; listing {}/ contents", LOCKFILE_NAME, config.install_dir.display()));
+    }
+
+    let mut out = PagedOutput::new();
+    writeln!(out, "{}", "─".repeat(80))?;
+    writeln!(
+        out,
+        "  {:<28} {:<12} {:<16} {:>10}  {}",
+        "NAME".bold(), "VERSION".bold(), "CID".bold(), "SIZE".bold(), "STATUS".bold()
+    )?;
+    for package in &packages {
+        let cid = package.ipfs_cid.as_deref().map(cid_prefix).unwrap_or_else(|| "-".to_string());
+        let size = if package.present { format_size(package.size) } else { "-".to_string() };
+        writeln!(
+            out,
+            "  {:<28} {:<12} {:<16} {:>10}  {}",
+            package.name.green(),
+            package.version.as_deref().unwrap_or("?"),
+            cid,
+            size,
+            status_label(package)
+        )?;
+    }
+    writeln!(out, "{}", "─".repeat(80))?;
+    writeln!(out, "  {} package(s)", packages.len())?;
+    out.finish()?;
+
+    Ok(())
+}
+
+/// Show each workspace member's manifest version against its latest on-chain version
+fn list_workspace(json: bool) -> Result<()> {
+    let config = Config::load()?;
+    let members = workspace::load(Path::new("."))?;
+    let solana_client = AntSolClient::new(&config)?;
+    let spinner = (!json).then(|| create_spinner("🔍 Looking up workspace members on the blockchain..."));
+    let rows = members.iter().map(|member| member_status(&solana_client, member)).collect::<Result<Vec<_>>>();
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+    let rows = rows?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+
+    println!("\n{}", "📦 Workspace Members".cyan().bold());
+    println!("{}", "─".repeat(80));
+    println!("  {:<28} {:<14} {:<14} {}", "NAME".bold(), "MANIFEST".bold(), "ON-CHAIN".bold(), "STATUS".bold());
+    for row in &rows {
+        let status = match row.status {
+            MemberStatus::Unpublished => "not published".yellow(),
+            MemberStatus::Published => "✓ published".green(),
+            MemberStatus::Ahead => "ready to publish".cyan(),
+            MemberStatus::Behind => "✗ behind the chain".red(),
+        };
+        println!(
+            "  {:<28} {:<14} {:<14} {}",
+            row.name.green(),
+            row.manifest_version,
+            row.latest_on_chain.as_deref().unwrap_or("-"),
+            status
+        );
+    }
+    println!("{}", "─".repeat(80));
+    println!("  {} member(s)", rows.len());
+    Ok(())
+}
+
+/// The highest on-chain version of `member`, from a scan of the program's
+/// accounts so it doesn't wait on the indexer catching up
+fn member_status(client: &AntSolClient, member: &workspace::Member) -> Result<WorkspaceMemberStatus> {
+    let latest_on_chain = client.package_versions(member.name())?.pop().map(|package| package.version);
+    Ok(WorkspaceMemberStatus {
+        name: member.name().to_string(),
+        path: member.dir.display().to_string(),
+        manifest_version: member.version().to_string(),
+        status: compare_with_chain(member.version(), latest_on_chain.as_deref()),
+        latest_on_chain,
+    })
+}
+
+fn compare_with_chain(manifest_version: &str, latest_on_chain: Option<&str>) -> MemberStatus {
+    match latest_on_chain.map(|latest| compare_versions(manifest_version, latest)) {
+        None => MemberStatus::Unpublished,
+        Some(std::cmp::Ordering::Equal) => MemberStatus::Published,
+        Some(std::cmp::Ordering::Greater) => MemberStatus::Ahead,
+        Some(std::cmp::Ordering::Less) => MemberStatus::Behind,
+    }
+}
+
+/// Gather installed packages from the lockfile, plus any directories it doesn't know about
+fn collect_installed(lockfile: Option<&LockFile>, packages_dir: &Path) -> Result<Vec<InstalledPackage>> {
+    let mut packages: Vec<InstalledPackage> = lockfile
+        .map(|lock| lock.packages.iter().map(|locked| {
+            let dir = packages_dir.join(&locked.name);
+            InstalledPackage {
+                name: locked.name.clone(),
+                version: Some(locked.version.clone()),
+                ipfs_cid: Some(locked.ipfs_cid.clone()),
+                size: dir_size(&dir),
+                present: dir.is_dir(),
+                locked: true,
+                on_chain: None,
+            }
+        }).collect())
+        .unwrap_or_default();
+
+    if packages_dir.is_dir() {
+        for entry in std::fs::read_dir(packages_dir)? {
+            let entry = entry?;
+            let dir = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !dir.is_dir() || packages.iter().any(|p| p.name == name) {
+                continue;
+            }
+            // Untracked directory: take the version from its manifest if it has one
+            let version = std::fs::read_to_string(dir.join("antsol.toml"))
+                .ok()
+                .and_then(|content| toml::from_str::<AntSolManifest>(&content).ok())
+                .map(|manifest| manifest.package.version);
+            packages.push(InstalledPackage {
+                name,
+                version,
+                ipfs_cid: None,
+                size: dir_size(&dir),
+                present: true,
+                locked: false,
+                on_chain: None,
+            });
+        }
+    }
+
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(packages)
+}
+
+fn check_on_chain(client: &AntSolClient, package: &InstalledPackage) -> ChainStatus {
+    let Some(version) = &package.version else {
+        return ChainStatus::Unknown { reason: "version unknown".to_string() };
+    };
+    match client.get_package(&package.name, version) {
+        Ok(account) => chain_status(package.ipfs_cid.as_deref(), account.as_ref()),
+        Err(e) => ChainStatus::Unknown { reason: e.to_string() },
+    }
+}
+
+fn chain_status(local_cid: Option<&str>, on_chain: Option<&PackageAccount>) -> ChainStatus {
+    match (local_cid, on_chain) {
+        (_, None) => ChainStatus::NotFound,
+        (None, Some(_)) => ChainStatus::Unknown { reason: "no CID recorded locally".to_string() },
+        (Some(cid), Some(account)) if account.ipfs_cid == cid => ChainStatus::Match,
+        (Some(_), Some(account)) => ChainStatus::Mismatch { on_chain_cid: account.ipfs_cid.clone() },
+    }
+}
+
+fn status_label(package: &InstalledPackage) -> ColoredString {
+    let label = match &package.on_chain {
+        Some(ChainStatus::Match) => return "✓ matches chain".green(),
+        Some(ChainStatus::Mismatch { on_chain_cid }) => {
+            return format!("✗ on-chain CID is now {}", cid_prefix(on_chain_cid)).red();
+        }
+        Some(ChainStatus::NotFound) => return "✗ not found on-chain".red(),
+        Some(ChainStatus::Unknown { reason }) => format!("? {}", reason),
+        None if !package.present => "missing directory".to_string(),
+        None if !package.locked => "not in lockfile".to_string(),
+        None => "installed".to_string(),
+    };
+    label.yellow()
+}
+
+/// First characters of a CID, enough to tell packages apart in a table
+fn cid_prefix(cid: &str) -> String {
+    match cid.char_indices().nth(12) {
+        Some((index, _)) => format!("{}…", &cid[..index]),
+        None => cid.to_string(),
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::{client_with, sample_package};
+    use crate::types::LockedPackage;
+
+    #[test]
+    fn collects_locked_missing_and_untracked_packages() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages_dir = dir.path().join("antsol_packages");
+        std::fs::create_dir_all(packages_dir.join("app")).unwrap();
+        std::fs::write(packages_dir.join("app/lib.rs"), "x".repeat(100)).unwrap();
+        std::fs::create_dir_all(packages_dir.join("stray")).unwrap();
+        std::fs::write(packages_dir.join("stray/antsol.toml"), "[package]\nname = \"stray\"\nversion = \"0.2.0\"\ndescription = \"\"\n").unwrap();
+
+        let mut lock = LockFile::new();
+        for name in ["app", "gone"] {
+            lock.upsert(LockedPackage {
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                ipfs_cid: format!("Qm{}", "a".repeat(44)),
+                sha256: "ab".repeat(32),
+                files_sha256: None,
+                authority: None,
+                dependencies: Vec::new(),
+                requirement: None,
+                from_cache: false,
+            });
+        }
+
+        let packages = collect_installed(Some(&lock), &packages_dir).unwrap();
+        let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "gone", "stray"]);
+        assert_eq!((packages[0].size, packages[0].present, packages[0].locked), (100, true, true));
+        assert_eq!((packages[1].size, packages[1].present), (0, false));
+        assert_eq!(packages[2].version.as_deref(), Some("0.2.0"));
+        assert!(!packages[2].locked);
+
+        let scanned = collect_installed(None, &packages_dir).unwrap();
+        assert_eq!(scanned.len(), 2);
+    }
+
+    #[test]
+    fn compares_locked_cid_with_chain() {
+        let account = sample_package("app", "1.0.0", &[]);
+        assert_eq!(chain_status(Some(&account.ipfs_cid), Some(&account)), ChainStatus::Match);
+        assert_eq!(chain_status(Some("QmOther"), Some(&account)), ChainStatus::Mismatch {
+            on_chain_cid: account.ipfs_cid.clone(),
+        });
+        assert_eq!(chain_status(Some("QmOther"), None), ChainStatus::NotFound);
+        assert!(matches!(chain_status(None, Some(&account)), ChainStatus::Unknown { .. }));
+        assert_eq!(cid_prefix(&account.ipfs_cid), "Qmaaaaaaaaaa…");
+    }
+
+    #[test]
+    fn compares_workspace_members_with_the_latest_on_chain_version() {
+        let client = client_with(&[sample_package("core", "1.2.0", &[]), sample_package("core", "1.10.0", &[])], None);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "[workspace]\nmembers = [\"core\", \"app\"]\n").unwrap();
+        for name in ["core", "app"] {
+            std::fs::create_dir_all(dir.path().join(name)).unwrap();
+            std::fs::write(
+                dir.path().join(name).join("antsol.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"1.10.0\"\ndescription = \"\"\n", name),
+            ).unwrap();
+        }
+        let members = workspace::load(dir.path()).unwrap();
+
+        let core = member_status(&client, &members[0]).unwrap();
+        assert_eq!((core.latest_on_chain.as_deref(), core.status), (Some("1.10.0"), MemberStatus::Published));
+        let app = member_status(&client, &members[1]).unwrap();
+        assert_eq!((app.latest_on_chain, app.status), (None, MemberStatus::Unpublished));
+
+        assert_eq!(compare_with_chain("1.11.0", Some("1.10.0")), MemberStatus::Ahead);
+        assert_eq!(compare_with_chain("1.9.0", Some("1.10.0")), MemberStatus::Behind);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let package = InstalledPackage {
+            name: "app".to_string(),
+            version: Some("1.0.0".to_string()),
+            ipfs_cid: Some("QmApp".to_string()),
+            size: 100,
+            present: true,
+            locked: true,
+            on_chain: Some(ChainStatus::Mismatch { on_chain_cid: "QmNew".to_string() }),
+        };
+        let untracked = InstalledPackage { on_chain: None, locked: false, ipfs_cid: None, ..package.clone() };
+        assert_eq!(serde_json::to_value([package, untracked]).unwrap(), serde_json::json!([
+            {
+                "name": "app",
+                "version": "1.0.0",
+                "ipfs_cid": "QmApp",
+                "size": 100,
+                "present": true,
+                "locked": true,
+                "on_chain": { "status": "mismatch", "on_chain_cid": "QmNew" }
+            },
+            {
+                "name": "app",
+                "version": "1.0.0",
+                "ipfs_cid": null,
+                "size": 100,
+                "present": true,
+                "locked": false
+            }
+        ]));
+    }
+}
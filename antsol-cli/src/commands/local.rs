@@ -0,0 +1,81 @@
+use crate::error::AntSolError;
+use crate::ipfs::create_archive;
+use crate::local_registry::LocalRegistry;
+use crate::types::{AntSolManifest, Result};
+use crate::utils::*;
+use colored::*;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Copy a package into the local development registry
+pub async fn handle_publish(path: PathBuf, version_override: Option<String>) -> Result<()> {
+    let manifest_path = path.join("antsol.toml");
+    if !manifest_path.exists() {
+        return Err(AntSolError::ManifestNotFound(path).into());
+    }
+
+    let manifest_content = std::fs::read_to_string(&manifest_path)?;
+    let mut manifest: AntSolManifest = toml::from_str(&manifest_content)?;
+
+    if let Some(version) = version_override {
+        if !validate_version(&version) {
+            return Err(AntSolError::Usage("Invalid version format".into()).into());
+        }
+        manifest.package.version = version;
+    }
+
+    print_info(&format!("Publishing {} v{} to the local registry", manifest.package.name.cyan(), manifest.package.version.cyan()));
+
+    let registry = LocalRegistry::open_default()?;
+    let archive_path = create_archive(&path)?;
+    let published = registry.publish(&manifest, &archive_path, chrono::Utc::now().timestamp());
+    std::fs::remove_file(&archive_path)?;
+    let (package, replaced) = published?;
+
+    if replaced {
+        print_warning(&format!("Replaced existing local copy of {}", package.id()));
+    }
+    print_success(&format!("Published {} locally", package.id().green().bold()));
+
+    println!("\n{}", "Local Package Details".cyan().bold());
+    println!("  Location: {}", registry.archive_path(&package.name, &package.version).display().to_string().yellow());
+    println!("  sha256: {}", package.sha256.cyan());
+    print_tips("💡 Install it with:".yellow(), [format!("antsol install {} --local", package.id()).cyan()]);
+
+    Ok(())
+}
+
+/// List everything in the local registry
+pub async fn handle_list(json: bool) -> Result<()> {
+    let registry = LocalRegistry::open_default()?;
+    let packages = registry.list()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packages)?);
+        return Ok(());
+    }
+
+    println!("\n{} {}", "🏠 Local Registry".cyan().bold(), registry.root().display().to_string().dimmed());
+    if packages.is_empty() {
+        print_info("No local packages. Publish one with: antsol local publish");
+        return Ok(());
+    }
+    let mut out = PagedOutput::new();
+    for package in &packages {
+        let published = chrono::DateTime::<chrono::Utc>::from_timestamp(package.published_at, 0)
+            .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        writeln!(out, "  {:<32} {:<12} {}", package.name.green(), package.version.cyan(), published.dimmed())?;
+    }
+    out.finish()
+}
+
+/// Remove `name` (every version) or `name@version` from the local registry
+pub async fn handle_remove(package_spec: String) -> Result<()> {
+    let (name, version) = parse_package_spec(&package_spec);
+    let registry = LocalRegistry::open_default()?;
+    for id in registry.remove(&name, version.as_deref())? {
+        print_success(&format!("Removed {} from the local registry", id.green()));
+    }
+    Ok(())
+}
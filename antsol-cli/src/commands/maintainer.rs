@@ -0,0 +1,131 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::signer::load_signer;
+use crate::solana_client::AntSolClient;
+use crate::tx::SendOptions;
+use crate::types::{PackageAccount, Result};
+use crate::utils::*;
+use crate::validation::MAX_MAINTAINERS;
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+/// Add (`add: true`) or remove a maintainer on the configured wallet's list
+/// for `package`. The transaction is signed as the authority of
+/// `package@version`, or without a version of the newest version the wallet
+/// owns.
+pub async fn handle_change(package: String, wallet: String, add: bool, send: SendOptions) -> Result<()> {
+    let maintainer = Pubkey::from_str(&wallet)
+        .map_err(|_| AntSolError::Usage(format!("Invalid wallet address: {}", wallet)))?;
+    let (name, version) = parse_package_spec(&package);
+
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    let signer = load_signer(&config)?;
+    let solana_client = AntSolClient::new(&config)?;
+
+    let owned = match version {
+        Some(version) => solana_client.require_package(&name, &version)?,
+        None => owned_version(solana_client.package_versions(&name)?, &signer.pubkey()).ok_or_else(|| {
+            AntSolError::Wallet(format!("Wallet {} is not the authority of any version of {}", signer.pubkey(), name))
+        })?,
+    };
+    if owned.authority != signer.pubkey() {
+        return Err(AntSolError::Wallet(format!(
+            "{}@{} is owned by {}, not the configured wallet {}",
+            owned.name, owned.version, owned.authority, signer.pubkey()
+        )).into());
+    }
+
+    let maintainers = solana_client.get_maintainers(&name, &signer.pubkey())?;
+    check_change(&maintainers, &maintainer, add)?;
+
+    let action = if add { "Adding" } else { "Removing" };
+    let spinner = create_spinner(&format!("{} maintainer of {}...", action, name));
+    let signature = solana_client.change_maintainer(signer.as_ref(), &name, &owned.version, maintainer, add).await;
+    spinner.finish_and_clear();
+    let signature = signature?;
+
+    if add {
+        print_success(&format!("{} can now publish new versions of {}", maintainer.to_string().cyan(), name.green().bold()));
+    } else {
+        print_success(&format!("{} is no longer a maintainer of {}", maintainer.to_string().cyan(), name.green().bold()));
+    }
+    println!("  Transaction: {}", signature.cyan());
+    Ok(())
+}
+
+/// Show the maintainers listed by the authority of `package@version`, or
+/// without a version of the newest version
+pub async fn handle_list(package: String) -> Result<()> {
+    let (name, version) = parse_package_spec(&package);
+    let config = Config::load()?;
+    let solana_client = AntSolClient::new(&config)?;
+    let listed_for = match version {
+        Some(version) => solana_client.require_package(&name, &version)?,
+        // package_versions sorts oldest first
+        None => solana_client.package_versions(&name)?.pop().ok_or_else(|| {
+            AntSolError::PackageNotFound { name: name.clone(), version: None }
+        })?,
+    };
+    let maintainers = solana_client.get_maintainers(&name, &listed_for.authority)?;
+
+    println!("\n{}", format!("👥 Maintainers of {}@{}", name, listed_for.version).cyan().bold());
+    println!("  Listed by the authority {}", listed_for.authority.to_string().dimmed());
+    if maintainers.is_empty() {
+        println!("  None; only the package authority can publish new versions");
+    }
+    for maintainer in &maintainers {
+        println!("  • {}", maintainer.to_string().green());
+    }
+    Ok(())
+}
+
+/// The newest version of a package whose authority is `wallet`
+fn owned_version(versions: Vec<PackageAccount>, wallet: &Pubkey) -> Option<PackageAccount> {
+    // package_versions sorts oldest first
+    versions.into_iter().rev().find(|package| package.authority == *wallet)
+}
+
+/// Refuse changes the program would reject, before signing anything
+fn check_change(maintainers: &[Pubkey], maintainer: &Pubkey, add: bool) -> Result<()> {
+    let listed = maintainers.contains(maintainer);
+    if add && listed {
+        return Err(AntSolError::Usage(format!("{} is already a maintainer", maintainer)).into());
+    }
+    if add && maintainers.len() >= MAX_MAINTAINERS {
+        return Err(AntSolError::Usage(format!("Packages can have at most {} maintainers", MAX_MAINTAINERS)).into());
+    }
+    if !add && !listed {
+        return Err(AntSolError::Usage(format!("{} is not a maintainer", maintainer)).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::sample_package;
+
+    #[test]
+    fn picks_the_newest_version_the_wallet_owns() {
+        let wallet = Pubkey::new_unique();
+        let mut versions: Vec<PackageAccount> = ["1.0.0", "1.1.0", "2.0.0"].iter().map(|v| sample_package("foo", v, &[])).collect();
+        versions[0].authority = wallet;
+        versions[1].authority = wallet;
+        assert_eq!(owned_version(versions.clone(), &wallet).unwrap().version, "1.1.0");
+        assert!(owned_version(versions, &Pubkey::new_unique()).is_none());
+    }
+
+    #[test]
+    fn rejects_changes_the_program_would_refuse() {
+        let listed = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        assert!(check_change(&[listed], &other, true).is_ok());
+        assert!(check_change(&[listed], &listed, false).is_ok());
+        assert!(check_change(&[listed], &listed, true).is_err());
+        assert!(check_change(&[listed], &other, false).is_err());
+        let full: Vec<Pubkey> = (0..MAX_MAINTAINERS).map(|_| Pubkey::new_unique()).collect();
+        assert!(check_change(&full, &other, true).is_err());
+    }
+}
@@ -1,8 +1,29 @@
 pub mod init;
 pub mod publish;
+pub mod check;
 pub mod install;
+pub mod fetch;
 pub mod search;
 pub mod info;
 pub mod wallet;
+pub mod namespace;
+pub mod maintainer;
+pub mod freeze;
 pub mod update;
+pub mod update_deps;
 pub mod setup;
+pub mod errors;
+pub mod gateways;
+pub mod local;
+pub mod cache;
+pub mod uninstall;
+pub mod list;
+pub mod outdated;
+pub mod watch;
+pub mod verify;
+pub mod tree;
+pub mod config;
+pub mod profile;
+pub mod debug;
+pub mod doctor;
+pub mod completions;
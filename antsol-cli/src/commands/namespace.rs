@@ -0,0 +1,132 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::signer::load_signer;
+use crate::solana_client::AntSolClient;
+use crate::tx::SendOptions;
+use crate::types::Result;
+use crate::utils::*;
+use crate::validation::{is_valid_scope, MAX_NAMESPACE_PUBLISHERS, MAX_SCOPE_LENGTH};
+use chrono::{DateTime, Utc};
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use std::str::FromStr;
+
+pub async fn handle_register(scope: String, send: SendOptions) -> Result<()> {
+    check_scope(&scope)?;
+    let (solana_client, signer) = signing_client(&send)?;
+    if let Some(namespace) = solana_client.get_namespace(&scope)? {
+        return Err(AntSolError::Usage(format!("Namespace '{}' is already owned by {}", scope, namespace.owner)).into());
+    }
+
+    let spinner = create_spinner(&format!("Registering namespace {}...", scope));
+    let signature = solana_client.register_namespace(signer.as_ref(), &scope).await;
+    spinner.finish_and_clear();
+    let signature = signature?;
+
+    print_success(&format!("Registered namespace {} to {}", scope.green().bold(), signer.pubkey().to_string().cyan()));
+    println!("  Packages named {}/<name> can now only be published by this wallet", scope);
+    println!("  Transaction: {}", signature.cyan());
+    Ok(())
+}
+
+/// Replace the namespace's publisher list; no wallets clears it
+pub async fn handle_publishers(scope: String, wallets: Vec<String>, send: SendOptions) -> Result<()> {
+    check_scope(&scope)?;
+    let publishers = parse_publishers(&wallets)?;
+    let (solana_client, signer) = signing_client(&send)?;
+    let namespace = solana_client.get_namespace(&scope)?
+        .ok_or_else(|| AntSolError::NotFound(format!("Namespace '{}' is not registered", scope)))?;
+    if namespace.owner != signer.pubkey() {
+        return Err(AntSolError::Wallet(format!(
+            "Namespace '{}' is owned by {}, not the configured wallet {}",
+            scope, namespace.owner, signer.pubkey()
+        )).into());
+    }
+
+    let spinner = create_spinner(&format!("Updating publishers of {}...", scope));
+    let signature = solana_client.set_namespace_publishers(signer.as_ref(), &scope, publishers.clone()).await;
+    spinner.finish_and_clear();
+    let signature = signature?;
+
+    print_success(&format!("{} now has {} publisher(s) besides its owner", scope.green().bold(), publishers.len()));
+    for publisher in &publishers {
+        println!("  • {}", publisher.to_string().cyan());
+    }
+    println!("  Transaction: {}", signature.cyan());
+    Ok(())
+}
+
+pub async fn handle_show(scope: String) -> Result<()> {
+    check_scope(&scope)?;
+    let config = Config::load()?;
+    let solana_client = AntSolClient::new(&config)?;
+    let namespace = solana_client.get_namespace(&scope)?
+        .ok_or_else(|| AntSolError::NotFound(format!("Namespace '{}' is not registered", scope)))?;
+
+    println!("\n{}", format!("🏷️  {}", namespace.scope).cyan().bold());
+    println!("  Owner: {}", namespace.owner.to_string().green());
+    let registered = DateTime::<Utc>::from_timestamp(namespace.created_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("  Registered: {}", registered);
+    if namespace.publishers.is_empty() {
+        println!("  Publishers: owner only");
+    } else {
+        println!("  Publishers:");
+        for publisher in &namespace.publishers {
+            println!("    • {}", publisher.to_string().cyan());
+        }
+    }
+    Ok(())
+}
+
+fn check_scope(scope: &str) -> Result<()> {
+    if !is_valid_scope(scope) {
+        return Err(AntSolError::Usage(format!(
+            "Invalid scope '{}'. Use 1-{} lowercase letters, numbers, and hyphens.",
+            scope, MAX_SCOPE_LENGTH
+        )).into());
+    }
+    Ok(())
+}
+
+fn parse_publishers(wallets: &[String]) -> Result<Vec<Pubkey>> {
+    if wallets.len() > MAX_NAMESPACE_PUBLISHERS {
+        return Err(AntSolError::Usage(format!(
+            "{} publishers given (max {})", wallets.len(), MAX_NAMESPACE_PUBLISHERS
+        )).into());
+    }
+    let publishers = wallets
+        .iter()
+        .map(|wallet| Pubkey::from_str(wallet).map_err(|_| AntSolError::Usage(format!("Invalid wallet address: {}", wallet)).into()))
+        .collect::<Result<_>>()?;
+    Ok(publishers)
+}
+
+fn signing_client(send: &SendOptions) -> Result<(AntSolClient, Box<dyn Signer>)> {
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    let signer = load_signer(&config)?;
+    Ok((AntSolClient::new(&config)?, signer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+
+    #[test]
+    fn scopes_and_publishers_are_checked_before_sending() {
+        assert!(check_scope("alice").is_ok());
+        for scope in ["Alice", "alice/x", "-alice", ""] {
+            assert_eq!(error_kind(check_scope(scope).unwrap_err().as_ref()), ErrorKind::Usage, "{}", scope);
+        }
+
+        let wallet = Pubkey::new_unique();
+        assert_eq!(parse_publishers(&[wallet.to_string()]).unwrap(), vec![wallet]);
+        assert!(parse_publishers(&[]).unwrap().is_empty());
+        assert!(parse_publishers(&["not-a-key".to_string()]).is_err());
+        assert!(parse_publishers(&vec![wallet.to_string(); 11]).is_err());
+    }
+}
@@ -0,0 +1,245 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::indexer_client::IndexerClient;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::resolver::VersionSpec;
+use crate::types::{AntSolManifest, LockFile, Result};
+use crate::utils::*;
+use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// How far behind the latest release a package is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delta {
+    UpToDate,
+    Patch,
+    Minor,
+    Major,
+    /// Not indexed, or no version to compare
+    Unknown,
+}
+
+impl Delta {
+    fn is_outdated(self) -> bool {
+        matches!(self, Delta::Patch | Delta::Minor | Delta::Major)
+    }
+}
+
+/// One row of `antsol outdated`
+#[derive(Debug, Clone, Serialize)]
+pub struct OutdatedPackage {
+    pub name: String,
+    /// Installed version, or with `--manifest` the requirement in antsol.toml
+    pub current: String,
+    /// Highest version the recorded requirement accepts, for ranges
+    pub wanted: Option<String>,
+    /// Highest release the indexer knows; `None` when the package isn't indexed
+    pub latest: Option<String>,
+    pub delta: Delta,
+}
+
+/// Compare installed packages (or with `manifest` the dependencies declared
+/// in antsol.toml) with the newest versions known to the indexer. Fails with
+/// exit code 1 when anything is outdated.
+pub async fn handle_outdated(json: bool, manifest: bool) -> Result<()> {
+    let checked = checked_packages(manifest)?;
+    let config = Config::load()?;
+    let indexer = IndexerClient::new(&config.indexer_url);
+
+    let spinner = (!json).then(|| create_spinner("🔎 Looking up latest versions via indexer..."));
+    let mut packages = Vec::new();
+    for (name, current, requirement) in checked {
+        let versions: Option<Vec<String>> = match indexer.get_package(&name).await {
+            Ok(details) => details.map(|d| d.versions.into_iter().map(|v| v.version).collect()),
+            Err(e) => {
+                if let Some(spinner) = &spinner {
+                    spinner.finish_and_clear();
+                }
+                return Err(e);
+            }
+        };
+        packages.push(compare(name, current, requirement.as_deref(), versions.as_deref()));
+    }
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&packages)?);
+    } else {
+        print_outdated(&packages, manifest)?;
+    }
+
+    let outdated = packages.iter().filter(|p| p.delta.is_outdated()).count();
+    if outdated > 0 {
+        return Err(format!("{} of {} package(s) are outdated", outdated, packages.len()).into());
+    }
+    if !json {
+        print_success(&format!("All {} package(s) are up to date", packages.len()));
+    }
+    Ok(())
+}
+
+/// `(name, current, requirement)` for every package to check: lockfile
+/// entries, or the manifest's dependencies with `manifest` or when there is
+/// no lockfile
+fn checked_packages(manifest: bool) -> Result<Vec<(String, String, Option<String>)>> {
+    if !manifest {
+        if let Some(lockfile) = LockFile::load(Path::new(LOCKFILE_NAME))? {
+            return Ok(lockfile
+                .packages
+                .into_iter()
+                .map(|locked| (locked.name, locked.version, locked.requirement))
+                .collect());
+        }
+    }
+
+    let manifest_path = Path::new("antsol.toml");
+    if !manifest_path.exists() {
+        return Err(AntSolError::ManifestNotFound(PathBuf::from(".")).into());
+    }
+    let manifest: AntSolManifest = toml::from_str(&std::fs::read_to_string(manifest_path)?)?;
+    Ok(manifest
+        .dependencies
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dep| (dep.name, dep.version.clone(), Some(dep.version)))
+        .collect())
+}
+
+/// Compare `current` (a version, or a requirement from the manifest) with
+/// the indexed `versions`; `None` when the package isn't indexed
+fn compare(name: String, current: String, requirement: Option<&str>, versions: Option<&[String]>) -> OutdatedPackage {
+    let versions = versions.unwrap_or_default();
+    let latest = latest_version(versions.iter().map(String::as_str));
+    let spec = requirement.and_then(|r| VersionSpec::parse(r).ok());
+    let wanted = match &spec {
+        Some(spec @ VersionSpec::Range(_)) => spec.select(versions.iter().map(String::as_str)),
+        _ => None,
+    };
+    // A declared range is as current as the best version it accepts
+    let installed = match &spec {
+        Some(VersionSpec::Range(_)) if semver::Version::parse(&current).is_err() => wanted.clone(),
+        _ => Some(current.clone()),
+    };
+    let delta = match (installed, &latest) {
+        (Some(installed), Some(latest)) => classify(&installed, latest),
+        _ => Delta::Unknown,
+    };
+    OutdatedPackage { name, current, wanted, latest, delta }
+}
+
+/// Size of the step from `current` up to `latest`
+fn classify(current: &str, latest: &str) -> Delta {
+    let (Ok(from), Ok(to)) = (semver::Version::parse(current), semver::Version::parse(latest)) else {
+        return Delta::Unknown;
+    };
+    if compare_versions(latest, current) != std::cmp::Ordering::Greater {
+        Delta::UpToDate
+    } else if to.major != from.major {
+        Delta::Major
+    } else if to.minor != from.minor {
+        Delta::Minor
+    } else {
+        Delta::Patch
+    }
+}
+
+fn print_outdated(packages: &[OutdatedPackage], manifest: bool) -> Result<()> {
+    let source = if manifest { "antsol.toml" } else { LOCKFILE_NAME };
+    println!("\n{}", format!("📅 Outdated Packages ({})", source).cyan().bold());
+    if packages.is_empty() {
+        print_info("No dependencies to check");
+        return Ok(());
+    }
+
+    let mut out = PagedOutput::new();
+    writeln!(out, "{}", "─".repeat(80))?;
+    writeln!(
+        out,
+        "  {:<28} {:<14} {:<14} {:<14} {}",
+        "NAME".bold(), "CURRENT".bold(), "WANTED".bold(), "LATEST".bold(), "DELTA".bold()
+    )?;
+    for package in packages {
+        let latest = package.latest.as_deref().unwrap_or("-");
+        let (latest, delta) = match package.delta {
+            Delta::UpToDate => (latest.dimmed(), "up to date".dimmed()),
+            Delta::Patch => (latest.green(), "patch".green()),
+            Delta::Minor => (latest.yellow(), "minor".yellow()),
+            Delta::Major => (latest.red().bold(), "major".red().bold()),
+            Delta::Unknown if package.latest.is_none() => (latest.normal(), "not indexed".dimmed()),
+            Delta::Unknown => (latest.normal(), "unknown".dimmed()),
+        };
+        writeln!(
+            out,
+            "  {:<28} {:<14} {:<14} {:<14} {}",
+            package.name,
+            package.current,
+            package.wanted.as_deref().unwrap_or("-"),
+            latest,
+            delta
+        )?;
+    }
+    writeln!(out, "{}", "─".repeat(80))?;
+    out.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn classify_by_semver_step() {
+        assert_eq!(classify("1.2.3", "1.2.3"), Delta::UpToDate);
+        assert_eq!(classify("1.2.3", "1.2.4"), Delta::Patch);
+        assert_eq!(classify("1.2.3", "1.3.0"), Delta::Minor);
+        assert_eq!(classify("1.2.3", "2.0.0"), Delta::Major);
+        assert_eq!(classify("1.0.0-rc.1", "1.0.0"), Delta::Patch);
+        // Ahead of the indexer, e.g. right after publishing
+        assert_eq!(classify("1.3.0", "1.2.9"), Delta::UpToDate);
+        assert_eq!(classify("latest", "1.0.0"), Delta::Unknown);
+    }
+
+    #[test]
+    fn compare_locked_and_declared_versions() {
+        let known = versions(&["1.0.0", "1.1.0", "1.2.0-beta.1", "2.0.0"]);
+
+        let locked = compare("dep".into(), "1.0.0".into(), Some("^1.0"), Some(&known));
+        assert_eq!(locked.wanted.as_deref(), Some("1.1.0"));
+        assert_eq!(locked.latest.as_deref(), Some("2.0.0"));
+        assert_eq!(locked.delta, Delta::Major);
+
+        // --manifest: a range is judged by the best version it accepts
+        let declared = compare("dep".into(), "^2".into(), Some("^2"), Some(&known));
+        assert_eq!(declared.wanted.as_deref(), Some("2.0.0"));
+        assert_eq!(declared.delta, Delta::UpToDate);
+
+        let pinned = compare("dep".into(), "1.1.0".into(), Some("1.1.0"), Some(&known));
+        assert_eq!(pinned.wanted, None);
+        assert_eq!(pinned.delta, Delta::Major);
+
+        let missing = compare("ghost".into(), "1.0.0".into(), None, None);
+        assert_eq!(missing.latest, None);
+        assert_eq!(missing.delta, Delta::Unknown);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let package = compare("dep".into(), "1.0.0".into(), None, Some(&versions(&["1.0.0", "1.0.1"])));
+        assert_eq!(serde_json::to_value(&package).unwrap(), serde_json::json!({
+            "name": "dep",
+            "current": "1.0.0",
+            "wanted": null,
+            "latest": "1.0.1",
+            "delta": "patch",
+        }));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::config::{Config, NetworkProfile};
+use crate::error::AntSolError;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct ProfileEntry<'a> {
+    name: &'a str,
+    active: bool,
+    #[serde(flatten)]
+    profile: &'a NetworkProfile,
+}
+
+/// List the profiles in config.toml, marking the one in use
+pub async fn handle_list(json: bool) -> Result<()> {
+    let config = Config::load_file()?;
+    let in_use = config.selected_profile.as_deref();
+    let entries: Vec<ProfileEntry> = config.profiles.iter()
+        .map(|(name, profile)| ProfileEntry { name, active: Some(name.as_str()) == in_use, profile })
+        .collect();
+
+    if json {
+        return emit_json(&entries);
+    }
+
+    println!("\n{}", "🌐 Network Profiles".cyan().bold());
+    for entry in &entries {
+        let marker = if entry.active { "*".green().bold() } else { " ".normal() };
+        println!("{} {:<12} {}", marker, entry.name.bold(), entry.profile.rpc_url.yellow());
+        println!("    program {}  indexer {}", entry.profile.program_id.dimmed(), entry.profile.indexer_url.dimmed());
+    }
+    Ok(())
+}
+
+/// Make `name` the active profile
+pub async fn handle_use(name: String) -> Result<()> {
+    let mut config = Config::load_file()?;
+    if !config.profiles.contains_key(&name) {
+        return Err(config.unknown_profile(&name));
+    }
+    config.active_profile = Some(name.clone());
+    config.save()?;
+    let profile = &config.profiles[&name];
+    print_success(&format!("Using profile {} ({})", name.cyan(), profile.rpc_url));
+    Ok(())
+}
+
+/// Values given to `profile add`; unset ones are copied from the profile in use
+pub struct AddOptions {
+    pub rpc_url: String,
+    pub program_id: Option<String>,
+    pub indexer_url: Option<String>,
+    pub ipfs_url: Option<String>,
+}
+
+/// Create profile `name`, validating values like `config set` does
+pub async fn handle_add(name: String, options: AddOptions) -> Result<()> {
+    let mut config = Config::load_file()?;
+    if config.profiles.contains_key(&name) {
+        return Err(AntSolError::Usage(format!("Profile '{}' already exists", name)).into());
+    }
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(AntSolError::Usage(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name)).into());
+    }
+
+    let mut draft = Config::default();
+    draft.apply_profile(&config.network_profile());
+    draft.set("rpc_url", &options.rpc_url)?;
+    for (key, value) in [("program_id", options.program_id), ("indexer_url", options.indexer_url), ("ipfs_url", options.ipfs_url)] {
+        if let Some(value) = value {
+            draft.set(key, &value)?;
+        }
+    }
+    config.profiles.insert(name.clone(), draft.network_profile());
+    config.save()?;
+
+    print_success(&format!("Added profile {}", name.cyan()));
+    print_info(&format!("Switch to it with: antsol profile use {}", name));
+    Ok(())
+}
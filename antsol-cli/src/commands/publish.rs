@@ -1,71 +1,981 @@
+use crate::commands::install::resolve_range;
 use crate::config::Config;
-use crate::ipfs::IpfsClient;
-use crate::solana_client::AntSolClient;
-use crate::types::{AntSolManifest, Result};
+use crate::error::AntSolError;
+use crate::indexer_client::IndexerClient;
+use crate::ipfs::{create_archive, IpfsClient, UploadedArchive};
+use crate::manifest::{bump_version, record_publish, Bump};
+use crate::manifest_check;
+use crate::package_files::{content_warnings, package_files, readme_path, IGNORE_FILE};
+use crate::publish_state::PublishState;
+use crate::signer::{known_pubkey, load_signer};
+use crate::resolver::VersionSpec;
+use crate::solana_client::{AntSolClient, NamespaceAccount, PublishRequest, PublishStatus};
+use crate::tx::SendOptions;
+use crate::types::{AntSolManifest, Dependency, ExternalDependency, Result};
 use crate::utils::*;
+use crate::validation::{
+    check_external_dependencies, check_metadata, check_package, package_scope, package_space, Violation, MAX_CID_LENGTH,
+};
+use crate::workspace;
 use colored::*;
-use solana_sdk::signature::Keypair;
-use std::path::PathBuf;
+use serde::Serialize;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-pub async fn handle_publish(path: PathBuf, version_override: Option<String>) -> Result<()> {
+/// Result of `antsol publish --output json` and `antsol update --output json`
+#[derive(Debug, Serialize)]
+pub struct PublishOutput {
+    pub name: String,
+    pub version: String,
+    /// Version the update was published from; `None` for `publish`
+    pub previous_version: Option<String>,
+    pub ipfs_cid: String,
+    pub content_sha256: String,
+    /// CID of the README.md pinned alongside, if the package has one
+    pub readme_cid: Option<String>,
+    pub signature: String,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn handle_publish(
+    path: PathBuf,
+    workspace: bool,
+    version_override: Option<String>,
+    bump: Option<Bump>,
+    list_files: bool,
+    dry_run: bool,
+    force: bool,
+    fresh: bool,
+    max_size: Option<u64>,
+    yes: bool,
+    with_deps: bool,
+    send: SendOptions,
+) -> Result<()> {
+    if workspace {
+        return publish_workspace(&path, dry_run, force, fresh, max_size, yes, &send).await;
+    }
+    if with_deps {
+        return publish_with_deps(&path, force, fresh, max_size, yes, &send).await;
+    }
     let manifest_path = path.join("antsol.toml");
-    if !manifest_path.exists() {
-        return Err("No antsol.toml found. Run 'antsol init' first.".into());
+    let mut report = manifest_check::check_dir(&path)?;
+    if version_override.is_some() {
+        report.ignore_key("package.version");
     }
-    
-    let manifest_content = std::fs::read_to_string(&manifest_path)?;
-    let mut manifest: AntSolManifest = toml::from_str(&manifest_content)?;
+    report.print(&manifest_path);
+    let mut manifest = report.into_manifest(&manifest_path)?;
     
     if let Some(version) = version_override {
         if !validate_version(&version) {
-            return Err("Invalid version format".into());
+            return Err(AntSolError::Usage("Invalid version format".into()).into());
         }
         manifest.package.version = version;
     }
+    if let Some(bump) = bump {
+        manifest.package.version = bump_version(&manifest.package.version, bump)?;
+    }
+    
+    if json_output() && (list_files || dry_run) {
+        return Err(AntSolError::Usage("--output json is not supported with --list-files or --dry-run".into()).into());
+    }
+    if list_files {
+        return print_package_files(&path);
+    }
+    
+    if dry_run {
+        return dry_run_publish(&path, &manifest, max_size).await;
+    }
+    ensure_can_confirm(yes)?;
+    
+    let output = publish_package(&path, &manifest, force, fresh, max_size, yes, &send).await?;
+    if json_output() {
+        return emit_json(&output);
+    }
+    Ok(())
+}
+
+/// Result of `antsol publish --workspace` or `--with-deps` with `--output json`
+#[derive(Debug, Serialize)]
+pub struct WorkspacePublishOutput {
+    /// Members published by this run, in publish order
+    pub published: Vec<PublishOutput>,
+    /// `name@version` of members whose version was already on-chain
+    pub skipped: Vec<String>,
+}
+
+/// Publish every member of the workspace at `root`, each after the members
+/// it depends on, with dependencies between members pinned to the versions
+/// being published. Members whose version is already on-chain are skipped,
+/// so running it again after a failure picks up where it stopped.
+async fn publish_workspace(
+    root: &Path,
+    dry_run: bool,
+    force: bool,
+    fresh: bool,
+    max_size: Option<u64>,
+    yes: bool,
+    send: &SendOptions,
+) -> Result<()> {
+    if json_output() && dry_run {
+        return Err(AntSolError::Usage("--output json is not supported with --dry-run".into()).into());
+    }
+    let mut members = workspace::load(root)?;
+    let order = workspace::publish_order(&members)?;
+    pin_workspace(&mut members, &order);
+    let labels: Vec<String> = order.iter().map(|&i| members[i].label()).collect();
+    print_info(&format!("Workspace publish order: {}", labels.join(", ")));
+    check_members(&members, &order)?;
+    
+    if dry_run {
+        let mut failed = Vec::new();
+        for &i in &order {
+            println!();
+            if let Err(e) = dry_run_publish(&members[i].dir, &members[i].manifest, max_size).await {
+                print_error(&e.to_string());
+                failed.push(members[i].label());
+            }
+        }
+        return match failed.len() {
+            0 => Ok(()),
+            n => Err(AntSolError::Usage(format!("Dry run failed for {} of {} member(s): {}", n, order.len(), failed.join(", "))).into()),
+        };
+    }
+    ensure_can_confirm(yes)?;
+    
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    let solana_client = AntSolClient::new(&config)?;
+    let mut output = WorkspacePublishOutput { published: Vec::new(), skipped: Vec::new() };
+    for (position, &i) in order.iter().enumerate() {
+        let member = &members[i];
+        let result = match solana_client.package_exists(member.name(), member.version()) {
+            Ok(true) => {
+                print_info(&format!("{} is already published; skipping", member.label()));
+                output.skipped.push(member.label());
+                continue;
+            }
+            Ok(false) => {
+                println!();
+                publish_package(&member.dir, &member.manifest, force, fresh, max_size, yes, send).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(published) => output.published.push(published),
+            Err(e) => {
+                let unpublished: Vec<String> = order[position + 1..].iter().map(|&j| members[j].label()).collect();
+                report_stopped(&output, &member.label(), &unpublished);
+                return Err(e);
+            }
+        }
+    }
+    
+    if json_output() {
+        return emit_json(&output);
+    }
+    print_success(&format!(
+        "Published {} workspace member(s); {} already published",
+        output.published.len(),
+        output.skipped.len()
+    ));
+    Ok(())
+}
+
+/// Pin the dependencies of the members at `order` on other members to the
+/// versions being published
+fn pin_workspace(members: &mut [workspace::Member], order: &[usize]) {
+    let versions: HashMap<String, String> =
+        members.iter().map(|m| (m.name().to_string(), m.version().to_string())).collect();
+    for &i in order {
+        let member = &mut members[i];
+        let name = member.manifest.package.name.clone();
+        for (dep, from, to) in workspace::pin_members(&mut member.manifest, &versions) {
+            print_info(&format!("Pinned {}'s dependency on {} {} to {}", name, dep, from.cyan(), to.cyan()));
+        }
+    }
+}
+
+/// Run the manifest checks on the members at `order`, failing if any has errors
+fn check_members(members: &[workspace::Member], order: &[usize]) -> Result<()> {
+    let mut invalid = Vec::new();
+    for &i in order {
+        let report = manifest_check::check_dir(&members[i].dir)?;
+        report.print(&members[i].dir.join("antsol.toml"));
+        if report.errors() > 0 {
+            invalid.push(members[i].label());
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(AntSolError::Config(format!("Fix the errors in antsol.toml of: {}", invalid.join(", "))).into());
+    }
+    Ok(())
+}
+
+/// Publish the package at `path` together with the members of its workspace
+/// it depends on that aren't on-chain yet. Each is checked and uploaded on
+/// its own, then the publish instructions go out batched into as few
+/// transactions as fit, so the wallet signs once per transaction.
+async fn publish_with_deps(
+    path: &Path,
+    force: bool,
+    fresh: bool,
+    max_size: Option<u64>,
+    yes: bool,
+    send: &SendOptions,
+) -> Result<()> {
+    let root = workspace::find_root(path).ok_or_else(|| {
+        AntSolError::Usage(format!(
+            "--with-deps publishes dependencies from the package's workspace, but {} is not a member of one",
+            path.display()
+        ))
+    })?;
+    let mut members = workspace::load(&root)?;
+    let dir = path.canonicalize()?;
+    let target = members
+        .iter()
+        .position(|m| m.dir.canonicalize().is_ok_and(|member| member == dir))
+        .ok_or_else(|| AntSolError::Config(format!("{} is not listed in {}", path.display(), root.join("antsol.toml").display())))?;
+    let order = workspace::with_dependencies(&members, target)?;
+    pin_workspace(&mut members, &order);
+    check_members(&members, &order)?;
+    ensure_can_confirm(yes)?;
+    
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    if let Some(max_size) = max_size {
+        config.max_package_size = max_size;
+    }
+    let signer = load_signer(&config)?;
+    let solana_client = AntSolClient::new(&config)?;
+    let mut output = WorkspacePublishOutput { published: Vec::new(), skipped: Vec::new() };
+    let mut pending = Vec::new();
+    for &i in &order {
+        if solana_client.package_exists(members[i].name(), members[i].version())? {
+            print_info(&format!("{} is already published; skipping", members[i].label()));
+            output.skipped.push(members[i].label());
+        } else {
+            pending.push(i);
+        }
+    }
+    if pending.is_empty() {
+        print_success(&format!("{} and its workspace dependencies are already published", members[target].label()));
+        return if json_output() { emit_json(&output) } else { Ok(()) };
+    }
+    
+    let mut cost = 0;
+    let mut pinned = Vec::with_capacity(pending.len());
+    for &i in &pending {
+        let member = &members[i];
+        ensure_namespace_allows(&solana_client, member.name(), &signer.pubkey())?;
+        ensure_valid_external_dependencies(&member.manifest)?;
+        confirm_package_contents(&member.dir, !force)?;
+        let dependencies = pin_dependencies(&config.indexer_url, member.manifest.dependencies.as_deref().unwrap_or_default()).await?;
+        cost += solana_client.publish_cost(estimated_space(&member.dir, &member.manifest, member.version(), &dependencies));
+        pinned.push(dependencies);
+    }
+    if !force {
+        let available = solana_client.balance(&signer.pubkey())?;
+        if available < cost {
+            return Err(AntSolError::InsufficientFunds { needed: cost, available }.into());
+        }
+    }
+    let labels: Vec<String> = pending.iter().map(|&i| members[i].label()).collect();
+    if !json_output() {
+        println!("\n{}", "Publish summary".cyan().bold());
+        println!("  Packages: {}", labels.join(", ").green());
+        println!("  Estimated cost: {} SOL (rent plus fees)", lamports_to_sol(cost));
+        println!("  Cluster: {} ({})", Cluster::detect(&config.rpc_url).as_str().cyan(), config.rpc_url);
+        println!();
+    }
+    if !yes {
+        confirm_batch(&labels)?;
+    }
+    
+    let ipfs_client = IpfsClient::from_config(&config);
+    let mut requests = Vec::with_capacity(pending.len());
+    let mut uploads = Vec::with_capacity(pending.len());
+    for (&i, dependencies) in pending.iter().zip(pinned) {
+        let member = &members[i];
+        let spinner = create_spinner(&format!("Uploading {} to IPFS...", member.label()));
+        let uploaded = ipfs_client.upload_package(&member.dir, fresh).await;
+        spinner.finish_and_clear();
+        let uploaded = uploaded?;
+        report_upload(&uploaded);
+        requests.push(PublishRequest {
+            name: member.name().to_string(),
+            version: member.version().to_string(),
+            ipfs_cid: uploaded.cid.clone(),
+            description: member.manifest.package.description.clone(),
+            dependencies,
+            content_sha256: uploaded.sha256.clone(),
+            metadata: member.manifest.package.metadata(),
+            readme_cid: uploaded.readme_cid.clone(),
+            external_dependencies: member.manifest.external_dependencies.clone().unwrap_or_default(),
+        });
+        uploads.push(uploaded);
+    }
+    
+    let spinner = create_spinner(&format!("Publishing {} package(s) to Solana...", requests.len()));
+    let statuses = solana_client.publish_many(signer.as_ref(), requests).await;
+    spinner.finish_and_clear();
+    let mut failed = 0;
+    for ((&i, uploaded), status) in pending.iter().zip(uploads).zip(statuses?) {
+        let member = &members[i];
+        match status {
+            PublishStatus::Published(signature) => {
+                print_success(&format!("Published {} in {}", member.label().green(), signature.cyan()));
+                PublishState::clear(&member.dir);
+                write_back(&member.dir.join("antsol.toml"), member.version(), &uploaded.cid);
+                output.published.push(PublishOutput {
+                    name: member.name().to_string(),
+                    version: member.version().to_string(),
+                    previous_version: None,
+                    ipfs_cid: uploaded.cid,
+                    content_sha256: uploaded.sha256,
+                    readme_cid: uploaded.readme_cid,
+                    signature,
+                });
+            }
+            PublishStatus::Failed(reason) => {
+                failed += 1;
+                print_error(&format!("{} failed: {}", member.label(), reason));
+            }
+            PublishStatus::NotPublished => {
+                failed += 1;
+                print_warning(&format!("{} not published: its transaction was rolled back or not sent", member.label()));
+            }
+        }
+    }
+    if failed > 0 {
+        print_info("Run the same command again to resume; packages already on-chain are skipped");
+        return Err(AntSolError::Program(format!(
+            "Published {} of {} package(s)", output.published.len(), output.published.len() + failed
+        )).into());
+    }
+    await_visible(&solana_client, members[target].name(), members[target].version()).await?;
     
+    if json_output() {
+        return emit_json(&output);
+    }
+    print_success(&format!(
+        "Published {} package(s); {} already published",
+        output.published.len(),
+        output.skipped.len()
+    ));
+    Ok(())
+}
+
+fn confirm_batch(labels: &[String]) -> Result<()> {
+    print!("Published versions can't be changed or removed. Type \"yes\" to publish {}: ", labels.join(", "));
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("yes") {
+        Ok(())
+    } else {
+        Err(AntSolError::Usage("Cancelled; nothing was uploaded".into()).into())
+    }
+}
+
+/// Say exactly where a workspace publish stopped: which members are
+/// on-chain and which are not, with `failed` first among the latter
+fn report_stopped(output: &WorkspacePublishOutput, failed: &str, unpublished: &[String]) {
+    let published: Vec<String> = output.published.iter().map(|p| format!("{}@{}", p.name, p.version)).collect();
+    print_error(&format!("Workspace publish stopped at {}", failed));
+    print_info(&format!("Published in this run: {}", list_or_none(&published)));
+    if !output.skipped.is_empty() {
+        print_info(&format!("Already published: {}", output.skipped.join(", ")));
+    }
+    let mut not_published = vec![format!("{} (failed)", failed)];
+    not_published.extend_from_slice(unpublished);
+    print_warning(&format!("Not published: {}", not_published.join(", ")));
+    print_info("Run the same command again to resume; members already on-chain are skipped");
+}
+
+fn list_or_none(labels: &[String]) -> String {
+    if labels.is_empty() {
+        "none".to_string()
+    } else {
+        labels.join(", ")
+    }
+}
+
+/// Checked before anything slow so scripts fail fast instead of waiting on a prompt
+fn ensure_can_confirm(yes: bool) -> Result<()> {
+    if !yes && (json_output() || !std::io::stdin().is_terminal()) {
+        return Err(AntSolError::Usage(
+            "A publish can't be undone and needs confirmation, but stdin is not a terminal. Pass --yes to publish without asking".into()
+        ).into());
+    }
+    Ok(())
+}
+
+/// Check, confirm, upload and send the publish of the package at `path`,
+/// then record the version and CID in its `antsol.toml`. Prints the
+/// details unless the command outputs JSON.
+async fn publish_package(
+    path: &Path,
+    manifest: &AntSolManifest,
+    force: bool,
+    fresh: bool,
+    max_size: Option<u64>,
+    yes: bool,
+    send: &SendOptions,
+) -> Result<PublishOutput> {
     print_info(&format!("Publishing {} v{}", manifest.package.name.cyan(), manifest.package.version.cyan()));
     
     // Load wallet and config
-    let config = Config::load()?;
-    let wallet_path = config.wallet_path.as_ref().ok_or("No wallet connected. Use 'antsol wallet connect'")?;
-    let keypair_bytes = std::fs::read(&wallet_path)?;
-    let keypair_vec: Vec<u8> = serde_json::from_slice(&keypair_bytes)?;
-    let keypair = Keypair::from_bytes(&keypair_vec)?;
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    if let Some(max_size) = max_size {
+        config.max_package_size = max_size;
+    }
+    let dependencies = pin_dependencies(&config.indexer_url, manifest.dependencies.as_deref().unwrap_or_default()).await?;
+    let signer = load_signer(&config)?;
+    let solana_client = AntSolClient::new(&config)?;
+    let space = estimated_space(path, manifest, &manifest.package.version, &dependencies);
+    // Both would otherwise only surface as a failed transaction after the IPFS pin
+    let mut already_published = false;
+    if force {
+        print_warning("--force: skipping the existing-version and balance checks");
+        already_published = solana_client.package_exists(&manifest.package.name, &manifest.package.version).unwrap_or(false);
+    } else {
+        ensure_unpublished(&solana_client, &manifest.package.name, &manifest.package.version)?;
+        solana_client.ensure_publish_funds(&signer.pubkey(), space)?;
+    }
+    ensure_namespace_allows(&solana_client, &manifest.package.name, &signer.pubkey())?;
     
-    let spinner = create_spinner("Uploading package to IPFS...");
+    ensure_valid_external_dependencies(manifest)?;
+    confirm_package_contents(path, !force)?;
     
-    // Create IPFS client with JWT from config or environment
-    let ipfs_client = if let Some(jwt) = config.pinata_jwt.clone() {
-        IpfsClient::with_jwt(config.ipfs_url.clone(), jwt)
-    } else {
-        IpfsClient::new(config.ipfs_url.clone())
+    let archive = archive_bytes(path)?;
+    let latest_indexed = latest_indexed_version(&config.indexer_url, &manifest.package.name).await;
+    warn_if_frozen(&solana_client, &manifest.package.name, latest_indexed.as_deref());
+    let summary = PublishSummary {
+        name: manifest.package.name.clone(),
+        version: manifest.package.version.clone(),
+        description: manifest.package.description.clone(),
+        files: package_files(path)?.len(),
+        archive_size: archive.len() as u64,
+        dependencies: dependencies.clone(),
+        external_dependencies: manifest.external_dependencies.clone().unwrap_or_default(),
+        cost: solana_client.publish_cost(space),
+        space,
+        cluster: Cluster::detect(&config.rpc_url),
+        rpc_url: config.rpc_url.clone(),
+        already_published,
+        latest_indexed,
     };
+    if !json_output() {
+        summary.print();
+    }
+    if !yes {
+        confirm_publish(&summary)?;
+    }
+    
+    let spinner = create_spinner("Uploading package to IPFS...");
     
-    let cid = ipfs_client.upload_package(&path).await?;
+    // Create IPFS client for the configured pinning provider and its credential
+    let ipfs_client = IpfsClient::from_config(&config);
+    
+    let uploaded = ipfs_client.upload_package(path, fresh).await?;
     spinner.finish_and_clear();
-    print_success(&format!("Uploaded to IPFS: {}", cid.green()));
+    report_upload(&uploaded);
+    let cid = uploaded.cid;
     
     let spinner = create_spinner("Publishing to Solana...");
-    let solana_client = AntSolClient::new(&config)?;
     
-    let signature = solana_client.publish_package(
-        &keypair,
-        manifest.package.name.clone(),
-        manifest.package.version.clone(),
-        cid.clone(),
-        manifest.package.description.clone(),
-        manifest.dependencies.unwrap_or_default(),
-        manifest.external_dependencies.unwrap_or_default(),
-    ).await?;
+    let signature = solana_client.publish_package(signer.as_ref(), PublishRequest {
+        name: manifest.package.name.clone(),
+        version: manifest.package.version.clone(),
+        ipfs_cid: cid.clone(),
+        description: manifest.package.description.clone(),
+        dependencies,
+        content_sha256: uploaded.sha256.clone(),
+        metadata: manifest.package.metadata(),
+        readme_cid: uploaded.readme_cid.clone(),
+        external_dependencies: manifest.external_dependencies.clone().unwrap_or_default(),
+    }).await?;
     
     spinner.finish_and_clear();
+    await_visible(&solana_client, &manifest.package.name, &manifest.package.version).await?;
     
     print_success(&format!("Published {}@{}", manifest.package.name.green().bold(), manifest.package.version.green()));
+    PublishState::clear(path);
+    write_back(&path.join("antsol.toml"), &manifest.package.version, &cid);
+    
+    if !json_output() {
+        println!("\n{}", "Package Details".cyan().bold());
+        println!("  IPFS CID: {}", cid.cyan());
+        println!("  Content sha256: {}", uploaded.sha256.cyan());
+        if let Some(readme_cid) = &uploaded.readme_cid {
+            println!("  README CID: {}", readme_cid.cyan());
+        }
+        println!("  Transaction: {}", signature.cyan());
+        println!("  Explorer: {}", explorer_url(&config.rpc_url, &format!("tx/{}", signature)).blue());
+    }
     
-    println!("\n{}", "Package Details".cyan().bold());
-    println!("  IPFS CID: {}", cid.cyan());
-    println!("  Transaction: {}", signature.cyan());
-    println!("  Explorer: {}", format!("https://explorer.solana.com/tx/{}?cluster=devnet", signature).blue());
+    Ok(PublishOutput {
+        name: manifest.package.name.clone(),
+        version: manifest.package.version.clone(),
+        previous_version: None,
+        ipfs_cid: cid,
+        content_sha256: uploaded.sha256,
+        readme_cid: uploaded.readme_cid,
+        signature,
+    })
+}
+
+/// What a publish is about to make permanent, shown before anything is uploaded
+#[derive(Debug)]
+struct PublishSummary {
+    name: String,
+    version: String,
+    description: String,
+    files: usize,
+    archive_size: u64,
+    dependencies: Vec<Dependency>,
+    external_dependencies: Vec<ExternalDependency>,
+    /// Rent plus fees, in lamports
+    cost: u64,
+    space: usize,
+    cluster: Cluster,
+    rpc_url: String,
+    /// Only found out under `--force`; otherwise publish has already stopped
+    already_published: bool,
+    /// Highest version of the package the indexer knows, if it answered
+    latest_indexed: Option<String>,
+}
+
+impl PublishSummary {
+    /// Reasons this publish is probably a mistake, for the prompt to call out
+    fn hazards(&self) -> Vec<String> {
+        let mut hazards = Vec::new();
+        if self.already_published {
+            hazards.push(format!(
+                "{}@{} is already published on-chain; the transaction will fail",
+                self.name, self.version
+            ));
+        }
+        if let Some(latest) = &self.latest_indexed {
+            if compare_versions(&self.version, latest) == std::cmp::Ordering::Less {
+                hazards.push(format!(
+                    "{} is lower than {}, the latest published version of {}",
+                    self.version, latest, self.name
+                ));
+            }
+        }
+        hazards
+    }
     
+    fn print(&self) {
+        println!("\n{}", "Publish summary".cyan().bold());
+        println!("  Package: {}@{}", self.name.green().bold(), self.version.green());
+        println!("  Description: {}", self.description);
+        println!("  Files: {} ({} archive)", self.files, format_size(self.archive_size));
+        if self.dependencies.is_empty() {
+            println!("  Dependencies: none");
+        } else {
+            let dependencies: Vec<String> = self.dependencies.iter().map(|d| format!("{}@{}", d.name, d.version)).collect();
+            println!("  Dependencies: {}", dependencies.join(", "));
+        }
+        if !self.external_dependencies.is_empty() {
+            let external: Vec<String> =
+                self.external_dependencies.iter().map(|d| format!("{} ({})", d.name, d.dep_type)).collect();
+            println!("  External dependencies: {}", external.join(", "));
+        }
+        println!("  Estimated cost: {} SOL (rent for up to {} bytes plus fees)", lamports_to_sol(self.cost), self.space);
+        println!("  Cluster: {} ({})", self.cluster.as_str().cyan(), self.rpc_url);
+        println!();
+    }
+}
+
+/// Highest version of `name` in the indexer; `None` when it isn't indexed or
+/// the indexer can't be reached, which shouldn't hold up a publish
+async fn latest_indexed_version(indexer_url: &str, name: &str) -> Option<String> {
+    match IndexerClient::new(indexer_url).get_package(name).await {
+        Ok(details) => details.and_then(|d| d.latest_version),
+        Err(e) => {
+            tracing::debug!("Could not ask the indexer for earlier versions of {}: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Warn when the latest published version of `name` is frozen: its
+/// publisher marked the package final, so a new version is likely a mistake
+fn warn_if_frozen(solana_client: &AntSolClient, name: &str, latest: Option<&str>) {
+    let Some(latest) = latest else {
+        return;
+    };
+    if let Ok(Some(package)) = solana_client.get_package(name, latest) {
+        if package.frozen {
+            print_warning(&format!(
+                "{}@{} is frozen 🔒; its publisher marked the package final, and updates to it are rejected",
+                name, latest
+            ));
+        }
+    }
+}
+
+/// Ask for a typed "yes" before anything irreversible, with any hazards in red
+fn confirm_publish(summary: &PublishSummary) -> Result<()> {
+    for hazard in summary.hazards() {
+        println!("{} {}", "✗".red().bold(), hazard.red().bold());
+    }
+    print!("Published versions can't be changed or removed. Type \"yes\" to publish {}@{}: ", summary.name, summary.version);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("yes") {
+        Ok(())
+    } else {
+        Err(AntSolError::Usage("Cancelled; nothing was uploaded".into()).into())
+    }
+}
+
+/// The archive `publish` would upload, built in a temp file and read back
+fn archive_bytes(path: &Path) -> Result<Vec<u8>> {
+    let archive = create_archive(path)?;
+    let bytes = std::fs::read(&archive);
+    let _ = std::fs::remove_file(&archive);
+    Ok(bytes?)
+}
+
+/// Say where the CID came from: a fresh upload, or the publish state left
+/// by an earlier attempt whose transaction failed
+pub(crate) fn report_upload(uploaded: &UploadedArchive) {
+    if uploaded.reused {
+        print_success(&format!("Reusing {} from the previous upload of this exact archive", uploaded.cid.green()));
+        print_info("Pass --fresh to upload it again");
+    } else {
+        print_success(&format!("Uploaded to IPFS: {}", uploaded.cid.green()));
+    }
+    if let Some(readme_cid) = &uploaded.readme_cid {
+        print_info(&format!("README pinned as {}", readme_cid));
+    }
+}
+
+/// Wait for `name@version` to read back before reporting success, so an
+/// `antsol info` right after finds it. The transaction has landed either way,
+/// so running out of time only warns.
+pub(crate) async fn await_visible(solana_client: &AntSolClient, name: &str, version: &str) -> Result<()> {
+    let spinner = create_spinner(&format!("Waiting for {}@{} to be readable...", name, version));
+    let visible = solana_client.wait_until_visible(name, version).await;
+    spinner.finish_and_clear();
+    if !visible? {
+        print_warning(&format!(
+            "{}@{} landed but the RPC node doesn't return it yet; it should show up shortly",
+            name, version
+        ));
+    }
     Ok(())
 }
+
+/// Record a published version and CID in `antsol.toml`. Only called once the
+/// transaction has landed; a failure here doesn't undo the publish, so it warns.
+pub(crate) fn write_back(manifest_path: &Path, version: &str, cid: &str) {
+    match record_publish(manifest_path, version, cid) {
+        Ok(()) => print_info(&format!("Set version {} in {}", version, manifest_path.display())),
+        Err(e) => print_warning(&format!(
+            "Published, but could not update {} ({}); set version = \"{}\" by hand",
+            manifest_path.display(),
+            e,
+            version
+        )),
+    }
+}
+
+/// Refuse external dependencies the program would reject before anything is
+/// uploaded, listing all of them rather than the first the transaction hits
+pub(crate) fn ensure_valid_external_dependencies(manifest: &AntSolManifest) -> Result<()> {
+    let violations = check_external_dependencies(manifest.external_dependencies.as_deref().unwrap_or_default());
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let messages: Vec<String> = violations.into_iter().map(|v| v.message).collect();
+    Err(AntSolError::Usage(format!("external_dependencies in antsol.toml: {}", messages.join("; "))).into())
+}
+
+/// Fail before anything is uploaded when `name` is scoped and `wallet` may
+/// not publish under its scope
+fn ensure_namespace_allows(solana_client: &AntSolClient, name: &str, wallet: &Pubkey) -> Result<()> {
+    let Some(scope) = package_scope(name) else {
+        return Ok(());
+    };
+    let namespace = solana_client.get_namespace(scope)?;
+    match namespace_violation(scope, namespace.as_ref(), Some(wallet)) {
+        Some(violation) if namespace.is_none() => Err(AntSolError::NotFound(violation.message).into()),
+        Some(violation) => Err(AntSolError::Wallet(violation.message).into()),
+        None => Ok(()),
+    }
+}
+
+/// Fail before anything is uploaded when `name@version` already has an
+/// account, naming who published it and when
+pub(crate) fn ensure_unpublished(solana_client: &AntSolClient, name: &str, version: &str) -> Result<()> {
+    let Some(existing) = solana_client.get_package(name, version)? else {
+        return Ok(());
+    };
+    let published = chrono::DateTime::<chrono::Utc>::from_timestamp(existing.published_at, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "an unknown date".to_string());
+    Err(AntSolError::Usage(format!(
+        "{}@{} is already published by {} on {}. Published versions can't be replaced; publish the next version with 'antsol update'",
+        name, version, existing.authority, published
+    )).into())
+}
+
+/// Size of the account the program will create for the package at `path`,
+/// assuming the longest CIDs since they're only known after the upload
+pub(crate) fn estimated_space(path: &Path, manifest: &AntSolManifest, version: &str, dependencies: &[Dependency]) -> usize {
+    package_space(
+        &manifest.package.name,
+        version,
+        MAX_CID_LENGTH,
+        &manifest.package.description,
+        dependencies,
+        &manifest.package.metadata(),
+        readme_path(path).map(|_| MAX_CID_LENGTH),
+        manifest.external_dependencies.as_deref().unwrap_or_default(),
+    )
+}
+
+/// Why the program would refuse to publish under `scope`, if it would.
+/// Without a `wallet` only the namespace's existence is checked.
+fn namespace_violation(scope: &str, namespace: Option<&NamespaceAccount>, wallet: Option<&Pubkey>) -> Option<Violation> {
+    match (namespace, wallet) {
+        (None, _) => Some(Violation {
+            code: "NamespaceRequired",
+            message: format!("Namespace '{}' is not registered; claim it with 'antsol namespace register {}'", scope, scope),
+        }),
+        (Some(namespace), Some(wallet)) if !namespace.can_publish(wallet) => Some(Violation {
+            code: "UnauthorizedNamespacePublisher",
+            message: format!(
+                "Wallet {} may not publish under '{}' (owned by {}); ask the owner to run 'antsol namespace publishers {} <wallet>...'",
+                wallet, scope, namespace.owner, scope
+            ),
+        }),
+        _ => None,
+    }
+}
+
+/// Replace every range in `dependencies` with the highest indexed version it
+/// matches; the program only accepts exact versions. Exact versions pass through.
+pub(crate) async fn pin_dependencies(indexer_url: &str, dependencies: &[Dependency]) -> Result<Vec<Dependency>> {
+    let mut pinned = Vec::with_capacity(dependencies.len());
+    for dep in dependencies {
+        let version = match VersionSpec::parse(&dep.version)? {
+            VersionSpec::Exact(version) => version,
+            range => {
+                let version = resolve_range(indexer_url, &dep.name, &dep.version, &range).await?;
+                print_info(&format!("Pinned {} {} to {}", dep.name, dep.version.cyan(), version.cyan()));
+                version
+            }
+        };
+        pinned.push(Dependency { name: dep.name.clone(), version });
+    }
+    Ok(pinned)
+}
+
+/// Warn about files that probably shouldn't be published and, with `prompt`
+/// on an interactive terminal, ask before going on
+pub(crate) fn confirm_package_contents(path: &Path, prompt: bool) -> Result<()> {
+    let warnings = content_warnings(path, &package_files(path)?)?;
+    if warnings.is_empty() {
+        return Ok(());
+    }
+    print_warning(&format!("{} file(s) look out of place in a package:", warnings.len()));
+    for (file, reason) in &warnings {
+        print_warning(&format!("  {}: {}", file.display(), reason));
+    }
+    print_info(&format!("Exclude them in {} if they aren't needed", IGNORE_FILE));
+    
+    if !prompt || json_output() || !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    print!("Continue anyway? [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") || answer.trim().eq_ignore_ascii_case("yes") {
+        Ok(())
+    } else {
+        Err(AntSolError::Usage("Cancelled; nothing was uploaded".into()).into())
+    }
+}
+
+/// Print exactly what `create_archive` would pack, for auditing before upload
+fn print_package_files(path: &Path) -> Result<()> {
+    let files = package_files(path)?;
+    let mut total = 0;
+    for file in &files {
+        let size = std::fs::metadata(path.join(file))?.len();
+        total += size;
+        println!("  {:>10}  {}", format_size(size), file.display());
+    }
+    
+    let source = if path.join(IGNORE_FILE).is_file() {
+        format!("patterns from {}", IGNORE_FILE)
+    } else {
+        format!("default exclusions (no {})", IGNORE_FILE)
+    };
+    println!("\n  {} file(s), {} total, using {}", files.len(), format_size(total), source);
+    confirm_package_contents(path, false)
+}
+
+/// Run every check publish would hit without uploading or sending anything.
+/// Fails if the registry program would reject the publish.
+async fn dry_run_publish(path: &Path, manifest: &AntSolManifest, max_size: Option<u64>) -> Result<()> {
+    let name = &manifest.package.name;
+    let version = &manifest.package.version;
+    println!("{} {}@{}\n", "🧪 Dry run:".cyan().bold(), name, version);
+    
+    let config = Config::load()?;
+    let dependencies = pin_dependencies(&config.indexer_url, manifest.dependencies.as_deref().unwrap_or_default()).await?;
+    let mut violations = check_package(name, version, &manifest.package.description, &dependencies);
+    violations.extend(check_metadata(&manifest.package.metadata()));
+    violations.extend(check_external_dependencies(manifest.external_dependencies.as_deref().unwrap_or_default()));
+    if violations.is_empty() {
+        print_success("Manifest passes the registry program's checks");
+    }
+    for violation in &violations {
+        println!("  {} {}: {}", "✗".red(), violation.code.red(), violation.message);
+    }
+    
+    // Build the archive exactly as publish would, then throw it away
+    let files = package_files(path)?;
+    let bytes = archive_bytes(path)?;
+    println!("\n{}", "Archive".cyan().bold());
+    println!("  Files: {}", files.len());
+    println!("  Size: {}", format_size(bytes.len() as u64));
+    println!("  Content sha256: {}", sha256_hex(&bytes));
+    let max_size = max_size.unwrap_or(config.max_package_size);
+    if bytes.len() as u64 > max_size {
+        let violation = Violation {
+            code: "ArchiveTooLarge",
+            message: format!("{} is over the {} upload limit", format_size(bytes.len() as u64), format_size(max_size)),
+        };
+        println!("  {} {}: {}", "✗".red(), violation.code.red(), violation.message);
+        violations.push(violation);
+    }
+    confirm_package_contents(path, false)?;
+    
+    let solana_client = AntSolClient::new(&config)?;
+    let (pda, _) = solana_client.derive_package_pda(name, version);
+    println!("\n{}", "On-chain".cyan().bold());
+    println!("  Package PDA: {}", pda.to_string().cyan());
+    match solana_client.package_exists(name, version) {
+        Ok(true) => {
+            let violation = Violation {
+                code: "PackageExists",
+                message: format!("{}@{} is already published", name, version),
+            };
+            println!("  {} {}: {}", "✗".red(), violation.code.red(), violation.message);
+            violations.push(violation);
+        }
+        Ok(false) => println!("  {} {}@{} is not yet published", "✓".green(), name, version),
+        Err(e) => print_warning(&format!("Could not check whether {}@{} exists: {}", name, version, e)),
+    }
+    if let Some(scope) = package_scope(name) {
+        let (namespace_pda, _) = solana_client.derive_namespace_pda(scope);
+        println!("  Namespace PDA: {}", namespace_pda.to_string().cyan());
+        let wallet = config.wallet_path.as_ref().and_then(|path| known_pubkey(path).ok().flatten());
+        match solana_client.get_namespace(scope) {
+            Ok(namespace) => match namespace_violation(scope, namespace.as_ref(), wallet.as_ref()) {
+                Some(violation) => {
+                    println!("  {} {}: {}", "✗".red(), violation.code.red(), violation.message);
+                    violations.push(violation);
+                }
+                None => println!("  {} namespace '{}' is registered", "✓".green(), scope),
+            },
+            Err(e) => print_warning(&format!("Could not check namespace '{}': {}", scope, e)),
+        }
+    }
+    
+    let space = estimated_space(path, manifest, version, &dependencies);
+    let rent = solana_client.rent_exempt_minimum(space).unwrap_or_else(|e| {
+        print_warning(&format!("Could not fetch rent from RPC ({}); using the default rent schedule", e));
+        solana_sdk::rent::Rent::default().minimum_balance(space)
+    });
+    println!("  Rent: {} SOL for up to {} bytes (plus transaction fees)", lamports_to_sol(rent), space);
+    
+    println!();
+    if violations.is_empty() {
+        print_success("Dry run passed; nothing was uploaded or sent");
+        Ok(())
+    } else {
+        Err(AntSolError::Usage(format!("{} check(s) would fail", violations.len())).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::{client_with, sample_package};
+
+    #[test]
+    fn existing_versions_are_refused_before_upload() {
+        let existing = sample_package("foo", "1.0.0", &[]);
+        let authority = existing.authority;
+        let client = client_with(&[existing], None);
+        let err = ensure_unpublished(&client, "foo", "1.0.0").unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains(&format!("foo@1.0.0 is already published by {} on 2023-11-14", authority)));
+        assert!(message.contains("antsol update"));
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Usage);
+        ensure_unpublished(&client, "foo", "1.0.1").unwrap();
+    }
+
+    #[test]
+    fn summary_calls_out_existing_and_older_versions() {
+        let mut summary = PublishSummary {
+            name: "foo".to_string(),
+            version: "1.10.0".to_string(),
+            description: "foo package".to_string(),
+            files: 3,
+            archive_size: 2048,
+            dependencies: vec![],
+            external_dependencies: vec![],
+            cost: 2_000_000,
+            space: 300,
+            cluster: Cluster::Devnet,
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            already_published: false,
+            latest_indexed: Some("1.9.0".to_string()),
+        };
+        assert!(summary.hazards().is_empty());
+
+        summary.version = "1.2.0".to_string();
+        summary.already_published = true;
+        assert_eq!(summary.hazards(), [
+            "foo@1.2.0 is already published on-chain; the transaction will fail",
+            "1.2.0 is lower than 1.9.0, the latest published version of foo",
+        ]);
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let output = PublishOutput {
+            name: "foo".to_string(),
+            version: "1.1.0".to_string(),
+            previous_version: Some("1.0.0".to_string()),
+            ipfs_cid: "QmFoo".to_string(),
+            content_sha256: "ab".repeat(32),
+            readme_cid: None,
+            signature: "5sig".to_string(),
+        };
+        assert_eq!(serde_json::to_value(output).unwrap(), serde_json::json!({
+            "name": "foo",
+            "version": "1.1.0",
+            "previous_version": "1.0.0",
+            "ipfs_cid": "QmFoo",
+            "content_sha256": "ab".repeat(32),
+            "readme_cid": null,
+            "signature": "5sig"
+        }));
+    }
+}
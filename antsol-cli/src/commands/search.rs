@@ -1,131 +1,449 @@
 use crate::types::Result;
 use crate::utils::*;
 use crate::config::Config;
+use crate::error::AntSolError;
 use colored::*;
-use serde::Deserialize;
+use crate::indexer_client::{IndexerClient, PackageDetails, PackageRow, SearchFilters, SearchSort};
+use crate::solana_client::AntSolClient;
+use crate::types::PackageAccount;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
-#[derive(Debug, Deserialize)]
-struct PackageRow {
-    id: i64,
-    name: String,
-    author: Option<String>,
-    description: Option<String>,
-    repository: Option<String>,
-    homepage: Option<String>,
-    total_downloads: Option<u64>,
-}
-
-#[derive(Debug, Deserialize)]
-struct VersionRow {
-    id: i64,
-    package_id: i64,
-    version: String,
-    ipfs_hash: Option<String>,
-    downloads: Option<u64>,
-    published_at: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct PackageDetails {
-    id: i64,
-    name: String,
-    author: Option<String>,
-    description: Option<String>,
-    repository: Option<String>,
-    homepage: Option<String>,
-    total_downloads: Option<u64>,
-    versions: Vec<VersionRow>,
+/// Most package accounts an on-chain search reads
+const MAX_SCANNED_ACCOUNTS: usize = 5_000;
+
+/// Result of `antsol search --output json`
+#[derive(Debug, Serialize)]
+pub struct SearchOutput {
+    pub query: String,
+    pub page: u32,
+    pub limit: u32,
+    /// `"indexer"` or `"chain"`
+    pub source: &'static str,
+    pub packages: Vec<SearchHit>,
+    /// Whether `page + 1` may have more results
+    pub has_more: bool,
+    /// Matches across all pages, when the source reports it
+    pub total: Option<u64>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ApiResponse<T> {
-    success: bool,
-    data: T,
-    error: Option<String>,
+#[derive(Debug, Serialize)]
+pub struct SearchHit {
+    pub name: String,
+    pub latest_version: Option<String>,
+    /// Every published version, oldest first (only with `--verbose`)
+    pub versions: Option<Vec<String>>,
+    pub description: Option<String>,
+    /// Base58 pubkey of the package authority
+    pub author: Option<String>,
+    /// RFC 3339 publish time of `latest_version`
+    pub published_at: Option<String>,
+    /// Only known to the indexer
+    pub downloads: Option<u64>,
 }
 
-/// Search for packages in the decentralized registry
-pub async fn handle_search(query: String) -> Result<()> {
-    println!("\n{}", "🔍 Searching Decentralized Registry".cyan().bold());
-    let spinner = create_spinner(&format!("Searching for '{}'...", query));
-    
+/// Search for packages in the decentralized registry.
+///
+/// Versions come straight from the search response; `verbose` additionally
+/// fetches each hit's full version list. With `on_chain`, or when the indexer
+/// is unreachable, the registry program's accounts are scanned instead, and
+/// `filters` are applied locally.
+pub async fn handle_search(query: String, limit: u32, page: u32, filters: SearchFilters, on_chain: bool, verbose: bool) -> Result<()> {
+    if limit == 0 || page == 0 {
+        return Err(AntSolError::Usage("--limit and --page must be at least 1".into()).into());
+    }
+    // Checked here so a typo is reported the same way by both sources
+    let version_req = match filters.has_version.as_deref() {
+        Some(req) => Some(semver::VersionReq::parse(req).map_err(|e| {
+            AntSolError::Usage(format!("--has-version '{}' is not a semver requirement: {}", req, e))
+        })?),
+        None => None,
+    };
+    if let Some(author) = &filters.author {
+        Pubkey::from_str(author)
+            .map_err(|_| AntSolError::Usage(format!("--author '{}' is not a base58 public key", author)))?;
+    }
+    let chain_filters = ChainFilters { author: filters.author.clone(), version_req, sort: filters.sort };
+    if !json_output() {
+        print_heading("🔍 Searching Decentralized Registry".cyan().bold());
+    }
     let config = Config::load()?;
-    let base = config.indexer_url.trim_end_matches('/');
-    // Use the dedicated search endpoint: /api/search?q=<query>
-    let list_url = format!("{}/api/search", base);
+    if on_chain {
+        return search_on_chain(&config, &query, limit, page, &chain_filters, verbose);
+    }
+    let spinner = create_spinner(&format!("Searching for '{}'...", query));
     
-    let client = reqwest::Client::new();
-    let list_resp = client
-        .get(&list_url)
-        .query(&[("q", query.clone())])
-        .send()
-        .await;
+    let indexer = IndexerClient::new(&config.indexer_url);
+    let offset = u64::from(page - 1) * u64::from(limit);
     
-    match list_resp {
-        Ok(resp) if resp.status().is_success() => {
-            let api: ApiResponse<Vec<PackageRow>> = resp.json().await?;
-            let rows = api.data;
-            if rows.is_empty() {
-                spinner.finish_and_clear();
-                print_warning(&format!("No packages found matching '{}'", query));
-                println!("\n{}", "💡 Tips:".yellow());
-                println!("  • Check your spelling");
-                println!("  • Try broader search terms");
-                println!("  • Use 'antsol info <package>' if you know the exact name");
-                return Ok(());
-            }
-            
-            // For each package, fetch details to get versions
-            let mut results: Vec<(PackageRow, Option<PackageDetails>)> = Vec::with_capacity(rows.len());
-            for row in rows {
-                let details_url = format!("{}/api/packages/{}", base, row.name);
-                let details_resp = client.get(&details_url).send().await;
-                let details: Option<PackageDetails> = match details_resp {
-                    Ok(r) if r.status().is_success() => {
-                        match r.json::<ApiResponse<PackageDetails>>().await {
-                            Ok(api_ok) => Some(api_ok.data),
-                            Err(_) => None,
-                        }
-                    }
-                    _ => None,
+    match indexer.search(&query, limit, offset, &filters).await {
+        Ok(found) => {
+            // Full version lists cost one request per hit, so only with --verbose
+            let mut results: Vec<(PackageRow, Option<PackageDetails>)> = Vec::with_capacity(found.rows.len());
+            for row in found.rows {
+                let details = if verbose {
+                    indexer.get_package(&row.name).await.ok().flatten()
+                } else {
+                    None
                 };
                 results.push((row, details));
             }
-            
             spinner.finish_and_clear();
-            println!("\n{} {}", "📦 Found".cyan().bold(), format!("{} packages", results.len()).green());
-            println!("{}", "─".repeat(80));
             
-            for (row, details_opt) in results {
-                let latest = details_opt
-                    .as_ref()
-                    .and_then(|d| d.versions.iter().map(|v| v.version.clone()).max())
-                    .unwrap_or_else(|| "unknown".to_string());
-                println!("\n{} {}", "📦".cyan(), row.name.green().bold());
-                println!("  Version: {}", latest.cyan());
-                let desc = row.description.unwrap_or_else(|| "No description".into());
-                println!("  Description: {}", desc);
-                if let Some(dls) = row.total_downloads { println!("  Downloads: {}", dls.to_string().yellow()); }
+            let output = SearchOutput {
+                has_more: match found.total {
+                    Some(total) => offset + (results.len() as u64) < total,
+                    None => results.len() as u32 == limit,
+                },
+                total: found.total,
+                packages: results.into_iter().map(|(row, details)| indexer_hit(row, details)).collect(),
+                query,
+                page,
+                limit,
+                source: "indexer",
+            };
+            if json_output() {
+                return emit_json(&output);
             }
-            
-            println!("\n{}", "─".repeat(80));
-            println!("\n{}", "💡 To install:".yellow());
-            println!("  {}", "antsol install <package-name>@<version>".cyan());
-            println!("\n{}", "💡 To view details:".yellow());
-            println!("  {}", "antsol info <package-name>".cyan());
+            print_indexer_results(&output, &filters);
+        }
+        Err(e) if crate::error::error_kind(e.as_ref()) == crate::error::ErrorKind::Usage => {
+            spinner.finish_and_clear();
+            return Err(e);
         }
-        _ => {
+        Err(e) => {
             spinner.finish_and_clear();
-            print_warning("⚠️  Indexer service not available");
-            println!("\n{}", "ℹ️  About the Indexer:".blue().bold());
-            println!("  The indexer reads package data from the Solana blockchain");
-            println!("  and provides fast search functionality.");
-            println!("\n{}", "💡 Alternative:".yellow());
-            println!("  • If you know the exact package name, use:");
-            println!("    {}", "antsol info <package-name>@<version>".cyan());
-            println!("  • This queries the blockchain directly");
+            print_warning(&format!("Indexer service not available ({}); searching on-chain instead", e));
+            search_on_chain(&config, &query, limit, page, &chain_filters, verbose)?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn indexer_hit(row: PackageRow, details: Option<PackageDetails>) -> SearchHit {
+    let (details_latest, versions) = match details {
+        Some(d) => (d.latest_version, Some(d.versions.into_iter().map(|v| v.version).collect())),
+        None => (None, None),
+    };
+    SearchHit {
+        name: row.name,
+        latest_version: row.latest_version.or(details_latest),
+        versions,
+        description: row.description,
+        author: row.author,
+        published_at: row.latest_published_at,
+        downloads: row.total_downloads,
+    }
+}
+
+/// `--author`/`--sort`/`--has-version` as typed, for the "More results" hint
+fn filter_flags(filters: &SearchFilters) -> String {
+    let mut flags = String::new();
+    if let Some(author) = &filters.author {
+        flags.push_str(&format!(" --author {}", author));
+    }
+    if let Some(sort) = filters.sort {
+        flags.push_str(&format!(" --sort {}", sort.as_str()));
+    }
+    if let Some(req) = &filters.has_version {
+        flags.push_str(&format!(" --has-version \"{}\"", req));
+    }
+    flags
+}
+
+/// `abcd…wxyz` for a base58 pubkey
+fn short_key(key: &str) -> String {
+    if key.chars().count() <= 12 {
+        return key.to_string();
+    }
+    let chars: Vec<char> = key.chars().collect();
+    format!("{}…{}", chars[..4].iter().collect::<String>(), chars[chars.len() - 4..].iter().collect::<String>())
+}
+
+/// Author and publish date lines shared by both result listings
+fn print_provenance(hit: &SearchHit) {
+    if let Some(author) = &hit.author {
+        println!("  Author: {}", short_key(author).dimmed());
+    }
+    let published = hit.published_at.as_deref().and_then(|at| DateTime::parse_from_rfc3339(at).ok());
+    if let Some(published) = published {
+        println!("  Published: {}", published.format("%Y-%m-%d"));
+    }
+}
+
+fn print_indexer_results(output: &SearchOutput, filters: &SearchFilters) {
+    if output.packages.is_empty() && output.page > 1 {
+        print_warning(&format!("No results on page {} for '{}'", output.page, output.query));
+        return;
+    }
+    if output.packages.is_empty() {
+        print_warning(&format!("No packages found matching '{}'", output.query));
+        print_tips("💡 Tips:".yellow(), [
+            "• Check your spelling",
+            "• Try broader search terms",
+            "• Use 'antsol info <package>' if you know the exact name",
+        ]);
+        return;
+    }
+    
+    let (found, page) = match output.total {
+        Some(total) => (total, format!("(page {} of {})", output.page, total.div_ceil(u64::from(output.limit)))),
+        None => (output.packages.len() as u64, format!("(page {})", output.page)),
+    };
+    println!("\n{} {} {}", "📦 Found".cyan().bold(), format!("{} packages", found).green(), page.dimmed());
+    println!("{}", "─".repeat(80));
+    
+    for hit in &output.packages {
+        println!("\n{} {}", "📦".cyan(), hit.name.green().bold());
+        println!("  Version: {}", hit.latest_version.as_deref().unwrap_or("unknown").cyan());
+        if let Some(versions) = &hit.versions {
+            println!("  All versions: {}", versions.join(", ").dimmed());
+        }
+        println!("  Description: {}", hit.description.as_deref().unwrap_or("No description"));
+        print_provenance(hit);
+        if let Some(dls) = hit.downloads { println!("  Downloads: {}", dls.to_string().yellow()); }
+    }
+    
+    println!("\n{}", "─".repeat(80));
+    if output.has_more {
+        println!("\n{} {}", "➡️  More results:".yellow(), format!("antsol search \"{}\"{} --page {}", output.query, filter_flags(filters), output.page + 1).cyan());
+    }
+    print_tips("💡 To install:".yellow(), ["antsol install <package-name>@<version>".cyan()]);
+    print_tips("💡 To view details:".yellow(), ["antsol info <package-name>".cyan()]);
+}
+
+/// Every version of one package that matched an on-chain search
+#[derive(Debug)]
+struct OnChainMatch {
+    latest: PackageAccount,
+    versions: Vec<String>,
+}
+
+/// Search filters, already validated, applied to scanned accounts
+#[derive(Debug, Default)]
+struct ChainFilters {
+    author: Option<String>,
+    version_req: Option<semver::VersionReq>,
+    sort: Option<SearchSort>,
+}
+
+impl ChainFilters {
+    /// The flags these came from, for the "More results" hint
+    fn flags(&self) -> String {
+        filter_flags(&SearchFilters {
+            author: self.author.clone(),
+            sort: self.sort,
+            has_version: self.version_req.as_ref().map(ToString::to_string),
+        })
+    }
+}
+
+/// Keep the matches of the wanted author with a version satisfying the
+/// requirement, then order them. Downloads aren't recorded on-chain, so that
+/// sort, like relevance, keeps name order.
+fn filter_matches(matches: Vec<OnChainMatch>, filters: &ChainFilters) -> Vec<OnChainMatch> {
+    let mut matches: Vec<OnChainMatch> = matches
+        .into_iter()
+        .filter(|m| filters.author.as_ref().is_none_or(|author| m.latest.authority.to_string() == *author))
+        .filter(|m| {
+            filters.version_req.as_ref().is_none_or(|req| {
+                m.versions.iter().filter_map(|v| semver::Version::parse(v).ok()).any(|v| req.matches(&v))
+            })
+        })
+        .collect();
+    if filters.sort == Some(SearchSort::Recent) {
+        matches.sort_by_key(|m| std::cmp::Reverse(m.latest.published_at));
+    }
+    matches
+}
+
+/// Scan the registry program for packages whose name or description contains
+/// `query`, and print one page of them
+fn search_on_chain(config: &Config, query: &str, limit: u32, page: u32, filters: &ChainFilters, verbose: bool) -> Result<()> {
+    print_warning("On-chain search reads every package account from the RPC node; it is slow and public endpoints may rate limit it");
+    let spinner = create_spinner(&format!("Scanning registry accounts for '{}'...", query));
+    let client = AntSolClient::new(config)?;
+    let scan = client.list_all_packages(MAX_SCANNED_ACCOUNTS);
+    spinner.finish_and_clear();
+    let scan = scan?;
+    if scan.total > MAX_SCANNED_ACCOUNTS {
+        print_warning(&format!(
+            "Only {} of {} package accounts were searched; results may be incomplete",
+            MAX_SCANNED_ACCOUNTS, scan.total
+        ));
+    }
+    
+    if filters.sort == Some(SearchSort::Downloads) {
+        print_warning("Download counts are only kept by the indexer; on-chain results are sorted by name");
+    }
+    let matches = filter_matches(match_packages(scan.packages, query), filters);
+    let start = (page as usize - 1) * limit as usize;
+    let hits: Vec<&OnChainMatch> = matches.iter().skip(start).take(limit as usize).collect();
+    if json_output() {
+        return emit_json(&SearchOutput {
+            query: query.to_string(),
+            page,
+            limit,
+            source: "chain",
+            has_more: start + hits.len() < matches.len(),
+            total: Some(matches.len() as u64),
+            packages: hits.iter().map(|hit| chain_hit(hit, verbose)).collect(),
+        });
+    }
+    if hits.is_empty() {
+        if page > 1 {
+            print_warning(&format!("No results on page {} for '{}'", page, query));
+        } else {
+            print_warning(&format!("No packages found on-chain matching '{}'", query));
         }
+        return Ok(());
     }
     
+    let pages = matches.len().div_ceil(limit as usize);
+    println!("\n{} {} {}", "📦 Found on-chain".cyan().bold(), format!("{} packages", matches.len()).green(), format!("(page {} of {})", page, pages).dimmed());
+    println!("{}", "─".repeat(80));
+    for hit in &hits {
+        println!("\n{} {}", "📦".cyan(), hit.latest.name.green().bold());
+        println!("  Version: {}", hit.latest.version.cyan());
+        if verbose {
+            println!("  All versions: {}", hit.versions.join(", ").dimmed());
+        }
+        println!("  Description: {}", hit.latest.description);
+        print_provenance(&chain_hit(hit, false));
+    }
+    println!("\n{}", "─".repeat(80));
+    if start + hits.len() < matches.len() {
+        println!("\n{} {}", "➡️  More results:".yellow(), format!("antsol search \"{}\"{} --on-chain --page {}", query, filters.flags(), page + 1).cyan());
+    }
+    print_tips("💡 To view details:".yellow(), ["antsol info <package-name>".cyan()]);
     Ok(())
 }
+
+fn chain_hit(hit: &OnChainMatch, verbose: bool) -> SearchHit {
+    SearchHit {
+        name: hit.latest.name.clone(),
+        latest_version: Some(hit.latest.version.clone()),
+        versions: verbose.then(|| hit.versions.clone()),
+        description: Some(hit.latest.description.clone()),
+        author: Some(hit.latest.authority.to_string()),
+        published_at: DateTime::<Utc>::from_timestamp(hit.latest.published_at, 0)
+            .map(|at| at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        downloads: None,
+    }
+}
+
+/// Group package accounts by name and keep the packages whose name or latest
+/// description contains `query` (case-insensitive), sorted by name
+fn match_packages(packages: Vec<PackageAccount>, query: &str) -> Vec<OnChainMatch> {
+    let query = query.to_lowercase();
+    let mut by_name: BTreeMap<String, Vec<PackageAccount>> = BTreeMap::new();
+    for package in packages {
+        by_name.entry(package.name.clone()).or_default().push(package);
+    }
+    
+    by_name
+        .into_values()
+        .filter_map(|mut versions| {
+            let latest = latest_version(versions.iter().map(|p| p.version.as_str()))?;
+            versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+            let all_versions: Vec<String> = versions.iter().map(|p| p.version.clone()).collect();
+            let latest = versions.into_iter().find(|p| p.version == latest)?;
+            let matches = latest.name.to_lowercase().contains(&query)
+                || latest.description.to_lowercase().contains(&query);
+            matches.then_some(OnChainMatch { latest, versions: all_versions })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana_client::tests::sample_package;
+
+    #[test]
+    fn on_chain_matches_group_versions_under_the_latest() {
+        let packages = vec![
+            sample_package("json-parser", "1.10.0", &[]),
+            sample_package("json-parser", "1.2.0", &[]),
+            sample_package("json-parser", "2.0.0-rc.1", &[]),
+            sample_package("math-utils", "0.1.0", &[]),
+            sample_package("yaml", "1.0.0", &[]),
+        ];
+
+        let matches = match_packages(packages.clone(), "JSON");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].latest.version, "1.10.0");
+        assert_eq!(matches[0].versions, ["1.2.0", "1.10.0", "2.0.0-rc.1"]);
+
+        // Descriptions match too; sample descriptions are "<name> test package"
+        let names: Vec<String> = match_packages(packages, "test").into_iter().map(|m| m.latest.name).collect();
+        assert_eq!(names, ["json-parser", "math-utils", "yaml"]);
+    }
+
+    #[test]
+    fn on_chain_filters_apply_author_version_and_recency() {
+        let author = Pubkey::new_unique();
+        let mut packages = vec![
+            sample_package("json-parser", "1.2.0", &[]),
+            sample_package("json-parser", "2.0.0", &[]),
+            sample_package("json-schema", "0.3.0", &[]),
+            sample_package("json-yaml", "1.4.1", &[]),
+        ];
+        for package in &mut packages[..3] {
+            package.authority = author;
+        }
+        packages[2].published_at = 1_800_000_000;
+        let names = |filters: &ChainFilters| -> Vec<String> {
+            filter_matches(match_packages(packages.clone(), "json"), filters).into_iter().map(|m| m.latest.name).collect()
+        };
+
+        let by_author = ChainFilters { author: Some(author.to_string()), ..ChainFilters::default() };
+        assert_eq!(names(&by_author), ["json-parser", "json-schema"]);
+        // Any version counts, not only the latest
+        let with_version = ChainFilters { version_req: Some("^1.2".parse().unwrap()), ..ChainFilters::default() };
+        assert_eq!(names(&with_version), ["json-parser", "json-yaml"]);
+        let recent = ChainFilters { sort: Some(SearchSort::Recent), ..ChainFilters::default() };
+        assert_eq!(names(&recent)[0], "json-schema");
+        assert_eq!(
+            ChainFilters { sort: Some(SearchSort::Name), ..with_version }.flags(),
+            " --sort name --has-version \"^1.2\""
+        );
+    }
+
+    #[test]
+    fn json_shape_is_stable() {
+        let mut packages = vec![sample_package("yaml", "1.0.0", &[]), sample_package("yaml", "1.1.0", &[])];
+        packages[1].authority = Pubkey::from_str("6sgbWr5XbZXbPWtue8uRcdCsQyXGGcAGayYRVyiXzR6V").unwrap();
+        let matches = match_packages(packages, "yaml");
+        let output = SearchOutput {
+            query: "yaml".to_string(),
+            page: 1,
+            limit: 20,
+            source: "chain",
+            has_more: false,
+            total: Some(1),
+            packages: vec![chain_hit(&matches[0], true)],
+        };
+        assert_eq!(serde_json::to_value(&output).unwrap(), serde_json::json!({
+            "query": "yaml",
+            "page": 1,
+            "limit": 20,
+            "source": "chain",
+            "packages": [{
+                "name": "yaml",
+                "latest_version": "1.1.0",
+                "versions": ["1.0.0", "1.1.0"],
+                "description": "yaml test package",
+                "author": "6sgbWr5XbZXbPWtue8uRcdCsQyXGGcAGayYRVyiXzR6V",
+                "published_at": "2023-11-14T22:13:20Z",
+                "downloads": null
+            }],
+            "has_more": false,
+            "total": 1
+        }));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::config::Config;
+use crate::config::{Config, IpfsBackend};
+use crate::credentials;
+use crate::indexer_client::IndexerClient;
+use crate::ipfs::{order_by_latency, IpfsClient};
 use crate::utils::*;
 use colored::*;
 use solana_sdk::signature::{Keypair, Signer};
@@ -9,14 +12,7 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
     print_info("🚀 Welcome to AntSol Setup - Let's configure your decentralized registry CLI!\n");
     
     // Load existing config or create default
-    let mut config = Config::load().unwrap_or_else(|_| Config {
-        rpc_url: "https://api.devnet.solana.com".to_string(),
-        ipfs_url: "https://api.pinata.cloud".to_string(),
-        program_id: "A9igkBugcujD9Nw9d97FFN4aY3qHXnJxEqCChJt8C42S".to_string(),
-        wallet_path: None,
-        pinata_jwt: None,
-        indexer_url: Config::default_indexer_url(),
-    });
+    let mut config = Config::load_file().unwrap_or_default();
     
     println!("{}", "═".repeat(50).cyan());
     println!("{}", "Step 1: Wallet Configuration".cyan().bold());
@@ -80,23 +76,98 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
     }
     
     println!("\n{}", "═".repeat(50).cyan());
-    println!("{}", "Step 2: IPFS Configuration (Pinata)".cyan().bold());
+    println!("{}", "Step 2: IPFS Configuration".cyan().bold());
     println!("{}", "═".repeat(50).cyan());
     
-    println!("\n{}", "ℹ️  Pinata JWT is required for publishing packages to IPFS.".yellow());
-    println!("   Get your free token at: {}", "https://app.pinata.cloud".blue().underline());
-    
-    print!("\nEnter your Pinata JWT token (or press Enter to skip): ");
-    std::io::stdout().flush()?;
-    let mut jwt = String::new();
-    std::io::stdin().read_line(&mut jwt)?;
-    let jwt = jwt.trim().to_string();
+    // Prefer a local Kubo node when one is running: no account or token needed
+    let kubo_url = if config.ipfs_backend() == IpfsBackend::Kubo {
+        config.ipfs_url.clone()
+    } else {
+        IpfsBackend::KUBO_DEFAULT_URL.to_string()
+    };
+    let mut use_kubo = false;
+    if let Some(version) = IpfsClient::kubo_version(&kubo_url).await {
+        println!("\n{} Local IPFS node (Kubo {}) detected at {}", "✓".green(), version, kubo_url.cyan());
+        print!("Publish through this node instead of a pinning service? (Y/n): ");
+        std::io::stdout().flush()?;
+        let mut kubo_choice = String::new();
+        std::io::stdin().read_line(&mut kubo_choice)?;
+        use_kubo = kubo_choice.trim().to_lowercase() != "n";
+    }
     
-    if !jwt.is_empty() {
-        config.pinata_jwt = Some(jwt);
-        print_success("✓ Pinata JWT token saved");
+    if use_kubo {
+        config.ipfs_url = kubo_url;
+        config.ipfs_backend = Some(IpfsBackend::Kubo);
+        print_success(&format!("✓ Packages will be added and pinned on {}", config.ipfs_url));
     } else {
-        print_info("Skipped Pinata JWT. You can add it later by editing: ~/.antsol/config.toml");
+        println!("\nWhere should packages be pinned?");
+        println!("  1. Pinata (default) - {}", "https://app.pinata.cloud".cyan());
+        println!("  2. web3.storage - {}", "https://web3.storage".cyan());
+        println!("  3. Filebase - {}", "https://filebase.com".cyan());
+        println!("  Or run a local node ({}) and re-run setup.", "ipfs daemon".cyan());
+        print!("\nChoice (1-3): ");
+        std::io::stdout().flush()?;
+        let mut provider_choice = String::new();
+        std::io::stdin().read_line(&mut provider_choice)?;
+        let backend = match provider_choice.trim() {
+            "2" => IpfsBackend::Web3Storage,
+            "3" => IpfsBackend::Filebase,
+            _ => IpfsBackend::Pinata,
+        };
+        if config.ipfs_backend() != backend {
+            config.ipfs_url = backend.default_url().to_string();
+        }
+        config.ipfs_backend = Some(backend);
+        
+        if backend == IpfsBackend::Pinata {
+            println!("\n{}", "ℹ️  Pinata JWT is required for publishing packages to IPFS.".yellow());
+            println!("   Get your free token at: {}", "https://app.pinata.cloud".blue().underline());
+            
+            print!("\nEnter your Pinata JWT token (or press Enter to skip): ");
+            std::io::stdout().flush()?;
+            let mut jwt = String::new();
+            std::io::stdin().read_line(&mut jwt)?;
+            let jwt = jwt.trim().to_string();
+            
+            if !jwt.is_empty() {
+                let keyring = credentials::keyring();
+                config.store_pinata_jwt(keyring.as_ref(), Some(jwt))?;
+                if keyring.is_some() {
+                    print_success("✓ Pinata JWT token saved to the OS keyring");
+                } else {
+                    print_success("✓ Pinata JWT token saved");
+                }
+            } else {
+                print_info("Skipped Pinata JWT. You can add it later with: antsol config set pinata_jwt <token>");
+            }
+        } else {
+            let (provider, where_to_find) = match backend {
+                IpfsBackend::Filebase => ("Filebase", "the bucket's IPFS RPC API key in the Filebase console"),
+                _ => ("web3.storage", "an API token from your web3.storage account"),
+            };
+            println!("\n{}", format!("ℹ️  {} needs {}.", provider, where_to_find).yellow());
+            
+            print!("\nEnter your {} token (or press Enter to skip): ", provider);
+            std::io::stdout().flush()?;
+            let mut token = String::new();
+            std::io::stdin().read_line(&mut token)?;
+            let token = token.trim().to_string();
+            
+            if !token.is_empty() {
+                config.pinning_token = Some(token);
+                print_success(&format!("✓ {} token saved", provider));
+            } else {
+                print_info("Skipped the token. You can add it later with: antsol config set pinning_token <token>");
+            }
+        }
+    }
+    
+    print!("\nTest the download gateways' latency and try the fastest first? (y/N): ");
+    std::io::stdout().flush()?;
+    let mut gateway_choice = String::new();
+    std::io::stdin().read_line(&mut gateway_choice)?;
+    if gateway_choice.trim().to_lowercase() == "y" {
+        order_gateways_by_latency(&mut config).await?;
     }
     
     println!("\n{}", "═".repeat(50).cyan());
@@ -153,8 +224,7 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
         std::io::stdin().read_line(&mut new_idx)?;
         let url = new_idx.trim().to_string();
         if !url.is_empty() {
-            // quick health check hint
-            print_info("Tip: Indexer health endpoint should be /health returning { success: true }");
+            print_indexer_health(&url).await;
             config.indexer_url = url;
             print_success(&format!("✓ Indexer URL set to: {}", config.indexer_url));
         }
@@ -169,7 +239,7 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
     
     println!("\n{}", "Configuration Summary:".cyan().bold());
     println!("  RPC URL: {}", config.rpc_url.cyan());
-    println!("  IPFS Gateway: {}", config.ipfs_url.cyan());
+    println!("  IPFS API: {} ({})", config.ipfs_url.cyan(), config.ipfs_backend().as_str());
     println!("  Program ID: {}", config.program_id.cyan());
     println!("  Indexer URL: {}", config.indexer_url.cyan());
     
@@ -179,20 +249,34 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
         println!("  Wallet: {} {}", "✗".red(), "Not connected".yellow());
     }
     
-    if config.pinata_jwt.is_some() {
+    let needs_jwt = config.ipfs_backend() == IpfsBackend::Pinata;
+    let has_jwt = config.pinata_jwt.is_some() || config.pinata_jwt_in_keyring;
+    if has_jwt {
         println!("  Pinata JWT: {} {}", "✓".green(), "Configured".cyan());
-    } else {
+    } else if needs_jwt {
         println!("  Pinata JWT: {} {}", "✗".red(), "Not configured".yellow());
     }
     
+    let needs_token = config.ipfs_backend().uses_pinning_token();
+    if needs_token {
+        match config.pinning_token {
+            Some(_) => println!("  Pinning token: {} {}", "✓".green(), "Configured".cyan()),
+            None => println!("  Pinning token: {} {}", "✗".red(), "Not configured".yellow()),
+        }
+    }
+    
     println!("\n{}", "Next Steps:".cyan().bold());
     
     if config.wallet_path.is_none() {
         println!("  • Connect wallet: {}", "antsol wallet connect <keypair.json>".yellow());
     }
     
-    if config.pinata_jwt.is_none() {
-        println!("  • Add Pinata JWT to: {}", "~/.antsol/config.toml".yellow());
+    if needs_jwt && !has_jwt {
+        println!("  • Add Pinata JWT: {}", "antsol config set pinata_jwt <token>".yellow());
+    }
+    
+    if needs_token && config.pinning_token.is_none() {
+        println!("  • Add a pinning token: {}", "antsol config set pinning_token <token>".yellow());
     }
     
     if config.wallet_path.is_some() {
@@ -207,3 +291,49 @@ pub async fn handle_setup() -> Result<(), Box<dyn std::error::Error>> {
     
     Ok(())
 }
+
+/// Probe each configured gateway, show how long it took, and store the
+/// gateways fastest first. Unreachable ones keep their place at the end.
+async fn order_gateways_by_latency(config: &mut Config) -> Result<(), Box<dyn std::error::Error>> {
+    let probes = IpfsClient::from_config(config).probe_gateways().await?;
+    for (gateway, result) in &probes {
+        match result {
+            Ok(latency) => println!("  {} {} ({} ms)", "✓".green(), gateway, latency.as_millis()),
+            Err(reason) => println!("  {} {}: {}", "✗".red(), gateway, reason),
+        }
+    }
+    if probes.iter().all(|(_, result)| result.is_err()) {
+        print_warning("No gateway answered; keeping the configured order");
+        return Ok(());
+    }
+    let ordered = order_by_latency(&probes);
+    if ordered == config.ipfs_gateways {
+        print_success("✓ Gateways are already fastest first");
+    } else {
+        config.ipfs_gateways = ordered;
+        print_success(&format!("✓ Gateway order: {}", config.ipfs_gateways.join(", ")));
+    }
+    Ok(())
+}
+
+/// Quick check of a newly entered indexer; a failure is only a warning, since
+/// the indexer may simply not be deployed yet
+async fn print_indexer_health(url: &str) {
+    match IndexerClient::new(url).health().await {
+        Ok(report) => {
+            let lag = match report.indexer.lag_slots {
+                Some(lag) => format!("{} slots behind the chain", lag),
+                None => "lag unknown".to_string(),
+            };
+            if report.status == "ok" {
+                print_success(&format!("✓ Indexer is healthy ({})", lag));
+            } else {
+                print_warning(&format!("Indexer reports {} ({})", report.status, lag));
+                if let Some(error) = report.indexer.last_error {
+                    print_info(&format!("Last indexer error: {}", error));
+                }
+            }
+        }
+        Err(e) => print_warning(&format!("Could not check indexer health: {}", e)),
+    }
+}
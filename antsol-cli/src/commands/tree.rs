@@ -0,0 +1,44 @@
+use crate::commands::install::{resolve_latest_version, resolve_range};
+use crate::config::Config;
+use crate::resolver::{self, VersionSpec};
+use crate::solana_client::AntSolClient;
+use crate::types::Result;
+use crate::utils::*;
+use colored::*;
+
+/// Print the dependency tree of a package.
+///
+/// A missing version or a range is resolved through the indexer, exactly as
+/// `install` does; the dependency lists themselves come from the chain (the
+/// indexer doesn't store them) via the same resolver, so the tree always
+/// matches what `install` would fetch. Cycles and version conflicts fail.
+pub async fn handle_tree(package_spec: String, depth: Option<usize>, json: bool) -> Result<()> {
+    let (name, version) = parse_package_spec(&package_spec);
+    let config = Config::load()?;
+    let version = match version {
+        Some(spec) => match VersionSpec::parse(&spec)? {
+            VersionSpec::Exact(version) => version,
+            range => resolve_range(&config.indexer_url, &name, &spec, &range).await?,
+        },
+        None => resolve_latest_version(&config.indexer_url, &name).await?,
+    };
+    
+    let solana_client = AntSolClient::new(&config)?;
+    let spinner = create_spinner(&format!("🔍 Resolving dependency graph of {}@{}...", name, version));
+    let resolution = resolver::resolve(&solana_client, &name, &version).await;
+    spinner.finish_and_clear();
+    let resolution = resolution?;
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&resolution.graph())?);
+        return Ok(());
+    }
+    
+    println!();
+    for line in resolution.render_tree_to_depth(depth) {
+        println!("{}", line);
+    }
+    let count = resolution.packages.len() - 1;
+    println!("\n{}", format!("{} transitive dependenc{}; (*) marks a subtree shown above", count, if count == 1 { "y" } else { "ies" }).dimmed());
+    Ok(())
+}
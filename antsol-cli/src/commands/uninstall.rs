@@ -0,0 +1,150 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::types::{AntSolManifest, LockFile, Result};
+use crate::utils::*;
+use colored::*;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Remove an installed package from the install directory (`antsol_packages/`
+/// by default) and `antsol.lock`.
+///
+/// Refuses while other installed packages depend on it unless `force`.
+/// With `dry_run` only the removal plan is printed.
+pub async fn handle_uninstall(package_spec: String, force: bool, dry_run: bool) -> Result<()> {
+    let (name, version) = parse_package_spec(&package_spec);
+    
+    print_heading("🗑️  Uninstalling Package".cyan().bold());
+    
+    let packages_dir = &Config::load()?.install_dir;
+    let package_dir = packages_dir.join(&name);
+    let lockfile_path = Path::new(LOCKFILE_NAME);
+    let mut lockfile = LockFile::load(lockfile_path)?;
+    let locked = lockfile.as_ref().and_then(|lock| lock.get(&name)).cloned();
+    
+    if let (Some(requested), Some(locked)) = (&version, &locked) {
+        if *requested != locked.version {
+            return Err(AntSolError::Usage(format!(
+                "{} is installed at {}, not {}",
+                name, locked.version, requested
+            )).into());
+        }
+    }
+    
+    let dir_exists = package_dir.exists();
+    if !dir_exists && locked.is_none() {
+        return Err(AntSolError::NotFound(format!("{} is not installed", name)).into());
+    }
+    
+    let dependents = installed_dependents(lockfile.as_ref(), packages_dir, &name)?;
+    if !dependents.is_empty() {
+        let list = dependents.iter().cloned().collect::<Vec<_>>().join(", ");
+        if !force {
+            return Err(AntSolError::Usage(format!(
+                "Cannot uninstall {}: still required by {}. Pass --force to remove it anyway.",
+                name, list
+            )).into());
+        }
+        print_warning(&format!("Removing {} although {} still depend on it", name, list));
+    }
+    
+    let id = match &locked {
+        Some(locked) => format!("{}@{}", locked.name, locked.version),
+        None => name.clone(),
+    };
+    
+    if dry_run {
+        println!("\n{}", "Would remove:".yellow().bold());
+        if dir_exists {
+            println!("  directory {}", package_dir.display());
+        }
+        if locked.is_some() {
+            println!("  {} entry {}", LOCKFILE_NAME, id);
+        }
+        println!("\n{}", "No changes made (--dry-run).".dimmed());
+        return Ok(());
+    }
+    
+    if dir_exists {
+        std::fs::remove_dir_all(&package_dir)?;
+        print_success(&format!("Removed {}", package_dir.display()));
+    } else {
+        print_info(&format!("{} was already deleted; cleaning up {}", package_dir.display(), LOCKFILE_NAME));
+    }
+    
+    if let (Some(lock), Some(_)) = (lockfile.as_mut(), &locked) {
+        lock.remove(&name);
+        lock.save(lockfile_path)?;
+        print_success(&format!("Removed {} from {}", id, LOCKFILE_NAME));
+    }
+    
+    println!("\n{} {}", "✨ Uninstalled".green().bold(), id.green());
+    Ok(())
+}
+
+/// Installed packages that directly depend on `name`, from the lockfile
+/// and from the manifests of packages present in `packages_dir`
+fn installed_dependents(lockfile: Option<&LockFile>, packages_dir: &Path, name: &str) -> Result<BTreeSet<String>> {
+    let mut dependents: BTreeSet<String> = lockfile
+        .map(|lock| lock.dependents(name).into_iter().map(|p| p.name.clone()).collect())
+        .unwrap_or_default();
+    
+    if packages_dir.is_dir() {
+        for entry in std::fs::read_dir(packages_dir)? {
+            let entry = entry?;
+            let manifest_path = entry.path().join("antsol.toml");
+            let Ok(content) = std::fs::read_to_string(&manifest_path) else {
+                continue;
+            };
+            let Ok(manifest) = toml::from_str::<AntSolManifest>(&content) else {
+                continue;
+            };
+            let depends = manifest.dependencies.unwrap_or_default().iter().any(|d| d.name == name);
+            if depends && manifest.package.name != name {
+                dependents.insert(manifest.package.name);
+            }
+        }
+    }
+    Ok(dependents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::LockedPackage;
+
+    #[test]
+    fn dependents_come_from_lockfile_and_installed_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let packages_dir = dir.path().join("antsol_packages");
+        std::fs::create_dir_all(packages_dir.join("tool")).unwrap();
+        std::fs::write(packages_dir.join("tool/antsol.toml"), r#"
+[package]
+name = "tool"
+version = "0.1.0"
+description = "uses lib-a"
+
+[[dependencies]]
+name = "lib-a"
+version = "1.0.0"
+"#).unwrap();
+
+        let mut lock = LockFile::new();
+        lock.upsert(LockedPackage {
+            name: "app".to_string(),
+            version: "1.0.0".to_string(),
+            ipfs_cid: format!("Qm{}", "a".repeat(44)),
+            sha256: "ab".repeat(32),
+            files_sha256: None,
+            authority: None,
+            dependencies: vec!["lib-a@1.0.0".to_string()],
+            requirement: None,
+            from_cache: false,
+        });
+
+        let dependents = installed_dependents(Some(&lock), &packages_dir, "lib-a").unwrap();
+        assert_eq!(dependents.into_iter().collect::<Vec<_>>(), vec!["app", "tool"]);
+        assert!(installed_dependents(None, &packages_dir, "tool").unwrap().is_empty());
+    }
+}
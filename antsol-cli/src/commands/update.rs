@@ -1,74 +1,135 @@
+use crate::commands::publish::{await_visible, confirm_package_contents, estimated_space, pin_dependencies, ensure_valid_external_dependencies, report_upload, write_back, PublishOutput};
 use crate::config::Config;
+use crate::error::AntSolError;
 use crate::ipfs::IpfsClient;
-use crate::solana_client::AntSolClient;
+use crate::manifest::{bump_version, Bump};
+use crate::publish_state::PublishState;
+use crate::signer::load_signer;
+use crate::solana_client::{AntSolClient, PublishRequest};
+use crate::tx::SendOptions;
 use crate::types::{AntSolManifest, Result};
 use crate::utils::*;
 use colored::*;
-use solana_sdk::signature::Keypair;
 use std::path::PathBuf;
 
-pub async fn handle_update(path: PathBuf, new_version: String) -> Result<()> {
-    if !validate_version(&new_version) {
-        return Err("Invalid version format. Use semantic versioning (e.g., 1.0.1)".into());
+/// Publish a new version of the package at `path`, given explicitly or bumped
+/// from the manifest's version
+pub async fn handle_update(
+    path: PathBuf,
+    new_version: Option<String>,
+    bump: Option<Bump>,
+    fresh: bool,
+    max_size: Option<u64>,
+    send: SendOptions,
+) -> Result<()> {
+    if new_version.as_deref().is_some_and(|version| !validate_version(version)) {
+        return Err(AntSolError::Usage("Invalid version format. Use semantic versioning (e.g., 1.0.1)".into()).into());
     }
     
     let manifest_path = path.join("antsol.toml");
     if !manifest_path.exists() {
-        return Err("No antsol.toml found.".into());
+        return Err(AntSolError::ManifestNotFound(path).into());
     }
     
     let manifest_content = std::fs::read_to_string(&manifest_path)?;
     let manifest: AntSolManifest = toml::from_str(&manifest_content)?;
     let old_version = manifest.package.version.clone();
     
+    let new_version = match (new_version, bump) {
+        (Some(version), _) => version,
+        (None, Some(bump)) => bump_version(&old_version, bump)?,
+        (None, None) => return Err(AntSolError::Usage("Pass --version or --bump".into()).into()),
+    };
+    
     print_info(&format!("Updating {} from {} to {}", manifest.package.name.cyan(), old_version.yellow(), new_version.green()));
     
     // Load wallet and config
-    let config = Config::load()?;
-    let wallet_path = config.wallet_path.as_ref().ok_or("No wallet connected")?;
-    let keypair_bytes = std::fs::read(&wallet_path)?;
-    let keypair_vec: Vec<u8> = serde_json::from_slice(&keypair_bytes)?;
-    let keypair = Keypair::from_bytes(&keypair_vec)?;
+    let mut config = Config::load()?;
+    send.apply(&mut config);
+    if let Some(max_size) = max_size {
+        config.max_package_size = max_size;
+    }
+    let signer = load_signer(&config)?;
+    let solana_client = AntSolClient::new(&config)?;
+    let dependencies = pin_dependencies(&config.indexer_url, manifest.dependencies.as_deref().unwrap_or_default()).await?;
+    solana_client.ensure_publish_funds(&signer.pubkey(), estimated_space(&path, &manifest, &new_version, &dependencies))?;
+    
+    // The program accepts the authority or a listed maintainer; check before uploading
+    let existing = solana_client.require_package(&manifest.package.name, &old_version)?;
+    if existing.frozen {
+        print_warning(&format!("{}@{} is frozen 🔒; the program rejects updates to it", manifest.package.name, old_version));
+        return Err(AntSolError::Usage("Nothing was uploaded or sent: a frozen version can't be updated".into()).into());
+    }
+    // A maintainer signs from the list kept by the authority of old_version
+    let maintainer_of = Some(existing.authority).filter(|authority| *authority != signer.pubkey());
+    if let Some(authority) = maintainer_of {
+        if !solana_client.get_maintainers(&manifest.package.name, &authority)?.contains(&signer.pubkey()) {
+            return Err(AntSolError::Wallet(format!(
+                "Wallet {} is neither the authority of {} ({}) nor one of its maintainers",
+                signer.pubkey(),
+                manifest.package.name,
+                existing.authority
+            )).into());
+        }
+        print_info(&format!("Publishing as a maintainer of {}", manifest.package.name));
+    }
+    
+    ensure_valid_external_dependencies(&manifest)?;
+    confirm_package_contents(&path, true)?;
     
     let spinner = create_spinner("Uploading updated package to IPFS...");
     
-    // Create IPFS client with JWT from config or environment
-    let ipfs_client = if let Some(jwt) = config.pinata_jwt.clone() {
-        IpfsClient::with_jwt(config.ipfs_url.clone(), jwt)
-    } else {
-        IpfsClient::new(config.ipfs_url.clone())
-    };
+    // Create IPFS client for the configured pinning provider and its credential
+    let ipfs_client = IpfsClient::from_config(&config);
     
-    let new_cid = ipfs_client.upload_package(&path).await?;
+    let uploaded = ipfs_client.upload_package(&path, fresh).await?;
     spinner.finish_and_clear();
-    print_success(&format!("New IPFS CID: {}", new_cid.green()));
+    report_upload(&uploaded);
+    let new_cid = uploaded.cid;
     
     let spinner = create_spinner("Updating package on Solana...");
-    let solana_client = AntSolClient::new(&config)?;
     
-    let signature = solana_client.update_package(
-        &keypair,
-        manifest.package.name.clone(),
-        old_version.clone(),
-        new_version.clone(),
-        new_cid.clone(),
-        manifest.package.description.clone(),
-        manifest.dependencies.unwrap_or_default(),
-        manifest.external_dependencies.unwrap_or_default(),
-    ).await?;
+    let request = PublishRequest {
+        name: manifest.package.name.clone(),
+        version: new_version.clone(),
+        ipfs_cid: new_cid.clone(),
+        description: manifest.package.description.clone(),
+        dependencies,
+        content_sha256: uploaded.sha256.clone(),
+        metadata: manifest.package.metadata(),
+        readme_cid: uploaded.readme_cid.clone(),
+        external_dependencies: manifest.external_dependencies.clone().unwrap_or_default(),
+    };
+    let signature = solana_client.update_package(signer.as_ref(), &old_version, request, maintainer_of).await?;
     
     spinner.finish_and_clear();
+    await_visible(&solana_client, &manifest.package.name, &new_version).await?;
     
     print_success(&format!("Updated {}@{}", manifest.package.name.green().bold(), new_version.green()));
+    PublishState::clear(&path);
+    write_back(&manifest_path, &new_version, &new_cid);
+    if json_output() {
+        return emit_json(&PublishOutput {
+            name: manifest.package.name,
+            version: new_version,
+            previous_version: Some(old_version),
+            ipfs_cid: new_cid,
+            content_sha256: uploaded.sha256,
+            readme_cid: uploaded.readme_cid,
+            signature,
+        });
+    }
     
     println!("\n{}", "Update Details".cyan().bold());
     println!("  Previous: {}", old_version.yellow());
     println!("  Current: {}", new_version.green());
     println!("  New IPFS CID: {}", new_cid.cyan());
+    println!("  Content sha256: {}", uploaded.sha256.cyan());
+    if let Some(readme_cid) = &uploaded.readme_cid {
+        println!("  README CID: {}", readme_cid.cyan());
+    }
     println!("  Transaction: {}", signature.cyan());
-    println!("  Explorer: {}", format!("https://explorer.solana.com/tx/{}?cluster=devnet", signature).blue());
-    
-    print_info("\nDon't forget to update antsol.toml with the new version!");
+    println!("  Explorer: {}", explorer_url(&config.rpc_url, &format!("tx/{}", signature)).blue());
     
     Ok(())
 }
@@ -0,0 +1,60 @@
+use crate::commands::install::{handle_install, resolve_range};
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::resolver::VersionSpec;
+use crate::types::{AntSolManifest, LockFile, Result};
+use crate::utils::*;
+use colored::*;
+use std::path::{Path, PathBuf};
+
+/// Re-resolve every dependency range in `antsol.toml` against the indexer and
+/// install the ones whose best match moved. Exact versions are left alone.
+pub async fn handle_update_deps(dry_run: bool) -> Result<()> {
+    let manifest_path = Path::new("antsol.toml");
+    if !manifest_path.exists() {
+        return Err(AntSolError::ManifestNotFound(PathBuf::from(".")).into());
+    }
+    let manifest: AntSolManifest = toml::from_str(&std::fs::read_to_string(manifest_path)?)?;
+    let dependencies = manifest.dependencies.unwrap_or_default();
+    
+    print_heading("🔄 Updating dependency ranges".cyan().bold());
+    
+    let config = Config::load()?;
+    let lockfile = LockFile::load(Path::new(LOCKFILE_NAME))?.unwrap_or_else(LockFile::new);
+    
+    let mut outdated = Vec::new();
+    for dep in &dependencies {
+        let range = match VersionSpec::parse(&dep.version)? {
+            VersionSpec::Exact(_) => {
+                println!("  {} {} (pinned)", dep.name, dep.version.dimmed());
+                continue;
+            }
+            range => range,
+        };
+        let best = resolve_range(&config.indexer_url, &dep.name, &dep.version, &range).await?;
+        match lockfile.get(&dep.name).map(|l| l.version.as_str()) {
+            Some(current) if current == best => {
+                println!("  {} {} up to date at {}", dep.name, dep.version.cyan(), best.green());
+            }
+            current => {
+                println!("  {} {} {} -> {}", dep.name, dep.version.cyan(), current.unwrap_or("(not installed)").yellow(), best.green());
+                outdated.push(format!("{}@{}", dep.name, dep.version));
+            }
+        }
+    }
+    
+    if outdated.is_empty() {
+        print_success("All dependency ranges are up to date");
+        return Ok(());
+    }
+    if dry_run {
+        println!("\n{}", "No changes made (--dry-run).".dimmed());
+        return Ok(());
+    }
+    
+    for spec in outdated {
+        handle_install(Some(spec), false, false, false, false, false, false, false, None, false).await?;
+    }
+    Ok(())
+}
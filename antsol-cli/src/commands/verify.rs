@@ -0,0 +1,148 @@
+use crate::audit::{self, AuditReport};
+use crate::config::Config;
+use crate::commands::install::resolve_latest_version;
+use crate::error::AntSolError;
+use crate::ipfs::IpfsClient;
+use crate::lockfile::LOCKFILE_NAME;
+use crate::solana_client::AntSolClient;
+use crate::types::{LockFile, PackageAccount, Result};
+use crate::utils::*;
+use crate::verify::{self, PackageVerifier, VerifyReport};
+use colored::*;
+use indicatif::{MultiProgress, ProgressDrawTarget};
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Verifies through IPFS, showing one spinner per in-flight package
+struct ProgressVerifier {
+    ipfs: IpfsClient,
+    progress: MultiProgress,
+}
+
+impl PackageVerifier for ProgressVerifier {
+    async fn verify(&self, package: &PackageAccount) -> Result<String> {
+        let id = format!("{}@{}", package.name, package.version);
+        let spinner = self.progress.add(create_spinner(&format!("🔍 Verifying {}...", id)));
+        let result = self.ipfs.verify(package).await;
+        match &result {
+            Ok(_) => spinner.finish_with_message(format!("{} {}", "✓".green(), id)),
+            Err(_) => spinner.finish_with_message(format!("{} {}", "✗".red(), id)),
+        }
+        result
+    }
+}
+
+/// Check a package (and with `recursive`, its whole dependency graph)
+/// against the CIDs recorded on-chain. Without a package, see [`handle_audit`].
+pub async fn handle_verify(package_spec: String, recursive: bool, jobs: usize, json: bool) -> Result<()> {
+    let (name, version) = parse_package_spec(&package_spec);
+    let config = Config::load()?;
+    let version = match version {
+        Some(version) => version,
+        None => resolve_latest_version(&config.indexer_url, &name).await?,
+    };
+    
+    if !json {
+        print_heading("🛡️  Verifying Package Content".cyan().bold());
+        print_info(&format!("Package: {}@{}{}", name.cyan(), version.cyan(), if recursive { " (with dependencies)" } else { "" }));
+    }
+    
+    let solana_client = AntSolClient::new(&config)?;
+    let graph = verify::walk(&solana_client, &name, &version, recursive).await;
+    
+    let progress = MultiProgress::new();
+    if json {
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    let verifier = Rc::new(ProgressVerifier { ipfs: IpfsClient::from_config(&config), progress });
+    let results = verify::verify_all(verifier, graph.fetched(), jobs).await;
+    let report = VerifyReport::build(&graph, &results);
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        let mut out = PagedOutput::new();
+        writeln!(out, "\n{}", "Verification Report".cyan().bold())?;
+        for line in report.render() {
+            let line = if line.contains('✗') { line.red().to_string() } else { line };
+            writeln!(out, "  {}", line)?;
+        }
+        out.finish()?;
+    }
+    
+    if !report.ok {
+        return Err(AntSolError::Verification(format!(
+            "{} of {} package(s) failed verification",
+            report.failed,
+            report.passed + report.failed
+        )).into());
+    }
+    if !json {
+        print_success(&format!("All {} package(s) verified", report.passed));
+    }
+    Ok(())
+}
+
+/// Audit the current project: every `antsol.lock` entry against its installed
+/// files and its on-chain account, and with `deep` against a fresh IPFS download
+pub async fn handle_audit(deep: bool, json: bool) -> Result<()> {
+    let lockfile = LockFile::load(Path::new(LOCKFILE_NAME))?
+        .ok_or_else(|| AntSolError::Config(format!(
+            "No {} found in the current directory. Run 'antsol install <package>' first.",
+            LOCKFILE_NAME
+        )))?;
+    let config = Config::load()?;
+    let solana_client = AntSolClient::new(&config)?;
+    let ipfs = IpfsClient::from_config(&config);
+    
+    if !json {
+        print_heading("🛡️  Auditing Installed Packages".cyan().bold());
+        print_info(&format!(
+            "{} package(s) from {}{}",
+            lockfile.packages.len(),
+            LOCKFILE_NAME,
+            if deep { ", re-downloading each from IPFS" } else { "" }
+        ));
+    }
+    let spinner = (!json).then(|| create_spinner("🔍 Checking disk, chain and content..."));
+    let report = audit::audit(&lockfile, &config.install_dir, &solana_client, deep.then_some(&ipfs)).await;
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+    
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_audit(&report)?;
+    }
+    
+    if !report.ok {
+        return Err(AntSolError::Verification(format!(
+            "{} of {} package(s) failed the audit",
+            report.failed,
+            report.packages.len()
+        )).into());
+    }
+    if !json {
+        print_success(&format!("All {} package(s) match {}", report.packages.len(), LOCKFILE_NAME));
+    }
+    Ok(())
+}
+
+fn print_audit(report: &AuditReport) -> Result<()> {
+    let mut out = PagedOutput::new();
+    writeln!(out, "\n{}", "Audit Report".cyan().bold())?;
+    for (index, line) in report.render().into_iter().enumerate() {
+        let line = if index == 0 {
+            line.bold().to_string()
+        } else if line.contains("MISMATCH") || line.contains("MISSING") || line.contains("ERROR") || line.contains('✗') {
+            line.red().to_string()
+        } else {
+            line
+        };
+        writeln!(out, "  {}", line)?;
+    }
+    out.finish()?;
+    Ok(())
+}
@@ -1,27 +1,59 @@
 use crate::config::Config;
+use crate::error::AntSolError;
+use crate::permissions;
+use crate::signer::{cache_keypair, prompt_seed_phrase, wallet_pubkey, wallet_signer, Wallet};
+use crate::solana_client::AntSolClient;
 use crate::types::Result;
 use crate::utils::*;
+use crate::validation::PACKAGE_MAX_SPACE;
 use colored::*;
+use serde::Serialize;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
 use std::path::PathBuf;
+use std::time::Duration;
 
-pub async fn handle_connect(keypair_path: PathBuf) -> Result<()> {
-    let spinner = create_spinner("Connecting wallet to decentralized registry...");
+pub async fn handle_connect(wallet_path: PathBuf, cache: bool) -> Result<()> {
+    let wallet = Wallet::parse(&wallet_path)?;
+    let (wallet_path, address) = match &wallet {
+        Wallet::SeedPhrase(derivation_path) if cache => {
+            let dir = Config::config_dir()?;
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join("wallet.enc.json");
+            if path.exists() {
+                return Err(AntSolError::Wallet(format!(
+                    "{} already exists. Remove it to cache a different seed phrase",
+                    path.display()
+                )).into());
+            }
+            let keypair = prompt_seed_phrase(derivation_path)?;
+            cache_keypair(&keypair, &path)?;
+            (path, keypair.pubkey())
+        }
+        _ if cache => {
+            return Err(AntSolError::Usage("--cache only applies to seed phrase wallets (prompt://)".into()).into());
+        }
+        Wallet::File(_) => {
+            let spinner = create_spinner("Connecting wallet to decentralized registry...");
+            let signer = wallet_signer(&wallet_path);
+            spinner.finish_and_clear();
+            (wallet_path, signer?.pubkey())
+        }
+        _ => {
+            let address = wallet_signer(&wallet_path)?.pubkey();
+            (wallet_path, address)
+        }
+    };
     
-    let keypair_bytes = std::fs::read(&keypair_path)?;
-    let keypair_vec: Vec<u8> = serde_json::from_slice(&keypair_bytes)?;
-    let keypair = Keypair::from_bytes(&keypair_vec)?;
-    
-    let mut config = Config::load()?;
-    config.wallet_path = Some(keypair_path.clone());
+    let mut config = Config::load_file()?;
+    config.wallet_path = Some(wallet_path.clone());
     config.save()?;
     
-    spinner.finish_and_clear();
-    
-    print_success(&format!("Wallet connected: {}", keypair.pubkey().to_string().cyan()));
+    print_success(&format!("Wallet connected: {}", address.to_string().cyan()));
     println!("\n{}", "🔐 Wallet Details:".cyan().bold());
-    println!("  Public Key: {}", keypair.pubkey().to_string().green());
-    println!("  Keypair Path: {}", keypair_path.display());
+    println!("  Public Key: {}", address.to_string().green());
+    println!("  Wallet: {}", Wallet::parse(&wallet_path)?);
     println!("\n{}", "⚡ This wallet will be used for:".yellow());
     println!("  • Signing package publish transactions");
     println!("  • Proving package ownership on-chain");
@@ -30,17 +62,51 @@ pub async fn handle_connect(keypair_path: PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// Result of `antsol wallet show --output json`
+#[derive(Debug, Serialize)]
+pub struct WalletOutput {
+    pub connected: bool,
+    pub address: Option<String>,
+    pub path: Option<PathBuf>,
+    pub rpc_url: String,
+    pub program_id: String,
+    pub ipfs_url: String,
+}
+
+fn wallet_output(config: &Config, address: Option<Pubkey>) -> WalletOutput {
+    WalletOutput {
+        connected: address.is_some(),
+        address: address.map(|address| address.to_string()),
+        path: address.and(config.wallet_path.clone()),
+        rpc_url: config.rpc_url.clone(),
+        program_id: config.program_id.clone(),
+        ipfs_url: config.ipfs_url.clone(),
+    }
+}
+
 pub async fn handle_show() -> Result<()> {
     let config = Config::load()?;
+    if json_output() {
+        let address = config.wallet_path.as_ref().map(|_| wallet_pubkey(&config)).transpose()?;
+        return emit_json(&wallet_output(&config, address));
+    }
     
-    if let Some(wallet_path) = config.wallet_path {
-        let keypair_bytes = std::fs::read(&wallet_path)?;
-        let keypair_vec: Vec<u8> = serde_json::from_slice(&keypair_bytes)?;
-        let keypair = Keypair::from_bytes(&keypair_vec)?;
+    if let Some(wallet_path) = config.wallet_path.clone() {
+        let address = wallet_pubkey(&config)?;
+        let wallet = Wallet::parse(&wallet_path)?;
         
         println!("\n{}", "🔐 Current Wallet".cyan().bold());
-        println!("  Address: {}", keypair.pubkey().to_string().green());
-        println!("  Path: {}", wallet_path.display());
+        println!("  Address: {}", address.to_string().green());
+        println!("  Wallet: {}", wallet);
+        
+        println!("\n{}", "🛡️  File Permissions".cyan().bold());
+        if matches!(wallet, Wallet::File(_)) {
+            println!("  Keypair: {}", permissions::describe(&wallet_path));
+        }
+        let config_file = Config::config_dir()?.join("config.toml");
+        if config_file.exists() {
+            println!("  Config: {}", permissions::describe(&config_file));
+        }
         
         println!("\n{}", "🌐 Network Configuration".cyan().bold());
         println!("  RPC Endpoint: {}", config.rpc_url.yellow());
@@ -51,18 +117,132 @@ pub async fn handle_show() -> Result<()> {
         
         println!("\n{}", "🔗 Explorer Links:".blue().bold());
         println!("  Wallet: {}", 
-            format!("https://explorer.solana.com/address/{}?cluster=devnet", keypair.pubkey()).blue()
+            explorer_url(&config.rpc_url, &format!("address/{}", address)).blue()
         );
         println!("  Program: {}", 
-            format!("https://explorer.solana.com/address/{}?cluster=devnet", config.program_id).blue()
+            explorer_url(&config.rpc_url, &format!("address/{}", config.program_id)).blue()
         );
     } else {
         print_warning("No wallet connected to the decentralized registry.");
-        println!("\n{}", "📌 To get started:".yellow());
-        println!("  {}", "antsol wallet connect <path-to-keypair.json>".cyan());
-        println!("\n{}", "💡 Don't have a wallet?".blue());
-        println!("  Generate one with: {}", "solana-keygen new".cyan());
+        print_tips("📌 To get started:".yellow(), ["antsol wallet connect <keypair.json|prompt://|usb://ledger>".cyan()]);
+        print_tips("💡 Don't have a wallet?".blue(), [format!("Generate one with: {}", "antsol wallet new".cyan())]);
+    }
+    
+    Ok(())
+}
+
+/// Generate a keypair, save it owner-only and make it the configured wallet
+pub async fn handle_new(output: Option<PathBuf>) -> Result<()> {
+    let path = match output {
+        Some(path) => path,
+        None => {
+            let dir = Config::config_dir()?;
+            std::fs::create_dir_all(&dir)?;
+            dir.join("wallet.json")
+        }
+    };
+    if path.exists() {
+        return Err(AntSolError::Wallet(format!(
+            "{} already exists. Pass --output <path> to write the new keypair elsewhere",
+            path.display()
+        )).into());
     }
     
+    let keypair = Keypair::new();
+    write_keypair(&path, &keypair)?;
+    
+    let mut config = Config::load_file()?;
+    config.wallet_path = Some(path.clone());
+    config.save()?;
+    
+    print_success(&format!("Created wallet {}", keypair.pubkey().to_string().green()));
+    println!("  Keypair Path: {}", path.display());
+    println!("\n{}", "⚠️  Back this file up. Anyone with it controls the wallet and its packages.".yellow());
+    println!("  Fund it on devnet with: {}", "antsol wallet airdrop".cyan());
+    
     Ok(())
 }
+
+/// Print the configured wallet's balance, warning when it can't cover a publish
+pub async fn handle_balance() -> Result<()> {
+    let config = Config::load()?;
+    let address = wallet_pubkey(&config)?;
+    let client = AntSolClient::new(&config)?;
+    
+    let lamports = client.balance(&address)
+        .map_err(|e| AntSolError::Network(format!("Could not fetch balance from {}: {}", config.rpc_url, e)))?;
+    println!("\n{}", "💰 Wallet Balance".cyan().bold());
+    println!("  Address: {}", address.to_string().green());
+    println!("  Balance: {} SOL", lamports_to_sol(lamports).to_string().cyan());
+    
+    let threshold = match config.min_balance_sol {
+        Some(sol) => sol_to_lamports(sol),
+        None => client.publish_cost(PACKAGE_MAX_SPACE),
+    };
+    if lamports < threshold {
+        print_warning(&format!(
+            "Balance is below {} SOL, what the largest publish can cost. Fund the wallet before publishing",
+            lamports_to_sol(threshold)
+        ));
+    }
+    
+    Ok(())
+}
+
+/// Request a faucet airdrop to the configured wallet and wait for it to land
+pub async fn handle_airdrop(amount: f64) -> Result<()> {
+    let config = Config::load()?;
+    if is_mainnet_rpc(&config.rpc_url) {
+        return Err(AntSolError::Usage(format!(
+            "Refusing to airdrop on mainnet ({}). Airdrops only exist on devnet, testnet and local validators",
+            config.rpc_url
+        )).into());
+    }
+    if !(amount.is_finite() && amount > 0.0) {
+        return Err(AntSolError::Usage("Airdrop amount must be a positive number of SOL".into()).into());
+    }
+    let address = wallet_pubkey(&config)?;
+    let client = AntSolClient::new(&config)?;
+    
+    let spinner = create_spinner(&format!("Requesting {} SOL airdrop...", amount));
+    let result = client.airdrop(&address, sol_to_lamports(amount), Duration::from_secs(60)).await;
+    spinner.finish_and_clear();
+    let signature = result?;
+    
+    print_success(&format!("Airdropped {} SOL to {}", amount, address.to_string().green()));
+    println!("  Transaction: {}", signature.to_string().cyan());
+    if let Ok(lamports) = client.balance(&address) {
+        println!("  Balance: {} SOL", lamports_to_sol(lamports).to_string().cyan());
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_shape_is_stable() {
+        let keypair = Keypair::new();
+        let config = Config {
+            wallet_path: Some(PathBuf::from("/home/me/wallet.json")),
+            rpc_url: "https://api.devnet.solana.com".to_string(),
+            program_id: "Prog111".to_string(),
+            ipfs_url: "http://127.0.0.1:5001".to_string(),
+            ..Config::default()
+        };
+        assert_eq!(serde_json::to_value(wallet_output(&config, Some(keypair.pubkey()))).unwrap(), serde_json::json!({
+            "connected": true,
+            "address": keypair.pubkey().to_string(),
+            "path": "/home/me/wallet.json",
+            "rpc_url": "https://api.devnet.solana.com",
+            "program_id": "Prog111",
+            "ipfs_url": "http://127.0.0.1:5001"
+        }));
+
+        let disconnected = serde_json::to_value(wallet_output(&config, None)).unwrap();
+        assert_eq!(disconnected["connected"], false);
+        assert!(disconnected["address"].is_null() && disconnected["path"].is_null());
+    }
+}
@@ -0,0 +1,250 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::indexer_client::{IndexerClient, PackageDetails};
+use crate::types::Result;
+use crate::utils::*;
+use crate::watches::{self, Watch, WatchList};
+use colored::*;
+use serde::Serialize;
+use std::process::Command;
+
+/// One row of `antsol watch list`
+#[derive(Debug, Serialize)]
+struct WatchEntry<'a> {
+    name: &'a str,
+    last_seen: Option<&'a str>,
+}
+
+/// A version published since the package was last checked
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NewVersion {
+    pub name: String,
+    /// Version last seen before this check; `None` when the package wasn't indexed yet
+    pub old: Option<String>,
+    pub new: String,
+    pub cid: Option<String>,
+}
+
+/// Start watching `name`, remembering its current latest version so only
+/// later releases are reported
+pub async fn handle_add(name: String) -> Result<()> {
+    if !validate_package_name(&name) {
+        return Err(AntSolError::Usage(format!("Invalid package name: {}", name)).into());
+    }
+    let path = WatchList::default_path()?;
+    let mut list = WatchList::load(&path)?;
+    if list.packages.contains_key(&name) {
+        print_info(&format!("Already watching {}", name));
+        return Ok(());
+    }
+
+    let config = Config::load()?;
+    let details = IndexerClient::new(&config.indexer_url).get_package(&name).await?;
+    let last_seen = details.as_ref().and_then(|d| latest_version(d.versions.iter().map(|v| v.version.as_str())));
+    match &last_seen {
+        Some(version) => print_success(&format!("Watching {} (latest {})", name.cyan(), version)),
+        None => print_warning(&format!(
+            "{} isn't indexed yet; its first published version will be reported by `antsol watch check`",
+            name
+        )),
+    }
+    list.packages.insert(name, Watch { last_seen });
+    list.save(&path)
+}
+
+/// Stop watching `name`
+pub async fn handle_remove(name: String) -> Result<()> {
+    let path = WatchList::default_path()?;
+    let mut list = WatchList::load(&path)?;
+    if list.packages.remove(&name).is_none() {
+        return Err(AntSolError::NotFound(format!("Not watching {}", name)).into());
+    }
+    list.save(&path)?;
+    print_success(&format!("Stopped watching {}", name));
+    Ok(())
+}
+
+/// Print the watched packages with the last version seen for each
+pub async fn handle_list(json: bool) -> Result<()> {
+    let list = WatchList::load(&WatchList::default_path()?)?;
+    let entries: Vec<WatchEntry> = list.packages.iter()
+        .map(|(name, watch)| WatchEntry { name, last_seen: watch.last_seen.as_deref() })
+        .collect();
+
+    if json {
+        return emit_json(&entries);
+    }
+
+    println!("\n{}", "👀 Watched Packages".cyan().bold());
+    if entries.is_empty() {
+        print_info("Nothing watched yet. Add a package with `antsol watch add <name>`");
+        return Ok(());
+    }
+    for entry in &entries {
+        println!("  {:<32} {}", entry.name.bold(), entry.last_seen.unwrap_or("not indexed").dimmed());
+    }
+    Ok(())
+}
+
+/// Ask the indexer about every watched package and report versions newer
+/// than the last seen one, running `notify_cmd` for each. Reported versions
+/// are remembered; fails with exit code 1 when anything new was found so
+/// cron jobs and CI steps notice.
+pub async fn handle_check(notify_cmd: Option<String>, json: bool) -> Result<()> {
+    let path = WatchList::default_path()?;
+    let mut list = WatchList::load(&path)?;
+    let config = Config::load()?;
+    let indexer = IndexerClient::new(&config.indexer_url);
+
+    let spinner = (!json).then(|| create_spinner("🔎 Checking watched packages via indexer..."));
+    let mut found = Vec::new();
+    for (name, watch) in &list.packages {
+        let details = match indexer.get_package(name).await {
+            Ok(details) => details,
+            Err(e) => {
+                if let Some(spinner) = &spinner {
+                    spinner.finish_and_clear();
+                }
+                return Err(e);
+            }
+        };
+        if let Some(details) = details {
+            found.extend(new_versions(name, watch, &details));
+        }
+    }
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
+    }
+
+    let mut failed_notifications = 0;
+    for new in &found {
+        if let Some(command) = &notify_cmd {
+            if let Err(e) = notify(command, new) {
+                print_warning(&format!("--notify-cmd failed for {}@{}: {}", new.name, new.new, e));
+                failed_notifications += 1;
+                // Reported again on the next check
+                continue;
+            }
+        }
+        let watch = list.packages.entry(new.name.clone()).or_default();
+        if watch.last_seen.as_deref().is_none_or(|seen| compare_versions(&new.new, seen).is_gt()) {
+            watch.last_seen = Some(new.new.clone());
+        }
+    }
+    list.save(&path)?;
+
+    if json {
+        emit_json(&found)?;
+    } else {
+        print_new_versions(&found, list.packages.len());
+    }
+
+    if failed_notifications > 0 {
+        return Err(format!("--notify-cmd failed for {} of {} new version(s)", failed_notifications, found.len()).into());
+    }
+    if !found.is_empty() {
+        return Err(format!("{} new version(s) of watched packages", found.len()).into());
+    }
+    Ok(())
+}
+
+/// Indexed versions of a watched package above its last seen one
+fn new_versions(name: &str, watch: &Watch, details: &PackageDetails) -> Vec<NewVersion> {
+    let known = details.versions.iter().map(|v| v.version.as_str());
+    watches::new_versions(watch.last_seen.as_deref(), known)
+        .into_iter()
+        .map(|version| NewVersion {
+            name: name.to_string(),
+            old: watch.last_seen.clone(),
+            new: version.to_string(),
+            cid: details.versions.iter().find(|v| v.version == version).and_then(|v| v.ipfs_hash.clone()),
+        })
+        .collect()
+}
+
+/// Run `command` through the shell with NAME, OLD, NEW and CID set;
+/// OLD and CID are empty when unknown
+fn notify(command: &str, new: &NewVersion) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let status = shell
+        .arg(command)
+        .env("NAME", &new.name)
+        .env("OLD", new.old.as_deref().unwrap_or_default())
+        .env("NEW", &new.new)
+        .env("CID", new.cid.as_deref().unwrap_or_default())
+        .status()?;
+    if !status.success() {
+        return Err(format!("exited with {}", status).into());
+    }
+    Ok(())
+}
+
+fn print_new_versions(found: &[NewVersion], watched: usize) {
+    if found.is_empty() {
+        print_success(&format!("No new versions of {} watched package(s)", watched));
+        return;
+    }
+    println!("\n{}", "🆕 New Versions".cyan().bold());
+    for new in found {
+        println!(
+            "  {:<32} {} → {}  {}",
+            new.name.bold(),
+            new.old.as_deref().unwrap_or("-").dimmed(),
+            new.new.green().bold(),
+            new.cid.as_deref().unwrap_or("").dimmed()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer_client::VersionRow;
+
+    fn details(versions: &[(&str, &str)]) -> PackageDetails {
+        PackageDetails {
+            latest_version: None,
+            versions: versions.iter().map(|(version, cid)| VersionRow {
+                version: version.to_string(),
+                ipfs_hash: Some(cid.to_string()),
+                downloads: None,
+                published_at: None,
+            }).collect(),
+        }
+    }
+
+    #[test]
+    fn reports_each_version_above_the_last_seen_with_its_cid() {
+        let details = details(&[("1.0.0", "QmOld"), ("1.2.0", "QmTwo"), ("1.1.0", "QmOne")]);
+        let watch = Watch { last_seen: Some("1.0.0".into()) };
+        let found = new_versions("dep", &watch, &details);
+        assert_eq!(found.iter().map(|n| n.new.as_str()).collect::<Vec<_>>(), vec!["1.1.0", "1.2.0"]);
+        assert_eq!(serde_json::to_value(&found[1]).unwrap(), serde_json::json!({
+            "name": "dep",
+            "old": "1.0.0",
+            "new": "1.2.0",
+            "cid": "QmTwo",
+        }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn notify_cmd_gets_the_version_in_its_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let new = NewVersion { name: "dep".into(), old: None, new: "1.1.0".into(), cid: Some("QmOne".into()) };
+        let command = format!("echo \"$NAME|$OLD|$NEW|$CID\" > '{}'", out.display());
+        notify(&command, &new).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "dep||1.1.0|QmOne\n");
+
+        assert!(notify("exit 3", &new).is_err());
+    }
+}
@@ -1,50 +1,832 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::str::FromStr;
+use crate::credentials::{self, SecretStore};
+use crate::error::AntSolError;
+use crate::gateway_stats::{DEFAULT_GATEWAYS, RETIRED_GATEWAYS};
+use crate::permissions::{self, SecretFile};
+use crate::signer;
+use crate::tx::{Commitment, PriorityFee};
 use crate::types::Result;
+use crate::utils::{parse_size, Cluster};
+use std::sync::OnceLock;
+
+/// Which API `ipfs_url` speaks when uploading packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpfsBackend {
+    /// Pinata pinning service (`pinFileToIPFS`, needs a JWT)
+    Pinata,
+    /// A local or self-hosted Kubo node (`/api/v0/add`)
+    Kubo,
+    /// web3.storage (`/upload`, needs `pinning_token`)
+    Web3Storage,
+    /// Filebase's IPFS RPC API (needs the bucket's key as `pinning_token`)
+    Filebase,
+}
+
+impl IpfsBackend {
+    /// Kubo's RPC API listens on port 5001 by default
+    pub const KUBO_DEFAULT_URL: &'static str = "http://127.0.0.1:5001";
+    pub const PINATA_URL: &'static str = "https://api.pinata.cloud";
+    pub const WEB3_STORAGE_URL: &'static str = "https://api.web3.storage";
+    pub const FILEBASE_URL: &'static str = "https://rpc.filebase.io";
+    
+    /// Guess the backend from the URL: the hosted providers by domain,
+    /// loopback hosts and port 5001 as Kubo, anything else as Pinata
+    pub fn detect(ipfs_url: &str) -> Self {
+        let Ok(url) = reqwest::Url::parse(ipfs_url) else {
+            return IpfsBackend::Pinata;
+        };
+        let host = url.host_str().unwrap_or_default();
+        if host == "web3.storage" || host.ends_with(".web3.storage") {
+            return IpfsBackend::Web3Storage;
+        }
+        if host == "filebase.io" || host.ends_with(".filebase.io") {
+            return IpfsBackend::Filebase;
+        }
+        let local = matches!(host, "localhost" | "127.0.0.1" | "[::1]");
+        if local || url.port() == Some(5001) {
+            IpfsBackend::Kubo
+        } else {
+            IpfsBackend::Pinata
+        }
+    }
+    
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IpfsBackend::Pinata => "pinata",
+            IpfsBackend::Kubo => "kubo",
+            IpfsBackend::Web3Storage => "web3storage",
+            IpfsBackend::Filebase => "filebase",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        [IpfsBackend::Pinata, IpfsBackend::Kubo, IpfsBackend::Web3Storage, IpfsBackend::Filebase]
+            .into_iter()
+            .find(|backend| backend.as_str() == value)
+    }
+    
+    /// API endpoint `ipfs_url` should point at for this backend
+    pub fn default_url(self) -> &'static str {
+        match self {
+            IpfsBackend::Pinata => Self::PINATA_URL,
+            IpfsBackend::Kubo => Self::KUBO_DEFAULT_URL,
+            IpfsBackend::Web3Storage => Self::WEB3_STORAGE_URL,
+            IpfsBackend::Filebase => Self::FILEBASE_URL,
+        }
+    }
+    
+    /// Backends that authenticate with `pinning_token`
+    pub fn uses_pinning_token(self) -> bool {
+        matches!(self, IpfsBackend::Web3Storage | IpfsBackend::Filebase)
+    }
+}
+
+/// Network settings kept per profile in `[profiles.<name>]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub rpc_url: String,
+    pub program_id: String,
+    #[serde(default = "Config::default_indexer_url")]
+    pub indexer_url: String,
+    #[serde(default = "Config::default_ipfs_url")]
+    pub ipfs_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_backend: Option<IpfsBackend>,
+    #[serde(default = "Config::default_ipfs_gateways")]
+    pub ipfs_gateways: Vec<String>,
+}
+
+/// Keys stored in the selected profile rather than at the top level of config.toml
+pub const PROFILE_KEYS: &[&str] = &["rpc_url", "program_id", "indexer_url", "ipfs_url", "ipfs_backend", "ipfs_gateways"];
+
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+static COMMITMENT_OVERRIDE: OnceLock<Commitment> = OnceLock::new();
+
+/// Use `name` instead of `active_profile` for this invocation (`--profile`);
+/// later calls are ignored
+pub fn set_profile_override(name: String) {
+    let _ = PROFILE_OVERRIDE.set(name);
+}
+
+/// Use `commitment` over the configured one for this invocation (`--commitment`);
+/// later calls are ignored
+pub fn set_commitment_override(commitment: Commitment) {
+    let _ = COMMITMENT_OVERRIDE.set(commitment);
+}
 
 /// Global configuration for AntSol CLI
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub wallet_path: Option<PathBuf>,
+    /// Network keys hold the selected profile's values once loaded
+    #[serde(default = "Config::default_rpc_url")]
     pub rpc_url: String,
+    #[serde(default = "Config::default_ipfs_url")]
     pub ipfs_url: String,
+    #[serde(default = "Config::default_program_id")]
     pub program_id: String,
+    /// Legacy plaintext JWT; migrated to the OS keyring on load unless `--no-keyring`
     pub pinata_jwt: Option<String>,
+    /// The Pinata JWT lives in the OS keyring, not in this file
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinata_jwt_in_keyring: bool,
+    /// API token for the web3.storage and Filebase backends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinning_token: Option<String>,
+    /// Upload backend; detected from `ipfs_url` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ipfs_backend: Option<IpfsBackend>,
     #[serde(default = "Config::default_indexer_url")]
     pub indexer_url: String,
+    /// Optional cap on RPC requests per CLI invocation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rpc_calls: Option<u64>,
+    /// Resolve install/info against the local development registry first
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local_registry: bool,
+    /// `wallet balance` warns below this many SOL; defaults to the estimated publish cost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_balance_sol: Option<f64>,
+    /// Report installs to the indexer's download counter (`install --no-telemetry` skips it once)
+    #[serde(default = "Config::default_telemetry")]
+    pub telemetry: bool,
+    /// IPFS gateways downloads race, in preference order before stats ranking
+    #[serde(default = "Config::default_ipfs_gateways")]
+    pub ipfs_gateways: Vec<String>,
+    /// Seconds to wait for a connection to a gateway
+    #[serde(default = "Config::default_ipfs_connect_timeout_secs")]
+    pub ipfs_connect_timeout_secs: u64,
+    /// Seconds a gateway may go without sending anything before it is dropped
+    #[serde(default = "Config::default_ipfs_read_timeout_secs")]
+    pub ipfs_read_timeout_secs: u64,
+    /// Seconds one download may take across all gateways
+    #[serde(default = "Config::default_ipfs_download_timeout_secs")]
+    pub ipfs_download_timeout_secs: u64,
+    /// Seconds one RPC request may take before it fails
+    #[serde(default = "Config::default_rpc_timeout_secs")]
+    pub rpc_timeout_secs: u64,
+    /// Resubmissions of a publish/update transaction after a transient failure
+    #[serde(default = "Config::default_tx_max_retries")]
+    pub tx_max_retries: u32,
+    /// Commitment accounts are read at and transactions must reach before
+    /// publish/update report success (`tx_commitment` before it covered reads)
+    #[serde(default = "Config::default_commitment", alias = "tx_commitment")]
+    pub commitment: Commitment,
+    /// Seconds one submission may take to reach `commitment` before it is retried
+    #[serde(default = "Config::default_tx_confirm_timeout_secs")]
+    pub tx_confirm_timeout_secs: u64,
+    /// Send transactions without preflight simulation (`--skip-preflight` sets it once)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_preflight: bool,
+    /// Compute unit price for publish/update: microlamports or "auto"; unset means
+    /// auto on mainnet and none elsewhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority_fee: Option<PriorityFee>,
+    /// Largest archive, in bytes, that publish/update upload and install downloads
+    #[serde(default = "Config::default_max_package_size")]
+    pub max_package_size: u64,
+    /// Most an installed archive may extract to, in bytes, across all of its files
+    #[serde(default = "Config::default_max_extracted_size")]
+    pub max_extracted_size: u64,
+    /// Largest single file an installed archive may extract, in bytes
+    #[serde(default = "Config::default_max_extracted_file_size")]
+    pub max_extracted_file_size: u64,
+    /// Directory packages are extracted into, relative to the project (`install --target-dir` overrides it)
+    #[serde(default = "Config::default_install_dir")]
+    pub install_dir: PathBuf,
+    /// Profile used when `--profile` isn't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, NetworkProfile>,
+    /// Profile whose values the network keys hold; edits are saved back to it
+    #[serde(skip)]
+    pub selected_profile: Option<String>,
 }
 
 impl Config {
+    pub fn default_rpc_url() -> String {
+        "https://api.devnet.solana.com".to_string()
+    }
+    
+    pub fn default_ipfs_url() -> String {
+        IpfsBackend::PINATA_URL.to_string()
+    }
+    
+    pub fn default_program_id() -> String {
+        "A9igkBugcujD9Nw9d97FFN4aY3qHXnJxEqCChJt8C42S".to_string()
+    }
+    
     pub fn default_indexer_url() -> String {
         "https://antsol-indexer-v2.onrender.com".to_string()
     }
+    
+    pub fn default_telemetry() -> bool {
+        true
+    }
+    
+    pub fn default_ipfs_gateways() -> Vec<String> {
+        DEFAULT_GATEWAYS.iter().map(|g| g.to_string()).collect()
+    }
+    
+    pub fn default_ipfs_connect_timeout_secs() -> u64 {
+        10
+    }
+    
+    pub fn default_ipfs_read_timeout_secs() -> u64 {
+        30
+    }
+    
+    pub fn default_ipfs_download_timeout_secs() -> u64 {
+        600
+    }
+    
+    pub fn default_tx_max_retries() -> u32 {
+        3
+    }
+    
+    pub fn default_rpc_timeout_secs() -> u64 {
+        30
+    }
+    
+    pub fn default_commitment() -> Commitment {
+        Commitment::Confirmed
+    }
+    
+    pub fn default_tx_confirm_timeout_secs() -> u64 {
+        60
+    }
+    
+    pub fn default_max_package_size() -> u64 {
+        50 * 1024 * 1024
+    }
+    
+    pub fn default_max_extracted_size() -> u64 {
+        500 * 1024 * 1024
+    }
+    
+    pub fn default_max_extracted_file_size() -> u64 {
+        100 * 1024 * 1024
+    }
+    
+    pub fn default_install_dir() -> PathBuf {
+        PathBuf::from("antsol_packages")
+    }
+    
+    /// Configured upload backend, falling back to detection from `ipfs_url`
+    pub fn ipfs_backend(&self) -> IpfsBackend {
+        self.ipfs_backend.unwrap_or_else(|| IpfsBackend::detect(&self.ipfs_url))
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             wallet_path: None,
-            rpc_url: "https://api.devnet.solana.com".to_string(),
-            ipfs_url: "https://api.pinata.cloud".to_string(),
-            program_id: "A9igkBugcujD9Nw9d97FFN4aY3qHXnJxEqCChJt8C42S".to_string(),
+            rpc_url: Self::default_rpc_url(),
+            ipfs_url: Self::default_ipfs_url(),
+            program_id: Self::default_program_id(),
             pinata_jwt: None,
+            pinata_jwt_in_keyring: false,
+            pinning_token: None,
+            ipfs_backend: None,
             indexer_url: Self::default_indexer_url(),
+            max_rpc_calls: None,
+            local_registry: false,
+            min_balance_sol: None,
+            telemetry: Self::default_telemetry(),
+            ipfs_gateways: Self::default_ipfs_gateways(),
+            ipfs_connect_timeout_secs: Self::default_ipfs_connect_timeout_secs(),
+            ipfs_read_timeout_secs: Self::default_ipfs_read_timeout_secs(),
+            ipfs_download_timeout_secs: Self::default_ipfs_download_timeout_secs(),
+            rpc_timeout_secs: Self::default_rpc_timeout_secs(),
+            tx_max_retries: Self::default_tx_max_retries(),
+            commitment: Self::default_commitment(),
+            tx_confirm_timeout_secs: Self::default_tx_confirm_timeout_secs(),
+            skip_preflight: false,
+            priority_fee: None,
+            max_package_size: Self::default_max_package_size(),
+            max_extracted_size: Self::default_max_extracted_size(),
+            max_extracted_file_size: Self::default_max_extracted_file_size(),
+            install_dir: Self::default_install_dir(),
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            selected_profile: None,
         }
     }
 }
 
 impl Config {
-    /// Load configuration from ~/.antsol/config.toml
+    /// The network keys' current values as a profile
+    pub fn network_profile(&self) -> NetworkProfile {
+        NetworkProfile {
+            rpc_url: self.rpc_url.clone(),
+            program_id: self.program_id.clone(),
+            indexer_url: self.indexer_url.clone(),
+            ipfs_url: self.ipfs_url.clone(),
+            ipfs_backend: self.ipfs_backend,
+            ipfs_gateways: self.ipfs_gateways.clone(),
+        }
+    }
+    
+    pub fn apply_profile(&mut self, profile: &NetworkProfile) {
+        self.rpc_url = profile.rpc_url.clone();
+        self.program_id = profile.program_id.clone();
+        self.indexer_url = profile.indexer_url.clone();
+        self.ipfs_url = profile.ipfs_url.clone();
+        self.ipfs_backend = profile.ipfs_backend;
+        self.ipfs_gateways = profile.ipfs_gateways.clone();
+    }
+    
+    /// Turn a config from before profiles into one profile named after its
+    /// cluster, made active. Returns the profile's name when it migrated.
+    fn migrate_to_profiles(&mut self) -> Option<String> {
+        if !self.profiles.is_empty() {
+            return None;
+        }
+        let name = Cluster::detect(&self.rpc_url).as_str().to_string();
+        self.profiles.insert(name.clone(), self.network_profile());
+        self.active_profile = Some(name.clone());
+        Some(name)
+    }
+    
+    /// Remove [`RETIRED_GATEWAYS`] from the top-level and every profile's
+    /// `ipfs_gateways`, falling back to the defaults for a list left empty.
+    /// Returns whether anything was removed.
+    fn drop_retired_gateways(&mut self) -> bool {
+        let mut removed = false;
+        let lists = std::iter::once(&mut self.ipfs_gateways).chain(self.profiles.values_mut().map(|p| &mut p.ipfs_gateways));
+        for gateways in lists {
+            let before = gateways.len();
+            gateways.retain(|g| !RETIRED_GATEWAYS.contains(&g.trim_end_matches('/')));
+            if gateways.len() != before {
+                removed = true;
+                if gateways.is_empty() {
+                    *gateways = Self::default_ipfs_gateways();
+                }
+            }
+        }
+        removed
+    }
+    
+    /// Load `name`, or the active profile, into the network keys
+    pub fn select_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let Some(name) = name.or(self.active_profile.as_deref()).map(str::to_string) else {
+            return Ok(());
+        };
+        let profile = self.profiles.get(&name).cloned().ok_or_else(|| self.unknown_profile(&name))?;
+        self.apply_profile(&profile);
+        self.selected_profile = Some(name);
+        Ok(())
+    }
+    
+    pub fn unknown_profile(&self, name: &str) -> Box<dyn std::error::Error> {
+        let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+        AntSolError::Config(format!("Unknown profile '{}'. Profiles: {}", name, known.join(", "))).into()
+    }
+    
+    /// The file's TOML: network keys go to the selected profile instead of
+    /// the top level once profiles exist
+    fn to_toml(&self) -> Result<String> {
+        let mut table = toml::Table::try_from(self)?;
+        if !self.profiles.is_empty() {
+            let mut profiles = self.profiles.clone();
+            if let Some(name) = &self.selected_profile {
+                profiles.insert(name.clone(), self.network_profile());
+            }
+            for key in PROFILE_KEYS {
+                table.remove(*key);
+            }
+            table.insert("profiles".to_string(), toml::Value::try_from(profiles)?);
+        }
+        Ok(toml::to_string_pretty(&table)?)
+    }
+}
+
+/// Keys `antsol config get/set/list` understand, in display order
+pub const CONFIG_KEYS: &[&str] = &[
+    "rpc_url",
+    "ipfs_url",
+    "ipfs_backend",
+    "program_id",
+    "indexer_url",
+    "wallet_path",
+    "pinata_jwt",
+    "pinning_token",
+    "max_rpc_calls",
+    "local_registry",
+    "min_balance_sol",
+    "telemetry",
+    "ipfs_gateways",
+    "ipfs_connect_timeout_secs",
+    "ipfs_read_timeout_secs",
+    "ipfs_download_timeout_secs",
+    "rpc_timeout_secs",
+    "tx_max_retries",
+    "commitment",
+    "tx_confirm_timeout_secs",
+    "skip_preflight",
+    "priority_fee",
+    "max_package_size",
+    "max_extracted_size",
+    "max_extracted_file_size",
+    "install_dir",
+];
+
+/// Keys whose values are masked in `antsol config list`
+pub fn is_secret_key(key: &str) -> bool {
+    matches!(key, "pinata_jwt" | "pinning_token")
+}
+
+/// Keep the first few characters of a secret so it can still be told apart
+pub fn mask_secret(value: &str) -> String {
+    let visible: String = value.chars().take(4).collect();
+    format!("{}{}", visible, "*".repeat(8))
+}
+
+impl Config {
+    /// Current value of `key` as a string, `None` when the optional field is unset
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(match key {
+            "rpc_url" => Some(self.rpc_url.clone()),
+            "ipfs_url" => Some(self.ipfs_url.clone()),
+            "ipfs_backend" => self.ipfs_backend.map(|b| b.as_str().to_string()),
+            "program_id" => Some(self.program_id.clone()),
+            "indexer_url" => Some(self.indexer_url.clone()),
+            "wallet_path" => self.wallet_path.as_ref().map(|p| p.display().to_string()),
+            "pinata_jwt" => self.pinata_jwt.clone(),
+            "pinning_token" => self.pinning_token.clone(),
+            "max_rpc_calls" => self.max_rpc_calls.map(|n| n.to_string()),
+            "local_registry" => Some(self.local_registry.to_string()),
+            "min_balance_sol" => self.min_balance_sol.map(|sol| sol.to_string()),
+            "telemetry" => Some(self.telemetry.to_string()),
+            "ipfs_gateways" => Some(self.ipfs_gateways.join(",")),
+            "ipfs_connect_timeout_secs" => Some(self.ipfs_connect_timeout_secs.to_string()),
+            "ipfs_read_timeout_secs" => Some(self.ipfs_read_timeout_secs.to_string()),
+            "ipfs_download_timeout_secs" => Some(self.ipfs_download_timeout_secs.to_string()),
+            "tx_max_retries" => Some(self.tx_max_retries.to_string()),
+            "rpc_timeout_secs" => Some(self.rpc_timeout_secs.to_string()),
+            "commitment" | "tx_commitment" => Some(self.commitment.as_str().to_string()),
+            "tx_confirm_timeout_secs" => Some(self.tx_confirm_timeout_secs.to_string()),
+            "skip_preflight" => Some(self.skip_preflight.to_string()),
+            "priority_fee" => self.priority_fee.map(|fee| fee.to_string()),
+            "max_package_size" => Some(self.max_package_size.to_string()),
+            "max_extracted_size" => Some(self.max_extracted_size.to_string()),
+            "max_extracted_file_size" => Some(self.max_extracted_file_size.to_string()),
+            "install_dir" => Some(self.install_dir.display().to_string()),
+            _ => return Err(unknown_key(key)),
+        })
+    }
+    
+    /// Validate `value` and store it under `key`.
+    /// An empty value clears optional keys.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = value.trim();
+        let invalid = |reason: String| -> Box<dyn std::error::Error> {
+            AntSolError::Usage(format!("Invalid value for {}: {}", key, reason)).into()
+        };
+        match key {
+            "rpc_url" => self.rpc_url = parse_url(value).map_err(invalid)?,
+            "ipfs_url" => self.ipfs_url = parse_url(value).map_err(invalid)?,
+            "indexer_url" => self.indexer_url = parse_url(value).map_err(invalid)?,
+            "ipfs_backend" => {
+                self.ipfs_backend = match value {
+                    "" => None,
+                    other => Some(IpfsBackend::parse(other).ok_or_else(|| {
+                        invalid(format!("'{}' (expected pinata, kubo, web3storage or filebase)", other))
+                    })?),
+                }
+            }
+            "program_id" => {
+                solana_sdk::pubkey::Pubkey::from_str(value)
+                    .map_err(|e| invalid(format!("'{}' is not a valid public key ({})", value, e)))?;
+                self.program_id = value.to_string();
+            }
+            "wallet_path" => {
+                self.wallet_path = match value {
+                    "" => None,
+                    uri if signer::is_wallet_uri(uri) => {
+                        signer::Wallet::parse(uri.as_ref()).map_err(|e| invalid(e.to_string()))?;
+                        Some(PathBuf::from(uri))
+                    }
+                    path if PathBuf::from(path).is_file() => Some(PathBuf::from(path)),
+                    path => return Err(invalid(format!("{} does not exist", path))),
+                }
+            }
+            "pinata_jwt" => self.pinata_jwt = (!value.is_empty()).then(|| value.to_string()),
+            "pinning_token" => self.pinning_token = (!value.is_empty()).then(|| value.to_string()),
+            "max_rpc_calls" => {
+                self.max_rpc_calls = match value {
+                    "" => None,
+                    n => Some(n.parse().map_err(|_| invalid(format!("'{}' is not a positive integer", n)))?),
+                }
+            }
+            "local_registry" => {
+                self.local_registry = value.parse().map_err(|_| invalid(format!("'{}' (expected true or false)", value)))?
+            }
+            "min_balance_sol" => {
+                self.min_balance_sol = match value {
+                    "" => None,
+                    sol => match sol.parse::<f64>() {
+                        Ok(sol) if sol.is_finite() && sol >= 0.0 => Some(sol),
+                        _ => return Err(invalid(format!("'{}' is not a non-negative number of SOL", sol))),
+                    },
+                }
+            }
+            "telemetry" => {
+                self.telemetry = value.parse().map_err(|_| invalid(format!("'{}' (expected true or false)", value)))?
+            }
+            "ipfs_gateways" => {
+                self.ipfs_gateways = match value {
+                    "" => Self::default_ipfs_gateways(),
+                    list => list
+                        .split(',')
+                        .map(|g| parse_url(g.trim()).map(|g| g.trim_end_matches('/').to_string()))
+                        .collect::<std::result::Result<_, _>>()
+                        .map_err(invalid)?,
+                }
+            }
+            "ipfs_connect_timeout_secs" | "ipfs_read_timeout_secs" | "ipfs_download_timeout_secs" => {
+                let secs = match value.parse() {
+                    Ok(secs) if secs > 0 => secs,
+                    _ => return Err(invalid(format!("'{}' is not a positive number of seconds", value))),
+                };
+                match key {
+                    "ipfs_connect_timeout_secs" => self.ipfs_connect_timeout_secs = secs,
+                    "ipfs_read_timeout_secs" => self.ipfs_read_timeout_secs = secs,
+                    _ => self.ipfs_download_timeout_secs = secs,
+                }
+            }
+            "tx_max_retries" => {
+                self.tx_max_retries = value.parse().map_err(|_| invalid(format!("'{}' is not a non-negative integer", value)))?
+            }
+            "rpc_timeout_secs" => {
+                self.rpc_timeout_secs = match value.parse() {
+                    Ok(secs) if secs > 0 => secs,
+                    _ => return Err(invalid(format!("'{}' is not a positive number of seconds", value))),
+                }
+            }
+            "commitment" | "tx_commitment" => {
+                self.commitment = Commitment::parse(value).ok_or_else(|| {
+                    invalid(format!("'{}' (expected processed, confirmed or finalized)", value))
+                })?
+            }
+            "tx_confirm_timeout_secs" => {
+                self.tx_confirm_timeout_secs = match value.parse() {
+                    Ok(secs) if secs > 0 => secs,
+                    _ => return Err(invalid(format!("'{}' is not a positive number of seconds", value))),
+                }
+            }
+            "skip_preflight" => {
+                self.skip_preflight = value.parse().map_err(|_| invalid(format!("'{}' (expected true or false)", value)))?
+            }
+            "priority_fee" => {
+                self.priority_fee = match value {
+                    "" => None,
+                    fee => Some(fee.parse().map_err(invalid)?),
+                }
+            }
+            "max_package_size" => {
+                self.max_package_size = match value {
+                    "" => Self::default_max_package_size(),
+                    size => parse_size(size).map_err(invalid)?,
+                }
+            }
+            "max_extracted_size" => {
+                self.max_extracted_size = match value {
+                    "" => Self::default_max_extracted_size(),
+                    size => parse_size(size).map_err(invalid)?,
+                }
+            }
+            "max_extracted_file_size" => {
+                self.max_extracted_file_size = match value {
+                    "" => Self::default_max_extracted_file_size(),
+                    size => parse_size(size).map_err(invalid)?,
+                }
+            }
+            "install_dir" => {
+                self.install_dir = match value {
+                    "" => Self::default_install_dir(),
+                    dir => PathBuf::from(dir),
+                }
+            }
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> Box<dyn std::error::Error> {
+    AntSolError::Usage(format!("Unknown config key '{}'. Valid keys: {}", key, CONFIG_KEYS.join(", "))).into()
+}
+
+/// Accept only absolute http(s) URLs
+fn parse_url(value: &str) -> std::result::Result<String, String> {
+    let url = reqwest::Url::parse(value).map_err(|e| format!("'{}' is not a URL ({})", value, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("'{}' must use http or https", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Environment variables layered over the config file, as (key, variable)
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("rpc_url", "ANTSOL_RPC_URL"),
+    ("ipfs_url", "ANTSOL_IPFS_URL"),
+    ("program_id", "ANTSOL_PROGRAM_ID"),
+    ("indexer_url", "ANTSOL_INDEXER_URL"),
+    ("wallet_path", "ANTSOL_WALLET_PATH"),
+    ("pinata_jwt", "PINATA_JWT"),
+    ("pinning_token", "ANTSOL_PINNING_TOKEN"),
+    ("telemetry", "ANTSOL_TELEMETRY"),
+    ("ipfs_gateways", "ANTSOL_IPFS_GATEWAYS"),
+    ("tx_max_retries", "ANTSOL_TX_MAX_RETRIES"),
+    ("rpc_timeout_secs", "ANTSOL_RPC_TIMEOUT_SECS"),
+    // The older name first, so ANTSOL_COMMITMENT wins when both are set
+    ("commitment", "ANTSOL_TX_COMMITMENT"),
+    ("commitment", "ANTSOL_COMMITMENT"),
+    ("skip_preflight", "ANTSOL_SKIP_PREFLIGHT"),
+    ("priority_fee", "ANTSOL_PRIORITY_FEE"),
+    ("max_package_size", "ANTSOL_MAX_PACKAGE_SIZE"),
+    ("install_dir", "ANTSOL_INSTALL_DIR"),
+];
+
+/// Keyring entry holding the Pinata JWT
+const PINATA_JWT_ENTRY: &str = "pinata_jwt";
+
+/// Where an effective config value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env(&'static str),
+    Keyring,
+    /// The selected `[profiles.<name>]` table
+    Profile,
+    /// A global command-line flag
+    Flag(&'static str),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File => write!(f, "file"),
+            ConfigSource::Env(var) => write!(f, "env ({})", var),
+            ConfigSource::Keyring => write!(f, "keyring"),
+            ConfigSource::Profile => write!(f, "profile"),
+            ConfigSource::Flag(flag) => write!(f, "flag ({})", flag),
+        }
+    }
+}
+
+impl Config {
+    /// Load the effective configuration: ~/.antsol/config.toml with the
+    /// selected profile and then environment overrides applied on top. A Pinata JWT in the OS keyring
+    /// takes precedence over `PINATA_JWT`, which beats a plaintext one.
     pub fn load() -> Result<Self> {
+        Ok(Self::load_with_sources()?.0)
+    }
+    
+    /// Effective configuration plus where each key's value came from
+    pub fn load_with_sources() -> Result<(Self, BTreeMap<&'static str, ConfigSource>)> {
+        let (mut config, mut sources) = Self::load_file_with_sources()?;
+        config.apply_env(&mut sources, |var| std::env::var(var).ok())?;
+        if let Some(commitment) = COMMITMENT_OVERRIDE.get() {
+            config.commitment = *commitment;
+            sources.insert("commitment", ConfigSource::Flag("--commitment"));
+        }
+        if let Some(keyring) = credentials::keyring() {
+            config.apply_keyring(&keyring, &mut sources);
+        }
+        Ok((config, sources))
+    }
+    
+    /// Load ~/.antsol/config.toml alone, ignoring the environment.
+    /// Use this when the config will be saved back, so overrides aren't persisted.
+    pub fn load_file() -> Result<Self> {
+        Ok(Self::load_file_with_sources()?.0)
+    }
+    
+    fn load_file_with_sources() -> Result<(Self, BTreeMap<&'static str, ConfigSource>)> {
         let config_dir = Self::config_dir()?;
         let config_file = config_dir.join("config.toml");
         
-        if config_file.exists() {
-            let content = std::fs::read_to_string(&config_file)?;
-            Ok(toml::from_str(&content)?)
-        } else {
-            Ok(Self::default())
+        let mut sources: BTreeMap<&'static str, ConfigSource> =
+            CONFIG_KEYS.iter().map(|key| (*key, ConfigSource::Default)).collect();
+        let profile_override = PROFILE_OVERRIDE.get().map(String::as_str);
+        if !config_file.exists() {
+            let mut config = Self::default();
+            config.migrate_to_profiles();
+            config.select_profile(profile_override)?;
+            return Ok((config, sources));
+        }
+        
+        permissions::check(&config_file, SecretFile::Config)?;
+        let content = std::fs::read_to_string(&config_file)?;
+        let table: toml::Table = toml::from_str(&content)?;
+        for (key, source) in sources.iter_mut() {
+            if table.contains_key(*key) || (*key == "commitment" && table.contains_key("tx_commitment")) {
+                *source = ConfigSource::File;
+            }
+        }
+        let mut config: Self = toml::from_str(&content)?;
+        if let Some(name) = config.migrate_to_profiles() {
+            config.save()?;
+            eprintln!("✓ Moved the network settings in {} into the '{}' profile", config_file.display(), name);
+        }
+        if config.drop_retired_gateways() {
+            config.save()?;
+            eprintln!("✓ Removed shut-down gateways ({}) from ipfs_gateways", RETIRED_GATEWAYS.join(", "));
+        }
+        config.select_profile(profile_override)?;
+        if config.selected_profile.is_some() {
+            for key in PROFILE_KEYS {
+                sources.insert(key, ConfigSource::Profile);
+            }
         }
+        if let Some(keyring) = credentials::keyring() {
+            if config.migrate_pinata_jwt(&keyring) {
+                config.save()?;
+                eprintln!("✓ Moved pinata_jwt from {} to the OS keyring", config_file.display());
+            }
+        }
+        Ok((config, sources))
+    }
+    
+    /// Move a plaintext `pinata_jwt` into `store`. Returns whether it moved;
+    /// when the store is unavailable the plaintext value is left as it was.
+    fn migrate_pinata_jwt<S: SecretStore>(&mut self, store: &S) -> bool {
+        let Some(jwt) = self.pinata_jwt.as_deref() else {
+            return false;
+        };
+        if store.set(PINATA_JWT_ENTRY, jwt).is_err() {
+            return false;
+        }
+        self.pinata_jwt = None;
+        self.pinata_jwt_in_keyring = true;
+        true
+    }
+    
+    /// Fill `pinata_jwt` from `store` when the file says it is kept there
+    fn apply_keyring<S: SecretStore>(&mut self, store: &S, sources: &mut BTreeMap<&'static str, ConfigSource>) {
+        if !self.pinata_jwt_in_keyring {
+            return;
+        }
+        match store.get(PINATA_JWT_ENTRY) {
+            Ok(Some(jwt)) => {
+                self.pinata_jwt = Some(jwt);
+                sources.insert("pinata_jwt", ConfigSource::Keyring);
+            }
+            // Removed outside antsol: fall back to the environment, if set
+            Ok(None) => {}
+            Err(e) => eprintln!("⚠ {}", e),
+        }
+    }
+    
+    /// Set (or with `None` clear) the Pinata JWT: in `store` when there is
+    /// one, otherwise in plaintext in this file
+    pub fn store_pinata_jwt<S: SecretStore>(&mut self, store: Option<&S>, jwt: Option<String>) -> Result<()> {
+        match store {
+            Some(store) => {
+                match &jwt {
+                    Some(jwt) => store.set(PINATA_JWT_ENTRY, jwt)?,
+                    None => store.delete(PINATA_JWT_ENTRY)?,
+                }
+                self.pinata_jwt = None;
+                self.pinata_jwt_in_keyring = jwt.is_some();
+            }
+            None => {
+                self.pinata_jwt = jwt;
+                self.pinata_jwt_in_keyring = false;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Overwrite keys whose environment variable (see [`ENV_OVERRIDES`]) is set and non-empty
+    fn apply_env<F>(&mut self, sources: &mut BTreeMap<&'static str, ConfigSource>, lookup: F) -> Result<()>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        for (key, var) in ENV_OVERRIDES {
+            let Some(value) = lookup(var).filter(|v| !v.trim().is_empty()) else {
+                continue;
+            };
+            // The wallet is only needed by commands that sign, which report a missing file themselves
+            if *key == "wallet_path" {
+                self.wallet_path = Some(PathBuf::from(value.trim()));
+            } else {
+                self.set(key, &value)
+                    .map_err(|e| AntSolError::Config(format!("{} is set but invalid: {}", var, e)))?;
+            }
+            sources.insert(key, ConfigSource::Env(var));
+        }
+        Ok(())
     }
     
     /// Save configuration to ~/.antsol/config.toml
@@ -53,15 +835,320 @@ impl Config {
         std::fs::create_dir_all(&config_dir)?;
         
         let config_file = config_dir.join("config.toml");
-        let content = toml::to_string_pretty(self)?;
-        std::fs::write(config_file, content)?;
+        std::fs::write(config_file, self.to_toml()?)?;
         
         Ok(())
     }
     
     /// Get the config directory path
     pub fn config_dir() -> Result<PathBuf> {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        let home = dirs::home_dir()
+            .ok_or_else(|| AntSolError::Config("Could not find home directory".into()))?;
         Ok(home.join(".antsol"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::tests::MemoryStore;
+
+    #[test]
+    fn detects_kubo_for_local_nodes() {
+        assert_eq!(IpfsBackend::detect("http://127.0.0.1:5001"), IpfsBackend::Kubo);
+        assert_eq!(IpfsBackend::detect("http://localhost:5001/"), IpfsBackend::Kubo);
+        assert_eq!(IpfsBackend::detect("http://ipfs.internal:5001"), IpfsBackend::Kubo);
+        assert_eq!(IpfsBackend::detect("https://api.pinata.cloud"), IpfsBackend::Pinata);
+        assert_eq!(IpfsBackend::detect("not a url"), IpfsBackend::Pinata);
+    }
+
+    #[test]
+    fn detects_hosted_pinning_providers() {
+        for backend in [IpfsBackend::Pinata, IpfsBackend::Kubo, IpfsBackend::Web3Storage, IpfsBackend::Filebase] {
+            assert_eq!(IpfsBackend::detect(backend.default_url()), backend);
+            assert_eq!(IpfsBackend::parse(backend.as_str()), Some(backend));
+        }
+        assert_eq!(IpfsBackend::parse("s3"), None);
+
+        let parsed: Config = toml::from_str(&format!("{}ipfs_backend = \"web3storage\"\n", toml::to_string(&Config::default()).unwrap())).unwrap();
+        assert_eq!(parsed.ipfs_backend, Some(IpfsBackend::Web3Storage));
+    }
+
+    #[test]
+    fn explicit_backend_overrides_detection() {
+        let mut config = Config::default();
+        assert_eq!(config.ipfs_backend(), IpfsBackend::Pinata);
+        config.ipfs_url = IpfsBackend::KUBO_DEFAULT_URL.to_string();
+        assert_eq!(config.ipfs_backend(), IpfsBackend::Kubo);
+        config.ipfs_backend = Some(IpfsBackend::Pinata);
+        assert_eq!(config.ipfs_backend(), IpfsBackend::Pinata);
+
+        let parsed: Config = toml::from_str(&format!("{}ipfs_backend = \"kubo\"\n", toml::to_string(&Config::default()).unwrap())).unwrap();
+        assert_eq!(parsed.ipfs_backend, Some(IpfsBackend::Kubo));
+    }
+
+    #[test]
+    fn every_key_round_trips_through_get_and_set() {
+        let mut config = Config::default();
+        for key in CONFIG_KEYS {
+            if let Some(value) = config.get(key).unwrap() {
+                config.set(key, &value).unwrap();
+            }
+        }
+        config.set("max_rpc_calls", "50").unwrap();
+        assert_eq!(config.get("max_rpc_calls").unwrap().as_deref(), Some("50"));
+        config.set("max_rpc_calls", "").unwrap();
+        assert_eq!(config.get("max_rpc_calls").unwrap(), None);
+        config.set("max_package_size", "100MB").unwrap();
+        assert_eq!(config.get("max_package_size").unwrap().as_deref(), Some("104857600"));
+        config.set("install_dir", "vendor/antsol").unwrap();
+        assert_eq!(config.install_dir, PathBuf::from("vendor/antsol"));
+        config.set("install_dir", "").unwrap();
+        assert_eq!(config.install_dir, Config::default_install_dir());
+    }
+
+    #[test]
+    fn flat_configs_migrate_into_a_profile() {
+        let legacy = "rpc_url = \"http://127.0.0.1:8899\"\nipfs_url = \"http://127.0.0.1:5001\"\nprogram_id = \"11111111111111111111111111111111\"\n";
+        let mut config: Config = toml::from_str(legacy).unwrap();
+        assert_eq!(config.migrate_to_profiles().as_deref(), Some("localnet"));
+        assert_eq!(config.migrate_to_profiles(), None);
+
+        let saved: toml::Table = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert!(!saved.contains_key("rpc_url"));
+        assert_eq!(saved["active_profile"].as_str(), Some("localnet"));
+        assert_eq!(saved["profiles"]["localnet"]["rpc_url"].as_str(), Some("http://127.0.0.1:8899"));
+        assert_eq!(saved["profiles"]["localnet"]["ipfs_url"].as_str(), Some("http://127.0.0.1:5001"));
+    }
+
+    #[test]
+    fn selected_profile_fills_network_keys_and_keeps_edits() {
+        let mut config = Config::default();
+        assert_eq!(config.migrate_to_profiles().as_deref(), Some("devnet"));
+        let localnet = NetworkProfile {
+            rpc_url: "http://127.0.0.1:8899".to_string(),
+            program_id: "11111111111111111111111111111111".to_string(),
+            ..config.network_profile()
+        };
+        config.profiles.insert("localnet".to_string(), localnet);
+
+        config.select_profile(Some("localnet")).unwrap();
+        assert_eq!((config.rpc_url.as_str(), config.program_id.as_str()), ("http://127.0.0.1:8899", "11111111111111111111111111111111"));
+        config.set("indexer_url", "http://127.0.0.1:3000").unwrap();
+
+        // Edits land in the selected profile; the active one is untouched
+        let reloaded: Config = toml::from_str(&config.to_toml().unwrap()).unwrap();
+        assert_eq!(reloaded.profiles["localnet"].indexer_url, "http://127.0.0.1:3000");
+        assert_eq!(reloaded.profiles["devnet"].indexer_url, Config::default_indexer_url());
+        assert_eq!(reloaded.active_profile.as_deref(), Some("devnet"));
+
+        let err = config.select_profile(Some("mainnet")).unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Config);
+        assert!(err.to_string().contains("devnet, localnet"));
+    }
+
+    #[test]
+    fn set_validates_values() {
+        let mut config = Config::default();
+        assert!(config.set("rpc_url", "not a url").is_err());
+        assert!(config.set("indexer_url", "ftp://example.com").is_err());
+        assert!(config.set("program_id", "not-a-pubkey").is_err());
+        assert!(config.set("ipfs_backend", "s3").is_err());
+        assert!(config.set("local_registry", "yes").is_err());
+        assert!(config.set("telemetry", "off").is_err());
+        assert!(config.set("tx_max_retries", "-1").is_err());
+        assert!(config.set("tx_commitment", "max").is_err());
+        assert!(config.set("commitment", "max").is_err());
+        assert!(config.set("rpc_timeout_secs", "0").is_err());
+        assert!(config.set("tx_confirm_timeout_secs", "0").is_err());
+        assert!(config.set("ipfs_read_timeout_secs", "0").is_err());
+        assert!(config.set("ipfs_download_timeout_secs", "soon").is_err());
+        assert!(config.set("priority_fee", "fast").is_err());
+        assert!(config.set("max_package_size", "huge").is_err());
+        assert!(config.set("wallet_path", "/definitely/missing/wallet.json").is_err());
+        config.set("wallet_path", "usb://ledger?key=1").unwrap();
+        assert!(config.set("wallet_path", "prompt://?key=not-a-path").is_err());
+        assert_eq!(config.rpc_url, Config::default().rpc_url);
+
+        config.set("rpc_url", "http://127.0.0.1:8899").unwrap();
+        config.set("program_id", "11111111111111111111111111111111").unwrap();
+        config.set("tx_commitment", "finalized").unwrap();
+        assert_eq!(config.commitment, Commitment::Finalized);
+        config.set("commitment", "processed").unwrap();
+        assert_eq!(config.get("tx_commitment").unwrap().as_deref(), Some("processed"));
+        config.set("priority_fee", "auto").unwrap();
+        assert_eq!(config.priority_fee, Some(PriorityFee::Auto));
+        assert_eq!(config.rpc_url, "http://127.0.0.1:8899");
+        assert_eq!(config.program_id, "11111111111111111111111111111111");
+    }
+
+    #[test]
+    fn retired_gateways_are_dropped_from_every_profile() {
+        let mut config = Config {
+            ipfs_gateways: vec!["https://ipfs.io".to_string(), "https://cloudflare-ipfs.com/".to_string()],
+            ..Config::default()
+        };
+        config.migrate_to_profiles();
+        let mut only_retired = config.network_profile();
+        only_retired.ipfs_gateways = vec!["https://cloudflare-ipfs.com".to_string()];
+        config.profiles.insert("mainnet".to_string(), only_retired);
+
+        assert!(config.drop_retired_gateways());
+        assert_eq!(config.ipfs_gateways, ["https://ipfs.io"]);
+        assert_eq!(config.profiles["devnet"].ipfs_gateways, ["https://ipfs.io"]);
+        assert_eq!(config.profiles["mainnet"].ipfs_gateways, Config::default_ipfs_gateways());
+        assert!(!config.drop_retired_gateways());
+    }
+
+    #[test]
+    fn ipfs_gateways_parse_as_an_ordered_list() {
+        let mut config = Config::default();
+        config.set("ipfs_gateways", "http://127.0.0.1:8080/, https://dweb.link").unwrap();
+        assert_eq!(config.ipfs_gateways, vec!["http://127.0.0.1:8080", "https://dweb.link"]);
+        assert_eq!(config.get("ipfs_gateways").unwrap().as_deref(), Some("http://127.0.0.1:8080,https://dweb.link"));
+        assert!(config.set("ipfs_gateways", "https://ipfs.io,not a url").is_err());
+        config.set("ipfs_gateways", "").unwrap();
+        assert_eq!(config.ipfs_gateways, Config::default_ipfs_gateways());
+    }
+
+    #[test]
+    fn unknown_key_lists_valid_keys() {
+        let err = Config::default().set("rpc", "x").unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Usage);
+        assert!(err.to_string().contains("rpc_url, ipfs_url"));
+        assert!(Config::default().get("nope").is_err());
+    }
+
+    #[test]
+    fn secrets_are_masked() {
+        assert!(is_secret_key("pinata_jwt"));
+        assert!(is_secret_key("pinning_token"));
+        assert!(!is_secret_key("rpc_url"));
+        assert_eq!(mask_secret("eyJhbGciOiJIUzI1NiJ9.payload"), "eyJh********");
+    }
+
+    #[test]
+    fn plaintext_jwt_moves_to_the_keyring_once() {
+        let store = MemoryStore::default();
+        let mut config = Config { pinata_jwt: Some("legacy-jwt".to_string()), ..Config::default() };
+        assert!(config.migrate_pinata_jwt(&store));
+        assert!(!config.migrate_pinata_jwt(&store));
+        assert_eq!(store.entries.borrow()["pinata_jwt"], "legacy-jwt");
+
+        // Only the marker is written back to the file
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("pinata_jwt_in_keyring = true"));
+        assert!(!saved.contains("legacy-jwt"));
+
+        let broken = MemoryStore { broken: true, ..MemoryStore::default() };
+        let mut config = Config { pinata_jwt: Some("legacy-jwt".to_string()), ..Config::default() };
+        assert!(!config.migrate_pinata_jwt(&broken));
+        assert_eq!(config.pinata_jwt.as_deref(), Some("legacy-jwt"));
+        assert!(!config.pinata_jwt_in_keyring);
+    }
+
+    #[test]
+    fn keyring_jwt_beats_the_environment() {
+        let store = MemoryStore::default();
+        let mut config = Config::default();
+        config.store_pinata_jwt(Some(&store), Some("keyring-jwt".to_string())).unwrap();
+        assert_eq!((config.pinata_jwt.as_deref(), config.pinata_jwt_in_keyring), (None, true));
+
+        let mut sources = file_sources();
+        config.apply_env(&mut sources, |var| (var == "PINATA_JWT").then(|| "env-jwt".to_string())).unwrap();
+        config.apply_keyring(&store, &mut sources);
+        assert_eq!(config.pinata_jwt.as_deref(), Some("keyring-jwt"));
+        assert_eq!(sources["pinata_jwt"], ConfigSource::Keyring);
+
+        // An entry deleted outside antsol leaves the environment's value
+        store.entries.borrow_mut().clear();
+        let mut config = Config { pinata_jwt_in_keyring: true, ..Config::default() };
+        config.apply_env(&mut sources, |var| (var == "PINATA_JWT").then(|| "env-jwt".to_string())).unwrap();
+        config.apply_keyring(&store, &mut sources);
+        assert_eq!(config.pinata_jwt.as_deref(), Some("env-jwt"));
+    }
+
+    #[test]
+    fn no_keyring_keeps_the_jwt_in_the_file() {
+        let store = MemoryStore::default();
+        let mut config = Config::default();
+        config.store_pinata_jwt(Some(&store), Some("jwt".to_string())).unwrap();
+        config.store_pinata_jwt(None::<&MemoryStore>, Some("plain-jwt".to_string())).unwrap();
+        assert_eq!((config.pinata_jwt.as_deref(), config.pinata_jwt_in_keyring), (Some("plain-jwt"), false));
+
+        config.store_pinata_jwt(Some(&store), None).unwrap();
+        assert!(store.entries.borrow().is_empty());
+        assert_eq!((config.pinata_jwt.as_deref(), config.pinata_jwt_in_keyring), (None, false));
+    }
+
+    fn file_sources() -> BTreeMap<&'static str, ConfigSource> {
+        CONFIG_KEYS.iter().map(|key| (*key, ConfigSource::File)).collect()
+    }
+
+    #[test]
+    fn env_overrides_file_values() {
+        let mut config = Config { rpc_url: "http://file:8899".to_string(), ..Config::default() };
+        let mut sources = file_sources();
+        config.apply_env(&mut sources, |var| match var {
+            "ANTSOL_RPC_URL" => Some("http://env:8899".to_string()),
+            "ANTSOL_WALLET_PATH" => Some("/ci/wallet.json".to_string()),
+            "PINATA_JWT" => Some("jwt-from-env".to_string()),
+            "ANTSOL_INDEXER_URL" => Some("".to_string()),
+            _ => None,
+        }).unwrap();
+
+        assert_eq!(config.rpc_url, "http://env:8899");
+        assert_eq!(config.wallet_path, Some(PathBuf::from("/ci/wallet.json")));
+        assert_eq!(config.pinata_jwt.as_deref(), Some("jwt-from-env"));
+        assert_eq!(config.indexer_url, Config::default_indexer_url());
+        assert_eq!(sources["rpc_url"], ConfigSource::Env("ANTSOL_RPC_URL"));
+        assert_eq!(sources["indexer_url"], ConfigSource::File);
+        assert_eq!(sources["program_id"], ConfigSource::File);
+    }
+
+    #[test]
+    fn commitment_keeps_its_old_name() {
+        let config: Config = toml::from_str("tx_commitment = \"finalized\"").unwrap();
+        assert_eq!(config.commitment, Commitment::Finalized);
+        assert!(config.to_toml().unwrap().contains("commitment = \"finalized\""));
+
+        let mut config = Config::default();
+        let mut sources = file_sources();
+        config.apply_env(&mut sources, |var| match var {
+            "ANTSOL_TX_COMMITMENT" => Some("processed".to_string()),
+            "ANTSOL_COMMITMENT" => Some("finalized".to_string()),
+            _ => None,
+        }).unwrap();
+        assert_eq!(config.commitment, Commitment::Finalized);
+        assert_eq!(sources["commitment"], ConfigSource::Env("ANTSOL_COMMITMENT"));
+    }
+
+    #[test]
+    fn invalid_env_value_names_the_variable() {
+        let err = Config::default()
+            .apply_env(&mut file_sources(), |var| (var == "ANTSOL_PROGRAM_ID").then(|| "nope".to_string()))
+            .unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Config);
+        assert!(err.to_string().contains("ANTSOL_PROGRAM_ID"));
+    }
+
+    #[test]
+    fn process_environment_takes_precedence_until_unset() {
+        // Only this test touches these variables
+        let lookup = |var: &str| std::env::var(var).ok();
+        std::env::set_var("ANTSOL_IPFS_URL", "http://127.0.0.1:5001");
+
+        let mut config = Config::default();
+        let mut sources = file_sources();
+        config.apply_env(&mut sources, lookup).unwrap();
+        assert_eq!(config.ipfs_url, "http://127.0.0.1:5001");
+        assert_eq!(sources["ipfs_url"].to_string(), "env (ANTSOL_IPFS_URL)");
+
+        std::env::remove_var("ANTSOL_IPFS_URL");
+        let mut config = Config::default();
+        let mut sources = file_sources();
+        config.apply_env(&mut sources, lookup).unwrap();
+        assert_eq!(config.ipfs_url, Config::default().ipfs_url);
+        assert_eq!(sources["ipfs_url"], ConfigSource::File);
+    }
+}
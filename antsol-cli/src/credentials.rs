@@ -0,0 +1,104 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use std::sync::OnceLock;
+
+/// Service name entries are filed under in the OS credential store
+pub const KEYRING_SERVICE: &str = "antsol";
+
+/// Somewhere secrets can live outside `config.toml`
+pub trait SecretStore {
+    /// The stored secret, `None` when there is no entry
+    fn get(&self, key: &str) -> Result<Option<String>>;
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+    /// Remove the entry; a missing entry is not an error
+    fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// The OS credential store: macOS Keychain, Windows Credential Manager,
+/// or Secret Service on Linux
+pub struct OsKeyring;
+
+impl OsKeyring {
+    fn entry(key: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, key).map_err(|e| keyring_error("open", key, e))
+    }
+}
+
+impl SecretStore for OsKeyring {
+    fn get(&self, key: &str) -> Result<Option<String>> {
+        match Self::entry(key)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(keyring_error("read", key, e)),
+        }
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        Self::entry(key)?.set_password(value).map_err(|e| keyring_error("store", key, e))
+    }
+
+    fn delete(&self, key: &str) -> Result<()> {
+        match Self::entry(key)?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(keyring_error("delete", key, e)),
+        }
+    }
+}
+
+fn keyring_error(action: &str, key: &str, e: keyring::Error) -> Box<dyn std::error::Error> {
+    AntSolError::Config(format!(
+        "Could not {} {} in the OS keyring ({}). Rerun with --no-keyring to keep it in config.toml instead",
+        action, key, e
+    )).into()
+}
+
+static KEYRING_DISABLED: OnceLock<bool> = OnceLock::new();
+
+/// Keep secrets in config.toml for this invocation (`--no-keyring`)
+pub fn disable_keyring() {
+    let _ = KEYRING_DISABLED.set(true);
+}
+
+/// The OS keyring, unless disabled for this invocation
+pub fn keyring() -> Option<OsKeyring> {
+    (!KEYRING_DISABLED.get().copied().unwrap_or(false)).then_some(OsKeyring)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// In-memory store; `broken` fails every call like a headless box without Secret Service
+    #[derive(Default)]
+    pub(crate) struct MemoryStore {
+        pub entries: RefCell<HashMap<String, String>>,
+        pub broken: bool,
+    }
+
+    impl SecretStore for MemoryStore {
+        fn get(&self, key: &str) -> Result<Option<String>> {
+            if self.broken {
+                return Err("no keyring available".into());
+            }
+            Ok(self.entries.borrow().get(key).cloned())
+        }
+
+        fn set(&self, key: &str, value: &str) -> Result<()> {
+            if self.broken {
+                return Err("no keyring available".into());
+            }
+            self.entries.borrow_mut().insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<()> {
+            if self.broken {
+                return Err("no keyring available".into());
+            }
+            self.entries.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+}
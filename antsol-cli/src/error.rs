@@ -0,0 +1,337 @@
+use serde::Serialize;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Error categories with stable process exit codes.
+///
+/// This is the single source of truth for the exit code catalog: `--help`,
+/// `antsol errors list` and the top-level handler in `main.rs` all read it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    General,
+    Usage,
+    Config,
+    Wallet,
+    Network,
+    Ipfs,
+    NotFound,
+    Verification,
+    Program,
+    RpcBudget,
+    Resolution,
+    InsufficientFunds,
+}
+
+impl ErrorKind {
+    pub const ALL: [ErrorKind; 12] = [
+        ErrorKind::General,
+        ErrorKind::Usage,
+        ErrorKind::Config,
+        ErrorKind::Wallet,
+        ErrorKind::Network,
+        ErrorKind::Ipfs,
+        ErrorKind::NotFound,
+        ErrorKind::Verification,
+        ErrorKind::Program,
+        ErrorKind::RpcBudget,
+        ErrorKind::Resolution,
+        ErrorKind::InsufficientFunds,
+    ];
+
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::General => 1,
+            ErrorKind::Usage => 2,
+            ErrorKind::Config => 3,
+            ErrorKind::Wallet => 4,
+            ErrorKind::Network => 5,
+            ErrorKind::Ipfs => 6,
+            ErrorKind::NotFound => 7,
+            ErrorKind::Verification => 8,
+            ErrorKind::Program => 9,
+            ErrorKind::RpcBudget => 10,
+            ErrorKind::Resolution => 11,
+            ErrorKind::InsufficientFunds => 12,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::General => "general",
+            ErrorKind::Usage => "usage",
+            ErrorKind::Config => "config",
+            ErrorKind::Wallet => "wallet",
+            ErrorKind::Network => "network",
+            ErrorKind::Ipfs => "ipfs",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Verification => "verification",
+            ErrorKind::Program => "program",
+            ErrorKind::RpcBudget => "rpc_budget",
+            ErrorKind::Resolution => "resolution",
+            ErrorKind::InsufficientFunds => "insufficient_funds",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ErrorKind::General => "Unexpected or uncategorized failure",
+            ErrorKind::Usage => "Invalid arguments, package spec, or version format",
+            ErrorKind::Config => "CLI config or antsol.toml manifest missing or invalid",
+            ErrorKind::Wallet => "Wallet not connected or keypair unreadable",
+            ErrorKind::Network => "Solana RPC or indexer unreachable or returned an error",
+            ErrorKind::Ipfs => "IPFS upload or download failed",
+            ErrorKind::NotFound => "Package or version not found",
+            ErrorKind::Verification => "Downloaded content failed integrity verification",
+            ErrorKind::Program => "Transaction rejected by the on-chain registry program",
+            ErrorKind::RpcBudget => "Command would exceed the configured max_rpc_calls",
+            ErrorKind::Resolution => "Dependency graph has a cycle or conflicting versions",
+            ErrorKind::InsufficientFunds => "Wallet balance can't cover account rent and fees",
+        }
+    }
+}
+
+/// One row of the machine-readable error catalog
+#[derive(Debug, Serialize)]
+pub struct CatalogEntry {
+    pub code: i32,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Full catalog, ordered by exit code
+pub fn catalog() -> Vec<CatalogEntry> {
+    ErrorKind::ALL
+        .iter()
+        .map(|kind| CatalogEntry {
+            code: kind.exit_code(),
+            name: kind.name(),
+            description: kind.description(),
+        })
+        .collect()
+}
+
+/// Exit code table appended to `antsol --help`
+pub fn exit_codes_help() -> String {
+    let mut help = String::from("Exit codes:\n  0  success\n");
+    for entry in catalog() {
+        help.push_str(&format!("  {:<2} {} - {}\n", entry.code, entry.name, entry.description));
+    }
+    help
+}
+
+/// Structured CLI error carrying its catalog category.
+///
+/// The string variants cover each category; the named variants carry a
+/// tailored message for failures users hit often.
+#[derive(Debug, thiserror::Error)]
+pub enum AntSolError {
+    #[error("No wallet connected. Use 'antsol wallet connect <keypair.json|prompt://|usb://ledger>' or 'antsol wallet new'")]
+    WalletNotConnected,
+    #[error("No antsol.toml found in {}. Run 'antsol init' first.", display_dir(.0))]
+    ManifestNotFound(PathBuf),
+    #[error("{}", package_not_found(.name, .version.as_deref()))]
+    PackageNotFound { name: String, version: Option<String> },
+    #[error("Solana RPC error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error(
+        "Insufficient funds: this needs about {} SOL but the wallet has {} SOL. Need {} more SOL: on devnet run 'antsol wallet airdrop' or 'solana airdrop 1', then retry.",
+        format_sol(*.needed),
+        format_sol(*.available),
+        format_sol(.needed.saturating_sub(*.available))
+    )]
+    InsufficientFunds { needed: u64, available: u64 },
+    #[error("{0}")]
+    Usage(String),
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    Wallet(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("{0}")]
+    Ipfs(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Verification(String),
+    #[error("{0}")]
+    Program(String),
+    #[error("{0}")]
+    RpcBudget(String),
+    #[error("{0}")]
+    Resolution(String),
+}
+
+impl AntSolError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            AntSolError::WalletNotConnected => ErrorKind::Wallet,
+            AntSolError::ManifestNotFound(_) => ErrorKind::Config,
+            AntSolError::PackageNotFound { .. } => ErrorKind::NotFound,
+            AntSolError::Rpc(_) => ErrorKind::Network,
+            AntSolError::InsufficientFunds { .. } => ErrorKind::InsufficientFunds,
+            AntSolError::Usage(_) => ErrorKind::Usage,
+            AntSolError::Config(_) => ErrorKind::Config,
+            AntSolError::Wallet(_) => ErrorKind::Wallet,
+            AntSolError::Network(_) => ErrorKind::Network,
+            AntSolError::Ipfs(_) => ErrorKind::Ipfs,
+            AntSolError::NotFound(_) => ErrorKind::NotFound,
+            AntSolError::Verification(_) => ErrorKind::Verification,
+            AntSolError::Program(_) => ErrorKind::Program,
+            AntSolError::RpcBudget(_) => ErrorKind::RpcBudget,
+            AntSolError::Resolution(_) => ErrorKind::Resolution,
+        }
+    }
+}
+
+fn display_dir(dir: &std::path::Path) -> String {
+    if dir.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        dir.display().to_string()
+    }
+}
+
+fn package_not_found(name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("Package {}@{} not found on-chain. Check the name and version with 'antsol search {}'", name, version, name),
+        None => format!("Package {} not found. Check the name with 'antsol search {}'", name, name),
+    }
+}
+
+fn format_sol(lamports: u64) -> String {
+    format!("{:.4}", solana_sdk::native_token::lamports_to_sol(lamports))
+}
+
+/// Map any error returned by a command handler onto the catalog
+pub fn error_kind(err: &(dyn Error + 'static)) -> ErrorKind {
+    if let Some(e) = err.downcast_ref::<AntSolError>() {
+        return e.kind();
+    }
+    if err.is::<solana_client::client_error::ClientError>() || err.is::<reqwest::Error>() {
+        return ErrorKind::Network;
+    }
+    if err.is::<toml::de::Error>() {
+        return ErrorKind::Config;
+    }
+    ErrorKind::General
+}
+
+/// Process exit code for an error returned by a command handler
+pub fn exit_code(err: &(dyn Error + 'static)) -> i32 {
+    error_kind(err).exit_code()
+}
+
+/// What a failed command prints under `--output json`
+#[derive(Debug, Serialize)]
+pub struct ErrorOutput {
+    pub error: ErrorReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    /// Exit code, as in the catalog
+    pub code: i32,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl ErrorOutput {
+    pub fn new(err: &(dyn Error + 'static)) -> Self {
+        let kind = error_kind(err);
+        Self {
+            error: ErrorReport { code: kind.exit_code(), kind: kind.name(), message: err.to_string() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalog_codes_are_unique_and_stable() {
+        let codes: HashSet<i32> = catalog().iter().map(|e| e.code).collect();
+        assert_eq!(codes.len(), ErrorKind::ALL.len());
+        assert_eq!(ErrorKind::Usage.exit_code(), 2);
+        assert_eq!(ErrorKind::Config.exit_code(), 3);
+        assert_eq!(ErrorKind::Wallet.exit_code(), 4);
+        assert_eq!(ErrorKind::Network.exit_code(), 5);
+        assert_eq!(ErrorKind::Ipfs.exit_code(), 6);
+        assert_eq!(ErrorKind::NotFound.exit_code(), 7);
+        assert_eq!(ErrorKind::Verification.exit_code(), 8);
+        assert_eq!(ErrorKind::Program.exit_code(), 9);
+        assert_eq!(ErrorKind::InsufficientFunds.exit_code(), 12);
+    }
+
+    #[test]
+    fn named_variants_have_tailored_messages() {
+        let err = AntSolError::PackageNotFound { name: "foo".into(), version: Some("1.0.0".into()) };
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("Package foo@1.0.0 not found on-chain"));
+
+        let err = AntSolError::ManifestNotFound(PathBuf::from("pkgs/app"));
+        assert_eq!(err.kind(), ErrorKind::Config);
+        assert!(err.to_string().contains("No antsol.toml found in pkgs/app"));
+
+        let err = AntSolError::InsufficientFunds { needed: 11_000_000, available: 1_000_000 };
+        assert_eq!(exit_code(&err), 12);
+        assert!(err.to_string().contains("0.0110 SOL but the wallet has 0.0010 SOL"));
+        assert!(err.to_string().contains("Need 0.0100 more SOL"));
+
+        assert_eq!(AntSolError::WalletNotConnected.kind(), ErrorKind::Wallet);
+    }
+
+    #[test]
+    fn exit_code_maps_structured_and_foreign_errors() {
+        let not_found: Box<dyn Error> = AntSolError::NotFound("missing".into()).into();
+        assert_eq!(exit_code(not_found.as_ref()), 7);
+
+        let toml_err: Box<dyn Error> = toml::from_str::<toml::Value>("= broken").unwrap_err().into();
+        assert_eq!(exit_code(toml_err.as_ref()), 3);
+
+        let plain: Box<dyn Error> = "something odd".into();
+        assert_eq!(exit_code(plain.as_ref()), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_failures_map_to_catalog_codes() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = crate::commands::publish::handle_publish(dir.path().to_path_buf(), false, None, None, false, false, false, false, None, false, false, Default::default())
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(err.as_ref()), ErrorKind::Config.exit_code());
+
+        let err = crate::commands::update::handle_update(dir.path().to_path_buf(), Some("1.0".into()), None, false, None, Default::default())
+            .await
+            .unwrap_err();
+        assert_eq!(exit_code(err.as_ref()), ErrorKind::Usage.exit_code());
+
+        let client = crate::solana_client::tests::client_with(&[], Some(0));
+        let err = client.get_package("foo", "1.0.0").unwrap_err();
+        assert_eq!(exit_code(err.as_ref()), ErrorKind::RpcBudget.exit_code());
+    }
+
+    #[test]
+    fn json_error_shape_is_stable() {
+        let err = AntSolError::NotFound("Namespace 'acme' is not registered".into());
+        assert_eq!(serde_json::to_value(ErrorOutput::new(&err)).unwrap(), serde_json::json!({
+            "error": {
+                "code": 7,
+                "kind": "not_found",
+                "message": "Namespace 'acme' is not registered"
+            }
+        }));
+    }
+
+    #[test]
+    fn help_lists_every_code() {
+        let help = exit_codes_help();
+        for entry in catalog() {
+            assert!(help.contains(entry.name));
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::types::Result;
+use crate::utils::format_size;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+use tar::{Archive, EntryType};
+
+/// What an archive may expand to. Archives come from whoever published the
+/// package, so every limit is enforced while reading, before anything
+/// oversized reaches the disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtractLimits {
+    /// Sum of the sizes of every extracted file, in bytes
+    pub max_total_size: u64,
+    /// Largest single extracted file, in bytes
+    pub max_file_size: u64,
+    /// Files, directories and links together
+    pub max_entries: usize,
+    /// Extract symlinks whose target stays inside the package instead of rejecting them
+    pub allow_symlinks: bool,
+}
+
+impl ExtractLimits {
+    pub const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_total_size: config.max_extracted_size,
+            max_file_size: config.max_extracted_file_size,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_size: Config::default_max_extracted_size(),
+            max_file_size: Config::default_max_extracted_file_size(),
+            max_entries: Self::DEFAULT_MAX_ENTRIES,
+            allow_symlinks: false,
+        }
+    }
+}
+
+/// Extract a tar.gz archive into `output_path`, rejecting anything that
+/// could write outside it or exhaust the disk:
+///
+/// - absolute paths and `..` components
+/// - hard links, devices, FIFOs and sparse files
+/// - symlinks, unless `limits.allow_symlinks`; even then their targets must
+///   stay inside `output_path`, and they are only created once every file is
+///   written so no entry can be written through one
+/// - more entries, or larger files, than `limits` allows
+pub fn extract_archive(archive_path: &Path, output_path: &Path, limits: &ExtractLimits) -> Result<()> {
+    let mut archive = Archive::new(GzDecoder::new(File::open(archive_path)?));
+
+    std::fs::create_dir_all(output_path)?;
+    let root = output_path.canonicalize()?;
+
+    let mut entries = 0usize;
+    let mut total_size = 0u64;
+    let mut symlinks = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if matches!(entry_type, EntryType::XGlobalHeader | EntryType::XHeader | EntryType::GNULongName | EntryType::GNULongLink) {
+            // Metadata for the next entry, not an entry of its own
+            continue;
+        }
+
+        entries += 1;
+        if entries > limits.max_entries {
+            return Err(malicious(format!("Archive has more than {} entries", limits.max_entries)));
+        }
+
+        let path = entry.path()?.into_owned();
+        let relative = contained_path(&path)?;
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+        let output_file = root.join(&relative);
+
+        match entry_type {
+            EntryType::Directory => {
+                ensure_inside(&root, &output_file)?;
+                std::fs::create_dir_all(&output_file)?;
+            }
+            EntryType::Regular | EntryType::Continuous => {
+                let size = entry.size();
+                if size > limits.max_file_size {
+                    return Err(malicious(format!(
+                        "{} would extract to {}, over the {} per-file limit",
+                        path.display(), format_size(size), format_size(limits.max_file_size)
+                    )));
+                }
+                total_size += size;
+                if total_size > limits.max_total_size {
+                    return Err(malicious(format!(
+                        "Archive would extract to more than {}; raise max_extracted_size to install it",
+                        format_size(limits.max_total_size)
+                    )));
+                }
+                if let Some(parent) = output_file.parent() {
+                    ensure_inside(&root, parent)?;
+                    std::fs::create_dir_all(parent)?;
+                }
+                // Never write through a link left in the directory by an earlier install
+                if std::fs::symlink_metadata(&output_file).is_ok_and(|m| m.file_type().is_symlink()) {
+                    std::fs::remove_file(&output_file)?;
+                }
+                entry.unpack(&output_file)?;
+            }
+            EntryType::Symlink if limits.allow_symlinks => {
+                let target = entry.link_name()?.ok_or_else(|| malicious(format!("Symlink {} has no target", path.display())))?;
+                let resolved = relative.parent().unwrap_or(Path::new("")).join(&target);
+                if target.is_absolute() || contained_path(&resolved).is_err() {
+                    return Err(malicious(format!("Symlink {} points outside the package ({})", path.display(), target.display())));
+                }
+                symlinks.push((output_file, target.into_owned()));
+            }
+            EntryType::Symlink => {
+                return Err(malicious(format!(
+                    "Archive contains a symlink ({}); pass --allow-symlinks to extract links that stay inside the package",
+                    path.display()
+                )));
+            }
+            EntryType::Link => {
+                return Err(malicious(format!("Archive contains a hard link ({})", path.display())));
+            }
+            other => {
+                return Err(malicious(format!("Archive entry {} has unsupported type {:?}", path.display(), other)));
+            }
+        }
+    }
+
+    for (link, target) in &symlinks {
+        if let Some(parent) = link.parent() {
+            ensure_inside(&root, parent)?;
+            std::fs::create_dir_all(parent)?;
+        }
+        if std::fs::symlink_metadata(link).is_ok() {
+            return Err(malicious(format!("Symlink {} would replace an extracted file", link.display())));
+        }
+        create_symlink(target, link)?;
+    }
+    // Links through other links can still escape once they all exist
+    for (link, target) in &symlinks {
+        if let Ok(resolved) = link.canonicalize() {
+            if !resolved.starts_with(&root) {
+                for (link, _) in &symlinks {
+                    let _ = std::fs::remove_file(link);
+                }
+                return Err(malicious(format!("Symlink {} resolves outside the package ({})", link.display(), target.display())));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn malicious(reason: String) -> Box<dyn std::error::Error> {
+    AntSolError::Verification(format!("Malicious archive rejected: {}", reason)).into()
+}
+
+/// `path` with `.` components dropped, rejected when it is absolute or
+/// climbs out with `..` at any point
+fn contained_path(path: &Path) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir if normalized.pop() => {}
+            _ => return Err(malicious(format!("{} escapes the package directory", path.display()))),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Check that the deepest existing ancestor of `path` really is inside
+/// `root`, so creating directories can't follow a link out of it
+fn ensure_inside(root: &Path, path: &Path) -> Result<()> {
+    let mut existing = path;
+    while std::fs::symlink_metadata(existing).is_err() {
+        match existing.parent() {
+            Some(parent) => existing = parent,
+            None => break,
+        }
+    }
+    match existing.canonicalize() {
+        Ok(resolved) if resolved.starts_with(root) => Ok(()),
+        _ => Err(malicious(format!("{} escapes the package directory", path.display()))),
+    }
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tar::Header;
+
+    /// Entry of a hand-built archive. Names and link targets are written
+    /// into the header raw, bypassing the checks `tar::Builder` would apply.
+    struct Raw<'a> {
+        path: &'a str,
+        kind: EntryType,
+        data: &'a [u8],
+        link: Option<&'a str>,
+    }
+
+    fn file<'a>(path: &'a str, data: &'a [u8]) -> Raw<'a> {
+        Raw { path, kind: EntryType::Regular, data, link: None }
+    }
+
+    fn link<'a>(kind: EntryType, path: &'a str, target: &'a str) -> Raw<'a> {
+        Raw { path, kind, data: b"", link: Some(target) }
+    }
+
+    fn archive(dir: &Path, entries: &[Raw]) -> PathBuf {
+        let path = dir.join("hostile.tar.gz");
+        let mut builder = tar::Builder::new(GzEncoder::new(File::create(&path).unwrap(), Compression::default()));
+        for entry in entries {
+            let mut header = Header::new_gnu();
+            let gnu = header.as_gnu_mut().unwrap();
+            gnu.name[..entry.path.len()].copy_from_slice(entry.path.as_bytes());
+            if let Some(target) = entry.link {
+                gnu.linkname[..target.len()].copy_from_slice(target.as_bytes());
+            }
+            header.set_entry_type(entry.kind);
+            header.set_size(entry.data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, entry.data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    fn extract(entries: &[Raw], limits: ExtractLimits) -> (tempfile::TempDir, Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = archive(dir.path(), entries);
+        let result = extract_archive(&archive, &dir.path().join("out"), &limits);
+        (dir, result)
+    }
+
+    fn rejected(entries: &[Raw], limits: ExtractLimits) -> String {
+        let (_dir, result) = extract(entries, limits);
+        let err = result.unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Verification);
+        err.to_string()
+    }
+
+    #[test]
+    fn extracts_an_ordinary_archive() {
+        let (dir, result) = extract(&[file("./src/lib.rs", b"pub fn ok() {}"), file("a..b.txt", b"dots")], ExtractLimits::default());
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("out/src/lib.rs")).unwrap(), "pub fn ok() {}");
+        assert!(dir.path().join("out/a..b.txt").exists());
+    }
+
+    #[test]
+    fn rejects_paths_outside_the_output_directory() {
+        for path in ["../escape.txt", "src/../../escape.txt", "/tmp/antsol-absolute.txt"] {
+            let message = rejected(&[file(path, b"x")], ExtractLimits::default());
+            assert!(message.contains("escapes the package directory"), "{}: {}", path, message);
+        }
+        assert!(!Path::new("/tmp/antsol-absolute.txt").exists());
+    }
+
+    #[test]
+    fn rejects_links_and_special_files() {
+        let symlink = rejected(&[link(EntryType::Symlink, "evil", "/etc/passwd")], ExtractLimits::default());
+        assert!(symlink.contains("--allow-symlinks"));
+        let hard = rejected(&[link(EntryType::Link, "evil", "/etc/passwd")], ExtractLimits::default());
+        assert!(hard.contains("hard link"));
+        let device = rejected(&[Raw { path: "dev", kind: EntryType::Char, data: b"", link: None }], ExtractLimits::default());
+        assert!(device.contains("unsupported type"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn allowed_symlinks_must_stay_inside_and_are_never_written_through() {
+        let allow = ExtractLimits { allow_symlinks: true, ..ExtractLimits::default() };
+
+        let (dir, result) = extract(&[file("src/lib.rs", b"lib"), link(EntryType::Symlink, "docs/lib.rs", "../src/lib.rs")], allow);
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("out/docs/lib.rs")).unwrap(), "lib");
+
+        for target in ["/etc", "../..", "src/../../.."] {
+            let message = rejected(&[link(EntryType::Symlink, "out-link", target)], allow);
+            assert!(message.contains("points outside the package"), "{}: {}", target, message);
+        }
+
+        // `d -> .` makes `d/up -> ..` climb one level further than it looks
+        let message = rejected(&[link(EntryType::Symlink, "d", "."), link(EntryType::Symlink, "d/up", "..")], allow);
+        assert!(message.contains("resolves outside the package"), "{}", message);
+
+        // Files are written before any link exists, so one can't land behind a link
+        let message = rejected(&[link(EntryType::Symlink, "sub", "real"), file("sub/file", b"x")], allow);
+        assert!(message.contains("would replace an extracted file"), "{}", message);
+    }
+
+    #[test]
+    fn enforces_size_and_entry_limits() {
+        let limits = ExtractLimits { max_total_size: 1000, max_file_size: 600, max_entries: 3, allow_symlinks: false };
+        let big = vec![0u8; 700];
+        let half = vec![0u8; 500];
+
+        assert!(rejected(&[file("big", &big)], limits).contains("per-file limit"));
+        assert!(rejected(&[file("a", &half), file("b", &half), file("c", b"!")], limits).contains("max_extracted_size"));
+        let many: Vec<Raw> = ["a", "b", "c", "d"].iter().map(|name| file(name, b"")).collect();
+        assert!(rejected(&many, limits).contains("more than 3 entries"));
+
+        let (_dir, result) = extract(&[file("a", &half), file("b", &half)], limits);
+        result.unwrap();
+    }
+}
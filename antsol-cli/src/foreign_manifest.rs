@@ -0,0 +1,325 @@
+use crate::error::AntSolError;
+use crate::types::{ExternalDependency, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Another ecosystem's manifest that `antsol init` can scaffold from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestKind {
+    Cargo,
+    Npm,
+}
+
+impl ManifestKind {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ManifestKind::Cargo => "Cargo.toml",
+            ManifestKind::Npm => "package.json",
+        }
+    }
+}
+
+/// Package metadata read from a Cargo.toml or package.json. Values are as
+/// written there; `antsol init` checks them against the registry's rules.
+#[derive(Debug, Default)]
+pub struct ForeignManifest {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+    pub dependencies: Vec<ExternalDependency>,
+    /// Dependencies without a registry version (path, git, file: ...), left out
+    pub skipped: Vec<String>,
+}
+
+/// The manifest in `dir` to offer scaffolding from, Cargo.toml first
+pub fn detect(dir: &Path) -> Option<ManifestKind> {
+    [ManifestKind::Cargo, ManifestKind::Npm].into_iter().find(|kind| dir.join(kind.file_name()).is_file())
+}
+
+pub fn load(kind: ManifestKind, dir: &Path) -> Result<ForeignManifest> {
+    let path = dir.join(kind.file_name());
+    if !path.is_file() {
+        return Err(AntSolError::Usage(format!("No {} in {}", kind.file_name(), dir.display())).into());
+    }
+    match kind {
+        ManifestKind::Cargo => from_cargo(&path),
+        ManifestKind::Npm => from_npm(&path),
+    }
+}
+
+fn read_toml(path: &Path) -> Result<Table> {
+    std::fs::read_to_string(path)?
+        .parse::<Table>()
+        .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", path.display(), e)).into())
+}
+
+/// Read a Cargo.toml. A virtual workspace manifest is followed to its one
+/// member with a `[package]`, and `workspace = true` fields and
+/// dependencies are resolved against the enclosing workspace.
+fn from_cargo(path: &Path) -> Result<ForeignManifest> {
+    let root = read_toml(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let (manifest, workspace) = if root.contains_key("package") {
+        let workspace = if root.contains_key("workspace") { Some(root.clone()) } else { find_workspace(dir) };
+        (root, workspace)
+    } else if root.contains_key("workspace") {
+        (single_member(path, &root)?, Some(root))
+    } else {
+        return Err(AntSolError::Usage(format!("{} has neither [package] nor [workspace]", path.display())).into());
+    };
+
+    let workspace = workspace.as_ref().and_then(|w| w.get("workspace")).and_then(Value::as_table);
+    let inherited = workspace.and_then(|w| w.get("package")).and_then(Value::as_table);
+    let package = manifest.get("package").and_then(Value::as_table).cloned().unwrap_or_default();
+    let field = |key: &str| -> Option<&Value> {
+        match package.get(key)? {
+            Value::Table(t) if t.get("workspace").and_then(Value::as_bool) == Some(true) => inherited?.get(key),
+            value => Some(value),
+        }
+    };
+    let text = |key: &str| field(key).and_then(Value::as_str).map(str::to_string);
+
+    let mut imported = ForeignManifest {
+        name: text("name"),
+        version: text("version"),
+        description: text("description"),
+        authors: field("authors")
+            .and_then(Value::as_array)
+            .map(|authors| authors.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default(),
+        license: text("license"),
+        ..Default::default()
+    };
+
+    let shared = workspace.and_then(|w| w.get("dependencies")).and_then(Value::as_table);
+    for (key, spec) in manifest.get("dependencies").and_then(Value::as_table).into_iter().flatten() {
+        let spec = match spec {
+            Value::Table(t) if t.get("workspace").and_then(Value::as_bool) == Some(true) => shared.and_then(|s| s.get(key)),
+            spec => Some(spec),
+        };
+        match spec.and_then(|spec| cargo_dependency(key, spec)) {
+            Some(dep) => imported.dependencies.push(dep),
+            None => imported.skipped.push(key.clone()),
+        }
+    }
+    Ok(imported)
+}
+
+/// A crates.io dependency; `None` for path and git dependencies without a version
+fn cargo_dependency(key: &str, spec: &Value) -> Option<ExternalDependency> {
+    let (name, version) = match spec {
+        Value::String(version) => (key, version.as_str()),
+        Value::Table(t) => (
+            t.get("package").and_then(Value::as_str).unwrap_or(key),
+            t.get("version").and_then(Value::as_str)?,
+        ),
+        _ => return None,
+    };
+    Some(ExternalDependency {
+        name: name.to_string(),
+        version: version.to_string(),
+        dep_type: "rust".to_string(),
+        registry: Some("crates.io".to_string()),
+    })
+}
+
+/// The closest ancestor manifest declaring a `[workspace]`, as cargo finds it
+fn find_workspace(dir: &Path) -> Option<Table> {
+    dir.ancestors()
+        .skip(1)
+        .map(|ancestor| ancestor.join("Cargo.toml"))
+        .filter(|path| path.is_file())
+        .filter_map(|path| read_toml(&path).ok())
+        .find(|table| table.contains_key("workspace"))
+}
+
+/// The manifest of the only workspace member with a `[package]`
+fn single_member(path: &Path, root: &Table) -> Result<Table> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let patterns = root
+        .get("workspace")
+        .and_then(|w| w.get("members"))
+        .and_then(Value::as_array)
+        .map(|members| members.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let mut packages = Vec::new();
+    for member in patterns.into_iter().flat_map(|pattern| expand_member(dir, pattern)) {
+        let manifest = read_toml(&member.join("Cargo.toml"))?;
+        if manifest.contains_key("package") {
+            packages.push((member, manifest));
+        }
+    }
+    match packages.len() {
+        0 => Err(AntSolError::Usage(format!("Workspace {} has no member with a [package]", path.display())).into()),
+        1 => Ok(packages.remove(0).1),
+        _ => {
+            let members: Vec<String> = packages
+                .iter()
+                .map(|(member, _)| member.strip_prefix(dir).unwrap_or(member).display().to_string())
+                .collect();
+            Err(AntSolError::Usage(format!(
+                "Workspace {} has several packages ({}); run antsol init in the member to publish",
+                path.display(),
+                members.join(", ")
+            ))
+            .into())
+        }
+    }
+}
+
+/// Member directories for a `members` entry; a trailing `/*` matches every
+/// subdirectory with a Cargo.toml
+fn expand_member(dir: &Path, pattern: &str) -> Vec<PathBuf> {
+    let Some(parent) = pattern.strip_suffix("/*") else {
+        return vec![dir.join(pattern)];
+    };
+    let mut members: Vec<PathBuf> = std::fs::read_dir(dir.join(parent))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join("Cargo.toml").is_file())
+        .collect();
+    members.sort();
+    members
+}
+
+/// The bits of package.json `antsol init` uses
+#[derive(Debug, Deserialize)]
+struct PackageJson {
+    name: Option<String>,
+    version: Option<String>,
+    description: Option<String>,
+    author: Option<Person>,
+    #[serde(default)]
+    contributors: Vec<Person>,
+    license: Option<String>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+}
+
+/// An npm person field: `"Name <email>"` or `{ "name": ..., "email": ... }`
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Person {
+    Text(String),
+    Object { name: String, email: Option<String> },
+}
+
+impl Person {
+    fn into_author(self) -> String {
+        match self {
+            Person::Text(text) => text,
+            Person::Object { name, email: Some(email) } => format!("{} <{}>", name, email),
+            Person::Object { name, email: None } => name,
+        }
+    }
+}
+
+fn from_npm(path: &Path) -> Result<ForeignManifest> {
+    let package: PackageJson = serde_json::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", path.display(), e)))?;
+
+    let mut imported = ForeignManifest {
+        name: package.name,
+        version: package.version,
+        description: package.description,
+        authors: package.author.into_iter().chain(package.contributors).map(Person::into_author).collect(),
+        license: package.license,
+        ..Default::default()
+    };
+    for (name, version) in package.dependencies {
+        // file:, git+https:, workspace:, github shorthand "user/repo" and the like
+        if version.contains(':') || version.contains('/') {
+            imported.skipped.push(name);
+            continue;
+        }
+        imported.dependencies.push(ExternalDependency {
+            name,
+            version,
+            dep_type: "npm".to_string(),
+            registry: Some("npmjs.com".to_string()),
+        });
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/manifests").join(name)
+    }
+
+    fn deps(imported: &ForeignManifest) -> Vec<(String, String, String, Option<String>)> {
+        imported
+            .dependencies
+            .iter()
+            .map(|d| (d.name.clone(), d.version.clone(), d.dep_type.clone(), d.registry.clone()))
+            .collect()
+    }
+
+    fn crates_io(name: &str, version: &str) -> (String, String, String, Option<String>) {
+        (name.to_string(), version.to_string(), "rust".to_string(), Some("crates.io".to_string()))
+    }
+
+    #[test]
+    fn reads_a_crate_and_its_registry_dependencies() {
+        let dir = fixture("cargo-crate");
+        assert_eq!(detect(&dir), Some(ManifestKind::Cargo));
+        let imported = load(ManifestKind::Cargo, &dir).unwrap();
+        assert_eq!(imported.name.as_deref(), Some("token_utils"));
+        assert_eq!(imported.version.as_deref(), Some("1.2.3"));
+        assert_eq!(imported.description.as_deref(), Some("Helpers for SPL tokens"));
+        assert_eq!(imported.authors, vec!["Ada <ada@example.com>", "Grace"]);
+        assert_eq!(imported.license.as_deref(), Some("MIT OR Apache-2.0"));
+        assert_eq!(deps(&imported), vec![crates_io("anchor-lang", "0.30.1"), crates_io("serde", "1")]);
+        assert_eq!(imported.skipped, vec!["local-helpers"]);
+    }
+
+    #[test]
+    fn workspace_root_follows_its_member_and_resolves_inherited_fields() {
+        for dir in [fixture("cargo-workspace"), fixture("cargo-workspace/crates/vault")] {
+            let imported = load(ManifestKind::Cargo, &dir).unwrap();
+            assert_eq!(imported.name.as_deref(), Some("vault"), "{}", dir.display());
+            assert_eq!(imported.version.as_deref(), Some("0.4.0"));
+            assert_eq!(imported.description.as_deref(), Some("Vault helpers"));
+            assert_eq!(imported.authors, vec!["Ada <ada@example.com>"]);
+            assert_eq!(imported.license.as_deref(), Some("MIT"));
+            assert_eq!(
+                deps(&imported),
+                vec![crates_io("borsh", "1.5"), crates_io("sha2", "0.10"), crates_io("thiserror", "1")]
+            );
+        }
+    }
+
+    #[test]
+    fn workspace_with_several_packages_asks_for_the_member() {
+        let err = load(ManifestKind::Cargo, &fixture("cargo-multi")).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Usage);
+        assert!(err.to_string().contains("(alpha, beta)"), "{}", err);
+    }
+
+    #[test]
+    fn reads_package_json_people_and_npm_dependencies() {
+        let dir = fixture("npm-package");
+        assert_eq!(detect(&dir), Some(ManifestKind::Npm));
+        let imported = load(ManifestKind::Npm, &dir).unwrap();
+        assert_eq!(imported.name.as_deref(), Some("@acme/wallet-kit"));
+        assert_eq!(imported.version.as_deref(), Some("2.1.0"));
+        assert_eq!(imported.authors, vec!["Ada <ada@example.com>", "Grace"]);
+        assert_eq!(imported.license.as_deref(), Some("ISC"));
+        let npm = |name: &str, version: &str| (name.to_string(), version.to_string(), "npm".to_string(), Some("npmjs.com".to_string()));
+        assert_eq!(deps(&imported), vec![npm("@solana/web3.js", "^1.95.0"), npm("bs58", "~6.0.0")]);
+        assert_eq!(imported.skipped, vec!["local-utils"]);
+
+        let err = load(ManifestKind::Npm, &fixture("cargo-crate")).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Usage);
+    }
+}
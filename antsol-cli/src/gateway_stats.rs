@@ -0,0 +1,199 @@
+use crate::config::Config;
+use crate::types::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Public IPFS gateways tried when downloading, before ranking
+pub const DEFAULT_GATEWAYS: [&str; 3] = [
+    "https://gateway.pinata.cloud",
+    "https://ipfs.io",
+    "https://dweb.link",
+];
+
+/// Gateways that have shut down; they are dropped from configs that still list them
+pub const RETIRED_GATEWAYS: [&str; 1] = ["https://cloudflare-ipfs.com"];
+
+/// Result of a single gateway request.
+///
+/// Serialized names match the indexer's `GatewayOutcome` so stats from both
+/// sides can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayOutcome {
+    Success,
+    HttpError,
+    Timeout,
+    ConnectError,
+    VerificationFailed,
+}
+
+impl GatewayOutcome {
+    pub fn is_success(self) -> bool {
+        self == GatewayOutcome::Success
+    }
+}
+
+/// Aggregated outcomes for one gateway
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GatewayRecord {
+    pub successes: u64,
+    pub failures: u64,
+    /// Sum of latencies of successful requests, in milliseconds
+    pub success_latency_ms: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failures_by_outcome: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_outcome: Option<GatewayOutcome>,
+}
+
+impl GatewayRecord {
+    pub fn attempts(&self) -> u64 {
+        self.successes + self.failures
+    }
+
+    /// Raw success rate, `None` before the first attempt
+    pub fn success_rate(&self) -> Option<f64> {
+        match self.attempts() {
+            0 => None,
+            n => Some(self.successes as f64 / n as f64),
+        }
+    }
+
+    /// Mean latency of successful requests in milliseconds
+    pub fn average_latency_ms(&self) -> Option<f64> {
+        match self.successes {
+            0 => None,
+            n => Some(self.success_latency_ms as f64 / n as f64),
+        }
+    }
+
+    /// Success rate with add-one smoothing, so an untried gateway scores 0.5
+    /// and a single failure doesn't bury a gateway forever
+    pub fn score(&self) -> f64 {
+        (self.successes as f64 + 1.0) / (self.attempts() as f64 + 2.0)
+    }
+}
+
+/// Per-gateway stats persisted in `~/.antsol/gateway-stats.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GatewayStats {
+    pub gateways: BTreeMap<String, GatewayRecord>,
+}
+
+impl GatewayStats {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(Config::config_dir()?.join("gateway-stats.json"))
+    }
+
+    /// Load stats, starting empty when the file is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, gateway: &str, outcome: GatewayOutcome, latency: Duration) {
+        let record = self.gateways.entry(gateway.to_string()).or_default();
+        if outcome.is_success() {
+            record.successes += 1;
+            record.success_latency_ms += latency.as_millis() as u64;
+        } else {
+            record.failures += 1;
+            let key = serde_json::to_value(outcome)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default();
+            *record.failures_by_outcome.entry(key).or_insert(0) += 1;
+        }
+        record.last_outcome = Some(outcome);
+    }
+
+    /// Order gateways best-first: smoothed success rate, then average latency.
+    /// Ties keep the caller's order.
+    pub fn rank(&self, gateways: &[&str]) -> Vec<String> {
+        let empty = GatewayRecord::default();
+        let mut ranked: Vec<(&str, &GatewayRecord)> = gateways
+            .iter()
+            .map(|g| (*g, self.gateways.get(*g).unwrap_or(&empty)))
+            .collect();
+        ranked.sort_by(|(_, a), (_, b)| {
+            b.score()
+                .partial_cmp(&a.score())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| {
+                    let la = a.average_latency_ms().unwrap_or(f64::INFINITY);
+                    let lb = b.average_latency_ms().unwrap_or(f64::INFINITY);
+                    la.partial_cmp(&lb).unwrap_or(std::cmp::Ordering::Equal)
+                })
+        });
+        ranked.into_iter().map(|(g, _)| g.to_string()).collect()
+    }
+
+    /// Best-ranked gateway among `gateways`
+    pub fn preferred(&self, gateways: &[&str]) -> Option<String> {
+        self.rank(gateways).into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_aggregates_rates_and_latency() {
+        let mut stats = GatewayStats::default();
+        stats.record("https://a", GatewayOutcome::Success, Duration::from_millis(100));
+        stats.record("https://a", GatewayOutcome::Success, Duration::from_millis(300));
+        stats.record("https://a", GatewayOutcome::Timeout, Duration::from_millis(5000));
+
+        let a = &stats.gateways["https://a"];
+        assert_eq!(a.attempts(), 3);
+        assert_eq!(a.success_rate(), Some(2.0 / 3.0));
+        assert_eq!(a.average_latency_ms(), Some(200.0));
+        assert_eq!(a.score(), 3.0 / 5.0);
+        assert_eq!(a.failures_by_outcome["timeout"], 1);
+        assert_eq!(a.last_outcome, Some(GatewayOutcome::Timeout));
+
+        let untried = GatewayRecord::default();
+        assert_eq!(untried.success_rate(), None);
+        assert_eq!(untried.average_latency_ms(), None);
+        assert_eq!(untried.score(), 0.5);
+    }
+
+    #[test]
+    fn rank_prefers_reliable_then_fast_gateways() {
+        let mut stats = GatewayStats::default();
+        stats.record("https://flaky", GatewayOutcome::HttpError, Duration::ZERO);
+        stats.record("https://slow", GatewayOutcome::Success, Duration::from_millis(900));
+        stats.record("https://fast", GatewayOutcome::Success, Duration::from_millis(50));
+
+        let ranked = stats.rank(&["https://flaky", "https://new", "https://slow", "https://fast"]);
+        assert_eq!(ranked, vec!["https://fast", "https://slow", "https://new", "https://flaky"]);
+        assert_eq!(stats.preferred(&DEFAULT_GATEWAYS), Some(DEFAULT_GATEWAYS[0].to_string()));
+    }
+
+    #[test]
+    fn stats_round_trip_and_tolerate_corrupt_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway-stats.json");
+
+        let mut stats = GatewayStats::default();
+        stats.record("https://a", GatewayOutcome::ConnectError, Duration::ZERO);
+        stats.save(&path).unwrap();
+        assert_eq!(GatewayStats::load(&path), stats);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert_eq!(GatewayStats::load(&path), GatewayStats::default());
+    }
+}
@@ -0,0 +1,617 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use solana_sdk::pubkey::Pubkey;
+use std::io::Read;
+
+pub const PUBLISH_PACKAGE: &str = "publish_package";
+pub const UPDATE_PACKAGE: &str = "update_package";
+pub const REGISTER_NAMESPACE: &str = "register_namespace";
+pub const SET_NAMESPACE_PUBLISHERS: &str = "set_namespace_publishers";
+pub const ADD_MAINTAINER: &str = "add_maintainer";
+pub const REMOVE_MAINTAINER: &str = "remove_maintainer";
+pub const RECORD_DOWNLOAD: &str = "record_download";
+pub const FREEZE_PACKAGE: &str = "freeze_package";
+pub const PACKAGE_ACCOUNT: &str = "Package";
+pub const NAMESPACE_ACCOUNT: &str = "Namespace";
+pub const MAINTAINERS_ACCOUNT: &str = "Maintainers";
+
+/// Anchor's instruction discriminator: the first 8 bytes of `sha256("global:<name>")`
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    discriminator("global", name)
+}
+
+/// Anchor's account discriminator: the first 8 bytes of `sha256("account:<Name>")`
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    discriminator("account", name)
+}
+
+fn discriminator(namespace: &str, name: &str) -> [u8; 8] {
+    let hash = Sha256::digest(format!("{}:{}", namespace, name).as_bytes());
+    let mut out = [0u8; 8];
+    out.copy_from_slice(&hash[..8]);
+    out
+}
+
+/// An account an instruction takes, in order
+pub struct AccountLayout {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+}
+
+/// A named, typed argument or field. Types are written the way
+/// [`type_name`] renders IDL types: `string`, `vec<T>`, `[u8; 32]`, `pubkey`.
+pub struct Field {
+    pub name: &'static str,
+    pub ty: &'static str,
+}
+
+/// What the CLI sends for one instruction
+pub struct InstructionLayout {
+    pub name: &'static str,
+    pub accounts: &'static [AccountLayout],
+    pub args: &'static [Field],
+}
+
+const PACKAGE_ARGS: &[Field] = &[
+    Field { name: "name", ty: "string" },
+    Field { name: "version", ty: "string" },
+    Field { name: "ipfs_cid", ty: "string" },
+    Field { name: "description", ty: "string" },
+    Field { name: "dependencies", ty: "vec<PackageDependency>" },
+    Field { name: "content_sha256", ty: "[u8; 32]" },
+    Field { name: "repository", ty: "string" },
+    Field { name: "homepage", ty: "string" },
+    Field { name: "keywords", ty: "vec<string>" },
+    Field { name: "readme_cid", ty: "option<string>" },
+    Field { name: "external_dependencies", ty: "vec<ExternalDependency>" },
+];
+
+const UPDATE_ARGS: &[Field] = &[
+    Field { name: "name", ty: "string" },
+    Field { name: "new_version", ty: "string" },
+    Field { name: "ipfs_cid", ty: "string" },
+    Field { name: "description", ty: "string" },
+    Field { name: "dependencies", ty: "vec<PackageDependency>" },
+    Field { name: "content_sha256", ty: "[u8; 32]" },
+    Field { name: "repository", ty: "string" },
+    Field { name: "homepage", ty: "string" },
+    Field { name: "keywords", ty: "vec<string>" },
+    Field { name: "readme_cid", ty: "option<string>" },
+    Field { name: "external_dependencies", ty: "vec<ExternalDependency>" },
+];
+
+const MAINTAINER_ARGS: &[Field] = &[
+    Field { name: "name", ty: "string" },
+    Field { name: "version", ty: "string" },
+    Field { name: "maintainer", ty: "pubkey" },
+];
+
+/// Instructions the CLI builds, as `solana_client` encodes them
+pub const INSTRUCTIONS: &[InstructionLayout] = &[
+    InstructionLayout {
+        name: PUBLISH_PACKAGE,
+        accounts: &[
+            AccountLayout { name: "authority", writable: true, signer: true },
+            AccountLayout { name: "package", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+            // Optional: the program ID stands in for unscoped names
+            AccountLayout { name: "namespace", writable: false, signer: false },
+        ],
+        args: PACKAGE_ARGS,
+    },
+    InstructionLayout {
+        name: UPDATE_PACKAGE,
+        accounts: &[
+            AccountLayout { name: "authority", writable: true, signer: true },
+            AccountLayout { name: "existing_package", writable: false, signer: false },
+            AccountLayout { name: "new_package", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+            // Optional: the program ID stands in unless a maintainer signs
+            AccountLayout { name: "maintainers", writable: false, signer: false },
+        ],
+        args: UPDATE_ARGS,
+    },
+    InstructionLayout {
+        name: REGISTER_NAMESPACE,
+        accounts: &[
+            AccountLayout { name: "owner", writable: true, signer: true },
+            AccountLayout { name: "namespace", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+        ],
+        args: &[Field { name: "scope", ty: "string" }],
+    },
+    InstructionLayout {
+        name: SET_NAMESPACE_PUBLISHERS,
+        accounts: &[
+            AccountLayout { name: "owner", writable: false, signer: true },
+            AccountLayout { name: "namespace", writable: true, signer: false },
+        ],
+        args: &[Field { name: "scope", ty: "string" }, Field { name: "publishers", ty: "vec<pubkey>" }],
+    },
+    InstructionLayout {
+        name: ADD_MAINTAINER,
+        accounts: &[
+            AccountLayout { name: "authority", writable: true, signer: true },
+            AccountLayout { name: "package", writable: false, signer: false },
+            AccountLayout { name: "maintainers", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+        ],
+        args: MAINTAINER_ARGS,
+    },
+    InstructionLayout {
+        name: REMOVE_MAINTAINER,
+        accounts: &[
+            AccountLayout { name: "authority", writable: false, signer: true },
+            AccountLayout { name: "package", writable: false, signer: false },
+            AccountLayout { name: "maintainers", writable: true, signer: false },
+        ],
+        args: MAINTAINER_ARGS,
+    },
+    InstructionLayout {
+        name: RECORD_DOWNLOAD,
+        accounts: &[
+            AccountLayout { name: "payer", writable: true, signer: true },
+            AccountLayout { name: "package", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+        ],
+        args: &[Field { name: "name", ty: "string" }, Field { name: "version", ty: "string" }],
+    },
+    InstructionLayout {
+        name: FREEZE_PACKAGE,
+        accounts: &[
+            AccountLayout { name: "authority", writable: true, signer: true },
+            AccountLayout { name: "package", writable: true, signer: false },
+            AccountLayout { name: "system_program", writable: false, signer: false },
+        ],
+        args: &[Field { name: "name", ty: "string" }, Field { name: "version", ty: "string" }],
+    },
+];
+
+/// Types the CLI reads and writes, by IDL name
+pub const TYPES: &[(&str, &[Field])] = &[
+    (PACKAGE_ACCOUNT, &[
+        Field { name: "name", ty: "string" },
+        Field { name: "version", ty: "string" },
+        Field { name: "authority", ty: "pubkey" },
+        Field { name: "ipfs_cid", ty: "string" },
+        Field { name: "published_at", ty: "i64" },
+        Field { name: "description", ty: "string" },
+        Field { name: "dependencies", ty: "vec<PackageDependency>" },
+        Field { name: "bump", ty: "u8" },
+        Field { name: "content_sha256", ty: "[u8; 32]" },
+        Field { name: "repository", ty: "string" },
+        Field { name: "homepage", ty: "string" },
+        Field { name: "keywords", ty: "vec<string>" },
+        // A tuple struct around `Option<String>` that tolerates accounts ending before it
+        Field { name: "readme_cid", ty: "ReadmeCid" },
+        // Likewise around `Vec<ExternalDependency>`
+        Field { name: "external_dependencies", ty: "ExternalDependencies" },
+        // Likewise around `u64`
+        Field { name: "downloads", ty: "DownloadCount" },
+        // Likewise around `bool`
+        Field { name: "frozen", ty: "Frozen" },
+    ]),
+    ("PackageDependency", &[
+        Field { name: "name", ty: "string" },
+        Field { name: "version", ty: "string" },
+    ]),
+    ("ExternalDependency", &[
+        Field { name: "name", ty: "string" },
+        Field { name: "version", ty: "string" },
+        Field { name: "dep_type", ty: "string" },
+        Field { name: "registry", ty: "option<string>" },
+    ]),
+    (NAMESPACE_ACCOUNT, &[
+        Field { name: "scope", ty: "string" },
+        Field { name: "owner", ty: "pubkey" },
+        Field { name: "publishers", ty: "vec<pubkey>" },
+        Field { name: "created_at", ty: "i64" },
+        Field { name: "bump", ty: "u8" },
+    ]),
+    (MAINTAINERS_ACCOUNT, &[
+        Field { name: "name", ty: "string" },
+        Field { name: "maintainers", ty: "vec<pubkey>" },
+        Field { name: "bump", ty: "u8" },
+        Field { name: "authority", ty: "pubkey" },
+    ]),
+];
+
+/// Where `anchor idl init` stores a program's IDL
+pub fn idl_address(program_id: &Pubkey) -> Pubkey {
+    let (base, _) = Pubkey::find_program_address(&[], program_id);
+    Pubkey::create_with_seed(&base, "anchor:idl", program_id).expect("static seed is valid")
+}
+
+/// Decode an IDL account: discriminator, authority, then a length-prefixed
+/// zlib-compressed JSON document
+pub fn decode_idl_account(data: &[u8]) -> Result<Value> {
+    let invalid = |reason: &str| AntSolError::Program(format!("Invalid IDL account: {}", reason));
+    let header = data.get(40..44).ok_or_else(|| invalid("too short"))?;
+    let len = u32::from_le_bytes(header.try_into().expect("4 bytes")) as usize;
+    let compressed = data.get(44..44 + len).ok_or_else(|| invalid("data shorter than its length prefix"))?;
+    let mut json = String::new();
+    ZlibDecoder::new(compressed)
+        .read_to_string(&mut json)
+        .map_err(|e| invalid(&format!("not zlib-compressed JSON ({})", e)))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Every difference between what the CLI encodes and what `idl` declares;
+/// empty when they agree. Reads both the Anchor 0.30 IDL format and the
+/// older camelCase one.
+pub fn check_idl(idl: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    let empty = Vec::new();
+    let instructions = idl["instructions"].as_array().unwrap_or(&empty);
+    for expected in INSTRUCTIONS {
+        let Some(found) = instructions.iter().find(|ix| idl_name(&ix["name"]) == expected.name) else {
+            problems.push(format!("instruction {} is missing from the IDL", expected.name));
+            continue;
+        };
+        if let Some(declared) = byte_array(&found["discriminator"]) {
+            let computed = instruction_discriminator(expected.name);
+            if declared != computed {
+                problems.push(format!(
+                    "instruction {}: IDL discriminator {:?} but the CLI sends {:?}",
+                    expected.name, declared, computed
+                ));
+            }
+        }
+        check_accounts(expected, found["accounts"].as_array().unwrap_or(&empty), &mut problems);
+        check_fields(&format!("instruction {} args", expected.name), expected.args, &found["args"], &mut problems);
+    }
+
+    for (name, fields) in TYPES {
+        match type_fields(idl, name) {
+            Some(declared) => check_fields(&format!("type {}", name), fields, declared, &mut problems),
+            None => problems.push(format!("type {} is missing from the IDL", name)),
+        }
+    }
+    for name in [PACKAGE_ACCOUNT, NAMESPACE_ACCOUNT, MAINTAINERS_ACCOUNT] {
+        let Some(account) = idl["accounts"].as_array().unwrap_or(&empty).iter().find(|a| a["name"] == name) else {
+            continue;
+        };
+        if let Some(declared) = byte_array(&account["discriminator"]) {
+            let computed = account_discriminator(name);
+            if declared != computed {
+                problems.push(format!(
+                    "account {}: IDL discriminator {:?} but the CLI expects {:?}",
+                    name, declared, computed
+                ));
+            }
+        }
+    }
+    problems
+}
+
+fn check_accounts(expected: &InstructionLayout, declared: &[Value], problems: &mut Vec<String>) {
+    let declared: Vec<(String, bool, bool)> = declared
+        .iter()
+        .map(|account| {
+            let flag = |new: &str, old: &str| account[new].as_bool().or_else(|| account[old].as_bool()).unwrap_or(false);
+            (idl_name(&account["name"]), flag("writable", "isMut"), flag("signer", "isSigner"))
+        })
+        .collect();
+    if declared.len() != expected.accounts.len() {
+        problems.push(format!(
+            "instruction {}: IDL lists {} accounts but the CLI passes {}",
+            expected.name,
+            declared.len(),
+            expected.accounts.len()
+        ));
+    }
+    for (i, (want, (name, writable, signer))) in expected.accounts.iter().zip(&declared).enumerate() {
+        if want.name != name || want.writable != *writable || want.signer != *signer {
+            problems.push(format!(
+                "instruction {} account #{}: IDL has {} (writable: {}, signer: {}) but the CLI passes {} (writable: {}, signer: {})",
+                expected.name, i, name, writable, signer, want.name, want.writable, want.signer
+            ));
+        }
+    }
+}
+
+fn check_fields(context: &str, expected: &[Field], declared: &Value, problems: &mut Vec<String>) {
+    let declared: Vec<(String, String)> = declared
+        .as_array()
+        .map(|fields| fields.iter().map(|f| (idl_name(&f["name"]), type_name(&f["type"]))).collect())
+        .unwrap_or_default();
+    let expected: Vec<(String, String)> = expected.iter().map(|f| (f.name.to_string(), f.ty.to_string())).collect();
+    if declared != expected {
+        let render = |fields: &[(String, String)]| {
+            fields.iter().map(|(name, ty)| format!("{}: {}", name, ty)).collect::<Vec<_>>().join(", ")
+        };
+        problems.push(format!(
+            "{} differ:\n      IDL: ({})\n      CLI: ({})",
+            context,
+            render(&declared),
+            render(&expected)
+        ));
+    }
+}
+
+/// `(name, type)` of each field of the struct type `name`, spelled as in
+/// [`Field`]; `None` when the IDL doesn't declare it
+pub fn struct_fields(idl: &Value, name: &str) -> Option<Vec<(String, String)>> {
+    let fields = type_fields(idl, name)?.as_array()?;
+    Some(fields.iter().map(|f| (idl_name(&f["name"]), type_name(&f["type"]))).collect())
+}
+
+/// Fields of a struct type, from `types` (0.30) or the account entry itself (older IDLs)
+fn type_fields<'a>(idl: &'a Value, name: &str) -> Option<&'a Value> {
+    ["types", "accounts"].iter().find_map(|section| {
+        idl[*section]
+            .as_array()?
+            .iter()
+            .find(|entry| entry["name"] == name && entry["type"]["fields"].is_array())
+            .map(|entry| &entry["type"]["fields"])
+    })
+}
+
+/// Render an IDL type the way [`Field::ty`] spells it
+pub fn type_name(ty: &Value) -> String {
+    match ty {
+        Value::String(s) if s == "publicKey" => "pubkey".to_string(),
+        Value::String(s) => s.clone(),
+        Value::Object(map) => {
+            if let Some(inner) = map.get("vec") {
+                format!("vec<{}>", type_name(inner))
+            } else if let Some(inner) = map.get("option") {
+                format!("option<{}>", type_name(inner))
+            } else if let Some(Value::Array(parts)) = map.get("array") {
+                match parts.as_slice() {
+                    [inner, len] => format!("[{}; {}]", type_name(inner), len),
+                    _ => ty.to_string(),
+                }
+            } else if let Some(defined) = map.get("defined") {
+                defined["name"].as_str().or_else(|| defined.as_str()).unwrap_or_default().to_string()
+            } else {
+                ty.to_string()
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+/// IDL names in snake_case; Anchor before 0.30 wrote them in camelCase
+fn idl_name(name: &Value) -> String {
+    let name = name.as_str().unwrap_or_default();
+    let mut out = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            if !out.is_empty() {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn byte_array(value: &Value) -> Option<[u8; 8]> {
+    let bytes: Vec<u8> = value.as_array()?.iter().map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok())).collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::ZlibEncoder, Compression};
+    use serde_json::json;
+    use std::io::Write;
+
+    #[test]
+    fn discriminators_match_the_deployed_program() {
+        assert_eq!(instruction_discriminator(PUBLISH_PACKAGE), [244, 240, 208, 233, 198, 38, 46, 197]);
+        assert_eq!(instruction_discriminator(UPDATE_PACKAGE), [167, 29, 15, 20, 179, 137, 50, 145]);
+        assert_eq!(account_discriminator(PACKAGE_ACCOUNT), [203, 12, 246, 255, 184, 43, 206, 39]);
+    }
+
+    fn field(name: &str, ty: Value) -> Value {
+        json!({ "name": name, "type": ty })
+    }
+
+    fn package_args(version_arg: &str) -> Vec<Value> {
+        vec![
+            field("name", json!("string")),
+            field(version_arg, json!("string")),
+            field("ipfs_cid", json!("string")),
+            field("description", json!("string")),
+            field("dependencies", json!({ "vec": { "defined": { "name": "PackageDependency" } } })),
+            field("content_sha256", json!({ "array": ["u8", 32] })),
+            field("repository", json!("string")),
+            field("homepage", json!("string")),
+            field("keywords", json!({ "vec": "string" })),
+            field("readme_cid", json!({ "option": "string" })),
+            field("external_dependencies", json!({ "vec": { "defined": { "name": "ExternalDependency" } } })),
+        ]
+    }
+
+    fn maintainer_args() -> Vec<Value> {
+        vec![field("name", json!("string")), field("version", json!("string")), field("maintainer", json!("pubkey"))]
+    }
+
+    /// The registry program's IDL as Anchor 0.30 generates it
+    fn registry_idl() -> Value {
+        let account = |name: &str, writable: bool, signer: bool| json!({ "name": name, "writable": writable, "signer": signer });
+        json!({
+            "instructions": [
+                {
+                    "name": "publish_package",
+                    "discriminator": instruction_discriminator(PUBLISH_PACKAGE),
+                    "accounts": [
+                        account("authority", true, true),
+                        account("package", true, false),
+                        { "name": "system_program" },
+                        { "name": "namespace", "optional": true },
+                    ],
+                    "args": package_args("version"),
+                },
+                {
+                    "name": "update_package",
+                    "discriminator": instruction_discriminator(UPDATE_PACKAGE),
+                    "accounts": [
+                        account("authority", true, true),
+                        { "name": "existing_package" },
+                        account("new_package", true, false),
+                        { "name": "system_program" },
+                        { "name": "maintainers", "optional": true },
+                    ],
+                    "args": package_args("new_version"),
+                },
+                {
+                    "name": "register_namespace",
+                    "discriminator": instruction_discriminator(REGISTER_NAMESPACE),
+                    "accounts": [account("owner", true, true), account("namespace", true, false), { "name": "system_program" }],
+                    "args": [field("scope", json!("string"))],
+                },
+                {
+                    "name": "set_namespace_publishers",
+                    "discriminator": instruction_discriminator(SET_NAMESPACE_PUBLISHERS),
+                    "accounts": [account("owner", false, true), account("namespace", true, false)],
+                    "args": [field("scope", json!("string")), field("publishers", json!({ "vec": "pubkey" }))],
+                },
+                {
+                    "name": "add_maintainer",
+                    "discriminator": instruction_discriminator(ADD_MAINTAINER),
+                    "accounts": [
+                        account("authority", true, true),
+                        { "name": "package" },
+                        account("maintainers", true, false),
+                        { "name": "system_program" },
+                    ],
+                    "args": maintainer_args(),
+                },
+                {
+                    "name": "remove_maintainer",
+                    "discriminator": instruction_discriminator(REMOVE_MAINTAINER),
+                    "accounts": [account("authority", false, true), { "name": "package" }, account("maintainers", true, false)],
+                    "args": maintainer_args(),
+                },
+                {
+                    "name": "record_download",
+                    "discriminator": instruction_discriminator(RECORD_DOWNLOAD),
+                    "accounts": [account("payer", true, true), account("package", true, false), { "name": "system_program" }],
+                    "args": [field("name", json!("string")), field("version", json!("string"))],
+                },
+                {
+                    "name": "freeze_package",
+                    "discriminator": instruction_discriminator(FREEZE_PACKAGE),
+                    "accounts": [account("authority", true, true), account("package", true, false), { "name": "system_program" }],
+                    "args": [field("name", json!("string")), field("version", json!("string"))],
+                },
+            ],
+            "accounts": [
+                { "name": "Maintainers", "discriminator": account_discriminator(MAINTAINERS_ACCOUNT) },
+                { "name": "Namespace", "discriminator": account_discriminator(NAMESPACE_ACCOUNT) },
+                { "name": "Package", "discriminator": account_discriminator(PACKAGE_ACCOUNT) },
+            ],
+            "types": [
+                { "name": "Package", "type": { "kind": "struct", "fields": [
+                    field("name", json!("string")),
+                    field("version", json!("string")),
+                    field("authority", json!("pubkey")),
+                    field("ipfs_cid", json!("string")),
+                    field("published_at", json!("i64")),
+                    field("description", json!("string")),
+                    field("dependencies", json!({ "vec": { "defined": { "name": "PackageDependency" } } })),
+                    field("bump", json!("u8")),
+                    field("content_sha256", json!({ "array": ["u8", 32] })),
+                    field("repository", json!("string")),
+                    field("homepage", json!("string")),
+                    field("keywords", json!({ "vec": "string" })),
+                    field("readme_cid", json!({ "defined": { "name": "ReadmeCid" } })),
+                    field("external_dependencies", json!({ "defined": { "name": "ExternalDependencies" } })),
+                    field("downloads", json!({ "defined": { "name": "DownloadCount" } })),
+                    field("frozen", json!({ "defined": { "name": "Frozen" } })),
+                ] } },
+                { "name": "DownloadCount", "type": { "kind": "struct", "fields": ["u64"] } },
+                { "name": "Frozen", "type": { "kind": "struct", "fields": ["bool"] } },
+                { "name": "ReadmeCid", "type": { "kind": "struct", "fields": [{ "option": "string" }] } },
+                { "name": "ExternalDependencies", "type": { "kind": "struct", "fields": [
+                    { "vec": { "defined": { "name": "ExternalDependency" } } },
+                ] } },
+                { "name": "PackageDependency", "type": { "kind": "struct", "fields": [
+                    field("name", json!("string")),
+                    field("version", json!("string")),
+                ] } },
+                { "name": "ExternalDependency", "type": { "kind": "struct", "fields": [
+                    field("name", json!("string")),
+                    field("version", json!("string")),
+                    field("dep_type", json!("string")),
+                    field("registry", json!({ "option": "string" })),
+                ] } },
+                { "name": "Namespace", "type": { "kind": "struct", "fields": [
+                    field("scope", json!("string")),
+                    field("owner", json!("pubkey")),
+                    field("publishers", json!({ "vec": "pubkey" })),
+                    field("created_at", json!("i64")),
+                    field("bump", json!("u8")),
+                ] } },
+                { "name": "Maintainers", "type": { "kind": "struct", "fields": [
+                    field("name", json!("string")),
+                    field("maintainers", json!({ "vec": "pubkey" })),
+                    field("bump", json!("u8")),
+                    field("authority", json!("pubkey")),
+                ] } },
+            ],
+        })
+    }
+
+    #[test]
+    fn matching_idl_has_no_problems() {
+        assert_eq!(check_idl(&registry_idl()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn argument_and_discriminator_drift_is_reported() {
+        let mut idl = registry_idl();
+        // The layout the CLI used to send, with an external dependencies list
+        idl["instructions"][0]["args"].as_array_mut().unwrap().insert(5, field("ext_deps", json!({ "vec": "string" })));
+        idl["instructions"][1]["discriminator"] = json!([0, 0, 0, 0, 0, 0, 0, 0]);
+        idl["instructions"][1]["accounts"][1]["writable"] = json!(true);
+
+        let problems = check_idl(&idl);
+        assert_eq!(problems.len(), 3, "{:#?}", problems);
+        assert!(problems[0].starts_with("instruction publish_package args differ"));
+        assert!(problems[0].contains("ext_deps: vec<string>"));
+        assert!(problems[1].starts_with("instruction update_package: IDL discriminator [0, 0, 0, 0, 0, 0, 0, 0]"));
+        assert!(problems[2].contains("account #1: IDL has existing_package (writable: true"));
+    }
+
+    #[test]
+    fn missing_instructions_and_types_are_reported() {
+        let problems = check_idl(&json!({ "instructions": [] }));
+        assert!(problems.contains(&"instruction publish_package is missing from the IDL".to_string()));
+        assert!(problems.contains(&"type Package is missing from the IDL".to_string()));
+    }
+
+    #[test]
+    fn reads_pre_030_camel_case_idls() {
+        assert_eq!(idl_name(&json!("publishPackage")), "publish_package");
+        assert_eq!(idl_name(&json!("contentSha256")), "content_sha256");
+        assert_eq!(type_name(&json!("publicKey")), "pubkey");
+        assert_eq!(type_name(&json!({ "vec": { "defined": "PackageDependency" } })), "vec<PackageDependency>");
+        assert_eq!(type_name(&json!({ "option": { "array": ["u8", 32] } })), "option<[u8; 32]>");
+    }
+
+    #[test]
+    fn decodes_compressed_idl_accounts() {
+        let idl = registry_idl();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(idl.to_string().as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut data = vec![0u8; 40];
+        data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        data.extend_from_slice(&compressed);
+        assert_eq!(decode_idl_account(&data).unwrap(), idl);
+        assert!(decode_idl_account(&data[..30]).is_err());
+        assert!(decode_idl_account(&data[..60]).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use serde::Deserialize;
+
+/// Package row as returned by the indexer list/search endpoints
+#[derive(Debug, Deserialize)]
+pub struct PackageRow {
+    pub name: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub total_downloads: Option<u64>,
+    /// Highest version, included in search results (absent from older indexers)
+    #[serde(default)]
+    pub latest_version: Option<String>,
+    /// RFC 3339 publish time of `latest_version`, in search results
+    #[serde(default)]
+    pub latest_published_at: Option<String>,
+}
+
+/// Version row as returned by `GET /api/packages/:name`
+#[derive(Debug, Deserialize)]
+pub struct VersionRow {
+    pub version: String,
+    pub ipfs_hash: Option<String>,
+    pub downloads: Option<u64>,
+    pub published_at: Option<String>,
+}
+
+/// Package with all known versions
+#[derive(Debug, Deserialize)]
+pub struct PackageDetails {
+    /// Highest release by semver, as the indexer orders versions (absent from older indexers)
+    #[serde(default)]
+    pub latest_version: Option<String>,
+    pub versions: Vec<VersionRow>,
+}
+
+/// `GET /health` report; the indexer serves it with 503 when unhealthy too
+#[derive(Debug, Deserialize)]
+pub struct HealthReport {
+    /// `ok`, `degraded` or `unhealthy`
+    pub status: String,
+    pub indexer: IndexerHealth,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndexerHealth {
+    pub lag_slots: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// `GET /api/packages/:name/:version/dependents`: who an update to a version affects
+#[derive(Debug, Deserialize)]
+pub struct VersionDependents {
+    pub direct_count: u64,
+    pub transitive_count: u64,
+    /// Nearest first, each package once
+    pub dependents: Vec<VersionDependent>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VersionDependent {
+    pub name: String,
+    /// The dependent's latest version
+    pub version: String,
+}
+
+/// One page of search results
+#[derive(Debug)]
+pub struct SearchPage {
+    pub rows: Vec<PackageRow>,
+    /// Matches across all pages; unknown from indexers that answer with a bare array
+    pub total: Option<u64>,
+}
+
+/// Result order of `/api/search`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SearchSort {
+    /// Exact name first, then text rank blended with downloads
+    Relevance,
+    /// Most downloaded first
+    Downloads,
+    /// Most recently updated first
+    Recent,
+    /// Alphabetical by name
+    Name,
+}
+
+impl SearchSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Downloads => "downloads",
+            SearchSort::Recent => "recent",
+            SearchSort::Name => "name",
+        }
+    }
+}
+
+/// Narrowing and order for [`IndexerClient::search`]; unset values aren't sent
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilters {
+    /// Base58 pubkey of the package authority
+    pub author: Option<String>,
+    pub sort: Option<SearchSort>,
+    /// Semver requirement one of the versions must match, e.g. `^1.2`
+    pub has_version: Option<String>,
+}
+
+/// `data` of a list endpoint: a `{items, total, ...}` page when asked for
+/// with `envelope=1`, or the bare array older indexers always send
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ListData<T> {
+    Page { items: Vec<T>, total: u64 },
+    Bare(Vec<T>),
+}
+
+/// Standard indexer response envelope
+#[derive(Debug, Deserialize)]
+pub struct ApiResponse<T> {
+    pub data: T,
+    pub error: Option<String>,
+}
+
+/// HTTP client for the AntSol indexer REST API
+pub struct IndexerClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl IndexerClient {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+    
+    /// URL of `GET /api/packages/:name`, with the name always percent-encoded
+    /// so scoped names like `@scope/pkg` stay a single path segment
+    fn package_url(&self, name: &str) -> String {
+        format!("{}/api/packages/{}", self.base_url, encode_path_segment(name))
+    }
+    
+    /// Search packages by name/description, one page at a time
+    pub async fn search(&self, query: &str, limit: u32, offset: u64, filters: &SearchFilters) -> Result<SearchPage> {
+        let url = format!("{}/api/search", self.base_url);
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("limit", limit.to_string()),
+            ("offset", offset.to_string()),
+            ("envelope", "1".to_string()),
+        ];
+        if let Some(author) = &filters.author {
+            params.push(("author", author.clone()));
+        }
+        if let Some(sort) = filters.sort {
+            params.push(("sort", sort.as_str().to_string()));
+        }
+        if let Some(req) = &filters.has_version {
+            params.push(("has_version", req.clone()));
+        }
+        let resp = self.client
+            .get(&url)
+            .query(&params)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Indexer unreachable: {}", e)))?;
+        
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            // The error envelope says which parameter the indexer rejected
+            let message = resp.json::<ApiResponse<serde_json::Value>>().await.ok().and_then(|api| api.error);
+            return Err(AntSolError::Usage(format!(
+                "Indexer rejected the search: {}",
+                message.unwrap_or_else(|| "bad request".to_string())
+            )).into());
+        }
+        if !resp.status().is_success() {
+            return Err(AntSolError::Network(format!("Indexer search failed with status {}", resp.status())).into());
+        }
+        
+        let api: ApiResponse<ListData<PackageRow>> = resp.json().await?;
+        Ok(match api.data {
+            ListData::Page { items, total } => SearchPage { rows: items, total: Some(total) },
+            ListData::Bare(rows) => SearchPage { rows, total: None },
+        })
+    }
+    
+    /// Fetch a package with all of its versions; `None` when the indexer doesn't know it
+    pub async fn get_package(&self, name: &str) -> Result<Option<PackageDetails>> {
+        let url = self.package_url(name);
+        let resp = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Indexer unreachable: {}", e)))?;
+        
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(AntSolError::Network(format!("Indexer returned status {} for {}", resp.status(), name)).into());
+        }
+        
+        let api: ApiResponse<PackageDetails> = resp.json().await?;
+        Ok(Some(api.data))
+    }
+    
+    /// Subsystem status and indexing lag from `GET /health`. Indexers that
+    /// predate the detailed report only answer "OK", which is a parse error here.
+    pub async fn health(&self) -> Result<HealthReport> {
+        let url = format!("{}/health", self.base_url);
+        let resp = self.client
+            .get(&url)
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Indexer unreachable: {}", e)))?;
+        
+        let api: ApiResponse<Option<HealthReport>> = resp
+            .json()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Unexpected /health response: {}", e)))?;
+        api.data.ok_or_else(|| {
+            AntSolError::Network(api.error.unwrap_or_else(|| "Indexer sent no health report".to_string())).into()
+        })
+    }
+    
+    /// Packages whose latest version depends on `name@version`, transitively
+    /// up to the indexer's depth limit; `None` when the version isn't indexed
+    /// or the indexer predates the route
+    pub async fn get_version_dependents(&self, name: &str, version: &str) -> Result<Option<VersionDependents>> {
+        let url = format!("{}/{}/dependents", self.package_url(name), encode_path_segment(version));
+        let resp = self.client
+            .get(&url)
+            .query(&[("transitive", "true")])
+            .timeout(DEPENDENTS_LOOKUP_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Indexer unreachable: {}", e)))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(AntSolError::Network(format!("Indexer returned status {} for dependents of {}@{}", resp.status(), name, version)).into());
+        }
+
+        let api: ApiResponse<VersionDependents> = resp.json().await?;
+        Ok(Some(api.data))
+    }
+    
+    /// Count one install of `name@version` (`POST /api/packages/:name/:version/download`).
+    /// Only the package, version and the connection's address reach the indexer.
+    pub async fn record_download(&self, name: &str, version: &str) -> Result<()> {
+        let url = format!("{}/{}/download", self.package_url(name), encode_path_segment(version));
+        let resp = self.client
+            .post(&url)
+            .timeout(DOWNLOAD_REPORT_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Network(format!("Indexer unreachable: {}", e)))?;
+    
+        if !resp.status().is_success() {
+            return Err(AntSolError::Network(format!("Indexer returned status {} recording {}@{}", resp.status(), name, version)).into());
+        }
+        Ok(())
+    }
+}
+
+/// The setup wizard checks a freshly entered URL; don't stall it
+const HEALTH_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The dependents lookup only adds context to a confirmation prompt
+const DEPENDENTS_LOOKUP_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Download reports are best-effort; never hold an install up for long
+const DOWNLOAD_REPORT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Percent-encode a value for use as a single URL path segment
+pub fn encode_path_segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_urls_are_percent_encoded() {
+        let client = IndexerClient::new("http://localhost:8080/");
+        assert_eq!(client.package_url("plain-pkg"), "http://localhost:8080/api/packages/plain-pkg");
+        assert_eq!(client.package_url("@scope/pkg"), "http://localhost:8080/api/packages/%40scope%2Fpkg");
+    }
+    
+    #[tokio::test]
+    async fn health_reads_the_report_from_an_unhealthy_indexer() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/health"))
+            .respond_with(ResponseTemplate::new(503).set_body_json(serde_json::json!({
+                "success": false,
+                "data": {
+                    "status": "unhealthy",
+                    "database": { "ok": true, "error": null },
+                    "rpc": { "ok": true, "error": null },
+                    "indexer": { "ok": false, "last_processed_slot": 100, "current_slot": 900, "lag_slots": 800, "last_error": null },
+                },
+                "error": "Indexer is 800 slots behind the chain (limit 300)",
+                "code": "unhealthy",
+            })))
+            .mount(&server)
+            .await;
+        let report = IndexerClient::new(&server.uri()).health().await.unwrap();
+        assert_eq!(report.status, "unhealthy");
+        assert_eq!(report.indexer.lag_slots, Some(800));
+    }
+    
+    #[tokio::test]
+    async fn search_reads_paginated_and_bare_responses() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let row = serde_json::json!({ "id": 1, "name": "pkg", "author": null, "description": null,
+            "repository": null, "homepage": null, "total_downloads": 3 });
+        let server = MockServer::start().await;
+        Mock::given(path("/api/search"))
+            .and(query_param("envelope", "1"))
+            .and(query_param("q", "paged"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "items": [row.clone()], "total": 41, "limit": 20, "offset": 40 },
+                "error": null,
+            })))
+            .mount(&server)
+            .await;
+        // An indexer from before the envelope ignores the parameter
+        Mock::given(path("/api/search"))
+            .and(query_param("q", "bare"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": [row],
+                "error": null,
+            })))
+            .mount(&server)
+            .await;
+
+        let client = IndexerClient::new(&server.uri());
+        let page = client.search("paged", 20, 40, &SearchFilters::default()).await.unwrap();
+        assert_eq!((page.rows.len(), page.total), (1, Some(41)));
+        let page = client.search("bare", 20, 0, &SearchFilters::default()).await.unwrap();
+        assert_eq!((page.rows[0].name.as_str(), page.total), ("pkg", None));
+    }
+    
+    #[tokio::test]
+    async fn search_sends_filters_and_reports_rejections() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/api/search"))
+            .and(query_param("author", "Auth1111"))
+            .and(query_param("sort", "name"))
+            .and(query_param("has_version", "^1.2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "items": [{ "id": 1, "name": "pkg", "author": "Auth1111", "latest_version": "1.4.0",
+                    "latest_published_at": "2024-06-10T12:00:00Z" }], "total": 1, "limit": 20, "offset": 0 },
+                "error": null,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/api/search"))
+            .and(query_param("has_version", "^9"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "success": false,
+                "data": null,
+                "error": "has_version is not supported",
+                "code": "invalid_query",
+            })))
+            .mount(&server)
+            .await;
+
+        let client = IndexerClient::new(&server.uri());
+        let filters = SearchFilters {
+            author: Some("Auth1111".into()),
+            sort: Some(SearchSort::Name),
+            has_version: Some("^1.2".into()),
+        };
+        let page = client.search("pkg", 20, 0, &filters).await.unwrap();
+        assert_eq!(page.rows[0].latest_published_at.as_deref(), Some("2024-06-10T12:00:00Z"));
+
+        let filters = SearchFilters { has_version: Some("^9".into()), ..SearchFilters::default() };
+        let err = client.search("pkg", 20, 0, &filters).await.unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Usage);
+        assert!(err.to_string().contains("has_version is not supported"), "{}", err);
+    }
+    
+    #[tokio::test]
+    async fn version_dependents_are_none_when_not_indexed() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/api/packages/%40scope%2Fcore/1.0.0/dependents"))
+            .and(query_param("transitive", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "data": { "name": "@scope/core", "version": "1.0.0", "direct_count": 1, "transitive_count": 1, "max_depth": 5,
+                    "dependents": [
+                        { "name": "lib", "version": "2.0.0", "via": "@scope/core", "requires": "1.0.0", "depth": 1 },
+                        { "name": "app", "version": "0.3.0", "via": "lib", "requires": "2.0.0", "depth": 2 },
+                    ] },
+                "error": null,
+            })))
+            .mount(&server)
+            .await;
+
+        let client = IndexerClient::new(&server.uri());
+        let impact = client.get_version_dependents("@scope/core", "1.0.0").await.unwrap().unwrap();
+        assert_eq!((impact.direct_count, impact.transitive_count), (1, 1));
+        assert_eq!(impact.dependents[1].name, "app");
+        assert!(client.get_version_dependents("@scope/core", "9.9.9").await.unwrap().is_none());
+    }
+    
+    #[tokio::test]
+    async fn record_download_fails_softly_when_unreachable() {
+        let client = IndexerClient::new("http://127.0.0.1:1");
+        let err = client.record_download("pkg", "1.0.0").await.unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Network);
+    }
+}
@@ -0,0 +1,465 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use cid::Cid;
+use sha2::{Digest, Sha256};
+use std::ops::Range;
+
+/// Multicodec for raw binary blocks
+const RAW_CODEC: u64 = 0x55;
+/// Multicodec for dag-pb (UnixFS) blocks
+const DAG_PB_CODEC: u64 = 0x70;
+/// Multihash code for sha2-256
+const SHA2_256: u64 = 0x12;
+
+/// Default IPFS chunk size; files up to this size are a single block
+pub const DEFAULT_CHUNK_SIZE: usize = 262_144;
+/// Largest leaf the IPFS chunkers will produce; bigger children are intermediate nodes
+const MAX_LEAF_SIZE: u64 = 1_048_576;
+
+/// UnixFS node types
+const UNIXFS_RAW: u64 = 0;
+const UNIXFS_FILE: u64 = 2;
+
+/// Outcome of checking downloaded bytes against a CID locally
+#[derive(Debug, PartialEq, Eq)]
+pub enum CidCheck {
+    /// The bytes hash to the expected CID
+    Verified,
+    /// The CID is a multi-block UnixFS DAG; the root block is needed to verify
+    NeedsRootBlock,
+}
+
+/// Parse a CID string, rejecting anything but sha2-256 multihashes
+pub fn parse_cid(cid: &str) -> Result<Cid> {
+    let parsed = Cid::try_from(cid)
+        .map_err(|e| AntSolError::Verification(format!("Invalid CID {}: {}", cid, e)))?;
+    if parsed.hash().code() != SHA2_256 {
+        return Err(AntSolError::Verification(format!(
+            "Unsupported multihash 0x{:x} in CID {} (only sha2-256 is supported)",
+            parsed.hash().code(),
+            cid
+        )).into());
+    }
+    Ok(parsed)
+}
+
+/// Verify `data` against `expected` for the single-block cases: a raw block,
+/// or a UnixFS file small enough to be encoded as one dag-pb node.
+pub fn check_single_block(data: &[u8], expected: &str) -> Result<CidCheck> {
+    let cid = parse_cid(expected)?;
+    match cid.codec() {
+        RAW_CODEC => {
+            if digest_matches(&cid, data) {
+                Ok(CidCheck::Verified)
+            } else {
+                Err(mismatch(expected, "raw block hash differs"))
+            }
+        }
+        DAG_PB_CODEC => {
+            if data.len() <= DEFAULT_CHUNK_SIZE && digest_matches(&cid, &unixfs_file_block(data, UNIXFS_FILE)) {
+                Ok(CidCheck::Verified)
+            } else {
+                // Chunked file, or imported with non-default options
+                Ok(CidCheck::NeedsRootBlock)
+            }
+        }
+        other => Err(AntSolError::Verification(format!(
+            "Unsupported codec 0x{:x} in CID {}",
+            other, expected
+        )).into()),
+    }
+}
+
+/// A child of a UnixFS DAG node that can't be checked from `data` alone:
+/// its block has to be fetched and passed to [`verify_child_block`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingBlock {
+    pub cid: Cid,
+    /// The slice of the downloaded file this block covers
+    pub range: Range<usize>,
+}
+
+/// Verify `data` against a multi-block UnixFS file given its root block
+/// (fetched from a gateway with `?format=raw`).
+///
+/// The root block must hash to `expected` and declare `data.len()` as the file
+/// size. Leaf children are recomputed from the matching slice of `data`;
+/// intermediate nodes of deeper DAGs are returned, and the download is only
+/// verified once each of them has passed [`verify_child_block`].
+pub fn verify_root_block(data: &[u8], expected: &str, root_block: &[u8]) -> Result<Vec<PendingBlock>> {
+    let cid = parse_cid(expected)?;
+    if !digest_matches(&cid, root_block) {
+        return Err(mismatch(expected, "root block does not hash to the CID"));
+    }
+    verify_node(data, expected, 0..data.len(), root_block)
+}
+
+/// Verify an intermediate block returned by [`verify_root_block`] or a
+/// previous call, returning its own intermediate children in turn
+pub fn verify_child_block(data: &[u8], expected: &str, child: &PendingBlock, block: &[u8]) -> Result<Vec<PendingBlock>> {
+    if !digest_matches(&child.cid, block) {
+        return Err(mismatch(expected, &format!("block {} does not hash to its CID", child.cid)));
+    }
+    verify_node(data, expected, child.range.clone(), block)
+}
+
+/// Check a dag-pb node whose hash is already verified against the slice of
+/// `data` it covers
+fn verify_node(data: &[u8], expected: &str, range: Range<usize>, block: &[u8]) -> Result<Vec<PendingBlock>> {
+    let content = data.get(range.clone())
+        .ok_or_else(|| mismatch(expected, "block sizes exceed downloaded content"))?;
+    let node = PbNode::decode(block)
+        .ok_or_else(|| mismatch(expected, "block is not a valid dag-pb node"))?;
+    let unixfs = node.data.as_deref().and_then(UnixFsData::decode)
+        .ok_or_else(|| mismatch(expected, "block has no UnixFS data"))?;
+
+    if node.links.is_empty() {
+        if unixfs.data.as_deref().unwrap_or_default() == content {
+            return Ok(Vec::new());
+        }
+        return Err(mismatch(expected, &format!("content at offset {} differs from its block", range.start)));
+    }
+
+    if unixfs.filesize != Some(content.len() as u64) {
+        return Err(mismatch(expected, &format!(
+            "block at offset {} declares {} bytes but {} were downloaded",
+            range.start,
+            unixfs.filesize.unwrap_or(0),
+            content.len()
+        )));
+    }
+    if unixfs.blocksizes.len() != node.links.len() {
+        return Err(mismatch(expected, &format!(
+            "block at offset {} has {} links but {} block sizes",
+            range.start,
+            node.links.len(),
+            unixfs.blocksizes.len()
+        )));
+    }
+
+    let mut pending = Vec::new();
+    let mut offset = range.start;
+    for (link, size) in node.links.iter().zip(&unixfs.blocksizes) {
+        let end = offset + *size as usize;
+        let slice = data.get(offset..end).filter(|_| end <= range.end)
+            .ok_or_else(|| mismatch(expected, "block sizes exceed downloaded content"))?;
+        let child = Cid::try_from(link.as_slice())
+            .map_err(|_| mismatch(expected, "block contains an invalid link"))?;
+        match child.codec() {
+            RAW_CODEC if digest_matches(&child, slice) => {}
+            DAG_PB_CODEC if *size <= MAX_LEAF_SIZE
+                && (digest_matches(&child, &unixfs_file_block(slice, UNIXFS_RAW))
+                    || digest_matches(&child, &unixfs_file_block(slice, UNIXFS_FILE))) => {}
+            // An intermediate node, or a leaf that doesn't match: its block decides
+            DAG_PB_CODEC => pending.push(PendingBlock { cid: child, range: offset..end }),
+            _ => return Err(mismatch(expected, &format!("chunk at offset {} does not match {}", offset, child))),
+        }
+        offset = end;
+    }
+    if offset != range.end {
+        return Err(mismatch(expected, &format!("block sizes at offset {} do not add up to its file size", range.start)));
+    }
+    Ok(pending)
+}
+
+fn digest_matches(cid: &Cid, bytes: &[u8]) -> bool {
+    cid.hash().code() == SHA2_256 && cid.hash().digest() == &Sha256::digest(bytes)[..]
+}
+
+fn mismatch(cid: &str, detail: &str) -> Box<dyn std::error::Error> {
+    AntSolError::Verification(format!("File integrity check failed - CID mismatch for {}: {}", cid, detail)).into()
+}
+
+/// dag-pb node wrapping a UnixFS node of `unixfs_type` holding `data`
+/// (the encoding `ipfs add` uses for a single-block file)
+pub fn unixfs_file_block(data: &[u8], unixfs_type: u64) -> Vec<u8> {
+    let mut unixfs = Vec::new();
+    put_varint_field(&mut unixfs, 1, unixfs_type);
+    if !data.is_empty() {
+        put_bytes_field(&mut unixfs, 2, data);
+    }
+    put_varint_field(&mut unixfs, 3, data.len() as u64);
+
+    let mut node = Vec::new();
+    put_bytes_field(&mut node, 1, &unixfs);
+    node
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u64, value: u64) {
+    put_varint(out, field << 3);
+    put_varint(out, value);
+}
+
+fn put_bytes_field(out: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(out, (field << 3) | 2);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Minimal protobuf reader for the dag-pb and UnixFS messages
+struct ProtoReader<'a> {
+    buf: &'a [u8],
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+impl<'a> ProtoReader<'a> {
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = self.buf.split_first()?;
+            self.buf = rest;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    /// Next `(field number, value)`, `None` at end of input
+    fn next_field(&mut self) -> Option<Option<(u64, ProtoValue<'a>)>> {
+        if self.buf.is_empty() {
+            return Some(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => ProtoValue::Varint(self.varint()?),
+            2 => {
+                let len = self.varint()? as usize;
+                if len > self.buf.len() {
+                    return None;
+                }
+                let (bytes, rest) = self.buf.split_at(len);
+                self.buf = rest;
+                ProtoValue::Bytes(bytes)
+            }
+            _ => return None,
+        };
+        Some(Some((key >> 3, value)))
+    }
+}
+
+/// dag-pb `PBNode`: link hashes and the opaque data field
+struct PbNode {
+    links: Vec<Vec<u8>>,
+    data: Option<Vec<u8>>,
+}
+
+impl PbNode {
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let mut reader = ProtoReader { buf };
+        let mut node = PbNode { links: Vec::new(), data: None };
+        while let Some((field, value)) = reader.next_field()? {
+            match (field, value) {
+                (1, ProtoValue::Bytes(bytes)) => node.data = Some(bytes.to_vec()),
+                (2, ProtoValue::Bytes(link)) => {
+                    let mut link_reader = ProtoReader { buf: link };
+                    let mut hash = None;
+                    while let Some((field, value)) = link_reader.next_field()? {
+                        if let (1, ProtoValue::Bytes(bytes)) = (field, value) {
+                            hash = Some(bytes.to_vec());
+                        }
+                    }
+                    node.links.push(hash?);
+                }
+                _ => {}
+            }
+        }
+        Some(node)
+    }
+}
+
+/// UnixFS `Data` message fields needed for verification
+struct UnixFsData {
+    data: Option<Vec<u8>>,
+    filesize: Option<u64>,
+    blocksizes: Vec<u64>,
+}
+
+impl UnixFsData {
+    fn decode(buf: &[u8]) -> Option<Self> {
+        let mut reader = ProtoReader { buf };
+        let mut unixfs = UnixFsData { data: None, filesize: None, blocksizes: Vec::new() };
+        while let Some((field, value)) = reader.next_field()? {
+            match (field, value) {
+                (2, ProtoValue::Bytes(bytes)) => unixfs.data = Some(bytes.to_vec()),
+                (3, ProtoValue::Varint(size)) => unixfs.filesize = Some(size),
+                (4, ProtoValue::Varint(size)) => unixfs.blocksizes.push(size),
+                _ => {}
+            }
+        }
+        Some(unixfs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+    use cid::multihash::Multihash;
+
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/hello-pkg.tar.gz");
+    const FIXTURE_CID_V0: &str = "QmY3KRhGpGgnMBG5Vg4tL9f9SBvs5RaZK2dX6FVrC6FhwY";
+    const FIXTURE_CID_V1_RAW: &str = "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu";
+    const FIXTURE_CID_V1_DAG_PB: &str = "bafybeieqeqytifoyaj72jenqspfhurfwktfsjvs45nmflmaitv7f542htm";
+
+    fn cid_v1(codec: u64, block: &[u8]) -> Cid {
+        Cid::new_v1(codec, Multihash::wrap(SHA2_256, &Sha256::digest(block)).unwrap())
+    }
+
+    #[test]
+    fn known_unixfs_cids() {
+        assert_eq!(check_single_block(b"", "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH").unwrap(), CidCheck::Verified);
+        assert_eq!(check_single_block(b"hello world\n", "QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o").unwrap(), CidCheck::Verified);
+        assert_eq!(
+            check_single_block(b"hello world\n", "bafkreifjjcie6lypi6ny7amxnfftagclbuxndqonfipmb64f2km2devei4").unwrap(),
+            CidCheck::Verified
+        );
+    }
+
+    #[test]
+    fn fixture_tarball_matches_its_cids() {
+        for cid in [FIXTURE_CID_V0, FIXTURE_CID_V1_RAW, FIXTURE_CID_V1_DAG_PB] {
+            assert_eq!(check_single_block(FIXTURE, cid).unwrap(), CidCheck::Verified, "{}", cid);
+        }
+    }
+
+    #[test]
+    fn tampered_fixture_is_rejected() {
+        let mut tampered = FIXTURE.to_vec();
+        tampered[100] ^= 0xff;
+
+        let err = check_single_block(&tampered, FIXTURE_CID_V1_RAW).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Verification);
+
+        // dag-pb needs the root block to tell a chunked file from a bad download
+        assert_eq!(check_single_block(&tampered, FIXTURE_CID_V0).unwrap(), CidCheck::NeedsRootBlock);
+        let root = unixfs_file_block(FIXTURE, UNIXFS_FILE);
+        assert!(verify_root_block(&tampered, FIXTURE_CID_V0, &root).is_err());
+        assert!(verify_root_block(FIXTURE, FIXTURE_CID_V0, &root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_and_unsupported_cids_are_rejected() {
+        assert!(check_single_block(FIXTURE, "Qm-not-a-cid").is_err());
+        let blake3 = Cid::new_v1(RAW_CODEC, Multihash::wrap(0x1e, &[0u8; 32]).unwrap()).to_string();
+        assert!(check_single_block(FIXTURE, &blake3).is_err());
+    }
+
+    /// dag-pb file node linking `children`, each covering `size` bytes
+    fn file_node(children: &[(Cid, usize)]) -> Vec<u8> {
+        let mut unixfs = Vec::new();
+        put_varint_field(&mut unixfs, 1, UNIXFS_FILE);
+        put_varint_field(&mut unixfs, 3, children.iter().map(|(_, size)| *size as u64).sum());
+        for (_, size) in children {
+            put_varint_field(&mut unixfs, 4, *size as u64);
+        }
+
+        let mut node = Vec::new();
+        for (child, size) in children {
+            let mut link = Vec::new();
+            put_bytes_field(&mut link, 1, &child.to_bytes());
+            put_varint_field(&mut link, 3, *size as u64);
+            put_bytes_field(&mut node, 2, &link);
+        }
+        put_bytes_field(&mut node, 1, &unixfs);
+        node
+    }
+
+    /// Build a two-level UnixFS file the way `ipfs add` does for content
+    /// larger than one chunk, returning (data, root block, root CID)
+    fn chunked_file(raw_leaves: bool) -> (Vec<u8>, Vec<u8>, String) {
+        let data: Vec<u8> = (0..DEFAULT_CHUNK_SIZE + 1000).map(|i| (i % 251) as u8).collect();
+        let leaves: Vec<(Cid, usize)> = data.chunks(DEFAULT_CHUNK_SIZE)
+            .map(|chunk| {
+                let leaf = if raw_leaves {
+                    cid_v1(RAW_CODEC, chunk)
+                } else {
+                    cid_v1(DAG_PB_CODEC, &unixfs_file_block(chunk, UNIXFS_RAW))
+                };
+                (leaf, chunk.len())
+            })
+            .collect();
+
+        let root = file_node(&leaves);
+        let cid = cid_v1(DAG_PB_CODEC, &root).to_string();
+        (data, root, cid)
+    }
+
+    #[test]
+    fn chunked_file_verifies_through_root_block() {
+        for raw_leaves in [true, false] {
+            let (data, root, cid) = chunked_file(raw_leaves);
+            assert_eq!(check_single_block(&data, &cid).unwrap(), CidCheck::NeedsRootBlock);
+            assert!(verify_root_block(&data, &cid, &root).unwrap().is_empty());
+
+            let mut tampered = data.clone();
+            tampered[DEFAULT_CHUNK_SIZE + 10] ^= 1;
+            if raw_leaves {
+                assert!(verify_root_block(&tampered, &cid, &root).is_err());
+            } else {
+                // A dag-pb child that doesn't match could be an intermediate node, so its block decides
+                let pending = verify_root_block(&tampered, &cid, &root).unwrap();
+                assert_eq!(pending.len(), 1);
+                let leaf = unixfs_file_block(&data[pending[0].range.clone()], UNIXFS_RAW);
+                assert!(verify_child_block(&tampered, &cid, &pending[0], &leaf).is_err());
+            }
+            assert!(verify_root_block(&data[..data.len() - 1], &cid, &root).is_err());
+        }
+    }
+
+    #[test]
+    fn forged_root_block_is_rejected() {
+        let (data, mut root, cid) = chunked_file(true);
+        let last = root.len() - 1;
+        root[last] ^= 1;
+        let err = verify_root_block(&data, &cid, &root).unwrap_err();
+        assert!(err.to_string().contains("root block does not hash"));
+    }
+
+    #[test]
+    fn intermediate_blocks_are_fetched_and_verified() {
+        // root -> two intermediate nodes -> raw leaves
+        let data: Vec<u8> = (0..4_500).map(|i| (i % 251) as u8).collect();
+        let mut blocks = std::collections::HashMap::new();
+        let intermediates: Vec<(Cid, usize)> = data.chunks(2_000)
+            .map(|part| {
+                let leaves: Vec<(Cid, usize)> = part.chunks(1_000).map(|chunk| (cid_v1(RAW_CODEC, chunk), chunk.len())).collect();
+                let block = file_node(&leaves);
+                let cid = cid_v1(DAG_PB_CODEC, &block);
+                blocks.insert(cid, block);
+                (cid, part.len())
+            })
+            .collect();
+        let root = file_node(&intermediates);
+        let cid = cid_v1(DAG_PB_CODEC, &root).to_string();
+
+        let pending = verify_root_block(&data, &cid, &root).unwrap();
+        assert_eq!(pending.iter().map(|child| child.range.clone()).collect::<Vec<_>>(), vec![0..2_000, 2_000..4_000, 4_000..4_500]);
+        for child in &pending {
+            assert!(verify_child_block(&data, &cid, child, &blocks[&child.cid]).unwrap().is_empty());
+        }
+
+        // The root alone can't see a change below an intermediate node
+        let mut tampered = data.clone();
+        tampered[2_500] ^= 1;
+        let pending = verify_root_block(&tampered, &cid, &root).unwrap();
+        let err = verify_child_block(&tampered, &cid, &pending[1], &blocks[&pending[1].cid]).unwrap_err();
+        assert!(err.to_string().contains("chunk at offset 2000"));
+
+        let err = verify_child_block(&data, &cid, &pending[1], &blocks[&pending[0].cid]).unwrap_err();
+        assert!(err.to_string().contains("does not hash to its CID"));
+    }
+}
@@ -1,214 +1,1273 @@
+use crate::cache::PackageCache;
+use crate::config::{Config, IpfsBackend};
+use crate::error::AntSolError;
+use crate::extract::{extract_archive, ExtractLimits};
+use crate::gateway_stats::{GatewayOutcome, GatewayStats};
+use crate::integrity::{self, CidCheck};
+use crate::package_files::{largest_files, package_files, readme_path, IGNORE_FILE};
+use crate::pinning::{Filebase, Kubo, Pinata, PinningProvider, Web3Storage};
+use crate::publish_state::PublishState;
 use crate::types::Result;
-use flate2::write::GzEncoder;
-use flate2::read::GzDecoder;
-use flate2::Compression;
-use reqwest::multipart;
+use crate::utils::{format_size, print_warning, sha256_hex};
+use flate2::{Compression, GzBuilder};
+use indicatif::ProgressBar;
 use serde::Deserialize;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use tar::Archive;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
 
 #[derive(Debug, Deserialize)]
-struct PinataResponse {
-    #[serde(rename = "IpfsHash")]
-    ipfs_hash: String,
+struct KuboVersionResponse {
+    #[serde(rename = "Version")]
+    version: String,
 }
 
-/// Client for IPFS operations via Pinata
+/// An archive pinned to IPFS
+#[derive(Debug, Clone)]
+pub struct UploadedArchive {
+    pub cid: String,
+    /// Hex sha256 of the exact tar.gz bytes uploaded, recorded on-chain
+    pub sha256: String,
+    /// The CID came from the package's publish state instead of a new upload
+    pub reused: bool,
+    /// CID of the package's README.md, pinned as a file of its own
+    pub readme_cid: Option<String>,
+}
+
+/// An archive installed by [`IpfsClient::download_package_with_progress`]
+#[derive(Debug, Clone)]
+pub struct FetchedArchive {
+    /// Hex sha256 of the archive
+    pub sha256: String,
+    /// Extracted from the download cache instead of a gateway
+    pub from_cache: bool,
+}
+
+/// How long downloads wait on gateways
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GatewayTimeouts {
+    /// To connect to a gateway
+    pub connect: Duration,
+    /// For a response, and then for each chunk of its body
+    pub read: Duration,
+    /// For one whole download, across every gateway tried
+    pub total: Duration,
+}
+
+impl GatewayTimeouts {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            connect: Duration::from_secs(config.ipfs_connect_timeout_secs),
+            read: Duration::from_secs(config.ipfs_read_timeout_secs),
+            total: Duration::from_secs(config.ipfs_download_timeout_secs),
+        }
+    }
+}
+
+impl Default for GatewayTimeouts {
+    fn default() -> Self {
+        Self::from_config(&Config::default())
+    }
+}
+
+/// Client for IPFS uploads through the configured [`PinningProvider`] and gateway downloads
 pub struct IpfsClient {
     api_url: String,
     jwt_token: Option<String>,
+    /// API token for web3.storage and Filebase
+    pinning_token: Option<String>,
+    backend: IpfsBackend,
+    /// Where per-gateway download stats are persisted, if anywhere
+    stats_path: Option<PathBuf>,
+    /// Download gateways, in configured order
+    gateways: Vec<String>,
+    timeouts: GatewayTimeouts,
+    /// Largest archive uploads and downloads accept, in bytes
+    max_archive_size: u64,
+    /// Download cache consulted before any gateway, if any
+    cache: Option<PackageCache>,
+    /// Serve downloads from the cache only; a miss never reaches a gateway
+    offline: bool,
+    /// What downloaded archives may extract to
+    extract_limits: ExtractLimits,
 }
 
 impl IpfsClient {
     /// Create new IPFS client with optional JWT token
     pub fn new(api_url: String) -> Self {
         let jwt_token = std::env::var("PINATA_JWT").ok();
-        Self { api_url, jwt_token }
+        Self {
+            api_url,
+            jwt_token,
+            pinning_token: None,
+            backend: IpfsBackend::Pinata,
+            stats_path: GatewayStats::default_path().ok(),
+            gateways: Config::default_ipfs_gateways(),
+            timeouts: GatewayTimeouts::default(),
+            max_archive_size: Config::default_max_package_size(),
+            cache: None,
+            offline: false,
+            extract_limits: ExtractLimits::default(),
+        }
     }
     
     /// Create new IPFS client with explicit JWT token
     pub fn with_jwt(api_url: String, jwt: String) -> Self {
         Self { 
             api_url, 
-            jwt_token: Some(jwt) 
+            jwt_token: Some(jwt),
+            pinning_token: None,
+            backend: IpfsBackend::Pinata,
+            stats_path: GatewayStats::default_path().ok(),
+            gateways: Config::default_ipfs_gateways(),
+            timeouts: GatewayTimeouts::default(),
+            max_archive_size: Config::default_max_package_size(),
+            cache: None,
+            offline: false,
+            extract_limits: ExtractLimits::default(),
         }
     }
     
-    /// Upload a package directory to IPFS
-    pub async fn upload_package(&self, package_path: &Path) -> Result<String> {
+    /// Create an upload client for the configured backend and credentials
+    pub fn from_config(config: &Config) -> Self {
+        let mut client = match config.pinata_jwt.clone() {
+            Some(jwt) => Self::with_jwt(config.ipfs_url.clone(), jwt),
+            None => Self::new(config.ipfs_url.clone()),
+        };
+        client.backend = config.ipfs_backend();
+        client.pinning_token = config.pinning_token.clone();
+        client.gateways = config.ipfs_gateways.clone();
+        client.timeouts = GatewayTimeouts::from_config(config);
+        client.max_archive_size = config.max_package_size;
+        client.extract_limits = ExtractLimits::from_config(config);
+        client
+    }
+    
+    /// Serve downloads from `cache` when it holds the CID, and store every
+    /// verified download there
+    pub fn with_cache(mut self, cache: PackageCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+    
+    /// Never contact a gateway: downloads the cache can't serve fail with an
+    /// error naming the CID
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+    
+    /// Extract symlinks that stay inside the package instead of rejecting them
+    pub fn with_symlinks_allowed(mut self, allow: bool) -> Self {
+        self.extract_limits.allow_symlinks = allow;
+        self
+    }
+    
+    pub fn extract_limits(&self) -> &ExtractLimits {
+        &self.extract_limits
+    }
+    
+    /// Upload a package directory to IPFS.
+    ///
+    /// Unless `fresh`, the upload is skipped when the package's
+    /// [`PublishState`] recorded this exact archive pinned through the same
+    /// API, and that CID is reused. A new upload is recorded there so a
+    /// failed transaction can be retried without uploading again.
+    ///
+    /// A `README.md` in the package root is pinned separately as well.
+    pub async fn upload_package(&self, package_path: &Path, fresh: bool) -> Result<UploadedArchive> {
         // Create compressed archive
-        let archive_path = self.create_archive(package_path)?;
+        let archive_path = create_archive(package_path)?;
+        let size = std::fs::metadata(&archive_path)?.len();
+        if size > self.max_archive_size {
+            std::fs::remove_file(&archive_path)?;
+            return Err(archive_too_large(package_path, size, self.max_archive_size)?.into());
+        }
+        let sha256 = std::fs::read(&archive_path).map(|bytes| sha256_hex(&bytes));
+        let sha256 = match sha256 {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                std::fs::remove_file(archive_path)?;
+                return Err(e.into());
+            }
+        };
+        
+        let readme = readme_path(package_path);
+        let readme_sha256 = match &readme {
+            Some(path) => match std::fs::read(path) {
+                Ok(bytes) => Some(sha256_hex(&bytes)),
+                Err(e) => {
+                    std::fs::remove_file(archive_path)?;
+                    return Err(e.into());
+                }
+            },
+            None => None,
+        };
+        
+        let previous = if fresh { None } else { PublishState::load(package_path) };
+        if let Some(state) = previous.filter(|state| state.matches(&sha256, readme_sha256.as_deref(), &self.api_url)) {
+            std::fs::remove_file(archive_path)?;
+            return Ok(UploadedArchive { cid: state.cid, sha256, reused: true, readme_cid: state.readme_cid });
+        }
         
-        // Upload to Pinata (IPFS pinning service)
-        let cid = self.upload_to_pinata(&archive_path).await?;
+        // Upload to the pinning service or local node
+        let cid = self.pin(&archive_path).await;
         
         // Clean up temporary archive
         std::fs::remove_file(archive_path)?;
-        
-        Ok(cid)
+        let cid = cid?;
+        let readme_cid = match &readme {
+            Some(path) => Some(self.pin(path).await?),
+            None => None,
+        };
+        let state = PublishState {
+            archive_sha256: sha256.clone(),
+            cid: cid.clone(),
+            pinned_with: self.api_url.clone(),
+            uploaded_at: chrono::Utc::now().timestamp(),
+            readme_sha256,
+            readme_cid: readme_cid.clone(),
+        };
+        // Only costs a re-upload on retry, so it doesn't fail the publish
+        if let Err(e) = state.save(package_path) {
+            print_warning(&format!("Could not record the upload in {}: {}", PublishState::path(package_path).display(), e));
+        }
+        Ok(UploadedArchive { cid, sha256, reused: false, readme_cid })
     }
     
-    /// Create tar.gz archive from package directory
-    fn create_archive(&self, package_path: &Path) -> Result<PathBuf> {
-        let temp_dir = std::env::temp_dir();
-        let archive_name = format!("antsol_package_{}.tar.gz", uuid::Uuid::new_v4());
-        let archive_path = temp_dir.join(archive_name);
-        
-        let tar_gz = File::create(&archive_path)?;
-        let enc = GzEncoder::new(tar_gz, Compression::default());
-        let mut tar = tar::Builder::new(enc);
-        
-        // Add all files from package directory
-        for entry in walkdir::WalkDir::new(package_path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let path = entry.path();
-            let relative_path = path.strip_prefix(package_path)?;
-            
-            // Skip target directories, hidden files, and build artifacts
-            let path_str = relative_path.to_string_lossy();
-            if path_str.contains("target/") 
-                || path_str.starts_with(".") 
-                || path_str.ends_with(".lock") {
-                continue;
-            }
-            
-            tar.append_path_with_name(path, relative_path)?;
+    /// Pin the file at `path` with the configured provider
+    async fn pin(&self, path: &Path) -> Result<String> {
+        let api_url = self.api_url.as_str();
+        match self.backend {
+            IpfsBackend::Pinata => Pinata::new(api_url, self.jwt_token.clone()).pin_file(path).await,
+            IpfsBackend::Kubo => Kubo::new(api_url).pin_file(path).await,
+            IpfsBackend::Web3Storage => Web3Storage::new(api_url, self.pinning_token.clone()).pin_file(path).await,
+            IpfsBackend::Filebase => Filebase::new(api_url, self.pinning_token.clone()).pin_file(path).await,
         }
-        
-        tar.finish()?;
-        Ok(archive_path)
     }
     
-    /// Upload file to Pinata IPFS pinning service
-    async fn upload_to_pinata(&self, archive_path: &Path) -> Result<String> {
-        let jwt = self.jwt_token.as_ref()
-            .ok_or("PINATA_JWT token not found. Set PINATA_JWT environment variable.")?;
-        
-        let client = reqwest::Client::new();
-        let file = tokio::fs::read(archive_path).await?;
-        let file_part = multipart::Part::bytes(file)
-            .file_name(archive_path.file_name().unwrap().to_string_lossy().to_string());
-        
-        let form = multipart::Form::new().part("file", file_part);
-        
+    /// Version of the Kubo node at `api_url`, `None` if nothing answers there
+    pub async fn kubo_version(api_url: &str) -> Option<String> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(2))
+            .build()
+            .ok()?;
         let response = client
-            .post(&format!("{}/pinning/pinFileToIPFS", self.api_url))
-            .header("Authorization", format!("Bearer {}", jwt))
-            .multipart(form)
+            .post(format!("{}/api/v0/version", api_url.trim_end_matches('/')))
             .send()
-            .await?;
-        
+            .await
+            .ok()?;
         if !response.status().is_success() {
-            let error_text = response.text().await?;
-            return Err(format!("Failed to upload to Pinata: {}", error_text).into());
+            return None;
         }
-        
-        let result: PinataResponse = response.json().await?;
-        Ok(result.ipfs_hash)
+        response.json::<KuboVersionResponse>().await.ok().map(|v| v.version)
     }
     
-    /// Download package from IPFS and verify integrity
-    pub async fn download_package(&self, cid: &str, output_path: &Path) -> Result<()> {
-        let gateways = vec![
-            format!("https://gateway.pinata.cloud/ipfs/{}", cid),
-            format!("https://ipfs.io/ipfs/{}", cid),
-            format!("https://cloudflare-ipfs.com/ipfs/{}", cid),
-        ];
-        
-        let client = reqwest::Client::new();
-        let mut last_error = None;
-        
-        // Try multiple IPFS gateways for reliability
-        for gateway in gateways {
-            match client.get(&gateway).send().await {
-                Ok(response) if response.status().is_success() => {
-                    let bytes = response.bytes().await?;
-                    
-                    // Save to temporary file
-                    let temp_file = output_path.join("package.tar.gz");
-                    std::fs::write(&temp_file, bytes)?;
-                    
-                    // Verify file integrity using CID
-                    if !self.verify_cid(&temp_file, cid)? {
-                        std::fs::remove_file(&temp_file)?;
-                        return Err("File integrity check failed - CID mismatch!".into());
+    /// Gateway base URLs, best-ranked first according to local stats
+    fn ranked_gateways(&self) -> Vec<String> {
+        self.ranked_gateways_with(&self.load_stats())
+    }
+    
+    fn ranked_gateways_with(&self, stats: &GatewayStats) -> Vec<String> {
+        let gateways: Vec<&str> = self.gateways.iter().map(String::as_str).collect();
+        stats.rank(&gateways)
+    }
+    
+    fn load_stats(&self) -> GatewayStats {
+        self.stats_path.as_deref().map(GatewayStats::load).unwrap_or_default()
+    }
+    
+    /// HTTP client for gateway requests, with the connect timeout
+    fn gateway_client(&self) -> reqwest::Result<reqwest::Client> {
+        reqwest::Client::builder().connect_timeout(self.timeouts.connect).build()
+    }
+    
+    /// Time each configured gateway takes to answer a request it can serve
+    /// without looking anything up, in configured order
+    pub async fn probe_gateways(&self) -> Result<Vec<(String, std::result::Result<Duration, String>)>> {
+        let client = self.gateway_client()?;
+        let mut probes = JoinSet::new();
+        for (index, gateway) in self.gateways.iter().cloned().enumerate() {
+            let request = client.get(format!("{}/ipfs/{}", gateway, PROBE_CID));
+            let read = self.timeouts.read;
+            probes.spawn(async move {
+                let started = Instant::now();
+                let result = send(request, read).await.map(|_| started.elapsed()).map_err(|(_, reason)| reason);
+                (index, gateway, result)
+            });
+        }
+        let mut results = Vec::new();
+        while let Some(joined) = probes.join_next().await {
+            if let Ok(probe) = joined {
+                results.push(probe);
+            }
+        }
+        results.sort_by_key(|(index, _, _)| *index);
+        Ok(results.into_iter().map(|(_, gateway, result)| (gateway, result)).collect())
+    }
+    
+    /// Archive size reported by the first gateway that answers a HEAD request
+    pub async fn content_size(&self, cid: &str) -> Option<u64> {
+        let client = self.gateway_client().ok()?;
+        for gateway in self.ranked_gateways() {
+            let url = format!("{}/ipfs/{}", gateway, cid);
+            if let Ok(response) = client.head(&url).timeout(self.timeouts.read).send().await {
+                if response.status().is_success() {
+                    if let Some(size) = response.content_length() {
+                        return Some(size);
                     }
-                    
-                    // Extract archive
-                    self.extract_archive(&temp_file, output_path)?;
-                    
-                    // Clean up
-                    std::fs::remove_file(temp_file)?;
-                    
-                    return Ok(());
                 }
-                Err(e) => {
-                    last_error = Some(e.to_string());
-                    continue;
-                }
-                _ => continue,
             }
         }
-        
-        Err(format!(
-            "Failed to download from all IPFS gateways. Last error: {}",
-            last_error.unwrap_or_else(|| "Unknown error".to_string())
-        ).into())
+        None
     }
     
-    /// Verify CID matches file content (simplified verification)
-    fn verify_cid(&self, file_path: &Path, expected_cid: &str) -> Result<bool> {
-        use sha2::{Sha256, Digest};
-        
-        let mut file = File::open(file_path)?;
-        let mut hasher = Sha256::new();
-        let mut buffer = [0; 8192];
-        
-        loop {
-            let n = file.read(&mut buffer)?;
-            if n == 0 { break; }
-            hasher.update(&buffer[..n]);
+    /// Download package from IPFS and verify integrity.
+    ///
+    /// Returns the sha256 of the downloaded archive; when `expected_sha256`
+    /// is given a mismatching archive is rejected before extraction. Every
+    /// gateway attempt is recorded in the local gateway stats.
+    pub async fn download_package(&self, cid: &str, output_path: &Path, expected_sha256: Option<&str>) -> Result<String> {
+        let fetched = self.download_package_with_progress(cid, output_path, expected_sha256, &ProgressBar::hidden()).await?;
+        Ok(fetched.sha256)
+    }
+    
+    /// [`Self::download_package`], advancing `progress` by the bytes written.
+    /// Its length is set from the winning gateway's Content-Length. A cache
+    /// hit is extracted without contacting any gateway. The download fails
+    /// once it has taken longer than the configured deadline.
+    pub async fn download_package_with_progress(
+        &self,
+        cid: &str,
+        output_path: &Path,
+        expected_sha256: Option<&str>,
+        progress: &ProgressBar,
+    ) -> Result<FetchedArchive> {
+        if let Some(cache) = &self.cache {
+            if let Some((archive, sha256)) = cache.fetch(cid, expected_sha256, chrono::Utc::now().timestamp())? {
+                extract_archive(&archive, output_path, &self.extract_limits)?;
+                return Ok(FetchedArchive { sha256, from_cache: true });
+            }
         }
-        
-        let hash = hasher.finalize();
-        let computed_hash = format!("{:x}", hash);
-        
-        // Simplified CID verification (in production, use proper multihash)
-        // For now, just check if the CID looks valid (starts with Qm or bafy)
-        Ok(expected_cid.starts_with("Qm") || expected_cid.starts_with("bafy"))
+        if self.offline {
+            return Err(AntSolError::NotFound(format!(
+                "{} is not in the download cache and gateways are off limits offline; run 'antsol fetch' while online",
+                cid
+            )).into());
+        }
+        let mut stats = self.load_stats();
+        let download = self.download_from_gateways(cid, output_path, expected_sha256, &mut stats, progress);
+        let result = match tokio::time::timeout(self.timeouts.total, download).await {
+            Ok(result) => result,
+            Err(_) => {
+                // Dropping the download removed its partial archive
+                Err(AntSolError::Ipfs(format!(
+                    "Downloading {} took longer than {}s; raise ipfs_download_timeout_secs for large packages or slow connections",
+                    cid,
+                    self.timeouts.total.as_secs()
+                )).into())
+            }
+        };
+        if let Some(path) = &self.stats_path {
+            // Stats are best-effort; never fail a download because of them
+            let _ = stats.save(path);
+        }
+        Ok(FetchedArchive { sha256: result?, from_cache: false })
     }
     
-    /// Extract tar.gz archive with security checks
-    fn extract_archive(&self, archive_path: &Path, output_path: &Path) -> Result<()> {
-        let tar_gz = File::open(archive_path)?;
-        let tar = GzDecoder::new(tar_gz);
-        let mut archive = Archive::new(tar);
-        
-        std::fs::create_dir_all(output_path)?;
+    async fn download_from_gateways(
+        &self,
+        cid: &str,
+        output_path: &Path,
+        expected_sha256: Option<&str>,
+        stats: &mut GatewayStats,
+        progress: &ProgressBar,
+    ) -> Result<String> {
+        let client = self.gateway_client()?;
+        let temp_file = PartialArchive::new();
+        let mut file = File::create(&temp_file.0)?;
+        let mut written = 0u64;
+        let mut candidates = self.ranked_gateways_with(stats);
+        let mut failures = GatewayFailures::default();
+        let mut verification_failure = None;
         
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let path = entry.path()?;
+        // Race every remaining gateway; after a failure mid-stream the next
+        // race asks for the rest of the archive with a Range request
+        while !candidates.is_empty() {
+            let race = race_gateways(&client, &mut candidates, cid, written, self.timeouts.read, stats, &mut failures);
+            let Some(mut stream) = race.await else {
+                break;
+            };
+            if !stream.resumed && written > 0 {
+                // The gateway ignored the Range header; start over from byte 0
+                file.set_len(0)?;
+                file.seek(SeekFrom::Start(0))?;
+                written = 0;
+            }
+            if let Some(remaining) = stream.response.content_length() {
+                // Every gateway serves the same bytes for a CID, so there is no point trying another
+                if written + remaining > self.max_archive_size {
+                    return Err(download_too_large(cid, written + remaining, self.max_archive_size).into());
+                }
+                progress.set_length(written + remaining);
+            }
+            progress.set_position(written);
             
-            // Security check: prevent path traversal attacks
-            if path.to_string_lossy().contains("..") {
-                return Err("Malicious path detected in archive!".into());
+            let mut chunk = Some(std::mem::take(&mut stream.first_chunk));
+            let mut failed = None;
+            while let Some(bytes) = chunk {
+                // Gateways that stream without a Content-Length are cut off at the limit
+                if written + bytes.len() as u64 > self.max_archive_size {
+                    return Err(download_too_large(cid, written + bytes.len() as u64, self.max_archive_size).into());
+                }
+                file.write_all(&bytes)?;
+                written += bytes.len() as u64;
+                progress.set_position(written);
+                chunk = match read_chunk(&mut stream.response, self.timeouts.read).await {
+                    Ok(next) => next,
+                    Err(e) => {
+                        failed = Some(e);
+                        None
+                    }
+                };
+            }
+            if let Some((outcome, reason)) = failed {
+                stats.record(&stream.gateway, outcome, stream.started.elapsed());
+                failures.push(&stream.gateway, reason);
+                candidates.retain(|g| *g != stream.gateway);
+                continue;
             }
+            let latency = stream.started.elapsed();
+            file.flush()?;
             
-            let output_file = output_path.join(path.as_ref());
+            let bytes = std::fs::read(&temp_file.0)?;
+            let sha256 = sha256_hex(&bytes);
+            if let Some(expected) = expected_sha256 {
+                if expected != sha256 {
+                    // A corrupted copy from one gateway; another may serve the real bytes
+                    stats.record(&stream.gateway, GatewayOutcome::VerificationFailed, latency);
+                    verification_failure = Some(format!(
+                        "Archive sha256 mismatch for {} from {}: expected {}, got {}",
+                        cid, stream.gateway, expected, sha256
+                    ));
+                    candidates.retain(|g| *g != stream.gateway);
+                    file.set_len(0)?;
+                    file.seek(SeekFrom::Start(0))?;
+                    written = 0;
+                    continue;
+                }
+            }
             
-            if let Some(parent) = output_file.parent() {
-                std::fs::create_dir_all(parent)?;
+            // Verify file integrity against the CID before extracting anything;
+            // like a checksum mismatch, a bad copy only rules out this gateway
+            if let Err(e) = self.verify_cid(&client, &stream.gateway, &bytes, cid).await {
+                stats.record(&stream.gateway, GatewayOutcome::VerificationFailed, latency);
+                verification_failure = Some(format!("CID verification failed for {} from {}: {}", cid, stream.gateway, e));
+                candidates.retain(|g| *g != stream.gateway);
+                file.set_len(0)?;
+                file.seek(SeekFrom::Start(0))?;
+                written = 0;
+                continue;
             }
+            stats.record(&stream.gateway, GatewayOutcome::Success, latency);
             
-            entry.unpack(&output_file)?;
+            drop(file);
+            if let Some(cache) = &self.cache {
+                // A cache that can't be written only costs the next install a download
+                let _ = cache.store(cid, &temp_file.0, &sha256, chrono::Utc::now().timestamp());
+            }
+            extract_archive(&temp_file.0, output_path, &self.extract_limits)?;
+            return Ok(sha256);
         }
         
+        if let Some(failure) = verification_failure {
+            return Err(AntSolError::Verification(failure).into());
+        }
+        Err(failures.into_error(cid).into())
+    }
+    
+    /// Make sure the download cache holds `cid`, downloading it if needed.
+    /// The archive is unpacked once into scratch space, which checks it the
+    /// same way an install would, then discarded. `from_cache` tells whether
+    /// it was already there.
+    pub async fn fetch_to_cache(&self, cid: &str, expected_sha256: Option<&str>, progress: &ProgressBar) -> Result<FetchedArchive> {
+        let Some(cache) = &self.cache else {
+            return Err(AntSolError::Config("No download cache to fetch into".into()).into());
+        };
+        let now = chrono::Utc::now().timestamp();
+        if let Some((_, sha256)) = cache.fetch(cid, expected_sha256, now)? {
+            return Ok(FetchedArchive { sha256, from_cache: true });
+        }
+        let scratch = std::env::temp_dir().join(format!("antsol-fetch-{}-{}", std::process::id(), cid));
+        std::fs::create_dir_all(&scratch)?;
+        let fetched = self.download_package_with_progress(cid, &scratch, expected_sha256, progress).await;
+        let _ = std::fs::remove_dir_all(&scratch);
+        let fetched = fetched?;
+        // Downloads only warn about a cache they can't write; here that's the whole point
+        if cache.fetch(cid, Some(&fetched.sha256), now)?.is_none() {
+            return Err(AntSolError::Config(format!(
+                "Downloaded {} but could not store it in {}",
+                cid,
+                cache.root().display()
+            )).into());
+        }
+        Ok(fetched)
+    }
+    
+    /// Download a single small file, such as a README, from the first gateway
+    /// whose bytes match `cid`. Nothing is cached or extracted.
+    pub async fn download_file(&self, cid: &str) -> Result<Vec<u8>> {
+        let client = self.gateway_client()?;
+        let mut failures = GatewayFailures::default();
+        for gateway in self.ranked_gateways() {
+            let request = client.get(format!("{}/ipfs/{}", gateway, cid)).timeout(self.timeouts.total);
+            let response = match send(request, self.timeouts.read).await {
+                Ok(response) => response,
+                Err((_, reason)) => {
+                    failures.push(&gateway, reason);
+                    continue;
+                }
+            };
+            if let Some(size) = response.content_length().filter(|size| *size > self.max_archive_size) {
+                return Err(download_too_large(cid, size, self.max_archive_size).into());
+            }
+            let bytes = match response.bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    failures.push(&gateway, format!("read failed: {}", e));
+                    continue;
+                }
+            };
+            match self.verify_cid(&client, &gateway, &bytes, cid).await {
+                Ok(()) => return Ok(bytes.to_vec()),
+                Err(e) => failures.push(&gateway, e),
+            }
+        }
+        Err(failures.into_error(cid).into())
+    }
+    
+    /// Verify downloaded bytes against the expected CID.
+    ///
+    /// Raw and single-block dag-pb CIDs are recomputed locally. For chunked
+    /// UnixFS files the root block, and any intermediate blocks below it, are
+    /// fetched from `gateway` with `?format=raw` and checked against the CID
+    /// and the content.
+    async fn verify_cid(&self, client: &reqwest::Client, gateway: &str, data: &[u8], expected_cid: &str) -> Result<()> {
+        if integrity::check_single_block(data, expected_cid)? == CidCheck::Verified {
+            return Ok(());
+        }
+        
+        let root_block = self.fetch_raw_block(client, gateway, expected_cid).await?;
+        let mut pending = integrity::verify_root_block(data, expected_cid, &root_block)?;
+        while let Some(child) = pending.pop() {
+            let block = self.fetch_raw_block(client, gateway, &child.cid.to_string()).await?;
+            pending.extend(integrity::verify_child_block(data, expected_cid, &child, &block)?);
+        }
         Ok(())
     }
+    
+    /// One block of a DAG, as stored, from `gateway`
+    async fn fetch_raw_block(&self, client: &reqwest::Client, gateway: &str, cid: &str) -> Result<Vec<u8>> {
+        let url = format!("{}/ipfs/{}?format=raw", gateway, cid);
+        let response = client.get(&url)
+            .header(reqwest::header::ACCEPT, "application/vnd.ipld.raw")
+            .timeout(self.timeouts.total)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Verification(format!("Could not fetch block {} from {}: {}", cid, gateway, e)))?;
+        if !response.status().is_success() {
+            return Err(AntSolError::Verification(format!(
+                "Could not fetch block {} from {}: status {}",
+                cid, gateway, response.status()
+            )).into());
+        }
+        let block = response.bytes().await
+            .map_err(|e| AntSolError::Verification(format!("Could not read block {} from {}: {}", cid, gateway, e)))?;
+        Ok(block.to_vec())
+    }
+}
+
+/// A gateway that won a download race, with the bytes it answered first
+struct GatewayStream {
+    gateway: String,
+    response: reqwest::Response,
+    first_chunk: Vec<u8>,
+    started: Instant,
+    /// The gateway honoured the Range request and sent only the remainder
+    resumed: bool,
+}
+
+/// Why each gateway failed a download, in the order they failed
+#[derive(Debug, Default)]
+struct GatewayFailures(Vec<(String, String)>);
+
+impl GatewayFailures {
+    fn push(&mut self, gateway: &str, reason: impl std::fmt::Display) {
+        self.0.push((gateway.to_string(), reason.to_string()));
+    }
+    
+    /// The error once every gateway has failed, one line per gateway
+    fn into_error(self, cid: &str) -> AntSolError {
+        if self.0.is_empty() {
+            return AntSolError::Ipfs(format!("Failed to download {}: no IPFS gateways configured", cid));
+        }
+        let lines: Vec<String> = self.0.iter().map(|(gateway, reason)| format!("  {}: {}", gateway, reason)).collect();
+        AntSolError::Ipfs(format!("Failed to download {} from all IPFS gateways:\n{}", cid, lines.join("\n")))
+    }
+}
+
+/// Where a download is written until it is extracted: a file in the system
+/// temp directory, outside the extraction target, removed when dropped
+struct PartialArchive(PathBuf);
+
+impl PartialArchive {
+    fn new() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        Self(std::env::temp_dir().join(format!("antsol-download-{}-{}.tar.gz", std::process::id(), n)))
+    }
+}
+
+impl Drop for PartialArchive {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// The empty raw block, whose bytes are inlined in the CID, so gateways
+/// answer for it without looking anything up
+const PROBE_CID: &str = "bafkqaaa";
+
+/// `probes` from [`IpfsClient::probe_gateways`] ordered fastest first; gateways
+/// that didn't answer keep their relative order at the end
+pub fn order_by_latency(probes: &[(String, std::result::Result<Duration, String>)]) -> Vec<String> {
+    let mut answered: Vec<(&String, Duration)> = probes
+        .iter()
+        .filter_map(|(gateway, result)| result.as_ref().ok().map(|latency| (gateway, *latency)))
+        .collect();
+    answered.sort_by_key(|(_, latency)| *latency);
+    answered
+        .into_iter()
+        .map(|(gateway, _)| gateway.clone())
+        .chain(probes.iter().filter(|(_, result)| result.is_err()).map(|(gateway, _)| gateway.clone()))
+        .collect()
+}
+
+/// Request `cid` from every gateway in `candidates` at once and keep the
+/// first to start returning bytes; the others are aborted. `None` once
+/// every candidate has failed.
+///
+/// Gateways that fail before the winner answers are recorded in `stats` and
+/// `failures` and dropped from `candidates`. With a non-zero `offset` a
+/// Range request asks for the rest of a partially downloaded archive.
+async fn race_gateways(
+    client: &reqwest::Client,
+    candidates: &mut Vec<String>,
+    cid: &str,
+    offset: u64,
+    read_timeout: Duration,
+    stats: &mut GatewayStats,
+    failures: &mut GatewayFailures,
+) -> Option<GatewayStream> {
+    let mut race = JoinSet::new();
+    for gateway in candidates.iter().cloned() {
+        tracing::debug!("Requesting {} from {}", cid, gateway);
+        let mut request = client.get(format!("{}/ipfs/{}", gateway, cid));
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        race.spawn(async move {
+            let started = Instant::now();
+            let result = first_bytes(request, read_timeout).await;
+            (gateway, started, result)
+        });
+    }
+    
+    while let Some(joined) = race.join_next().await {
+        let Ok((gateway, started, result)) = joined else {
+            continue;
+        };
+        match result {
+            Ok((response, first_chunk)) => {
+                race.abort_all();
+                tracing::debug!("{} answered first after {:?}", gateway, started.elapsed());
+                let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+                return Some(GatewayStream { gateway, response, first_chunk, started, resumed });
+            }
+            Err((outcome, reason)) => {
+                tracing::debug!("{} failed: {}", gateway, reason);
+                stats.record(&gateway, outcome, started.elapsed());
+                failures.push(&gateway, reason);
+                candidates.retain(|g| *g != gateway);
+            }
+        }
+    }
+    None
+}
+
+/// Send `request` and wait for the first chunk of a successful response
+async fn first_bytes(
+    request: reqwest::RequestBuilder,
+    read_timeout: Duration,
+) -> std::result::Result<(reqwest::Response, Vec<u8>), (GatewayOutcome, String)> {
+    let mut response = send(request, read_timeout).await?;
+    let chunk = read_chunk(&mut response, read_timeout).await?;
+    Ok((response, chunk.unwrap_or_default()))
+}
+
+/// Send `request`, giving up when no response arrives within `read_timeout`.
+/// Anything but a success status is a failure.
+async fn send(
+    request: reqwest::RequestBuilder,
+    read_timeout: Duration,
+) -> std::result::Result<reqwest::Response, (GatewayOutcome, String)> {
+    let response = match tokio::time::timeout(read_timeout, request.send()).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => return Err((transport_outcome(&e), format!("request failed: {}", e))),
+        Err(_) => return Err((GatewayOutcome::Timeout, format!("no response within {}s", read_timeout.as_secs_f64()))),
+    };
+    if !response.status().is_success() {
+        return Err((GatewayOutcome::HttpError, format!("returned {}", response.status())));
+    }
+    Ok(response)
+}
+
+/// The next chunk of `response`'s body, giving up when none arrives within `read_timeout`
+async fn read_chunk(
+    response: &mut reqwest::Response,
+    read_timeout: Duration,
+) -> std::result::Result<Option<Vec<u8>>, (GatewayOutcome, String)> {
+    match tokio::time::timeout(read_timeout, response.chunk()).await {
+        Ok(Ok(chunk)) => Ok(chunk.map(|b| b.to_vec())),
+        Ok(Err(e)) => Err((transport_outcome(&e), format!("read failed: {}", e))),
+        Err(_) => Err((GatewayOutcome::Timeout, format!("sent nothing for {}s", read_timeout.as_secs_f64()))),
+    }
+}
+
+fn transport_outcome(e: &reqwest::Error) -> GatewayOutcome {
+    if e.is_timeout() { GatewayOutcome::Timeout } else { GatewayOutcome::ConnectError }
+}
+
+/// Upload refusal for an archive over `limit`, naming the largest files so
+/// they can be excluded
+fn archive_too_large(package_path: &Path, size: u64, limit: u64) -> Result<AntSolError> {
+    let files = package_files(package_path)?;
+    let largest: Vec<String> = largest_files(package_path, &files, 5)?
+        .into_iter()
+        .map(|(path, size)| format!("  {:>10}  {}", format_size(size), path.display()))
+        .collect();
+    Ok(AntSolError::Usage(format!(
+        "Package archive is {}, over the {} limit. Largest files:\n{}\nExclude them in {}, or raise the limit with --max-size or 'antsol config set max_package_size <SIZE>'",
+        format_size(size),
+        format_size(limit),
+        largest.join("\n"),
+        IGNORE_FILE
+    )))
+}
+
+fn download_too_large(cid: &str, size: u64, limit: u64) -> AntSolError {
+    AntSolError::Ipfs(format!(
+        "Archive {} is at least {}, over the {} package size limit; raise max_package_size to install it",
+        cid,
+        format_size(size),
+        format_size(limit)
+    ))
+}
+
+/// OS byte of the gzip header meaning "unknown" (RFC 1952)
+const GZIP_OS_UNKNOWN: u8 = 255;
+
+/// Create tar.gz archive from package directory.
+///
+/// The bytes depend only on the packaged paths, contents and executable
+/// bits, so publishing an unchanged directory again yields the same CID:
+/// entries go in path order, headers carry a fixed mtime and no owner, and
+/// the gzip header has no timestamp or OS.
+pub fn create_archive(package_path: &Path) -> Result<PathBuf> {
+    let temp_dir = std::env::temp_dir();
+    let archive_name = format!("antsol_package_{}.tar.gz", uuid::Uuid::new_v4());
+    let archive_path = temp_dir.join(archive_name);
+    
+    let tar_gz = File::create(&archive_path)?;
+    let enc = GzBuilder::new()
+        .mtime(0)
+        .operating_system(GZIP_OS_UNKNOWN)
+        .write(tar_gz, Compression::default());
+    let mut tar = tar::Builder::new(enc);
+    tar.mode(tar::HeaderMode::Deterministic);
+    
+    // Add the files selected by .antsolignore (or the default exclusions),
+    // which come sorted by path
+    for relative_path in package_files(package_path)? {
+        tar.append_path_with_name(package_path.join(&relative_path), &relative_path)?;
+    }
+    
+    tar.into_inner()?.finish()?;
+    Ok(archive_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use tar::Archive;
+
+    #[test]
+    fn from_config_uses_configured_backend() {
+        let mut config = Config::default();
+        assert_eq!(IpfsClient::from_config(&config).backend, IpfsBackend::Pinata);
+        config.ipfs_url = IpfsBackend::KUBO_DEFAULT_URL.to_string();
+        assert_eq!(IpfsClient::from_config(&config).backend, IpfsBackend::Kubo);
+    }
+
+    #[test]
+    fn archives_are_byte_identical_across_mtimes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "[package]\nname = \"same\"\n").unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "pub fn same() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/nested/b.rs"), "// b\n").unwrap();
+        std::fs::write(dir.path().join("src/nested/a.rs"), "// a\n").unwrap();
+
+        let touch = |secs: u64| {
+            for file in ["antsol.toml", "src/lib.rs", "src/nested/a.rs", "src/nested/b.rs"] {
+                File::options()
+                    .write(true)
+                    .open(dir.path().join(file))
+                    .unwrap()
+                    .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+                    .unwrap();
+            }
+        };
+        touch(1_600_000_000);
+        let first = create_archive(dir.path()).unwrap();
+        touch(1_700_000_000);
+        let second = create_archive(dir.path()).unwrap();
+
+        let (first_bytes, second_bytes) = (std::fs::read(&first).unwrap(), std::fs::read(&second).unwrap());
+        std::fs::remove_file(first).unwrap();
+        std::fs::remove_file(second).unwrap();
+        assert_eq!(first_bytes, second_bytes);
+
+        let mut archive = Archive::new(GzDecoder::new(first_bytes.as_slice()));
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| entry.unwrap().path().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["antsol.toml", "src/lib.rs", "src/nested/a.rs", "src/nested/b.rs"]);
+    }
+
+    const FIXTURE: &[u8] = include_bytes!("../tests/fixtures/hello-pkg.tar.gz");
+    const FIXTURE_CID: &str = "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu";
+
+    #[derive(Clone, Copy)]
+    enum TestGateway {
+        NotFound,
+        /// Answers at once but hangs up halfway through the body
+        Truncated,
+        /// Answers after a delay and honours Range requests
+        Slow,
+        /// Answers at once, sends half the body and then nothing more
+        Stalled,
+        /// Answers at once with the whole body, one byte of it flipped
+        Corrupt,
+    }
+
+    /// Serve the fixture archive on a local port the way `gateway` behaves
+    async fn serve(gateway: TestGateway) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => request.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let offset = request
+                        .lines()
+                        .find_map(|line| line.strip_prefix("range: bytes="))
+                        .and_then(|range| range.trim_end_matches('-').parse::<usize>().ok());
+                    let len = FIXTURE.len();
+                    let (head, body) = match (gateway, offset) {
+                        (TestGateway::NotFound, _) => ("HTTP/1.1 404 Not Found\r\ncontent-length: 0".to_string(), &[][..]),
+                        (TestGateway::Truncated, _) | (TestGateway::Stalled, _) => {
+                            (format!("HTTP/1.1 200 OK\r\ncontent-length: {}", len), &FIXTURE[..len / 2])
+                        }
+                        (TestGateway::Slow, Some(offset)) => {
+                            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                            let head = format!(
+                                "HTTP/1.1 206 Partial Content\r\ncontent-range: bytes {}-{}/{}\r\ncontent-length: {}",
+                                offset, len - 1, len, len - offset
+                            );
+                            (head, &FIXTURE[offset..])
+                        }
+                        (TestGateway::Corrupt, _) => {
+                            let mut corrupt = FIXTURE.to_vec();
+                            corrupt[len / 2] ^= 0xff;
+                            let head = format!("HTTP/1.1 200 OK\r\ncontent-length: {}", len);
+                            let _ = socket.write_all(format!("{}\r\nconnection: close\r\n\r\n", head).as_bytes()).await;
+                            let _ = socket.write_all(&corrupt).await;
+                            let _ = socket.shutdown().await;
+                            return;
+                        }
+                        (TestGateway::Slow, None) => {
+                            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                            (format!("HTTP/1.1 200 OK\r\ncontent-length: {}", len), FIXTURE)
+                        }
+                    };
+                    let _ = socket.write_all(format!("{}\r\nconnection: close\r\n\r\n", head).as_bytes()).await;
+                    let _ = socket.write_all(body).await;
+                    if let TestGateway::Stalled = gateway {
+                        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    }
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn download_races_gateways_and_resumes_with_range() {
+        let not_found = serve(TestGateway::NotFound).await;
+        let truncated = serve(TestGateway::Truncated).await;
+        let slow = serve(TestGateway::Slow).await;
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![not_found.clone(), truncated.clone(), slow.clone()];
+
+        let dir = tempfile::tempdir().unwrap();
+        let progress = ProgressBar::hidden();
+        let mut stats = GatewayStats::default();
+        let expected = sha256_hex(FIXTURE);
+        let sha256 = client
+            .download_from_gateways(FIXTURE_CID, dir.path(), Some(&expected), &mut stats, &progress)
+            .await
+            .unwrap();
+
+        assert_eq!(sha256, expected);
+        assert_eq!(progress.position(), FIXTURE.len() as u64);
+        assert!(dir.path().join("antsol.toml").is_file());
+        assert!(!dir.path().join("package.tar.gz").exists());
+
+        // The truncated gateway won the first race; the slow one finished the archive
+        assert_eq!(stats.gateways[&not_found].last_outcome, Some(GatewayOutcome::HttpError));
+        assert_eq!(stats.gateways[&truncated].failures, 1);
+        assert_eq!(stats.gateways[&slow].successes, 1);
+        assert_eq!(stats.gateways[&slow].failures, 0);
+    }
+
+    #[tokio::test]
+    async fn a_gateway_failing_cid_verification_falls_back_to_the_next() {
+        let corrupt = serve(TestGateway::Corrupt).await;
+        let slow = serve(TestGateway::Slow).await;
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![corrupt.clone(), slow.clone()];
+
+        // An archive of that name in the target is not the download's to remove
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("package.tar.gz"), "mine").unwrap();
+        let mut stats = GatewayStats::default();
+        // No expected sha256, so only the CID check can catch the corrupt copy
+        let sha256 = client
+            .download_from_gateways(FIXTURE_CID, dir.path(), None, &mut stats, &ProgressBar::hidden())
+            .await
+            .unwrap();
+
+        assert_eq!(sha256, sha256_hex(FIXTURE));
+        assert!(dir.path().join("antsol.toml").is_file());
+        assert_eq!(std::fs::read_to_string(dir.path().join("package.tar.gz")).unwrap(), "mine");
+        assert_eq!(stats.gateways[&corrupt].last_outcome, Some(GatewayOutcome::VerificationFailed));
+        assert_eq!(stats.gateways[&slow].successes, 1);
+
+        client.gateways = vec![corrupt];
+        let err = client
+            .download_from_gateways(FIXTURE_CID, dir.path(), None, &mut GatewayStats::default(), &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("CID verification failed"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn downloads_fill_the_cache_and_hits_skip_gateways() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut client = IpfsClient::new(String::new()).with_cache(PackageCache::new(dir.path().join("cache")));
+        client.stats_path = None;
+        client.gateways = vec![serve(TestGateway::Slow).await];
+
+        let expected = sha256_hex(FIXTURE);
+        std::fs::create_dir_all(dir.path().join("first")).unwrap();
+        let first = client
+            .download_package_with_progress(FIXTURE_CID, &dir.path().join("first"), Some(&expected), &ProgressBar::hidden())
+            .await
+            .unwrap();
+        assert!(!first.from_cache);
+
+        // Every gateway is now broken; the cached copy is still served
+        client.gateways = vec![serve(TestGateway::NotFound).await];
+        std::fs::create_dir_all(dir.path().join("second")).unwrap();
+        let second = client
+            .download_package_with_progress(FIXTURE_CID, &dir.path().join("second"), Some(&expected), &ProgressBar::hidden())
+            .await
+            .unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.sha256, expected);
+        assert!(dir.path().join("second/antsol.toml").is_file());
+    }
+
+    #[tokio::test]
+    async fn offline_downloads_only_use_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PackageCache::new(dir.path().join("cache"));
+        let mut client = IpfsClient::new(String::new()).with_cache(cache).with_offline(true);
+        client.stats_path = None;
+        // A gateway that would serve the archive, were it asked
+        client.gateways = vec![serve(TestGateway::Slow).await];
+
+        let expected = sha256_hex(FIXTURE);
+        let err = client
+            .download_package_with_progress(FIXTURE_CID, dir.path(), Some(&expected), &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains(FIXTURE_CID), "{}", err);
+        assert!(err.to_string().contains("antsol fetch"), "{}", err);
+
+        let archive = dir.path().join("seed.tar.gz");
+        std::fs::write(&archive, FIXTURE).unwrap();
+        PackageCache::new(dir.path().join("cache")).store(FIXTURE_CID, &archive, &expected, 1).unwrap();
+        std::fs::create_dir_all(dir.path().join("out")).unwrap();
+        let fetched = client
+            .download_package_with_progress(FIXTURE_CID, &dir.path().join("out"), Some(&expected), &ProgressBar::hidden())
+            .await
+            .unwrap();
+        assert!(fetched.from_cache);
+        assert!(dir.path().join("out/antsol.toml").is_file());
+    }
+
+    #[tokio::test]
+    async fn fetch_to_cache_downloads_once_without_installing() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let mut client = IpfsClient::new(String::new()).with_cache(PackageCache::new(cache_dir.clone()));
+        client.stats_path = None;
+        client.gateways = vec![serve(TestGateway::Slow).await];
+
+        let expected = sha256_hex(FIXTURE);
+        let first = client.fetch_to_cache(FIXTURE_CID, Some(&expected), &ProgressBar::hidden()).await.unwrap();
+        assert_eq!((first.from_cache, first.sha256.as_str()), (false, expected.as_str()));
+        assert!(cache_dir.join(format!("{}.tar.gz", FIXTURE_CID)).is_file());
+
+        client.gateways = vec![serve(TestGateway::NotFound).await];
+        let second = client.fetch_to_cache(FIXTURE_CID, Some(&expected), &ProgressBar::hidden()).await.unwrap();
+        assert!(second.from_cache);
+    }
+
+    #[tokio::test]
+    async fn download_stops_at_the_size_limit() {
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![serve(TestGateway::Slow).await];
+        client.max_archive_size = FIXTURE.len() as u64 - 1;
+
+        let dir = tempfile::tempdir().unwrap();
+        let err = client
+            .download_from_gateways(FIXTURE_CID, dir.path(), None, &mut GatewayStats::default(), &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("package size limit"), "{}", err);
+        assert!(!dir.path().join("package.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn upload_refuses_archives_over_the_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "").unwrap();
+        std::fs::write(dir.path().join("data.json"), "x".repeat(4096)).unwrap();
+        let mut client = IpfsClient::new(String::new());
+        client.max_archive_size = 16;
+
+        let err = client.upload_package(dir.path(), false).await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("over the 16 B limit"), "{}", message);
+        assert!(message.contains("4.0 KB  data.json"), "{}", message);
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Usage);
+    }
+
+    #[tokio::test]
+    async fn failed_publishes_reuse_the_pinned_archive_until_it_changes() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/add"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "Hash": "QmPinned" })))
+            .mount(&server)
+            .await;
+        let mut client = IpfsClient::new(server.uri());
+        client.backend = IpfsBackend::Kubo;
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "[package]\nname = \"foo\"\n").unwrap();
+        std::fs::write(dir.path().join("index.js"), "module.exports = 1;\n").unwrap();
+        let uploads = || async { server.received_requests().await.unwrap().len() };
+
+        let first = client.upload_package(dir.path(), false).await.unwrap();
+        assert!(!first.reused);
+        assert_eq!(PublishState::load(dir.path()).unwrap().cid, "QmPinned");
+
+        // The transaction failed; the retry skips the upload
+        let retry = client.upload_package(dir.path(), false).await.unwrap();
+        assert!(retry.reused);
+        assert_eq!((retry.cid, retry.sha256), (first.cid.clone(), first.sha256.clone()));
+        assert_eq!(uploads().await, 1);
+
+        // Any change to the packaged files means a new archive and a new upload
+        std::fs::write(dir.path().join("index.js"), "module.exports = 2;\n").unwrap();
+        let changed = client.upload_package(dir.path(), false).await.unwrap();
+        assert!(!changed.reused);
+        assert_ne!(changed.sha256, first.sha256);
+        assert_eq!(PublishState::load(dir.path()).unwrap().archive_sha256, changed.sha256);
+        assert_eq!(uploads().await, 2);
+
+        // So does --fresh, and another pinning API
+        assert!(!client.upload_package(dir.path(), true).await.unwrap().reused);
+        client.api_url = format!("{}/", server.uri());
+        assert!(!client.upload_package(dir.path(), false).await.unwrap().reused);
+        assert_eq!(uploads().await, 4);
+
+        PublishState::clear(dir.path());
+        assert!(!dir.path().join(".antsol").exists());
+    }
+
+    #[tokio::test]
+    async fn readmes_are_pinned_alongside_and_reused_only_while_unchanged() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v0/add"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "Hash": "QmPinned" })))
+            .mount(&server)
+            .await;
+        let mut client = IpfsClient::new(server.uri());
+        client.backend = IpfsBackend::Kubo;
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "[package]\nname = \"foo\"\n").unwrap();
+        // Kept out of the archive, so only the README's own hash notices edits
+        std::fs::write(dir.path().join(IGNORE_FILE), "README.md\n").unwrap();
+        std::fs::write(dir.path().join("README.md"), "# foo\n").unwrap();
+        let uploads = || async { server.received_requests().await.unwrap().len() };
+
+        let first = client.upload_package(dir.path(), false).await.unwrap();
+        assert_eq!(first.readme_cid.as_deref(), Some("QmPinned"));
+        assert_eq!(uploads().await, 2);
+
+        let retry = client.upload_package(dir.path(), false).await.unwrap();
+        assert!(retry.reused);
+        assert_eq!(retry.readme_cid.as_deref(), Some("QmPinned"));
+        assert_eq!(uploads().await, 2);
+
+        std::fs::write(dir.path().join("README.md"), "# foo\n\nNow documented.\n").unwrap();
+        let edited = client.upload_package(dir.path(), false).await.unwrap();
+        assert!(!edited.reused);
+        assert_eq!(edited.sha256, first.sha256);
+        assert_eq!(uploads().await, 4);
+
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+        assert_eq!(client.upload_package(dir.path(), false).await.unwrap().readme_cid, None);
+    }
+
+    #[tokio::test]
+    async fn file_downloads_skip_gateways_serving_other_bytes() {
+        use cid::multihash::Multihash;
+        use sha2::{Digest, Sha256};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let readme = b"# foo\n\nA package.\n";
+        let cid = cid::Cid::new_v1(0x55, Multihash::wrap(0x12, &Sha256::digest(readme)).unwrap()).to_string();
+        let (tampered, honest) = (MockServer::start().await, MockServer::start().await);
+        for (server, body) in [(&tampered, &b"# evil\n"[..]), (&honest, &readme[..])] {
+            Mock::given(method("GET"))
+                .and(path(format!("/ipfs/{}", cid)))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(body))
+                .mount(server)
+                .await;
+        }
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![tampered.uri(), honest.uri()];
+
+        assert_eq!(client.download_file(&cid).await.unwrap(), readme);
+
+        client.gateways = vec![tampered.uri()];
+        let err = client.download_file(&cid).await.unwrap_err();
+        assert!(err.to_string().contains("Failed to download"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn download_fails_when_every_gateway_fails() {
+        let (not_found, stalled) = (serve(TestGateway::NotFound).await, serve(TestGateway::Stalled).await);
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![not_found.clone(), stalled.clone()];
+        client.timeouts.read = Duration::from_millis(200);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut stats = GatewayStats::default();
+        let err = client
+            .download_from_gateways(FIXTURE_CID, dir.path(), None, &mut stats, &ProgressBar::hidden())
+            .await
+            .unwrap_err()
+            .to_string();
+        // Each gateway is listed with its own reason
+        assert!(err.contains(&format!("{}: returned 404", not_found)), "{}", err);
+        assert!(err.contains(&format!("{}: sent nothing for 0.2s", stalled)), "{}", err);
+        assert_eq!(stats.gateways[&stalled].last_outcome, Some(GatewayOutcome::Timeout));
+        assert!(!dir.path().join("package.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn download_gives_up_at_the_deadline() {
+        let mut client = IpfsClient::new(String::new());
+        client.stats_path = None;
+        client.gateways = vec![serve(TestGateway::Stalled).await];
+        client.timeouts.total = Duration::from_millis(300);
+
+        let dir = tempfile::tempdir().unwrap();
+        let err = client
+            .download_package_with_progress(FIXTURE_CID, dir.path(), None, &ProgressBar::hidden())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("ipfs_download_timeout_secs"), "{}", err);
+        assert!(!dir.path().join("package.tar.gz").exists());
+    }
+
+    #[tokio::test]
+    async fn gateways_are_ordered_by_probe_latency() {
+        let (not_found, slow, stalled) =
+            (serve(TestGateway::NotFound).await, serve(TestGateway::Slow).await, serve(TestGateway::Stalled).await);
+        let mut client = IpfsClient::new(String::new());
+        client.gateways = vec![not_found.clone(), slow.clone(), stalled.clone()];
+
+        let probes = client.probe_gateways().await.unwrap();
+        assert_eq!(probes.iter().map(|(gateway, _)| gateway).collect::<Vec<_>>(), [&not_found, &slow, &stalled]);
+        assert!(probes[0].1.as_ref().unwrap_err().contains("404"));
+        assert!(probes[1].1.as_ref().unwrap() > probes[2].1.as_ref().unwrap());
+        assert_eq!(order_by_latency(&probes), [stalled, slow, not_found]);
+    }
 }
@@ -0,0 +1,316 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::resolver::MetadataSource;
+use crate::types::{AntSolManifest, Dependency, ExternalDependency, PackageAccount, Result};
+use crate::utils::{compare_versions, latest_version, sha256_hex};
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::path::{Path, PathBuf};
+
+/// CID prefix marking packages served from the local registry.
+///
+/// Local packages are never on IPFS; their "CID" is `local:<archive sha256>`
+/// so they stand out in plans and trees and can't be mistaken for published ones.
+pub const LOCAL_CID_PREFIX: &str = "local:";
+
+const ARCHIVE_NAME: &str = "package.tar.gz";
+const METADATA_NAME: &str = "metadata.json";
+
+/// Whether `cid` refers to a local registry package
+pub fn is_local_cid(cid: &str) -> bool {
+    cid.starts_with(LOCAL_CID_PREFIX)
+}
+
+/// Metadata stored next to each locally published archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalPackage {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub external_dependencies: Vec<ExternalDependency>,
+    /// sha256 of the stored archive
+    pub sha256: String,
+    pub published_at: i64,
+}
+
+impl LocalPackage {
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    /// Present the local package the way the resolver sees chain packages
+    pub fn to_account(&self) -> PackageAccount {
+        PackageAccount {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            authority: Pubkey::default(),
+            ipfs_cid: format!("{}{}", LOCAL_CID_PREFIX, self.sha256),
+            published_at: self.published_at,
+            description: self.description.clone(),
+            dependencies: self.dependencies.clone(),
+            external_dependencies: self.external_dependencies.clone(),
+            content_sha256: Some(self.sha256.clone()),
+            metadata: Default::default(),
+            readme_cid: None,
+            downloads: 0,
+            frozen: false,
+        }
+    }
+}
+
+/// Directory-backed registry for developing packages without devnet or IPFS.
+///
+/// Layout: `<root>/<name>/<version>/{package.tar.gz,metadata.json}`
+pub struct LocalRegistry {
+    root: PathBuf,
+}
+
+impl LocalRegistry {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// `~/.antsol/local-registry`
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::new(Config::config_dir()?.join("local-registry")))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn version_dir(&self, name: &str, version: &str) -> PathBuf {
+        self.root.join(name).join(version)
+    }
+
+    pub fn archive_path(&self, name: &str, version: &str) -> PathBuf {
+        self.version_dir(name, version).join(ARCHIVE_NAME)
+    }
+
+    /// Store `archive` as `manifest`'s package, replacing an earlier local
+    /// publish of the same version. Returns the stored metadata and whether
+    /// a previous copy was replaced.
+    pub fn publish(&self, manifest: &AntSolManifest, archive: &Path, published_at: i64) -> Result<(LocalPackage, bool)> {
+        let dir = self.version_dir(&manifest.package.name, &manifest.package.version);
+        let replaced = dir.exists();
+        std::fs::create_dir_all(&dir)?;
+
+        let bytes = std::fs::read(archive)?;
+        std::fs::write(dir.join(ARCHIVE_NAME), &bytes)?;
+
+        let package = LocalPackage {
+            name: manifest.package.name.clone(),
+            version: manifest.package.version.clone(),
+            description: manifest.package.description.clone(),
+            dependencies: manifest.dependencies.clone().unwrap_or_default(),
+            external_dependencies: manifest.external_dependencies.clone().unwrap_or_default(),
+            sha256: sha256_hex(&bytes),
+            published_at,
+        };
+        std::fs::write(dir.join(METADATA_NAME), serde_json::to_string_pretty(&package)?)?;
+        Ok((package, replaced))
+    }
+
+    pub fn get(&self, name: &str, version: &str) -> Result<Option<LocalPackage>> {
+        let path = self.version_dir(name, version).join(METADATA_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let package = serde_json::from_str(&content).map_err(|e| {
+            AntSolError::Config(format!("Corrupt local registry entry {}: {}", path.display(), e))
+        })?;
+        Ok(Some(package))
+    }
+
+    /// Locally published versions of `name`, lowest first
+    pub fn versions(&self, name: &str) -> Result<Vec<String>> {
+        let dir = self.root.join(name);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut versions = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if entry.path().join(METADATA_NAME).exists() {
+                versions.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        versions.sort_by(|a, b| compare_versions(a, b));
+        Ok(versions)
+    }
+
+    /// Highest locally published version of `name`
+    pub fn latest_version(&self, name: &str) -> Result<Option<String>> {
+        Ok(latest_version(self.versions(name)?.iter().map(String::as_str)))
+    }
+
+    /// Every local package, sorted by name then version
+    pub fn list(&self) -> Result<Vec<LocalPackage>> {
+        if !self.root.is_dir() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = std::fs::read_dir(&self.root)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        let mut packages = Vec::new();
+        for name in names {
+            for version in self.versions(&name)? {
+                packages.extend(self.get(&name, &version)?);
+            }
+        }
+        Ok(packages)
+    }
+
+    /// Remove one version, or every version when `version` is `None`.
+    /// Returns the removed `name@version` ids.
+    pub fn remove(&self, name: &str, version: Option<&str>) -> Result<Vec<String>> {
+        let versions = match version {
+            Some(version) => vec![version.to_string()],
+            None => self.versions(name)?,
+        };
+        let mut removed = Vec::new();
+        for version in versions {
+            let dir = self.version_dir(name, &version);
+            if dir.exists() {
+                std::fs::remove_dir_all(&dir)?;
+                removed.push(format!("{}@{}", name, version));
+            }
+        }
+        if removed.is_empty() {
+            let spec = version.map(|v| format!("{}@{}", name, v)).unwrap_or_else(|| name.to_string());
+            return Err(AntSolError::NotFound(format!("{} is not in the local registry", spec)).into());
+        }
+
+        // Drop the package directory once its last version is gone
+        let package_dir = self.root.join(name);
+        if package_dir.is_dir() && std::fs::read_dir(&package_dir)?.next().is_none() {
+            std::fs::remove_dir(&package_dir)?;
+        }
+        Ok(removed)
+    }
+
+    /// Read a stored archive, checking it still matches its recorded sha256
+    pub fn read_archive(&self, name: &str, version: &str) -> Result<(Vec<u8>, String)> {
+        let package = self.get(name, version)?
+            .ok_or_else(|| AntSolError::NotFound(format!("{}@{} is not in the local registry", name, version)))?;
+        let bytes = std::fs::read(self.archive_path(name, version))?;
+        let sha256 = sha256_hex(&bytes);
+        if sha256 != package.sha256 {
+            return Err(AntSolError::Verification(format!(
+                "Local archive for {} was modified after publishing (expected sha256 {}, got {})",
+                package.id(), package.sha256, sha256
+            )).into());
+        }
+        Ok((bytes, sha256))
+    }
+}
+
+impl MetadataSource for LocalRegistry {
+    async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
+        Ok(self.get(name, version)?.map(|p| p.to_account()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::{resolve, LocalFirst};
+    use crate::resolver::tests::MockSource;
+    use crate::solana_client::tests::sample_package;
+    use crate::types::PackageInfo;
+
+    fn manifest(name: &str, version: &str, deps: &[(&str, &str)]) -> AntSolManifest {
+        AntSolManifest {
+            package: PackageInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                description: format!("{} local package", name),
+                authors: None,
+                license: None,
+                repository: None,
+                homepage: None,
+                keywords: None,
+            },
+            dependencies: Some(deps.iter()
+                .map(|(n, v)| Dependency { name: n.to_string(), version: v.to_string() })
+                .collect()),
+            external_dependencies: None,
+        }
+    }
+
+    fn publish(registry: &LocalRegistry, name: &str, version: &str, deps: &[(&str, &str)]) -> LocalPackage {
+        let archive = registry.root().join("upload.tar.gz");
+        std::fs::create_dir_all(registry.root()).unwrap();
+        std::fs::write(&archive, format!("{}@{}", name, version)).unwrap();
+        let (package, _) = registry.publish(&manifest(name, version, deps), &archive, 1_700_000_000).unwrap();
+        std::fs::remove_file(archive).unwrap();
+        package
+    }
+
+    #[test]
+    fn publish_list_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LocalRegistry::new(dir.path().join("local-registry"));
+
+        publish(&registry, "lib-a", "1.0.0", &[]);
+        publish(&registry, "lib-a", "1.10.0", &[]);
+        let app = publish(&registry, "app", "0.1.0", &[("lib-a", "1.0.0")]);
+
+        assert_eq!(registry.versions("lib-a").unwrap(), vec!["1.0.0", "1.10.0"]);
+        assert_eq!(registry.latest_version("lib-a").unwrap().as_deref(), Some("1.10.0"));
+        assert_eq!(registry.latest_version("missing").unwrap(), None);
+        let ids: Vec<String> = registry.list().unwrap().iter().map(LocalPackage::id).collect();
+        assert_eq!(ids, vec!["app@0.1.0", "lib-a@1.0.0", "lib-a@1.10.0"]);
+
+        let account = app.to_account();
+        assert!(is_local_cid(&account.ipfs_cid));
+        assert_eq!(registry.read_archive("app", "0.1.0").unwrap().1, app.sha256);
+
+        assert_eq!(registry.remove("lib-a", Some("1.0.0")).unwrap(), vec!["lib-a@1.0.0"]);
+        assert_eq!(registry.remove("lib-a", None).unwrap(), vec!["lib-a@1.10.0"]);
+        assert!(!registry.root().join("lib-a").exists());
+        assert!(registry.remove("lib-a", None).is_err());
+    }
+
+    #[test]
+    fn modified_archive_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LocalRegistry::new(dir.path().to_path_buf());
+        publish(&registry, "app", "1.0.0", &[]);
+        std::fs::write(registry.archive_path("app", "1.0.0"), "tampered").unwrap();
+
+        let err = registry.read_archive("app", "1.0.0").unwrap_err();
+        assert!(err.to_string().contains("modified after publishing"));
+    }
+
+    #[tokio::test]
+    async fn local_packages_shadow_chain_and_fall_back_for_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = LocalRegistry::new(dir.path().to_path_buf());
+        // Local app depends on a local lib-a and a chain-only shared package
+        publish(&registry, "app", "1.0.0", &[("lib-a", "1.0.0"), ("shared", "0.1.0")]);
+        publish(&registry, "lib-a", "1.0.0", &[]);
+        let chain = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[]),
+            sample_package("lib-a", "1.0.0", &[]),
+            sample_package("shared", "0.1.0", &[]),
+        ]);
+
+        let source = LocalFirst { local: &registry, fallback: &chain };
+        let resolution = resolve(&source, "app", "1.0.0").await.unwrap();
+
+        assert!(is_local_cid(&resolution.packages["app"].package.ipfs_cid));
+        assert!(is_local_cid(&resolution.packages["lib-a"].package.ipfs_cid));
+        assert!(!is_local_cid(&resolution.packages["shared"].package.ipfs_cid));
+        // The chain is only consulted for what the local registry lacks
+        assert_eq!(*chain.fetches.lock().unwrap(), vec!["shared@0.1.0"]);
+    }
+}
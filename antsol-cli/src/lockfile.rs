@@ -0,0 +1,203 @@
+use crate::error::AntSolError;
+use crate::resolver::MetadataSource;
+use crate::types::{Dependency, LockFile, LockedPackage, PackageAccount, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Lockfile written next to `antsol.toml`
+pub const LOCKFILE_NAME: &str = "antsol.lock";
+
+/// Current lockfile format version
+pub const LOCKFILE_VERSION: u32 = 1;
+
+const HEADER: &str = "# This file is generated by `antsol install`. Do not edit it by hand.\n";
+
+impl LockFile {
+    pub fn new() -> Self {
+        Self {
+            version: LOCKFILE_VERSION,
+            packages: Vec::new(),
+        }
+    }
+
+    /// Read a lockfile, returning `None` when it doesn't exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)?;
+        let lock: LockFile = toml::from_str(&content)
+            .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", path.display(), e)))?;
+        if lock.version > LOCKFILE_VERSION {
+            return Err(AntSolError::Config(format!(
+                "{} uses lockfile format {} but this antsol understands up to {}. Upgrade antsol.",
+                path.display(),
+                lock.version,
+                LOCKFILE_VERSION
+            )).into());
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let body = toml::to_string_pretty(self)?;
+        std::fs::write(path, format!("{}\n{}", HEADER, body))?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Insert or replace the entry for `package.name`, keeping entries sorted by name
+    pub fn upsert(&mut self, package: LockedPackage) {
+        self.packages.retain(|p| p.name != package.name);
+        self.packages.push(package);
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Drop the entry for `name`, returning it if there was one
+    pub fn remove(&mut self, name: &str) -> Option<LockedPackage> {
+        let index = self.packages.iter().position(|p| p.name == name)?;
+        Some(self.packages.remove(index))
+    }
+
+    /// Entries that list `name` (at any version) as a direct dependency
+    pub fn dependents(&self, name: &str) -> Vec<&LockedPackage> {
+        self.packages
+            .iter()
+            .filter(|p| p.dependencies.iter().any(|d| dependency_name(d) == name))
+            .collect()
+    }
+}
+
+impl LockedPackage {
+    /// Package account as the lockfile remembers it: CID, checksum, authority
+    /// and dependencies, with nothing the chain would add
+    pub fn to_account(&self) -> PackageAccount {
+        PackageAccount {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            authority: self.authority.as_deref().and_then(|a| Pubkey::from_str(a).ok()).unwrap_or_default(),
+            ipfs_cid: self.ipfs_cid.clone(),
+            published_at: 0,
+            description: String::new(),
+            dependencies: self.dependencies.iter()
+                .map(|entry| {
+                    let (name, version) = entry.rsplit_once('@').unwrap_or((entry.as_str(), ""));
+                    Dependency { name: name.to_string(), version: version.to_string() }
+                })
+                .collect(),
+            external_dependencies: Vec::new(),
+            content_sha256: Some(self.sha256.clone()),
+            metadata: Default::default(),
+            readme_cid: None,
+            downloads: 0,
+            frozen: false,
+        }
+    }
+}
+
+/// The lockfile standing in for the chain when installing offline: every
+/// pinned entry is trusted as recorded
+impl MetadataSource for LockFile {
+    async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
+        Ok(self.get(name).filter(|p| p.version == version).map(LockedPackage::to_account))
+    }
+}
+
+/// Name part of a `name@version` dependency entry
+pub fn dependency_name(entry: &str) -> &str {
+    entry.rsplit_once('@').map_or(entry, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(name: &str, version: &str, deps: &[&str]) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            ipfs_cid: format!("Qm{}", "a".repeat(44)),
+            sha256: "ab".repeat(32),
+            files_sha256: None,
+            authority: None,
+            dependencies: deps.iter().map(|d| d.to_string()).collect(),
+            requirement: None,
+            from_cache: false,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+
+        let mut lock = LockFile::new();
+        lock.upsert(locked("lib-b", "2.0.0", &[]));
+        lock.upsert(locked("app", "1.0.0", &["lib-b@2.0.0"]));
+        lock.save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# This file is generated"));
+        assert!(content.contains("[[package]]"));
+
+        let loaded = LockFile::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, lock);
+        assert_eq!(loaded.packages[0].name, "app");
+    }
+
+    #[test]
+    fn upsert_replaces_existing_entry() {
+        let mut lock = LockFile::new();
+        lock.upsert(locked("app", "1.0.0", &[]));
+        lock.upsert(locked("app", "1.1.0", &[]));
+
+        assert_eq!(lock.packages.len(), 1);
+        assert_eq!(lock.get("app").unwrap().version, "1.1.0");
+    }
+
+    #[test]
+    fn remove_and_dependents() {
+        let mut lock = LockFile::new();
+        lock.upsert(locked("app", "1.0.0", &["lib-a@1.0.0", "lib-b@2.0.0"]));
+        lock.upsert(locked("tool", "0.1.0", &["lib-a@1.0.0"]));
+        lock.upsert(locked("lib-a", "1.0.0", &[]));
+
+        let dependents: Vec<&str> = lock.dependents("lib-a").iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(dependents, vec!["app", "tool"]);
+        assert!(lock.dependents("app").is_empty());
+
+        assert_eq!(lock.remove("lib-a").unwrap().version, "1.0.0");
+        assert!(lock.remove("lib-a").is_none());
+        assert_eq!(lock.packages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn plans_offline_from_its_own_entries() {
+        let mut lock = LockFile::new();
+        lock.upsert(locked("app", "1.0.0", &["lib-a@1.0.0"]));
+        lock.upsert(locked("lib-a", "1.0.0", &[]));
+
+        let account = lock.fetch_package("app", "1.0.0").await.unwrap().unwrap();
+        assert_eq!(account.dependencies[0].name, "lib-a");
+        assert_eq!(account.content_sha256.as_deref(), Some("ab".repeat(32).as_str()));
+        assert!(lock.fetch_package("app", "2.0.0").await.unwrap().is_none());
+
+        let plan = crate::resolver::plan_locked(&lock, &lock, Path::new("antsol_packages")).await.unwrap();
+        assert_eq!(plan.packages.len(), 2);
+        assert_eq!(plan.packages[1].sha256.as_deref(), Some("ab".repeat(32).as_str()));
+    }
+
+    #[test]
+    fn missing_lockfile_is_none_and_newer_format_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKFILE_NAME);
+        assert!(LockFile::load(&path).unwrap().is_none());
+
+        std::fs::write(&path, "version = 99\n").unwrap();
+        assert!(LockFile::load(&path).is_err());
+    }
+}
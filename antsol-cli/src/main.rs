@@ -1,14 +1,41 @@
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
 
+mod audit;
+mod cache;
 mod commands;
 mod config;
+mod credentials;
+mod error;
+mod extract;
+mod foreign_manifest;
+mod gateway_stats;
+mod idl;
+mod indexer_client;
+mod integrity;
 mod ipfs;
+mod local_registry;
+mod lockfile;
+mod manifest;
+mod manifest_check;
+mod markdown;
+mod package_files;
+mod package_layout;
+mod permissions;
+mod pinning;
+mod publish_state;
+mod resolver;
+mod rpc;
+mod signer;
 mod solana_client;
+mod tx;
 mod types;
 mod utils;
+mod validation;
+mod verify;
+mod watches;
+mod workspace;
 
 use commands::*;
 
@@ -22,7 +49,45 @@ A fully decentralized package registry powered by Solana blockchain and IPFS.
 • Wallet-based ownership and version control
 ")]
 #[command(version)]
+#[command(after_long_help = error::exit_codes_help())]
 struct Cli {
+    /// Print only each command's result: no banner, spinners or tips
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    
+    /// Log debug diagnostics to stderr (RPC endpoints and calls, simulation
+    /// logs, IPFS gateway attempts) and a summary of RPC calls made
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    
+    /// Refuse to use wallet or config files readable by other users
+    #[arg(long, global = true, conflicts_with = "fix_permissions")]
+    strict: bool,
+    
+    /// chmod wallet and config files readable by other users to 600
+    #[arg(long, global = true)]
+    fix_permissions: bool,
+    
+    /// Keep the Pinata JWT in config.toml instead of the OS keyring (headless servers)
+    #[arg(long, global = true)]
+    no_keyring: bool,
+    
+    /// Print long listings directly instead of through $ANTSOL_PAGER / $PAGER
+    #[arg(long, global = true)]
+    no_pager: bool,
+    
+    /// Network profile to use instead of the active one (see `antsol profile list`)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+    
+    /// Commitment to read accounts at and wait for on sends, over `commitment` in the config
+    #[arg(long, global = true, value_enum, value_name = "LEVEL")]
+    commitment: Option<tx::Commitment>,
+    
+    /// `json` prints a single JSON document on stdout (status lines go to stderr)
+    #[arg(long, global = true, value_enum, default_value = "human")]
+    output: utils::OutputFormat,
+    
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,7 +98,39 @@ enum Commands {
     Setup,
     
     /// Initialize package manifest for decentralized registry
-    Init,
+    Init {
+        /// Package name (defaults to the directory name with --yes)
+        #[arg(long)]
+        name: Option<String>,
+        
+        /// Initial version (default: 0.1.0)
+        #[arg(long)]
+        version: Option<String>,
+        
+        /// Package description
+        #[arg(long)]
+        description: Option<String>,
+        
+        /// Author, e.g. "Name <email>"
+        #[arg(long)]
+        author: Option<String>,
+        
+        /// License identifier, e.g. MIT
+        #[arg(long)]
+        license: Option<String>,
+        
+        /// Don't prompt; use defaults for anything not given
+        #[arg(short, long)]
+        yes: bool,
+        
+        /// Prefill from ./Cargo.toml (or its workspace member) without prompting
+        #[arg(long, conflicts_with = "from_npm")]
+        from_cargo: bool,
+        
+        /// Prefill from ./package.json without prompting
+        #[arg(long)]
+        from_npm: bool,
+    },
     
     /// Publish a package to the on-chain registry
     Publish {
@@ -41,27 +138,262 @@ enum Commands {
         #[arg(default_value = ".")]
         path: PathBuf,
         
+        /// Publish every member listed under [workspace] in the path's antsol.toml,
+        /// dependencies first; members already on-chain are skipped
+        #[arg(long, conflicts_with_all = ["version", "bump", "list_files"])]
+        workspace: bool,
+        
         /// Specify version (overrides manifest)
-        #[arg(short, long)]
+        #[arg(long)]
         version: Option<String>,
+        
+        /// Publish the next patch, minor or major version after the manifest's
+        #[arg(long, value_enum, conflicts_with = "version")]
+        bump: Option<manifest::Bump>,
+        
+        /// Print the files that would be archived and exit without uploading
+        #[arg(long)]
+        list_files: bool,
+        
+        /// Validate, build the archive and check the chain without uploading or sending anything
+        #[arg(long, conflicts_with = "list_files")]
+        dry_run: bool,
+        
+        /// Skip the pre-upload checks that the version is unpublished and the wallet can pay for it,
+        /// and the prompt about unusual package contents
+        #[arg(long)]
+        force: bool,
+        
+        /// Upload the archive even if an earlier, failed publish already pinned it
+        #[arg(long)]
+        fresh: bool,
+        
+        /// Largest archive to upload, e.g. 100MB (default: the max_package_size config, 50MB)
+        #[arg(long, value_name = "SIZE", value_parser = utils::parse_size)]
+        max_size: Option<u64>,
+        
+        /// Publish without asking for confirmation; required when stdin is not a terminal
+        #[arg(short, long)]
+        yes: bool,
+        
+        /// Also publish the workspace members the package depends on that aren't on-chain
+        /// yet, batching the publishes into as few transactions as fit
+        #[arg(long, conflicts_with_all = ["workspace", "version", "bump", "list_files", "dry_run"])]
+        with_deps: bool,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Check antsol.toml against the registry's rules without publishing
+    ///
+    /// Reports each problem with its line and column and a suggested fix.
+    /// Exits non-zero if any is an error; warnings alone pass. `publish` runs
+    /// the same checks first.
+    Check {
+        /// Path to package directory
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        
+        /// Emit the diagnostics as JSON
+        #[arg(long)]
+        json: bool,
     },
     
     /// Install a package from the decentralized registry
+    ///
+    /// After a successful install, antsol tells the indexer which package
+    /// versions were downloaded so their download counts stay accurate. Only
+    /// the package name and version are sent; the indexer sees your IP address
+    /// as with any request and keeps an md5 of it for an hour to count repeat
+    /// installs once. Opt out with --no-telemetry, `antsol config set
+    /// telemetry false` or ANTSOL_TELEMETRY=false. Reporting runs in the
+    /// background and never fails an install. With --record-download the
+    /// count is kept on-chain instead, at the cost of a transaction fee.
     Install {
+        /// Package name with optional version (e.g., spl-token-utils@1.0.0)
+        #[arg(required_unless_present_any = ["locked", "offline"])]
+        package: Option<String>,
+        
+        /// Install exactly what antsol.lock pins; fail if an on-chain CID changed
+        #[arg(long, conflicts_with_all = ["package", "plan"])]
+        locked: bool,
+        
+        /// Install what antsol.lock pins from the download cache only, never
+        /// touching the RPC, the indexer or IPFS gateways (fill the cache
+        /// with `antsol fetch`). On-chain verification is skipped
+        #[arg(long, conflicts_with_all = ["package", "plan", "local", "record_download"])]
+        offline: bool,
+        
+        /// Print the resolution plan without downloading or writing anything
+        #[arg(long)]
+        plan: bool,
+        
+        /// Emit the plan as JSON (with --plan)
+        #[arg(long, requires = "plan")]
+        json: bool,
+        
+        /// Prefer packages from the local development registry (never written to antsol.lock)
+        #[arg(long, conflicts_with = "locked")]
+        local: bool,
+        
+        /// Don't report this install to the indexer's download counter
+        #[arg(long)]
+        no_telemetry: bool,
+        
+        /// Count the download on-chain with a transaction from your wallet
+        /// (a few thousand lamports per package) instead of reporting it to the indexer
+        #[arg(long)]
+        record_download: bool,
+        
+        /// Extract packages here instead of the install_dir config (default: antsol_packages)
+        #[arg(long, value_name = "DIR")]
+        target_dir: Option<PathBuf>,
+        
+        /// Extract symlinks that point inside the package instead of refusing the archive
+        #[arg(long)]
+        allow_symlinks: bool,
+    },
+    
+    /// Download everything antsol.lock pins into the download cache without
+    /// installing, for a later `antsol install --offline`
+    Fetch,
+    
+    /// Remove an installed package from the install directory and antsol.lock
+    Uninstall {
         /// Package name with optional version (e.g., spl-token-utils@1.0.0)
         package: String,
+        
+        /// Remove it even if other installed packages depend on it
+        #[arg(long)]
+        force: bool,
+        
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// List packages installed in this project
+    List {
+        /// Emit the list as JSON
+        #[arg(long)]
+        json: bool,
+        
+        /// Check each package's locked CID against its on-chain record
+        #[arg(long)]
+        verify: bool,
+        
+        /// List the members of the workspace in the current directory with their
+        /// manifest and latest on-chain versions instead of installed packages
+        #[arg(long, conflicts_with = "verify")]
+        workspace: bool,
+    },
+    
+    /// Show installed dependencies with newer versions in the indexer
+    ///
+    /// Reads antsol.lock (or antsol.toml's dependencies when there is no
+    /// lockfile). Exits with code 1 when anything is outdated.
+    Outdated {
+        /// Check the dependencies declared in antsol.toml instead of what is installed
+        #[arg(long)]
+        manifest: bool,
+        
+        /// Emit the comparison as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Follow packages and report when they publish new versions
+    Watch {
+        #[command(subcommand)]
+        action: WatchAction,
     },
     
     /// Search for packages in the registry
     Search {
         /// Search query
         query: String,
+        
+        /// Results per page
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+        
+        /// Page number, starting at 1
+        #[arg(long, default_value_t = 1)]
+        page: u32,
+        
+        /// Only packages published by this authority (base58 pubkey)
+        #[arg(long, value_name = "PUBKEY")]
+        author: Option<String>,
+        
+        /// Result order [default: relevance]
+        #[arg(long, value_enum)]
+        sort: Option<indexer_client::SearchSort>,
+        
+        /// Only packages with a version matching this requirement, e.g. "^1.2"
+        #[arg(long, value_name = "SEMVER_REQ")]
+        has_version: Option<String>,
+        
+        /// Scan the registry program's accounts instead of asking the indexer (slow)
+        #[arg(long)]
+        on_chain: bool,
     },
     
     /// Show package information from blockchain
     Info {
         /// Package name
         package: String,
+        
+        /// Look in the local development registry first
+        #[arg(long)]
+        local: bool,
+        
+        /// Download and show the README pinned with the version
+        #[arg(long)]
+        readme: bool,
+    },
+    
+    /// Print a package's dependency tree
+    ///
+    /// Uses the same resolver as install, so cycles and version conflicts
+    /// fail here too. Subtrees already shown are marked (*).
+    Tree {
+        /// Package name with optional version or range (e.g., spl-token-utils@1.0.0)
+        package: String,
+        
+        /// Only show dependencies up to this many levels below the package
+        #[arg(long)]
+        depth: Option<usize>,
+        
+        /// Emit the full resolved graph as JSON (ignores --depth)
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Check a package's content against the CID recorded on-chain
+    ///
+    /// Without a package, audits the current project instead: every entry in
+    /// antsol.lock is checked against its installed files and its on-chain
+    /// account. Exits non-zero if anything fails.
+    Verify {
+        /// Package name with optional version (e.g., spl-token-utils@1.0.0)
+        package: Option<String>,
+        
+        /// Also verify every transitive dependency
+        #[arg(long, requires = "package")]
+        recursive: bool,
+        
+        /// When auditing the project, also re-download each tarball from IPFS and compare it
+        #[arg(long, conflicts_with = "package")]
+        deep: bool,
+        
+        /// Maximum number of packages verified at once
+        #[arg(long, default_value_t = 4)]
+        jobs: usize,
+        
+        /// Emit the verification report as JSON
+        #[arg(long)]
+        json: bool,
     },
     
     /// Manage wallet for on-chain transactions
@@ -70,6 +402,32 @@ enum Commands {
         action: WalletAction,
     },
     
+    /// Claim a scope for `scope/name` packages and manage who may publish under it
+    Namespace {
+        #[command(subcommand)]
+        action: NamespaceAction,
+    },
+    
+    /// Manage the wallets, besides the authority, that may publish new versions of a package
+    Maintainer {
+        #[command(subcommand)]
+        action: MaintainerAction,
+    },
+    
+    /// Make a package version final: burn its authority so it can never be
+    /// updated or transferred again
+    Freeze {
+        /// Package name and version (e.g., spl-token-utils@1.0.0)
+        package: String,
+        
+        /// Freeze without asking for confirmation; required when stdin is not a terminal
+        #[arg(short, long)]
+        yes: bool,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
     /// Update a package to a new version on-chain
     Update {
         /// Path to package directory
@@ -77,8 +435,193 @@ enum Commands {
         path: PathBuf,
         
         /// New version
-        #[arg(short, long)]
-        version: String,
+        #[arg(long, required_unless_present = "bump")]
+        version: Option<String>,
+        
+        /// Use the next patch, minor or major version after the manifest's
+        #[arg(long, value_enum, conflicts_with = "version")]
+        bump: Option<manifest::Bump>,
+        
+        /// Upload the archive even if an earlier, failed update already pinned it
+        #[arg(long)]
+        fresh: bool,
+        
+        /// Largest archive to upload, e.g. 100MB (default: the max_package_size config, 50MB)
+        #[arg(long, value_name = "SIZE", value_parser = utils::parse_size)]
+        max_size: Option<u64>,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Re-resolve dependency ranges in antsol.toml and install newer matches
+    UpdateDeps {
+        /// Show what would change without installing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Inspect the exit code catalog
+    Errors {
+        #[command(subcommand)]
+        action: ErrorsAction,
+    },
+    
+    /// Check config, wallet, RPC, program, pinning and indexer, with a fix for each problem
+    Doctor,
+    
+    /// Troubleshooting tools for the CLI's view of the registry program
+    Debug {
+        #[command(subcommand)]
+        action: DebugAction,
+    },
+    
+    /// Inspect IPFS gateway health
+    Gateways {
+        #[command(subcommand)]
+        action: GatewaysAction,
+    },
+    
+    /// Manage the local development registry (~/.antsol/local-registry)
+    Local {
+        #[command(subcommand)]
+        action: LocalAction,
+    },
+    
+    /// Manage the download cache of package archives (~/.antsol/cache)
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    
+    /// Switch between network profiles (devnet, localnet, ...)
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    
+    /// View and edit settings in ~/.antsol/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    
+    /// Print a shell completion script (e.g. antsol completions bash > /etc/bash_completion.d/antsol)
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    
+    /// Print the antsol man page in roff format
+    Man,
+    
+    /// Package names for shell completion; called by the completion scripts
+    #[command(name = "__complete-packages", hide = true)]
+    CompletePackages {
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the value of one key
+    Get {
+        /// Config key, e.g. rpc_url
+        key: String,
+    },
+    
+    /// Validate and save a value (an empty value clears optional keys)
+    Set {
+        /// Config key, e.g. rpc_url
+        key: String,
+        
+        /// New value
+        value: String,
+    },
+    
+    /// Print every key, with secrets masked
+    List {
+        /// Show where each value came from (default, file, profile, env or keyring)
+        #[arg(long)]
+        sources: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum LocalAction {
+    /// Copy a package into the local registry instead of chain + IPFS
+    Publish {
+        /// Path to package directory (defaults to current directory)
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        
+        /// Specify version (overrides manifest)
+        #[arg(long)]
+        version: Option<String>,
+    },
+    
+    /// List locally published packages
+    List {
+        /// Emit the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Remove a package (all versions) or name@version from the local registry
+    Remove {
+        /// Package name with optional version
+        package: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheAction {
+    /// List cached archives, most recently used first
+    Ls {
+        /// Emit the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Remove cached archives, least recently used first
+    Clean {
+        /// Keep the cache at or below this size, e.g. 500MB (default: remove everything)
+        #[arg(long, value_name = "SIZE", value_parser = utils::parse_size)]
+        max_size: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// List profiles, marking the one in use
+    List {
+        /// Emit the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Make a profile the active one
+    Use {
+        /// Profile name
+        name: String,
+    },
+    
+    /// Add a profile; unset values are copied from the profile in use
+    Add {
+        /// Profile name, e.g. localnet
+        name: String,
+        
+        #[arg(long)]
+        rpc_url: String,
+        
+        #[arg(long)]
+        program_id: Option<String>,
+        
+        #[arg(long)]
+        indexer_url: Option<String>,
+        
+        #[arg(long)]
+        ipfs_url: Option<String>,
     },
 }
 
@@ -86,40 +629,347 @@ enum Commands {
 enum WalletAction {
     /// Connect a wallet for signing transactions
     Connect {
-        /// Path to wallet keypair JSON file
-        keypair: PathBuf,
+        /// Keypair JSON file, `prompt://[?key=0/0]` for a seed phrase, or
+        /// `usb://ledger[?key=0/0]` for a Ledger
+        wallet: PathBuf,
+        
+        /// Encrypt the seed phrase's keypair under a password instead of
+        /// asking for the phrase on each use
+        #[arg(long)]
+        cache: bool,
     },
     
     /// Show current wallet and network info
     Show,
+    
+    /// Generate a new keypair and make it the configured wallet
+    New {
+        /// Where to write the keypair (default: ~/.antsol/wallet.json)
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    
+    /// Show the configured wallet's SOL balance
+    Balance,
+    
+    /// Request a devnet/testnet airdrop (refused on mainnet)
+    Airdrop {
+        /// Amount in SOL
+        #[arg(default_value_t = 1.0)]
+        amount: f64,
+    },
+}
+
+#[derive(Subcommand)]
+enum MaintainerAction {
+    /// Let a wallet publish new versions of a package you own
+    Add {
+        /// Package name, optionally @version to sign as that version's authority
+        package: String,
+        
+        /// Wallet address to add
+        wallet: String,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Stop a wallet from publishing new versions of a package you own
+    Remove {
+        /// Package name, optionally @version to sign as that version's authority
+        package: String,
+        
+        /// Wallet address to remove
+        wallet: String,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Show a package's maintainers
+    List {
+        /// Package name
+        package: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum NamespaceAction {
+    /// Register a scope to the configured wallet
+    Register {
+        /// Scope to claim (lowercase letters, numbers and hyphens)
+        scope: String,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Replace the wallets, besides the owner, allowed to publish under a scope
+    Publishers {
+        /// Scope owned by the configured wallet
+        scope: String,
+        
+        /// Wallet addresses; none leaves only the owner
+        wallets: Vec<String>,
+        
+        #[command(flatten)]
+        send: tx::SendOptions,
+    },
+    
+    /// Show a scope's owner and publishers
+    Show {
+        /// Scope to look up
+        scope: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WatchAction {
+    /// Start watching a package from its current latest version
+    Add {
+        /// Package name
+        name: String,
+    },
+    
+    /// Stop watching a package
+    Remove {
+        /// Package name
+        name: String,
+    },
+    
+    /// List watched packages with the last version seen
+    List {
+        /// Emit the list as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    
+    /// Report versions published since the last check; exits with code 1 when there are any
+    Check {
+        /// Shell command run once per new version, with NAME, OLD, NEW and CID set
+        #[arg(long, value_name = "COMMAND")]
+        notify_cmd: Option<String>,
+        
+        /// Emit the new versions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GatewaysAction {
+    /// Show per-gateway success rates and latency from past downloads
+    Stats {
+        /// Emit the stats as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum DebugAction {
+    /// Fetch the program's on-chain IDL and check instruction and account layouts against the CLI's
+    VerifyIdl,
+}
+
+#[derive(Subcommand)]
+enum ErrorsAction {
+    /// List every exit code the CLI can return
+    List {
+        /// Emit the catalog as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl Commands {
+    /// Whether this invocation writes JSON to stdout
+    fn json_output(&self) -> bool {
+        match self {
+            Commands::Install { json, .. } => *json,
+            Commands::List { json, .. } => *json,
+            Commands::Outdated { json, .. } => *json,
+            Commands::Tree { json, .. } => *json,
+            Commands::Verify { json, .. } => *json,
+            Commands::Check { json, .. } => *json,
+            Commands::Errors { action: ErrorsAction::List { json } } => *json,
+            Commands::Gateways { action: GatewaysAction::Stats { json } } => *json,
+            Commands::Local { action: LocalAction::List { json } } => *json,
+            Commands::Cache { action: CacheAction::Ls { json } } => *json,
+            Commands::Profile { action: ProfileAction::List { json } } => *json,
+            Commands::Watch { action: WatchAction::List { json } } => *json,
+            Commands::Watch { action: WatchAction::Check { json, .. } } => *json,
+            _ => false,
+        }
+    }
+    
+    /// Whether stdout is a script or document that must not get the banner
+    fn raw_output(&self) -> bool {
+        matches!(self, Commands::Completions { .. } | Commands::Man | Commands::CompletePackages { .. })
+    }
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    utils::configure_colors();
+    permissions::set_policy(if cli.strict {
+        permissions::PermissionPolicy::Strict
+    } else if cli.fix_permissions {
+        permissions::PermissionPolicy::Fix
+    } else {
+        permissions::PermissionPolicy::Warn
+    });
+    if cli.no_keyring {
+        credentials::disable_keyring();
+    }
+    if cli.quiet {
+        utils::enable_quiet();
+    }
+    if cli.verbose {
+        utils::enable_verbose();
+    }
+    if let Some(profile) = cli.profile.clone() {
+        config::set_profile_override(profile);
+    }
+    if let Some(commitment) = cli.commitment {
+        config::set_commitment_override(commitment);
+    }
+    
+    // Per-command --json flags predate --output and mean the same thing
+    utils::set_output_format(if cli.command.json_output() {
+        utils::OutputFormat::Json
+    } else {
+        cli.output
+    });
+    let json = utils::json_output();
+    
+    let raw = json || cli.command.raw_output();
+    
+    if cli.no_pager || raw {
+        utils::disable_pager();
+    }
     
-    // Print banner
-    print_banner();
+    // Print banner (kept off stdout when it must stay machine-readable)
+    if !raw && !cli.quiet {
+        print_banner();
+    }
     
     let result = match cli.command {
         Commands::Setup => setup::handle_setup().await,
-        Commands::Init => init::handle_init().await,
-        Commands::Publish { path, version } => publish::handle_publish(path, version).await,
-        Commands::Install { package } => install::handle_install(package).await,
-        Commands::Search { query } => search::handle_search(query).await,
-        Commands::Info { package } => info::handle_info(package).await,
+        Commands::Init { name, version, description, author, license, yes, from_cargo, from_npm } => {
+            let from = if from_cargo {
+                Some(foreign_manifest::ManifestKind::Cargo)
+            } else if from_npm {
+                Some(foreign_manifest::ManifestKind::Npm)
+            } else {
+                None
+            };
+            init::handle_init(init::InitOptions { name, version, description, author, license, yes, from }).await
+        }
+        Commands::Publish { path, workspace, version, bump, list_files, dry_run, force, fresh, max_size, yes, with_deps, send } => {
+            publish::handle_publish(path, workspace, version, bump, list_files, dry_run, force, fresh, max_size, yes, with_deps, send).await
+        }
+        Commands::Check { path, .. } => check::handle_check(path, json).await,
+        Commands::Install { package, plan, locked, offline, local, no_telemetry, record_download, target_dir, allow_symlinks, .. } => {
+            install::handle_install(package, plan, json, locked, offline, local, no_telemetry, record_download, target_dir, allow_symlinks).await
+        }
+        Commands::Fetch => fetch::handle_fetch().await,
+        Commands::Uninstall { package, force, dry_run } => uninstall::handle_uninstall(package, force, dry_run).await,
+        Commands::List { verify, workspace, .. } => list::handle_list(json, verify, workspace).await,
+        Commands::Outdated { manifest, .. } => outdated::handle_outdated(json, manifest).await,
+        Commands::Watch { action } => match action {
+            WatchAction::Add { name } => watch::handle_add(name).await,
+            WatchAction::Remove { name } => watch::handle_remove(name).await,
+            WatchAction::List { .. } => watch::handle_list(json).await,
+            WatchAction::Check { notify_cmd, .. } => watch::handle_check(notify_cmd, json).await,
+        },
+        Commands::Search { query, limit, page, author, sort, has_version, on_chain } => {
+            search::handle_search(query, limit, page, indexer_client::SearchFilters { author, sort, has_version }, on_chain, cli.verbose).await
+        }
+        Commands::Info { package, local, readme } => info::handle_info(package, local, readme).await,
+        Commands::Tree { package, depth, .. } => commands::tree::handle_tree(package, depth, json).await,
+        Commands::Verify { package: Some(package), recursive, jobs, .. } => commands::verify::handle_verify(package, recursive, jobs, json).await,
+        Commands::Verify { package: None, deep, .. } => commands::verify::handle_audit(deep, json).await,
         Commands::Wallet { action } => match action {
-            WalletAction::Connect { keypair } => wallet::handle_connect(keypair).await,
+            WalletAction::Connect { wallet, cache } => wallet::handle_connect(wallet, cache).await,
             WalletAction::Show => wallet::handle_show().await,
+            WalletAction::New { output } => wallet::handle_new(output).await,
+            WalletAction::Balance => wallet::handle_balance().await,
+            WalletAction::Airdrop { amount } => wallet::handle_airdrop(amount).await,
+        },
+        Commands::Namespace { action } => match action {
+            NamespaceAction::Register { scope, send } => namespace::handle_register(scope, send).await,
+            NamespaceAction::Publishers { scope, wallets, send } => namespace::handle_publishers(scope, wallets, send).await,
+            NamespaceAction::Show { scope } => namespace::handle_show(scope).await,
+        },
+        Commands::Freeze { package, yes, send } => freeze::handle_freeze(package, yes, send).await,
+        Commands::Maintainer { action } => match action {
+            MaintainerAction::Add { package, wallet, send } => maintainer::handle_change(package, wallet, true, send).await,
+            MaintainerAction::Remove { package, wallet, send } => maintainer::handle_change(package, wallet, false, send).await,
+            MaintainerAction::List { package } => maintainer::handle_list(package).await,
+        },
+        Commands::Update { path, version, bump, fresh, max_size, send } => update::handle_update(path, version, bump, fresh, max_size, send).await,
+        Commands::UpdateDeps { dry_run } => update_deps::handle_update_deps(dry_run).await,
+        Commands::Errors { action } => match action {
+            ErrorsAction::List { .. } => errors::handle_list(json).await,
+        },
+        Commands::Doctor => doctor::handle_doctor().await,
+        Commands::Debug { action } => match action {
+            DebugAction::VerifyIdl => debug::handle_verify_idl().await,
+        },
+        Commands::Gateways { action } => match action {
+            GatewaysAction::Stats { .. } => gateways::handle_stats(json).await,
         },
-        Commands::Update { path, version } => update::handle_update(path, version).await,
+        Commands::Local { action } => match action {
+            LocalAction::Publish { path, version } => local::handle_publish(path, version).await,
+            LocalAction::List { .. } => local::handle_list(json).await,
+            LocalAction::Remove { package } => local::handle_remove(package).await,
+        },
+        Commands::Cache { action } => match action {
+            CacheAction::Ls { .. } => commands::cache::handle_ls(json).await,
+            CacheAction::Clean { max_size } => commands::cache::handle_clean(max_size).await,
+        },
+        Commands::Profile { action } => match action {
+            ProfileAction::List { .. } => commands::profile::handle_list(json).await,
+            ProfileAction::Use { name } => commands::profile::handle_use(name).await,
+            ProfileAction::Add { name, rpc_url, program_id, indexer_url, ipfs_url } => {
+                commands::profile::handle_add(name, commands::profile::AddOptions { rpc_url, program_id, indexer_url, ipfs_url }).await
+            }
+        },
+        Commands::Config { action } => match action {
+            ConfigAction::Get { key } => commands::config::handle_get(key).await,
+            ConfigAction::Set { key, value } => commands::config::handle_set(key, value).await,
+            ConfigAction::List { sources } => commands::config::handle_list(sources).await,
+        },
+        Commands::Completions { shell } => completions::handle_completions(shell, Cli::command()).await,
+        Commands::Man => completions::handle_man(Cli::command()).await,
+        Commands::CompletePackages { prefix } => completions::handle_complete_packages(prefix).await,
     };
     
+    if cli.verbose {
+        let usage = rpc::global_usage();
+        if json {
+            // stdout carries the command's own JSON, so the tally goes to stderr
+            if usage.total() > 0 {
+                if let Ok(report) = serde_json::to_string(&usage.report()) {
+                    eprintln!("{}", report);
+                }
+            }
+        } else if let Some(summary) = usage.summary() {
+            eprintln!("\n{}", summary.dimmed());
+        }
+    }
+    
     match result {
         Ok(_) => std::process::exit(0),
+        Err(e) if json => {
+            let _ = utils::emit_json(&error::ErrorOutput::new(e.as_ref()));
+            std::process::exit(error::exit_code(e.as_ref()));
+        }
         Err(e) => {
             eprintln!("\n{} {}", "✗".red().bold(), e.to_string().red());
-            std::process::exit(1);
+            std::process::exit(error::exit_code(e.as_ref()));
         }
     }
 }
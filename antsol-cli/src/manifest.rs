@@ -0,0 +1,149 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use crate::utils::validate_version;
+use std::path::Path;
+use toml_edit::{value, DocumentMut, Item, Table, Value};
+
+/// Which part of the manifest version `--bump` increments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+/// The version after `current` for `bump`. A pre-release is released first
+/// when the bump wouldn't skip past it, so `1.0.0-rc.1` bumps to `1.0.0`
+/// (patch, minor or major) and `1.2.0-rc.1` to `1.2.0` (patch or minor).
+/// Build metadata is dropped.
+pub fn bump_version(current: &str, bump: Bump) -> Result<String> {
+    let mut version = semver::Version::parse(current)
+        .map_err(|e| AntSolError::Usage(format!("Cannot bump version '{}' in antsol.toml: {}", current, e)))?;
+    let releases_pre = !version.pre.is_empty()
+        && match bump {
+            Bump::Patch => true,
+            Bump::Minor => version.patch == 0,
+            Bump::Major => version.minor == 0 && version.patch == 0,
+        };
+    if !releases_pre {
+        match bump {
+            Bump::Patch => version.patch += 1,
+            Bump::Minor => {
+                version.minor += 1;
+                version.patch = 0;
+            }
+            Bump::Major => {
+                version.major += 1;
+                version.minor = 0;
+                version.patch = 0;
+            }
+        }
+    }
+    version.pre = semver::Prerelease::EMPTY;
+    version.build = semver::BuildMetadata::EMPTY;
+
+    let next = version.to_string();
+    if !validate_version(&next) {
+        return Err(AntSolError::Usage(format!("Bumped version {} is not a valid registry version", next)).into());
+    }
+    Ok(next)
+}
+
+/// Rewrite `antsol.toml` after a successful publish: set `package.version`
+/// and record the CID under `[publish]`. Comments, ordering and the rest of
+/// the formatting are left as they were.
+pub fn record_publish(manifest_path: &Path, version: &str, cid: &str) -> Result<()> {
+    let content = std::fs::read_to_string(manifest_path)?;
+    let updated = with_published(&content, version, cid)
+        .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", manifest_path.display(), e)))?;
+    if updated != content {
+        std::fs::write(manifest_path, updated)?;
+    }
+    Ok(())
+}
+
+fn with_published(content: &str, version: &str, cid: &str) -> std::result::Result<String, toml_edit::TomlError> {
+    let mut doc: DocumentMut = content.parse()?;
+    set_keeping_decor(&mut doc["package"]["version"], version);
+    if !doc.get("publish").is_some_and(Item::is_table_like) {
+        doc.insert("publish", Item::Table(Table::new()));
+    }
+    set_keeping_decor(&mut doc["publish"]["cid"], cid);
+    Ok(doc.to_string())
+}
+
+/// Replace a string value, keeping the whitespace and trailing comment around it
+fn set_keeping_decor(item: &mut Item, new: &str) {
+    match item.as_value_mut() {
+        Some(existing) => {
+            let decor = existing.decor().clone();
+            *existing = Value::from(new);
+            *existing.decor_mut() = decor;
+        }
+        None => *item = value(new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn bumps_each_component() {
+        assert_eq!(bump_version("1.2.3", Bump::Patch).unwrap(), "1.2.4");
+        assert_eq!(bump_version("1.2.3", Bump::Minor).unwrap(), "1.3.0");
+        assert_eq!(bump_version("1.2.3", Bump::Major).unwrap(), "2.0.0");
+        assert_eq!(bump_version("0.1.0+build.7", Bump::Patch).unwrap(), "0.1.1");
+        assert!(bump_version("1.0", Bump::Patch).is_err());
+    }
+
+    #[test]
+    fn bumping_a_prerelease_releases_it_first() {
+        assert_eq!(bump_version("1.0.0-rc.1", Bump::Patch).unwrap(), "1.0.0");
+        assert_eq!(bump_version("1.0.0-rc.1", Bump::Major).unwrap(), "1.0.0");
+        assert_eq!(bump_version("1.2.0-rc.1", Bump::Minor).unwrap(), "1.2.0");
+        assert_eq!(bump_version("1.2.0-rc.1", Bump::Major).unwrap(), "2.0.0");
+        assert_eq!(bump_version("1.2.3-rc.1", Bump::Minor).unwrap(), "1.3.0");
+    }
+
+    #[test]
+    fn record_publish_keeps_comments_and_layout() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("antsol.toml");
+        std::fs::write(&path, r#"# My package
+[package]
+name = "demo"
+version   = "1.0.0"   # bumped by antsol
+description = "Demo"
+
+[[dependencies]]
+name = "math-utils"
+version = "^1.2"
+"#).unwrap();
+
+        record_publish(&path, "1.0.1", "bafyfirst").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), r#"# My package
+[package]
+name = "demo"
+version   = "1.0.1"   # bumped by antsol
+description = "Demo"
+
+[[dependencies]]
+name = "math-utils"
+version = "^1.2"
+
+[publish]
+cid = "bafyfirst"
+"#);
+
+        // A second publish updates the existing section in place
+        record_publish(&path, "1.1.0", "bafysecond").unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("version   = \"1.1.0\"   # bumped by antsol"));
+        assert!(content.ends_with("[publish]\ncid = \"bafysecond\"\n"));
+        assert_eq!(content.matches("[publish]").count(), 1);
+        let manifest: crate::types::AntSolManifest = toml::from_str(&content).unwrap();
+        assert_eq!(manifest.package.version, "1.1.0");
+    }
+}
@@ -0,0 +1,437 @@
+use crate::error::AntSolError;
+use crate::package_files::{package_files, readme_path, README_FILE};
+use crate::resolver::VersionSpec;
+use crate::utils::print_status;
+use crate::types::{AntSolManifest, Dependency, PackageMetadata, Result};
+use crate::validation::{
+    check_dependency, check_external_dependencies, check_metadata, check_package, Violation, MAX_DEPENDENCIES,
+    EXTERNAL_DEPENDENCY_TYPES,
+};
+use colored::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::Path;
+use toml_edit::{ImDocument, Item};
+
+/// How much an issue in `antsol.toml` matters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The registry program would refuse the publish, or the file doesn't parse
+    Error,
+    /// Publishable, but probably not what was meant
+    Warning,
+}
+
+/// One issue found in `antsol.toml`, with where it is and how to fix it
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// `RegistryError` variant for checks the program runs, else a name of our own
+    pub code: &'static str,
+    pub message: String,
+    /// Dotted TOML key the issue is about, e.g. `dependencies[1].name`
+    pub key: Option<String>,
+    /// 1-based line and column in `antsol.toml`
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub help: Option<String>,
+}
+
+/// What checking a package's `antsol.toml` found
+#[derive(Debug)]
+pub struct ManifestReport {
+    /// `None` when the file doesn't parse as a manifest
+    pub manifest: Option<AntSolManifest>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ManifestReport {
+    pub fn errors(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error).count()
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.diagnostics.len() - self.errors()
+    }
+
+    /// Drop diagnostics about `key`, e.g. the version when `--version` replaces it
+    pub fn ignore_key(&mut self, key: &str) {
+        self.diagnostics.retain(|d| d.key.as_deref() != Some(key));
+    }
+
+    /// Print every diagnostic, errors first, pointing into `manifest_path`
+    pub fn print(&self, manifest_path: &Path) {
+        let mut sorted: Vec<&Diagnostic> = self.diagnostics.iter().collect();
+        sorted.sort_by_key(|d| (d.severity != Severity::Error, d.line));
+        for diagnostic in sorted {
+            let label = match diagnostic.severity {
+                Severity::Error => format!("error[{}]", diagnostic.code).red().bold(),
+                Severity::Warning => format!("warning[{}]", diagnostic.code).yellow().bold(),
+            };
+            let mut lines = vec![format!("{}: {}", label, diagnostic.message)];
+            match (diagnostic.line, diagnostic.column) {
+                (Some(line), Some(column)) => lines.push(format!("  {} {}:{}:{}", "-->".blue(), manifest_path.display(), line, column)),
+                _ => lines.push(format!("  {} {}", "-->".blue(), manifest_path.display())),
+            }
+            if let Some(help) = &diagnostic.help {
+                lines.push(format!("  {} {}", "help:".cyan(), help));
+            }
+            print_status(lines.join("\n"));
+        }
+    }
+
+    /// The manifest, or a config error when any diagnostic is an error
+    pub fn into_manifest(self, manifest_path: &Path) -> Result<AntSolManifest> {
+        let errors = self.errors();
+        match self.manifest {
+            Some(manifest) if errors == 0 => Ok(manifest),
+            _ => Err(AntSolError::Config(format!(
+                "{} has {} error(s)",
+                manifest_path.display(),
+                errors.max(1)
+            )).into()),
+        }
+    }
+}
+
+/// Check the `antsol.toml` in `dir` against the rules the registry program
+/// enforces, plus the mistakes it can't see: duplicate and self
+/// dependencies, an empty package directory, a missing README
+pub fn check_dir(dir: &Path) -> Result<ManifestReport> {
+    let manifest_path = dir.join("antsol.toml");
+    if !manifest_path.exists() {
+        return Err(AntSolError::ManifestNotFound(dir.to_path_buf()).into());
+    }
+    let content = std::fs::read_to_string(&manifest_path)?;
+    let mut report = check_manifest(&content);
+    if report.manifest.is_some() {
+        report.diagnostics.extend(check_files(dir)?);
+    }
+    Ok(report)
+}
+
+/// Check manifest `content`; file checks are left to [`check_dir`]
+pub fn check_manifest(content: &str) -> ManifestReport {
+    let doc = match ImDocument::parse(content) {
+        Ok(doc) => doc,
+        Err(e) => {
+            let diagnostic = Diagnostic::error("InvalidToml", e.message().trim(), None)
+                .at(content, e.span())
+                .help("Fix the TOML syntax at this position; strings need double quotes, tables [brackets]");
+            return ManifestReport { manifest: None, diagnostics: vec![diagnostic] };
+        }
+    };
+    let manifest: AntSolManifest = match toml::from_str(content) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let message = e.message().trim().to_string();
+            let help = missing_field(&message).map(|field| format!("Add {} = \"...\" under [package]", field));
+            let mut diagnostic = Diagnostic::error("InvalidManifest", message, None).at(content, e.span());
+            diagnostic.help = help.or_else(|| Some("Check the type of the value against the manifest format in the README".to_string()));
+            return ManifestReport { manifest: None, diagnostics: vec![diagnostic] };
+        }
+    };
+
+    let locate = |key: &str| span_of(doc.as_item(), key);
+    let mut diagnostics = Vec::new();
+    let mut push = |violation: Violation, key: String| {
+        let span = locate(&key);
+        diagnostics.push(Diagnostic::from_violation(violation, key).at(content, span));
+    };
+
+    let package = &manifest.package;
+    for violation in check_package(&package.name, &package.version, &package.description, &[]) {
+        let key = match violation.code {
+            "NameEmpty" | "NameTooLong" | "InvalidNameFormat" => "package.name",
+            "VersionEmpty" | "VersionTooLong" | "InvalidVersionFormat" => "package.version",
+            _ => "package.description",
+        };
+        push(violation, key.to_string());
+    }
+    let metadata = package.metadata();
+    for (field, only) in [
+        ("repository", PackageMetadata { repository: metadata.repository.clone(), ..Default::default() }),
+        ("homepage", PackageMetadata { homepage: metadata.homepage.clone(), ..Default::default() }),
+        ("keywords", PackageMetadata { keywords: metadata.keywords.clone(), ..Default::default() }),
+    ] {
+        for violation in check_metadata(&only) {
+            push(violation, format!("package.{}", field));
+        }
+    }
+
+    let dependencies = manifest.dependencies.as_deref().unwrap_or_default();
+    if dependencies.len() > MAX_DEPENDENCIES {
+        push(
+            Violation { code: "TooManyDependencies", message: format!("{} dependencies (max {})", dependencies.len(), MAX_DEPENDENCIES) },
+            "dependencies".to_string(),
+        );
+    }
+    let mut seen = HashSet::new();
+    for (i, dep) in dependencies.iter().enumerate() {
+        for violation in dependency_violations(dep) {
+            let field = if violation.code == "InvalidDependencyName" { "name" } else { "version" };
+            push(violation, format!("dependencies[{}].{}", i, field));
+        }
+        if dep.name == package.name {
+            push(
+                Violation { code: "SelfDependency", message: format!("{} depends on itself", package.name) },
+                format!("dependencies[{}].name", i),
+            );
+        } else if !seen.insert(dep.name.as_str()) {
+            push(
+                Violation { code: "DuplicateDependency", message: format!("{} is listed more than once in dependencies", dep.name) },
+                format!("dependencies[{}].name", i),
+            );
+        }
+    }
+
+    let external = manifest.external_dependencies.as_deref().unwrap_or_default();
+    for violation in check_external_dependencies(external).into_iter().filter(|v| v.code == "TooManyExternalDependencies") {
+        push(violation, "external_dependencies".to_string());
+    }
+    let mut seen = HashSet::new();
+    for (i, dep) in external.iter().enumerate() {
+        for violation in check_external_dependencies(std::slice::from_ref(dep)) {
+            let field = match violation.code {
+                "InvalidExternalDependencyName" => "name",
+                "InvalidExternalDependencyVersion" => "version",
+                "InvalidExternalDependencyType" => "type",
+                _ => "registry",
+            };
+            push(violation, format!("external_dependencies[{}].{}", i, field));
+        }
+        if !seen.insert((dep.name.as_str(), dep.dep_type.as_str())) {
+            push(
+                Violation {
+                    code: "DuplicateExternalDependency",
+                    message: format!("{} ({}) is listed more than once in external_dependencies", dep.name, dep.dep_type),
+                },
+                format!("external_dependencies[{}].name", i),
+            );
+        }
+    }
+
+    if package.description.trim().is_empty() {
+        let mut diagnostic = Diagnostic::warning("EmptyDescription", "The description is empty", Some("package.description".to_string()))
+            .help("Describe the package in a sentence; search and 'antsol info' show it");
+        diagnostic = diagnostic.at(content, locate("package.description"));
+        diagnostics.push(diagnostic);
+    }
+
+    ManifestReport { manifest: Some(manifest), diagnostics }
+}
+
+/// Warnings about the files a publish would archive from `dir`
+fn check_files(dir: &Path) -> Result<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    let files = package_files(dir)?;
+    if files.iter().all(|file| file == Path::new("antsol.toml")) {
+        diagnostics.push(
+            Diagnostic::warning("EmptyPackage", format!("{} has no files to publish besides antsol.toml", dir.display()), None)
+                .help("Add the package's files, or check that .antsolignore doesn't exclude them"),
+        );
+    }
+    if readme_path(dir).is_none() {
+        diagnostics.push(
+            Diagnostic::warning("MissingReadme", format!("{} has no {}", dir.display(), README_FILE), None)
+                .help(format!("Add a {} so registry pages and 'antsol info --readme' can show it", README_FILE)),
+        );
+    }
+    Ok(diagnostics)
+}
+
+/// Program checks for a manifest dependency, whose version may be a range
+/// that publish pins to an exact version
+fn dependency_violations(dep: &Dependency) -> Vec<Violation> {
+    match VersionSpec::parse(&dep.version) {
+        Ok(VersionSpec::Exact(_)) => check_dependency(dep),
+        Ok(VersionSpec::Range(_)) => check_dependency(dep).into_iter().filter(|v| v.code != "InvalidDependencyVersion").collect(),
+        Err(_) => {
+            let mut violations: Vec<Violation> = check_dependency(dep).into_iter().filter(|v| v.code != "InvalidDependencyVersion").collect();
+            violations.push(Violation {
+                code: "InvalidDependencyVersion",
+                message: format!("Dependency {} has version '{}', which is neither a version nor a semver range", dep.name, dep.version),
+            });
+            violations
+        }
+    }
+}
+
+/// `field` of a serde "missing field `field`" message
+fn missing_field(message: &str) -> Option<&str> {
+    message.strip_prefix("missing field `")?.split('`').next()
+}
+
+/// Span of the value at a dotted `key` such as `dependencies[1].name`, or
+/// of the nearest enclosing table or array that has one
+fn span_of(root: &Item, key: &str) -> Option<Range<usize>> {
+    let mut item = root;
+    let mut span = None;
+    for part in key.split('.') {
+        let (name, index) = match part.split_once('[') {
+            Some((name, index)) => (name, index.trim_end_matches(']').parse::<usize>().ok()),
+            None => (part, None),
+        };
+        let Some(next) = item.get(name) else { break };
+        item = next;
+        span = item.span().or(span);
+        if let Some(index) = index {
+            let Some(next) = item.get(index) else { break };
+            item = next;
+            span = item.span().or(span);
+        }
+    }
+    span
+}
+
+/// Suggested fix for each check the program runs
+fn help_for(code: &str) -> Option<String> {
+    let help = match code {
+        "NameEmpty" | "NameTooLong" | "InvalidNameFormat" => {
+            "Use lowercase letters, digits and hyphens, optionally under a scope: name = \"my-package\" or \"scope/my-package\"".to_string()
+        }
+        "VersionEmpty" | "VersionTooLong" | "InvalidVersionFormat" => "Use a semver version, e.g. version = \"0.1.0\"".to_string(),
+        "DescriptionTooLong" => "Shorten the description to 256 bytes; longer text belongs in the README".to_string(),
+        "RepositoryTooLong" | "HomepageTooLong" | "InvalidUrl" => "Use an http:// or https:// URL of at most 128 bytes".to_string(),
+        "TooManyKeywords" | "InvalidKeyword" => "Keep up to 5 keywords of lowercase letters, digits and hyphens".to_string(),
+        "TooManyDependencies" => "Remove dependencies; the program stores at most 10".to_string(),
+        "InvalidDependencyName" => "Use the dependency's registry name, e.g. name = \"token-utils\"".to_string(),
+        "InvalidDependencyVersion" => "Use an exact version or a range, e.g. version = \"1.2.0\" or \"^1.2\"".to_string(),
+        "SelfDependency" => "Remove the package from its own dependencies".to_string(),
+        "DuplicateDependency" | "DuplicateExternalDependency" => "Keep one entry per dependency".to_string(),
+        "InvalidExternalDependencyType" => format!("Set type to one of {}", EXTERNAL_DEPENDENCY_TYPES.join(", ")),
+        "TooManyExternalDependencies" => "Remove external dependencies; the program stores at most 10".to_string(),
+        "InvalidExternalDependencyName" | "InvalidExternalDependencyVersion" | "InvalidExternalDependencyRegistry" => {
+            "Use printable characters without spaces, ';' or '|'".to_string()
+        }
+        _ => return None,
+    };
+    Some(help)
+}
+
+impl Diagnostic {
+    fn error(code: &'static str, message: impl Into<String>, key: Option<String>) -> Self {
+        Self { severity: Severity::Error, code, message: message.into(), key, line: None, column: None, help: None }
+    }
+
+    fn warning(code: &'static str, message: impl Into<String>, key: Option<String>) -> Self {
+        Self { severity: Severity::Warning, ..Self::error(code, message, key) }
+    }
+
+    fn from_violation(violation: Violation, key: String) -> Self {
+        let help = help_for(violation.code);
+        Self { help, ..Self::error(violation.code, violation.message, Some(key)) }
+    }
+
+    fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Point at the start of `span` in `content`
+    fn at(mut self, content: &str, span: Option<Range<usize>>) -> Self {
+        if let Some(span) = span {
+            let before = &content[..span.start.min(content.len())];
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            self.line = Some(before.matches('\n').count() + 1);
+            self.column = Some(before[line_start..].chars().count() + 1);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(report: &ManifestReport) -> Vec<(&'static str, Option<usize>)> {
+        report.diagnostics.iter().map(|d| (d.code, d.line)).collect()
+    }
+
+    #[test]
+    fn valid_manifest_has_no_diagnostics() {
+        let report = check_manifest(
+            "[package]\nname = \"my-pkg\"\nversion = \"1.0.0\"\ndescription = \"Fine\"\n\n[[dependencies]]\nname = \"dep\"\nversion = \"^1.2\"\n",
+        );
+        assert!(report.manifest.is_some());
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn program_rules_point_at_the_offending_value() {
+        let report = check_manifest(concat!(
+            "[package]\n",
+            "name = \"My_Pkg\"\n",
+            "version = \"1.0\"\n",
+            "description = \"\"\n",
+            "keywords = [\"ok\", \"Not OK\"]\n",
+            "\n",
+            "[[dependencies]]\n",
+            "name = \"dep\"\n",
+            "version = \"one\"\n",
+            "\n",
+            "[[external_dependencies]]\n",
+            "name = \"serde\"\n",
+            "version = \"1\"\n",
+            "type = \"cargo\"\n",
+        ));
+        assert_eq!(report.errors(), 5);
+        assert_eq!(report.warnings(), 1);
+        let found = codes(&report);
+        for expected in [
+            ("InvalidNameFormat", Some(2)),
+            ("InvalidVersionFormat", Some(3)),
+            ("EmptyDescription", Some(4)),
+            ("InvalidKeyword", Some(5)),
+            ("InvalidDependencyVersion", Some(9)),
+            ("InvalidExternalDependencyType", Some(14)),
+        ] {
+            assert!(found.contains(&expected), "{:?} not in {:?}", expected, found);
+        }
+        let name = report.diagnostics.iter().find(|d| d.code == "InvalidNameFormat").unwrap();
+        assert_eq!(name.column, Some(8));
+        assert!(name.help.is_some());
+        let dep_type = report.diagnostics.iter().find(|d| d.code == "InvalidExternalDependencyType").unwrap();
+        assert!(dep_type.help.as_deref().unwrap().contains("rust, npm, python, go"));
+    }
+
+    #[test]
+    fn duplicate_and_self_dependencies_are_errors() {
+        let report = check_manifest(concat!(
+            "dependencies = [\n",
+            "  { name = \"lib\", version = \"1.0.0\" },\n",
+            "  { name = \"lib\", version = \"^1\" },\n",
+            "  { name = \"app\", version = \"1.0.0\" },\n",
+            "]\n",
+            "\n[package]\nname = \"app\"\nversion = \"1.0.0\"\ndescription = \"d\"\n",
+        ));
+        assert_eq!(codes(&report), vec![("DuplicateDependency", Some(3)), ("SelfDependency", Some(4))]);
+    }
+
+    #[test]
+    fn parse_errors_carry_a_position_and_a_fix() {
+        let report = check_manifest("[package]\nname = \"pkg\"\nversion = \"1.0.0\"\n");
+        assert!(report.manifest.is_none());
+        let missing = &report.diagnostics[0];
+        assert_eq!(missing.code, "InvalidManifest");
+        assert!(missing.line.is_some());
+        assert_eq!(missing.help.as_deref(), Some("Add description = \"...\" under [package]"));
+
+        let report = check_manifest("[package]\nname = pkg\n");
+        assert_eq!(codes(&report), vec![("InvalidToml", Some(2))]);
+    }
+
+    #[test]
+    fn file_checks_warn_about_an_empty_package() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("antsol.toml"), "[package]\nname = \"pkg\"\nversion = \"1.0.0\"\ndescription = \"d\"\n").unwrap();
+        let report = check_dir(dir.path()).unwrap();
+        assert_eq!(codes(&report), vec![("EmptyPackage", None), ("MissingReadme", None)]);
+
+        std::fs::write(dir.path().join("README.md"), "# pkg").unwrap();
+        assert!(check_dir(dir.path()).unwrap().diagnostics.is_empty());
+        assert!(check_dir(dir.path()).unwrap().into_manifest(Path::new("antsol.toml")).is_ok());
+    }
+}
@@ -0,0 +1,128 @@
+use colored::*;
+
+/// Render a README for the terminal: headings, bold text and code are
+/// styled, list markers become bullets and links show their target.
+/// Anything else passes through as written.
+pub fn render(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(format!("    {}", line.dimmed()));
+            continue;
+        }
+        let indent = &line[..line.len() - trimmed.len()];
+        let rendered = if let Some((level, text)) = heading(trimmed) {
+            let text = inline(text).cyan().bold();
+            if level == 1 { text.underline().to_string() } else { text.to_string() }
+        } else if is_rule(trimmed) {
+            "─".repeat(40).dimmed().to_string()
+        } else if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|marker| trimmed.strip_prefix(marker)) {
+            format!("{}  • {}", indent, inline(item))
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            format!("  │ {}", inline(quote.trim_start()).dimmed())
+        } else {
+            inline(line)
+        };
+        lines.push(rendered);
+    }
+    lines.join("\n")
+}
+
+/// `# Title` up to `###### Title`, with any closing hashes dropped
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let text = line[level..].strip_prefix(' ')?;
+    Some((level, text.trim_end_matches('#').trim_end()))
+}
+
+/// `---`, `***` or `___`
+fn is_rule(line: &str) -> bool {
+    let line = line.trim_end();
+    line.len() >= 3 && ['-', '*', '_'].iter().any(|&c| line.chars().all(|l| l == c))
+}
+
+/// Inline code, `**bold**` and `[links](url)`; images render as their alt text
+fn inline(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                out.push_str(&rest[1..1 + end].yellow().to_string());
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+        if rest.starts_with("**") || rest.starts_with("__") {
+            let marker = &rest[..2];
+            if let Some(end) = rest[2..].find(marker).filter(|&end| end > 0) {
+                out.push_str(&inline(&rest[2..2 + end]).bold().to_string());
+                rest = &rest[end + 4..];
+                continue;
+            }
+        }
+        if rest.starts_with("![") {
+            if let Some((alt, _, len)) = link(&rest[1..]) {
+                out.push_str(&format!("[image: {}]", alt).dimmed().to_string());
+                rest = &rest[1 + len..];
+                continue;
+            }
+        }
+        if c == '[' {
+            if let Some((label, url, len)) = link(rest) {
+                out.push_str(&format!("{} ({})", inline(label).underline(), url.blue()));
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Label, target and length of a `[label](target)` at the start of `text`
+fn link(text: &str) -> Option<(&str, &str, usize)> {
+    let close = text.find("](")?;
+    let end = close + 2 + text[close + 2..].find(')')?;
+    Some((&text[1..close], &text[close + 2..end], end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(markdown: &str) -> String {
+        console::strip_ansi_codes(&render(markdown)).into_owned()
+    }
+
+    #[test]
+    fn renders_block_structure() {
+        let markdown = "# Title #\n\nSome text.\n\n- one\n  * nested\n> quoted\n---\n```rust\nlet x = 1;\n```\n## Next";
+        assert_eq!(
+            plain(markdown),
+            "Title\n\nSome text.\n\n  • one\n    • nested\n  │ quoted\n────────────────────────────────────────\n    let x = 1;\nNext"
+        );
+        // Without a space it's not a heading
+        assert_eq!(plain("#hashtag"), "#hashtag");
+    }
+
+    #[test]
+    fn renders_inline_markup() {
+        assert_eq!(
+            plain("Run `antsol install` or see [the docs](https://antsol.dev) for **more**."),
+            "Run antsol install or see the docs (https://antsol.dev) for more."
+        );
+        assert_eq!(plain("![logo](logo.png) 2 ** 3"), "[image: logo] 2 ** 3");
+        assert_eq!(plain("unclosed `tick and [bracket"), "unclosed `tick and [bracket");
+    }
+}
@@ -0,0 +1,261 @@
+use crate::publish_state::STATE_DIR;
+use crate::types::Result;
+use crate::utils::{format_size, sha256_hex};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// gitignore-style exclusion file read from the package root (and subdirectories)
+pub const IGNORE_FILE: &str = ".antsolignore";
+
+/// Files that go into a published archive, relative to `root` and sorted.
+///
+/// With an `.antsolignore` in the root, its patterns (and those of nested
+/// `.antsolignore` files) decide; otherwise the defaults apply: skip `target/`,
+/// top-level dotfiles and `*.lock`. `.git/` and the top-level `.antsol/`
+/// working directory are never archived.
+pub fn package_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let use_ignore_file = root.join(IGNORE_FILE).is_file();
+
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != ".git" && !(entry.depth() == 1 && entry.file_name() == STATE_DIR));
+    if use_ignore_file {
+        builder.add_custom_ignore_filename(IGNORE_FILE);
+    }
+
+    let mut files = Vec::new();
+    for entry in builder.build() {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(root)?.to_path_buf();
+        if use_ignore_file || !excluded_by_default(&relative) {
+            files.push(relative);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Pinned on its own at publish so registry pages can render it
+pub const README_FILE: &str = "README.md";
+
+/// The package's README, if its root has one
+pub fn readme_path(root: &Path) -> Option<PathBuf> {
+    Some(root.join(README_FILE)).filter(|path| path.is_file())
+}
+
+/// Single files above this size are flagged before upload
+pub const LARGE_FILE_WARNING: u64 = 5 * 1024 * 1024;
+
+/// Extensions of build output that rarely belongs in a published package
+const BUILD_ARTIFACT_EXTENSIONS: &[&str] = &["o", "obj", "a", "so", "dylib", "dll", "exe", "class", "pyc"];
+
+/// Magic numbers of native executables: ELF, PE, Mach-O (32/64-bit, both
+/// byte orders) and universal Mach-O
+const EXECUTABLE_MAGIC: &[&[u8]] = &[
+    b"\x7fELF",
+    b"MZ",
+    &[0xfe, 0xed, 0xfa, 0xce],
+    &[0xfe, 0xed, 0xfa, 0xcf],
+    &[0xce, 0xfa, 0xed, 0xfe],
+    &[0xcf, 0xfa, 0xed, 0xfe],
+    &[0xca, 0xfe, 0xba, 0xbe],
+];
+
+/// Files in `files` (from [`package_files`]) that probably shouldn't be
+/// published, each with the reason: larger than [`LARGE_FILE_WARNING`],
+/// inside `node_modules/` or `.git/`, build artifacts and native executables
+pub fn content_warnings(root: &Path, files: &[PathBuf]) -> Result<Vec<(PathBuf, String)>> {
+    let mut warnings = Vec::new();
+    for relative in files {
+        let path = root.join(relative);
+        let size = std::fs::metadata(&path)?.len();
+        let reason = if size > LARGE_FILE_WARNING {
+            format!("{} (over the {} warning size)", format_size(size), format_size(LARGE_FILE_WARNING))
+        } else if let Some(dir) = relative.components().find_map(|c| {
+            let name = c.as_os_str();
+            (name == "node_modules" || name == ".git").then(|| name.to_string_lossy().into_owned())
+        }) {
+            format!("inside {}/", dir)
+        } else if relative
+            .extension()
+            .is_some_and(|ext| BUILD_ARTIFACT_EXTENSIONS.iter().any(|a| ext.eq_ignore_ascii_case(a)))
+        {
+            "build artifact".to_string()
+        } else if is_executable_binary(&path)? {
+            "native executable".to_string()
+        } else {
+            continue;
+        };
+        warnings.push((relative.clone(), reason));
+    }
+    Ok(warnings)
+}
+
+/// The `count` largest of `files`, biggest first, with their sizes
+pub fn largest_files(root: &Path, files: &[PathBuf], count: usize) -> Result<Vec<(PathBuf, u64)>> {
+    let mut sized = files
+        .iter()
+        .map(|relative| Ok((relative.clone(), std::fs::metadata(root.join(relative))?.len())))
+        .collect::<Result<Vec<_>>>()?;
+    sized.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sized.truncate(count);
+    Ok(sized)
+}
+
+fn is_executable_binary(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let read = std::fs::File::open(path)?.read(&mut magic)?;
+    Ok(EXECUTABLE_MAGIC.iter().any(|m| read >= m.len() && magic.starts_with(m)))
+}
+
+/// The exclusions used when there is no `.antsolignore`
+fn excluded_by_default(relative: &Path) -> bool {
+    let path_str = relative.to_string_lossy();
+    path_str.contains("target/")
+        || path_str.starts_with('.')
+        || path_str.ends_with(".lock")
+}
+
+/// Digest of every file under `dir`: sha256 over one `path\0file-sha256\n`
+/// line per file, in path order with `/` separators. Recorded in the lockfile
+/// at install so the extracted tree can be checked later without the tarball.
+pub fn tree_sha256(dir: &Path) -> Result<String> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry.path().strip_prefix(dir)?.to_string_lossy().replace('\\', "/");
+            files.push((relative, entry.into_path()));
+        }
+    }
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for (relative, path) in files {
+        let contents = std::fs::read(path)?;
+        hasher.update(format!("{}\0{}\n", relative, sha256_hex(&contents)));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    fn listed(dir: &tempfile::TempDir) -> Vec<String> {
+        package_files(dir.path()).unwrap()
+            .iter()
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect()
+    }
+
+    #[test]
+    fn content_warnings_flag_unwanted_files() {
+        let dir = tree(&[
+            (".antsolignore", ""),
+            ("antsol.toml", ""),
+            ("src/lib.rs", "pub fn ok() {}"),
+            ("src/lib.o", ""),
+            ("web/node_modules/dep/index.js", ""),
+            ("bin/tool", "\x7fELF\x02\x01"),
+            ("README.md", "See MZ headers in docs"),
+        ]);
+        std::fs::write(dir.path().join("assets.bin"), vec![0u8; LARGE_FILE_WARNING as usize + 1]).unwrap();
+        let files = package_files(dir.path()).unwrap();
+
+        let flagged: Vec<(String, String)> = content_warnings(dir.path(), &files).unwrap()
+            .into_iter()
+            .map(|(path, reason)| (path.to_string_lossy().replace('\\', "/"), reason))
+            .collect();
+        assert_eq!(flagged, vec![
+            ("assets.bin".to_string(), "5.0 MB (over the 5.0 MB warning size)".to_string()),
+            ("bin/tool".to_string(), "native executable".to_string()),
+            ("src/lib.o".to_string(), "build artifact".to_string()),
+            ("web/node_modules/dep/index.js".to_string(), "inside node_modules/".to_string()),
+        ]);
+
+        let largest = largest_files(dir.path(), &files, 2).unwrap();
+        assert_eq!(largest[0], (PathBuf::from("assets.bin"), LARGE_FILE_WARNING + 1));
+        assert_eq!(largest[1].0, PathBuf::from("README.md"));
+    }
+
+    #[test]
+    fn defaults_apply_without_ignore_file() {
+        let dir = tree(&[
+            ("antsol.toml", ""),
+            ("src/lib.rs", ""),
+            ("target/debug/app", ""),
+            (".cargo/config.toml", ""),
+            ("Cargo.lock", ""),
+        ]);
+        assert_eq!(listed(&dir), vec!["antsol.toml", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn negation_patterns_re_include_files() {
+        let dir = tree(&[
+            (".antsolignore", "*.bin\n!keep.bin\ntarget/\n"),
+            (".antsol/publish-state.json", "{}"),
+            (".cargo/config.toml", ""),
+            (".git/HEAD", ""),
+            ("antsol.toml", ""),
+            ("assets/big.bin", ""),
+            ("assets/keep.bin", ""),
+            ("target/debug/app", ""),
+        ]);
+        assert_eq!(listed(&dir), vec![
+            ".antsolignore",
+            ".cargo/config.toml",
+            "antsol.toml",
+            "assets/keep.bin",
+        ]);
+    }
+
+    #[test]
+    fn patterns_match_in_nested_directories() {
+        let dir = tree(&[
+            (".antsolignore", "node_modules/\n/build\n"),
+            ("antsol.toml", ""),
+            ("build/out.js", ""),
+            ("web/build/keep.js", ""),
+            ("web/node_modules/dep/index.js", ""),
+            ("tests/.antsolignore", "fixtures/\n"),
+            ("tests/fixtures/large.json", ""),
+            ("tests/it.rs", ""),
+        ]);
+        assert_eq!(listed(&dir), vec![
+            ".antsolignore",
+            "antsol.toml",
+            "tests/.antsolignore",
+            "tests/it.rs",
+            "web/build/keep.js",
+        ]);
+    }
+
+    #[test]
+    fn tree_sha256_covers_paths_and_contents() {
+        let dir = tree(&[("antsol.toml", "a"), ("src/lib.rs", "b")]);
+        let original = tree_sha256(dir.path()).unwrap();
+        assert_eq!(original, tree_sha256(tree(&[("src/lib.rs", "b"), ("antsol.toml", "a")]).path()).unwrap());
+
+        std::fs::write(dir.path().join("src/lib.rs"), "changed").unwrap();
+        assert_ne!(tree_sha256(dir.path()).unwrap(), original);
+        assert_ne!(tree_sha256(tree(&[("antsol.toml", "a"), ("src/main.rs", "b")]).path()).unwrap(), original);
+        assert_ne!(tree_sha256(tree(&[("antsol.toml", "a"), ("src/lib.rs", "b"), ("extra", "")]).path()).unwrap(), original);
+    }
+}
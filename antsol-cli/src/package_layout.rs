@@ -0,0 +1,417 @@
+use crate::error::AntSolError;
+use crate::idl;
+use crate::solana_client::{ExternalDep, PackageDep};
+use crate::types::{Dependency, ExternalDependency, PackageAccount, PackageMetadata, Result};
+use borsh::BorshDeserialize;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use std::fmt;
+
+/// A field of the registry's `Package` account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageField {
+    Name,
+    Version,
+    Authority,
+    IpfsCid,
+    PublishedAt,
+    Description,
+    Dependencies,
+    ExternalDependencies,
+    Bump,
+    ContentSha256,
+    Repository,
+    Homepage,
+    Keywords,
+    ReadmeCid,
+    Downloads,
+    Frozen,
+}
+
+impl PackageField {
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageField::Name => "name",
+            PackageField::Version => "version",
+            PackageField::Authority => "authority",
+            PackageField::IpfsCid => "ipfs_cid",
+            PackageField::PublishedAt => "published_at",
+            PackageField::Description => "description",
+            PackageField::Dependencies => "dependencies",
+            PackageField::ExternalDependencies => "external_dependencies",
+            PackageField::Bump => "bump",
+            PackageField::ContentSha256 => "content_sha256",
+            PackageField::Repository => "repository",
+            PackageField::Homepage => "homepage",
+            PackageField::Keywords => "keywords",
+            PackageField::ReadmeCid => "readme_cid",
+            PackageField::Downloads => "downloads",
+            PackageField::Frozen => "frozen",
+        }
+    }
+
+    /// The field an IDL entry declares, if the CLI can read it with that type
+    fn from_idl(name: &str, ty: &str) -> Option<Self> {
+        let field = match (name, ty) {
+            ("name", "string") => PackageField::Name,
+            ("version", "string") => PackageField::Version,
+            ("authority", "pubkey") => PackageField::Authority,
+            ("ipfs_cid", "string") => PackageField::IpfsCid,
+            ("published_at", "i64") => PackageField::PublishedAt,
+            ("description", "string") => PackageField::Description,
+            ("dependencies", "vec<PackageDependency>") => PackageField::Dependencies,
+            ("external_dependencies", "ExternalDependencies" | "vec<ExternalDependency>") => PackageField::ExternalDependencies,
+            ("bump", "u8") => PackageField::Bump,
+            ("content_sha256", "[u8; 32]") => PackageField::ContentSha256,
+            ("repository", "string") => PackageField::Repository,
+            ("homepage", "string") => PackageField::Homepage,
+            ("keywords", "vec<string>") => PackageField::Keywords,
+            ("readme_cid", "ReadmeCid" | "option<string>") => PackageField::ReadmeCid,
+            ("downloads", "DownloadCount" | "u64") => PackageField::Downloads,
+            ("frozen", "Frozen" | "bool") => PackageField::Frozen,
+            _ => return None,
+        };
+        Some(field)
+    }
+}
+
+/// The order a deployment stores `Package` fields in.
+///
+/// Fields after the bump were appended to the account over time: accounts
+/// created before one existed either end before it or hold zero padding
+/// there, and both read as empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageLayout {
+    fields: Vec<PackageField>,
+}
+
+impl PackageLayout {
+    /// The registry program in this repository, as declared in [`idl::TYPES`]
+    pub fn current() -> Self {
+        let (_, fields) = idl::TYPES
+            .iter()
+            .find(|(name, _)| *name == idl::PACKAGE_ACCOUNT)
+            .expect("TYPES declares Package");
+        Self::from_fields(fields.iter().map(|f| (f.name, f.ty))).expect("TYPES Package fields are readable")
+    }
+
+    /// The layout declared by the `Package` type of a program's IDL
+    pub fn from_idl(idl: &Value) -> Result<Self> {
+        let fields = idl::struct_fields(idl, idl::PACKAGE_ACCOUNT)
+            .ok_or_else(|| AntSolError::Program("The program IDL declares no Package type".into()))?;
+        Self::from_fields(fields.iter().map(|(name, ty)| (name.as_str(), ty.as_str())))
+    }
+
+    fn from_fields<'a>(declared: impl Iterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        let mut fields = Vec::new();
+        for (name, ty) in declared {
+            let field = PackageField::from_idl(name, ty).ok_or_else(|| {
+                AntSolError::Program(format!("Package field `{}: {}` in the program IDL is unknown to this CLI", name, ty))
+            })?;
+            fields.push(field);
+        }
+        if !fields.contains(&PackageField::Bump) {
+            return Err(AntSolError::Program("Package type in the program IDL has no bump field".into()).into());
+        }
+        Ok(Self { fields })
+    }
+
+    /// Decode a `Package` account, discriminator included. A field that
+    /// doesn't fit names itself and the byte it starts at.
+    pub fn decode(&self, data: &[u8]) -> Result<PackageAccount> {
+        let discriminator = idl::account_discriminator(idl::PACKAGE_ACCOUNT);
+        if data.get(..8) != Some(&discriminator[..]) {
+            return Err(AntSolError::Program(format!(
+                "Not a Package account: discriminator {:?}",
+                &data[..data.len().min(8)]
+            ))
+            .into());
+        }
+
+        let mut reader = Reader { data, pos: 8 };
+        let mut package = PackageAccount {
+            name: String::new(),
+            version: String::new(),
+            authority: Pubkey::default(),
+            ipfs_cid: String::new(),
+            published_at: 0,
+            description: String::new(),
+            dependencies: vec![],
+            external_dependencies: vec![],
+            content_sha256: None,
+            metadata: PackageMetadata::default(),
+            readme_cid: None,
+            downloads: 0,
+            frozen: false,
+        };
+        let mut appended = false;
+        for &field in &self.fields {
+            if appended && reader.rest().iter().all(|&b| b == 0) {
+                break;
+            }
+            match field {
+                PackageField::Name => package.name = reader.read(field)?,
+                PackageField::Version => package.version = reader.read(field)?,
+                PackageField::Authority => package.authority = reader.read(field)?,
+                PackageField::IpfsCid => package.ipfs_cid = reader.read(field)?,
+                PackageField::PublishedAt => package.published_at = reader.read(field)?,
+                PackageField::Description => package.description = reader.read(field)?,
+                PackageField::Dependencies => {
+                    let deps: Vec<PackageDep> = reader.read(field)?;
+                    package.dependencies = deps.into_iter().map(|d| Dependency { name: d.name, version: d.version }).collect();
+                }
+                PackageField::ExternalDependencies => {
+                    let deps: Vec<ExternalDep> = reader.read(field)?;
+                    package.external_dependencies = deps
+                        .into_iter()
+                        .map(|d| ExternalDependency { name: d.name, version: d.version, dep_type: d.dep_type, registry: d.registry })
+                        .collect();
+                }
+                PackageField::Bump => {
+                    reader.read::<u8>(field)?;
+                    appended = true;
+                }
+                PackageField::ContentSha256 => {
+                    let bytes: [u8; 32] = reader.read(field)?;
+                    // All zeros: published before checksums
+                    package.content_sha256 =
+                        bytes.iter().any(|&b| b != 0).then(|| bytes.iter().map(|b| format!("{:02x}", b)).collect());
+                }
+                PackageField::Repository => package.metadata.repository = reader.read(field)?,
+                PackageField::Homepage => package.metadata.homepage = reader.read(field)?,
+                PackageField::Keywords => package.metadata.keywords = reader.read(field)?,
+                PackageField::ReadmeCid => package.readme_cid = reader.read(field)?,
+                PackageField::Downloads => package.downloads = reader.read(field)?,
+                PackageField::Frozen => package.frozen = reader.read(field)?,
+            }
+        }
+        Ok(package)
+    }
+}
+
+impl fmt::Display for PackageLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.fields.iter().map(|field| field.name()).collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
+/// Borsh reads that remember where they are in the account
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn rest(&self) -> &[u8] {
+        &self.data[self.pos..]
+    }
+
+    fn read<T: BorshDeserialize>(&mut self, field: PackageField) -> Result<T> {
+        let mut rest = self.rest();
+        let value = T::deserialize(&mut rest).map_err(|e| {
+            AntSolError::Program(format!(
+                "Cannot decode Package account: field `{}` at byte {} of {}: {}",
+                field.name(),
+                self.pos,
+                self.data.len(),
+                e
+            ))
+        })?;
+        self.pos = self.data.len() - rest.len();
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+impl PackageLayout {
+    /// Early deployments, which stored external dependencies before the bump.
+    /// Outside tests they are only recognised through their IDL.
+    pub(crate) fn with_external_dependencies() -> Self {
+        let mut layout = Self::current();
+        layout.fields.retain(|&f| f != PackageField::ExternalDependencies);
+        let bump = layout.fields.iter().position(|&f| f == PackageField::Bump).expect("layout has a bump");
+        layout.fields.insert(bump, PackageField::ExternalDependencies);
+        layout
+    }
+
+    /// Encode `package` the way a deployment with this layout stores it
+    pub(crate) fn encode(&self, package: &PackageAccount, bump: u8) -> Vec<u8> {
+        use borsh::BorshSerialize;
+
+        let mut data = idl::account_discriminator(idl::PACKAGE_ACCOUNT).to_vec();
+        for field in &self.fields {
+            let bytes = match field {
+                PackageField::Name => package.name.try_to_vec(),
+                PackageField::Version => package.version.try_to_vec(),
+                PackageField::Authority => package.authority.try_to_vec(),
+                PackageField::IpfsCid => package.ipfs_cid.try_to_vec(),
+                PackageField::PublishedAt => package.published_at.try_to_vec(),
+                PackageField::Description => package.description.try_to_vec(),
+                PackageField::Dependencies => package
+                    .dependencies
+                    .iter()
+                    .map(|d| PackageDep { name: d.name.clone(), version: d.version.clone() })
+                    .collect::<Vec<_>>()
+                    .try_to_vec(),
+                PackageField::ExternalDependencies => package
+                    .external_dependencies
+                    .iter()
+                    .map(|d| ExternalDep {
+                        name: d.name.clone(),
+                        version: d.version.clone(),
+                        dep_type: d.dep_type.clone(),
+                        registry: d.registry.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .try_to_vec(),
+                PackageField::Bump => bump.try_to_vec(),
+                PackageField::ContentSha256 => Ok(package
+                    .content_sha256
+                    .as_deref()
+                    .and_then(crate::utils::decode_sha256_hex)
+                    .unwrap_or([0u8; 32])
+                    .to_vec()),
+                PackageField::Repository => package.metadata.repository.try_to_vec(),
+                PackageField::Homepage => package.metadata.homepage.try_to_vec(),
+                PackageField::Keywords => package.metadata.keywords.try_to_vec(),
+                PackageField::ReadmeCid => package.readme_cid.try_to_vec(),
+                PackageField::Downloads => package.downloads.try_to_vec(),
+                PackageField::Frozen => package.frozen.try_to_vec(),
+            };
+            data.extend_from_slice(&bytes.unwrap());
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+    use solana_account_decoder::UiAccount;
+    use solana_sdk::account::Account;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    /// Account data from a captured `getAccountInfo` result
+    fn captured(name: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/accounts").join(name);
+        let account: UiAccount = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        account.decode::<Account>().unwrap().data
+    }
+
+    /// Re-encoding what was decoded gives the captured bytes back, followed
+    /// only by the account's zero padding
+    fn assert_round_trips(layout: &PackageLayout, data: &[u8], package: &PackageAccount, bump: u8) {
+        let encoded = layout.encode(package, bump);
+        assert_eq!(&data[..encoded.len()], &encoded[..]);
+        assert!(data[encoded.len()..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn decodes_accounts_from_deployments_with_external_dependencies() {
+        let data = captured("package-external-deps.json");
+        let layout = PackageLayout::with_external_dependencies();
+        let package = layout.decode(&data).unwrap();
+        assert_eq!(package.name, "token-utils");
+        assert_eq!(package.version, "1.0.0");
+        assert_eq!(package.authority, Pubkey::from_str("6sgbWr5XbZXbPWtue8uRcdCsQyXGGcAGayYRVyiXzR6V").unwrap());
+        assert_eq!(package.published_at, 1_718_000_000);
+        assert_eq!(package.dependencies.len(), 1);
+        assert_eq!(package.dependencies[0].name, "math-core");
+        assert_eq!(package.external_dependencies.len(), 1);
+        assert_eq!(package.external_dependencies[0].name, "serde");
+        assert_eq!(package.external_dependencies[0].registry.as_deref(), Some("crates.io"));
+        // Zero padding after the bump
+        assert_eq!(package.content_sha256, None);
+        assert_eq!(package.metadata, PackageMetadata::default());
+        assert_eq!(package.readme_cid, None);
+        assert_round_trips(&layout, &data, &package, 254);
+    }
+
+    #[test]
+    fn decodes_accounts_from_the_current_program() {
+        let data = captured("package-current.json");
+        let layout = PackageLayout::current();
+        let package = layout.decode(&data).unwrap();
+        assert_eq!(package.name, "vault");
+        assert_eq!(package.version, "0.4.0");
+        assert!(package.external_dependencies.is_empty());
+        assert_eq!(package.content_sha256.as_deref(), Some("5e8f4c2d0a9b1e7f3c6d8a2b4e0f9c1d7a3b5e8f2c4d6a0b9e1f3c5d7a2b4e6f"));
+        assert_eq!(package.metadata.repository, "https://github.com/acme/vault");
+        assert_eq!(package.metadata.keywords, vec!["vault", "defi"]);
+        assert_eq!(package.readme_cid.as_deref(), Some("bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"));
+        // Captured before external dependencies were appended: the account
+        // ends after the README CID, where the program now writes an empty
+        // list, a zero download count and the frozen flag unset
+        assert_eq!(package.downloads, 0);
+        assert!(!package.frozen);
+        assert_eq!(layout.encode(&package, 253), [&data[..], &[0u8; 4 + 8 + 1]].concat());
+
+        let mut with_external = package.clone();
+        with_external.external_dependencies = vec![ExternalDependency {
+            name: "serde".into(),
+            version: "^1.0".into(),
+            dep_type: "rust".into(),
+            registry: None,
+        }];
+        with_external.downloads = 42;
+        with_external.frozen = true;
+        let encoded = layout.encode(&with_external, 253);
+        let decoded = layout.decode(&encoded).unwrap();
+        assert_eq!(decoded.external_dependencies[0].name, "serde");
+        assert_eq!(decoded.downloads, 42);
+        assert!(decoded.frozen);
+        // Sized before the flag existed: the account ends after the counter
+        assert!(!layout.decode(&encoded[..encoded.len() - 1]).unwrap().frozen);
+
+        // Sized before the README CID existed: the account ends after the keywords
+        let mut older = package.clone();
+        older.readme_cid = None;
+        let encoded = layout.encode(&older, 253);
+        let before_readme = &encoded[..encoded.len() - 1 - 4];
+        assert_eq!(layout.decode(before_readme).unwrap().readme_cid, None);
+    }
+
+    #[test]
+    fn wrong_layout_names_the_field_and_offset() {
+        let data = captured("package-current.json");
+        let err = PackageLayout::with_external_dependencies().decode(&data).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Program);
+        assert!(
+            err.to_string().starts_with("Cannot decode Package account: field `external_dependencies` at byte 150 of "),
+            "{}",
+            err
+        );
+
+        let err = PackageLayout::current().decode(&data[8..]).unwrap_err();
+        assert!(err.to_string().starts_with("Not a Package account"), "{}", err);
+    }
+
+    #[test]
+    fn layout_follows_the_idl_field_order() {
+        let idl = serde_json::json!({ "types": [{ "name": "Package", "type": { "kind": "struct", "fields": [
+            { "name": "name", "type": "string" },
+            { "name": "version", "type": "string" },
+            { "name": "authority", "type": "publicKey" },
+            { "name": "ipfsCid", "type": "string" },
+            { "name": "publishedAt", "type": "i64" },
+            { "name": "description", "type": "string" },
+            { "name": "dependencies", "type": { "vec": { "defined": "PackageDependency" } } },
+            { "name": "externalDependencies", "type": { "vec": { "defined": "ExternalDependency" } } },
+            { "name": "bump", "type": "u8" },
+        ] } }] });
+        let layout = PackageLayout::from_idl(&idl).unwrap();
+        assert_eq!(
+            layout.to_string(),
+            "name, version, authority, ipfs_cid, published_at, description, dependencies, external_dependencies, bump"
+        );
+        assert_eq!(layout.decode(&captured("package-external-deps.json")).unwrap().name, "token-utils");
+
+        let mut unknown = idl.clone();
+        unknown["types"][0]["type"]["fields"][8] = serde_json::json!({ "name": "stars", "type": "u64" });
+        let err = PackageLayout::from_idl(&unknown).unwrap_err();
+        assert!(err.to_string().contains("`stars: u64`"), "{}", err);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use colored::*;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// What to do when a secret file is readable by group or others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionPolicy {
+    /// Print a warning and carry on (default)
+    Warn,
+    /// Refuse to use the file (`--strict`)
+    Strict,
+    /// chmod the file to 600 (`--fix-permissions`)
+    Fix,
+}
+
+/// Kind of secret a checked file holds, which decides the error category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretFile {
+    /// Wallet keypair JSON
+    Wallet,
+    /// `~/.antsol/config.toml`, which may hold pinning credentials
+    Config,
+}
+
+impl SecretFile {
+    fn label(self) -> &'static str {
+        match self {
+            SecretFile::Wallet => "Wallet keypair",
+            SecretFile::Config => "Config file",
+        }
+    }
+}
+
+static POLICY: OnceLock<PermissionPolicy> = OnceLock::new();
+
+/// Set the policy for this invocation; later calls are ignored
+pub fn set_policy(policy: PermissionPolicy) {
+    let _ = POLICY.set(policy);
+}
+
+pub fn policy() -> PermissionPolicy {
+    POLICY.get().copied().unwrap_or(PermissionPolicy::Warn)
+}
+
+/// Unix mode bits of `path` when group or others can access it.
+/// Always `None` on platforms without Unix permissions.
+#[cfg(unix)]
+pub fn insecure_mode(path: &Path) -> Result<Option<u32>> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path)?.permissions().mode() & 0o777;
+    Ok(if mode & 0o077 != 0 { Some(mode) } else { None })
+}
+
+#[cfg(not(unix))]
+pub fn insecure_mode(_path: &Path) -> Result<Option<u32>> {
+    Ok(None)
+}
+
+/// Check `path` under the invocation's policy
+pub fn check(path: &Path, secret: SecretFile) -> Result<()> {
+    enforce(path, secret, policy())
+}
+
+/// Check `path` and warn, refuse, or fix according to `policy`
+pub fn enforce(path: &Path, secret: SecretFile, policy: PermissionPolicy) -> Result<()> {
+    let Some(mode) = insecure_mode(path)? else {
+        return Ok(());
+    };
+    let problem = format!(
+        "{} {} is accessible by other users (mode {:03o})",
+        secret.label(),
+        path.display(),
+        mode
+    );
+    match policy {
+        PermissionPolicy::Warn => {
+            // stderr, so JSON output on stdout stays parseable
+            eprintln!(
+                "{} {}\n  Fix with: {} (or rerun with --fix-permissions)",
+                "⚠".yellow().bold(),
+                problem.yellow().bold(),
+                format!("chmod 600 {}", path.display()).cyan()
+            );
+            Ok(())
+        }
+        PermissionPolicy::Strict => {
+            let message = format!("{}. Refusing to use it under --strict; run chmod 600 {}", problem, path.display());
+            Err(match secret {
+                SecretFile::Wallet => AntSolError::Wallet(message),
+                SecretFile::Config => AntSolError::Config(message),
+            }.into())
+        }
+        PermissionPolicy::Fix => {
+            restrict(path)?;
+            eprintln!("{} Restricted {} to mode 600", "✓".green().bold(), path.display());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn restrict(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict(_path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// One-line status for `wallet show`, e.g. "owner only ✓" or "644 (accessible by others)"
+pub fn describe(path: &Path) -> String {
+    match insecure_mode(path) {
+        Ok(None) if cfg!(unix) => "owner only ✓".to_string(),
+        Ok(None) => "not checked on this platform".to_string(),
+        Ok(Some(mode)) => format!("{:03o} (accessible by other users)", mode),
+        Err(e) => format!("unknown ({})", e),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+    use std::os::unix::fs::PermissionsExt;
+
+    fn secret_with_mode(dir: &Path, mode: u32) -> std::path::PathBuf {
+        let path = dir.join(format!("secret-{:o}.json", mode));
+        std::fs::write(&path, "[]").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn detects_group_and_world_bits() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(insecure_mode(&secret_with_mode(dir.path(), 0o600)).unwrap(), None);
+        assert_eq!(insecure_mode(&secret_with_mode(dir.path(), 0o400)).unwrap(), None);
+        assert_eq!(insecure_mode(&secret_with_mode(dir.path(), 0o640)).unwrap(), Some(0o640));
+        assert_eq!(insecure_mode(&secret_with_mode(dir.path(), 0o604)).unwrap(), Some(0o604));
+        assert_eq!(insecure_mode(&secret_with_mode(dir.path(), 0o644)).unwrap(), Some(0o644));
+    }
+
+    #[test]
+    fn policies_warn_refuse_or_fix() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = secret_with_mode(dir.path(), 0o644);
+
+        assert!(enforce(&path, SecretFile::Wallet, PermissionPolicy::Warn).is_ok());
+
+        let err = enforce(&path, SecretFile::Wallet, PermissionPolicy::Strict).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Wallet);
+        let err = enforce(&path, SecretFile::Config, PermissionPolicy::Strict).unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Config);
+
+        enforce(&path, SecretFile::Wallet, PermissionPolicy::Fix).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert!(enforce(&path, SecretFile::Wallet, PermissionPolicy::Strict).is_ok());
+        assert_eq!(describe(&path), "owner only ✓");
+    }
+}
@@ -0,0 +1,387 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use reqwest::multipart;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::path::Path;
+
+/// A service that stores a file on IPFS and keeps it pinned
+pub trait PinningProvider {
+    /// Upload and pin the file at `path`, returning its CID
+    async fn pin_file(&self, path: &Path) -> Result<String>;
+}
+
+#[derive(Debug, Deserialize)]
+struct PinataResponse {
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: String,
+}
+
+/// Response of Kubo's `/api/v0/add`, also spoken by Filebase's RPC API
+#[derive(Debug, Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Web3StorageResponse {
+    cid: String,
+}
+
+/// Pinata pinning service (`pinFileToIPFS`)
+pub struct Pinata {
+    api_url: String,
+    jwt: Option<String>,
+}
+
+impl Pinata {
+    pub fn new(api_url: impl Into<String>, jwt: Option<String>) -> Self {
+        Self { api_url: api_url.into(), jwt }
+    }
+
+    /// Check the JWT against `/data/testAuthentication` without uploading anything
+    pub async fn test_authentication(&self, timeout: std::time::Duration) -> Result<()> {
+        let jwt = self.jwt.as_ref()
+            .ok_or_else(|| AntSolError::Config("PINATA_JWT token not found. Set PINATA_JWT environment variable.".into()))?;
+
+        let response = reqwest::Client::new()
+            .get(format!("{}/data/testAuthentication", base_url(&self.api_url)))
+            .bearer_auth(jwt)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Ipfs(format!("Failed to reach Pinata: {}", e)))?;
+
+        let status = response.status();
+        if is_auth_failure(status) {
+            return Err(rejected_credential("Pinata", status, "Check PINATA_JWT or re-run 'antsol setup'."));
+        }
+        if !status.is_success() {
+            return Err(AntSolError::Ipfs(format!("Pinata authentication check failed ({})", status)).into());
+        }
+        Ok(())
+    }
+}
+
+impl PinningProvider for Pinata {
+    async fn pin_file(&self, path: &Path) -> Result<String> {
+        let jwt = self.jwt.as_ref()
+            .ok_or_else(|| AntSolError::Config("PINATA_JWT token not found. Set PINATA_JWT environment variable.".into()))?;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/pinning/pinFileToIPFS", base_url(&self.api_url)))
+            .bearer_auth(jwt)
+            .multipart(file_form(path).await?)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Ipfs(format!("Failed to reach Pinata: {}", e)))?;
+
+        let status = response.status();
+        if is_auth_failure(status) {
+            return Err(rejected_credential("Pinata", status, "Check PINATA_JWT or re-run 'antsol setup'."));
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AntSolError::Ipfs(format!("Failed to upload to Pinata: {}", error_text)).into());
+        }
+
+        let result: PinataResponse = response.json().await
+            .map_err(|e| AntSolError::Ipfs(format!("Unexpected Pinata response: {}", e)))?;
+        Ok(result.ipfs_hash)
+    }
+}
+
+/// A local or self-hosted Kubo node (`/api/v0/add`)
+pub struct Kubo {
+    api_url: String,
+}
+
+impl Kubo {
+    pub fn new(api_url: impl Into<String>) -> Self {
+        Self { api_url: api_url.into() }
+    }
+}
+
+impl PinningProvider for Kubo {
+    async fn pin_file(&self, path: &Path) -> Result<String> {
+        let base = base_url(&self.api_url);
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/v0/add?pin=true", base))
+            .multipart(file_form(path).await?)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Ipfs(kubo_connect_error(base, &e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AntSolError::Ipfs(kubo_status_error(base, status, &body)).into());
+        }
+
+        let result: AddResponse = response.json().await
+            .map_err(|e| AntSolError::Ipfs(format!(
+                "Unexpected response from IPFS node at {} ({}). Is ipfs_url pointing at the Kubo RPC API?",
+                base, e
+            )))?;
+        Ok(result.hash)
+    }
+}
+
+/// web3.storage (`/upload`, Bearer API token)
+pub struct Web3Storage {
+    api_url: String,
+    token: Option<String>,
+}
+
+impl Web3Storage {
+    pub fn new(api_url: impl Into<String>, token: Option<String>) -> Self {
+        Self { api_url: api_url.into(), token }
+    }
+}
+
+impl PinningProvider for Web3Storage {
+    async fn pin_file(&self, path: &Path) -> Result<String> {
+        let token = self.token.as_ref().ok_or_else(|| missing_token("web3.storage"))?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/upload", base_url(&self.api_url)))
+            .bearer_auth(token)
+            .header("X-Name", file_name)
+            .body(tokio::fs::read(path).await?)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Ipfs(format!("Failed to reach web3.storage: {}", e)))?;
+
+        let status = response.status();
+        if is_auth_failure(status) {
+            return Err(rejected_credential("web3.storage", status, "Check pinning_token or re-run 'antsol setup'."));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AntSolError::Ipfs(format!("Failed to upload to web3.storage ({}): {}", status, body.trim())).into());
+        }
+
+        let result: Web3StorageResponse = response.json().await
+            .map_err(|e| AntSolError::Ipfs(format!("Unexpected web3.storage response: {}", e)))?;
+        Ok(result.cid)
+    }
+}
+
+/// Filebase's IPFS RPC API: Kubo's `/api/v0/add` behind a bucket access token
+pub struct Filebase {
+    api_url: String,
+    token: Option<String>,
+}
+
+impl Filebase {
+    pub fn new(api_url: impl Into<String>, token: Option<String>) -> Self {
+        Self { api_url: api_url.into(), token }
+    }
+}
+
+impl PinningProvider for Filebase {
+    async fn pin_file(&self, path: &Path) -> Result<String> {
+        let token = self.token.as_ref().ok_or_else(|| missing_token("Filebase"))?;
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/v0/add?pin=true", base_url(&self.api_url)))
+            .bearer_auth(token)
+            .multipart(file_form(path).await?)
+            .send()
+            .await
+            .map_err(|e| AntSolError::Ipfs(format!("Failed to reach Filebase: {}", e)))?;
+
+        let status = response.status();
+        if is_auth_failure(status) {
+            return Err(rejected_credential(
+                "Filebase",
+                status,
+                "pinning_token must be the bucket's IPFS RPC API key; check it or re-run 'antsol setup'.",
+            ));
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AntSolError::Ipfs(format!("Failed to upload to Filebase ({}): {}", status, body.trim())).into());
+        }
+
+        let result: AddResponse = response.json().await
+            .map_err(|e| AntSolError::Ipfs(format!("Unexpected Filebase response: {}", e)))?;
+        Ok(result.hash)
+    }
+}
+
+fn base_url(api_url: &str) -> &str {
+    api_url.trim_end_matches('/')
+}
+
+/// The file as the single `file` part services with a multipart API expect
+async fn file_form(path: &Path) -> Result<multipart::Form> {
+    let file = tokio::fs::read(path).await?;
+    let file_part = multipart::Part::bytes(file)
+        .file_name(path.file_name().unwrap_or_default().to_string_lossy().to_string());
+    Ok(multipart::Form::new().part("file", file_part))
+}
+
+fn is_auth_failure(status: StatusCode) -> bool {
+    matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+}
+
+fn rejected_credential(provider: &str, status: StatusCode, hint: &str) -> Box<dyn std::error::Error> {
+    AntSolError::Config(format!("{} rejected the credential ({}). {}", provider, status, hint)).into()
+}
+
+fn missing_token(provider: &str) -> Box<dyn std::error::Error> {
+    AntSolError::Config(format!(
+        "No {} API token configured. Run 'antsol setup' or 'antsol config set pinning_token <token>'.",
+        provider
+    )).into()
+}
+
+fn kubo_connect_error(base: &str, error: &reqwest::Error) -> String {
+    if error.is_connect() {
+        format!(
+            "Could not connect to the IPFS node at {}. Is the daemon running? Start it with 'ipfs daemon', \
+             or check that ipfs_url uses the RPC API port (5001 by default).",
+            base
+        )
+    } else if error.is_timeout() {
+        format!("IPFS node at {} timed out while adding the package. Check the daemon's logs.", base)
+    } else {
+        format!("Failed to reach IPFS node at {}: {}", base, error)
+    }
+}
+
+fn kubo_status_error(base: &str, status: StatusCode, body: &str) -> String {
+    match status.as_u16() {
+        404 | 405 => format!(
+            "{} does not look like a Kubo RPC API ({} for /api/v0/add). \
+             Point ipfs_url at the API port (5001 by default), not the gateway (8080).",
+            base, status
+        ),
+        403 => format!(
+            "IPFS node at {} refused the upload ({}). Check the node's API access settings (API.Authorizations).",
+            base, status
+        ),
+        _ => format!("IPFS node at {} failed to add the package ({}): {}", base, status, body.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::IpfsBackend;
+    use crate::error::{error_kind, ErrorKind};
+    use serde_json::json;
+    use std::path::PathBuf;
+    use wiremock::matchers::{body_bytes, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const CID: &str = "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu";
+    const ARCHIVE: &[u8] = b"not really a tarball";
+
+    fn archive() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.tar.gz");
+        std::fs::write(&path, ARCHIVE).unwrap();
+        (dir, path)
+    }
+
+    async fn respond(route: &str, token: &str, response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path(route))
+            .and(header("authorization", format!("Bearer {}", token).as_str()))
+            .respond_with(response)
+            .expect(1)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn assert_rejected(err: Box<dyn std::error::Error>, provider: &str) {
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Config);
+        assert!(err.to_string().contains(&format!("{} rejected the credential (401", provider)), "{}", err);
+    }
+
+    #[test]
+    fn kubo_errors_are_actionable() {
+        let base = IpfsBackend::KUBO_DEFAULT_URL;
+        assert!(kubo_status_error(base, StatusCode::NOT_FOUND, "").contains("not the gateway (8080)"));
+        assert!(kubo_status_error(base, StatusCode::FORBIDDEN, "").contains("API.Authorizations"));
+        let other = kubo_status_error(base, StatusCode::INTERNAL_SERVER_ERROR, "blockstore full\n");
+        assert!(other.ends_with("(500 Internal Server Error): blockstore full"));
+    }
+
+    #[tokio::test]
+    async fn pinata_pins_with_jwt() {
+        let ok = ResponseTemplate::new(200).set_body_json(json!({ "IpfsHash": CID }));
+        let server = respond("/pinning/pinFileToIPFS", "jwt", ok).await;
+        let (_dir, file) = archive();
+        assert_eq!(Pinata::new(server.uri(), Some("jwt".into())).pin_file(&file).await.unwrap(), CID);
+
+        let server = respond("/pinning/pinFileToIPFS", "expired", ResponseTemplate::new(401)).await;
+        let err = Pinata::new(server.uri(), Some("expired".into())).pin_file(&file).await.unwrap_err();
+        assert_rejected(err, "Pinata");
+
+        let err = Pinata::new(server.uri(), None).pin_file(&file).await.unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Config);
+    }
+
+    #[tokio::test]
+    async fn pinata_tests_authentication_without_uploading() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/data/testAuthentication"))
+            .and(header("authorization", "Bearer jwt"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/data/testAuthentication"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        let timeout = std::time::Duration::from_secs(2);
+
+        Pinata::new(server.uri(), Some("jwt".into())).test_authentication(timeout).await.unwrap();
+        let err = Pinata::new(server.uri(), Some("expired".into())).test_authentication(timeout).await.unwrap_err();
+        assert_rejected(err, "Pinata");
+    }
+
+    #[tokio::test]
+    async fn web3_storage_uploads_the_raw_file() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/upload"))
+            .and(header("authorization", "Bearer w3-token"))
+            .and(header("x-name", "package.tar.gz"))
+            .and(body_bytes(ARCHIVE))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "cid": CID })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (_dir, file) = archive();
+        assert_eq!(Web3Storage::new(server.uri(), Some("w3-token".into())).pin_file(&file).await.unwrap(), CID);
+
+        let server = respond("/upload", "revoked", ResponseTemplate::new(401)).await;
+        let err = Web3Storage::new(server.uri(), Some("revoked".into())).pin_file(&file).await.unwrap_err();
+        assert_rejected(err, "web3.storage");
+
+        let err = Web3Storage::new(server.uri(), None).pin_file(&file).await.unwrap_err();
+        assert!(err.to_string().contains("antsol config set pinning_token"));
+    }
+
+    #[tokio::test]
+    async fn filebase_adds_through_the_rpc_api() {
+        let ok = ResponseTemplate::new(200).set_body_json(json!({ "Name": "package.tar.gz", "Hash": CID, "Size": "20" }));
+        let server = respond("/api/v0/add", "fb-key", ok).await;
+        let (_dir, file) = archive();
+        assert_eq!(Filebase::new(server.uri(), Some("fb-key".into())).pin_file(&file).await.unwrap(), CID);
+
+        let server = respond("/api/v0/add", "wrong-key", ResponseTemplate::new(401)).await;
+        let err = Filebase::new(server.uri(), Some("wrong-key".into())).pin_file(&file).await.unwrap_err();
+        assert_rejected(err, "Filebase");
+    }
+}
@@ -0,0 +1,68 @@
+use crate::types::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Per-package working directory, never archived
+pub const STATE_DIR: &str = ".antsol";
+const STATE_FILE: &str = "publish-state.json";
+
+/// The last upload of a package that hasn't been confirmed on-chain yet.
+///
+/// Written to `.antsol/publish-state.json` in the package directory right
+/// after the pin, so a publish or update whose transaction fails can be
+/// re-run without uploading (and pinning) the same archive again. Archives
+/// are deterministic, so an unchanged directory hashes the same.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PublishState {
+    /// Hex sha256 of the uploaded tar.gz
+    pub archive_sha256: String,
+    pub cid: String,
+    /// IPFS API the archive was pinned through; another provider doesn't hold the pin
+    pub pinned_with: String,
+    /// Unix time of the upload
+    pub uploaded_at: i64,
+    /// Hex sha256 and CID of the README pinned alongside, if there was one
+    #[serde(default)]
+    pub readme_sha256: Option<String>,
+    #[serde(default)]
+    pub readme_cid: Option<String>,
+}
+
+impl PublishState {
+    pub fn path(package_path: &Path) -> PathBuf {
+        package_path.join(STATE_DIR).join(STATE_FILE)
+    }
+
+    /// The recorded upload, if any; an unreadable file counts as none
+    pub fn load(package_path: &Path) -> Option<Self> {
+        std::fs::read_to_string(Self::path(package_path))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    pub fn save(&self, package_path: &Path) -> Result<()> {
+        let path = Self::path(package_path);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Whether this state recorded exactly the upload of an archive hashing
+    /// to `archive_sha256` through `api_url`, so its CIDs can be reused. The
+    /// README must be unchanged too; it can be excluded from the archive.
+    pub fn matches(&self, archive_sha256: &str, readme_sha256: Option<&str>, api_url: &str) -> bool {
+        self.archive_sha256 == archive_sha256 && self.readme_sha256.as_deref() == readme_sha256 && self.pinned_with == api_url
+    }
+
+    /// Forget the upload once its transaction is confirmed. The directory
+    /// goes too when nothing else is in it.
+    pub fn clear(package_path: &Path) {
+        let path = Self::path(package_path);
+        let _ = std::fs::remove_file(&path);
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
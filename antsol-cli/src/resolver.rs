@@ -0,0 +1,907 @@
+use crate::error::{error_kind, AntSolError, ErrorKind};
+use crate::local_registry::is_local_cid;
+use crate::lockfile::dependency_name;
+use crate::solana_client::AntSolClient;
+use crate::types::{LockFile, PackageAccount, Result};
+use crate::utils::format_size;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+/// Where the resolver gets package metadata from.
+///
+/// The install path reads the chain, optionally layered under the local
+/// registry; tests plug in an in-memory source.
+pub trait MetadataSource {
+    /// Fetch metadata for an exact `name@version`, `None` when it isn't published
+    async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>>;
+}
+
+impl MetadataSource for AntSolClient {
+    async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
+        self.get_package(name, version)
+    }
+}
+
+/// Consult `local` first and fall back to `fallback` for anything it lacks.
+///
+/// Used by `--local` installs so locally published packages shadow the chain.
+pub struct LocalFirst<'a, L, F> {
+    pub local: &'a L,
+    pub fallback: &'a F,
+}
+
+impl<L: MetadataSource, F: MetadataSource> MetadataSource for LocalFirst<'_, L, F> {
+    async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
+        match self.local.fetch_package(name, version).await? {
+            Some(package) => Ok(Some(package)),
+            None => self.fallback.fetch_package(name, version).await,
+        }
+    }
+}
+
+/// A package picked by the resolver, with the chain of requirements that pulled it in
+#[derive(Debug, Clone)]
+pub struct ResolvedPackage {
+    pub package: PackageAccount,
+    /// Requirement chain from the root, e.g. `["app@1.0.0", "lib@2.0.0"]`
+    pub path: Vec<String>,
+}
+
+impl ResolvedPackage {
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.package.name, self.package.version)
+    }
+}
+
+/// Fully resolved dependency closure of one root package
+#[derive(Debug)]
+pub struct Resolution {
+    pub root: String,
+    /// One entry per unique package name
+    pub packages: BTreeMap<String, ResolvedPackage>,
+    /// Package names in the order they were discovered (root first)
+    pub order: Vec<String>,
+}
+
+impl Resolution {
+    pub fn root_package(&self) -> &ResolvedPackage {
+        &self.packages[&self.root]
+    }
+
+    /// Packages in discovery order
+    pub fn iter(&self) -> impl Iterator<Item = &ResolvedPackage> {
+        self.order.iter().map(|name| &self.packages[name])
+    }
+
+    /// ASCII tree of the closure; repeated subtrees are marked `(*)`
+    pub fn render_tree(&self) -> Vec<String> {
+        self.render_tree_to_depth(None)
+    }
+
+    /// [`render_tree`](Self::render_tree), leaving out packages more than
+    /// `max_depth` levels below the root
+    pub fn render_tree_to_depth(&self, max_depth: Option<usize>) -> Vec<String> {
+        let root = self.root_package();
+        let mut lines = vec![format!("{} ({})", root.id(), root.package.ipfs_cid)];
+        let mut seen = HashSet::new();
+        seen.insert(self.root.clone());
+        self.render_children(&self.root, "", max_depth, &mut seen, &mut lines);
+        lines
+    }
+
+    fn render_children(&self, name: &str, prefix: &str, depth_left: Option<usize>, seen: &mut HashSet<String>, lines: &mut Vec<String>) {
+        if depth_left == Some(0) {
+            return;
+        }
+        let depth_left = depth_left.map(|d| d - 1);
+        let deps = &self.packages[name].package.dependencies;
+        for (i, dep) in deps.iter().enumerate() {
+            let last = i + 1 == deps.len();
+            let branch = if last { "└── " } else { "├── " };
+            let resolved = &self.packages[&dep.name];
+            if !seen.insert(dep.name.clone()) {
+                lines.push(format!("{}{}{} (*)", prefix, branch, resolved.id()));
+                continue;
+            }
+            lines.push(format!("{}{}{} ({})", prefix, branch, resolved.id(), resolved.package.ipfs_cid));
+            let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            self.render_children(&dep.name, &child_prefix, depth_left, seen, lines);
+        }
+    }
+
+    /// Every resolved package with its direct dependencies, for JSON output
+    pub fn graph(&self) -> ResolutionGraph {
+        ResolutionGraph {
+            root: self.root_package().id(),
+            packages: self.iter().map(|resolved| GraphNode {
+                name: resolved.package.name.clone(),
+                version: resolved.package.version.clone(),
+                ipfs_cid: resolved.package.ipfs_cid.clone(),
+                content_sha256: resolved.package.content_sha256.clone(),
+                dependencies: resolved.package.dependencies
+                    .iter()
+                    .map(|d| format!("{}@{}", d.name, d.version))
+                    .collect(),
+                path: resolved.path.clone(),
+            }).collect(),
+        }
+    }
+}
+
+/// The resolved dependency graph as `antsol tree --json` prints it
+#[derive(Debug, Serialize)]
+pub struct ResolutionGraph {
+    /// `name@version` of the root package
+    pub root: String,
+    /// One node per package, in discovery order (root first)
+    pub packages: Vec<GraphNode>,
+}
+
+/// One package in a [`ResolutionGraph`]
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    pub name: String,
+    pub version: String,
+    pub ipfs_cid: String,
+    pub content_sha256: Option<String>,
+    /// Direct dependencies as `name@version`
+    pub dependencies: Vec<String>,
+    /// Requirement chain from the root that first pulled this package in
+    pub path: Vec<String>,
+}
+
+/// Walk the dependency graph of `name@version` transitively.
+///
+/// Every package appears once; requiring the same name at two different
+/// exact versions, or a package depending on itself through any chain, is an
+/// error naming the paths involved.
+pub async fn resolve<S: MetadataSource>(source: &S, name: &str, version: &str) -> Result<Resolution> {
+    let root = fetch_required(source, name, version, &[]).await?;
+
+    let mut resolution = Resolution {
+        root: name.to_string(),
+        packages: BTreeMap::new(),
+        order: vec![name.to_string()],
+    };
+    let root_deps = root.dependencies.clone();
+    resolution.packages.insert(name.to_string(), ResolvedPackage {
+        path: vec![format!("{}@{}", name, version)],
+        package: root,
+    });
+
+    // Depth-first walk with an explicit stack of (package name, remaining deps)
+    let mut stack = vec![(name.to_string(), root_deps, 0usize)];
+    while let Some((current, deps, next)) = stack.last_mut() {
+        if *next >= deps.len() {
+            stack.pop();
+            continue;
+        }
+        let dep = deps[*next].clone();
+        *next += 1;
+        let current = current.clone();
+
+        let mut path = resolution.packages[&current].path.clone();
+        path.push(format!("{}@{}", dep.name, dep.version));
+
+        if stack.iter().any(|(name, _, _)| *name == dep.name) {
+            let existing = &resolution.packages[&dep.name];
+            if existing.package.version == dep.version {
+                let start = path.iter().position(|p| *p == existing.id()).unwrap_or(0);
+                return Err(AntSolError::Resolution(format!(
+                    "Dependency cycle detected: {}",
+                    path[start..].join(" -> ")
+                )).into());
+            }
+        }
+
+        if let Some(existing) = resolution.packages.get(&dep.name) {
+            if existing.package.version != dep.version {
+                return Err(AntSolError::Resolution(format!(
+                    "Version conflict for {}:\n  {}\n  {}",
+                    dep.name,
+                    existing.path.join(" -> "),
+                    path.join(" -> ")
+                )).into());
+            }
+            continue;
+        }
+
+        let package = fetch_required(source, &dep.name, &dep.version, &path[..path.len() - 1]).await?;
+        let child_deps = package.dependencies.clone();
+        resolution.order.push(dep.name.clone());
+        resolution.packages.insert(dep.name.clone(), ResolvedPackage { package, path });
+        stack.push((dep.name, child_deps, 0));
+    }
+
+    Ok(resolution)
+}
+
+/// A dependency version as written in `antsol.toml` or an install spec.
+///
+/// A full version (`1.2.3`, `1.3.0-beta.1`) is exact, as the program requires;
+/// anything else is parsed as a semver range (`^1.2`, `~0.3.1`, `>=2, <3`, `1.*`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSpec {
+    Exact(String),
+    Range(semver::VersionReq),
+}
+
+impl VersionSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if semver::Version::parse(spec).is_ok() {
+            return Ok(VersionSpec::Exact(spec.to_string()));
+        }
+        semver::VersionReq::parse(spec)
+            .map(VersionSpec::Range)
+            .map_err(|e| AntSolError::Usage(format!("Invalid version requirement '{}': {}", spec, e)).into())
+    }
+
+    /// Highest of `versions` this spec accepts; unparseable versions are ignored.
+    ///
+    /// Ranges follow semver's pre-release rule: a pre-release only matches when
+    /// the range itself names a pre-release of the same `major.minor.patch`.
+    pub fn select<'a, I>(&self, versions: I) -> Option<String>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        match self {
+            VersionSpec::Exact(exact) => versions.into_iter().find(|v| v == exact).map(str::to_string),
+            VersionSpec::Range(req) => versions
+                .into_iter()
+                .filter_map(|v| semver::Version::parse(v).ok())
+                .filter(|v| req.matches(v))
+                .max()
+                .map(|v| v.to_string()),
+        }
+    }
+}
+
+/// Why a package ends up at the version in the plan
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VersionReason {
+    /// Exact version given on the command line
+    Requested,
+    /// Highest version known to the indexer
+    Latest,
+    /// Pinned by another package's dependency list
+    Dependency { required_by: String },
+    /// Pinned by `antsol.lock` (`install --locked`)
+    Locked,
+    /// Highest indexed version matching a range
+    Range { requirement: String },
+}
+
+impl fmt::Display for VersionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionReason::Requested => write!(f, "requested"),
+            VersionReason::Latest => write!(f, "latest"),
+            VersionReason::Dependency { required_by } => write!(f, "required by {}", required_by),
+            VersionReason::Locked => write!(f, "locked"),
+            VersionReason::Range { requirement } => write!(f, "matches {}", requirement),
+        }
+    }
+}
+
+/// One package the executor will download
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedPackage {
+    pub name: String,
+    pub version: String,
+    pub ipfs_cid: String,
+    pub reason: VersionReason,
+    /// Archive size in bytes, when a gateway reported it
+    pub size: Option<u64>,
+    pub install_dir: String,
+    /// Archive sha256 the download must match (from the lockfile, else the on-chain checksum)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Served from the local development registry instead of chain + IPFS
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub local: bool,
+    /// Full on-chain metadata, for the post-install summary
+    #[serde(skip)]
+    pub account: PackageAccount,
+}
+
+/// A file or directory the executor will write
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanChange {
+    /// "create" or "replace"
+    pub action: &'static str,
+    pub path: String,
+}
+
+/// A lockfile entry the install will add or change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockChange {
+    pub name: String,
+    /// Currently locked version, `None` for a new entry
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// Why resolution could not produce an installable plan
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanFailure {
+    /// Catalog name of the error, e.g. "resolution" or "not_found"
+    pub kind: &'static str,
+    pub message: String,
+    #[serde(skip)]
+    error_kind: ErrorKind,
+}
+
+impl PlanFailure {
+    pub fn into_error(self) -> Box<dyn std::error::Error> {
+        match self.error_kind {
+            ErrorKind::NotFound => AntSolError::NotFound(self.message).into(),
+            _ => AntSolError::Resolution(self.message).into(),
+        }
+    }
+}
+
+/// Everything an install will do, computed before anything is touched.
+///
+/// `--plan` prints this; a normal install hands the same value to the
+/// executor, so what is shown is exactly what runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolutionPlan {
+    pub root: String,
+    pub packages: Vec<PlannedPackage>,
+    pub total_size: Option<u64>,
+    pub changes: Vec<PlanChange>,
+    /// `antsol.lock` entries that will be added or changed
+    pub lock_changes: Vec<LockChange>,
+    /// ASCII tree of the resolved graph
+    pub tree: Vec<String>,
+    /// Things worth a second look, e.g. an upgrade that adds a new dependency
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<PlanFailure>,
+}
+
+impl ResolutionPlan {
+    /// Record the archive size of `name` and recompute the total
+    pub fn record_size(&mut self, name: &str, size: Option<u64>) {
+        if let Some(package) = self.packages.iter_mut().find(|p| p.name == name) {
+            package.size = size;
+        }
+        self.total_size = self.packages.iter().map(|p| p.size).sum();
+    }
+
+    /// Human-readable plan: package table, total size, changes and tree
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![format!("Plan for {}", self.root)];
+        if let Some(failure) = &self.failure {
+            lines.push(format!("✗ Cannot install ({}):", failure.kind));
+            lines.extend(failure.message.lines().map(|l| format!("  {}", l)));
+            return lines;
+        }
+
+        lines.push(format!("  {:<24} {:<12} {:<32} {:>10}  {}", "PACKAGE", "VERSION", "REASON", "SIZE", "CID"));
+        for p in &self.packages {
+            let size = p.size.map(format_size).unwrap_or_else(|| "?".to_string());
+            let source = if p.local { " [local]" } else { "" };
+            lines.push(format!(
+                "  {:<24} {:<12} {:<32} {:>10}  {}{}",
+                p.name, p.version, p.reason.to_string(), size, p.ipfs_cid, source
+            ));
+        }
+        let total = self.total_size.map(format_size).unwrap_or_else(|| "unknown".to_string());
+        lines.push(format!("Total download: {} ({} packages)", total, self.packages.len()));
+
+        lines.push("Changes:".to_string());
+        for change in &self.changes {
+            lines.push(format!("  {} {}", change.action, change.path));
+        }
+        for change in &self.lock_changes {
+            match &change.from {
+                Some(from) => lines.push(format!("  lock {} {} -> {}", change.name, from, change.to)),
+                None => lines.push(format!("  lock {} {} (new)", change.name, change.to)),
+            }
+        }
+        if !self.warnings.is_empty() {
+            lines.push("Warnings:".to_string());
+            lines.extend(self.warnings.iter().map(|w| format!("  ⚠ {}", w)));
+        }
+
+        lines.push("Tree:".to_string());
+        lines.extend(self.tree.iter().map(|l| format!("  {}", l)));
+        lines
+    }
+}
+
+/// Resolve `name@version` into a plan without side effects.
+///
+/// Cycles, version conflicts and missing dependencies are captured in
+/// `failure` so the plan can explain them; transport errors are returned.
+pub async fn plan<S: MetadataSource>(
+    source: &S,
+    name: &str,
+    version: &str,
+    reason: VersionReason,
+    packages_dir: &Path,
+    lockfile: Option<&LockFile>,
+) -> Result<ResolutionPlan> {
+    let root = format!("{}@{}", name, version);
+    let resolution = match resolve(source, name, version).await {
+        Ok(resolution) => resolution,
+        Err(e) => {
+            let kind = error_kind(e.as_ref());
+            if kind != ErrorKind::Resolution && kind != ErrorKind::NotFound {
+                return Err(e);
+            }
+            return Ok(ResolutionPlan {
+                root,
+                packages: Vec::new(),
+                total_size: None,
+                changes: Vec::new(),
+                lock_changes: Vec::new(),
+                tree: Vec::new(),
+                warnings: Vec::new(),
+                failure: Some(PlanFailure { kind: kind.name(), message: e.to_string(), error_kind: kind }),
+            });
+        }
+    };
+
+    let mut packages = Vec::new();
+    let mut changes = Vec::new();
+    let mut lock_changes = Vec::new();
+    let mut warnings = Vec::new();
+    for resolved in resolution.iter() {
+        let install_dir = packages_dir.join(&resolved.package.name);
+        let reason = match resolved.path.len() {
+            1 => reason.clone(),
+            n => VersionReason::Dependency { required_by: resolved.path[n - 2].clone() },
+        };
+        changes.push(PlanChange {
+            action: if install_dir.exists() { "replace" } else { "create" },
+            path: install_dir.display().to_string(),
+        });
+        let locked = lockfile.and_then(|lock| lock.get(&resolved.package.name));
+        let unchanged = locked.is_some_and(|l| {
+            l.version == resolved.package.version && l.ipfs_cid == resolved.package.ipfs_cid
+        });
+        if let Some(locked) = locked.filter(|l| l.version != resolved.package.version) {
+            for added in added_dependencies(&locked.dependencies, &resolved.package) {
+                warnings.push(format!(
+                    "{} {} -> {} adds new dependency {}",
+                    resolved.package.name, locked.version, resolved.package.version, added
+                ));
+            }
+        }
+        if !unchanged {
+            lock_changes.push(LockChange {
+                name: resolved.package.name.clone(),
+                from: locked.map(|l| l.version.clone()),
+                to: resolved.package.version.clone(),
+            });
+        }
+        packages.push(PlannedPackage {
+            name: resolved.package.name.clone(),
+            version: resolved.package.version.clone(),
+            ipfs_cid: resolved.package.ipfs_cid.clone(),
+            reason,
+            size: None,
+            install_dir: install_dir.display().to_string(),
+            sha256: resolved.package.content_sha256.clone(),
+            local: is_local_cid(&resolved.package.ipfs_cid),
+            account: resolved.package.clone(),
+        });
+    }
+
+    Ok(ResolutionPlan {
+        root,
+        packages,
+        total_size: None,
+        changes,
+        lock_changes,
+        tree: resolution.render_tree(),
+        warnings,
+        failure: None,
+    })
+}
+
+/// Dependency names of `package` that the locked `name@version` list lacks
+fn added_dependencies(locked: &[String], package: &PackageAccount) -> Vec<String> {
+    let locked_names: HashSet<&str> = locked.iter().map(|d| dependency_name(d)).collect();
+    package.dependencies.iter()
+        .filter(|d| !locked_names.contains(d.name.as_str()))
+        .map(|d| d.name.clone())
+        .collect()
+}
+
+/// Plan an install of exactly what `lockfile` pins.
+///
+/// Every entry is re-read from `source`; a package that disappeared or whose
+/// CID no longer matches the lockfile is an error.
+pub async fn plan_locked<S: MetadataSource>(
+    source: &S,
+    lockfile: &LockFile,
+    packages_dir: &Path,
+) -> Result<ResolutionPlan> {
+    let mut packages = Vec::new();
+    let mut changes = Vec::new();
+    let mut tree = Vec::new();
+    for locked in &lockfile.packages {
+        let account = source.fetch_package(&locked.name, &locked.version).await?
+            .ok_or_else(|| AntSolError::NotFound(format!(
+                "Locked package {}@{} not found on-chain",
+                locked.name, locked.version
+            )))?;
+        if account.content_sha256.as_ref().is_some_and(|sha256| *sha256 != locked.sha256) {
+            return Err(AntSolError::Verification(format!(
+                "On-chain checksum for {}@{} does not match antsol.lock: locked {}, on-chain {}",
+                locked.name, locked.version, locked.sha256, account.content_sha256.as_deref().unwrap_or_default()
+            )).into());
+        }
+        if account.ipfs_cid != locked.ipfs_cid {
+            return Err(AntSolError::Verification(format!(
+                "On-chain CID for {}@{} no longer matches antsol.lock: locked {}, on-chain {}",
+                locked.name, locked.version, locked.ipfs_cid, account.ipfs_cid
+            )).into());
+        }
+
+        let install_dir = packages_dir.join(&locked.name);
+        changes.push(PlanChange {
+            action: if install_dir.exists() { "replace" } else { "create" },
+            path: install_dir.display().to_string(),
+        });
+        tree.push(format!("{}@{} ({})", locked.name, locked.version, locked.ipfs_cid));
+        packages.push(PlannedPackage {
+            name: locked.name.clone(),
+            version: locked.version.clone(),
+            ipfs_cid: locked.ipfs_cid.clone(),
+            reason: VersionReason::Locked,
+            size: None,
+            install_dir: install_dir.display().to_string(),
+            sha256: Some(locked.sha256.clone()),
+            local: false,
+            account,
+        });
+    }
+
+    Ok(ResolutionPlan {
+        root: crate::lockfile::LOCKFILE_NAME.to_string(),
+        packages,
+        total_size: None,
+        changes,
+        lock_changes: Vec::new(),
+        tree,
+        warnings: Vec::new(),
+        failure: None,
+    })
+}
+
+async fn fetch_required<S: MetadataSource>(
+    source: &S,
+    name: &str,
+    version: &str,
+    required_by: &[String],
+) -> Result<PackageAccount> {
+    match source.fetch_package(name, version).await? {
+        Some(package) => Ok(package),
+        None if required_by.is_empty() => {
+            Err(AntSolError::PackageNotFound { name: name.to_string(), version: Some(version.to_string()) }.into())
+        }
+        None => Err(AntSolError::NotFound(format!(
+            "Dependency {}@{} not found on-chain (required by {})",
+            name,
+            version,
+            required_by.join(" -> ")
+        )).into()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::error::{error_kind, ErrorKind};
+    use crate::solana_client::tests::sample_package;
+    use crate::types::LockedPackage;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// In-memory metadata keyed by `name@version`, counting lookups
+    #[derive(Default)]
+    pub(crate) struct MockSource {
+        pub packages: HashMap<String, PackageAccount>,
+        pub fetches: Mutex<Vec<String>>,
+    }
+
+    impl MockSource {
+        pub(crate) fn new(packages: Vec<PackageAccount>) -> Self {
+            Self {
+                packages: packages
+                    .into_iter()
+                    .map(|p| (format!("{}@{}", p.name, p.version), p))
+                    .collect(),
+                fetches: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl MetadataSource for MockSource {
+        async fn fetch_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
+            let key = format!("{}@{}", name, version);
+            self.fetches.lock().unwrap().push(key.clone());
+            Ok(self.packages.get(&key).cloned())
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_transitive_closure_once_per_package() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "2.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "0.1.0")]),
+            sample_package("lib-b", "2.0.0", &[("shared", "0.1.0")]),
+            sample_package("shared", "0.1.0", &[]),
+        ]);
+
+        let resolution = resolve(&source, "app", "1.0.0").await.unwrap();
+
+        assert_eq!(resolution.order, vec!["app", "lib-a", "shared", "lib-b"]);
+        assert_eq!(source.fetches.lock().unwrap().len(), 4);
+        assert_eq!(resolution.packages["shared"].path, vec!["app@1.0.0", "lib-a@1.0.0", "shared@0.1.0"]);
+
+        let cid = "Qm".to_string() + &"a".repeat(44);
+        assert_eq!(resolution.render_tree(), vec![
+            format!("app@1.0.0 ({})", cid),
+            format!("├── lib-a@1.0.0 ({})", cid),
+            format!("│   └── shared@0.1.0 ({})", cid),
+            format!("└── lib-b@2.0.0 ({})", cid),
+            "    └── shared@0.1.0 (*)".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn tree_depth_and_graph_cover_the_closure() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "2.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "0.1.0")]),
+            sample_package("lib-b", "2.0.0", &[("shared", "0.1.0")]),
+            sample_package("shared", "0.1.0", &[]),
+        ]);
+        let resolution = resolve(&source, "app", "1.0.0").await.unwrap();
+
+        let cid = "Qm".to_string() + &"a".repeat(44);
+        assert_eq!(resolution.render_tree_to_depth(Some(0)), vec![format!("app@1.0.0 ({})", cid)]);
+        assert_eq!(resolution.render_tree_to_depth(Some(1)), vec![
+            format!("app@1.0.0 ({})", cid),
+            format!("├── lib-a@1.0.0 ({})", cid),
+            format!("└── lib-b@2.0.0 ({})", cid),
+        ]);
+        assert_eq!(resolution.render_tree_to_depth(Some(5)), resolution.render_tree());
+
+        // The JSON graph is never cut short by the display depth
+        let graph = serde_json::to_value(resolution.graph()).unwrap();
+        assert_eq!(graph["root"], "app@1.0.0");
+        let names: Vec<&str> = graph["packages"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["app", "lib-a", "shared", "lib-b"]);
+        assert_eq!(graph["packages"][0]["dependencies"], serde_json::json!(["lib-a@1.0.0", "lib-b@2.0.0"]));
+        assert_eq!(graph["packages"][2]["path"], serde_json::json!(["app@1.0.0", "lib-a@1.0.0", "shared@0.1.0"]));
+    }
+
+    #[tokio::test]
+    async fn version_conflict_reports_both_paths() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "1.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "1.0.0")]),
+            sample_package("lib-b", "1.0.0", &[("shared", "2.0.0")]),
+            sample_package("shared", "1.0.0", &[]),
+            sample_package("shared", "2.0.0", &[]),
+        ]);
+
+        let err = resolve(&source, "app", "1.0.0").await.unwrap_err();
+        let message = err.to_string();
+
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Resolution);
+        assert!(message.contains("app@1.0.0 -> lib-a@1.0.0 -> shared@1.0.0"));
+        assert!(message.contains("app@1.0.0 -> lib-b@1.0.0 -> shared@2.0.0"));
+    }
+
+    #[tokio::test]
+    async fn cycle_is_detected() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("lib-b", "1.0.0")]),
+            sample_package("lib-b", "1.0.0", &[("lib-a", "1.0.0")]),
+        ]);
+
+        let err = resolve(&source, "app", "1.0.0").await.unwrap_err();
+
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Resolution);
+        assert!(err.to_string().contains("lib-a@1.0.0 -> lib-b@1.0.0 -> lib-a@1.0.0"));
+    }
+
+    #[tokio::test]
+    async fn plan_snapshot_for_shared_dependency() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "2.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "0.1.0")]),
+            sample_package("lib-b", "2.0.0", &[("shared", "0.1.0")]),
+            sample_package("shared", "0.1.0", &[]),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("shared")).unwrap();
+
+        let mut plan = plan(&source, "app", "1.0.0", VersionReason::Latest, dir.path(), None).await.unwrap();
+        plan.record_size("app", Some(2048));
+        plan.record_size("lib-a", Some(1024));
+        assert_eq!(plan.total_size, None);
+        plan.record_size("lib-b", Some(1024));
+        plan.record_size("shared", Some(512));
+
+        let cid = "Qm".to_string() + &"a".repeat(44);
+        let dir_of = |name: &str| dir.path().join(name).display().to_string();
+        let json = serde_json::to_value(&plan).unwrap();
+        assert_eq!(json["root"], "app@1.0.0");
+        assert_eq!(json["total_size"], 4608);
+        assert_eq!(json["packages"], serde_json::json!([
+            {"name": "app", "version": "1.0.0", "ipfs_cid": cid, "reason": {"kind": "latest"}, "size": 2048, "install_dir": dir_of("app")},
+            {"name": "lib-a", "version": "1.0.0", "ipfs_cid": cid, "reason": {"kind": "dependency", "required_by": "app@1.0.0"}, "size": 1024, "install_dir": dir_of("lib-a")},
+            {"name": "shared", "version": "0.1.0", "ipfs_cid": cid, "reason": {"kind": "dependency", "required_by": "lib-a@1.0.0"}, "size": 512, "install_dir": dir_of("shared")},
+            {"name": "lib-b", "version": "2.0.0", "ipfs_cid": cid, "reason": {"kind": "dependency", "required_by": "app@1.0.0"}, "size": 1024, "install_dir": dir_of("lib-b")},
+        ]));
+        assert_eq!(json["changes"], serde_json::json!([
+            {"action": "create", "path": dir_of("app")},
+            {"action": "create", "path": dir_of("lib-a")},
+            {"action": "replace", "path": dir_of("shared")},
+            {"action": "create", "path": dir_of("lib-b")},
+        ]));
+        assert!(json.get("failure").is_none());
+
+        let text = plan.render();
+        assert!(text.contains(&"Total download: 4.5 KB (4 packages)".to_string()));
+        assert!(text.iter().any(|l| l.contains("required by lib-a@1.0.0")));
+    }
+
+    #[tokio::test]
+    async fn plan_explains_conflict() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "1.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "1.0.0")]),
+            sample_package("lib-b", "1.0.0", &[("shared", "2.0.0")]),
+            sample_package("shared", "1.0.0", &[]),
+            sample_package("shared", "2.0.0", &[]),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+
+        let plan = plan(&source, "app", "1.0.0", VersionReason::Requested, dir.path(), None).await.unwrap();
+
+        assert!(plan.packages.is_empty());
+        assert_eq!(plan.render(), vec![
+            "Plan for app@1.0.0".to_string(),
+            "✗ Cannot install (resolution):".to_string(),
+            "  Version conflict for shared:".to_string(),
+            "    app@1.0.0 -> lib-a@1.0.0 -> shared@1.0.0".to_string(),
+            "    app@1.0.0 -> lib-b@1.0.0 -> shared@2.0.0".to_string(),
+        ]);
+        let err = plan.failure.unwrap().into_error();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Resolution);
+    }
+
+    #[tokio::test]
+    async fn plan_lists_lockfile_changes() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.1.0", &[("lib-a", "1.0.0")]),
+            sample_package("lib-a", "1.0.0", &[]),
+        ]);
+        let mut lock = LockFile::new();
+        for (name, version) in [("app", "1.0.0"), ("lib-a", "1.0.0")] {
+            lock.upsert(LockedPackage {
+                name: name.to_string(),
+                version: version.to_string(),
+                ipfs_cid: "Qm".to_string() + &"a".repeat(44),
+                sha256: "00".repeat(32),
+                files_sha256: None,
+                authority: None,
+                dependencies: Vec::new(),
+                requirement: None,
+                from_cache: false,
+            });
+        }
+        let dir = tempfile::tempdir().unwrap();
+
+        let plan = plan(&source, "app", "1.1.0", VersionReason::Requested, dir.path(), Some(&lock)).await.unwrap();
+
+        assert_eq!(plan.lock_changes, vec![LockChange {
+            name: "app".to_string(),
+            from: Some("1.0.0".to_string()),
+            to: "1.1.0".to_string(),
+        }]);
+        assert!(plan.render().contains(&"  lock app 1.0.0 -> 1.1.0".to_string()));
+        assert_eq!(plan.warnings, vec!["app 1.0.0 -> 1.1.0 adds new dependency lib-a".to_string()]);
+        assert!(plan.render().contains(&"  ⚠ app 1.0.0 -> 1.1.0 adds new dependency lib-a".to_string()));
+    }
+
+    #[tokio::test]
+    async fn locked_plan_rejects_changed_cid() {
+        let mut changed = sample_package("app", "1.0.0", &[]);
+        changed.ipfs_cid = "Qm".to_string() + &"b".repeat(44);
+        let source = MockSource::new(vec![changed]);
+        let mut lock = LockFile::new();
+        lock.upsert(LockedPackage {
+            name: "app".to_string(),
+            version: "1.0.0".to_string(),
+            ipfs_cid: "Qm".to_string() + &"a".repeat(44),
+            sha256: "00".repeat(32),
+            files_sha256: None,
+            authority: None,
+            dependencies: Vec::new(),
+            requirement: None,
+            from_cache: false,
+        });
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = plan_locked(&source, &lock, dir.path()).await.unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Verification);
+        assert!(err.to_string().contains("no longer matches antsol.lock"));
+
+        let source = MockSource::new(vec![sample_package("app", "1.0.0", &[])]);
+        let plan = plan_locked(&source, &lock, dir.path()).await.unwrap();
+        assert_eq!(plan.packages[0].reason, VersionReason::Locked);
+        assert_eq!(plan.packages[0].sha256.as_deref(), Some("00".repeat(32).as_str()));
+    }
+
+    fn select(spec: &str, versions: &[&str]) -> Option<String> {
+        VersionSpec::parse(spec).unwrap().select(versions.iter().copied())
+    }
+
+    #[test]
+    fn full_versions_are_exact_and_everything_else_is_a_range() {
+        assert_eq!(VersionSpec::parse("1.2.3").unwrap(), VersionSpec::Exact("1.2.3".to_string()));
+        assert_eq!(VersionSpec::parse("1.3.0-beta.1").unwrap(), VersionSpec::Exact("1.3.0-beta.1".to_string()));
+        assert!(matches!(VersionSpec::parse("^1.2").unwrap(), VersionSpec::Range(_)));
+        assert!(matches!(VersionSpec::parse(">=2, <3").unwrap(), VersionSpec::Range(_)));
+
+        let err = VersionSpec::parse("one point two").unwrap_err();
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::Usage);
+    }
+
+    #[test]
+    fn ranges_pick_highest_match() {
+        let versions = ["0.3.1", "0.3.9", "0.4.0", "1.2.0", "1.9.3", "2.0.0", "2.5.1", "3.0.0"];
+        assert_eq!(select("^1.2", &versions).as_deref(), Some("1.9.3"));
+        assert_eq!(select("~0.3.1", &versions).as_deref(), Some("0.3.9"));
+        assert_eq!(select(">=2, <3", &versions).as_deref(), Some("2.5.1"));
+        assert_eq!(select("^4", &versions), None);
+        assert_eq!(select("1.2.0", &versions).as_deref(), Some("1.2.0"));
+        assert_eq!(select("1.2.1", &versions), None);
+    }
+
+    #[test]
+    fn wildcards_match_any_release_in_scope() {
+        let versions = ["1.0.0", "1.4.2", "2.0.0-rc.1", "2.1.0", "not-a-version"];
+        assert_eq!(select("*", &versions).as_deref(), Some("2.1.0"));
+        assert_eq!(select("1.*", &versions).as_deref(), Some("1.4.2"));
+        assert_eq!(select("1.x", &versions).as_deref(), Some("1.4.2"));
+        assert_eq!(select("2.0.*", &versions), None);
+    }
+
+    #[test]
+    fn pre_releases_only_match_when_the_range_names_one() {
+        let versions = ["1.2.0", "1.3.0-alpha.1", "1.3.0-beta.2", "2.0.0-rc.1"];
+        assert_eq!(select("^1.2", &versions).as_deref(), Some("1.2.0"));
+        assert_eq!(select(">=1.3.0-alpha.1", &versions).as_deref(), Some("1.3.0-beta.2"));
+        assert_eq!(select("^2.0.0-rc.0", &versions).as_deref(), Some("2.0.0-rc.1"));
+        assert_eq!(select("*", &["2.0.0-rc.1"]), None);
+    }
+
+    #[tokio::test]
+    async fn missing_dependency_names_requirement_chain() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0")]),
+        ]);
+
+        let err = resolve(&source, "app", "1.0.0").await.unwrap_err();
+
+        assert_eq!(error_kind(err.as_ref()), ErrorKind::NotFound);
+        assert!(err.to_string().contains("lib-a@1.0.0 not found on-chain (required by app@1.0.0)"));
+    }
+}
@@ -0,0 +1,487 @@
+use crate::error::AntSolError;
+use crate::types::Result;
+use serde::Serialize;
+use solana_client::rpc_client::RpcClient;
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    account::Account,
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Minimal RPC surface used by the CLI.
+///
+/// Everything that talks to a Solana node goes through this trait so that calls
+/// can be counted, budgeted, and replaced by an in-memory mock in tests.
+pub trait RpcApi: Send + Sync {
+    /// Fetch an account, returning `None` when it does not exist
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>>;
+
+    /// Fetch a recent blockhash for signing transactions
+    fn get_latest_blockhash(&self) -> Result<Hash>;
+
+    /// Hash of the cluster's genesis block, which tells mainnet, devnet and testnet apart
+    fn get_genesis_hash(&self) -> Result<Hash>;
+
+    /// Submit a signed transaction without waiting for it to land
+    fn send_transaction(&self, transaction: &Transaction, skip_preflight: bool) -> Result<Signature>;
+
+    /// Outcome of a sent transaction once it reaches `commitment`; `None` while it hasn't
+    fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<std::result::Result<(), TransactionError>>>;
+
+    /// Whether transactions signed with `blockhash` can still land
+    fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> Result<bool>;
+
+    /// Simulate a transaction without needing its signatures
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation>;
+
+    /// Lamports an account of `data_len` bytes needs to be rent exempt
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64>;
+
+    /// Lamport balance of an account (0 when it doesn't exist)
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64>;
+
+    /// Ask the cluster's faucet for `lamports`; only devnet/testnet/local validators have one
+    fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature>;
+
+    /// Whether a transaction has reached the client's commitment level
+    fn confirm_transaction(&self, signature: &Signature) -> Result<bool>;
+
+    /// Per-slot minimum priority fees (microlamports per compute unit) recently
+    /// paid by transactions writing to `accounts`
+    fn get_recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>>;
+
+    /// Every account owned by `program_id` whose data starts with `prefix`.
+    /// One call, but the node scans the whole program, so it is slow and some
+    /// providers rate limit or disable it.
+    fn get_program_accounts(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<(Pubkey, Account)>>;
+
+    /// Like [`get_program_accounts`](Self::get_program_accounts), but only the
+    /// addresses come back, so the response stays small however many match
+    fn get_program_account_keys(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<Pubkey>>;
+
+    /// Fetch several accounts in one call (the RPC caps a call at
+    /// [`MAX_MULTIPLE_ACCOUNTS`]); missing accounts are `None`
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>>;
+}
+
+/// What a `simulateTransaction` call reports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Simulation {
+    /// Why the transaction would fail, if it would
+    pub err: Option<TransactionError>,
+    pub logs: Option<Vec<String>>,
+    pub units_consumed: Option<u64>,
+}
+
+/// Most accounts a node returns from one `getMultipleAccounts` call
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+
+fn prefix_filter(prefix: &[u8]) -> Option<Vec<RpcFilterType>> {
+    Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, prefix.to_vec()))])
+}
+
+/// Client errors surface as [`AntSolError::Rpc`]
+impl RpcApi for RpcClient {
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self.get_account_with_commitment(pubkey, self.commitment()).map_err(AntSolError::from)?.value)
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash> {
+        Ok(RpcClient::get_latest_blockhash(self).map_err(AntSolError::from)?)
+    }
+
+    fn get_genesis_hash(&self) -> Result<Hash> {
+        Ok(RpcClient::get_genesis_hash(self).map_err(AntSolError::from)?)
+    }
+
+    fn send_transaction(&self, transaction: &Transaction, skip_preflight: bool) -> Result<Signature> {
+        let config = RpcSendTransactionConfig {
+            skip_preflight,
+            preflight_commitment: Some(self.commitment().commitment),
+            ..RpcSendTransactionConfig::default()
+        };
+        Ok(RpcClient::send_transaction_with_config(self, transaction, config).map_err(AntSolError::from)?)
+    }
+
+    fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<std::result::Result<(), TransactionError>>> {
+        Ok(RpcClient::get_signature_status_with_commitment(self, signature, commitment).map_err(AntSolError::from)?)
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> Result<bool> {
+        Ok(RpcClient::is_blockhash_valid(self, blockhash, commitment).map_err(AntSolError::from)?)
+    }
+
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation> {
+        let result = RpcClient::simulate_transaction(self, transaction).map_err(AntSolError::from)?.value;
+        Ok(Simulation { err: result.err, logs: result.logs, units_consumed: result.units_consumed })
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        Ok(RpcClient::get_minimum_balance_for_rent_exemption(self, data_len).map_err(AntSolError::from)?)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        Ok(RpcClient::get_balance(self, pubkey).map_err(AntSolError::from)?)
+    }
+
+    fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+        Ok(RpcClient::request_airdrop(self, pubkey, lamports).map_err(AntSolError::from)?)
+    }
+
+    fn confirm_transaction(&self, signature: &Signature) -> Result<bool> {
+        Ok(RpcClient::confirm_transaction(self, signature).map_err(AntSolError::from)?)
+    }
+
+    fn get_recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        let fees = RpcClient::get_recent_prioritization_fees(self, accounts).map_err(AntSolError::from)?;
+        Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    fn get_program_accounts(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: prefix_filter(prefix),
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(RpcClient::get_program_accounts_with_config(self, program_id, config).map_err(AntSolError::from)?)
+    }
+
+    fn get_program_account_keys(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<Pubkey>> {
+        let config = RpcProgramAccountsConfig {
+            filters: prefix_filter(prefix),
+            account_config: RpcAccountInfoConfig {
+                data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        let accounts = RpcClient::get_program_accounts_with_config(self, program_id, config).map_err(AntSolError::from)?;
+        Ok(accounts.into_iter().map(|(pubkey, _)| pubkey).collect())
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        Ok(RpcClient::get_multiple_accounts(self, pubkeys).map_err(AntSolError::from)?)
+    }
+}
+
+/// Serializable snapshot of RPC usage for JSON output
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct RpcUsageReport {
+    pub total: u64,
+    pub by_method: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rpc_calls: Option<u64>,
+}
+
+/// Per-invocation tally of RPC calls with an optional budget
+#[derive(Debug, Default)]
+pub struct RpcUsage {
+    counts: Mutex<BTreeMap<&'static str, u64>>,
+    budget: Mutex<Option<u64>>,
+}
+
+impl RpcUsage {
+    pub fn new(budget: Option<u64>) -> Self {
+        Self {
+            counts: Mutex::new(BTreeMap::new()),
+            budget: Mutex::new(budget),
+        }
+    }
+
+    /// Set (or clear) the maximum number of RPC calls for this invocation
+    pub fn set_budget(&self, budget: Option<u64>) {
+        *self.budget.lock().unwrap() = budget;
+    }
+
+    /// Total number of calls made so far
+    pub fn total(&self) -> u64 {
+        self.counts.lock().unwrap().values().sum()
+    }
+
+    /// Number of calls made to a single RPC method
+    #[cfg(test)]
+    pub fn count(&self, method: &str) -> u64 {
+        self.counts.lock().unwrap().get(method).copied().unwrap_or(0)
+    }
+
+    /// Fail early if `expected` more calls would exceed the configured budget.
+    /// Resolution-heavy commands call this before starting so they abort
+    /// instead of running out of budget halfway through.
+    pub fn ensure_budget(&self, expected: u64) -> Result<()> {
+        if let Some(max) = *self.budget.lock().unwrap() {
+            let total = self.total();
+            if total + expected > max {
+                return Err(AntSolError::RpcBudget(format!(
+                    "RPC budget exceeded: this command needs about {} more call(s) but only {} of max_rpc_calls = {} remain. \
+                     Use the indexer-backed path (e.g. 'antsol search') or raise max_rpc_calls in ~/.antsol/config.toml",
+                    expected,
+                    max.saturating_sub(total),
+                    max
+                )).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a call to `method`, enforcing the budget
+    fn record(&self, method: &'static str) -> Result<()> {
+        self.ensure_budget(1)?;
+        tracing::debug!("RPC {}", method);
+        *self.counts.lock().unwrap().entry(method).or_insert(0) += 1;
+        Ok(())
+    }
+
+    /// One-line human summary, e.g. "RPC: 3 getAccountInfo, 1 sendTransaction"
+    pub fn summary(&self) -> Option<String> {
+        let counts = self.counts.lock().unwrap();
+        if counts.is_empty() {
+            return None;
+        }
+        let parts: Vec<String> = counts
+            .iter()
+            .map(|(method, n)| format!("{} {}", n, method))
+            .collect();
+        Some(format!("RPC: {}", parts.join(", ")))
+    }
+
+    /// Snapshot of the current counts for structured output
+    pub fn report(&self) -> RpcUsageReport {
+        let counts = self.counts.lock().unwrap();
+        RpcUsageReport {
+            total: counts.values().sum(),
+            by_method: counts.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            max_rpc_calls: *self.budget.lock().unwrap(),
+        }
+    }
+}
+
+/// Process-wide usage shared by every client created during this invocation
+pub fn global_usage() -> Arc<RpcUsage> {
+    static USAGE: OnceLock<Arc<RpcUsage>> = OnceLock::new();
+    USAGE.get_or_init(|| Arc::new(RpcUsage::new(None))).clone()
+}
+
+/// RPC wrapper that tallies every call by method name
+pub struct CountingRpc {
+    inner: Box<dyn RpcApi>,
+    usage: Arc<RpcUsage>,
+}
+
+impl CountingRpc {
+    pub fn new(inner: Box<dyn RpcApi>, usage: Arc<RpcUsage>) -> Self {
+        Self { inner, usage }
+    }
+
+    pub fn usage(&self) -> &Arc<RpcUsage> {
+        &self.usage
+    }
+}
+
+impl RpcApi for CountingRpc {
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        self.usage.record("getAccountInfo")?;
+        self.inner.get_account(pubkey)
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash> {
+        self.usage.record("getLatestBlockhash")?;
+        self.inner.get_latest_blockhash()
+    }
+
+    fn get_genesis_hash(&self) -> Result<Hash> {
+        self.usage.record("getGenesisHash")?;
+        self.inner.get_genesis_hash()
+    }
+
+    fn send_transaction(&self, transaction: &Transaction, skip_preflight: bool) -> Result<Signature> {
+        self.usage.record("sendTransaction")?;
+        self.inner.send_transaction(transaction, skip_preflight)
+    }
+
+    fn signature_status(
+        &self,
+        signature: &Signature,
+        commitment: CommitmentConfig,
+    ) -> Result<Option<std::result::Result<(), TransactionError>>> {
+        self.usage.record("getSignatureStatuses")?;
+        self.inner.signature_status(signature, commitment)
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> Result<bool> {
+        self.usage.record("isBlockhashValid")?;
+        self.inner.is_blockhash_valid(blockhash, commitment)
+    }
+
+    fn simulate_transaction(&self, transaction: &Transaction) -> Result<Simulation> {
+        self.usage.record("simulateTransaction")?;
+        self.inner.simulate_transaction(transaction)
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        self.usage.record("getMinimumBalanceForRentExemption")?;
+        self.inner.get_minimum_balance_for_rent_exemption(data_len)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.usage.record("getBalance")?;
+        self.inner.get_balance(pubkey)
+    }
+
+    fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+        self.usage.record("requestAirdrop")?;
+        self.inner.request_airdrop(pubkey, lamports)
+    }
+
+    fn confirm_transaction(&self, signature: &Signature) -> Result<bool> {
+        self.usage.record("getSignatureStatuses")?;
+        self.inner.confirm_transaction(signature)
+    }
+
+    fn get_recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>> {
+        self.usage.record("getRecentPrioritizationFees")?;
+        self.inner.get_recent_prioritization_fees(accounts)
+    }
+
+    fn get_program_accounts(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<(Pubkey, Account)>> {
+        self.usage.record("getProgramAccounts")?;
+        self.inner.get_program_accounts(program_id, prefix)
+    }
+
+    fn get_program_account_keys(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<Pubkey>> {
+        self.usage.record("getProgramAccounts")?;
+        self.inner.get_program_account_keys(program_id, prefix)
+    }
+
+    fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+        self.usage.record("getMultipleAccounts")?;
+        self.inner.get_multiple_accounts(pubkeys)
+    }
+}
+
+/// In-memory RPC backend for tests
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::collections::{HashMap, VecDeque};
+
+    #[derive(Default)]
+    pub struct MockRpc {
+        pub accounts: Mutex<HashMap<Pubkey, Account>>,
+        /// Transactions that were sent successfully; shared so tests can keep a handle
+        pub sent: Arc<Mutex<Vec<Transaction>>>,
+        /// Errors returned by the next sends, in order; a send succeeds once these run out
+        pub send_errors: Mutex<VecDeque<String>>,
+        /// What `getRecentPrioritizationFees` reports
+        pub prioritization_fees: Mutex<Vec<u64>>,
+        /// What `getGenesisHash` reports
+        pub genesis_hash: Hash,
+        /// What `simulateTransaction` reports
+        pub simulation: Mutex<Simulation>,
+    }
+
+    impl MockRpc {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn insert_account(&self, pubkey: Pubkey, account: Account) {
+            self.accounts.lock().unwrap().insert(pubkey, account);
+        }
+    }
+
+    impl RpcApi for MockRpc {
+        fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+            Ok(self.accounts.lock().unwrap().get(pubkey).cloned())
+        }
+
+        fn get_latest_blockhash(&self) -> Result<Hash> {
+            Ok(Hash::default())
+        }
+
+        fn get_genesis_hash(&self) -> Result<Hash> {
+            Ok(self.genesis_hash)
+        }
+
+        fn send_transaction(&self, transaction: &Transaction, _skip_preflight: bool) -> Result<Signature> {
+            if let Some(message) = self.send_errors.lock().unwrap().pop_front() {
+                return Err(message.into());
+            }
+            self.sent.lock().unwrap().push(transaction.clone());
+            Ok(transaction.signatures.first().copied().unwrap_or_default())
+        }
+
+        fn signature_status(
+            &self,
+            signature: &Signature,
+            _commitment: CommitmentConfig,
+        ) -> Result<Option<std::result::Result<(), TransactionError>>> {
+            let landed = self.sent.lock().unwrap().iter().any(|tx| tx.signatures.first() == Some(signature));
+            Ok(landed.then_some(Ok(())))
+        }
+
+        fn is_blockhash_valid(&self, _blockhash: &Hash, _commitment: CommitmentConfig) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn simulate_transaction(&self, _transaction: &Transaction) -> Result<Simulation> {
+            Ok(self.simulation.lock().unwrap().clone())
+        }
+
+        fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+            Ok(solana_sdk::rent::Rent::default().minimum_balance(data_len))
+        }
+
+        fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+            Ok(self.accounts.lock().unwrap().get(pubkey).map_or(0, |a| a.lamports))
+        }
+
+        fn request_airdrop(&self, pubkey: &Pubkey, lamports: u64) -> Result<Signature> {
+            self.accounts.lock().unwrap().entry(*pubkey).or_default().lamports += lamports;
+            Ok(Signature::new_unique())
+        }
+
+        fn confirm_transaction(&self, _signature: &Signature) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn get_recent_prioritization_fees(&self, _accounts: &[Pubkey]) -> Result<Vec<u64>> {
+            Ok(self.prioritization_fees.lock().unwrap().clone())
+        }
+
+        fn get_program_accounts(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<(Pubkey, Account)>> {
+            let accounts = self.accounts.lock().unwrap();
+            Ok(accounts
+                .iter()
+                .filter(|(_, account)| account.owner == *program_id && account.data.starts_with(prefix))
+                .map(|(pubkey, account)| (*pubkey, account.clone()))
+                .collect())
+        }
+
+        fn get_program_account_keys(&self, program_id: &Pubkey, prefix: &[u8]) -> Result<Vec<Pubkey>> {
+            Ok(self.get_program_accounts(program_id, prefix)?.into_iter().map(|(pubkey, _)| pubkey).collect())
+        }
+
+        fn get_multiple_accounts(&self, pubkeys: &[Pubkey]) -> Result<Vec<Option<Account>>> {
+            if pubkeys.len() > MAX_MULTIPLE_ACCOUNTS {
+                return Err(format!("Too many accounts requested: {}", pubkeys.len()).into());
+            }
+            let accounts = self.accounts.lock().unwrap();
+            Ok(pubkeys.iter().map(|pubkey| accounts.get(pubkey).cloned()).collect())
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::permissions::{self, SecretFile};
+use crate::types::Result;
+use crate::utils::{load_keypair, print_status, write_secret_file};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::consts::U12;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use bip39::{Language, Mnemonic, Seed};
+use serde::{Deserialize, Serialize};
+use solana_remote_wallet::locator::Locator;
+use solana_remote_wallet::remote_keypair::{generate_remote_keypair, RemoteKeypair};
+use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
+use solana_sdk::derivation_path::DerivationPath;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed_and_derivation_path, Keypair, Signature, Signer, SignerError};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// `wallet_path` prefix of a wallet derived from a seed phrase asked for on each use
+pub const SEED_PHRASE_SCHEME: &str = "prompt:";
+/// `wallet_path` prefix of a Ledger hardware wallet
+pub const LEDGER_SCHEME: &str = "usb:";
+/// Account and change of `m/44'/501'/0'/0'`, where Solana wallets derive a
+/// seed phrase's first address
+const SEED_PHRASE_KEY: &str = "0/0";
+/// PBKDF2 rounds for the key of a cached seed-phrase keypair
+const CACHE_KDF_ROUNDS: u32 = 600_000;
+
+/// What `wallet_path` points at, by its format
+#[derive(Debug)]
+pub enum Wallet {
+    /// Solana CLI keypair JSON, or a keypair `wallet connect --cache` encrypted
+    File(PathBuf),
+    /// `prompt://?key=0/0`: a BIP39 seed phrase, asked for on each use
+    SeedPhrase(DerivationPath),
+    /// `usb://ledger?key=0/0`, or `usb://ledger/<device pubkey>` for one of several
+    Ledger { locator: String, derivation_path: DerivationPath },
+}
+
+impl Wallet {
+    pub fn parse(wallet: &Path) -> Result<Self> {
+        let text = wallet.to_string_lossy();
+        if !is_wallet_uri(&text) {
+            return Ok(Wallet::File(wallet.to_path_buf()));
+        }
+        let (base, query) = text.split_once('?').unwrap_or((&text, ""));
+        let key = query.split('&').find_map(|param| param.strip_prefix("key="));
+        let derivation_path = |default: Option<&str>| -> Result<DerivationPath> {
+            match key.or(default) {
+                Some(key) => DerivationPath::from_key_str(key)
+                    .map_err(|e| AntSolError::Config(format!("Invalid derivation path '{}' in {}: {}", key, text, e)).into()),
+                None => Ok(DerivationPath::default()),
+            }
+        };
+        if text.starts_with(SEED_PHRASE_SCHEME) {
+            Ok(Wallet::SeedPhrase(derivation_path(Some(SEED_PHRASE_KEY))?))
+        } else {
+            Locator::new_from_path(base).map_err(|e| AntSolError::Config(format!("Invalid Ledger wallet {}: {}", text, e)))?;
+            Ok(Wallet::Ledger { locator: base.to_string(), derivation_path: derivation_path(None)? })
+        }
+    }
+}
+
+impl fmt::Display for Wallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Wallet::File(path) => write!(f, "{}", path.display()),
+            Wallet::SeedPhrase(path) => write!(f, "seed phrase at {:?}, asked for on each use", path),
+            Wallet::Ledger { locator, derivation_path } => write!(f, "Ledger {} at {:?}", locator, derivation_path),
+        }
+    }
+}
+
+/// Whether `wallet_path` is a seed phrase or Ledger URI rather than a file
+pub fn is_wallet_uri(wallet: &str) -> bool {
+    wallet.starts_with(SEED_PHRASE_SCHEME) || wallet.starts_with(LEDGER_SCHEME)
+}
+
+/// The configured wallet, ready to sign. Seed phrases and cached keypairs
+/// prompt for their secret; a Ledger is looked up over USB.
+pub fn load_signer(config: &Config) -> Result<Box<dyn Signer>> {
+    let wallet = config.wallet_path.as_ref().ok_or(AntSolError::WalletNotConnected)?;
+    wallet_signer(wallet)
+}
+
+pub fn wallet_signer(wallet: &Path) -> Result<Box<dyn Signer>> {
+    Ok(match Wallet::parse(wallet)? {
+        Wallet::File(path) => match read_cached(&path)? {
+            Some(cached) => Box::new(cached.unlock(&prompt_secret(&format!("Password for {}: ", path.display()))?)?),
+            None => Box::new(load_keypair(&path)?),
+        },
+        Wallet::SeedPhrase(derivation_path) => Box::new(prompt_seed_phrase(&derivation_path)?),
+        Wallet::Ledger { locator, derivation_path } => Box::new(ledger_signer(&locator, derivation_path)?),
+    })
+}
+
+/// The configured wallet's address, asking for a seed phrase only when
+/// there is no other way to know it
+pub fn wallet_pubkey(config: &Config) -> Result<Pubkey> {
+    let wallet = config.wallet_path.as_ref().ok_or(AntSolError::WalletNotConnected)?;
+    match known_pubkey(wallet)? {
+        Some(pubkey) => Ok(pubkey),
+        None => Ok(wallet_signer(wallet)?.pubkey()),
+    }
+}
+
+/// The wallet's address without prompting for anything; `None` for a seed
+/// phrase, whose address is only known once it is entered
+pub fn known_pubkey(wallet: &Path) -> Result<Option<Pubkey>> {
+    match Wallet::parse(wallet)? {
+        Wallet::File(path) => match read_cached(&path)? {
+            Some(cached) => Ok(Some(cached.pubkey()?)),
+            None => Ok(Some(load_keypair(&path)?.pubkey())),
+        },
+        Wallet::SeedPhrase(_) => Ok(None),
+        Wallet::Ledger { locator, derivation_path } => Ok(Some(ledger_signer(&locator, derivation_path)?.pubkey())),
+    }
+}
+
+/// Derive the keypair at `derivation_path` from a BIP39 seed phrase and
+/// optional passphrase, as Solana wallets do
+pub fn keypair_from_seed_phrase(phrase: &str, passphrase: &str, derivation_path: &DerivationPath) -> Result<Keypair> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    let mnemonic = Mnemonic::from_phrase(&phrase, Language::English)
+        .map_err(|e| AntSolError::Wallet(format!("Invalid seed phrase: {}", e)))?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path.clone()))
+        .map_err(|e| AntSolError::Wallet(format!("Could not derive a keypair from the seed phrase: {}", e)).into())
+}
+
+/// Ask for a seed phrase and its passphrase without echoing them
+pub fn prompt_seed_phrase(derivation_path: &DerivationPath) -> Result<Keypair> {
+    let phrase = prompt_secret("Seed phrase: ")?;
+    let passphrase = prompt_secret("BIP39 passphrase (empty for none): ")?;
+    keypair_from_seed_phrase(&phrase, &passphrase, derivation_path)
+}
+
+/// Encrypt `keypair` under a password asked for twice and write it to
+/// `path`, owner-only, for `wallet connect --cache`
+pub fn cache_keypair(keypair: &Keypair, path: &Path) -> Result<()> {
+    let password = prompt_secret("Password for the cached wallet: ")?;
+    if password.is_empty() {
+        return Err(AntSolError::Usage("The cached wallet needs a password".into()).into());
+    }
+    if prompt_secret("Repeat the password: ")? != password {
+        return Err(AntSolError::Usage("The passwords don't match".into()).into());
+    }
+    let cached = CachedWallet::seal(keypair, &password, CACHE_KDF_ROUNDS)?;
+    write_secret_file(path, serde_json::to_string_pretty(&cached)?.as_bytes())
+}
+
+fn prompt_secret(prompt: &str) -> Result<String> {
+    rpassword::prompt_password(prompt)
+        .map_err(|e| AntSolError::Wallet(format!("Could not read from the terminal: {}", e)).into())
+}
+
+/// A keypair encrypted with AES-256-GCM under a PBKDF2-HMAC-SHA256 key.
+/// The address is stored in the clear so it can be shown without the password.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedWallet {
+    pubkey: String,
+    kdf_rounds: u32,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl CachedWallet {
+    fn seal(keypair: &Keypair, password: &str, kdf_rounds: u32) -> Result<Self> {
+        let mut salt = vec![0u8; 16];
+        let mut nonce = vec![0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher(password, &salt, kdf_rounds)
+            .encrypt(&nonce_of(&nonce)?, keypair.to_bytes().as_slice())
+            .map_err(|_| AntSolError::Wallet("Could not encrypt the keypair".into()))?;
+        Ok(Self { pubkey: keypair.pubkey().to_string(), kdf_rounds, salt, nonce, ciphertext })
+    }
+
+    fn unlock(&self, password: &str) -> Result<Keypair> {
+        let bytes = cipher(password, &self.salt, self.kdf_rounds)
+            .decrypt(&nonce_of(&self.nonce)?, self.ciphertext.as_slice())
+            .map_err(|_| AntSolError::Wallet("Wrong password for the cached wallet".into()))?;
+        Keypair::from_bytes(&bytes).map_err(|e| AntSolError::Wallet(format!("Cached wallet is corrupt: {}", e)).into())
+    }
+
+    fn pubkey(&self) -> Result<Pubkey> {
+        Pubkey::from_str(&self.pubkey)
+            .map_err(|e| AntSolError::Wallet(format!("Cached wallet is corrupt: {}", e)).into())
+    }
+}
+
+fn nonce_of(bytes: &[u8]) -> Result<Nonce<U12>> {
+    let bytes: [u8; 12] = bytes.try_into()
+        .map_err(|_| AntSolError::Wallet("Cached wallet is corrupt: bad nonce".into()))?;
+    Ok(Nonce::from(bytes))
+}
+
+fn cipher(password: &str, salt: &[u8], rounds: u32) -> Aes256Gcm {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password.as_bytes(), salt, rounds, &mut key);
+    Aes256Gcm::new(&key.into())
+}
+
+/// The cached keypair in `path`, or `None` for a plain keypair file
+fn read_cached(path: &Path) -> Result<Option<CachedWallet>> {
+    let Ok(contents) = std::fs::read(path) else {
+        return Ok(None);
+    };
+    if !contents.trim_ascii_start().starts_with(b"{") {
+        return Ok(None);
+    }
+    permissions::check(path, SecretFile::Wallet)?;
+    let cached = serde_json::from_slice(&contents)
+        .map_err(|e| AntSolError::Wallet(format!("Could not parse cached wallet {}: {}", path.display(), e)))?;
+    Ok(Some(cached))
+}
+
+/// Signs on a Ledger, telling the user to approve each signature on the device
+struct LedgerSigner(RemoteKeypair);
+
+impl Signer for LedgerSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        self.0.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        print_status(format!("🔐 Approve the transaction on your Ledger ({})...", self.0.path));
+        self.0.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+fn ledger_signer(locator: &str, derivation_path: DerivationPath) -> Result<LedgerSigner> {
+    if !cfg!(feature = "ledger") {
+        return Err(AntSolError::Wallet(
+            "This antsol was built without Ledger support; reinstall it with '--features ledger'".into(),
+        ).into());
+    }
+    let locator = Locator::new_from_path(locator).map_err(|e| AntSolError::Config(format!("Invalid Ledger wallet: {}", e)))?;
+    let manager = maybe_wallet_manager()
+        .map_err(|e| AntSolError::Wallet(format!("Could not open USB devices: {}", e)))?
+        .ok_or_else(|| AntSolError::Wallet("No Ledger found. Connect and unlock it, then open the Solana app".into()))?;
+    let keypair = generate_remote_keypair(locator, derivation_path, &manager, false, "wallet")
+        .map_err(|e| AntSolError::Wallet(format!("Ledger: {}", e)))?;
+    Ok(LedgerSigner(keypair))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn wallet_format_picks_the_signer() {
+        assert!(matches!(Wallet::parse(Path::new("/home/me/wallet.json")).unwrap(), Wallet::File(_)));
+        match Wallet::parse(Path::new("prompt://")).unwrap() {
+            Wallet::SeedPhrase(path) => assert_eq!(path, DerivationPath::new_bip44(Some(0), Some(0))),
+            other => panic!("{:?}", other),
+        }
+        match Wallet::parse(Path::new("prompt://?key=3/0")).unwrap() {
+            Wallet::SeedPhrase(path) => assert_eq!(path, DerivationPath::new_bip44(Some(3), Some(0))),
+            other => panic!("{:?}", other),
+        }
+        match Wallet::parse(Path::new("usb://ledger?key=1")).unwrap() {
+            Wallet::Ledger { locator, derivation_path } => {
+                assert_eq!(locator, "usb://ledger");
+                assert_eq!(derivation_path, DerivationPath::new_bip44(Some(1), None));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(Wallet::parse(Path::new("prompt://?key=x")).is_err());
+        assert!(Wallet::parse(Path::new("usb://trezor")).is_err());
+    }
+
+    #[test]
+    fn seed_phrases_derive_per_path() {
+        let first = keypair_from_seed_phrase(PHRASE, "", &DerivationPath::new_bip44(Some(0), Some(0))).unwrap();
+        let spaced = keypair_from_seed_phrase(&format!("  {}\n", PHRASE.to_uppercase()), "", &DerivationPath::new_bip44(Some(0), Some(0))).unwrap();
+        assert_eq!(first.pubkey(), spaced.pubkey());
+        let second = keypair_from_seed_phrase(PHRASE, "", &DerivationPath::new_bip44(Some(1), Some(0))).unwrap();
+        let protected = keypair_from_seed_phrase(PHRASE, "secret", &DerivationPath::new_bip44(Some(0), Some(0))).unwrap();
+        assert_ne!(first.pubkey(), second.pubkey());
+        assert_ne!(first.pubkey(), protected.pubkey());
+
+        let bad_checksum = PHRASE.replace("about", "abandon");
+        assert!(keypair_from_seed_phrase(&bad_checksum, "", &DerivationPath::default()).is_err());
+    }
+
+    #[test]
+    fn cached_keypair_needs_its_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.enc.json");
+        let keypair = Keypair::new();
+        let cached = CachedWallet::seal(&keypair, "hunter2", 1_000).unwrap();
+        write_secret_file(&path, serde_json::to_string(&cached).unwrap().as_bytes()).unwrap();
+
+        let cached = read_cached(&path).unwrap().expect("cached wallet");
+        assert_eq!(known_pubkey(&path).unwrap(), Some(keypair.pubkey()));
+        assert_eq!(cached.unlock("hunter2").unwrap().to_bytes(), keypair.to_bytes());
+        assert!(cached.unlock("hunter3").is_err());
+        let stored = std::fs::read_to_string(&path).unwrap();
+        assert!(!stored.contains(&serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap()));
+    }
+}
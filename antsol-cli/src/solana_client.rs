@@ -1,19 +1,76 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_client::rpc_client::RpcClient;
 use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
     instruction::{AccountMeta, Instruction},
+    message::Message,
+    native_token::lamports_to_sol,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    hash::Hash,
+    signature::{Signature, Signer},
     system_program,
     transaction::Transaction,
 };
 use std::str::FromStr;
-use crate::types::{Dependency, ExternalDependency, PackageAccount, Result};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use crate::types::{Dependency, ExternalDependency, PackageAccount, PackageMetadata, Result};
 use crate::config::Config;
+use crate::error::AntSolError;
+use crate::idl;
+use crate::package_layout::PackageLayout;
+use crate::rpc::{self, CountingRpc, RpcApi, RpcUsage, Simulation};
+use crate::tx::{
+    classify_error, compute_unit_limit, estimate_priority_fee, failed_instruction, pack_instructions,
+    priority_fee_lamports, FailureKind, PriorityFee, TxSettings, COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT,
+    PRIORITY_FEE_NOTICE_LAMPORTS,
+};
+use crate::utils::{compare_versions, decode_sha256_hex, print_info, print_warning};
+use crate::validation::{check_cids, check_external_dependencies, package_scope};
+
+/// Fee margin added to the package rent when estimating what a publish costs
+pub const PUBLISH_FEE_LAMPORTS: u64 = 10_000;
 
 pub struct AntSolClient {
-    rpc_client: RpcClient,
+    rpc_client: CountingRpc,
     program_id: Pubkey,
+    tx: TxSettings,
+    /// Read from the program's IDL on first use
+    package_layout: OnceLock<PackageLayout>,
+}
+
+/// Result of [`AntSolClient::list_all_packages`]
+#[derive(Debug)]
+pub struct PackageScan {
+    pub packages: Vec<PackageAccount>,
+    /// How many package accounts exist, including any past the cap
+    pub total: usize,
+}
+
+/// One package to publish with [`AntSolClient::publish_package`],
+/// [`AntSolClient::publish_many`] or [`AntSolClient::update_package`]
+#[derive(Debug, Clone)]
+pub struct PublishRequest {
+    pub name: String,
+    pub version: String,
+    pub ipfs_cid: String,
+    pub description: String,
+    pub dependencies: Vec<Dependency>,
+    pub content_sha256: String,
+    pub metadata: PackageMetadata,
+    pub readme_cid: Option<String>,
+    pub external_dependencies: Vec<ExternalDependency>,
+}
+
+/// What became of one request of [`AntSolClient::publish_many`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PublishStatus {
+    /// Landed in the transaction with this signature
+    Published(String),
+    /// Made its transaction fail, for this reason
+    Failed(String),
+    /// Rolled back with another request's failure, or never sent after it
+    NotPublished,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
@@ -22,6 +79,33 @@ pub struct PackageDep {
     pub version: String,
 }
 
+/// A `Namespace` account: who may publish `scope/...` package names
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct NamespaceAccount {
+    pub scope: String,
+    pub owner: Pubkey,
+    /// Wallets besides the owner allowed to publish under the scope
+    pub publishers: Vec<Pubkey>,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl NamespaceAccount {
+    pub fn can_publish(&self, wallet: &Pubkey) -> bool {
+        self.owner == *wallet || self.publishers.contains(wallet)
+    }
+}
+
+/// A `Maintainers` account: wallets allowed to publish new versions of a
+/// package on behalf of one authority
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct MaintainersAccount {
+    pub name: String,
+    pub maintainers: Vec<Pubkey>,
+    pub bump: u8,
+    pub authority: Pubkey,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug)]
 pub struct ExternalDep {
     pub name: String,
@@ -32,15 +116,73 @@ pub struct ExternalDep {
 
 impl AntSolClient {
     pub fn new(config: &Config) -> Result<Self> {
-        let rpc_client = RpcClient::new(config.rpc_url.clone());
         let program_id = Pubkey::from_str(&config.program_id)?;
+        let usage = rpc::global_usage();
+        usage.set_budget(config.max_rpc_calls);
+        tracing::debug!("RPC endpoint {} (program {})", config.rpc_url, program_id);
+        // Reads use the same commitment sends wait for, so a package is
+        // visible as soon as publish reports success
+        let rpc_client = RpcClient::new_with_timeout_and_commitment(
+            config.rpc_url.clone(),
+            std::time::Duration::from_secs(config.rpc_timeout_secs),
+            config.commitment.config(),
+        );
         
-        Ok(Self {
-            rpc_client,
+        Ok(Self::with_rpc(Box::new(rpc_client), program_id, usage).with_tx_settings(TxSettings::from_config(config)))
+    }
+    
+    /// Build a client over any RPC backend (used by tests to inject a mock)
+    pub fn with_rpc(backend: Box<dyn RpcApi>, program_id: Pubkey, usage: Arc<RpcUsage>) -> Self {
+        Self {
+            rpc_client: CountingRpc::new(backend, usage),
             program_id,
+            tx: TxSettings::default(),
+            package_layout: OnceLock::new(),
+        }
+    }
+    
+    /// Replace how transactions are sent, retried and confirmed
+    pub fn with_tx_settings(mut self, tx: TxSettings) -> Self {
+        self.tx = tx;
+        self
+    }
+    
+    /// Decode `Package` accounts with `layout` instead of asking the program's IDL
+    #[cfg(test)]
+    pub fn with_package_layout(self, layout: PackageLayout) -> Self {
+        let _ = self.package_layout.set(layout);
+        self
+    }
+    
+    /// How this deployment lays out `Package` accounts, read once from its
+    /// IDL. Without an IDL account the program in this repository is assumed.
+    fn package_layout(&self) -> &PackageLayout {
+        self.package_layout.get_or_init(|| {
+            let idl = match self.fetch_idl() {
+                Ok(idl) => idl,
+                Err(e) => {
+                    tracing::debug!("{}; assuming the built-in Package layout", e);
+                    return PackageLayout::current();
+                }
+            };
+            match PackageLayout::from_idl(&idl) {
+                Ok(layout) => {
+                    tracing::debug!("Package layout from the program IDL: {}", layout);
+                    layout
+                }
+                Err(e) => {
+                    print_warning(&format!("{}; assuming the built-in Package layout", e));
+                    PackageLayout::current()
+                }
+            }
         })
     }
     
+    /// RPC call tally for this client
+    pub fn rpc_usage(&self) -> &Arc<RpcUsage> {
+        self.rpc_client.usage()
+    }
+    
     pub fn derive_package_pda(&self, name: &str, version: &str) -> (Pubkey, u8) {
         Pubkey::find_program_address(
             &[
@@ -52,156 +194,545 @@ impl AntSolClient {
         )
     }
     
-    pub async fn publish_package(
-        &self,
-        payer: &Keypair,
-        name: String,
-        version: String,
-        ipfs_cid: String,
-        description: String,
-        dependencies: Vec<Dependency>,
-        external_dependencies: Vec<ExternalDependency>,
-    ) -> Result<String> {
-        let (package_pda, _bump) = self.derive_package_pda(&name, &version);
+    /// The maintainer list `authority` keeps for `name`
+    pub fn derive_maintainers_pda(&self, name: &str, authority: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"maintainers", name.as_bytes(), authority.as_ref()], &self.program_id)
+    }
+    
+    pub fn derive_namespace_pda(&self, scope: &str) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"namespace", scope.as_bytes()], &self.program_id)
+    }
+    
+    pub async fn publish_package(&self, payer: &dyn Signer, request: PublishRequest) -> Result<String> {
+        let instruction = self.publish_instruction(&payer.pubkey(), request)?;
         
-        let deps: Vec<PackageDep> = dependencies
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Publish several packages in as few transactions as fit, in order.
+    /// Each transaction is simulated first to size its compute budget. A
+    /// transaction is all or nothing, so when one request fails the rest of
+    /// its transaction is rolled back and later transactions aren't sent.
+    /// Fails before sending anything if a request is invalid or too large.
+    pub async fn publish_many(&self, payer: &dyn Signer, requests: Vec<PublishRequest>) -> Result<Vec<PublishStatus>> {
+        let payer_key = payer.pubkey();
+        let instructions = requests
             .into_iter()
-            .map(|d| PackageDep {
-                name: d.name,
-                version: d.version,
-            })
-            .collect();
+            .map(|request| self.publish_instruction(&payer_key, request))
+            .collect::<Result<Vec<_>>>()?;
+        // Sized with the largest compute budget instructions a batch can carry
+        let widest_budget = [
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+        ];
+        let batches = pack_instructions(&instructions, &widest_budget, &payer_key)?;
+        tracing::debug!("Publishing {} package(s) in {} transaction(s)", instructions.len(), batches.len());
         
-        let ext_deps: Vec<ExternalDep> = external_dependencies
-            .into_iter()
-            .map(|d| ExternalDep {
-                name: d.name,
-                version: d.version,
-                dep_type: d.dep_type,
-                registry: d.registry,
-            })
-            .collect();
+        let mut statuses = vec![PublishStatus::NotPublished; instructions.len()];
+        for batch in batches {
+            let (budget_len, result) = self.send_batch(payer, &instructions[batch.clone()]).await;
+            match result {
+                Ok(signature) => statuses[batch].fill(PublishStatus::Published(signature.to_string())),
+                Err(err) => {
+                    let message = err.to_string();
+                    let blamed = failed_instruction(&message)
+                        .and_then(|index| index.checked_sub(budget_len))
+                        .filter(|offset| *offset < batch.len());
+                    for (offset, status) in statuses[batch].iter_mut().enumerate() {
+                        if blamed.is_none_or(|blamed| blamed == offset) {
+                            *status = PublishStatus::Failed(message.clone());
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(statuses)
+    }
+    
+    /// Simulate `instructions` to size their compute budget, then send them in
+    /// one transaction. Returns how many budget instructions went in front, so
+    /// an error's instruction index can be mapped back to `instructions`.
+    async fn send_batch(&self, payer: &dyn Signer, instructions: &[Instruction]) -> (usize, Result<Signature>) {
+        let price = match self.priority_fee_price(instructions) {
+            Ok(price) => price,
+            Err(e) => return (0, Err(e)),
+        };
+        let with_budget = |units: u32| {
+            let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(units)];
+            if price > 0 {
+                all.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+            }
+            let budget_len = all.len();
+            all.extend_from_slice(instructions);
+            (budget_len, all)
+        };
+        let (budget_len, simulated) = with_budget(MAX_COMPUTE_UNIT_LIMIT);
+        let result = match self.simulate_units(&payer.pubkey(), &simulated) {
+            Ok(units) => {
+                let (_, sized) = with_budget(compute_unit_limit(units));
+                self.send_prepared(payer, &sized).await
+            }
+            Err(err) => Err(err),
+        };
+        (budget_len, result)
+    }
+    
+    /// Compute units `instructions` consume, simulated without signatures so a
+    /// hardware wallet isn't asked to sign twice
+    fn simulate_units(&self, payer: &Pubkey, instructions: &[Instruction]) -> Result<u64> {
+        let blockhash = self.rpc_client.get_latest_blockhash()?;
+        let transaction = Transaction::new_unsigned(Message::new_with_blockhash(instructions, Some(payer), &blockhash));
+        let simulation = self.rpc_client.simulate_transaction(&transaction)?;
+        if let Some(err) = simulation.err {
+            if let Some(logs) = simulation.logs {
+                tracing::debug!("Simulation logs:\n{}", logs.join("\n"));
+            }
+            return Err(AntSolError::Program(format!("Simulation failed: {}", err)).into());
+        }
+        Ok(simulation.units_consumed.unwrap_or(u64::from(MAX_COMPUTE_UNIT_LIMIT)))
+    }
+    
+    /// The `publish_package` instruction for `request`, paid by `payer`
+    fn publish_instruction(&self, payer: &Pubkey, request: PublishRequest) -> Result<Instruction> {
+        check_cid_args(&request.ipfs_cid, request.readme_cid.as_deref())?;
+        check_external_dependency_args(&request.external_dependencies)?;
+        let (package_pda, _bump) = self.derive_package_pda(&request.name, &request.version);
+        // Anchor reads the program ID in an optional account's slot as "not passed"
+        let namespace = match package_scope(&request.name) {
+            Some(scope) => self.derive_namespace_pda(scope).0,
+            None => self.program_id,
+        };
+        let args = PackageArgs {
+            content_sha256: checksum_arg(&request.content_sha256)?,
+            name: request.name,
+            version: request.version,
+            ipfs_cid: request.ipfs_cid,
+            description: request.description,
+            dependencies: package_deps(request.dependencies),
+            metadata: request.metadata,
+            readme_cid: request.readme_cid,
+            external_dependencies: external_deps(request.external_dependencies),
+        };
         
-        let discriminator: [u8; 8] = [244, 240, 208, 233, 198, 38, 46, 197];
-        let args_data = (name, version, ipfs_cid, description, deps, ext_deps).try_to_vec()?;
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new(package_pda, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(namespace, false),
+            ],
+            data: instruction_data(idl::PUBLISH_PACKAGE, &args)?,
+        })
+    }
+    
+    /// Claim `scope` for `payer`, so only it (and publishers it lists) can
+    /// publish `scope/...` names
+    pub async fn register_namespace(&self, payer: &dyn Signer, scope: &str) -> Result<String> {
+        let (namespace_pda, _) = self.derive_namespace_pda(scope);
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(namespace_pda, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: instruction_data(idl::REGISTER_NAMESPACE, &scope.to_string())?,
+        };
+        
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Replace the wallets besides the owner allowed to publish under `scope`
+    pub async fn set_namespace_publishers(&self, payer: &dyn Signer, scope: &str, publishers: Vec<Pubkey>) -> Result<String> {
+        let (namespace_pda, _) = self.derive_namespace_pda(scope);
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new(namespace_pda, false),
+            ],
+            data: instruction_data(idl::SET_NAMESPACE_PUBLISHERS, &(scope.to_string(), publishers))?,
+        };
         
-        let mut data = discriminator.to_vec();
-        data.extend_from_slice(&args_data);
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Publish `request` as a new version of the package at `old_version`,
+    /// signing as a maintainer listed by `maintainer_of`, the authority of
+    /// `old_version`, rather than as that authority
+    pub async fn update_package(&self, payer: &dyn Signer, old_version: &str, request: PublishRequest, maintainer_of: Option<Pubkey>) -> Result<String> {
+        check_cid_args(&request.ipfs_cid, request.readme_cid.as_deref())?;
+        check_external_dependency_args(&request.external_dependencies)?;
+        let (existing_pda, _) = self.derive_package_pda(&request.name, old_version);
+        let (new_pda, _) = self.derive_package_pda(&request.name, &request.version);
+        // Only a maintainer's signature needs the list; otherwise it is left out
+        let maintainers = match maintainer_of {
+            Some(authority) => self.derive_maintainers_pda(&request.name, &authority).0,
+            None => self.program_id,
+        };
+        let args = PackageArgs {
+            content_sha256: checksum_arg(&request.content_sha256)?,
+            name: request.name,
+            version: request.version,
+            ipfs_cid: request.ipfs_cid,
+            description: request.description,
+            dependencies: package_deps(request.dependencies),
+            metadata: request.metadata,
+            readme_cid: request.readme_cid,
+            external_dependencies: external_deps(request.external_dependencies),
+        };
         
         let instruction = Instruction {
             program_id: self.program_id,
             accounts: vec![
                 AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new(package_pda, false),
+                AccountMeta::new_readonly(existing_pda, false),
+                AccountMeta::new(new_pda, false),
                 AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(maintainers, false),
             ],
-            data,
+            data: instruction_data(idl::UPDATE_PACKAGE, &args)?,
         };
         
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&payer.pubkey()),
-            &[payer],
-            recent_blockhash,
-        );
-
-        match self.rpc_client.send_and_confirm_transaction(&transaction) {
-            Ok(sig) => Ok(sig.to_string()),
-            Err(send_err) => {
-                match self.rpc_client.simulate_transaction(&transaction) {
-                    Ok(sim_result) => {
-                        if let Some(logs) = sim_result.value.logs {
-                            let joined = logs.join("\n");
-                            return Err(format!("RPC send error: {}\nSimulation logs:\n{}", send_err, joined).into());
-                        }
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// The registry program's IDL as stored on-chain by `anchor idl init`
+    pub fn fetch_idl(&self) -> Result<serde_json::Value> {
+        let address = idl::idl_address(&self.program_id);
+        let account = self.rpc_client.get_account(&address)?.ok_or_else(|| {
+            AntSolError::NotFound(format!(
+                "No IDL account at {} for program {}; it is created with 'anchor idl init'",
+                address, self.program_id
+            ))
+        })?;
+        idl::decode_idl_account(&account.data)
+    }
+    
+    /// Sign and send `instructions`, then wait for the configured commitment.
+    /// Transient failures (expired blockhash, timeouts, an unhealthy node) are
+    /// retried with a fresh blockhash and backoff; program errors are not.
+    async fn send_with_retry(&self, payer: &dyn Signer, instructions: &[Instruction]) -> Result<Signature> {
+        let instructions = self.with_priority_fee(instructions)?;
+        self.send_prepared(payer, &instructions).await
+    }
+    
+    /// [`send_with_retry`](Self::send_with_retry) for instructions that
+    /// already carry their compute budget
+    async fn send_prepared(&self, payer: &dyn Signer, instructions: &[Instruction]) -> Result<Signature> {
+        let mut sent = Vec::new();
+        let mut retry = 0;
+        loop {
+            let blockhash = self.rpc_client.get_latest_blockhash()?;
+            let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+            // A Ledger can refuse to sign, which `new_signed_with_payer` would panic on
+            transaction.try_sign(&[payer], blockhash)
+                .map_err(|e| AntSolError::Wallet(format!("Could not sign the transaction: {}", e)))?;
+            let result = match self.rpc_client.send_transaction(&transaction, self.tx.skip_preflight) {
+                Ok(signature) => {
+                    sent.push(signature);
+                    self.await_confirmation(&signature, &blockhash).await
+                }
+                Err(err) => Err(err),
+            };
+            let err = match result {
+                Ok(signature) => return Ok(signature),
+                Err(err) => err,
+            };
+            
+            // A submission may land after we gave up on it, and a resend then
+            // fails because the account already exists
+            if let Some(signature) = self.landed(&sent) {
+                return Ok(signature);
+            }
+            if classify_error(err.as_ref()) == FailureKind::Fatal || retry >= self.tx.max_retries {
+                return Err(self.explain_failure(&transaction, err));
+            }
+            
+            retry += 1;
+            let wait = self.tx.backoff_for(retry);
+            print_warning(&format!(
+                "Transaction failed ({}); retry {}/{} in {:.1}s with a fresh blockhash",
+                err,
+                retry,
+                self.tx.max_retries,
+                wait.as_secs_f64()
+            ));
+            tokio::time::sleep(wait).await;
+        }
+    }
+    
+    /// Prepend compute budget instructions when a priority fee applies; with
+    /// no fee the transaction is left exactly as built
+    fn with_priority_fee(&self, instructions: &[Instruction]) -> Result<Vec<Instruction>> {
+        let price = self.priority_fee_price(instructions)?;
+        if price == 0 {
+            return Ok(instructions.to_vec());
+        }
+        let extra = priority_fee_lamports(price, COMPUTE_UNIT_LIMIT);
+        if extra > PRIORITY_FEE_NOTICE_LAMPORTS {
+            print_info(&format!(
+                "Priority fee: {} microlamports per compute unit, up to {} SOL extra",
+                price,
+                lamports_to_sol(extra)
+            ));
+        }
+        let mut with_fee = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT),
+            ComputeBudgetInstruction::set_compute_unit_price(price),
+        ];
+        with_fee.extend_from_slice(instructions);
+        Ok(with_fee)
+    }
+    
+    /// Microlamports per compute unit to pay. `auto` looks at fees recently paid
+    /// for the accounts these instructions write to; if the node can't say, no fee is paid.
+    fn priority_fee_price(&self, instructions: &[Instruction]) -> Result<u64> {
+        match self.tx.priority_fee {
+            PriorityFee::MicroLamports(price) => Ok(price),
+            PriorityFee::Auto => {
+                let mut writable: Vec<Pubkey> = instructions
+                    .iter()
+                    .flat_map(|ix| ix.accounts.iter().filter(|meta| meta.is_writable).map(|meta| meta.pubkey))
+                    .collect();
+                writable.sort();
+                writable.dedup();
+                match self.rpc_client.get_recent_prioritization_fees(&writable) {
+                    Ok(recent) => Ok(estimate_priority_fee(recent)),
+                    Err(e) if matches!(e.downcast_ref::<AntSolError>(), Some(AntSolError::RpcBudget(_))) => Err(e),
+                    Err(e) => {
+                        print_warning(&format!("Could not estimate a priority fee ({}); sending without one", e));
+                        Ok(0)
                     }
-                    Err(_) => {}
                 }
-                Err(send_err.into())
             }
         }
     }
     
-    pub async fn update_package(
-        &self,
-        payer: &Keypair,
-        name: String,
-        old_version: String,
-        new_version: String,
-        ipfs_cid: String,
-        description: String,
-        dependencies: Vec<Dependency>,
-        external_dependencies: Vec<ExternalDependency>,
-    ) -> Result<String> {
-        let (existing_pda, _) = self.derive_package_pda(&name, &old_version);
-        let (new_pda, _) = self.derive_package_pda(&name, &new_version);
-        
-        let deps: Vec<PackageDep> = dependencies
-            .into_iter()
-            .map(|d| PackageDep {
-                name: d.name,
-                version: d.version,
-            })
-            .collect();
-        
-        let ext_deps: Vec<ExternalDep> = external_dependencies
-            .into_iter()
-            .map(|d| ExternalDep {
-                name: d.name,
-                version: d.version,
-                dep_type: d.dep_type,
-                registry: d.registry,
-            })
-            .collect();
-        
-        let discriminator: [u8; 8] = [167, 29, 15, 20, 179, 137, 50, 145];
-        let args_data = (name, new_version, ipfs_cid, description, deps, ext_deps).try_to_vec()?;
+    /// Poll `signature` until it reaches the configured commitment, fails, or
+    /// can no longer land
+    async fn await_confirmation(&self, signature: &Signature, blockhash: &Hash) -> Result<Signature> {
+        let commitment = self.tx.commitment.config();
+        let deadline = Instant::now() + self.tx.confirm_timeout;
+        loop {
+            match self.rpc_client.signature_status(signature, commitment)? {
+                Some(Ok(())) => return Ok(*signature),
+                Some(Err(err)) => {
+                    return Err(AntSolError::Program(format!("Transaction {} failed: {}", signature, err)).into());
+                }
+                None => {}
+            }
+            if !self.rpc_client.is_blockhash_valid(blockhash, commitment)? {
+                return Err(AntSolError::Network(format!(
+                    "Transaction {} expired before reaching {} commitment", signature, self.tx.commitment.as_str()
+                )).into());
+            }
+            if Instant::now() >= deadline {
+                return Err(AntSolError::Network(format!(
+                    "Transaction {} was not confirmed within {}s",
+                    signature,
+                    self.tx.confirm_timeout.as_secs()
+                )).into());
+            }
+            tokio::time::sleep(self.tx.poll_interval).await;
+        }
+    }
+    
+    /// The first of `signatures` that made it on-chain successfully
+    fn landed(&self, signatures: &[Signature]) -> Option<Signature> {
+        let commitment = self.tx.commitment.config();
+        signatures.iter().copied().find(|signature| {
+            matches!(self.rpc_client.signature_status(signature, commitment), Ok(Some(Ok(()))))
+        })
+    }
+    
+    /// Attach the program logs from a simulation to a final failure
+    fn explain_failure(&self, transaction: &Transaction, err: Box<dyn std::error::Error>) -> Box<dyn std::error::Error> {
+        if matches!(err.downcast_ref::<AntSolError>(), Some(AntSolError::RpcBudget(_))) {
+            return err;
+        }
+        match self.rpc_client.simulate_transaction(transaction) {
+            Ok(Simulation { logs: Some(logs), .. }) => {
+                tracing::debug!("Simulation logs:\n{}", logs.join("\n"));
+                AntSolError::Program(format!("RPC send error: {}\nSimulation logs:\n{}", err, logs.join("\n"))).into()
+            }
+            _ => err,
+        }
+    }
+    
+    /// Whether an account already exists at the `name@version` PDA
+    pub fn package_exists(&self, name: &str, version: &str) -> Result<bool> {
+        let (pda, _) = self.derive_package_pda(name, version);
+        Ok(self.rpc_client.get_account(&pda)?.is_some())
+    }
+    
+    /// Lamports needed to make an account of `space` bytes rent exempt
+    pub fn rent_exempt_minimum(&self, space: usize) -> Result<u64> {
+        self.rpc_client.get_minimum_balance_for_rent_exemption(space)
+    }
+    
+    /// Lamport balance of `pubkey`
+    pub fn balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        self.rpc_client.get_balance(pubkey)
+    }
+    
+    /// Estimated cost of creating one package account of `space` bytes: its
+    /// rent plus a fee margin
+    pub fn publish_cost(&self, space: usize) -> u64 {
+        let rent = self.rent_exempt_minimum(space)
+            .unwrap_or_else(|_| solana_sdk::rent::Rent::default().minimum_balance(space));
+        rent + PUBLISH_FEE_LAMPORTS
+    }
+    
+    /// Fail with [`AntSolError::InsufficientFunds`] before anything is uploaded
+    /// or signed when `payer` can't cover a publish creating `space` bytes
+    pub fn ensure_publish_funds(&self, payer: &Pubkey, space: usize) -> Result<()> {
+        let needed = self.publish_cost(space);
+        let available = self.balance(payer)?;
+        if available < needed {
+            return Err(AntSolError::InsufficientFunds { needed, available }.into());
+        }
+        Ok(())
+    }
+    
+    /// Add or remove (`add: false`) a maintainer of `name` on the list of
+    /// `payer`, which must be the authority of `name@version`
+    pub async fn change_maintainer(&self, payer: &dyn Signer, name: &str, version: &str, maintainer: Pubkey, add: bool) -> Result<String> {
+        let (package_pda, _) = self.derive_package_pda(name, version);
+        let (maintainers_pda, _) = self.derive_maintainers_pda(name, &payer.pubkey());
+        let args = (name.to_string(), version.to_string(), maintainer);
+        let instruction = if add {
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new(payer.pubkey(), true),
+                    AccountMeta::new_readonly(package_pda, false),
+                    AccountMeta::new(maintainers_pda, false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ],
+                data: instruction_data(idl::ADD_MAINTAINER, &args)?,
+            }
+        } else {
+            Instruction {
+                program_id: self.program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(payer.pubkey(), true),
+                    AccountMeta::new_readonly(package_pda, false),
+                    AccountMeta::new(maintainers_pda, false),
+                ],
+                data: instruction_data(idl::REMOVE_MAINTAINER, &args)?,
+            }
+        };
         
-        let mut data = discriminator.to_vec();
-        data.extend_from_slice(&args_data);
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Count a download of `name@version` on-chain. Anyone may send it;
+    /// `payer` pays the fee, plus rent for the counter on accounts created
+    /// before it existed.
+    pub async fn record_download(&self, payer: &dyn Signer, name: &str, version: &str) -> Result<String> {
+        let (package_pda, _) = self.derive_package_pda(name, version);
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new(package_pda, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: instruction_data(idl::RECORD_DOWNLOAD, &(name.to_string(), version.to_string()))?,
+        };
         
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Freeze `name@version` for good, signed by its authority, which the
+    /// program burns. `payer` also pays rent for the flag on accounts
+    /// created before it existed.
+    pub async fn freeze_package(&self, payer: &dyn Signer, name: &str, version: &str) -> Result<String> {
+        let (package_pda, _) = self.derive_package_pda(name, version);
         let instruction = Instruction {
             program_id: self.program_id,
             accounts: vec![
                 AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(existing_pda, false),
-                AccountMeta::new(new_pda, false),
+                AccountMeta::new(package_pda, false),
                 AccountMeta::new_readonly(system_program::id(), false),
             ],
-            data,
+            data: instruction_data(idl::FREEZE_PACKAGE, &(name.to_string(), version.to_string()))?,
         };
         
-        let recent_blockhash = self.rpc_client.get_latest_blockhash()?;
-        let transaction = Transaction::new_signed_with_payer(
-            &[instruction],
-            Some(&payer.pubkey()),
-            &[payer],
-            recent_blockhash,
-        );
-
-        match self.rpc_client.send_and_confirm_transaction(&transaction) {
-            Ok(sig) => Ok(sig.to_string()),
-            Err(send_err) => {
-                match self.rpc_client.simulate_transaction(&transaction) {
-                    Ok(sim_result) => {
-                        if let Some(logs) = sim_result.value.logs {
-                            let joined = logs.join("\n");
-                            return Err(format!("RPC send error: {}\nSimulation logs:\n{}", send_err, joined).into());
-                        }
-                    }
-                    Err(_) => {}
-                }
-                Err(send_err.into())
+        Ok(self.send_with_retry(payer, &[instruction]).await?.to_string())
+    }
+    
+    /// Wallets `authority` allows to publish new versions of `name` from
+    /// the versions it owns; empty when none were ever added
+    pub fn get_maintainers(&self, name: &str, authority: &Pubkey) -> Result<Vec<Pubkey>> {
+        let (pda, _) = self.derive_maintainers_pda(name, authority);
+        match self.rpc_client.get_account(&pda)? {
+            Some(account) if account.owner == self.program_id && account.data.len() >= 8 => {
+                Ok(MaintainersAccount::deserialize(&mut &account.data[8..])?.maintainers)
             }
+            _ => Ok(Vec::new()),
         }
     }
     
+    /// The namespace account for `scope`, if it has been registered
+    pub fn get_namespace(&self, scope: &str) -> Result<Option<NamespaceAccount>> {
+        let (pda, _) = self.derive_namespace_pda(scope);
+        match self.rpc_client.get_account(&pda)? {
+            Some(account) if account.owner == self.program_id && account.data.len() >= 8 => {
+                Ok(Some(NamespaceAccount::deserialize(&mut &account.data[8..])?))
+            }
+            _ => Ok(None),
+        }
+    }
+    
+    /// Like [`get_package`](Self::get_package), but a missing account is
+    /// [`AntSolError::PackageNotFound`]
+    pub fn require_package(&self, name: &str, version: &str) -> Result<PackageAccount> {
+        self.get_package(name, version)?.ok_or_else(|| {
+            AntSolError::PackageNotFound { name: name.to_string(), version: Some(version.to_string()) }.into()
+        })
+    }
+    
+    /// Poll until `name@version` can be read back, for at most the confirmation
+    /// timeout. Load-balanced RPC endpoints can answer from a node that is behind
+    /// the one that confirmed the transaction. Returns whether it showed up.
+    pub async fn wait_until_visible(&self, name: &str, version: &str) -> Result<bool> {
+        let deadline = Instant::now() + self.tx.confirm_timeout;
+        loop {
+            if self.get_package(name, version)?.is_some() {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            tokio::time::sleep(self.tx.poll_interval).await;
+        }
+    }
+    
+    /// Request an airdrop and poll until it is confirmed or `timeout` passes
+    pub async fn airdrop(&self, pubkey: &Pubkey, lamports: u64, timeout: std::time::Duration) -> Result<Signature> {
+        let signature = self.rpc_client.request_airdrop(pubkey, lamports)
+            .map_err(|e| AntSolError::Network(format!("Airdrop request failed: {}", e)))?;
+        let deadline = std::time::Instant::now() + timeout;
+        while !self.rpc_client.confirm_transaction(&signature)? {
+            if std::time::Instant::now() >= deadline {
+                return Err(AntSolError::Network(format!(
+                    "Airdrop {} was not confirmed within {}s", signature, timeout.as_secs()
+                )).into());
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        }
+        Ok(signature)
+    }
+    
     pub fn get_package(&self, name: &str, version: &str) -> Result<Option<PackageAccount>> {
         let (pda, _) = self.derive_package_pda(name, version);
         
-        match self.rpc_client.get_account(&pda) {
-            Ok(account) => {
+        match self.rpc_client.get_account(&pda)? {
+            Some(account) => {
                 if account.owner != self.program_id {
                     return Ok(None);
                 }
@@ -210,113 +741,678 @@ impl AntSolClient {
                     return Ok(None);
                 }
                 
-                eprintln!("DEBUG: Account data length: {} bytes", account.data.len());
-                eprintln!("DEBUG: First 16 bytes (discriminator + start): {:?}", &account.data[..16.min(account.data.len())]);
-                
-                let data = &account.data[8..];
-                eprintln!("DEBUG: Deserializing {} bytes after discriminator", data.len());
-                
-                Ok(Some(self.deserialize_package_account(data)?))
+                Ok(Some(self.package_layout().decode(&account.data)?))
             }
-            Err(_) => Ok(None),
+            None => Ok(None),
         }
     }
     
-    fn deserialize_package_account(&self, data: &[u8]) -> Result<PackageAccount> {
-        use borsh::BorshDeserialize;
+    /// Every published version of `name`, found by scanning the program's
+    /// accounts for a `Package` whose name field matches. Much slower than a
+    /// PDA lookup; meant for when the indexer can't list versions.
+    /// Accounts that fail to decode are skipped.
+    pub fn package_versions(&self, name: &str) -> Result<Vec<PackageAccount>> {
+        let mut prefix = idl::account_discriminator(idl::PACKAGE_ACCOUNT).to_vec();
+        prefix.extend_from_slice(&name.to_string().try_to_vec()?);
         
-        // Try new format first (with external_dependencies)
-        #[derive(BorshDeserialize, Debug)]
-        struct AnchorPackageNew {
-            name: String,
-            version: String,
-            authority: Pubkey,
-            ipfs_cid: String,
-            published_at: i64,
-            description: String,
-            dependencies: Vec<PackageDep>,
-            external_dependencies: Vec<ExternalDep>,
-            _bump: u8,
-        }
+        let mut packages: Vec<PackageAccount> = self.rpc_client
+            .get_program_accounts(&self.program_id, &prefix)?
+            .into_iter()
+            .filter_map(|(_, account)| self.package_layout().decode(&account.data).ok())
+            .filter(|package| package.name == name)
+            .collect();
+        packages.sort_by(|a, b| compare_versions(&a.version, &b.version));
+        Ok(packages)
+    }
+    
+    /// Every package account of the registry, decoded, up to `max_accounts`.
+    /// Addresses are listed first without their data, then fetched
+    /// [`rpc::MAX_MULTIPLE_ACCOUNTS`] at a time, so large registries don't
+    /// come back as one huge response. Accounts that fail to decode are skipped.
+    pub fn list_all_packages(&self, max_accounts: usize) -> Result<PackageScan> {
+        let discriminator = idl::account_discriminator(idl::PACKAGE_ACCOUNT);
+        let mut keys = self.rpc_client.get_program_account_keys(&self.program_id, &discriminator)?;
+        let total = keys.len();
+        // Sorted so a capped scan covers the same accounts every time
+        keys.sort();
+        keys.truncate(max_accounts);
         
-        // Fallback to old format (without external_dependencies)
-        #[derive(BorshDeserialize, Debug)]
-        struct AnchorPackageOld {
-            name: String,
-            version: String,
-            authority: Pubkey,
-            ipfs_cid: String,
-            published_at: i64,
-            description: String,
-            dependencies: Vec<PackageDep>,
-            _bump: u8,
+        let mut packages = Vec::with_capacity(keys.len());
+        for page in keys.chunks(rpc::MAX_MULTIPLE_ACCOUNTS) {
+            for account in self.rpc_client.get_multiple_accounts(page)?.into_iter().flatten() {
+                if account.owner != self.program_id || account.data.len() < 8 {
+                    continue;
+                }
+                if let Ok(package) = self.package_layout().decode(&account.data) {
+                    packages.push(package);
+                }
+            }
         }
+        Ok(PackageScan { packages, total })
+    }
+}
+
+/// Arguments of `publish_package` and `update_package`, in the program's
+/// order; the metadata struct serializes as its three fields before the README CID
+#[derive(BorshSerialize)]
+struct PackageArgs {
+    name: String,
+    version: String,
+    ipfs_cid: String,
+    description: String,
+    dependencies: Vec<PackageDep>,
+    content_sha256: [u8; 32],
+    metadata: PackageMetadata,
+    readme_cid: Option<String>,
+    external_dependencies: Vec<ExternalDep>,
+}
+
+fn package_deps(dependencies: Vec<Dependency>) -> Vec<PackageDep> {
+    dependencies
+        .into_iter()
+        .map(|d| PackageDep { name: d.name, version: d.version })
+        .collect()
+}
+
+fn external_deps(dependencies: Vec<ExternalDependency>) -> Vec<ExternalDep> {
+    dependencies
+        .into_iter()
+        .map(|d| ExternalDep { name: d.name, version: d.version, dep_type: d.dep_type, registry: d.registry })
+        .collect()
+}
+
+/// Anchor instruction data: the discriminator derived from `name`, then the Borsh args
+fn instruction_data(name: &str, args: &impl BorshSerialize) -> Result<Vec<u8>> {
+    let mut data = idl::instruction_discriminator(name).to_vec();
+    data.extend_from_slice(&args.try_to_vec()?);
+    Ok(data)
+}
+
+/// Refuse CIDs the program would reject before a transaction is built or signed
+fn check_cid_args(ipfs_cid: &str, readme_cid: Option<&str>) -> Result<()> {
+    match check_cids(ipfs_cid, readme_cid).into_iter().next() {
+        Some(violation) => Err(AntSolError::Usage(violation.message).into()),
+        None => Ok(()),
+    }
+}
+
+/// Likewise for external dependencies the program would reject
+fn check_external_dependency_args(dependencies: &[ExternalDependency]) -> Result<()> {
+    match check_external_dependencies(dependencies).into_iter().next() {
+        Some(violation) => Err(AntSolError::Usage(violation.message).into()),
+        None => Ok(()),
+    }
+}
+
+/// The checksum instruction argument; the program rejects all zeros, so refuse early
+fn checksum_arg(content_sha256: &str) -> Result<[u8; 32]> {
+    decode_sha256_hex(content_sha256)
+        .filter(|bytes| *bytes != [0u8; 32])
+        .ok_or_else(|| AntSolError::Usage(format!("Invalid content sha256: {}", content_sha256)).into())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::rpc::mock::MockRpc;
+    use crate::validation::PACKAGE_MAX_SPACE;
+    use solana_sdk::account::Account;
+    use solana_sdk::signature::Keypair;
+
+    /// Encode a package the way the registry program stores it (discriminator + Borsh)
+    pub(crate) fn encode_package_account(pkg: &PackageAccount) -> Vec<u8> {
+        PackageLayout::current().encode(pkg, 255)
+    }
+
+    pub(crate) fn sample_package(name: &str, version: &str, deps: &[(&str, &str)]) -> PackageAccount {
+        PackageAccount {
+            name: name.to_string(),
+            version: version.to_string(),
+            authority: Pubkey::new_unique(),
+            ipfs_cid: format!("Qm{}", "a".repeat(44)),
+            published_at: 1_700_000_000,
+            description: format!("{} test package", name),
+            dependencies: deps
+                .iter()
+                .map(|(n, v)| Dependency { name: n.to_string(), version: v.to_string() })
+                .collect(),
+            external_dependencies: vec![],
+            content_sha256: None,
+            metadata: PackageMetadata::default(),
+            readme_cid: None,
+            downloads: 0,
+            frozen: false,
+        }
+    }
+
+    pub(crate) fn client_with(packages: &[PackageAccount], budget: Option<u64>) -> AntSolClient {
+        let program_id = Pubkey::new_unique();
+        let mock = MockRpc::new();
+        for pkg in packages {
+            let (pda, _) = Pubkey::find_program_address(
+                &[b"package", pkg.name.as_bytes(), pkg.version.as_bytes()],
+                &program_id,
+            );
+            mock.insert_account(pda, Account {
+                lamports: 1,
+                data: encode_package_account(pkg),
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            });
+        }
+        AntSolClient::with_rpc(Box::new(mock), program_id, Arc::new(RpcUsage::new(budget)))
+            .with_package_layout(PackageLayout::current())
+    }
+
+    #[tokio::test]
+    async fn wait_until_visible_gives_up_after_the_confirm_timeout() {
+        let pkg = sample_package("foo", "1.0.0", &[]);
+        assert!(client_with(&[pkg], None).wait_until_visible("foo", "1.0.0").await.unwrap());
+
+        let client = client_with(&[], None).with_tx_settings(TxSettings {
+            confirm_timeout: std::time::Duration::ZERO,
+            ..TxSettings::default()
+        });
+        assert!(!client.wait_until_visible("foo", "1.0.0").await.unwrap());
+    }
+
+    #[test]
+    fn get_package_counts_one_account_fetch() {
+        let client = client_with(&[sample_package("foo", "1.0.0", &[])], None);
+
+        let found = client.get_package("foo", "1.0.0").unwrap().unwrap();
+        assert_eq!(found.name, "foo");
+        assert!(client.get_package("foo", "2.0.0").unwrap().is_none());
+
+        let usage = client.rpc_usage();
+        assert_eq!(usage.count("getAccountInfo"), 2);
+        assert_eq!(usage.total(), 2);
+        assert_eq!(usage.summary().unwrap(), "RPC: 2 getAccountInfo");
+    }
+
+    #[test]
+    fn reads_content_checksum_and_tolerates_old_accounts() {
+        let mut with_checksum = sample_package("new", "1.0.0", &[]);
+        with_checksum.content_sha256 = Some("cd".repeat(32));
+        let client = client_with(&[with_checksum, sample_package("old", "1.0.0", &[])], None);
+
+        let found = client.get_package("new", "1.0.0").unwrap().unwrap();
+        assert_eq!(found.content_sha256, Some("cd".repeat(32)));
+        // Zero padding where the checksum would be means it predates checksums
+        let old = client.get_package("old", "1.0.0").unwrap().unwrap();
+        assert_eq!(old.content_sha256, None);
+
+        // Accounts that end right after the bump byte: no checksum, empty
+        // metadata and no README CID follow
+        let data = encode_package_account(&sample_package("bare", "1.0.0", &[]));
+        let bare = PackageLayout::current().decode(&data[..data.len() - (32 + 4 + 4 + 4 + 1)]).unwrap();
+        assert_eq!(bare.content_sha256, None);
+        assert_eq!(bare.metadata, PackageMetadata::default());
+        assert!(checksum_arg(&"00".repeat(32)).is_err());
+        assert!(checksum_arg("not-hex").is_err());
+    }
+
+    #[test]
+    fn reads_metadata_after_checksum() {
+        let mut pkg = sample_package("meta", "1.0.0", &[]);
+        pkg.content_sha256 = Some("cd".repeat(32));
+        pkg.metadata = PackageMetadata {
+            repository: "https://github.com/antsol/meta".into(),
+            homepage: String::new(),
+            keywords: vec!["solana".into(), "anchor".into()],
+        };
+        let client = client_with(&[pkg.clone(), sample_package("old", "1.0.0", &[])], None);
+
+        assert_eq!(client.get_package("meta", "1.0.0").unwrap().unwrap().metadata, pkg.metadata);
+        assert_eq!(client.get_package("old", "1.0.0").unwrap().unwrap().metadata, PackageMetadata::default());
+    }
+
+    #[test]
+    fn reads_readme_cid_and_tolerates_accounts_sized_before_it() {
+        let mut pkg = sample_package("docs", "1.0.0", &[]);
+        pkg.content_sha256 = Some("cd".repeat(32));
+        pkg.readme_cid = Some(format!("Qm{}", "r".repeat(44)));
+        let client = client_with(&[pkg.clone()], None);
+        assert_eq!(client.get_package("docs", "1.0.0").unwrap().unwrap().readme_cid, pkg.readme_cid);
+
+        // An exactly sized account from before the field ends after the keywords
+        pkg.readme_cid = None;
+        let data = encode_package_account(&pkg);
+        let older = PackageLayout::current().decode(&data[..data.len() - 1]).unwrap();
+        assert_eq!(older.readme_cid, None);
+        assert_eq!(older.content_sha256, pkg.content_sha256);
+    }
+
+    #[test]
+    fn package_layout_is_read_from_the_idl_once() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let program_id = Pubkey::new_unique();
+        let mut fields: Vec<serde_json::Value> = idl::TYPES[0].1
+            .iter()
+            .map(|f| serde_json::json!({ "name": f.name, "type": f.ty }))
+            .collect();
+        fields.insert(7, serde_json::json!({ "name": "external_dependencies", "type": { "vec": { "defined": "ExternalDependency" } } }));
+        let idl = serde_json::json!({ "types": [{ "name": "Package", "type": { "kind": "struct", "fields": fields } }] });
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(idl.to_string().as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut idl_data = vec![0u8; 40];
+        idl_data.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        idl_data.extend_from_slice(&compressed);
+
+        let mut pkg = sample_package("ext", "1.0.0", &[]);
+        pkg.external_dependencies = vec![crate::types::ExternalDependency {
+            name: "serde".into(),
+            version: "1.0".into(),
+            dep_type: "rust".into(),
+            registry: Some("crates.io".into()),
+        }];
+        let (pda, _) = Pubkey::find_program_address(&[b"package", b"ext", b"1.0.0"], &program_id);
+        let mock = MockRpc::new();
+        let account = |data| Account { lamports: 1, data, owner: program_id, executable: false, rent_epoch: 0 };
+        mock.insert_account(idl::idl_address(&program_id), account(idl_data));
+        mock.insert_account(pda, account(PackageLayout::with_external_dependencies().encode(&pkg, 255)));
+        let client = AntSolClient::with_rpc(Box::new(mock), program_id, Arc::new(RpcUsage::new(None)));
+
+        for _ in 0..2 {
+            let found = client.get_package("ext", "1.0.0").unwrap().unwrap();
+            assert_eq!(found.external_dependencies[0].name, "serde");
+        }
+        assert_eq!(client.rpc_usage().count("getAccountInfo"), 3);
+    }
+
+    #[tokio::test]
+    async fn publish_counts_blockhash_and_send() {
+        let client = client_with(&[], None);
+        let payer = Keypair::new();
+
+        client.publish_package(&payer, publish_request("foo", 4)).await.unwrap();
+
+        let report = client.rpc_usage().report();
+        assert_eq!(report.total, 3);
+        assert_eq!(report.by_method.get("getLatestBlockhash"), Some(&1));
+        assert_eq!(report.by_method.get("sendTransaction"), Some(&1));
+        assert_eq!(report.by_method.get("getSignatureStatuses"), Some(&1));
+    }
+    
+    /// A client whose first sends fail with `send_errors`, retrying without waiting
+    fn flaky_client(send_errors: &[&str], max_retries: u32) -> AntSolClient {
+        let mock = MockRpc::new();
+        mock.send_errors.lock().unwrap().extend(send_errors.iter().map(|e| e.to_string()));
+        AntSolClient::with_rpc(Box::new(mock), Pubkey::new_unique(), Arc::new(RpcUsage::new(None)))
+            .with_tx_settings(TxSettings { max_retries, backoff: std::time::Duration::ZERO, ..TxSettings::default() })
+    }
+    
+    async fn publish_foo(client: &AntSolClient) -> Result<String> {
+        client.publish_package(&Keypair::new(), publish_request("foo", 4)).await
+    }
+    
+    type Sent = Arc<std::sync::Mutex<Vec<Transaction>>>;
+    
+    fn client_with_fees(priority_fee: PriorityFee, recent: &[u64]) -> (AntSolClient, Sent) {
+        let mock = MockRpc::new();
+        mock.prioritization_fees.lock().unwrap().extend_from_slice(recent);
+        let sent = mock.sent.clone();
+        let client = AntSolClient::with_rpc(Box::new(mock), Pubkey::new_unique(), Arc::new(RpcUsage::new(None)))
+            .with_tx_settings(TxSettings { priority_fee, ..TxSettings::default() });
+        (client, sent)
+    }
+    
+    /// Compute unit price set by the last sent transaction's compute budget instruction
+    fn sent_unit_price(sent: &Sent) -> Option<u64> {
+        let sent = sent.lock().unwrap();
+        let message = &sent.last().unwrap().message;
+        message.instructions.iter().find_map(|ix| {
+            let program = message.account_keys[ix.program_id_index as usize];
+            match (program == solana_sdk::compute_budget::id(), ix.data.split_first()) {
+                (true, Some((3, price))) => Some(u64::from_le_bytes(price.try_into().unwrap())),
+                _ => None,
+            }
+        })
+    }
+    
+    fn publish_request(name: &str, description_len: usize) -> PublishRequest {
+        PublishRequest {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            ipfs_cid: format!("Qm{}", "a".repeat(44)),
+            description: "d".repeat(description_len),
+            dependencies: vec![],
+            content_sha256: "ab".repeat(32),
+            metadata: PackageMetadata::default(),
+            readme_cid: None,
+            external_dependencies: vec![],
+        }
+    }
+    
+    fn batch_client(simulation: Simulation) -> (AntSolClient, Sent) {
+        let mock = MockRpc::new();
+        *mock.simulation.lock().unwrap() = simulation;
+        let sent = mock.sent.clone();
+        let client = AntSolClient::with_rpc(Box::new(mock), Pubkey::new_unique(), Arc::new(RpcUsage::new(None)));
+        (client, sent)
+    }
+    
+    #[tokio::test]
+    async fn publish_many_batches_with_a_simulated_budget() {
+        let (client, sent) = batch_client(Simulation { units_consumed: Some(50_000), ..Simulation::default() });
+        let requests = vec![publish_request("a", 10), publish_request("b", 10), publish_request("c", 10), publish_request("d", 700)];
+        let statuses = client.publish_many(&Keypair::new(), requests).await.unwrap();
+
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        let signatures: Vec<String> = sent.iter().map(|tx| tx.signatures[0].to_string()).collect();
+        assert_eq!(statuses, vec![
+            PublishStatus::Published(signatures[0].clone()),
+            PublishStatus::Published(signatures[0].clone()),
+            PublishStatus::Published(signatures[0].clone()),
+            PublishStatus::Published(signatures[1].clone()),
+        ]);
+        // A compute unit limit from the simulation, then the three publishes
+        let first = &sent[0].message;
+        assert_eq!(first.instructions.len(), 4);
+        assert_eq!(first.account_keys[first.instructions[0].program_id_index as usize], solana_sdk::compute_budget::id());
+        assert_eq!(first.instructions[0].data, [&[2u8][..], &60_000u32.to_le_bytes()].concat());
+    }
+    
+    #[tokio::test]
+    async fn publish_many_blames_the_failing_instruction() {
+        use solana_sdk::instruction::InstructionError;
+        use solana_sdk::transaction::TransactionError;
+        // Index 2 is the second publish, after the compute unit limit
+        let failure = TransactionError::InstructionError(2, InstructionError::Custom(0x1771));
+        let (client, sent) = batch_client(Simulation { err: Some(failure), ..Simulation::default() });
+        let requests = vec![publish_request("a", 10), publish_request("b", 10), publish_request("c", 10)];
+        let statuses = client.publish_many(&Keypair::new(), requests).await.unwrap();
+
+        assert!(sent.lock().unwrap().is_empty());
+        assert_eq!(statuses[0], PublishStatus::NotPublished);
+        assert!(matches!(&statuses[1], PublishStatus::Failed(reason) if reason.contains("custom program error: 0x1771")));
+        assert_eq!(statuses[2], PublishStatus::NotPublished);
+    }
+    
+    #[tokio::test]
+    async fn publish_data_follows_the_program_signature() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        let metadata = PackageMetadata {
+            repository: "https://github.com/antsol/foo".into(),
+            homepage: String::new(),
+            keywords: vec!["solana".into()],
+        };
+        client.publish_package(&Keypair::new(), PublishRequest {
+            name: "foo".into(),
+            version: "1.0.0".into(),
+            ipfs_cid: "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e".into(),
+            description: "desc".into(),
+            dependencies: vec![Dependency { name: "bar".into(), version: "2.0.0".into() }],
+            content_sha256: "ab".repeat(32),
+            metadata,
+            readme_cid: Some("QmVxA7HQNcDfk9Ncm5LcPShjqFGSzFdRV4wMb3Y8jkTsx1".into()),
+            external_dependencies: vec![ExternalDependency { name: "serde".into(), version: "^1.0".into(), dep_type: "rust".into(), registry: None }],
+        }).await.unwrap();
         
-        // Try new format first
-        let mut data_slice = data;
-        let result = AnchorPackageNew::deserialize(&mut data_slice);
+        let data = sent.lock().unwrap()[0].message.instructions[0].data.clone();
+        assert_eq!(data[..8], idl::instruction_discriminator(idl::PUBLISH_PACKAGE));
+        type Args = (String, String, String, String, Vec<PackageDep>, [u8; 32], (String, String, Vec<String>), Option<String>, Vec<ExternalDep>);
+        let (name, version, cid, description, deps, checksum, (repository, homepage, keywords), readme_cid, external) =
+            Args::try_from_slice(&data[8..]).unwrap();
+        assert_eq!((name.as_str(), version.as_str(), cid.as_str(), description.as_str()), ("foo", "1.0.0", "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e", "desc"));
+        assert_eq!(deps.len(), 1);
+        assert_eq!(checksum, [0xab; 32]);
+        assert_eq!((repository.as_str(), homepage.as_str()), ("https://github.com/antsol/foo", ""));
+        assert_eq!(keywords, vec!["solana"]);
+        assert_eq!(readme_cid.as_deref(), Some("QmVxA7HQNcDfk9Ncm5LcPShjqFGSzFdRV4wMb3Y8jkTsx1"));
+        assert_eq!((external[0].dep_type.as_str(), external[0].name.as_str(), external[0].registry.as_deref()), ("rust", "serde", None));
+    }
+    
+    #[tokio::test]
+    async fn scoped_publish_passes_the_namespace_account() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        publish_foo(&client).await.unwrap();
+        client.publish_package(&Keypair::new(), publish_request("alice/foo", 4)).await.unwrap();
         
-        match result {
-            Ok(anchor_pkg) => {
-                eprintln!("DEBUG: Successfully deserialized NEW format! Remaining bytes: {}", data_slice.len());
-                
-                let dependencies = anchor_pkg.dependencies
-                    .into_iter()
-                    .map(|d| Dependency {
-                        name: d.name,
-                        version: d.version,
-                    })
-                    .collect();
-                
-                let external_dependencies = anchor_pkg.external_dependencies
-                    .into_iter()
-                    .map(|d| ExternalDependency {
-                        name: d.name,
-                        version: d.version,
-                        dep_type: d.dep_type,
-                        registry: d.registry,
-                    })
-                    .collect();
-                
-                Ok(PackageAccount {
-                    name: anchor_pkg.name,
-                    version: anchor_pkg.version,
-                    authority: anchor_pkg.authority,
-                    ipfs_cid: anchor_pkg.ipfs_cid,
-                    published_at: anchor_pkg.published_at,
-                    description: anchor_pkg.description,
-                    dependencies,
-                    external_dependencies,
-                })
-            }
-            Err(_) => {
-                // Try old format
-                eprintln!("DEBUG: New format failed, trying OLD format...");
-                let mut data_slice = data;
-                let anchor_pkg: AnchorPackageOld = BorshDeserialize::deserialize(&mut data_slice)
-                    .map_err(|e| format!("Borsh deserialization error (old format): {}. Remaining bytes: {}", e, data_slice.len()))?;
-                
-                eprintln!("DEBUG: Successfully deserialized OLD format! Remaining bytes: {}", data_slice.len());
-                
-                let dependencies = anchor_pkg.dependencies
-                    .into_iter()
-                    .map(|d| Dependency {
-                        name: d.name,
-                        version: d.version,
-                    })
-                    .collect();
-                
-                Ok(PackageAccount {
-                    name: anchor_pkg.name,
-                    version: anchor_pkg.version,
-                    authority: anchor_pkg.authority,
-                    ipfs_cid: anchor_pkg.ipfs_cid,
-                    published_at: anchor_pkg.published_at,
-                    description: anchor_pkg.description,
-                    dependencies,
-                    external_dependencies: vec![], // Old format has no external deps
-                })
-            }
+        let sent = sent.lock().unwrap();
+        let namespace_account = |tx: &Transaction| {
+            let ix = &tx.message.instructions[0];
+            tx.message.account_keys[*ix.accounts.last().unwrap() as usize]
+        };
+        assert_eq!(namespace_account(&sent[0]), client.program_id);
+        assert_eq!(namespace_account(&sent[1]), client.derive_namespace_pda("alice").0);
+    }
+    
+    #[tokio::test]
+    async fn namespace_instructions_follow_the_program_signature() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        let publisher = Pubkey::new_unique();
+        client.register_namespace(&Keypair::new(), "alice").await.unwrap();
+        client.set_namespace_publishers(&Keypair::new(), "alice", vec![publisher]).await.unwrap();
+        
+        let sent = sent.lock().unwrap();
+        let data = &sent[0].message.instructions[0].data;
+        assert_eq!(data[..8], idl::instruction_discriminator(idl::REGISTER_NAMESPACE));
+        assert_eq!(String::try_from_slice(&data[8..]).unwrap(), "alice");
+        let data = &sent[1].message.instructions[0].data;
+        assert_eq!(data[..8], idl::instruction_discriminator(idl::SET_NAMESPACE_PUBLISHERS));
+        assert_eq!(<(String, Vec<Pubkey>)>::try_from_slice(&data[8..]).unwrap(), ("alice".to_string(), vec![publisher]));
+    }
+    
+    #[tokio::test]
+    async fn maintainer_instructions_follow_the_program_signature() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        let maintainer = Pubkey::new_unique();
+        let authority = Keypair::new();
+        client.change_maintainer(&authority, "foo", "1.0.0", maintainer, true).await.unwrap();
+        client.change_maintainer(&authority, "foo", "1.0.0", maintainer, false).await.unwrap();
+        
+        let sent = sent.lock().unwrap();
+        for (tx, name, accounts) in [(&sent[0], idl::ADD_MAINTAINER, 4), (&sent[1], idl::REMOVE_MAINTAINER, 3)] {
+            let ix = &tx.message.instructions[0];
+            assert_eq!(ix.data[..8], idl::instruction_discriminator(name));
+            assert_eq!(
+                <(String, String, Pubkey)>::try_from_slice(&ix.data[8..]).unwrap(),
+                ("foo".to_string(), "1.0.0".to_string(), maintainer)
+            );
+            assert_eq!(ix.accounts.len(), accounts);
+            assert_eq!(tx.message.account_keys[ix.accounts[2] as usize], client.derive_maintainers_pda("foo", &authority.pubkey()).0);
+        }
+    }
+    
+    #[tokio::test]
+    async fn record_download_follows_the_program_signature() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        let payer = Keypair::new();
+        client.record_download(&payer, "foo", "1.0.0").await.unwrap();
+        
+        let sent = sent.lock().unwrap();
+        let ix = &sent[0].message.instructions[0];
+        assert_eq!(ix.data[..8], idl::instruction_discriminator(idl::RECORD_DOWNLOAD));
+        assert_eq!(<(String, String)>::try_from_slice(&ix.data[8..]).unwrap(), ("foo".to_string(), "1.0.0".to_string()));
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|&i| sent[0].message.account_keys[i as usize]).collect();
+        assert_eq!(keys, vec![payer.pubkey(), client.derive_package_pda("foo", "1.0.0").0, system_program::id()]);
+    }
+    
+    #[tokio::test]
+    async fn maintainer_updates_pass_the_maintainer_list() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[]);
+        let authority = Pubkey::new_unique();
+        for maintainer_of in [None, Some(authority)] {
+            let request = PublishRequest { version: "1.1.0".into(), ..publish_request("foo", 4) };
+            client.update_package(&Keypair::new(), "1.0.0", request, maintainer_of).await.unwrap();
         }
+        
+        let sent = sent.lock().unwrap();
+        let last_account = |tx: &Transaction| {
+            let ix = &tx.message.instructions[0];
+            tx.message.account_keys[*ix.accounts.last().unwrap() as usize]
+        };
+        assert_eq!(last_account(&sent[0]), client.program_id);
+        assert_eq!(last_account(&sent[1]), client.derive_maintainers_pda("foo", &authority).0);
+    }
+    
+    #[test]
+    fn reads_registered_namespaces() {
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let namespace = NamespaceAccount {
+            scope: "alice".into(),
+            owner,
+            publishers: vec![],
+            created_at: 1_700_000_000,
+            bump: 255,
+        };
+        let mut data = idl::account_discriminator(idl::NAMESPACE_ACCOUNT).to_vec();
+        data.extend_from_slice(&namespace.try_to_vec().unwrap());
+        let (pda, _) = Pubkey::find_program_address(&[b"namespace", b"alice"], &program_id);
+        let mock = MockRpc::new();
+        mock.insert_account(pda, Account { lamports: 1, data, owner: program_id, executable: false, rent_epoch: 0 });
+        let client = AntSolClient::with_rpc(Box::new(mock), program_id, Arc::new(RpcUsage::new(None)));
+        
+        let found = client.get_namespace("alice").unwrap().unwrap();
+        assert_eq!(found, namespace);
+        assert!(found.can_publish(&owner));
+        assert!(!found.can_publish(&Pubkey::new_unique()));
+        assert_eq!(client.get_namespace("bob").unwrap(), None);
+    }
+    
+    #[tokio::test]
+    async fn zero_priority_fee_adds_no_instructions() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(0), &[5_000]);
+        publish_foo(&client).await.unwrap();
+        
+        assert_eq!(sent.lock().unwrap()[0].message.instructions.len(), 1);
+        assert_eq!(client.rpc_usage().count("getRecentPrioritizationFees"), 0);
+    }
+    
+    #[tokio::test]
+    async fn fixed_and_estimated_priority_fees_set_the_unit_price() {
+        let (client, sent) = client_with_fees(PriorityFee::MicroLamports(1_500), &[]);
+        publish_foo(&client).await.unwrap();
+        assert_eq!(sent.lock().unwrap()[0].message.instructions.len(), 3);
+        assert_eq!(sent_unit_price(&sent), Some(1_500));
+        
+        let (client, sent) = client_with_fees(PriorityFee::Auto, &[0, 100, 10_000, 200]);
+        publish_foo(&client).await.unwrap();
+        assert_eq!(sent_unit_price(&sent), Some(200));
+        assert_eq!(client.rpc_usage().count("getRecentPrioritizationFees"), 1);
+    }
+    
+    #[tokio::test]
+    async fn expired_blockhash_is_retried_with_a_fresh_one() {
+        let client = flaky_client(&["RPC response error -32002: Transaction simulation failed: Blockhash not found"], 3);
+        publish_foo(&client).await.unwrap();
+        
+        let usage = client.rpc_usage();
+        assert_eq!(usage.count("getLatestBlockhash"), 2);
+        assert_eq!(usage.count("sendTransaction"), 2);
+    }
+    
+    #[tokio::test]
+    async fn program_errors_are_not_retried() {
+        let client = flaky_client(&["Error processing Instruction 0: custom program error: 0x0"], 3);
+        let err = publish_foo(&client).await.unwrap_err();
+        
+        assert!(err.to_string().contains("custom program error"));
+        assert_eq!(client.rpc_usage().count("sendTransaction"), 1);
+    }
+    
+    #[tokio::test]
+    async fn retries_stop_at_the_configured_limit() {
+        let client = flaky_client(&["operation timed out"; 3], 2);
+        let err = publish_foo(&client).await.unwrap_err();
+        
+        assert!(err.to_string().contains("timed out"));
+        assert_eq!(client.rpc_usage().count("sendTransaction"), 3);
+        
+        // One more allowed retry gets through
+        let client = flaky_client(&["operation timed out"; 3], 3);
+        publish_foo(&client).await.unwrap();
+        assert_eq!(client.rpc_usage().count("sendTransaction"), 4);
+    }
+
+    #[test]
+    fn budget_aborts_before_exceeding() {
+        let client = client_with(&[sample_package("foo", "1.0.0", &[])], Some(1));
+
+        assert!(client.get_package("foo", "1.0.0").unwrap().is_some());
+        let err = client.get_package("foo", "1.0.0").unwrap_err();
+        assert!(err.to_string().contains("max_rpc_calls = 1"));
+        assert!(client.rpc_usage().ensure_budget(1).is_err());
+        assert_eq!(client.rpc_usage().total(), 1);
+    }
+
+    #[tokio::test]
+    async fn airdrop_waits_for_confirmation_and_shows_in_balance() {
+        let client = client_with(&[], None);
+        let wallet = Pubkey::new_unique();
+        assert_eq!(client.balance(&wallet).unwrap(), 0);
+
+        client.airdrop(&wallet, 1_000_000_000, std::time::Duration::from_secs(1)).await.unwrap();
+
+        assert_eq!(client.balance(&wallet).unwrap(), 1_000_000_000);
+        let usage = client.rpc_usage();
+        assert_eq!(usage.count("requestAirdrop"), 1);
+        assert_eq!(usage.count("getSignatureStatuses"), 1);
+    }
+
+    #[tokio::test]
+    async fn publish_needs_rent_and_fees() {
+        let client = client_with(&[], None);
+        let wallet = Pubkey::new_unique();
+        let space = 300;
+        let cost = client.publish_cost(space);
+        assert_eq!(cost, solana_sdk::rent::Rent::default().minimum_balance(space) + PUBLISH_FEE_LAMPORTS);
+        assert!(cost < client.publish_cost(PACKAGE_MAX_SPACE));
+
+        client.airdrop(&wallet, cost - 1, std::time::Duration::from_secs(1)).await.unwrap();
+        let err = client.ensure_publish_funds(&wallet, space).unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::InsufficientFunds);
+
+        client.airdrop(&wallet, 1, std::time::Duration::from_secs(1)).await.unwrap();
+        client.ensure_publish_funds(&wallet, space).unwrap();
+    }
+
+    #[test]
+    fn require_package_reports_name_and_version() {
+        let client = client_with(&[], None);
+        let err = client.require_package("foo", "1.0.0").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AntSolError>(),
+            Some(AntSolError::PackageNotFound { name, version }) if name == "foo" && version.as_deref() == Some("1.0.0")
+        ));
+    }
+
+    #[test]
+    fn package_versions_scans_for_the_exact_name() {
+        let client = client_with(&[
+            sample_package("foo", "1.10.0", &[]),
+            sample_package("foo", "1.2.0", &[]),
+            sample_package("foo-extra", "1.0.0", &[]),
+            sample_package("bar", "1.0.0", &[]),
+        ], None);
+
+        let versions: Vec<String> = client.package_versions("foo").unwrap().into_iter().map(|p| p.version).collect();
+        assert_eq!(versions, ["1.2.0", "1.10.0"]);
+        assert!(client.package_versions("fo").unwrap().is_empty());
+        assert_eq!(client.rpc_usage().count("getProgramAccounts"), 2);
+    }
+
+    #[test]
+    fn list_all_packages_pages_and_caps() {
+        let packages: Vec<PackageAccount> = (0..250)
+            .map(|i| sample_package(&format!("pkg-{}", i), "1.0.0", &[]))
+            .collect();
+        let client = client_with(&packages, None);
+
+        let scan = client.list_all_packages(usize::MAX).unwrap();
+        assert_eq!(scan.total, 250);
+        assert_eq!(scan.packages.len(), 250);
+        assert_eq!(client.rpc_usage().count("getProgramAccounts"), 1);
+        assert_eq!(client.rpc_usage().count("getMultipleAccounts"), 3);
+
+        let capped = client.list_all_packages(120).unwrap();
+        assert_eq!((capped.total, capped.packages.len()), (250, 120));
     }
 }
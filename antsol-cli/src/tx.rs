@@ -0,0 +1,515 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::utils::is_mainnet_rpc;
+
+/// Commitment accounts are read at and a sent transaction must reach before
+/// it counts as landed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Finalized => "finalized",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Commitment::Processed, Commitment::Confirmed, Commitment::Finalized]
+            .into_iter()
+            .find(|commitment| commitment.as_str() == value)
+    }
+
+    pub fn config(self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+/// Compute unit price attached to publish/update transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// Estimate from the fees recently paid for the accounts involved
+    Auto,
+    /// A fixed price in microlamports per compute unit; 0 adds no fee
+    MicroLamports(u64),
+}
+
+impl FromStr for PriorityFee {
+    type Err = String;
+    
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim() {
+            "auto" => Ok(PriorityFee::Auto),
+            price => price.parse().map(PriorityFee::MicroLamports).map_err(|_| {
+                format!("'{}' (expected auto or a price in microlamports per compute unit)", value)
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriorityFee::Auto => write!(f, "auto"),
+            PriorityFee::MicroLamports(price) => write!(f, "{}", price),
+        }
+    }
+}
+
+/// Stored as `"auto"` or a bare number
+impl Serialize for PriorityFee {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            PriorityFee::Auto => serializer.serialize_str("auto"),
+            PriorityFee::MicroLamports(price) => serializer.serialize_u64(*price),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PriorityFee {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Price(u64),
+            Text(String),
+        }
+        match Repr::deserialize(deserializer)? {
+            Repr::Price(price) => Ok(PriorityFee::MicroLamports(price)),
+            Repr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Compute units requested for a publish/update transaction when it carries a priority fee
+pub const COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// Most compute units one transaction may request
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// Priority fees costing more than this (0.0001 SOL) are announced before sending
+pub const PRIORITY_FEE_NOTICE_LAMPORTS: u64 = 100_000;
+
+/// Percentile of recent fees `auto` pays, so most recent transactions paid less
+const AUTO_FEE_PERCENTILE: usize = 75;
+
+/// Lamports a priority fee of `price` microlamports per unit adds for `units`
+pub fn priority_fee_lamports(price: u64, units: u32) -> u64 {
+    (u128::from(price) * u128::from(units)).div_ceil(1_000_000).try_into().unwrap_or(u64::MAX)
+}
+
+/// The `auto` price for a set of recent per-slot fees: their 75th percentile
+pub fn estimate_priority_fee(mut recent: Vec<u64>) -> u64 {
+    if recent.is_empty() {
+        return 0;
+    }
+    recent.sort_unstable();
+    let index = (recent.len() * AUTO_FEE_PERCENTILE).div_ceil(100).saturating_sub(1);
+    recent[index]
+}
+
+/// How transactions are sent, retried and confirmed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TxSettings {
+    /// Resubmissions with a fresh blockhash after a retryable failure
+    pub max_retries: u32,
+    pub commitment: Commitment,
+    /// Send without the RPC node simulating the transaction first
+    pub skip_preflight: bool,
+    /// How long one submission may take to reach `commitment`
+    pub confirm_timeout: Duration,
+    pub poll_interval: Duration,
+    /// Wait before the first retry; doubles on each further one
+    pub backoff: Duration,
+    pub priority_fee: PriorityFee,
+}
+
+/// Longest wait between two attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+impl Default for TxSettings {
+    fn default() -> Self {
+        Self {
+            max_retries: Config::default_tx_max_retries(),
+            commitment: Commitment::Confirmed,
+            skip_preflight: false,
+            confirm_timeout: Duration::from_secs(Config::default_tx_confirm_timeout_secs()),
+            poll_interval: Duration::from_millis(500),
+            backoff: Duration::from_millis(500),
+            priority_fee: PriorityFee::MicroLamports(0),
+        }
+    }
+}
+
+impl TxSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_retries: config.tx_max_retries,
+            commitment: config.commitment,
+            skip_preflight: config.skip_preflight,
+            confirm_timeout: Duration::from_secs(config.tx_confirm_timeout_secs),
+            priority_fee: config.priority_fee.unwrap_or_else(|| default_priority_fee(&config.rpc_url)),
+            ..Self::default()
+        }
+    }
+
+    /// Wait before retry number `retry` (1-based)
+    pub fn backoff_for(&self, retry: u32) -> Duration {
+        self.backoff.saturating_mul(1 << retry.saturating_sub(1).min(16)).min(MAX_BACKOFF)
+    }
+}
+
+/// Per-run overrides of the transaction settings, shared by `publish` and `update`
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct SendOptions {
+    /// Send the transaction without preflight simulation by the RPC node
+    #[arg(long)]
+    pub skip_preflight: bool,
+    
+    /// Compute unit price in microlamports, or "auto" to match recent fees (overrides priority_fee)
+    #[arg(long, value_name = "MICROLAMPORTS|auto")]
+    pub priority_fee: Option<PriorityFee>,
+}
+
+impl SendOptions {
+    /// Layer these flags over the loaded config
+    pub fn apply(&self, config: &mut Config) {
+        config.skip_preflight |= self.skip_preflight;
+        if let Some(fee) = self.priority_fee {
+            config.priority_fee = Some(fee);
+        }
+    }
+}
+
+/// Without a configured priority fee, mainnet estimates one and other
+/// clusters (devnet, local validators) pay none
+pub fn default_priority_fee(rpc_url: &str) -> PriorityFee {
+    if is_mainnet_rpc(rpc_url) {
+        PriorityFee::Auto
+    } else {
+        PriorityFee::MicroLamports(0)
+    }
+}
+
+/// Whether resubmitting a failed transaction can help
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// Expired blockhash, timeouts, rate limits, an unhealthy node
+    Retryable,
+    /// The program or runtime rejected the transaction and would again
+    Fatal,
+}
+
+/// Messages of deterministic failures; checked first, since RPC errors often
+/// wrap them in otherwise transient-sounding text
+const FATAL_PATTERNS: &[&str] = &[
+    "custom program error",
+    "already in use",
+    "constraint",
+    "anchorerror",
+    "instruction error",
+    "error processing instruction",
+    "insufficient funds",
+    "insufficientfunds",
+    "signature verification",
+    "invalid account data",
+    "account not found",
+];
+
+const RETRYABLE_PATTERNS: &[&str] = &[
+    "blockhash not found",
+    "blockhashnotfound",
+    "block height exceeded",
+    "expired",
+    "not confirmed within",
+    "timed out",
+    "timeout",
+    "connection",
+    "broken pipe",
+    "too many requests",
+    "429",
+    "502",
+    "503",
+    "504",
+    "service unavailable",
+    "node is behind",
+    "node is unhealthy",
+    "-32004",
+    "-32005",
+];
+
+/// Bytes `instructions` take as a transaction signed by `payer` alone;
+/// anything over [`PACKET_DATA_SIZE`] can't be sent
+pub fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = usize::from(message.header.num_required_signatures);
+    // The signature count is a compact-u16, one byte below 128
+    1 + signatures * 64 + message.serialize().len()
+}
+
+/// Split `instructions` into consecutive runs that each fit one transaction
+/// behind `prefix` (the compute budget instructions), keeping their order.
+/// Each run takes as many instructions as fit. Fails when one instruction
+/// doesn't fit even on its own.
+pub fn pack_instructions(instructions: &[Instruction], prefix: &[Instruction], payer: &Pubkey) -> crate::types::Result<Vec<Range<usize>>> {
+    let size = |run: &[Instruction]| {
+        let mut all = prefix.to_vec();
+        all.extend_from_slice(run);
+        transaction_size(&all, payer)
+    };
+    let mut batches = Vec::new();
+    let mut start = 0;
+    while start < instructions.len() {
+        let mut end = start;
+        while end < instructions.len() && size(&instructions[start..=end]) <= PACKET_DATA_SIZE {
+            end += 1;
+        }
+        if end == start {
+            return Err(AntSolError::Program(format!(
+                "Instruction {} needs a {}-byte transaction on its own; at most {} bytes can be sent",
+                start,
+                size(&instructions[start..=start]),
+                PACKET_DATA_SIZE
+            )).into());
+        }
+        batches.push(start..end);
+        start = end;
+    }
+    Ok(batches)
+}
+
+/// Index of the instruction a transaction error blames, from messages such
+/// as "Error processing Instruction 2: custom program error: 0x0"
+pub fn failed_instruction(message: &str) -> Option<usize> {
+    const MARKER: &str = "Instruction ";
+    let rest = &message[message.find(MARKER)? + MARKER.len()..];
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..digits].parse().ok()
+}
+
+/// Compute units to request for a transaction that used `consumed` in
+/// simulation: a fifth more, since state can change before it lands
+pub fn compute_unit_limit(consumed: u64) -> u32 {
+    u32::try_from(consumed.saturating_add(consumed / 5)).unwrap_or(u32::MAX).min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Sort a send or confirmation error into retryable or fatal. Unrecognised
+/// errors are fatal so a broken transaction isn't resent blindly.
+pub fn classify_error(err: &(dyn std::error::Error + 'static)) -> FailureKind {
+    if matches!(err.downcast_ref::<AntSolError>(), Some(AntSolError::RpcBudget(_))) {
+        return FailureKind::Fatal;
+    }
+    classify_message(&err.to_string())
+}
+
+pub fn classify_message(message: &str) -> FailureKind {
+    let message = message.to_lowercase();
+    if FATAL_PATTERNS.iter().any(|pattern| message.contains(pattern)) {
+        FailureKind::Fatal
+    } else if RETRYABLE_PATTERNS.iter().any(|pattern| message.contains(pattern)) {
+        FailureKind::Retryable
+    } else {
+        FailureKind::Fatal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transient_errors_are_retryable() {
+        for message in [
+            "RPC response error -32002: Transaction simulation failed: Blockhash not found",
+            "Transaction 5xyz expired before it was confirmed",
+            "Transaction 5xyz was not confirmed within 60s",
+            "error sending request for url (https://api.devnet.solana.com/): operation timed out",
+            "HTTP status client error (429 Too Many Requests) for url (https://api.devnet.solana.com/)",
+            "HTTP status server error (503 Service Unavailable)",
+            "RPC response error -32005: Node is behind by 152 slots",
+            "BlockhashNotFound",
+        ] {
+            assert_eq!(classify_message(message), FailureKind::Retryable, "{}", message);
+        }
+    }
+
+    #[test]
+    fn program_errors_are_fatal() {
+        for message in [
+            "RPC response error -32002: Transaction simulation failed: Error processing Instruction 0: custom program error: 0x0",
+            "Allocate: account Address { address: 9x.., base: None } already in use",
+            "AnchorError caused by account: package. Error Code: ConstraintSeeds. Error Number: 2006.",
+            "Transaction 5xyz failed: Error processing Instruction 0: custom program error: 0x1771",
+            "Attempt to debit an account but found no record of a prior credit. insufficient funds for rent",
+            // A program error inside a timeout-sounding wrapper is still fatal
+            "timed out waiting for confirmation: custom program error: 0x1",
+            "something nobody has seen before",
+        ] {
+            assert_eq!(classify_message(message), FailureKind::Fatal, "{}", message);
+        }
+        let budget: Box<dyn std::error::Error> = AntSolError::RpcBudget("connection budget spent".into()).into();
+        assert_eq!(classify_error(budget.as_ref()), FailureKind::Fatal);
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_a_cap() {
+        let settings = TxSettings::default();
+        assert_eq!(settings.backoff_for(1), Duration::from_millis(500));
+        assert_eq!(settings.backoff_for(2), Duration::from_secs(1));
+        assert_eq!(settings.backoff_for(3), Duration::from_secs(2));
+        assert_eq!(settings.backoff_for(30), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn priority_fee_parses_and_round_trips_through_toml() {
+        assert_eq!("auto".parse(), Ok(PriorityFee::Auto));
+        assert_eq!("5000".parse(), Ok(PriorityFee::MicroLamports(5000)));
+        assert!("-1".parse::<PriorityFee>().is_err());
+        assert!("fast".parse::<PriorityFee>().is_err());
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        struct Wrapper {
+            fee: PriorityFee,
+        }
+        for fee in [PriorityFee::Auto, PriorityFee::MicroLamports(25_000)] {
+            let text = toml::to_string(&Wrapper { fee }).unwrap();
+            assert_eq!(toml::from_str::<Wrapper>(&text).unwrap().fee, fee);
+        }
+        assert_eq!(toml::from_str::<Wrapper>("fee = \"1000\"").unwrap().fee, PriorityFee::MicroLamports(1000));
+        assert!(toml::from_str::<Wrapper>("fee = \"fast\"").is_err());
+    }
+
+    #[test]
+    fn auto_fee_pays_the_75th_percentile() {
+        assert_eq!(estimate_priority_fee(vec![]), 0);
+        assert_eq!(estimate_priority_fee(vec![0; 150]), 0);
+        assert_eq!(estimate_priority_fee(vec![400, 100, 300, 200]), 300);
+        assert_eq!(estimate_priority_fee((1..=100).collect()), 75);
+        assert_eq!(estimate_priority_fee(vec![7]), 7);
+    }
+
+    #[test]
+    fn fee_cost_rounds_up_to_whole_lamports() {
+        assert_eq!(priority_fee_lamports(0, COMPUTE_UNIT_LIMIT), 0);
+        assert_eq!(priority_fee_lamports(1, COMPUTE_UNIT_LIMIT), 1);
+        assert_eq!(priority_fee_lamports(500_000, COMPUTE_UNIT_LIMIT), 100_000);
+        assert_eq!(priority_fee_lamports(u64::MAX, u32::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn mainnet_estimates_a_fee_by_default() {
+        assert_eq!(default_priority_fee("https://api.mainnet-beta.solana.com"), PriorityFee::Auto);
+        assert_eq!(default_priority_fee("https://api.devnet.solana.com"), PriorityFee::MicroLamports(0));
+        assert_eq!(default_priority_fee("http://127.0.0.1:8899"), PriorityFee::MicroLamports(0));
+    }
+
+    fn instruction(program_id: &Pubkey, payer: &Pubkey, data_len: usize) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                solana_sdk::instruction::AccountMeta::new(*payer, true),
+                solana_sdk::instruction::AccountMeta::new(Pubkey::new_unique(), false),
+            ],
+            data: vec![7; data_len],
+        }
+    }
+
+    #[test]
+    fn size_estimate_matches_a_signed_transaction() {
+        use solana_sdk::signature::{Keypair, Signer};
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let instructions = vec![instruction(&program_id, &payer.pubkey(), 300), instruction(&program_id, &payer.pubkey(), 40)];
+        let transaction = solana_sdk::transaction::Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            solana_sdk::hash::Hash::default(),
+        );
+        assert_eq!(transaction_size(&instructions, &payer.pubkey()), bincode::serialize(&transaction).unwrap().len());
+    }
+
+    #[test]
+    fn packs_as_many_instructions_as_fit_in_order() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let prefix = [
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+        ];
+        let instructions: Vec<Instruction> =
+            [400, 400, 100, 700, 20, 20].iter().map(|&len| instruction(&program_id, &payer, len)).collect();
+
+        let batches = pack_instructions(&instructions, &prefix, &payer).unwrap();
+        assert_eq!(batches, vec![0..3, 3..6]);
+        for batch in &batches {
+            let mut all = prefix.to_vec();
+            all.extend_from_slice(&instructions[batch.clone()]);
+            assert!(transaction_size(&all, &payer) <= PACKET_DATA_SIZE);
+            // Greedy: the next instruction would not have fit
+            if batch.end < instructions.len() {
+                all.push(instructions[batch.end].clone());
+                assert!(transaction_size(&all, &payer) > PACKET_DATA_SIZE);
+            }
+        }
+
+        let small: Vec<Instruction> = (0..4).map(|_| instruction(&program_id, &payer, 10)).collect();
+        assert_eq!(pack_instructions(&small, &prefix, &payer).unwrap(), vec![0..4]);
+        assert_eq!(pack_instructions(&[], &prefix, &payer).unwrap(), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn an_instruction_too_large_alone_is_refused() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let instructions = vec![instruction(&program_id, &payer, 10), instruction(&program_id, &payer, PACKET_DATA_SIZE)];
+        let err = pack_instructions(&instructions, &[], &payer).unwrap_err();
+        assert!(err.to_string().starts_with("Instruction 1 needs a"), "{}", err);
+    }
+
+    #[test]
+    fn blamed_instruction_is_read_from_the_error() {
+        assert_eq!(failed_instruction("Transaction 5xyz failed: Error processing Instruction 3: custom program error: 0x1771"), Some(3));
+        assert_eq!(
+            failed_instruction("RPC response error -32002: Transaction simulation failed: Error processing Instruction 12: invalid account data"),
+            Some(12)
+        );
+        assert_eq!(failed_instruction("Transaction 5xyz was not confirmed within 60s"), None);
+    }
+
+    #[test]
+    fn compute_limit_leaves_headroom_up_to_the_cap() {
+        assert_eq!(compute_unit_limit(100_000), 120_000);
+        assert_eq!(compute_unit_limit(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+        assert_eq!(compute_unit_limit(u64::MAX), MAX_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn commitment_names_round_trip() {
+        for commitment in [Commitment::Processed, Commitment::Confirmed, Commitment::Finalized] {
+            assert_eq!(Commitment::parse(commitment.as_str()), Some(commitment));
+        }
+        assert_eq!(Commitment::parse("max"), None);
+        assert_eq!(Commitment::Finalized.config(), CommitmentConfig::finalized());
+    }
+}
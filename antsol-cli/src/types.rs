@@ -1,3 +1,4 @@
+use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 
@@ -17,9 +18,37 @@ pub struct PackageInfo {
     pub description: String,
     pub authors: Option<Vec<String>>,
     pub license: Option<String>,
+    /// Source repository URL (http or https), stored on-chain
+    pub repository: Option<String>,
+    /// Project homepage URL (http or https), stored on-chain
+    pub homepage: Option<String>,
+    /// Up to 5 search keywords, formatted like package names
+    pub keywords: Option<Vec<String>>,
 }
 
-/// Package dependency specification (other AntSol packages)
+impl PackageInfo {
+    /// The optional metadata as the registry program takes it
+    pub fn metadata(&self) -> PackageMetadata {
+        PackageMetadata {
+            repository: self.repository.clone().unwrap_or_default(),
+            homepage: self.homepage.clone().unwrap_or_default(),
+            keywords: self.keywords.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Optional package metadata stored on-chain; empty values mean "not set"
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PackageMetadata {
+    pub repository: String,
+    pub homepage: String,
+    pub keywords: Vec<String>,
+}
+
+/// Package dependency specification (other AntSol packages).
+///
+/// In `antsol.toml` `version` may be a semver range; publish pins it to an
+/// exact version before it goes on-chain.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Dependency {
     pub name: String,
@@ -38,7 +67,7 @@ pub struct ExternalDependency {
 }
 
 /// On-chain package account data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PackageAccount {
     pub name: String,
     pub version: String,
@@ -48,7 +77,58 @@ pub struct PackageAccount {
     pub description: String,
     pub dependencies: Vec<Dependency>,
     pub external_dependencies: Vec<ExternalDependency>,
+    /// Hex sha256 of the uploaded tar.gz; `None` for accounts published before checksums
+    pub content_sha256: Option<String>,
+    /// Empty for accounts published before metadata existed
+    pub metadata: PackageMetadata,
+    /// CID of the README.md pinned at publish; `None` without one or for older accounts
+    pub readme_cid: Option<String>,
+    /// Downloads counted on-chain with `record_download`; 0 for older accounts
+    pub downloads: u64,
+    /// Frozen with `freeze_package`: its authority is burned and it can no
+    /// longer be updated or transferred; false for older accounts
+    pub frozen: bool,
+}
+
+/// Lockfile (antsol.lock) pinning exactly what was installed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LockFile {
+    /// Lockfile format version
+    pub version: u32,
+    #[serde(default, rename = "package", skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// One installed package recorded in the lockfile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    pub ipfs_cid: String,
+    /// sha256 of the downloaded tarball
+    pub sha256: String,
+    /// [`tree_sha256`](crate::package_files::tree_sha256) of the extracted
+    /// files; absent in lockfiles written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files_sha256: Option<String>,
+    /// On-chain authority of the installed version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authority: Option<String>,
+    /// Direct dependencies as `name@version`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+    /// Range this version was picked from (`^1.2`); `None` when pinned exactly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    /// The archive came from the download cache rather than a gateway
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_cache: bool,
 }
 
-/// Result type for error handling
-pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+/// Result type for error handling.
+///
+/// Failures are boxed [`AntSolError`](crate::error::AntSolError)s wherever the
+/// CLI knows what went wrong; `error::error_kind` recovers the category (and
+/// exit code) from the box. Code that only produces `AntSolError` can say
+/// `Result<T, AntSolError>` and still `?` into callers using the default.
+pub type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
@@ -1,9 +1,24 @@
+use crate::error::AntSolError;
+use crate::permissions::{self, SecretFile};
+use crate::types::Result;
+use crate::validation::{self, MAX_VERSION_LENGTH};
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
+use solana_sdk::signature::Keypair;
+use std::cmp::Ordering;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
-/// Create a spinner progress indicator
+/// Create a spinner progress indicator (hidden with `--quiet`)
 pub fn create_spinner(msg: &str) -> ProgressBar {
+    if quiet() {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new_spinner();
     pb.set_style(
         ProgressStyle::default_spinner()
@@ -16,8 +31,11 @@ pub fn create_spinner(msg: &str) -> ProgressBar {
     pb
 }
 
-/// Create a progress bar
+/// Create a progress bar (hidden with `--quiet`)
 pub fn create_progress_bar(total: u64, msg: &str) -> ProgressBar {
+    if quiet() {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new(total);
     pb.set_style(
         ProgressStyle::default_bar()
@@ -29,9 +47,45 @@ pub fn create_progress_bar(total: u64, msg: &str) -> ProgressBar {
     pb
 }
 
+/// What `--output` asked stdout to carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Colored text for people
+    Human,
+    /// A single JSON document per command, for scripts and CI
+    Json,
+}
+
+static OUTPUT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Choose the output format for this invocation; only the first call counts
+pub fn set_output_format(format: OutputFormat) {
+    let _ = OUTPUT_FORMAT.set(format);
+}
+
+/// Whether stdout is reserved for a JSON document (`--output json`)
+pub fn json_output() -> bool {
+    OUTPUT_FORMAT.get() == Some(&OutputFormat::Json)
+}
+
+/// Write a command's result to stdout as pretty JSON
+pub fn emit_json<T: serde::Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// Print a status line: stdout normally, stderr when stdout carries JSON
+pub fn print_status(line: String) {
+    if json_output() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
 /// Print success message
 pub fn print_success(msg: &str) {
-    println!("\n{} {}", "✓".green().bold(), msg.green());
+    print_status(format!("\n{} {}", "✓".green().bold(), msg.green()));
 }
 
 /// Print error message
@@ -39,26 +93,113 @@ pub fn print_error(msg: &str) {
     eprintln!("\n{} {}", "✗".red().bold(), msg.red());
 }
 
-/// Print info message
+/// Print info message (not with `--quiet`)
 pub fn print_info(msg: &str) {
-    println!("{} {}", "ℹ".blue().bold(), msg);
+    if !quiet() {
+        print_status(format!("{} {}", "ℹ".blue().bold(), msg));
+    }
 }
 
 /// Print warning message
 pub fn print_warning(msg: &str) {
-    println!("{} {}", "⚠".yellow().bold(), msg.yellow());
+    print_status(format!("{} {}", "⚠".yellow().bold(), msg.yellow()));
 }
 
-/// Validate package name (lowercase alphanumeric and hyphens only)
+/// Print a section heading, preceded by a blank line (not with `--quiet`)
+pub fn print_heading(heading: impl std::fmt::Display) {
+    if !quiet() {
+        print_status(format!("\n{}", heading));
+    }
+}
+
+/// Print a tips section: a heading and one indented line per tip (not with
+/// `--quiet`)
+pub fn print_tips<T: std::fmt::Display>(heading: impl std::fmt::Display, tips: impl IntoIterator<Item = T>) {
+    if quiet() {
+        return;
+    }
+    print_status(format!("\n{}", heading));
+    for tip in tips {
+        print_status(format!("  {}", tip));
+    }
+}
+
+static QUIET: OnceLock<bool> = OnceLock::new();
+
+/// Print only each command's result line: no banner, spinners, info lines
+/// or tips (`--quiet`)
+pub fn enable_quiet() {
+    let _ = QUIET.set(true);
+}
+
+/// Whether `--quiet` was given
+pub fn quiet() -> bool {
+    QUIET.get().copied().unwrap_or(false)
+}
+
+/// Log `tracing::debug!` events from this crate to stderr (`--verbose`):
+/// RPC endpoints and methods, simulation logs, IPFS gateway attempts
+pub fn enable_verbose() {
+    let filter = tracing_subscriber::filter::Targets::new().with_target(env!("CARGO_CRATE_NAME"), tracing::Level::DEBUG);
+    let layer = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .without_time()
+        .with_target(false)
+        .with_ansi(colored::control::SHOULD_COLORIZE.should_colorize());
+    let _ = tracing_subscriber::registry().with(layer).with(filter).try_init();
+}
+
+/// Turn colors off when `NO_COLOR` is set or stdout is not a terminal,
+/// unless `CLICOLOR_FORCE` asks for them anyway
+pub fn configure_colors() {
+    let forced = std::env::var_os("CLICOLOR_FORCE").is_some_and(|v| v != "0");
+    if !forced && (std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) || !std::io::stdout().is_terminal()) {
+        colored::control::set_override(false);
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    }
+}
+
+/// Validate package name: lowercase alphanumerics and hyphens, optionally
+/// under one `scope/` prefix, as the registry program accepts it
 pub fn validate_package_name(name: &str) -> bool {
-    let re = regex::Regex::new(r"^[a-z0-9-]+$").unwrap();
-    re.is_match(name) && name.len() <= 64
+    validation::is_valid_package_name(name)
 }
 
-/// Validate semantic version format
+/// Validate semantic version format (semver 2.0, prerelease and build
+/// metadata included) within the program's length limit
 pub fn validate_version(version: &str) -> bool {
-    let re = regex::Regex::new(r"^\d+\.\d+\.\d+$").unwrap();
-    re.is_match(version) && version.len() <= 16
+    version.len() <= MAX_VERSION_LENGTH && semver::Version::parse(version).is_ok()
+}
+
+/// Semver precedence, as the registry program orders updates: numeric core,
+/// then a release above its prereleases. Build metadata is ignored.
+fn cmp_precedence(a: &semver::Version, b: &semver::Version) -> Ordering {
+    (a.major, a.minor, a.patch).cmp(&(b.major, b.minor, b.patch)).then_with(|| a.pre.cmp(&b.pre))
+}
+
+/// Compare two versions by semver precedence (so 1.10.0 > 1.9.0 and
+/// 1.0.0 > 1.0.0-rc.1). Unparseable versions sort below every valid one.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(a), Ok(b)) => cmp_precedence(&a, &b),
+        (Ok(_), Err(_)) => Ordering::Greater,
+        (Err(_), Ok(_)) => Ordering::Less,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+/// Pick the highest valid semantic version from a list. Prereleases are only
+/// picked when there is no release at all.
+pub fn latest_version<'a, I>(versions: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    versions
+        .into_iter()
+        .filter_map(|v| semver::Version::parse(v).ok().map(|parsed| (v, parsed)))
+        .max_by(|(_, a), (_, b)| a.pre.is_empty().cmp(&b.pre.is_empty()).then_with(|| cmp_precedence(a, b)))
+        .map(|(v, _)| v.to_string())
 }
 
 /// Parse package specification (name@version or just name)
@@ -71,3 +212,448 @@ pub fn parse_package_spec(spec: &str) -> (String, Option<String>) {
         (spec.to_string(), None)
     }
 }
+
+/// Load a Solana keypair JSON file (array of 64 bytes).
+/// Group/world-accessible files are flagged per the permission policy.
+pub fn load_keypair(path: &Path) -> Result<Keypair> {
+    if path.exists() {
+        permissions::check(path, SecretFile::Wallet)?;
+    }
+    let keypair_bytes = std::fs::read(path).map_err(|e| {
+        AntSolError::Wallet(format!("Could not read wallet file {}: {}", path.display(), e))
+    })?;
+    let keypair_vec: Vec<u8> = serde_json::from_slice(&keypair_bytes).map_err(|e| {
+        AntSolError::Wallet(format!("Could not parse keypair {}: {}", path.display(), e))
+    })?;
+    let keypair = Keypair::from_bytes(&keypair_vec)
+        .map_err(|e| AntSolError::Wallet(format!("Invalid keypair format: {}", e)))?;
+    Ok(keypair)
+}
+
+/// Write `keypair` as a Solana CLI-compatible JSON byte array, readable by the
+/// owner only. Refuses to overwrite an existing file.
+pub fn write_keypair(path: &Path, keypair: &Keypair) -> Result<()> {
+    write_secret_file(path, serde_json::to_string(&keypair.to_bytes().to_vec())?.as_bytes())
+}
+
+/// Create `path` readable by the owner only and write a wallet secret to it.
+/// Refuses to overwrite an existing file.
+pub fn write_secret_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(|e| {
+        AntSolError::Wallet(format!("Could not create wallet file {}: {}", path.display(), e))
+    })?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+/// Whether an RPC URL points at mainnet-beta, where faucets don't exist and
+/// real funds are at stake
+pub fn is_mainnet_rpc(rpc_url: &str) -> bool {
+    let host = reqwest::Url::parse(rpc_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_lowercase))
+        .unwrap_or_else(|| rpc_url.to_lowercase());
+    host.contains("mainnet")
+}
+
+/// Solana cluster an RPC URL belongs to, as far as its host tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    /// A validator on this machine
+    Localnet,
+    /// Any other RPC endpoint
+    Custom,
+}
+
+impl Cluster {
+    pub fn detect(rpc_url: &str) -> Self {
+        if is_mainnet_rpc(rpc_url) {
+            return Cluster::Mainnet;
+        }
+        let host = reqwest::Url::parse(rpc_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .unwrap_or_default();
+        if host.contains("devnet") {
+            Cluster::Devnet
+        } else if host.contains("testnet") {
+            Cluster::Testnet
+        } else if matches!(host.as_str(), "localhost" | "127.0.0.1" | "[::1]") {
+            Cluster::Localnet
+        } else {
+            Cluster::Custom
+        }
+    }
+    
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Cluster::Mainnet => "mainnet",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+            Cluster::Custom => "custom",
+        }
+    }
+}
+
+/// Solana Explorer link for `path` (`tx/<signature>` or `address/<pubkey>`)
+/// on the cluster `rpc_url` belongs to; local and custom endpoints are
+/// passed as `customUrl`
+pub fn explorer_url(rpc_url: &str, path: &str) -> String {
+    let mut url = reqwest::Url::parse("https://explorer.solana.com/")
+        .and_then(|base| base.join(path))
+        .expect("explorer paths are relative URLs");
+    match Cluster::detect(rpc_url) {
+        Cluster::Mainnet => {}
+        Cluster::Devnet => {
+            url.query_pairs_mut().append_pair("cluster", "devnet");
+        }
+        Cluster::Testnet => {
+            url.query_pairs_mut().append_pair("cluster", "testnet");
+        }
+        Cluster::Localnet | Cluster::Custom => {
+            url.query_pairs_mut().append_pair("cluster", "custom").append_pair("customUrl", rpc_url);
+        }
+    }
+    url.to_string()
+}
+
+/// Hex-encoded sha256 digest
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Decode a hex sha256 digest into raw bytes
+pub fn decode_sha256_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// Human-readable byte count, e.g. "4.5 KB"
+pub fn format_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    let b = bytes as f64;
+    if b >= MB {
+        format!("{:.1} MB", b / MB)
+    } else if b >= KB {
+        format!("{:.1} KB", b / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Parse a size such as `50MB`, `512KB`, `1.5GB` or a plain byte count,
+/// using the same 1024-based units as [`format_size`]
+pub fn parse_size(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "K" | "KB" | "KIB" => 1024.0,
+        "M" | "MB" | "MIB" => 1024.0 * 1024.0,
+        "G" | "GB" | "GIB" => 1024.0 * 1024.0 * 1024.0,
+        other => return Err(format!("unknown unit '{}' in '{}' (expected B, KB, MB or GB)", other, value)),
+    };
+    match number.parse::<f64>() {
+        Ok(n) if n.is_finite() && n * multiplier >= 1.0 => Ok((n * multiplier) as u64),
+        _ => Err(format!("'{}' is not a positive size", value)),
+    }
+}
+
+/// Pager used when neither `$ANTSOL_PAGER` nor `$PAGER` is set
+pub const DEFAULT_PAGER: &str = "less -FRX";
+
+static PAGER_DISABLED: OnceLock<bool> = OnceLock::new();
+
+/// Turn paging off for this invocation (`--no-pager`, JSON or quiet output)
+pub fn disable_pager() {
+    let _ = PAGER_DISABLED.set(true);
+}
+
+fn pager_disabled() -> bool {
+    PAGER_DISABLED.get().copied().unwrap_or(false)
+}
+
+/// Pick the pager command: `$ANTSOL_PAGER`, then `$PAGER`, then [`DEFAULT_PAGER`].
+/// An empty value or `cat` means "don't page".
+pub fn resolve_pager(antsol_pager: Option<String>, pager: Option<String>) -> Option<String> {
+    let command = antsol_pager.or(pager).unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command = command.trim();
+    if command.is_empty() || command == "cat" {
+        None
+    } else {
+        Some(command.to_string())
+    }
+}
+
+/// Page only output that wouldn't fit on an interactive terminal
+pub fn should_page(disabled: bool, is_tty: bool, lines: usize, terminal_height: Option<usize>) -> bool {
+    match terminal_height {
+        Some(height) => !disabled && is_tty && lines > height,
+        None => false,
+    }
+}
+
+/// Stdout for long listings: buffers everything written to it, then on
+/// [`PagedOutput::finish`] either prints it or pipes it through the pager
+#[derive(Default)]
+pub struct PagedOutput {
+    buffer: Vec<u8>,
+}
+
+impl PagedOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show the buffered output. A reader quitting the pager (or closing a
+    /// pipe) early is not an error.
+    pub fn finish(self) -> Result<()> {
+        let stdout = std::io::stdout();
+        let lines = self.buffer.iter().filter(|&&b| b == b'\n').count();
+        let height = console::Term::stdout().size_checked().map(|(rows, _)| rows as usize);
+        let pager = resolve_pager(std::env::var("ANTSOL_PAGER").ok(), std::env::var("PAGER").ok());
+
+        if let Some(pager) = pager.filter(|_| should_page(pager_disabled(), stdout.is_terminal(), lines, height)) {
+            if run_pager(&pager, &self.buffer)? {
+                return Ok(());
+            }
+        }
+        ignore_broken_pipe(stdout.lock().write_all(&self.buffer).and_then(|_| std::io::stdout().flush()))?;
+        Ok(())
+    }
+}
+
+impl Write for PagedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Feed `content` to `command`'s stdin and wait for it to exit.
+/// Returns `false` when the pager program doesn't exist, so the caller can
+/// print directly instead.
+pub fn run_pager(command: &str, content: &[u8]) -> std::io::Result<bool> {
+    let mut parts = command.split_whitespace();
+    let Some(program) = parts.next() else {
+        return Ok(false);
+    };
+    let mut child = match Command::new(program).args(parts).stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        ignore_broken_pipe(stdin.write_all(content))?;
+        // stdin drops here so the pager sees EOF
+    }
+    child.wait()?;
+    Ok(true)
+}
+
+fn ignore_broken_pipe(result: std::io::Result<()>) -> std::io::Result<()> {
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_accepts_units() {
+        assert_eq!(parse_size("50MB"), Ok(50 * 1024 * 1024));
+        assert_eq!(parse_size("1.5 gb"), Ok(1536 * 1024 * 1024));
+        assert_eq!(parse_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_size("4096"), Ok(4096));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("10TB").is_err());
+        assert!(parse_size("big").is_err());
+    }
+
+    #[test]
+    fn compare_versions_is_numeric() {
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("2.0.0", "10.0.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.0.10", "0.0.9"), Ordering::Greater);
+        assert_eq!(compare_versions("garbage", "0.0.1"), Ordering::Less);
+    }
+
+    #[test]
+    fn compare_versions_follows_semver_precedence() {
+        // semver.org section 11, each lower than the next
+        let ordered = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1-0",
+            "1.0.1-1",
+            "1.0.1-a",
+            "1.0.1",
+            "1.10.0-rc.1",
+            "1.10.0",
+        ];
+        for (i, a) in ordered.iter().enumerate() {
+            for (j, b) in ordered.iter().enumerate() {
+                assert_eq!(compare_versions(a, b), i.cmp(&j), "{} vs {}", a, b);
+            }
+        }
+        // Build metadata never changes precedence
+        assert_eq!(compare_versions("1.0.0+build.1", "1.0.0+build.2"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-rc.1+x", "1.0.0-rc.1"), Ordering::Equal);
+    }
+
+    #[test]
+    fn validate_version_accepts_full_semver() {
+        for valid in ["1.0.0", "0.0.1", "1.0.0-rc.1", "1.0.0-alpha.beta-2", "1.0.0+build.7", "1.0.0-0+sha.abc"] {
+            assert!(validate_version(valid), "{}", valid);
+        }
+        for invalid in ["1.0", "01.0.0", "1.0.0-", "1.0.0-01", "1.0.0-rc..1", "1.0.0+", "v1.0.0", &format!("1.0.0-{}", "a".repeat(27))] {
+            assert!(!validate_version(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn latest_version_picks_highest_semver() {
+        assert_eq!(latest_version(["1.9.0", "1.10.0", "1.2.0"]), Some("1.10.0".to_string()));
+        assert_eq!(latest_version(["0.1.0", "not-a-version"]), Some("0.1.0".to_string()));
+        assert_eq!(latest_version(["latest"]), None);
+        assert_eq!(latest_version(["1.0.0-rc.1", "1.0.0", "0.9.0"]), Some("1.0.0".to_string()));
+        // A release beats a newer prerelease; prereleases only win when alone
+        assert_eq!(latest_version(["1.0.0", "2.0.0-beta.1"]), Some("1.0.0".to_string()));
+        assert_eq!(latest_version(["2.0.0-beta.2", "2.0.0-beta.10"]), Some("2.0.0-beta.10".to_string()));
+        assert_eq!(latest_version(Vec::<&str>::new()), None);
+    }
+
+    #[test]
+    fn explorer_links_follow_the_cluster() {
+        assert_eq!(explorer_url("https://api.devnet.solana.com", "tx/abc"), "https://explorer.solana.com/tx/abc?cluster=devnet");
+        assert_eq!(explorer_url("https://api.mainnet-beta.solana.com", "address/Pk1"), "https://explorer.solana.com/address/Pk1");
+        assert_eq!(explorer_url("https://api.testnet.solana.com", "tx/abc"), "https://explorer.solana.com/tx/abc?cluster=testnet");
+        assert_eq!(
+            explorer_url("http://127.0.0.1:8899", "tx/abc"),
+            "https://explorer.solana.com/tx/abc?cluster=custom&customUrl=http%3A%2F%2F127.0.0.1%3A8899"
+        );
+        assert_eq!(Cluster::detect("http://localhost:8899"), Cluster::Localnet);
+        assert_eq!(Cluster::detect("https://rpc.example.com"), Cluster::Custom);
+    }
+
+    #[test]
+    fn mainnet_rpc_urls_are_recognised() {
+        assert!(is_mainnet_rpc("https://api.mainnet-beta.solana.com"));
+        assert!(is_mainnet_rpc("https://solana-mainnet.g.alchemy.com/v2/key"));
+        assert!(is_mainnet_rpc("HTTPS://API.MAINNET-BETA.SOLANA.COM/"));
+        assert!(!is_mainnet_rpc("https://api.devnet.solana.com"));
+        assert!(!is_mainnet_rpc("https://api.testnet.solana.com"));
+        assert!(!is_mainnet_rpc("http://127.0.0.1:8899"));
+        // Only the host counts, not paths or query strings
+        assert!(!is_mainnet_rpc("https://api.devnet.solana.com/?from=mainnet"));
+    }
+
+    #[test]
+    fn written_keypair_loads_back_and_is_not_overwritten() {
+        use solana_sdk::signature::Signer;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+        let keypair = Keypair::new();
+
+        write_keypair(&path, &keypair).unwrap();
+        assert_eq!(load_keypair(&path).unwrap().pubkey(), keypair.pubkey());
+        assert!(write_keypair(&path, &Keypair::new()).is_err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn sha256_hex_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"hello world\n"),
+            "a948904f2f0f479b8f8197694b30184b0d2ed1c1cd2a1ec0fb85d299a192a447"
+        );
+    }
+
+    #[test]
+    fn decode_sha256_hex_round_trips() {
+        let hex = sha256_hex(b"hello world\n");
+        let bytes = decode_sha256_hex(&hex).unwrap();
+        let encoded: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(encoded, hex);
+        assert_eq!(decode_sha256_hex("abc"), None);
+        assert_eq!(decode_sha256_hex(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn format_size_picks_unit() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(4608), "4.5 KB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MB");
+    }
+
+    #[test]
+    fn pages_only_long_output_on_a_terminal() {
+        assert!(should_page(false, true, 120, Some(40)));
+        assert!(!should_page(false, true, 40, Some(40)));
+        assert!(!should_page(false, false, 120, Some(40)));
+        assert!(!should_page(true, true, 120, Some(40)));
+        assert!(!should_page(false, true, 120, None));
+    }
+
+    #[test]
+    fn pager_env_precedence() {
+        assert_eq!(resolve_pager(Some("most".into()), Some("more".into())).as_deref(), Some("most"));
+        assert_eq!(resolve_pager(None, Some("more".into())).as_deref(), Some("more"));
+        assert_eq!(resolve_pager(None, None).as_deref(), Some(DEFAULT_PAGER));
+        assert_eq!(resolve_pager(Some("".into()), Some("more".into())), None);
+        assert_eq!(resolve_pager(None, Some("cat".into())), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pager_quitting_early_is_not_an_error() {
+        // `true` exits without reading, like a user pressing q straight away
+        let content = "line\n".repeat(100_000);
+        assert!(run_pager("true", content.as_bytes()).unwrap());
+        // `dd` reads a single byte and quits mid-stream, without printing to the test output
+        assert!(run_pager("dd bs=1 count=1 of=/dev/null status=none", content.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn missing_pager_falls_back() {
+        assert!(!run_pager("antsol-no-such-pager -R", b"text\n").unwrap());
+    }
+}
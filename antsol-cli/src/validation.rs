@@ -0,0 +1,599 @@
+use crate::types::{Dependency, ExternalDependency, PackageMetadata};
+use serde::Serialize;
+
+// Limits and rules mirror antsol-registry's `state` module and the checks in
+// its publish_package/update_package handlers. Keep them in sync so a dry run
+// fails exactly where the program would.
+
+/// Maximum length for package name, scope included (it is a PDA seed)
+pub const MAX_NAME_LENGTH: usize = 32;
+/// Maximum length for the scope of a `scope/name` package
+pub const MAX_SCOPE_LENGTH: usize = 32;
+/// Maximum number of publishers a namespace lists besides its owner
+pub const MAX_NAMESPACE_PUBLISHERS: usize = 10;
+/// Maximum number of maintainers a package lists besides its authority
+pub const MAX_MAINTAINERS: usize = 5;
+/// Maximum length for version string (semver, including prerelease and build metadata)
+pub const MAX_VERSION_LENGTH: usize = 32;
+/// Maximum length for IPFS CID
+pub const MAX_CID_LENGTH: usize = 64;
+/// Maximum length for description
+pub const MAX_DESCRIPTION_LENGTH: usize = 256;
+/// Maximum number of dependencies
+pub const MAX_DEPENDENCIES: usize = 10;
+/// Maximum length for the repository URL
+pub const MAX_REPOSITORY_LENGTH: usize = 128;
+/// Maximum length for the homepage URL
+pub const MAX_HOMEPAGE_LENGTH: usize = 128;
+/// Maximum number of keywords
+pub const MAX_KEYWORDS: usize = 5;
+/// Maximum length for a single keyword
+pub const MAX_KEYWORD_LENGTH: usize = 20;
+/// Maximum number of external (crates.io, npm, ...) dependencies
+pub const MAX_EXTERNAL_DEPENDENCIES: usize = 10;
+/// Maximum length for an external dependency's name
+pub const MAX_EXTERNAL_NAME_LENGTH: usize = 64;
+/// Maximum length for an external dependency's version requirement
+pub const MAX_EXTERNAL_VERSION_LENGTH: usize = 32;
+/// Maximum length for an external dependency's registry
+pub const MAX_REGISTRY_LENGTH: usize = 64;
+/// Ecosystems an external dependency can come from
+pub const EXTERNAL_DEPENDENCY_TYPES: [&str; 4] = ["rust", "npm", "python", "go"];
+/// Length of the longest entry in [`EXTERNAL_DEPENDENCY_TYPES`]
+const MAX_DEPENDENCY_TYPE_LENGTH: usize = 6;
+
+/// `PackageDependency::LEN` in the program
+const DEPENDENCY_LEN: usize = 4 + MAX_NAME_LENGTH + 4 + MAX_VERSION_LENGTH;
+/// `ExternalDependency::LEN` in the program
+const EXTERNAL_DEPENDENCY_LEN: usize =
+    4 + MAX_EXTERNAL_NAME_LENGTH + 4 + MAX_EXTERNAL_VERSION_LENGTH + 4 + MAX_DEPENDENCY_TYPE_LENGTH + 1 + 4 + MAX_REGISTRY_LENGTH;
+
+/// `Package::MAX_SPACE`: the largest account a publish can create
+pub const PACKAGE_MAX_SPACE: usize = 8 // discriminator
+    + 4 + MAX_NAME_LENGTH
+    + 4 + MAX_VERSION_LENGTH
+    + 32 // authority
+    + 4 + MAX_CID_LENGTH
+    + 8 // published_at
+    + 4 + MAX_DESCRIPTION_LENGTH
+    + 4 + MAX_DEPENDENCIES * DEPENDENCY_LEN
+    + 1 // bump
+    + 32 // content_sha256
+    + 4 + MAX_REPOSITORY_LENGTH
+    + 4 + MAX_HOMEPAGE_LENGTH
+    + 4 + MAX_KEYWORDS * (4 + MAX_KEYWORD_LENGTH)
+    + 1 + 4 + MAX_CID_LENGTH // readme_cid
+    + 4 + MAX_EXTERNAL_DEPENDENCIES * EXTERNAL_DEPENDENCY_LEN
+    + 8 // downloads
+    + 1; // frozen
+
+/// `Package::space_for`: the program sizes each account to exactly its contents.
+/// Pass [`MAX_CID_LENGTH`] as `cid_len` (and `readme_cid_len`, when there is
+/// a README) to estimate before the upload.
+#[allow(clippy::too_many_arguments)]
+pub fn package_space(
+    name: &str,
+    version: &str,
+    cid_len: usize,
+    description: &str,
+    dependencies: &[Dependency],
+    metadata: &PackageMetadata,
+    readme_cid_len: Option<usize>,
+    external_dependencies: &[ExternalDependency],
+) -> usize {
+    8 // discriminator
+        + 4 + name.len()
+        + 4 + version.len()
+        + 32 // authority
+        + 4 + cid_len
+        + 8 // published_at
+        + 4 + description.len()
+        + 4 + dependencies.iter().map(|d| 4 + d.name.len() + 4 + d.version.len()).sum::<usize>()
+        + 1 // bump
+        + 32 // content_sha256
+        + 4 + metadata.repository.len()
+        + 4 + metadata.homepage.len()
+        + 4 + metadata.keywords.iter().map(|k| 4 + k.len()).sum::<usize>()
+        + 1 + readme_cid_len.map_or(0, |len| 4 + len)
+        + 4 + external_dependencies
+            .iter()
+            .map(|d| 4 + d.name.len() + 4 + d.version.len() + 4 + d.dep_type.len() + 1 + d.registry.as_ref().map_or(0, |r| 4 + r.len()))
+            .sum::<usize>()
+        + 8 // downloads
+        + 1 // frozen
+}
+
+/// A check the registry program would fail, named after its `RegistryError` variant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Violation {
+    fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// Every check publish_package runs on its arguments, collected rather than
+/// stopping at the first failure
+pub fn check_package(name: &str, version: &str, description: &str, dependencies: &[Dependency]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if name.is_empty() {
+        violations.push(Violation::new("NameEmpty", "Package name is empty"));
+    } else if name.len() > MAX_NAME_LENGTH {
+        violations.push(Violation::new("NameTooLong", format!("Package name is {} characters (max {})", name.len(), MAX_NAME_LENGTH)));
+    } else if !is_valid_package_name(name) {
+        violations.push(Violation::new(
+            "InvalidNameFormat",
+            format!(
+                "Package name '{}' must be lowercase alphanumeric and hyphens, not starting or ending with a hyphen, with an optional 'scope/' prefix",
+                name
+            ),
+        ));
+    }
+
+    if version.is_empty() {
+        violations.push(Violation::new("VersionEmpty", "Version is empty"));
+    } else if version.len() > MAX_VERSION_LENGTH {
+        violations.push(Violation::new("VersionTooLong", format!("Version is {} characters (max {})", version.len(), MAX_VERSION_LENGTH)));
+    } else if !is_valid_semver(version) {
+        violations.push(Violation::new("InvalidVersionFormat", format!("Version '{}' must be X.Y.Z[-prerelease][+build]", version)));
+    }
+
+    if description.len() > MAX_DESCRIPTION_LENGTH {
+        violations.push(Violation::new(
+            "DescriptionTooLong",
+            format!("Description is {} bytes (max {})", description.len(), MAX_DESCRIPTION_LENGTH),
+        ));
+    }
+
+    if dependencies.len() > MAX_DEPENDENCIES {
+        violations.push(Violation::new(
+            "TooManyDependencies",
+            format!("{} dependencies (max {})", dependencies.len(), MAX_DEPENDENCIES),
+        ));
+    }
+    for dep in dependencies {
+        violations.extend(check_dependency(dep));
+    }
+
+    violations
+}
+
+/// The checks publish_package runs on each dependency's name and exact version
+pub fn check_dependency(dep: &Dependency) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    if dep.name.is_empty() || dep.name.len() > MAX_NAME_LENGTH || !is_valid_package_name(&dep.name) {
+        violations.push(Violation::new("InvalidDependencyName", format!("Dependency name '{}' is invalid", dep.name)));
+    }
+    if dep.version.is_empty() || dep.version.len() > MAX_VERSION_LENGTH || !is_valid_semver(&dep.version) {
+        violations.push(Violation::new(
+            "InvalidDependencyVersion",
+            format!("Dependency {} has invalid version '{}' (must be X.Y.Z[-prerelease][+build])", dep.name, dep.version),
+        ));
+    }
+    violations
+}
+
+/// The checks publish_package and update_package run on the optional metadata
+pub fn check_metadata(metadata: &PackageMetadata) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    for (field, url, max, too_long) in [
+        ("Repository", &metadata.repository, MAX_REPOSITORY_LENGTH, "RepositoryTooLong"),
+        ("Homepage", &metadata.homepage, MAX_HOMEPAGE_LENGTH, "HomepageTooLong"),
+    ] {
+        if url.len() > max {
+            violations.push(Violation::new(too_long, format!("{} URL is {} bytes (max {})", field, url.len(), max)));
+        } else if !url.is_empty() && !is_valid_url(url) {
+            violations.push(Violation::new("InvalidUrl", format!("{} '{}' must be an http:// or https:// URL", field, url)));
+        }
+    }
+
+    if metadata.keywords.len() > MAX_KEYWORDS {
+        violations.push(Violation::new(
+            "TooManyKeywords",
+            format!("{} keywords (max {})", metadata.keywords.len(), MAX_KEYWORDS),
+        ));
+    }
+    for keyword in &metadata.keywords {
+        if keyword.len() > MAX_KEYWORD_LENGTH || !is_valid_name_segment(keyword) {
+            violations.push(Violation::new(
+                "InvalidKeyword",
+                format!("Keyword '{}' must be 1-{} lowercase alphanumeric characters and hyphens", keyword, MAX_KEYWORD_LENGTH),
+            ));
+        }
+    }
+
+    violations
+}
+
+/// The checks publish_package and update_package run on the external
+/// dependencies. Names, versions and registries are free-form in their own
+/// ecosystems, so the program only asks for printable ASCII without `;` or `|`.
+pub fn check_external_dependencies(dependencies: &[ExternalDependency]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if dependencies.len() > MAX_EXTERNAL_DEPENDENCIES {
+        violations.push(Violation::new(
+            "TooManyExternalDependencies",
+            format!("{} external dependencies (max {})", dependencies.len(), MAX_EXTERNAL_DEPENDENCIES),
+        ));
+    }
+    for dep in dependencies {
+        if !is_loggable(&dep.name, MAX_EXTERNAL_NAME_LENGTH) {
+            violations.push(Violation::new(
+                "InvalidExternalDependencyName",
+                format!("External dependency name '{}' must be 1-{} printable characters without spaces, ';' or '|'", dep.name, MAX_EXTERNAL_NAME_LENGTH),
+            ));
+        }
+        if !is_loggable(&dep.version, MAX_EXTERNAL_VERSION_LENGTH) {
+            violations.push(Violation::new(
+                "InvalidExternalDependencyVersion",
+                format!(
+                    "External dependency {} has version '{}' (must be 1-{} printable characters without spaces, ';' or '|')",
+                    dep.name, dep.version, MAX_EXTERNAL_VERSION_LENGTH
+                ),
+            ));
+        }
+        if !EXTERNAL_DEPENDENCY_TYPES.contains(&dep.dep_type.as_str()) {
+            violations.push(Violation::new(
+                "InvalidExternalDependencyType",
+                format!("External dependency {} has type '{}' (expected one of {})", dep.name, dep.dep_type, EXTERNAL_DEPENDENCY_TYPES.join(", ")),
+            ));
+        }
+        if let Some(registry) = dep.registry.as_deref().filter(|r| !is_loggable(r, MAX_REGISTRY_LENGTH)) {
+            violations.push(Violation::new(
+                "InvalidExternalDependencyRegistry",
+                format!(
+                    "External dependency {} has registry '{}' (must be 1-{} printable characters without spaces, ';' or '|')",
+                    dep.name, registry, MAX_REGISTRY_LENGTH
+                ),
+            ));
+        }
+    }
+
+    violations
+}
+
+/// The program's rule for values it logs in `;`- and `|`-separated lists
+fn is_loggable(value: &str, max: usize) -> bool {
+    !value.is_empty() && value.len() <= max && value.bytes().all(|b| b.is_ascii_graphic() && b != b';' && b != b'|')
+}
+
+/// The checks publish_package and update_package run on the archive CID and
+/// the optional README CID
+pub fn check_cids(ipfs_cid: &str, readme_cid: Option<&str>) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    if ipfs_cid.is_empty() {
+        violations.push(Violation::new("CidEmpty", "IPFS CID is empty"));
+    } else if ipfs_cid.len() > MAX_CID_LENGTH {
+        violations.push(Violation::new("CidTooLong", format!("IPFS CID is {} characters (max {})", ipfs_cid.len(), MAX_CID_LENGTH)));
+    } else if !is_valid_cid(ipfs_cid) {
+        violations.push(Violation::new("InvalidCidFormat", format!("'{}' is not a valid CIDv0 or CIDv1", ipfs_cid)));
+    }
+    if let Some(cid) = readme_cid.filter(|cid| !is_valid_cid(cid)) {
+        violations.push(Violation::new("InvalidReadmeCid", format!("README CID '{}' is not a valid CIDv0 or CIDv1", cid)));
+    }
+
+    violations
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Multihash code of sha2-256, the only hash CIDv0 allows
+const SHA2_256: u64 = 0x12;
+/// Longest multihash digest the program accepts
+const MAX_DIGEST_LENGTH: u64 = 64;
+
+/// The program's `cid::is_valid_cid`: a 46-character base58 CIDv0 (`Qm...`),
+/// or a CIDv1 in base32 (`b...`) or base58btc (`z...`) with any codec and a
+/// multihash whose digest fills the rest
+pub fn is_valid_cid(cid: &str) -> bool {
+    if cid.is_empty() || cid.len() > MAX_CID_LENGTH { return false; }
+    if cid.len() == 46 && cid.starts_with("Qm") {
+        return decode_base58(cid.as_bytes()).is_some_and(|bytes| parse_multihash(&bytes) == Some(SHA2_256));
+    }
+
+    let (prefix, rest) = cid.as_bytes().split_at(1);
+    let decoded = match prefix[0] {
+        b'b' => decode_base32(rest),
+        b'z' => decode_base58(rest),
+        _ => None,
+    };
+    let Some(decoded) = decoded else { return false };
+    let mut bytes = decoded.as_slice();
+    read_varint(&mut bytes) == Some(1) && read_varint(&mut bytes).is_some() && parse_multihash(bytes).is_some()
+}
+
+/// The hash code of a multihash that fills `bytes` exactly; sha2-256 digests must be 32 bytes
+fn parse_multihash(mut bytes: &[u8]) -> Option<u64> {
+    let code = read_varint(&mut bytes)?;
+    let len = read_varint(&mut bytes)?;
+    if len == 0 || len > MAX_DIGEST_LENGTH || len as usize != bytes.len() { return None; }
+    if code == SHA2_256 && len != 32 { return None; }
+    Some(code)
+}
+
+/// Unsigned LEB128 as multiformats uses it: at most 9 bytes, minimally encoded
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *bytes.get(i)?;
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 { return None; }
+            *bytes = &bytes[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Base58btc, capped at `MAX_CID_LENGTH` bytes like the program's fixed buffer
+fn decode_base58(input: &[u8]) -> Option<Vec<u8>> {
+    if input.is_empty() { return None; }
+    let mut out: Vec<u8> = Vec::new();
+    for &c in input {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in out.iter_mut() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    out.extend(input.iter().take_while(|&&c| c == b'1').map(|_| 0));
+    if out.len() > MAX_CID_LENGTH { return None; }
+    out.reverse();
+    Some(out)
+}
+
+/// Unpadded lowercase RFC 4648 base32; leftover bits must be zero
+fn decode_base32(input: &[u8]) -> Option<Vec<u8>> {
+    if input.is_empty() { return None; }
+    let (mut buffer, mut bits, mut out) = (0u32, 0u32, Vec::new());
+    for &c in input {
+        let value = match c {
+            b'a'..=b'z' => c - b'a',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | u32::from(value);
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits >= 5 || buffer != 0 { return None; }
+    Some(out)
+}
+
+fn is_valid_url(url: &str) -> bool {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"));
+    matches!(rest, Some(rest) if !rest.is_empty() && !url.chars().any(|c| c.is_whitespace() || c.is_control()))
+}
+
+/// Lowercase alphanumerics and hyphens, not starting or ending with a hyphen
+fn is_valid_name_segment(segment: &str) -> bool {
+    if segment.is_empty() { return false; }
+    if segment.starts_with('-') || segment.ends_with('-') { return false; }
+    segment.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A namespace scope, as `register_namespace` accepts it
+pub fn is_valid_scope(scope: &str) -> bool {
+    scope.len() <= MAX_SCOPE_LENGTH && is_valid_name_segment(scope)
+}
+
+/// `name` or `scope/name`, with at most one `/` and both sides valid
+pub fn is_valid_package_name(name: &str) -> bool {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH { return false; }
+    match name.split_once('/') {
+        Some((scope, rest)) => is_valid_scope(scope) && is_valid_name_segment(rest),
+        None => is_valid_name_segment(name),
+    }
+}
+
+/// The scope of a scoped name (`alice` for `alice/token-utils`)
+pub fn package_scope(name: &str) -> Option<&str> {
+    name.split_once('/').map(|(scope, _)| scope)
+}
+
+fn is_valid_semver(version: &str) -> bool {
+    semver::Version::parse(version).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dep(name: &str, version: &str) -> Dependency {
+        Dependency { name: name.to_string(), version: version.to_string() }
+    }
+
+    fn external(dep_type: &str, name: &str, version: &str, registry: Option<&str>) -> ExternalDependency {
+        ExternalDependency {
+            name: name.to_string(),
+            version: version.to_string(),
+            dep_type: dep_type.to_string(),
+            registry: registry.map(str::to_string),
+        }
+    }
+
+    fn codes(violations: &[Violation]) -> Vec<&'static str> {
+        violations.iter().map(|v| v.code).collect()
+    }
+
+    #[test]
+    fn valid_package_passes() {
+        assert!(check_package("spl-utils", "1.2.3", "desc", &[dep("lib-a", "0.1.0")]).is_empty());
+        assert!(check_package("spl-utils", "2.0.0-rc.1+build.5", "desc", &[dep("lib-a", "0.2.0-beta.3")]).is_empty());
+    }
+
+    #[test]
+    fn reports_every_program_check() {
+        assert_eq!(codes(&check_package("", "", "", &[])), vec!["NameEmpty", "VersionEmpty"]);
+        assert_eq!(codes(&check_package(&"a".repeat(33), &format!("1.0.0-{}", "a".repeat(27)), "", &[])), vec!["NameTooLong", "VersionTooLong"]);
+        assert_eq!(codes(&check_package("-Bad", "1.0", &"d".repeat(257), &[])), vec![
+            "InvalidNameFormat",
+            "InvalidVersionFormat",
+            "DescriptionTooLong",
+        ]);
+
+        let many: Vec<Dependency> = (0..11).map(|i| dep(&format!("dep{}", i), "1.0.0")).collect();
+        assert_eq!(codes(&check_package("app", "1.0.0", "", &many)), vec!["TooManyDependencies"]);
+        assert_eq!(codes(&check_package("app", "1.0.0", "", &[dep("Bad_Name", "1.0")])), vec![
+            "InvalidDependencyName",
+            "InvalidDependencyVersion",
+        ]);
+    }
+
+    #[test]
+    fn metadata_checks_match_program() {
+        let metadata = |repository: &str, homepage: &str, keywords: &[&str]| PackageMetadata {
+            repository: repository.to_string(),
+            homepage: homepage.to_string(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+        };
+        assert!(check_metadata(&PackageMetadata::default()).is_empty());
+        assert!(check_metadata(&metadata("https://github.com/a/b", "http://a.dev", &["solana", "spl-token"])).is_empty());
+
+        assert_eq!(codes(&check_metadata(&metadata("git@github.com:a/b.git", "https://", &[]))), vec!["InvalidUrl", "InvalidUrl"]);
+        assert_eq!(codes(&check_metadata(&metadata(&format!("https://{}", "a".repeat(128)), "", &[]))), vec!["RepositoryTooLong"]);
+        assert_eq!(codes(&check_metadata(&metadata("", "", &["a", "b", "c", "d", "e", "f"]))), vec!["TooManyKeywords"]);
+        assert_eq!(codes(&check_metadata(&metadata("", "", &["Solana", "", &"k".repeat(21)]))), vec![
+            "InvalidKeyword",
+            "InvalidKeyword",
+            "InvalidKeyword",
+        ]);
+    }
+
+    #[test]
+    fn scoped_names_take_exactly_one_valid_scope() {
+        for name in ["token-utils", "alice/token-utils", "a1/b2"] {
+            assert!(is_valid_package_name(name), "{}", name);
+        }
+        for name in ["alice/", "/token-utils", "a/b/c", "Alice/x", "-alice/x", "alice/-x", "@alice/x", &format!("{}/x", "s".repeat(33))] {
+            assert!(!is_valid_package_name(name), "{}", name);
+        }
+        assert_eq!(package_scope("alice/token-utils"), Some("alice"));
+        assert_eq!(package_scope("token-utils"), None);
+        assert!(check_package("alice/token-utils", "1.0.0", "", &[dep("bob/lib", "1.0.0")]).is_empty());
+        assert_eq!(codes(&check_metadata(&PackageMetadata { keywords: vec!["a/b".into()], ..Default::default() })), vec!["InvalidKeyword"]);
+    }
+
+    #[test]
+    fn cid_checks_match_program() {
+        for cid in [
+            "QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L4",
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+            "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+            "bafk2bzaceaswza5ss4iu2ia3galz6pyo6dfm5f4dmiw2lf2de22dmf4k533ba",
+            "zb2rhj7crUKTQYRGCRATFaQ6YFLTde2YzdqbbhAASkL9uRDXn",
+        ] {
+            assert!(check_cids(cid, Some(cid)).is_empty(), "{} should be valid", cid);
+        }
+        for cid in [
+            "Qmxxxx",
+            "Qmzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz",
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n",
+            "bajkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+            "BAFKREIFZJUT3TE2NHYEKKLSS27NH3K72YSCO7Y32KOAO5EEI66WOF36N5E",
+        ] {
+            assert_eq!(codes(&check_cids(cid, None)), vec!["InvalidCidFormat"], "{}", cid);
+        }
+        assert_eq!(codes(&check_cids("", Some("not-a-cid"))), vec!["CidEmpty", "InvalidReadmeCid"]);
+        assert_eq!(codes(&check_cids(&"Qm".repeat(33), None)), vec!["CidTooLong"]);
+    }
+
+    #[test]
+    fn external_dependencies_follow_program_rules() {
+        assert!(check_external_dependencies(&[
+            external("rust", "serde", "^1.0", Some("crates.io")),
+            external("npm", "@solana/web3.js", ">=1.90,<2", None),
+            external("python", "numpy", "~=1.26", None),
+            external("go", "github.com/gagliardetto/solana-go", "v1.10.0", None),
+        ])
+        .is_empty());
+
+        assert_eq!(
+            codes(&check_external_dependencies(&[
+                external("ruby", "rails", "7.1", None),
+                external("rust", "a|b", "1;0", Some("")),
+                external("npm", &"x".repeat(MAX_EXTERNAL_NAME_LENGTH + 1), ">=1.90 <2", None),
+            ])),
+            vec![
+                "InvalidExternalDependencyType",
+                "InvalidExternalDependencyName",
+                "InvalidExternalDependencyVersion",
+                "InvalidExternalDependencyRegistry",
+                "InvalidExternalDependencyName",
+                "InvalidExternalDependencyVersion",
+            ]
+        );
+        let too_many = vec![external("rust", "serde", "1", None); MAX_EXTERNAL_DEPENDENCIES + 1];
+        assert_eq!(codes(&check_external_dependencies(&too_many)), vec!["TooManyExternalDependencies"]);
+    }
+
+    #[test]
+    fn max_space_matches_program_layout() {
+        assert_eq!(PACKAGE_MAX_SPACE, 3505);
+    }
+
+    #[test]
+    fn package_space_counts_actual_lengths() {
+        let empty = PackageMetadata::default();
+        // 8 + (4+3) + (4+5) + 32 + (4+46) + 8 + 4 + 4 + 1 + 32 + 4 + 4 + 4 + 1 + 4 + 8 + 1
+        assert_eq!(package_space("foo", "1.0.0", 46, "", &[], &empty, None, &[]), 181);
+        assert_eq!(package_space("foo", "1.0.0", 46, "", &[], &empty, Some(46), &[]), 181 + 4 + 46);
+
+        let metadata = PackageMetadata {
+            repository: "https://a.dev".into(),
+            homepage: String::new(),
+            keywords: vec!["sol".into()],
+        };
+        assert_eq!(
+            package_space("foo", "1.0.0", 46, "desc", &[dep("bar", "2.0.0")], &metadata, None, &[external("rust", "serde", "^1.0", None)]),
+            181 + 4 + (4 + 3 + 4 + 5) + 13 + (4 + 3) + (4 + 5 + 4 + 4 + 4 + 4 + 1)
+        );
+
+        let max_deps: Vec<Dependency> = (0..MAX_DEPENDENCIES).map(|_| dep(&"a".repeat(MAX_NAME_LENGTH), &"1".repeat(MAX_VERSION_LENGTH))).collect();
+        let max_metadata = PackageMetadata {
+            repository: "r".repeat(MAX_REPOSITORY_LENGTH),
+            homepage: "h".repeat(MAX_HOMEPAGE_LENGTH),
+            keywords: vec!["k".repeat(MAX_KEYWORD_LENGTH); MAX_KEYWORDS],
+        };
+        let max_external: Vec<ExternalDependency> = (0..MAX_EXTERNAL_DEPENDENCIES)
+            .map(|_| {
+                external(
+                    "python",
+                    &"e".repeat(MAX_EXTERNAL_NAME_LENGTH),
+                    &"1".repeat(MAX_EXTERNAL_VERSION_LENGTH),
+                    Some(&"r".repeat(MAX_REGISTRY_LENGTH)),
+                )
+            })
+            .collect();
+        assert_eq!(
+            package_space(
+                &"n".repeat(MAX_NAME_LENGTH),
+                &"1".repeat(MAX_VERSION_LENGTH),
+                MAX_CID_LENGTH,
+                &"d".repeat(MAX_DESCRIPTION_LENGTH),
+                &max_deps,
+                &max_metadata,
+                Some(MAX_CID_LENGTH),
+                &max_external,
+            ),
+            PACKAGE_MAX_SPACE
+        );
+    }
+}
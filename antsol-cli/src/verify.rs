@@ -0,0 +1,367 @@
+use crate::ipfs::IpfsClient;
+use crate::resolver::MetadataSource;
+use crate::types::{PackageAccount, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+/// Checks one package's content against its on-chain CID
+pub trait PackageVerifier {
+    /// Download `package` and verify it; returns the archive sha256
+    async fn verify(&self, package: &PackageAccount) -> Result<String>;
+}
+
+impl PackageVerifier for IpfsClient {
+    async fn verify(&self, package: &PackageAccount) -> Result<String> {
+        let dir = std::env::temp_dir().join(format!("antsol_verify_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir)?;
+        let result = self.download_package(&package.ipfs_cid, &dir, package.content_sha256.as_deref()).await;
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+}
+
+/// Why a node failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// No on-chain record for a required name@version
+    Missing,
+    /// The package (transitively) depends on itself
+    Cycle,
+    /// The on-chain record could not be fetched
+    Fetch,
+    /// Download or CID/hash check failed
+    Verification,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum NodeStatus {
+    Pass { sha256: String },
+    Fail { kind: FailureKind, message: String },
+}
+
+impl NodeStatus {
+    fn fail(kind: FailureKind, message: impl Into<String>) -> Self {
+        NodeStatus::Fail { kind, message: message.into() }
+    }
+
+    pub fn passed(&self) -> bool {
+        matches!(self, NodeStatus::Pass { .. })
+    }
+}
+
+/// A package in the walked graph; `package` is `None` when it couldn't be fetched
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    pub package: Option<PackageAccount>,
+    /// Dependency ids (`name@version`) in declaration order
+    pub children: Vec<String>,
+    pub failure: Option<NodeStatus>,
+}
+
+/// Every `name@version` reachable from the root, without failing on
+/// missing packages, conflicts or cycles (verification reports those)
+#[derive(Debug)]
+pub struct DependencyGraph {
+    pub root: String,
+    pub nodes: BTreeMap<String, GraphNode>,
+}
+
+impl DependencyGraph {
+    /// Packages that can be verified, in id order
+    pub fn fetched(&self) -> Vec<(String, PackageAccount)> {
+        self.nodes
+            .iter()
+            .filter_map(|(id, node)| node.package.clone().map(|p| (id.clone(), p)))
+            .collect()
+    }
+}
+
+/// Walk the closure of `name@version` (only the root unless `recursive`)
+pub async fn walk<S: MetadataSource>(source: &S, name: &str, version: &str, recursive: bool) -> DependencyGraph {
+    let root = format!("{}@{}", name, version);
+    let mut graph = DependencyGraph { root: root.clone(), nodes: BTreeMap::new() };
+    let mut queue = vec![(name.to_string(), version.to_string(), None::<String>)];
+
+    while let Some((name, version, required_by)) = queue.pop() {
+        let id = format!("{}@{}", name, version);
+        if graph.nodes.contains_key(&id) {
+            continue;
+        }
+        let node = match source.fetch_package(&name, &version).await {
+            Ok(Some(package)) => {
+                let deps = if recursive { package.dependencies.clone() } else { Vec::new() };
+                // Reverse so the stack pops dependencies in declaration order
+                for dep in deps.iter().rev() {
+                    queue.push((dep.name.clone(), dep.version.clone(), Some(id.clone())));
+                }
+                GraphNode {
+                    children: deps.iter().map(|d| format!("{}@{}", d.name, d.version)).collect(),
+                    package: Some(package),
+                    failure: None,
+                }
+            }
+            Ok(None) => {
+                let message = match &required_by {
+                    Some(parent) => format!("{} not found on-chain (required by {})", id, parent),
+                    None => format!("{} not found on-chain", id),
+                };
+                GraphNode { package: None, children: Vec::new(), failure: Some(NodeStatus::fail(FailureKind::Missing, message)) }
+            }
+            Err(e) => GraphNode {
+                package: None,
+                children: Vec::new(),
+                failure: Some(NodeStatus::fail(FailureKind::Fetch, e.to_string())),
+            },
+        };
+        graph.nodes.insert(id, node);
+    }
+    graph
+}
+
+/// Verify `packages` concurrently, at most `jobs` at a time.
+///
+/// Each package gets its own task, so one failure never cancels the others.
+/// Tasks run on a `LocalSet`, which lets verifiers hold non-`Send` state.
+pub async fn verify_all<V: PackageVerifier + 'static>(
+    verifier: Rc<V>,
+    packages: Vec<(String, PackageAccount)>,
+    jobs: usize,
+) -> HashMap<String, NodeStatus> {
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    let local = tokio::task::LocalSet::new();
+    local.run_until(async move {
+        let mut handles = Vec::new();
+        for (id, package) in packages {
+            let verifier = verifier.clone();
+            let semaphore = semaphore.clone();
+            handles.push((id, tokio::task::spawn_local(async move {
+                let _permit = semaphore.acquire_owned().await;
+                match verifier.verify(&package).await {
+                    Ok(sha256) => NodeStatus::Pass { sha256 },
+                    Err(e) => NodeStatus::fail(FailureKind::Verification, e.to_string()),
+                }
+            })));
+        }
+
+        let mut results = HashMap::new();
+        for (id, handle) in handles {
+            let status = handle.await
+                .unwrap_or_else(|e| NodeStatus::fail(FailureKind::Verification, format!("verification task failed: {}", e)));
+            results.insert(id, status);
+        }
+        results
+    }).await
+}
+
+/// One node of the verification report tree
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportNode {
+    pub id: String,
+    #[serde(flatten)]
+    pub status: NodeStatus,
+    /// Already shown earlier in the tree; children omitted
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repeated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<ReportNode>,
+}
+
+/// Result of `antsol verify`, suitable for CI artifacts
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyReport {
+    pub ok: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub tree: ReportNode,
+}
+
+impl VerifyReport {
+    /// Combine the walked graph and per-package results into one tree
+    pub fn build(graph: &DependencyGraph, results: &HashMap<String, NodeStatus>) -> Self {
+        let mut seen = HashSet::new();
+        let mut stack = Vec::new();
+        let tree = build_node(graph, results, &graph.root, &mut stack, &mut seen);
+
+        let mut passed = 0;
+        let mut failed = 0;
+        count(&tree, &mut passed, &mut failed);
+        VerifyReport { ok: failed == 0, passed, failed, tree }
+    }
+
+    /// ASCII tree with a pass/fail mark per node
+    pub fn render(&self) -> Vec<String> {
+        let mut lines = vec![node_label(&self.tree)];
+        render_children(&self.tree, "", &mut lines);
+        lines
+    }
+}
+
+fn build_node(
+    graph: &DependencyGraph,
+    results: &HashMap<String, NodeStatus>,
+    id: &str,
+    stack: &mut Vec<String>,
+    seen: &mut HashSet<String>,
+) -> ReportNode {
+    if let Some(start) = stack.iter().position(|s| s == id) {
+        let mut cycle = stack[start..].to_vec();
+        cycle.push(id.to_string());
+        return ReportNode {
+            id: id.to_string(),
+            status: NodeStatus::fail(FailureKind::Cycle, format!("dependency cycle: {}", cycle.join(" -> "))),
+            repeated: false,
+            children: Vec::new(),
+        };
+    }
+
+    let node = &graph.nodes[id];
+    let status = node.failure.clone()
+        .or_else(|| results.get(id).cloned())
+        .unwrap_or_else(|| NodeStatus::fail(FailureKind::Verification, "not verified"));
+    if !seen.insert(id.to_string()) {
+        return ReportNode { id: id.to_string(), status, repeated: true, children: Vec::new() };
+    }
+
+    stack.push(id.to_string());
+    let children = node.children.iter()
+        .map(|child| build_node(graph, results, child, stack, seen))
+        .collect();
+    stack.pop();
+    ReportNode { id: id.to_string(), status, repeated: false, children }
+}
+
+fn count(node: &ReportNode, passed: &mut usize, failed: &mut usize) {
+    if !node.repeated {
+        if node.status.passed() {
+            *passed += 1;
+        } else {
+            *failed += 1;
+        }
+    }
+    for child in &node.children {
+        count(child, passed, failed);
+    }
+}
+
+fn node_label(node: &ReportNode) -> String {
+    match (&node.status, node.repeated) {
+        (_, true) => format!("{} (*)", node.id),
+        (NodeStatus::Pass { .. }, _) => format!("✓ {}", node.id),
+        (NodeStatus::Fail { kind, message }, _) => {
+            let kind = serde_json::to_value(kind).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+            format!("✗ {} [{}] {}", node.id, kind, message)
+        }
+    }
+}
+
+fn render_children(node: &ReportNode, prefix: &str, lines: &mut Vec<String>) {
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let branch = if last { "└── " } else { "├── " };
+        lines.push(format!("{}{}{}", prefix, branch, node_label(child)));
+        let next = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_children(child, &next, lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AntSolError;
+    use crate::resolver::tests::MockSource;
+    use crate::solana_client::tests::sample_package;
+    use std::cell::Cell;
+
+    /// Fails verification for the listed ids and tracks peak concurrency
+    #[derive(Default)]
+    struct MockVerifier {
+        failing: Vec<&'static str>,
+        in_flight: Cell<usize>,
+        peak: Cell<usize>,
+    }
+
+    impl PackageVerifier for MockVerifier {
+        async fn verify(&self, package: &PackageAccount) -> Result<String> {
+            self.in_flight.set(self.in_flight.get() + 1);
+            self.peak.set(self.peak.get().max(self.in_flight.get()));
+            tokio::task::yield_now().await;
+            self.in_flight.set(self.in_flight.get() - 1);
+
+            let id = format!("{}@{}", package.name, package.version);
+            if self.failing.contains(&id.as_str()) {
+                return Err(AntSolError::Verification(format!("CID mismatch for {}", id)).into());
+            }
+            Ok("ab".repeat(32))
+        }
+    }
+
+    #[tokio::test]
+    async fn mixed_graph_reports_each_node() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0"), ("lib-b", "1.0.0"), ("ghost", "0.1.0")]),
+            sample_package("lib-a", "1.0.0", &[("shared", "1.0.0")]),
+            sample_package("lib-b", "1.0.0", &[("shared", "1.0.0")]),
+            sample_package("shared", "1.0.0", &[]),
+        ]);
+        let verifier = Rc::new(MockVerifier { failing: vec!["lib-b@1.0.0"], ..Default::default() });
+
+        let graph = walk(&source, "app", "1.0.0", true).await;
+        let results = verify_all(verifier.clone(), graph.fetched(), 2).await;
+        let report = VerifyReport::build(&graph, &results);
+
+        assert!(!report.ok);
+        assert_eq!((report.passed, report.failed), (3, 2));
+        assert!(verifier.peak.get() <= 2);
+        assert_eq!(report.render(), vec![
+            "✓ app@1.0.0".to_string(),
+            "├── ✓ lib-a@1.0.0".to_string(),
+            "│   └── ✓ shared@1.0.0".to_string(),
+            "├── ✗ lib-b@1.0.0 [verification] CID mismatch for lib-b@1.0.0".to_string(),
+            "│   └── shared@1.0.0 (*)".to_string(),
+            "└── ✗ ghost@0.1.0 [missing] ghost@0.1.0 not found on-chain (required by app@1.0.0)".to_string(),
+        ]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["tree"]["children"][2]["kind"], "missing");
+        assert_eq!(json["tree"]["children"][1]["children"][0]["repeated"], true);
+    }
+
+    #[tokio::test]
+    async fn cycles_are_their_own_failure_kind() {
+        let source = MockSource::new(vec![
+            sample_package("app", "1.0.0", &[("lib-a", "1.0.0")]),
+            sample_package("lib-a", "1.0.0", &[("app", "1.0.0")]),
+        ]);
+        let graph = walk(&source, "app", "1.0.0", true).await;
+        let results = verify_all(Rc::new(MockVerifier::default()), graph.fetched(), 4).await;
+        let report = VerifyReport::build(&graph, &results);
+
+        assert!(!report.ok);
+        assert_eq!((report.passed, report.failed), (2, 1));
+        let cycle = &report.tree.children[0].children[0];
+        assert_eq!(cycle.status, NodeStatus::Fail {
+            kind: FailureKind::Cycle,
+            message: "dependency cycle: app@1.0.0 -> lib-a@1.0.0 -> app@1.0.0".to_string(),
+        });
+    }
+
+    #[tokio::test]
+    async fn runs_in_parallel_up_to_the_job_limit() {
+        let packages: Vec<_> = (0..6)
+            .map(|i| sample_package(&format!("pkg-{}", i), "1.0.0", &[]))
+            .map(|p| (format!("{}@{}", p.name, p.version), p))
+            .collect();
+        let verifier = Rc::new(MockVerifier::default());
+
+        let results = verify_all(verifier.clone(), packages, 3).await;
+
+        assert_eq!(results.len(), 6);
+        assert!(results.values().all(NodeStatus::passed));
+        assert_eq!(verifier.peak.get(), 3);
+    }
+}
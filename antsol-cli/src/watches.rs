@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::error::AntSolError;
+use crate::types::Result;
+use crate::utils::compare_versions;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// A package followed with `antsol watch`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Watch {
+    /// Highest version already reported; `None` until the indexer knows the package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<String>,
+}
+
+/// Watched packages persisted in `~/.antsol/watches.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WatchList {
+    #[serde(default)]
+    pub packages: BTreeMap<String, Watch>,
+}
+
+impl WatchList {
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(Config::config_dir()?.join("watches.toml"))
+    }
+
+    /// Load the list, starting empty when the file is missing. Unlike the
+    /// caches it can be hand-edited, so a broken file is an error rather
+    /// than silently replaced.
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", path.display(), e)).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Versions in `known` above `last_seen`, lowest first. With nothing seen
+/// yet every version is new.
+pub fn new_versions<'a>(last_seen: Option<&str>, known: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut newer: Vec<&str> = known
+        .into_iter()
+        .filter(|&v| semver::Version::parse(v).is_ok())
+        .filter(|&v| last_seen.is_none_or(|seen| compare_versions(v, seen) == Ordering::Greater))
+        .collect();
+    newer.sort_by(|a, b| compare_versions(a, b));
+    newer.dedup();
+    newer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_versions_are_those_above_the_last_seen() {
+        let known = ["1.0.0", "1.2.0", "1.1.0", "2.0.0-beta.1", "not-semver"];
+        assert_eq!(new_versions(Some("1.0.0"), known), vec!["1.1.0", "1.2.0", "2.0.0-beta.1"]);
+        assert_eq!(new_versions(Some("2.0.0-beta.1"), known), Vec::<&str>::new());
+        assert_eq!(new_versions(None, ["1.0.0", "0.9.0"]), vec!["0.9.0", "1.0.0"]);
+    }
+
+    #[test]
+    fn watch_list_round_trips_and_rejects_a_broken_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("watches.toml");
+        assert_eq!(WatchList::load(&path).unwrap(), WatchList::default());
+
+        let mut list = WatchList::default();
+        list.packages.insert("@scope/pkg".into(), Watch { last_seen: Some("1.0.0".into()) });
+        list.packages.insert("unindexed".into(), Watch::default());
+        list.save(&path).unwrap();
+        assert_eq!(WatchList::load(&path).unwrap(), list);
+
+        std::fs::write(&path, "packages = 3").unwrap();
+        let err = WatchList::load(&path).unwrap_err();
+        assert_eq!(crate::error::error_kind(err.as_ref()), crate::error::ErrorKind::Config);
+    }
+}
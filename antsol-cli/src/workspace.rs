@@ -0,0 +1,258 @@
+//! Workspaces: several packages published from one repository
+//!
+//! The root `antsol.toml` lists the member directories, relative to itself:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["packages/core", "packages/cli"]
+//! ```
+//!
+//! Each member is an ordinary package directory with its own `antsol.toml`.
+//! Dependencies between members are published in order and pinned to the
+//! version each member is publishing.
+
+use crate::error::AntSolError;
+use crate::types::{AntSolManifest, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceFile {
+    workspace: Option<WorkspaceTable>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceTable {
+    members: Vec<String>,
+}
+
+/// One package of a workspace
+#[derive(Debug)]
+pub struct Member {
+    /// Member directory, as listed under the workspace root
+    pub dir: PathBuf,
+    pub manifest: AntSolManifest,
+}
+
+impl Member {
+    pub fn name(&self) -> &str {
+        &self.manifest.package.name
+    }
+
+    pub fn version(&self) -> &str {
+        &self.manifest.package.version
+    }
+
+    /// `name@version`, as reports print it
+    pub fn label(&self) -> String {
+        format!("{}@{}", self.name(), self.version())
+    }
+}
+
+/// The members listed in `root/antsol.toml`, in the order listed
+pub fn load(root: &Path) -> Result<Vec<Member>> {
+    let manifest_path = root.join("antsol.toml");
+    if !manifest_path.exists() {
+        return Err(AntSolError::ManifestNotFound(root.to_path_buf()).into());
+    }
+    let content = std::fs::read_to_string(&manifest_path)?;
+    let file: WorkspaceFile = toml::from_str(&content)
+        .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", manifest_path.display(), e)))?;
+    let Some(workspace) = file.workspace else {
+        return Err(AntSolError::Usage(format!(
+            "{} has no [workspace] table; list the member directories under [workspace] members",
+            manifest_path.display()
+        )).into());
+    };
+    if workspace.members.is_empty() {
+        return Err(AntSolError::Config(format!("[workspace] members in {} is empty", manifest_path.display())).into());
+    }
+
+    let mut members: Vec<Member> = Vec::with_capacity(workspace.members.len());
+    for entry in &workspace.members {
+        let dir = root.join(entry);
+        let member_manifest = dir.join("antsol.toml");
+        let content = std::fs::read_to_string(&member_manifest).map_err(|e| {
+            AntSolError::Config(format!("Workspace member '{}' has no readable antsol.toml: {}", entry, e))
+        })?;
+        let manifest: AntSolManifest = toml::from_str(&content)
+            .map_err(|e| AntSolError::Config(format!("Invalid {}: {}", member_manifest.display(), e)))?;
+        if let Some(other) = members.iter().find(|m| m.name() == manifest.package.name) {
+            return Err(AntSolError::Config(format!(
+                "Workspace members {} and {} are both named '{}'",
+                other.dir.display(),
+                dir.display(),
+                manifest.package.name
+            )).into());
+        }
+        members.push(Member { dir, manifest });
+    }
+    Ok(members)
+}
+
+/// Indexes into `members` such that each member comes after the members it
+/// depends on. Members with no order between them keep the listed order.
+pub fn publish_order(members: &[Member]) -> Result<Vec<usize>> {
+    let names: HashMap<&str, usize> = members.iter().enumerate().map(|(i, m)| (m.name(), i)).collect();
+    let internal: Vec<Vec<usize>> = members
+        .iter()
+        .map(|m| {
+            m.manifest.dependencies.iter().flatten().filter_map(|dep| names.get(dep.name.as_str()).copied()).collect()
+        })
+        .collect();
+
+    let mut order = Vec::with_capacity(members.len());
+    let mut placed = HashSet::new();
+    while order.len() < members.len() {
+        let next = (0..members.len())
+            .find(|i| !placed.contains(i) && internal[*i].iter().all(|dep| placed.contains(dep)));
+        let Some(next) = next else {
+            let remaining: Vec<&str> =
+                (0..members.len()).filter(|i| !placed.contains(i)).map(|i| members[i].name()).collect();
+            return Err(AntSolError::Resolution(format!(
+                "Workspace members depend on each other in a cycle: {}",
+                remaining.join(", ")
+            )).into());
+        };
+        placed.insert(next);
+        order.push(next);
+    }
+    Ok(order)
+}
+
+/// `publish_order` restricted to the member at `target` and the members it
+/// depends on, directly or through other members
+pub fn with_dependencies(members: &[Member], target: usize) -> Result<Vec<usize>> {
+    let names: HashMap<&str, usize> = members.iter().enumerate().map(|(i, m)| (m.name(), i)).collect();
+    let mut needed = HashSet::from([target]);
+    let mut pending = vec![target];
+    while let Some(i) = pending.pop() {
+        for dep in members[i].manifest.dependencies.iter().flatten() {
+            if let Some(&j) = names.get(dep.name.as_str()) {
+                if needed.insert(j) {
+                    pending.push(j);
+                }
+            }
+        }
+    }
+    Ok(publish_order(members)?.into_iter().filter(|i| needed.contains(i)).collect())
+}
+
+/// The root of the nearest workspace above `dir` that lists `dir` as a member
+pub fn find_root(dir: &Path) -> Option<PathBuf> {
+    let dir = dir.canonicalize().ok()?;
+    dir.ancestors().skip(1).find(|root| {
+        let Ok(content) = std::fs::read_to_string(root.join("antsol.toml")) else {
+            return false;
+        };
+        let Ok(WorkspaceFile { workspace: Some(workspace) }) = toml::from_str(&content) else {
+            return false;
+        };
+        workspace.members.iter().any(|entry| root.join(entry).canonicalize().is_ok_and(|member| member == dir))
+    }).map(Path::to_path_buf)
+}
+
+/// Point `manifest`'s dependencies on other members at the version those
+/// members are publishing, given as name → version. Returns each rewritten
+/// dependency as `(name, old requirement, new version)`.
+pub fn pin_members(manifest: &mut AntSolManifest, versions: &HashMap<String, String>) -> Vec<(String, String, String)> {
+    let mut rewritten = Vec::new();
+    for dep in manifest.dependencies.iter_mut().flatten() {
+        if let Some(version) = versions.get(&dep.name) {
+            if &dep.version != version {
+                rewritten.push((dep.name.clone(), std::mem::replace(&mut dep.version, version.clone()), version.clone()));
+            }
+        }
+    }
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_member(root: &Path, dir: &str, name: &str, deps: &[(&str, &str)]) {
+        let mut content = format!("[package]\nname = \"{}\"\nversion = \"1.0.0\"\ndescription = \"\"\n", name);
+        for (dep, version) in deps {
+            content.push_str(&format!("\n[[dependencies]]\nname = \"{}\"\nversion = \"{}\"\n", dep, version));
+        }
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("antsol.toml"), content).unwrap();
+    }
+
+    fn names(members: &[Member], order: &[usize]) -> Vec<String> {
+        order.iter().map(|&i| members[i].name().to_string()).collect()
+    }
+
+    #[test]
+    fn orders_members_after_their_dependencies() {
+        let root = TempDir::new().unwrap();
+        std::fs::write(root.path().join("antsol.toml"), "[workspace]\nmembers = [\"app\", \"util\", \"core\", \"docs\"]\n").unwrap();
+        write_member(root.path(), "app", "app", &[("util", "^1.0.0"), ("core", "1.0.0"), ("serde-lite", "2.0.0")]);
+        write_member(root.path(), "util", "util", &[("core", "~1.0")]);
+        write_member(root.path(), "core", "core", &[]);
+        write_member(root.path(), "docs", "docs", &[]);
+
+        let members = load(root.path()).unwrap();
+        assert_eq!(members[1].dir, root.path().join("util"));
+        let order = publish_order(&members).unwrap();
+        assert_eq!(names(&members, &order), vec!["core", "util", "app", "docs"]);
+    }
+
+    #[test]
+    fn refuses_cycles_and_bad_workspaces() {
+        let root = TempDir::new().unwrap();
+        std::fs::write(root.path().join("antsol.toml"), "[workspace]\nmembers = [\"a\", \"b\", \"c\"]\n").unwrap();
+        write_member(root.path(), "a", "a", &[("b", "1.0.0")]);
+        write_member(root.path(), "b", "b", &[("a", "1.0.0")]);
+        write_member(root.path(), "c", "c", &[]);
+        let err = publish_order(&load(root.path()).unwrap()).unwrap_err();
+        assert_eq!(err.to_string(), "Workspace members depend on each other in a cycle: a, b");
+
+        write_member(root.path(), "c", "a", &[]);
+        assert!(load(root.path()).unwrap_err().to_string().contains("are both named 'a'"));
+
+        std::fs::write(root.path().join("antsol.toml"), "[workspace]\nmembers = [\"missing\"]\n").unwrap();
+        assert!(load(root.path()).unwrap_err().to_string().contains("Workspace member 'missing'"));
+
+        std::fs::write(root.path().join("antsol.toml"), "[package]\nname = \"a\"\n").unwrap();
+        assert!(load(root.path()).unwrap_err().to_string().contains("has no [workspace] table"));
+    }
+
+    #[test]
+    fn finds_a_member_with_its_workspace_dependencies() {
+        let root = TempDir::new().unwrap();
+        std::fs::write(root.path().join("antsol.toml"), "[workspace]\nmembers = [\"packages/app\", \"packages/util\", \"packages/core\", \"docs\"]\n").unwrap();
+        write_member(root.path(), "packages/app", "app", &[("util", "^1.0.0"), ("serde-lite", "2.0.0")]);
+        write_member(root.path(), "packages/util", "util", &[("core", "~1.0")]);
+        write_member(root.path(), "packages/core", "core", &[]);
+        write_member(root.path(), "docs", "docs", &[]);
+        std::fs::create_dir_all(root.path().join("elsewhere")).unwrap();
+
+        let found = find_root(&root.path().join("packages/app")).unwrap();
+        assert_eq!(found, root.path().canonicalize().unwrap());
+        assert_eq!(find_root(&root.path().join("elsewhere")), None);
+        assert_eq!(find_root(root.path()), None);
+
+        let members = load(&found).unwrap();
+        assert_eq!(names(&members, &with_dependencies(&members, 0).unwrap()), vec!["core", "util", "app"]);
+        assert_eq!(names(&members, &with_dependencies(&members, 2).unwrap()), vec!["core"]);
+    }
+
+    #[test]
+    fn pins_dependencies_on_members_only() {
+        let root = TempDir::new().unwrap();
+        write_member(root.path(), "app", "app", &[("core", "^1.0.0"), ("util", "2.0.0"), ("serde-lite", "^2")]);
+        let mut manifest: AntSolManifest =
+            toml::from_str(&std::fs::read_to_string(root.path().join("app/antsol.toml")).unwrap()).unwrap();
+        let versions = HashMap::from([("core".to_string(), "1.3.0".to_string()), ("util".to_string(), "2.0.0".to_string())]);
+
+        let rewritten = pin_members(&mut manifest, &versions);
+        assert_eq!(rewritten, vec![("core".to_string(), "^1.0.0".to_string(), "1.3.0".to_string())]);
+        let deps: Vec<(&str, &str)> =
+            manifest.dependencies.iter().flatten().map(|d| (d.name.as_str(), d.version.as_str())).collect();
+        assert_eq!(deps, vec![("core", "1.3.0"), ("util", "2.0.0"), ("serde-lite", "^2")]);
+    }
+}
@@ -0,0 +1,101 @@
+//! Run the real binary and check that failures map to the documented exit codes.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run `antsol` with an empty HOME so no user config or wallet leaks in
+fn antsol(home: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_antsol"))
+        .args(args)
+        .env("HOME", home)
+        .env("ANTSOL_RPC_URL", "http://127.0.0.1:1")
+        .env("ANTSOL_INDEXER_URL", "http://127.0.0.1:1")
+        .env_remove("ANTSOL_WALLET_PATH")
+        .env_remove("PINATA_JWT")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn missing_manifest_is_a_config_error() {
+    let home = tempfile::tempdir().unwrap();
+    let package = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["publish", package.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(3));
+    assert!(stderr(&output).contains("No antsol.toml found"));
+}
+
+#[test]
+fn missing_wallet_is_a_wallet_error() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["wallet", "balance"]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(stderr(&output).contains("No wallet connected"));
+}
+
+#[test]
+fn unreachable_rpc_is_a_network_error() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["info", "some-package@1.0.0"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).contains("Solana RPC error"));
+}
+
+#[test]
+fn bad_arguments_are_usage_errors() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["update", ".", "--version", "1.0"]);
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn unconfirmed_publish_without_a_terminal_is_a_usage_error() {
+    let home = tempfile::tempdir().unwrap();
+    let package = tempfile::tempdir().unwrap();
+    std::fs::write(
+        package.path().join("antsol.toml"),
+        "[package]\nname = \"pkg\"\nversion = \"1.0.0\"\ndescription = \"test\"\n",
+    ).unwrap();
+    let output = antsol(home.path(), &["publish", package.path().to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--yes"));
+}
+
+#[test]
+fn catalog_lists_insufficient_funds() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["errors", "list", "--json"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    let catalog: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let entry = catalog.as_array().unwrap().iter().find(|e| e["name"] == "insufficient_funds").unwrap();
+    assert_eq!(entry["code"], 12);
+}
+
+#[test]
+fn quiet_drops_the_banner() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["-q", "errors", "list"]);
+
+    assert_eq!(output.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Decentralized Registry v"));
+}
+
+#[test]
+fn verbose_logs_the_rpc_endpoint() {
+    let home = tempfile::tempdir().unwrap();
+    let output = antsol(home.path(), &["-v", "info", "some-package@1.0.0"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).contains("RPC endpoint http://127.0.0.1:1"));
+}
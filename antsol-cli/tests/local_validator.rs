@@ -0,0 +1,233 @@
+//! End-to-end run of the real binary against `solana-test-validator` with
+//! the registry program deployed and IPFS stubbed by a wiremock server.
+//!
+//! Skipped unless `ANTSOL_E2E=1`. Needs `solana-test-validator` on PATH and
+//! the program built with `anchor build` (override the path with
+//! `ANTSOL_E2E_PROGRAM_SO`):
+//!
+//! ```text
+//! (cd ../antsol-registry && anchor build)
+//! ANTSOL_E2E=1 cargo test --test local_validator -- --nocapture
+//! ```
+
+use cid::multihash::Multihash;
+use cid::Cid;
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use wiremock::http::{HeaderName, Method};
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+/// `declare_id!` of the registry program, and the CLI's default program_id
+const PROGRAM_ID: &str = "A9igkBugcujD9Nw9d97FFN4aY3qHXnJxEqCChJt8C42S";
+const RPC_PORT: u16 = 18899;
+const FAUCET_PORT: u16 = 19900;
+
+/// Kills the validator when the test ends, however it ends
+struct Validator(Child);
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn program_so() -> PathBuf {
+    std::env::var_os("ANTSOL_E2E_PROGRAM_SO").map(PathBuf::from).unwrap_or_else(|| {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../antsol-registry/target/deploy/antsol_registry.so")
+    })
+}
+
+fn start_validator(ledger: &Path) -> Validator {
+    let child = Command::new("solana-test-validator")
+        .args(["--reset", "--quiet", "--ledger"])
+        .arg(ledger)
+        .args(["--rpc-port", &RPC_PORT.to_string(), "--faucet-port", &FAUCET_PORT.to_string()])
+        .args(["--bpf-program", PROGRAM_ID])
+        .arg(program_so())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("solana-test-validator on PATH");
+    let validator = Validator(child);
+
+    let rpc = RpcClient::new(rpc_url());
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while rpc.get_health().is_err() {
+        assert!(Instant::now() < deadline, "validator did not become healthy within 60s");
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    validator
+}
+
+fn rpc_url() -> String {
+    format!("http://127.0.0.1:{}", RPC_PORT)
+}
+
+/// dag-pb root of a single-block UnixFS file, which is what `ipfs add`
+/// returns for archives under the 256 KiB chunk size
+fn cid_v0(bytes: &[u8]) -> String {
+    fn varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+    let mut unixfs = vec![0x08, 0x02, 0x12];
+    varint(&mut unixfs, bytes.len() as u64);
+    unixfs.extend_from_slice(bytes);
+    unixfs.push(0x18);
+    varint(&mut unixfs, bytes.len() as u64);
+    let mut node = vec![0x0a];
+    varint(&mut node, unixfs.len() as u64);
+    node.extend_from_slice(&unixfs);
+    Cid::new_v0(Multihash::wrap(0x12, &Sha256::digest(&node)).unwrap()).unwrap().to_string()
+}
+
+/// Kubo's `/api/v0/add` and a gateway sharing one store, so whatever
+/// publish uploads can be installed again
+#[derive(Clone, Default)]
+struct FakeIpfs(Arc<Mutex<HashMap<String, Vec<u8>>>>);
+
+impl Respond for FakeIpfs {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        if request.method == Method::Post {
+            let archive = multipart_file(request).expect("multipart upload with a file part");
+            let cid = cid_v0(&archive);
+            self.0.lock().unwrap().insert(cid.clone(), archive);
+            return ResponseTemplate::new(200).set_body_json(serde_json::json!({ "Name": "package.tar.gz", "Hash": cid }));
+        }
+        let cid = request.url.path().trim_start_matches("/ipfs/");
+        match self.0.lock().unwrap().get(cid) {
+            Some(bytes) => ResponseTemplate::new(200).set_body_bytes(bytes.clone()),
+            None => ResponseTemplate::new(404),
+        }
+    }
+}
+
+/// Body of the first part of a `multipart/form-data` request
+fn multipart_file(request: &Request) -> Option<Vec<u8>> {
+    let content_type = request.headers.get(&HeaderName::from_str("content-type").ok()?)?.last();
+    let boundary = format!("--{}", content_type.as_str().split("boundary=").nth(1)?);
+    let body = &request.body;
+    let headers_end = find(body, b"\r\n\r\n")? + 4;
+    let end = headers_end + find(&body[headers_end..], format!("\r\n{}", boundary).as_bytes())?;
+    Some(body[headers_end..end].to_vec())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+struct Env {
+    home: tempfile::TempDir,
+    wallet: PathBuf,
+    ipfs_url: String,
+}
+
+impl Env {
+    /// Run `antsol` in `dir` with every endpoint pointed at the test doubles
+    fn antsol(&self, dir: &Path, args: &[&str]) -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_antsol"))
+            .args(args)
+            .current_dir(dir)
+            .env("HOME", self.home.path())
+            .env("ANTSOL_RPC_URL", rpc_url())
+            .env("ANTSOL_PROGRAM_ID", PROGRAM_ID)
+            .env("ANTSOL_WALLET_PATH", &self.wallet)
+            .env("ANTSOL_IPFS_URL", &self.ipfs_url)
+            .env("ANTSOL_IPFS_GATEWAYS", &self.ipfs_url)
+            .env("ANTSOL_INDEXER_URL", "http://127.0.0.1:1")
+            .env("ANTSOL_TELEMETRY", "false")
+            .env_remove("PINATA_JWT")
+            .env("NO_COLOR", "1")
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "antsol {} failed:\n{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    fn antsol_json(&self, dir: &Path, args: &[&str]) -> serde_json::Value {
+        let args: Vec<&str> = ["--no-keyring", "--output", "json"].into_iter().chain(args.iter().copied()).collect();
+        serde_json::from_slice(&self.antsol(dir, &args).stdout).unwrap()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn publish_info_install_update_round_trip() {
+    if std::env::var("ANTSOL_E2E").as_deref() != Ok("1") {
+        eprintln!("ANTSOL_E2E not set, skipping");
+        return;
+    }
+    let ledger = tempfile::tempdir().unwrap();
+    let _validator = start_validator(ledger.path());
+
+    let ipfs = MockServer::start().await;
+    let store = FakeIpfs::default();
+    Mock::given(method("POST")).and(path("/api/v0/add")).respond_with(store.clone()).mount(&ipfs).await;
+    Mock::given(method("GET")).and(path_regex("^/ipfs/")).respond_with(store.clone()).mount(&ipfs).await;
+
+    let home = tempfile::tempdir().unwrap();
+    let keypair = Keypair::new();
+    let wallet = home.path().join("wallet.json");
+    std::fs::write(&wallet, serde_json::to_string(&keypair.to_bytes().to_vec()).unwrap()).unwrap();
+    let env = Env { home, wallet, ipfs_url: ipfs.uri() };
+
+    let package = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    env.antsol(package.path(), &["--no-keyring", "wallet", "airdrop", "5"]);
+    env.antsol(package.path(), &["init", "--name", "e2e-pkg", "--version", "0.1.0", "--yes"]);
+    std::fs::write(package.path().join("index.js"), "module.exports = 1;\n").unwrap();
+
+    // publish
+    let published = env.antsol_json(package.path(), &["publish", ".", "--yes"]);
+    let cid = published["ipfs_cid"].as_str().unwrap().to_string();
+    assert!(store.0.lock().unwrap().contains_key(&cid));
+
+    let program_id = Pubkey::from_str(PROGRAM_ID).unwrap();
+    let (pda, _) = Pubkey::find_program_address(&[b"package", b"e2e-pkg", b"0.1.0"], &program_id);
+    let rpc = RpcClient::new_with_commitment(rpc_url(), CommitmentConfig::confirmed());
+    assert_eq!(rpc.get_account(&pda).unwrap().owner, program_id);
+
+    // info reads the account back through the CLI's Borsh layout
+    let info = env.antsol_json(package.path(), &["info", "e2e-pkg@0.1.0"]);
+    assert_eq!(info["ipfs_cid"], cid.as_str());
+    assert_eq!(info["authority"], keypair.pubkey().to_string());
+    assert_eq!(info["account"], pda.to_string());
+    assert_eq!(info["content_sha256"], published["content_sha256"]);
+
+    // install downloads through the stub gateway and verifies the CID and sha256
+    env.antsol(project.path(), &["--no-keyring", "install", "e2e-pkg@0.1.0"]);
+    let installed = project.path().join("antsol_packages/e2e-pkg/index.js");
+    assert_eq!(std::fs::read_to_string(installed).unwrap(), "module.exports = 1;\n");
+    let lock = std::fs::read_to_string(project.path().join("antsol.lock")).unwrap();
+    assert!(lock.contains(&cid));
+
+    // update publishes a new version account next to the old one
+    std::fs::write(package.path().join("index.js"), "module.exports = 2;\n").unwrap();
+    let updated = env.antsol_json(package.path(), &["update", ".", "--version", "0.2.0"]);
+    assert_eq!(updated["previous_version"], "0.1.0");
+    assert_ne!(updated["ipfs_cid"], cid.as_str());
+    let info = env.antsol_json(package.path(), &["info", "e2e-pkg@0.2.0"]);
+    assert_eq!(info["ipfs_cid"], updated["ipfs_cid"]);
+    assert_eq!(info["authority"], keypair.pubkey().to_string());
+    let (old_pda, _) = Pubkey::find_program_address(&[b"package", b"e2e-pkg", b"0.1.0"], &program_id);
+    assert!(rpc.get_account(&old_pda).is_ok());
+}
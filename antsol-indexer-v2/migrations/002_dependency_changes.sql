@@ -0,0 +1,13 @@
+-- Dependency set changes reported by update_package (DependenciesChanged)
+CREATE TABLE IF NOT EXISTS dependency_changes (
+    id SERIAL PRIMARY KEY,
+    package_name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    dependencies_added TEXT[] NOT NULL DEFAULT '{}',
+    dependencies_removed TEXT[] NOT NULL DEFAULT '{}',
+    transaction_signature TEXT NOT NULL UNIQUE,
+    slot BIGINT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_dependency_changes_package_name ON dependency_changes(package_name);
@@ -0,0 +1,3 @@
+-- Newest program transaction signature already ingested.
+-- The listener pages getSignaturesForAddress back to this signature (`until`).
+ALTER TABLE indexer_state ADD COLUMN IF NOT EXISTS last_signature TEXT;
@@ -0,0 +1,28 @@
+-- versions.published_at used to default to the insert time, so backfilled
+-- versions carried the wrong date. Recover the chain time from the earliest
+-- publish/update event recorded for each version.
+UPDATE versions v
+SET published_at = e.first_block_time
+FROM (
+    SELECT p.id AS package_id, ev.version, MIN(ev.block_time) AS first_block_time
+    FROM events ev
+    JOIN packages p ON p.name = ev.package_name
+    WHERE ev.event_type IN ('PackagePublished', 'PackageUpdated')
+      AND ev.version IS NOT NULL
+      AND ev.block_time IS NOT NULL
+    GROUP BY p.id, ev.version
+) e
+WHERE v.package_id = e.package_id
+  AND v.version = e.version
+  AND v.published_at > e.first_block_time;
+
+-- A package is as old as its first published version
+UPDATE packages p
+SET created_at = first_version.published_at
+FROM (
+    SELECT package_id, MIN(published_at) AS published_at
+    FROM versions
+    GROUP BY package_id
+) first_version
+WHERE p.id = first_version.package_id
+  AND p.created_at > first_version.published_at;
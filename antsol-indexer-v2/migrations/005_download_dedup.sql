@@ -0,0 +1,11 @@
+-- Clients that recently reported a download, so repeated installs from the
+-- same address within the dedup window count once. Only an md5 of the
+-- address is kept, and rows are pruned once the window has passed.
+CREATE TABLE IF NOT EXISTS download_clients (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    client_hash TEXT NOT NULL,
+    last_seen TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (version_id, client_hash)
+);
+
+CREATE INDEX IF NOT EXISTS idx_download_clients_last_seen ON download_clients(last_seen);
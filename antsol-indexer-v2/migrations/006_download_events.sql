@@ -0,0 +1,13 @@
+-- One row per counted download, so rankings can look at a time window
+-- instead of the all-time counters on packages/versions.
+CREATE TABLE IF NOT EXISTS download_events (
+    id BIGSERIAL PRIMARY KEY,
+    package_id INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    downloaded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_download_events_downloaded_at ON download_events(downloaded_at);
+CREATE INDEX IF NOT EXISTS idx_download_events_package_time ON download_events(package_id, downloaded_at);
+
+CREATE INDEX IF NOT EXISTS idx_versions_published_at ON versions(published_at);
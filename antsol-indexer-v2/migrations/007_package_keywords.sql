@@ -0,0 +1,5 @@
+-- Keywords from the on-chain package metadata (repository and homepage
+-- already have columns on packages).
+ALTER TABLE packages ADD COLUMN IF NOT EXISTS keywords TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_packages_keywords ON packages USING GIN (keywords);
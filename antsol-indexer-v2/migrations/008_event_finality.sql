@@ -0,0 +1,9 @@
+-- Reorg handling: events are ingested at the indexer's commitment level and
+-- only become 'finalized' once their slot is; events whose transaction
+-- disappeared with a fork are flagged 'orphaned' instead of deleted.
+ALTER TABLE events ADD COLUMN IF NOT EXISTS finality TEXT NOT NULL DEFAULT 'confirmed';
+
+CREATE INDEX IF NOT EXISTS idx_events_unfinalized ON events(slot) WHERE finality = 'confirmed';
+
+-- Newest finalized slot seen by the finality pass
+ALTER TABLE indexer_state ADD COLUMN IF NOT EXISTS finalized_slot BIGINT NOT NULL DEFAULT 0;
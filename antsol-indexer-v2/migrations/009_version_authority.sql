@@ -0,0 +1,7 @@
+-- Publisher authority: packages.author holds the current authority of the
+-- package (it used to be 'unknown'), versions.authority the pubkey that
+-- published each version.
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS authority TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_packages_author ON packages(author);
+CREATE INDEX IF NOT EXISTS idx_versions_authority ON versions(authority);
@@ -0,0 +1,17 @@
+-- Ranked full-text search over name, keywords and description. Names and
+-- keywords use the 'simple' configuration so identifiers are not stemmed;
+-- descriptions are English prose. Separators in names are blanked so
+-- "math-utils-extra" is found by "math-utils".
+-- array_to_string is only STABLE, so generated columns need this wrapper.
+CREATE OR REPLACE FUNCTION antsol_keywords_text(keywords TEXT[]) RETURNS TEXT
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT array_to_string(keywords, ' ') $$;
+
+ALTER TABLE packages ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('simple'::regconfig, regexp_replace(name, '[-_./@]+', ' ', 'g')), 'A') ||
+        setweight(to_tsvector('simple'::regconfig, antsol_keywords_text(keywords)), 'B') ||
+        setweight(to_tsvector('english'::regconfig, COALESCE(description, '')), 'C')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_packages_search ON packages USING GIN (search_vector);
@@ -0,0 +1,9 @@
+-- Wallets besides the authority that may publish new versions of a package,
+-- from MaintainerAdded / MaintainerRemoved. Keyed by name like the on-chain
+-- Maintainers account, so entries may precede the package being indexed.
+CREATE TABLE IF NOT EXISTS package_maintainers (
+    package_name TEXT NOT NULL,
+    maintainer TEXT NOT NULL,
+    added_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (package_name, maintainer)
+);
@@ -0,0 +1,12 @@
+-- Daily download counts for download_events past the retention period.
+-- Rolling up keeps the time series complete while the per-download rows
+-- behind trending windows stay small.
+CREATE TABLE IF NOT EXISTS download_rollups (
+    package_id INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    day DATE NOT NULL,
+    downloads BIGINT NOT NULL,
+    PRIMARY KEY (version_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_download_rollups_package_day ON download_rollups(package_id, day);
@@ -0,0 +1,11 @@
+-- Full dependency list of each version, from the "Package dependencies" log
+-- line publish_package and update_package write. Indexed by name to answer
+-- reverse-dependency lookups.
+CREATE TABLE IF NOT EXISTS dependencies (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    dep_name TEXT NOT NULL,
+    dep_version TEXT NOT NULL,
+    PRIMARY KEY (version_id, dep_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_dependencies_dep_name ON dependencies(dep_name);
@@ -0,0 +1,29 @@
+-- Events carry the CID and authority of the publish or update they record,
+-- and point at the version row they touched instead of relying on a
+-- name/version string match. version_id goes NULL when an orphaned version
+-- is removed; the event itself stays for the audit trail.
+ALTER TABLE events ADD COLUMN IF NOT EXISTS ipfs_hash TEXT;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS authority TEXT;
+ALTER TABLE events ADD COLUMN IF NOT EXISTS version_id INTEGER REFERENCES versions(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_events_version_id ON events(version_id);
+CREATE INDEX IF NOT EXISTS idx_events_ipfs_hash ON events(ipfs_hash);
+
+-- Backfill: link existing events to their versions by name and version
+UPDATE events e
+SET version_id = v.id
+FROM versions v
+JOIN packages p ON p.id = v.package_id
+WHERE e.version_id IS NULL
+  AND e.package_name = p.name
+  AND e.version = v.version;
+
+-- ...and copy the CID and publisher onto publish/update events
+UPDATE events e
+SET ipfs_hash = v.ipfs_hash,
+    authority = COALESCE(e.authority, v.authority)
+FROM versions v
+WHERE e.version_id = v.id
+  AND e.ipfs_hash IS NULL
+  AND e.event_type IN ('PackagePublished', 'PackageUpdated')
+  AND v.ipfs_hash <> 'unknown';
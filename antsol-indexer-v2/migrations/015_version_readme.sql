@@ -0,0 +1,2 @@
+-- README CID pinned with each version, logged on the package metadata line
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS readme_cid TEXT;
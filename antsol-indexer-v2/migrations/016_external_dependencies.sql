@@ -0,0 +1,11 @@
+-- Dependencies from other ecosystems (crates, npm packages, ...), from the
+-- "Package external dependencies" log line publish_package and update_package
+-- write. Informational only; the registry never resolves them.
+CREATE TABLE IF NOT EXISTS external_dependencies (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    dep_type TEXT NOT NULL,
+    dep_name TEXT NOT NULL,
+    dep_version TEXT NOT NULL,
+    registry TEXT,
+    PRIMARY KEY (version_id, dep_type, dep_name)
+);
@@ -0,0 +1,39 @@
+-- Semver precedence for versions, so lists and "latest" don't depend on the
+-- order things were published in (a 1.2.4 hotfix after 2.0.0 is not latest).
+-- The numeric parts and a prerelease sort key are generated from the version
+-- string; anything that isn't MAJOR.MINOR.PATCH[-PRE][+BUILD] leaves them NULL.
+CREATE OR REPLACE FUNCTION antsol_semver_part(version TEXT, part INTEGER) RETURNS NUMERIC
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$ SELECT (regexp_match(version, '^([0-9]+)\.([0-9]+)\.([0-9]+)(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?$'))[part]::numeric $$;
+
+-- Prerelease identifiers compare per semver under the "C" collation: numeric
+-- ones as '0' plus their zero-padded length plus the digits, so they sort
+-- numerically and before alphanumeric ones ('1' plus the identifier).
+-- Identifiers are joined with a space, which sorts before every identifier
+-- character, so a shorter list of equal identifiers sorts first.
+CREATE OR REPLACE FUNCTION antsol_semver_prerelease_key(version TEXT) RETURNS TEXT
+    LANGUAGE sql IMMUTABLE PARALLEL SAFE
+    AS $$
+        SELECT string_agg(
+            CASE WHEN ident ~ '^[0-9]+$' THEN '0' || lpad(length(ident)::text, 2, '0') || ident ELSE '1' || ident END,
+            ' ' ORDER BY ord
+        )
+        FROM unnest(string_to_array(
+            (regexp_match(version, '^[0-9]+\.[0-9]+\.[0-9]+-([0-9A-Za-z.-]+)(?:\+[0-9A-Za-z.-]+)?$'))[1], '.'
+        )) WITH ORDINALITY AS ids(ident, ord)
+    $$;
+
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS version_major NUMERIC
+    GENERATED ALWAYS AS (antsol_semver_part(version, 1)) STORED;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS version_minor NUMERIC
+    GENERATED ALWAYS AS (antsol_semver_part(version, 2)) STORED;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS version_patch NUMERIC
+    GENERATED ALWAYS AS (antsol_semver_part(version, 3)) STORED;
+-- NULL for releases
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS version_prerelease TEXT COLLATE "C"
+    GENERATED ALWAYS AS (antsol_semver_prerelease_key(version)) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_versions_semver ON versions (
+    package_id, version_major DESC NULLS LAST, version_minor DESC, version_patch DESC,
+    (version_prerelease IS NULL) DESC, version_prerelease DESC
+);
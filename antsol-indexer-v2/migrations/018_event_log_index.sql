@@ -0,0 +1,8 @@
+-- One transaction can emit several events (a batch instruction, a CPI), so
+-- events are unique per log line of their transaction rather than per
+-- transaction. Rows stored before this keep log_index 0; the listener
+-- already skips transactions whose signature is recorded, so they aren't
+-- ingested again under their real index.
+ALTER TABLE events ADD COLUMN IF NOT EXISTS log_index INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE events DROP CONSTRAINT IF EXISTS events_transaction_signature_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_events_signature_log_index ON events(transaction_signature, log_index);
@@ -0,0 +1,3 @@
+-- Set when a PackageFrozen event burns the version's authority; a frozen
+-- version is never updated or transferred again
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS frozen BOOLEAN NOT NULL DEFAULT FALSE;
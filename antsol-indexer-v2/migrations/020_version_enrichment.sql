@@ -0,0 +1,16 @@
+-- What the enrichment worker read from each version's tarball: antsol.toml's
+-- description and license, README.md, and the file count and unpacked size.
+-- enriched_at stays NULL until that succeeds. Failures are kept per CID and
+-- retried at enrichment_retry_at; enrichment_failed stops the retries.
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS description TEXT;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS license TEXT;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS readme TEXT;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS file_count INTEGER;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS unpacked_size BIGINT;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS enriched_at TIMESTAMPTZ;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS enrichment_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS enrichment_retry_at TIMESTAMPTZ;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS enrichment_error TEXT;
+ALTER TABLE versions ADD COLUMN IF NOT EXISTS enrichment_failed BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX IF NOT EXISTS idx_versions_unenriched ON versions(ipfs_hash)
+    WHERE enriched_at IS NULL AND NOT enrichment_failed;
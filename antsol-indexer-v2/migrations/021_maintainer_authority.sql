@@ -0,0 +1,8 @@
+-- Maintainer lists are kept per authority as well as per name, and a list
+-- only covers versions its authority owns. Rows from the old name-keyed
+-- lists, which the program no longer reads, have no authority and go.
+ALTER TABLE package_maintainers ADD COLUMN IF NOT EXISTS authority TEXT NOT NULL DEFAULT '';
+DELETE FROM package_maintainers WHERE authority = '';
+ALTER TABLE package_maintainers DROP CONSTRAINT IF EXISTS package_maintainers_pkey;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_package_maintainers_authority
+    ON package_maintainers(package_name, authority, maintainer);
@@ -0,0 +1,165 @@
+-- AntSol Indexer DB Schema for SQLite, matching Postgres migrations 001-017.
+-- Timestamps are UTC ISO 8601 text with milliseconds ('2024-01-31T12:00:00.000Z'),
+-- which sorts chronologically; days are 'YYYY-MM-DD'. Array columns hold
+-- JSON arrays of strings. The version_* sort columns Postgres generates are
+-- computed when a version is inserted (see db::sqlite::queries).
+CREATE TABLE IF NOT EXISTS packages (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    author TEXT NOT NULL,
+    description TEXT,
+    repository TEXT,
+    homepage TEXT,
+    keywords TEXT NOT NULL DEFAULT '[]',
+    total_downloads INTEGER DEFAULT 0,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    updated_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS versions (
+    id INTEGER PRIMARY KEY,
+    package_id INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+    version TEXT NOT NULL,
+    ipfs_hash TEXT NOT NULL,
+    downloads INTEGER DEFAULT 0,
+    published_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    authority TEXT,
+    readme_cid TEXT,
+    frozen INTEGER NOT NULL DEFAULT 0,
+    -- Read from the tarball by the enrichment worker; see 004_version_enrichment.sql
+    description TEXT,
+    license TEXT,
+    readme TEXT,
+    file_count INTEGER,
+    unpacked_size INTEGER,
+    enriched_at TEXT,
+    enrichment_attempts INTEGER NOT NULL DEFAULT 0,
+    enrichment_retry_at TEXT,
+    enrichment_error TEXT,
+    enrichment_failed INTEGER NOT NULL DEFAULT 0,
+    version_major INTEGER,
+    version_minor INTEGER,
+    version_patch INTEGER,
+    -- NULL for releases; compares bytewise like the "C" collation
+    version_prerelease TEXT,
+    UNIQUE(package_id, version)
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    package_name TEXT NOT NULL,
+    version TEXT,
+    transaction_signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time TEXT,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    finality TEXT NOT NULL DEFAULT 'confirmed',
+    ipfs_hash TEXT,
+    authority TEXT,
+    version_id INTEGER REFERENCES versions(id) ON DELETE SET NULL,
+    -- Position of the event's log line; one transaction can emit several events
+    log_index INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(transaction_signature, log_index)
+);
+
+CREATE TABLE IF NOT EXISTS indexer_state (
+    id INTEGER PRIMARY KEY,
+    last_processed_slot INTEGER NOT NULL,
+    last_processed_block_time TEXT,
+    updated_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    status TEXT DEFAULT 'running',
+    error_count INTEGER DEFAULT 0,
+    last_error TEXT,
+    last_signature TEXT,
+    finalized_slot INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO indexer_state (id, last_processed_slot)
+VALUES (1, 0)
+ON CONFLICT (id) DO NOTHING;
+
+CREATE TABLE IF NOT EXISTS dependency_changes (
+    id INTEGER PRIMARY KEY,
+    package_name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    dependencies_added TEXT NOT NULL DEFAULT '[]',
+    dependencies_removed TEXT NOT NULL DEFAULT '[]',
+    transaction_signature TEXT NOT NULL UNIQUE,
+    slot INTEGER NOT NULL,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS download_clients (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    client_hash TEXT NOT NULL,
+    last_seen TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (version_id, client_hash)
+);
+
+CREATE TABLE IF NOT EXISTS download_events (
+    id INTEGER PRIMARY KEY,
+    package_id INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    downloaded_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS package_maintainers (
+    package_name TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    maintainer TEXT NOT NULL,
+    added_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (package_name, authority, maintainer)
+);
+
+CREATE TABLE IF NOT EXISTS download_rollups (
+    package_id INTEGER NOT NULL REFERENCES packages(id) ON DELETE CASCADE,
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    day TEXT NOT NULL,
+    downloads INTEGER NOT NULL,
+    PRIMARY KEY (version_id, day)
+);
+
+CREATE TABLE IF NOT EXISTS dependencies (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    dep_name TEXT NOT NULL,
+    dep_version TEXT NOT NULL,
+    PRIMARY KEY (version_id, dep_name)
+);
+
+CREATE TABLE IF NOT EXISTS external_dependencies (
+    version_id INTEGER NOT NULL REFERENCES versions(id) ON DELETE CASCADE,
+    dep_type TEXT NOT NULL,
+    dep_name TEXT NOT NULL,
+    dep_version TEXT NOT NULL,
+    registry TEXT,
+    PRIMARY KEY (version_id, dep_type, dep_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_packages_total_downloads ON packages(total_downloads);
+CREATE INDEX IF NOT EXISTS idx_packages_created_at ON packages(created_at);
+CREATE INDEX IF NOT EXISTS idx_packages_updated_at ON packages(updated_at);
+CREATE INDEX IF NOT EXISTS idx_packages_author ON packages(author);
+
+CREATE INDEX IF NOT EXISTS idx_versions_package_id ON versions(package_id);
+CREATE INDEX IF NOT EXISTS idx_versions_version ON versions(version);
+CREATE INDEX IF NOT EXISTS idx_versions_published_at ON versions(published_at);
+CREATE INDEX IF NOT EXISTS idx_versions_authority ON versions(authority);
+CREATE INDEX IF NOT EXISTS idx_versions_semver ON versions (
+    package_id, version_major DESC, version_minor DESC, version_patch DESC,
+    version_prerelease IS NULL DESC, version_prerelease DESC
+);
+
+CREATE INDEX IF NOT EXISTS idx_events_package_name ON events(package_name);
+CREATE INDEX IF NOT EXISTS idx_events_event_type ON events(event_type);
+CREATE INDEX IF NOT EXISTS idx_events_slot ON events(slot);
+CREATE INDEX IF NOT EXISTS idx_events_unfinalized ON events(slot) WHERE finality = 'confirmed';
+CREATE INDEX IF NOT EXISTS idx_events_version_id ON events(version_id);
+CREATE INDEX IF NOT EXISTS idx_events_ipfs_hash ON events(ipfs_hash);
+
+CREATE INDEX IF NOT EXISTS idx_dependency_changes_package_name ON dependency_changes(package_name);
+CREATE INDEX IF NOT EXISTS idx_download_clients_last_seen ON download_clients(last_seen);
+CREATE INDEX IF NOT EXISTS idx_download_events_downloaded_at ON download_events(downloaded_at);
+CREATE INDEX IF NOT EXISTS idx_download_events_package_time ON download_events(package_id, downloaded_at);
+CREATE INDEX IF NOT EXISTS idx_download_rollups_package_day ON download_rollups(package_id, day);
+CREATE INDEX IF NOT EXISTS idx_dependencies_dep_name ON dependencies(dep_name);
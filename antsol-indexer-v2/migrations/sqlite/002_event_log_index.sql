@@ -0,0 +1,34 @@
+-- Rebuild an events table from before events were keyed on their log index
+-- as well as their signature. Only run when events has no log_index column;
+-- SQLite can't drop the old UNIQUE constraint in place.
+CREATE TABLE events_by_log_index (
+    id INTEGER PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    package_name TEXT NOT NULL,
+    version TEXT,
+    transaction_signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time TEXT,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    finality TEXT NOT NULL DEFAULT 'confirmed',
+    ipfs_hash TEXT,
+    authority TEXT,
+    version_id INTEGER REFERENCES versions(id) ON DELETE SET NULL,
+    log_index INTEGER NOT NULL DEFAULT 0,
+    UNIQUE(transaction_signature, log_index)
+);
+
+INSERT INTO events_by_log_index
+    (id, event_type, package_name, version, transaction_signature, slot, block_time, created_at, finality, ipfs_hash, authority, version_id)
+SELECT id, event_type, package_name, version, transaction_signature, slot, block_time, created_at, finality, ipfs_hash, authority, version_id
+FROM events;
+
+DROP TABLE events;
+ALTER TABLE events_by_log_index RENAME TO events;
+
+CREATE INDEX IF NOT EXISTS idx_events_package_name ON events(package_name);
+CREATE INDEX IF NOT EXISTS idx_events_event_type ON events(event_type);
+CREATE INDEX IF NOT EXISTS idx_events_slot ON events(slot);
+CREATE INDEX IF NOT EXISTS idx_events_unfinalized ON events(slot) WHERE finality = 'confirmed';
+CREATE INDEX IF NOT EXISTS idx_events_version_id ON events(version_id);
+CREATE INDEX IF NOT EXISTS idx_events_ipfs_hash ON events(ipfs_hash);
@@ -0,0 +1,4 @@
+-- Add the frozen flag to a versions table from before versions could be
+-- frozen. Only run when versions has no frozen column; SQLite has no
+-- ADD COLUMN IF NOT EXISTS.
+ALTER TABLE versions ADD COLUMN frozen INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Add the enrichment columns to a versions table from before tarballs were
+-- read. Only run when versions has no enriched_at column.
+ALTER TABLE versions ADD COLUMN description TEXT;
+ALTER TABLE versions ADD COLUMN license TEXT;
+ALTER TABLE versions ADD COLUMN readme TEXT;
+ALTER TABLE versions ADD COLUMN file_count INTEGER;
+ALTER TABLE versions ADD COLUMN unpacked_size INTEGER;
+ALTER TABLE versions ADD COLUMN enriched_at TEXT;
+ALTER TABLE versions ADD COLUMN enrichment_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE versions ADD COLUMN enrichment_retry_at TEXT;
+ALTER TABLE versions ADD COLUMN enrichment_error TEXT;
+ALTER TABLE versions ADD COLUMN enrichment_failed INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,12 @@
+-- Rebuild a package_maintainers table from before lists were kept per
+-- authority. Only run when package_maintainers has no authority column; the
+-- old name-keyed lists are no longer read by the program, so their rows
+-- aren't copied.
+DROP TABLE package_maintainers;
+CREATE TABLE package_maintainers (
+    package_name TEXT NOT NULL,
+    authority TEXT NOT NULL,
+    maintainer TEXT NOT NULL,
+    added_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    PRIMARY KEY (package_name, authority, maintainer)
+);
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::handlers::{client_address, ApiError, DependentsDepth};
+use super::health::HealthSettings;
+use crate::config::{Config, RateLimitConfig};
+use crate::indexer::accounts::PackageAccounts;
+
+/// Access control, health checks and metrics served by the router
+#[derive(Clone, Debug, Default)]
+pub struct ApiSettings {
+    /// Bearer tokens accepted by `POST /api/ingest`; ingestion is refused when empty
+    pub ingest_api_keys: Vec<String>,
+    /// Bearer tokens accepted by `/api/admin` routes; they are refused when empty
+    pub admin_api_keys: Vec<String>,
+    /// Per-client budget for `/api` routes; unlimited when `None`
+    pub rate_limit: Option<RateLimitConfig>,
+    /// What `/health` measures indexer lag against
+    pub health: HealthSettings,
+    /// Recorder rendered at `/metrics`; the route answers 404 without one
+    pub metrics: Option<PrometheusHandle>,
+    /// Package accounts `POST /api/ingest` reads descriptions from (logs only
+    /// when `None`) and `/api/admin/resync` re-reads versions from
+    pub package_accounts: Option<PackageAccounts>,
+    /// Deepest level `/api/packages/{name}/{version}/dependents` follows
+    pub dependents_depth: DependentsDepth,
+}
+
+impl ApiSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            ingest_api_keys: config.ingest_api_keys.clone(),
+            admin_api_keys: config.admin_api_keys.clone(),
+            rate_limit: config.rate_limit,
+            health: HealthSettings::from_config(config),
+            metrics: None,
+            package_accounts: Pubkey::from_str(&config.antsol_program_id).ok().map(|program_id| {
+                let rpc = RpcClient::new_with_commitment(config.solana_rpc_url.clone(), config.commitment);
+                PackageAccounts::new(Arc::new(rpc), program_id)
+            }),
+            dependents_depth: DependentsDepth(config.dependents_max_depth),
+        }
+    }
+}
+
+/// Keys accepted for ingestion. Several can be valid at once, so a key is
+/// rotated by adding the new one, moving clients over, then dropping the old.
+#[derive(Clone)]
+pub struct IngestKeys(Arc<Vec<String>>);
+
+impl IngestKeys {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self(Arc::new(keys))
+    }
+
+    /// Whether an `Authorization` header value is `Bearer <accepted key>`
+    pub fn authorizes(&self, authorization: Option<&str>) -> bool {
+        let Some(token) = authorization.and_then(|value| value.strip_prefix("Bearer ")).map(str::trim) else {
+            return false;
+        };
+        // Compare against every key so timing doesn't reveal which one matched
+        !token.is_empty()
+            && self.0.iter().fold(false, |found, key| found | constant_time_eq(key.as_bytes(), token.as_bytes()))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Keys accepted by `/api/admin` routes, rotated like [`IngestKeys`]
+#[derive(Clone)]
+pub struct AdminKeys(IngestKeys);
+
+impl AdminKeys {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self(IngestKeys::new(keys))
+    }
+}
+
+/// Middleware for the ingest route: 401 unless a configured key is presented
+pub async fn require_ingest_key(State(keys): State<IngestKeys>, request: Request, next: Next) -> Response {
+    let message = if keys.0.is_empty() {
+        "Ingestion is disabled: the indexer has no INGEST_API_KEYS configured"
+    } else {
+        "Missing or invalid ingest API key: send Authorization: Bearer <key>"
+    };
+    require_key(&keys, message, request, next).await
+}
+
+/// Middleware for admin routes: 401 unless a configured key is presented
+pub async fn require_admin_key(State(AdminKeys(keys)): State<AdminKeys>, request: Request, next: Next) -> Response {
+    let message = if keys.0.is_empty() {
+        "Admin routes are disabled: the indexer has no ADMIN_API_KEYS configured"
+    } else {
+        "Missing or invalid admin API key: send Authorization: Bearer <key>"
+    };
+    require_key(&keys, message, request, next).await
+}
+
+/// Run the request if it presents one of `keys`, else answer 401 with `message`
+async fn require_key(keys: &IngestKeys, message: &str, request: Request, next: Next) -> Response {
+    let authorization = request.headers().get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    if keys.authorizes(authorization) {
+        return next.run(request).await;
+    }
+    let mut response = ApiError::new(StatusCode::UNAUTHORIZED, "unauthorized", message).into_response();
+    response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Buckets kept before full (idle) ones are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Token bucket per client: `burst` requests at once, refilled at `per_minute`
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refilled(&self, now: Instant, per_sec: f64, burst: f64) -> f64 {
+        (self.tokens + now.saturating_duration_since(self.updated).as_secs_f64() * per_sec).min(burst)
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self { config, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    /// Spend one request of `client`'s budget at `now`. When the bucket is
+    /// empty, returns how long until the next request is allowed.
+    pub fn check_at(&self, client: &str, now: Instant) -> Result<(), Duration> {
+        let per_sec = f64::from(self.config.per_minute.max(1)) / 60.0;
+        let burst = f64::from(self.config.burst.max(1));
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            buckets.retain(|_, bucket| bucket.refilled(now, per_sec, burst) < burst);
+        }
+
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket { tokens: burst, updated: now });
+        bucket.tokens = bucket.refilled(now, per_sec, burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+
+    /// The key a request is counted under
+    fn client_key(&self, request: &Request, peer: Option<SocketAddr>) -> String {
+        client_address(request.headers(), peer, self.config.trust_forwarded_for).unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Middleware for `/api` routes: 429 with `Retry-After` once a client's budget is spent
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client = limiter.client_key(&request, connect_info.map(|ConnectInfo(addr)| addr));
+    let Err(wait) = limiter.check_at(&client, Instant::now()) else {
+        return next.run(request).await;
+    };
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = ApiError::new(
+        StatusCode::TOO_MANY_REQUESTS,
+        "rate_limited",
+        format!("Too many requests; retry in {}s", retry_after),
+    ).into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(per_minute: u32, burst: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig { per_minute, burst, trust_forwarded_for: false })
+    }
+
+    #[test]
+    fn any_configured_key_is_accepted() {
+        let keys = IngestKeys::new(vec!["old-key".to_string(), "new-key".to_string()]);
+        assert!(keys.authorizes(Some("Bearer old-key")));
+        assert!(keys.authorizes(Some("Bearer new-key")));
+        assert!(!keys.authorizes(Some("Bearer other-key")));
+        assert!(!keys.authorizes(Some("new-key")));
+        assert!(!keys.authorizes(Some("Bearer ")));
+        assert!(!keys.authorizes(None));
+        assert!(!IngestKeys::new(Vec::new()).authorizes(Some("Bearer ")));
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills() {
+        let limiter = limiter(60, 2);
+        let start = Instant::now();
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_ok());
+        let wait = limiter.check_at("a", start).unwrap_err();
+        assert_eq!(wait.as_secs_f64().ceil(), 1.0);
+        // Other clients have their own budget
+        assert!(limiter.check_at("b", start).is_ok());
+
+        // One request per second comes back; a long pause refills to the burst only
+        assert!(limiter.check_at("a", start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check_at("a", start + Duration::from_secs(1)).is_err());
+        let later = start + Duration::from_secs(600);
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_ok());
+        assert!(limiter.check_at("a", later).is_err());
+    }
+}
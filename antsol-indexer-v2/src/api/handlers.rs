@@ -1,19 +1,61 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    async_trait,
+    extract::{ConnectInfo, FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     Json,
 };
-use serde::{Deserialize, Serialize};
-use deadpool_postgres::Pool;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use crate::db::Pool;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tokio_stream::{Stream, StreamExt};
+use utoipa::{IntoParams, ToSchema};
 
+use crate::config::DEFAULT_DEPENDENTS_MAX_DEPTH;
 use crate::db::{models::*, queries};
-use crate::indexer::listener::{extract_ipfs_hash, ingest_event};
+use crate::indexer::accounts::{AccountError, PackageAccounts};
+use crate::indexer::broadcast::EventBus;
+use super::health::{unhealthy_reason, HealthProbe};
+use crate::indexer::listener::{extract_ipfs_hash, ingest_events, resync_version};
 
-#[derive(Serialize)]
+/// Envelope of every JSON response. On failure `data` is null, `error` is a
+/// human-readable message and `code` a machine-readable [`ApiError`] code.
+#[derive(Serialize, ToSchema)]
+#[aliases(
+    HealthResponse = ApiResponse<Health>,
+    ErrorResponse = ApiResponse<String>,
+    SearchResponse = ApiResponse<Vec<SearchResult>>,
+    PackageResponse = ApiResponse<PackageWithVersions>,
+    VersionResponse = ApiResponse<VersionDetail>,
+    DownloadSeriesResponse = ApiResponse<Vec<DownloadBucket>>,
+    DependenciesResponse = ApiResponse<PackageDependencies>,
+    DependentsResponse = ApiResponse<Vec<Dependent>>,
+    DownloadResponse = ApiResponse<DownloadRecorded>,
+    TrendingResponse = ApiResponse<Vec<TrendingPackage>>,
+    VersionListResponse = ApiResponse<Vec<VersionDetail>>,
+    PackageListResponse = ApiResponse<Vec<Package>>,
+    StatsResponse = ApiResponse<Stats>,
+    EventListResponse = ApiResponse<Vec<Event>>,
+    PackageEventListResponse = ApiResponse<Vec<PackageEvent>>,
+    SearchPageResponse = ApiResponse<SearchResultPage>,
+    PackagePageResponse = ApiResponse<PackagePage>,
+    EventPageResponse = ApiResponse<EventPage>,
+    PackageEventPageResponse = ApiResponse<PackageEventPage>,
+    IngestResponse = ApiResponse<IngestResult>,
+    ReadmeResponse = ApiResponse<PackageReadme>,
+    VersionDependentsResponse = ApiResponse<VersionDependents>
+)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    pub code: Option<String>,
 }
 
 impl<T> ApiResponse<T> {
@@ -22,28 +64,115 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            code: None,
         }
     }
 
-    pub fn error(error: String) -> Self {
+    pub fn error(code: &str, error: String) -> Self {
         Self {
             success: false,
             data: None,
             error: Some(error),
+            code: Some(code.to_string()),
         }
     }
 }
 
-#[derive(Deserialize)]
+/// A failed request, answered with the [`ApiResponse`] envelope.
+///
+/// Codes: `invalid_query`, `invalid_body`, `invalid_window`, `invalid_range`, `unauthorized`,
+/// `rate_limited`, `route_not_found`, `package_not_found`, `version_not_found`,
+/// `readme_not_found`, `scoped_name_not_encoded` and `internal_error`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self { status, code, message: message.into() }
+    }
+
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    /// Log `err` and answer with a generic 500, keeping database details out of the body
+    pub fn internal(context: &str, err: impl std::fmt::Display) -> Self {
+        tracing::error!("{} error: {}", context, err);
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", format!("{} failed", context))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(ApiResponse::<()>::error(self.code, self.message))).into_response()
+    }
+}
+
+/// `Query` that rejects malformed parameters with an `invalid_query` [`ApiError`]
+pub struct ApiQuery<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for ApiQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Query::<T>::from_request_parts(parts, state)
+            .await
+            .map(|Query(value)| ApiQuery(value))
+            .map_err(|rejection| ApiError::bad_request("invalid_query", rejection.body_text()))
+    }
+}
+
+/// `Json` that rejects malformed bodies with an `invalid_body` [`ApiError`]
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        Json::<T>::from_request(request, state)
+            .await
+            .map(|Json(value)| ApiJson(value))
+            .map_err(|rejection| ApiError::new(rejection.status(), "invalid_body", rejection.body_text()))
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SearchQuery {
     pub q: String,
+    /// `relevance` (default), `downloads`, `recent` or `name`
+    #[serde(default)]
+    pub sort: SearchSort,
+    /// Only packages whose authority is this base58 pubkey
+    pub author: Option<String>,
+    /// Only packages with a version matching this semver requirement, e.g. `^1.2`
+    pub has_version: Option<String>,
     #[serde(default = "default_limit")]
     pub limit: i64,
     #[serde(default)]
     pub offset: i64,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     #[serde(default = "default_limit")]
     pub limit: i64,
@@ -51,140 +180,769 @@ pub struct ListQuery {
     pub offset: i64,
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventsQuery {
+    /// Only events of this type, e.g. `PackagePublished`
+    pub event_type: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PackagesQuery {
+    /// Alternate way to look up a single package, e.g. `?name=@scope/pkg`
+    pub name: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// Opt-in to the paginated shape of the list endpoints that used to answer
+/// with a bare array. The bare array stays the default for one more release.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EnvelopeQuery {
+    /// `1` answers with `{items, total, limit, offset}` instead of a bare array
+    pub envelope: Option<String>,
+}
+
+impl EnvelopeQuery {
+    fn wanted(&self) -> bool {
+        matches!(self.envelope.as_deref(), Some("1" | "true"))
+    }
+}
+
+/// Answer `page` as a [`Paginated`] envelope when the client asked for one,
+/// otherwise as the bare array older clients expect
+fn page_response<T: Serialize>(page: Paginated<T>, envelope: &EnvelopeQuery) -> Response {
+    if envelope.wanted() {
+        Json(ApiResponse::success(page)).into_response()
+    } else {
+        Json(ApiResponse::success(page.items)).into_response()
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrendingQuery {
+    /// Ranking window such as `24h`, `7d` or `4w`
+    #[serde(default = "default_trending_window")]
+    pub window: String,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadSeriesQuery {
+    /// `day` (default), `week` or `month`
+    #[serde(default)]
+    pub interval: DownloadInterval,
+    /// First day, `YYYY-MM-DD` in UTC; defaults to 29 days before `to`
+    pub from: Option<chrono::NaiveDate>,
+    /// Last day, inclusive; defaults to today
+    pub to: Option<chrono::NaiveDate>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DependenciesQuery {
+    /// Version to list; defaults to the latest
+    pub version: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VersionDependentsQuery {
+    /// Follow dependents of dependents too; only direct dependents otherwise
+    #[serde(default)]
+    pub transitive: bool,
+    /// Levels to follow with `transitive`; defaults to, and is capped at,
+    /// `DEPENDENTS_MAX_DEPTH`
+    pub depth: Option<u32>,
+}
+
+/// Deepest level the version dependents route follows, from `DEPENDENTS_MAX_DEPTH`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DependentsDepth(pub u32);
+
+impl Default for DependentsDepth {
+    fn default() -> Self {
+        Self(DEFAULT_DEPENDENTS_MAX_DEPTH)
+    }
+}
+
+/// Longest `from`..`to` span of a download series (about ten years)
+pub const MAX_DOWNLOAD_SERIES_DAYS: i64 = 3660;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    /// Stored events sent before live ones, oldest first (capped at 100)
+    #[serde(default = "default_limit")]
+    pub replay: i64,
+}
+
 fn default_limit() -> i64 {
     20
 }
 
-pub async fn health_check() -> Json<ApiResponse<String>> {
-    Json(ApiResponse::success("OK".to_string()))
+/// Idle time after which `/api/events/stream` sends a heartbeat comment
+pub const STREAM_HEARTBEAT: Duration = Duration::from_secs(15);
+
+fn default_trending_window() -> String {
+    "7d".to_string()
 }
 
-pub async fn search_packages_handler(
-    State(pool): State<Pool>,
-    Query(params): Query<SearchQuery>,
-) -> Result<Json<ApiResponse<Vec<Package>>>, StatusCode> {
-    match queries::search_packages(&pool, &params.q, params.limit, params.offset).await {
-        Ok(packages) => Ok(Json(ApiResponse::success(packages))),
-        Err(e) => {
-            tracing::error!("Search error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+/// Longest trending window accepted, in seconds (90 days)
+pub const MAX_TRENDING_WINDOW_SECS: f64 = 90.0 * 86400.0;
+
+/// Parse a trending window (`<n>h`, `<n>d` or `<n>w`) into seconds.
+/// `None` for anything else, zero, or longer than [`MAX_TRENDING_WINDOW_SECS`].
+pub fn parse_window(window: &str) -> Option<f64> {
+    let unit_secs = match window.chars().last()? {
+        'h' => 3600.0,
+        'd' => 86400.0,
+        'w' => 7.0 * 86400.0,
+        _ => return None,
+    };
+    let count: u32 = window[..window.len() - 1].parse().ok()?;
+    let secs = f64::from(count) * unit_secs;
+    (count > 0 && secs <= MAX_TRENDING_WINDOW_SECS).then_some(secs)
+}
+
+/// Percent-encode a value for use as a single URL path segment
+pub fn encode_path_segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
         }
     }
+    encoded
+}
+
+/// Whether `scope` is a registry namespace: lowercase alphanumerics and
+/// hyphens, at most 32 bytes, not starting or ending with a hyphen
+fn is_namespace_scope(scope: &str) -> bool {
+    !scope.is_empty()
+        && scope.len() <= 32
+        && !scope.starts_with('-')
+        && !scope.ends_with('-')
+        && scope.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Point clients that sent an un-encoded scoped name (`/@scope/pkg` or a
+/// registry namespace's `/scope/pkg`) at the percent-encoded route,
+/// explaining the problem in the body for clients that don't follow redirects.
+fn scoped_name_redirect(prefix: &str, scope: &str, rest: &str) -> Response {
+    if !scope.starts_with('@') && !is_namespace_scope(scope) {
+        return ApiError::not_found("route_not_found", format!("No route for {}/{}/{}", prefix, scope, rest)).into_response();
+    }
+    let name = format!("{}/{}", scope, rest);
+    let location = format!("{}/{}", prefix, encode_path_segment(&name));
+    (
+        StatusCode::PERMANENT_REDIRECT,
+        [(header::LOCATION, location.clone())],
+        Json(ApiResponse::<()>::error("scoped_name_not_encoded", format!(
+            "Scoped package names must be percent-encoded in the path: use {} or {}?name={}",
+            location, prefix, encode_path_segment(&name)
+        ))),
+    ).into_response()
+}
+
+/// Any path without a route
+pub async fn route_not_found() -> ApiError {
+    ApiError::not_found("route_not_found", "No such route")
+}
+
+/// Status of the database, the RPC node and the listener. Unhealthy (the
+/// database is unreachable or indexing lags too far) is a 503 that still
+/// carries the full report, with `code` set to `unhealthy`.
+#[utoipa::path(
+    get, path = "/health", tag = "meta",
+    responses(
+        (status = 200, description = "Serving; `status` is `degraded` when the RPC node is unreachable", body = HealthResponse),
+        (status = 503, description = "Database unreachable or indexer lag over `HEALTH_MAX_LAG_SLOTS`", body = HealthResponse),
+    )
+)]
+pub async fn health_check(
+    State(pool): State<Pool>,
+    State(probe): State<HealthProbe>,
+) -> (StatusCode, Json<ApiResponse<Health>>) {
+    let health = probe.check(&pool).await;
+    match unhealthy_reason(&health) {
+        None => (StatusCode::OK, Json(ApiResponse::success(health))),
+        Some(reason) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse { success: false, data: Some(health), error: Some(reason), code: Some("unhealthy".to_string()) }),
+        ),
+    }
 }
 
+/// Prometheus text exposition of the indexer's counters, gauges and histograms
+#[utoipa::path(
+    get, path = "/metrics", tag = "meta",
+    responses(
+        (status = 200, description = "Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 404, description = "`route_not_found`: no metrics recorder installed", body = ErrorResponse),
+    )
+)]
+pub async fn metrics_handler(State(metrics): State<Option<PrometheusHandle>>) -> Result<Response, ApiError> {
+    let handle = metrics.ok_or_else(|| ApiError::not_found("route_not_found", "Metrics are not enabled"))?;
+    Ok(([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], handle.render()).into_response())
+}
+
+#[utoipa::path(
+    get, path = "/api/search", tag = "packages", params(SearchQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "A page of results; `data` is the bare array unless `?envelope=1`", body = SearchPageResponse),
+        (status = 400, description = "Unknown `sort`, `invalid_version_req` for a bad `has_version`, or malformed parameters", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn search_packages_handler(
+    State(pool): State<Pool>,
+    ApiQuery(params): ApiQuery<SearchQuery>,
+    ApiQuery(envelope): ApiQuery<EnvelopeQuery>,
+) -> Result<Response, ApiError> {
+    let has_version = params.has_version.as_deref()
+        .map(|req| semver::VersionReq::parse(req).map_err(|e| ApiError::bad_request(
+            "invalid_version_req",
+            format!("has_version '{}' is not a semver requirement: {}", req, e),
+        )))
+        .transpose()?;
+    let filter = SearchFilter { author: params.author, has_version };
+    let packages = queries::search_packages(&pool, &params.q, &filter, params.sort, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Search", e))?;
+    Ok(page_response(packages, &envelope))
+}
+
+/// `name` arrives percent-decoded, so `/api/packages/%40scope%2Fpkg` looks up `@scope/pkg`
+#[utoipa::path(
+    get, path = "/api/packages/{name}", tag = "packages",
+    params(("name" = String, Path, description = "Package name; percent-encode scoped names")),
+    responses(
+        (status = 200, body = PackageResponse),
+        (status = 404, description = "`package_not_found`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
 pub async fn get_package_handler(
     State(pool): State<Pool>,
     Path(name): Path<String>,
-) -> Result<Json<ApiResponse<PackageWithVersions>>, StatusCode> {
+) -> Result<Json<ApiResponse<PackageWithVersions>>, ApiError> {
     match queries::get_package_with_versions(&pool, &name).await {
         Ok(Some(pkg)) => Ok(Json(ApiResponse::success(pkg))),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Get package error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        Ok(None) => Err(ApiError::not_found("package_not_found", format!("Package '{}' is not indexed", name))),
+        Err(e) => Err(ApiError::internal("Get package", e)),
+    }
+}
+
+#[utoipa::path(
+    get, path = "/api/packages/{name}/versions/{version}", tag = "packages",
+    params(
+        ("name" = String, Path, description = "Package name; percent-encode scoped names"),
+        ("version" = String, Path),
+    ),
+    responses(
+        (status = 200, body = VersionResponse),
+        (status = 404, description = "`version_not_found`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_version_handler(
+    State(pool): State<Pool>,
+    Path((name, version)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<VersionDetail>>, ApiError> {
+    match queries::get_version(&pool, &name, &version).await {
+        Ok(Some(detail)) => Ok(Json(ApiResponse::success(detail))),
+        Ok(None) => Err(ApiError::not_found("version_not_found", format!("{}@{} is not indexed", name, version))),
+        Err(e) => Err(ApiError::internal("Get version", e)),
+    }
+}
+
+/// Downloads over time, one `{date, count}` per bucket with empty buckets
+/// zero-filled. Buckets start at `from` truncated to the interval.
+#[utoipa::path(
+    get, path = "/api/packages/{name}/downloads", tag = "packages",
+    params(("name" = String, Path, description = "Package name; percent-encode scoped names"), DownloadSeriesQuery),
+    responses(
+        (status = 200, body = DownloadSeriesResponse),
+        (status = 400, description = "`invalid_range`: `from` after `to` or a span over ten years; `invalid_query`", body = ErrorResponse),
+        (status = 404, description = "`package_not_found`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_download_series_handler(
+    State(pool): State<Pool>,
+    Path(name): Path<String>,
+    ApiQuery(params): ApiQuery<DownloadSeriesQuery>,
+) -> Result<Json<ApiResponse<Vec<DownloadBucket>>>, ApiError> {
+    let to = params.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let from = params.from.unwrap_or(to - chrono::Duration::days(29));
+    let span = (to - from).num_days();
+    if !(0..=MAX_DOWNLOAD_SERIES_DAYS).contains(&span) {
+        return Err(ApiError::bad_request(
+            "invalid_range",
+            format!("Invalid range {}..{}: `from` must not be after `to`, and the span is capped at {} days", from, to, MAX_DOWNLOAD_SERIES_DAYS),
+        ));
+    }
+    let db = pool.get().await.map_err(|e| ApiError::internal("Download series", e))?;
+    let package_id = queries::get_package_id(&db, &name)
+        .await
+        .map_err(|e| ApiError::internal("Download series", e))?
+        .ok_or_else(|| ApiError::not_found("package_not_found", format!("Package '{}' is not indexed", name)))?;
+    let series = queries::get_download_series(&pool, package_id, params.interval, from, to)
+        .await
+        .map_err(|e| ApiError::internal("Download series", e))?;
+    Ok(Json(ApiResponse::success(series)))
+}
+
+/// A version's README.md, read from its tarball by the enrichment worker.
+/// Versions whose tarball wasn't read yet, or that have no README, answer 404.
+#[utoipa::path(
+    get, path = "/api/packages/{name}/readme", tag = "packages",
+    params(("name" = String, Path, description = "Package name; percent-encode scoped names"), DependenciesQuery),
+    responses(
+        (status = 200, body = ReadmeResponse),
+        (status = 404, description = "`package_not_found`, `version_not_found`, or `readme_not_found` while there is no README to serve", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_readme_handler(
+    State(pool): State<Pool>,
+    Path(name): Path<String>,
+    ApiQuery(params): ApiQuery<DependenciesQuery>,
+) -> Result<Json<ApiResponse<PackageReadme>>, ApiError> {
+    match queries::get_readme(&pool, &name, params.version.as_deref()).await {
+        Ok(Some((version, Some(readme)))) => Ok(Json(ApiResponse::success(PackageReadme { name, version, readme }))),
+        Ok(Some((version, None))) => Err(ApiError::not_found(
+            "readme_not_found",
+            format!("{}@{} has no README, or its tarball hasn't been read yet", name, version),
+        )),
+        Ok(None) => Err(match params.version {
+            Some(version) => ApiError::not_found("version_not_found", format!("{}@{} is not indexed", name, version)),
+            None => ApiError::not_found("package_not_found", format!("Package '{}' is not indexed", name)),
+        }),
+        Err(e) => Err(ApiError::internal("Get readme", e)),
+    }
+}
+
+/// What a version depends on, as recorded on-chain when it was published
+#[utoipa::path(
+    get, path = "/api/packages/{name}/dependencies", tag = "packages",
+    params(("name" = String, Path, description = "Package name; percent-encode scoped names"), DependenciesQuery),
+    responses(
+        (status = 200, body = DependenciesResponse),
+        (status = 404, description = "`package_not_found`, or `version_not_found` for an unknown `version`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_dependencies_handler(
+    State(pool): State<Pool>,
+    Path(name): Path<String>,
+    ApiQuery(params): ApiQuery<DependenciesQuery>,
+) -> Result<Json<ApiResponse<PackageDependencies>>, ApiError> {
+    match queries::get_dependencies(&pool, &name, params.version.as_deref()).await {
+        Ok(Some(dependencies)) => Ok(Json(ApiResponse::success(dependencies))),
+        Ok(None) => Err(match params.version {
+            Some(version) => ApiError::not_found("version_not_found", format!("{}@{} is not indexed", name, version)),
+            None => ApiError::not_found("package_not_found", format!("Package '{}' is not indexed", name)),
+        }),
+        Err(e) => Err(ApiError::internal("Get dependencies", e)),
+    }
+}
+
+/// Packages whose latest version depends on this one, alphabetically. A
+/// name nothing depends on answers with an empty list, indexed or not.
+#[utoipa::path(
+    get, path = "/api/packages/{name}/dependents", tag = "packages",
+    params(("name" = String, Path, description = "Package name; percent-encode scoped names"), ListQuery),
+    responses((status = 200, body = DependentsResponse), (status = 500, body = ErrorResponse))
+)]
+pub async fn get_dependents_handler(
+    State(pool): State<Pool>,
+    Path(name): Path<String>,
+    ApiQuery(params): ApiQuery<ListQuery>,
+) -> Result<Json<ApiResponse<Vec<Dependent>>>, ApiError> {
+    let limit = params.limit.min(100);
+    let dependents = queries::get_dependents(&pool, &name, limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get dependents", e))?;
+    Ok(Json(ApiResponse::success(dependents)))
+}
+
+/// Who would be affected by changes to one version: packages whose latest
+/// version requires it and, with `transitive`, the packages depending on
+/// those, each listed once at the nearest level it is reached
+#[utoipa::path(
+    get, path = "/api/packages/{name}/{version}/dependents", tag = "packages",
+    params(
+        ("name" = String, Path, description = "Package name; percent-encode scoped names"),
+        ("version" = String, Path),
+        VersionDependentsQuery,
+    ),
+    responses(
+        (status = 200, body = VersionDependentsResponse),
+        (status = 404, description = "`version_not_found`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_version_dependents_handler(
+    State(pool): State<Pool>,
+    State(DependentsDepth(max_depth)): State<DependentsDepth>,
+    Path((name, version)): Path<(String, String)>,
+    ApiQuery(params): ApiQuery<VersionDependentsQuery>,
+) -> Result<Json<ApiResponse<VersionDependents>>, ApiError> {
+    let depth = if params.transitive { params.depth.unwrap_or(max_depth).clamp(1, max_depth.max(1)) } else { 1 };
+    let depth = depth as i32;
+    match queries::get_version_dependents(&pool, &name, &version, depth).await {
+        Ok(Some(dependents)) => {
+            let direct_count = dependents.iter().filter(|dependent| dependent.depth == 1).count();
+            Ok(Json(ApiResponse::success(VersionDependents {
+                name,
+                version,
+                direct_count,
+                transitive_count: dependents.len() - direct_count,
+                max_depth: depth,
+                dependents,
+            })))
         }
+        Ok(None) => Err(ApiError::not_found("version_not_found", format!("{}@{} is not indexed", name, version))),
+        Err(e) => Err(ApiError::internal("Get version dependents", e)),
     }
 }
 
+/// Repeat downloads from one client within this window count once
+pub const DOWNLOAD_DEDUP_WINDOW_SECS: f64 = 3600.0;
+
+#[derive(Serialize, ToSchema)]
+pub struct DownloadRecorded {
+    /// False when the client already reported this version within the window
+    pub counted: bool,
+}
+
+/// Whether clients are identified by the last `X-Forwarded-For` hop, from
+/// `RATE_LIMIT_TRUST_FORWARDED_FOR`; off unless a proxy sets the header
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrustForwardedFor(pub bool);
+
+/// Client address for rate limiting and download dedup: the last
+/// `X-Forwarded-For` hop when `trust_forwarded_for`, otherwise the peer address.
+/// Proxies append the address they saw, so earlier hops are whatever the
+/// client sent and can't be trusted.
+pub(crate) fn client_address(headers: &HeaderMap, peer: Option<SocketAddr>, trust_forwarded_for: bool) -> Option<String> {
+    let forwarded = if trust_forwarded_for {
+        headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .last()
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    } else {
+        None
+    };
+    forwarded.or_else(|| peer.map(|addr| addr.ip().to_string()))
+}
+
+#[utoipa::path(
+    post, path = "/api/packages/{name}/{version}/download", tag = "packages",
+    params(("name" = String, Path), ("version" = String, Path)),
+    responses(
+        (status = 200, body = DownloadResponse),
+        (status = 404, description = "`version_not_found`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn record_download_handler(
+    State(pool): State<Pool>,
+    State(TrustForwardedFor(trust_forwarded_for)): State<TrustForwardedFor>,
+    Path((name, version)): Path<(String, String)>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Result<Json<ApiResponse<DownloadRecorded>>, ApiError> {
+    let client = client_address(&headers, connect_info.map(|ConnectInfo(addr)| addr), trust_forwarded_for);
+    match queries::record_download(&pool, &name, &version, client.as_deref(), DOWNLOAD_DEDUP_WINDOW_SECS).await {
+        Ok(Some(counted)) => Ok(Json(ApiResponse::success(DownloadRecorded { counted }))),
+        Ok(None) => Err(ApiError::not_found("version_not_found", format!("{}@{} is not indexed", name, version))),
+        Err(e) => Err(ApiError::internal("Record download", e)),
+    }
+}
+
+#[utoipa::path(
+    get, path = "/api/stats/trending", tag = "packages", params(TrendingQuery),
+    responses(
+        (status = 200, body = TrendingResponse),
+        (status = 400, description = "`invalid_window`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn get_trending_packages_handler(
+    State(pool): State<Pool>,
+    ApiQuery(params): ApiQuery<TrendingQuery>,
+) -> Result<Json<ApiResponse<Vec<TrendingPackage>>>, ApiError> {
+    let Some(window_secs) = parse_window(&params.window) else {
+        return Err(ApiError::bad_request(
+            "invalid_window",
+            format!("Invalid window '{}': use <n>h, <n>d or <n>w, up to 90d", params.window),
+        ));
+    };
+    let limit = params.limit.min(100);
+    let packages = queries::get_trending_packages(&pool, window_secs, limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get trending packages", e))?;
+    Ok(Json(ApiResponse::success(packages)))
+}
+
+#[utoipa::path(
+    get, path = "/api/stats/recent", tag = "packages", params(ListQuery),
+    responses((status = 200, body = VersionListResponse), (status = 500, body = ErrorResponse))
+)]
+pub async fn get_recent_versions_handler(
+    State(pool): State<Pool>,
+    ApiQuery(params): ApiQuery<ListQuery>,
+) -> Result<Json<ApiResponse<Vec<VersionDetail>>>, ApiError> {
+    let limit = params.limit.min(100);
+    let versions = queries::get_recent_versions(&pool, limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get recent versions", e))?;
+    Ok(Json(ApiResponse::success(versions)))
+}
+
+/// With `?name=` this answers like `GET /api/packages/{name}`
+#[utoipa::path(
+    get, path = "/api/packages", tag = "packages", params(PackagesQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "A page of the newest packages (the bare array unless `?envelope=1`), or one package's detail (`PackageResponse`) with `?name=`", body = PackagePageResponse),
+        (status = 404, description = "`package_not_found` for `?name=`", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
 pub async fn list_packages_handler(
     State(pool): State<Pool>,
-    Query(params): Query<ListQuery>,
-) -> Result<Json<ApiResponse<Vec<Package>>>, StatusCode> {
-    match queries::list_packages(&pool, params.limit, params.offset).await {
-        Ok(packages) => Ok(Json(ApiResponse::success(packages))),
-        Err(e) => {
-            tracing::error!("List packages error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
+    ApiQuery(params): ApiQuery<PackagesQuery>,
+    ApiQuery(envelope): ApiQuery<EnvelopeQuery>,
+) -> Result<Response, ApiError> {
+    if let Some(name) = params.name {
+        return get_package_handler(State(pool), Path(name))
+            .await
+            .map(IntoResponse::into_response);
     }
+    let packages = queries::list_packages(&pool, params.limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("List packages", e))?;
+    Ok(page_response(packages, &envelope))
+}
+
+/// Packages whose current authority is the given base58 pubkey
+#[utoipa::path(
+    get, path = "/api/authors/{pubkey}/packages", tag = "packages",
+    params(("pubkey" = String, Path, description = "Base58 authority pubkey"), ListQuery),
+    responses((status = 200, body = PackageListResponse), (status = 500, body = ErrorResponse))
+)]
+pub async fn get_author_packages_handler(
+    State(pool): State<Pool>,
+    Path(pubkey): Path<String>,
+    ApiQuery(params): ApiQuery<ListQuery>,
+) -> Result<Json<ApiResponse<Vec<Package>>>, ApiError> {
+    let limit = params.limit.min(100);
+    let packages = queries::get_packages_by_author(&pool, &pubkey, limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get author packages", e))?;
+    Ok(Json(ApiResponse::success(packages)))
 }
 
+#[utoipa::path(
+    get, path = "/api/stats", tag = "meta",
+    responses((status = 200, body = StatsResponse), (status = 500, body = ErrorResponse))
+)]
 pub async fn get_stats_handler(
     State(pool): State<Pool>,
-) -> Result<Json<ApiResponse<Stats>>, StatusCode> {
-    match queries::get_stats(&pool).await {
-        Ok(stats) => Ok(Json(ApiResponse::success(stats))),
-        Err(e) => {
-            tracing::error!("Get stats error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<ApiResponse<Stats>>, ApiError> {
+    let stats = queries::get_stats(&pool).await.map_err(|e| ApiError::internal("Get stats", e))?;
+    Ok(Json(ApiResponse::success(stats)))
 }
 
+#[utoipa::path(
+    get, path = "/api/events/recent", tag = "events", params(EventsQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "A page of events; `data` is the bare array unless `?envelope=1`", body = EventPageResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
 pub async fn get_recent_events_handler(
     State(pool): State<Pool>,
-    Query(params): Query<ListQuery>,
-) -> Result<Json<ApiResponse<Vec<Event>>>, StatusCode> {
+    ApiQuery(params): ApiQuery<EventsQuery>,
+    ApiQuery(envelope): ApiQuery<EnvelopeQuery>,
+) -> Result<Response, ApiError> {
     let limit = params.limit.min(100); // Cap at 100
-    match queries::get_recent_events(&pool, limit).await {
-        Ok(events) => Ok(Json(ApiResponse::success(events))),
-        Err(e) => {
-            tracing::error!("Get recent events error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let events = queries::get_recent_events(&pool, params.event_type.as_deref(), limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get recent events", e))?;
+    Ok(page_response(events, &envelope))
+}
+
+/// Server-Sent Events feed of registry events: the latest `replay` stored
+/// events, then every new one as it is indexed. Each frame's data is the
+/// event as JSON and its id the event id.
+#[utoipa::path(
+    get, path = "/api/events/stream", tag = "events", params(StreamQuery),
+    responses(
+        (status = 200, description = "`text/event-stream` of `Event` JSON frames", content_type = "text/event-stream", body = Event),
+        (status = 500, body = ErrorResponse),
+    )
+)]
+pub async fn stream_events_handler(
+    State(pool): State<Pool>,
+    State(events): State<EventBus>,
+    ApiQuery(params): ApiQuery<StreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, axum::Error>>>, ApiError> {
+    // Subscribe before reading the backlog so nothing stored in between is lost
+    let live = events.subscribe();
+    let replay = params.replay.clamp(0, 100);
+    let mut backlog = if replay > 0 {
+        queries::get_recent_events(&pool, None, replay, 0)
+            .await
+            .map_err(|e| ApiError::internal("Event stream replay", e))?
+            .items
+    } else {
+        Vec::new()
+    };
+    backlog.reverse();
+    let replayed_up_to = backlog.iter().map(|event| event.id).max().unwrap_or(0);
+
+    let live = BroadcastStream::new(live).filter_map(move |received| match received {
+        Ok(event) if event.id > replayed_up_to => Some(event),
+        Ok(_) => None,
+        Err(BroadcastStreamRecvError::Lagged(missed)) => {
+            tracing::warn!("Event stream subscriber fell behind; skipped {} event(s)", missed);
+            None
         }
-    }
+    });
+    let stream = tokio_stream::iter(backlog)
+        .chain(live)
+        .map(|event| SseEvent::default().id(event.id.to_string()).json_data(&event));
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().interval(STREAM_HEARTBEAT).text("heartbeat")))
 }
 
+#[utoipa::path(
+    get, path = "/api/events/{package}", tag = "events",
+    params(("package" = String, Path, description = "Package name; percent-encode scoped names"), EventsQuery, EnvelopeQuery),
+    responses(
+        (status = 200, description = "A page of the package's events; `data` is the bare array unless `?envelope=1`", body = PackageEventPageResponse),
+        (status = 500, body = ErrorResponse),
+    )
+)]
 pub async fn get_package_events_handler(
     State(pool): State<Pool>,
     Path(package_name): Path<String>,
-    Query(params): Query<ListQuery>,
-) -> Result<Json<ApiResponse<Vec<Event>>>, StatusCode> {
+    ApiQuery(params): ApiQuery<EventsQuery>,
+    ApiQuery(envelope): ApiQuery<EnvelopeQuery>,
+) -> Result<Response, ApiError> {
     let limit = params.limit.min(100);
-    match queries::get_package_events(&pool, &package_name, limit, params.offset).await {
-        Ok(events) => Ok(Json(ApiResponse::success(events))),
-        Err(e) => {
-            tracing::error!("Get package events error: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    let events = queries::get_package_events(&pool, &package_name, params.event_type.as_deref(), limit, params.offset)
+        .await
+        .map_err(|e| ApiError::internal("Get package events", e))?;
+    Ok(page_response(events, &envelope))
+}
+
+#[utoipa::path(
+    get, path = "/api/packages/{scope}/{name}", tag = "packages",
+    params(("scope" = String, Path, description = "`@scope` or namespace of an un-encoded scoped name"), ("name" = String, Path)),
+    responses(
+        (status = 308, description = "`scoped_name_not_encoded`: redirect to the percent-encoded route", body = ErrorResponse),
+        (status = 404, description = "`route_not_found`", body = ErrorResponse),
+    )
+)]
+pub async fn unencoded_scoped_package_handler(
+    Path((scope, rest)): Path<(String, String)>,
+) -> Response {
+    scoped_name_redirect("/api/packages", &scope, &rest)
+}
+
+#[utoipa::path(
+    get, path = "/api/events/{scope}/{name}", tag = "events",
+    params(("scope" = String, Path, description = "`@scope` or namespace of an un-encoded scoped name"), ("name" = String, Path)),
+    responses(
+        (status = 308, description = "`scoped_name_not_encoded`: redirect to the percent-encoded route", body = ErrorResponse),
+        (status = 404, description = "`route_not_found`", body = ErrorResponse),
+    )
+)]
+pub async fn unencoded_scoped_events_handler(
+    Path((scope, rest)): Path<(String, String)>,
+) -> Response {
+    scoped_name_redirect("/api/events", &scope, &rest)
 }
 
 // --- Manual ingestion endpoint for testing indexer without waiting for chain slots ---
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct IngestRequest {
     pub log: String,
     pub signature: Option<String>,
+    /// Index of the log line among its transaction's log messages (default 0);
+    /// events of one transaction are told apart by it
+    pub log_index: Option<i32>,
     pub slot: Option<i64>,
     pub block_time: Option<i64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct IngestResult {
     pub event: Option<Event>,
     pub ipfs_hash: Option<String>,
     pub message: String,
 }
 
+#[utoipa::path(
+    post, path = "/api/ingest", tag = "events", request_body = IngestRequest,
+    security(("ingest_key" = [])),
+    responses(
+        (status = 200, body = IngestResponse),
+        (status = 400, description = "`invalid_body`", body = ErrorResponse),
+        (status = 401, description = "`unauthorized`: missing or unknown `Authorization: Bearer` key", body = ErrorResponse),
+        (status = 415, description = "`invalid_body`: not JSON", body = ErrorResponse),
+        (status = 422, description = "`invalid_body`: missing or mistyped fields", body = ErrorResponse),
+    )
+)]
 pub async fn ingest_log_handler(
     State(pool): State<Pool>,
-    Json(req): Json<IngestRequest>,
-) -> Result<Json<ApiResponse<IngestResult>>, StatusCode> {
+    State(events): State<EventBus>,
+    State(accounts): State<Option<PackageAccounts>>,
+    ApiJson(req): ApiJson<IngestRequest>,
+) -> Result<Json<ApiResponse<IngestResult>>, ApiError> {
     let signature = req.signature.unwrap_or_else(|| "manual_sig".to_string());
     let slot = req.slot.unwrap_or(0);
-    let event_opt = crate::indexer::parser::parse_transaction(&req.log, &signature, slot, req.block_time);
+    let log_index = req.log_index.unwrap_or(0);
+    let event_opt = crate::indexer::parser::parse_transaction(&req.log, &signature, log_index, slot, req.block_time);
     if let Some(event) = event_opt {
-        // Store event first
-        if let Err(e) = queries::insert_event(
-            &pool,
-            &event.event_type,
-            &event.package_name,
-            event.version.as_deref(),
-            &event.transaction_signature,
-            event.slot,
-            req.block_time,
-        ).await {
-            tracing::warn!("Failed to insert manual event: {}", e);
-        }
-        // Ingest metadata
-        if let Err(e) = ingest_event(&pool, &event, &req.log).await {
-            tracing::warn!("Manual ingestion failed: {}", e);
-        }
+        let message = match ingest_events(&pool, &events, accounts.as_ref(), &[(event.clone(), req.log.as_str())], req.block_time).await {
+            Ok(0) => "Event already ingested; nothing changed",
+            Ok(_) => "Event parsed and ingested",
+            Err(e) => {
+                tracing::warn!("Manual ingestion failed: {}", e);
+                "Event parsed but not stored"
+            }
+        };
         let ipfs = extract_ipfs_hash(&req.log);
         Ok(Json(ApiResponse::success(IngestResult {
             event: Some(event),
             ipfs_hash: ipfs,
-            message: "Event parsed and ingested".to_string(),
+            message: message.to_string(),
         })))
     } else {
         Ok(Json(ApiResponse::success(IngestResult {
@@ -194,3 +952,49 @@ pub async fn ingest_log_handler(
         })))
     }
 }
+
+/// Re-read `name@version` from its on-chain account and store it, for a
+/// publish the listener missed. Answers with the version as now indexed.
+#[utoipa::path(
+    post, path = "/api/admin/resync/{name}/{version}", tag = "admin",
+    params(
+        ("name" = String, Path, description = "Package name; percent-encode scoped names"),
+        ("version" = String, Path),
+    ),
+    security(("admin_key" = [])),
+    responses(
+        (status = 200, body = VersionResponse),
+        (status = 401, description = "`unauthorized`: missing or unknown `Authorization: Bearer` key", body = ErrorResponse),
+        (status = 404, description = "`account_not_found`: no package account for the version", body = ErrorResponse),
+        (status = 500, body = ErrorResponse),
+        (status = 502, description = "`rpc_error`, or `invalid_account` when the account isn't a package", body = ErrorResponse),
+        (status = 503, description = "`resync_unavailable`: no valid `ANTSOL_PROGRAM_ID` to derive addresses from", body = ErrorResponse),
+    )
+)]
+pub async fn resync_package_handler(
+    State(pool): State<Pool>,
+    State(accounts): State<Option<PackageAccounts>>,
+    Path((name, version)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<VersionDetail>>, ApiError> {
+    let Some(accounts) = accounts else {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "resync_unavailable",
+            "Re-sync is unavailable: the indexer has no valid ANTSOL_PROGRAM_ID",
+        ));
+    };
+    let account = accounts.read(&name, &version).await.map_err(|e| match e {
+        AccountError::NotFound(_) => ApiError::not_found("account_not_found", e.to_string()),
+        AccountError::Undecodable(_) => ApiError::new(StatusCode::BAD_GATEWAY, "invalid_account", e.to_string()),
+        AccountError::Rpc(..) => {
+            tracing::warn!("Re-sync of {}@{}: {}", name, version, e);
+            ApiError::new(StatusCode::BAD_GATEWAY, "rpc_error", format!("Could not fetch the account of {}@{}", name, version))
+        }
+    })?;
+    resync_version(&pool, &account).await.map_err(|e| ApiError::internal("Re-sync", e))?;
+    match queries::get_version(&pool, &name, &version).await {
+        Ok(Some(detail)) => Ok(Json(ApiResponse::success(detail))),
+        Ok(None) => Err(ApiError::internal("Re-sync", format!("{}@{} missing after re-sync", name, version))),
+        Err(e) => Err(ApiError::internal("Re-sync", e)),
+    }
+}
@@ -0,0 +1,161 @@
+use crate::db::Pool;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::{Config, DEFAULT_HEALTH_MAX_LAG_SLOTS};
+use crate::db::{models::*, queries};
+
+/// How long `/health` waits on the RPC node for the current slot
+const RPC_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// What `/health` checks the indexer against
+#[derive(Clone, Debug)]
+pub struct HealthSettings {
+    /// RPC node asked for the current slot; lag is unknown when `None`
+    pub rpc_url: Option<String>,
+    /// Commitment of the current slot, which should match the listener's
+    pub commitment: CommitmentConfig,
+    /// Lag beyond which the indexer counts as down
+    pub max_lag_slots: u64,
+}
+
+impl Default for HealthSettings {
+    fn default() -> Self {
+        Self {
+            rpc_url: None,
+            commitment: CommitmentConfig::confirmed(),
+            max_lag_slots: DEFAULT_HEALTH_MAX_LAG_SLOTS,
+        }
+    }
+}
+
+impl HealthSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rpc_url: Some(config.solana_rpc_url.clone()),
+            commitment: config.commitment,
+            max_lag_slots: config.health_max_lag_slots,
+        }
+    }
+}
+
+/// Router state for `/health`; holds one RPC client for all checks
+#[derive(Clone)]
+pub struct HealthProbe {
+    rpc: Option<Arc<RpcClient>>,
+    max_lag_slots: u64,
+}
+
+impl HealthProbe {
+    pub fn new(settings: HealthSettings) -> Self {
+        let rpc = settings.rpc_url.map(|url| {
+            Arc::new(RpcClient::new_with_timeout_and_commitment(url, RPC_TIMEOUT, settings.commitment))
+        });
+        Self { rpc, max_lag_slots: settings.max_lag_slots }
+    }
+
+    /// Query the database and the RPC node concurrently and assess the result
+    pub async fn check(&self, pool: &Pool) -> Health {
+        let state = async { queries::get_indexer_state(pool).await.map_err(|e| e.to_string()) };
+        let current_slot = async {
+            match &self.rpc {
+                Some(rpc) => rpc.get_slot().await.map_err(|e| e.to_string()),
+                None => Err("No RPC URL configured".to_string()),
+            }
+        };
+        let (state, current_slot) = tokio::join!(state, current_slot);
+        assess(state, current_slot, self.max_lag_slots)
+    }
+}
+
+/// Combine the checks. Only a database failure or excess lag is `unhealthy`;
+/// an unreachable RPC node leaves lag unknown and is `degraded`.
+pub fn assess(state: Result<IndexerState, String>, current_slot: Result<u64, String>, max_lag_slots: u64) -> Health {
+    let database = ComponentHealth { ok: state.is_ok(), error: state.as_ref().err().cloned() };
+    let rpc = ComponentHealth { ok: current_slot.is_ok(), error: current_slot.as_ref().err().cloned() };
+    let state = state.ok();
+    let current_slot = current_slot.ok();
+    let lag_slots = match (&state, current_slot) {
+        (Some(state), Some(current)) => Some(current.saturating_sub(state.last_processed_slot)),
+        _ => None,
+    };
+    let lagging = lag_slots.is_some_and(|lag| lag > max_lag_slots);
+
+    let status = if !database.ok || lagging {
+        "unhealthy"
+    } else if !rpc.ok {
+        "degraded"
+    } else {
+        "ok"
+    };
+    Health {
+        status: status.to_string(),
+        indexer: IndexerHealth {
+            ok: database.ok && !lagging,
+            last_processed_slot: state.as_ref().map(|state| state.last_processed_slot),
+            current_slot,
+            lag_slots,
+            max_lag_slots,
+            seconds_since_update: state.as_ref().and_then(|state| state.seconds_since_update),
+            error_count: state.as_ref().map(|state| state.error_count),
+            last_error: state.and_then(|state| state.last_error),
+        },
+        database,
+        rpc,
+    }
+}
+
+/// Why `health` is unhealthy, or `None` when it can serve traffic
+pub fn unhealthy_reason(health: &Health) -> Option<String> {
+    if let Some(error) = &health.database.error {
+        return Some(format!("Database unreachable: {}", error));
+    }
+    match health.indexer.lag_slots {
+        Some(lag) if !health.indexer.ok => Some(format!(
+            "Indexer is {} slots behind the chain (limit {})",
+            lag, health.indexer.max_lag_slots
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(slot: u64) -> IndexerState {
+        IndexerState { last_processed_slot: slot, error_count: 2, last_error: Some("timeout".to_string()), seconds_since_update: Some(4) }
+    }
+
+    #[test]
+    fn caught_up_indexer_is_ok() {
+        let health = assess(Ok(state(1_000)), Ok(1_010), 300);
+        assert_eq!(health.status, "ok");
+        assert_eq!(health.indexer.lag_slots, Some(10));
+        assert_eq!(health.indexer.last_error.as_deref(), Some("timeout"));
+        assert!(unhealthy_reason(&health).is_none());
+    }
+
+    #[test]
+    fn lag_over_the_limit_is_unhealthy() {
+        let health = assess(Ok(state(1_000)), Ok(1_301), 300);
+        assert_eq!(health.status, "unhealthy");
+        assert!(!health.indexer.ok);
+        assert!(unhealthy_reason(&health).unwrap().contains("301 slots behind"));
+    }
+
+    #[test]
+    fn database_down_is_unhealthy_and_rpc_down_is_degraded() {
+        let health = assess(Err("connection refused".to_string()), Ok(1_000), 300);
+        assert_eq!(health.status, "unhealthy");
+        assert_eq!(health.indexer.lag_slots, None);
+        assert!(unhealthy_reason(&health).unwrap().starts_with("Database unreachable"));
+
+        let health = assess(Ok(state(1_000)), Err("timed out".to_string()), 300);
+        assert_eq!(health.status, "degraded");
+        assert!(health.indexer.ok);
+        assert!(unhealthy_reason(&health).is_none());
+    }
+}
@@ -1,2 +1,5 @@
+pub mod access;
 pub mod handlers;
+pub mod health;
+pub mod openapi;
 pub mod routes;
@@ -0,0 +1,117 @@
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use super::handlers::{self, *};
+use crate::db::models::*;
+
+/// OpenAPI document for every route, served at `/api/openapi.json`
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AntSol Indexer API",
+        description = "Read API over the indexed AntSol registry. Every JSON response uses the `ApiResponse` envelope; failures carry a machine-readable `code`. `/api` routes may answer 429 (`rate_limited`) with a `Retry-After` header."
+    ),
+    paths(
+        handlers::health_check,
+        handlers::metrics_handler,
+        handlers::search_packages_handler,
+        handlers::get_trending_packages_handler,
+        handlers::get_recent_versions_handler,
+        handlers::get_package_handler,
+        handlers::unencoded_scoped_package_handler,
+        handlers::get_version_handler,
+        handlers::get_download_series_handler,
+        handlers::get_dependencies_handler,
+        handlers::get_dependents_handler,
+        handlers::get_version_dependents_handler,
+        handlers::get_readme_handler,
+        handlers::record_download_handler,
+        handlers::list_packages_handler,
+        handlers::get_author_packages_handler,
+        handlers::get_stats_handler,
+        handlers::get_recent_events_handler,
+        handlers::stream_events_handler,
+        handlers::get_package_events_handler,
+        handlers::unencoded_scoped_events_handler,
+        handlers::ingest_log_handler,
+        handlers::resync_package_handler,
+    ),
+    components(schemas(
+        Package,
+        SearchResult,
+        SearchSort,
+        DownloadInterval,
+        DownloadBucket,
+        TrendingPackage,
+        Version,
+        VersionDetail,
+        PackageWithVersions,
+        Event,
+        EventFinality,
+        DependencyChange,
+        Dependency,
+        ExternalDependency,
+        PackageDependencies,
+        PackageReadme,
+        Dependent,
+        VersionDependent,
+        VersionDependents,
+        PackageEvent,
+        Stats,
+        Health,
+        ComponentHealth,
+        IndexerHealth,
+        DownloadRecorded,
+        IngestRequest,
+        IngestResult,
+        PackagePage,
+        SearchResultPage,
+        EventPage,
+        PackageEventPage,
+        HealthResponse,
+        ErrorResponse,
+        SearchResponse,
+        PackageResponse,
+        VersionResponse,
+        DownloadSeriesResponse,
+        DependenciesResponse,
+        DependentsResponse,
+        DownloadResponse,
+        TrendingResponse,
+        VersionListResponse,
+        PackageListResponse,
+        StatsResponse,
+        EventListResponse,
+        PackageEventListResponse,
+        SearchPageResponse,
+        PackagePageResponse,
+        EventPageResponse,
+        PackageEventPageResponse,
+        IngestResponse,
+        ReadmeResponse,
+        VersionDependentsResponse,
+    )),
+    modifiers(&BearerKeySchemes),
+    tags(
+        (name = "packages", description = "Packages, versions and downloads"),
+        (name = "events", description = "Indexed program events"),
+        (name = "meta", description = "Health, metrics and registry statistics"),
+        (name = "admin", description = "Operator routes; require a key from `ADMIN_API_KEYS`"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the bearer keys `POST /api/ingest` and the admin routes require
+struct BearerKeySchemes;
+
+impl Modify for BearerKeySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        for name in ["ingest_key", "admin_key"] {
+            components.add_security_scheme(
+                name,
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+}
@@ -1,17 +1,128 @@
-use axum::{routing::{get, post}, Router};
-use deadpool_postgres::Pool;
+use axum::{extract::FromRef, middleware, routing::{get, post}, Router};
+use crate::db::Pool;
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
+use super::access::{rate_limit, require_admin_key, require_ingest_key, AdminKeys, ApiSettings, IngestKeys, RateLimiter};
 use super::handlers::*;
+use super::health::HealthProbe;
+use super::openapi::ApiDoc;
+use crate::indexer::accounts::PackageAccounts;
+use crate::indexer::broadcast::EventBus;
+use crate::telemetry::track_requests;
 
+/// Shared handler state; handlers extract the part they need
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: Pool,
+    pub events: EventBus,
+    pub health: HealthProbe,
+    pub metrics: Option<PrometheusHandle>,
+    pub package_accounts: Option<PackageAccounts>,
+    pub dependents_depth: DependentsDepth,
+    pub trust_forwarded_for: TrustForwardedFor,
+}
+
+impl FromRef<AppState> for Pool {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
+}
+
+impl FromRef<AppState> for EventBus {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for HealthProbe {
+    fn from_ref(state: &AppState) -> Self {
+        state.health.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<PackageAccounts> {
+    fn from_ref(state: &AppState) -> Self {
+        state.package_accounts.clone()
+    }
+}
+
+impl FromRef<AppState> for Option<PrometheusHandle> {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for DependentsDepth {
+    fn from_ref(state: &AppState) -> Self {
+        state.dependents_depth
+    }
+}
+
+impl FromRef<AppState> for TrustForwardedFor {
+    fn from_ref(state: &AppState) -> Self {
+        state.trust_forwarded_for
+    }
+}
+
+/// Router with its own event bus and default settings: no rate limit,
+/// ingestion and admin routes refused because no keys are configured, and
+/// no RPC node for `/health` to measure lag against
 pub fn create_router(pool: Pool) -> Router {
-    Router::new()
-        .route("/health", get(health_check))
+    create_router_with(pool, EventBus::new(), ApiSettings::default())
+}
+
+/// Router whose `/api/events/stream` also carries events published on
+/// `events`, with access control from `settings`
+pub fn create_router_with(pool: Pool, events: EventBus, settings: ApiSettings) -> Router {
+    let ingest_keys = IngestKeys::new(settings.ingest_api_keys);
+    let admin_keys = AdminKeys::new(settings.admin_api_keys);
+    let health = HealthProbe::new(settings.health);
+    let mut api = Router::new()
         .route("/api/search", get(search_packages_handler))
         .route("/api/packages/:name", get(get_package_handler))
+        .route("/api/packages/:name/:version", get(unencoded_scoped_package_handler))
+        .route("/api/packages/:name/versions/:version", get(get_version_handler))
+        .route("/api/packages/:name/downloads", get(get_download_series_handler))
+        .route("/api/packages/:name/dependencies", get(get_dependencies_handler))
+        .route("/api/packages/:name/dependents", get(get_dependents_handler))
+        .route("/api/packages/:name/readme", get(get_readme_handler))
+        .route("/api/packages/:name/:version/download", post(record_download_handler))
+        .route("/api/packages/:name/:version/dependents", get(get_version_dependents_handler))
         .route("/api/packages", get(list_packages_handler))
+        .route("/api/authors/:pubkey/packages", get(get_author_packages_handler))
         .route("/api/stats", get(get_stats_handler))
+        .route("/api/stats/trending", get(get_trending_packages_handler))
+        .route("/api/stats/recent", get(get_recent_versions_handler))
         .route("/api/events/recent", get(get_recent_events_handler))
+        .route("/api/events/stream", get(stream_events_handler))
         .route("/api/events/:package", get(get_package_events_handler))
-    .route("/api/ingest", post(ingest_log_handler))
-        .with_state(pool)
+        .route("/api/events/:package/:name", get(unencoded_scoped_events_handler))
+        .route(
+            "/api/ingest",
+            post(ingest_log_handler).route_layer(middleware::from_fn_with_state(ingest_keys, require_ingest_key)),
+        )
+        .route(
+            "/api/admin/resync/:name/:version",
+            post(resync_package_handler).route_layer(middleware::from_fn_with_state(admin_keys, require_admin_key)),
+        );
+    let trust_forwarded_for = TrustForwardedFor(settings.rate_limit.is_some_and(|config| config.trust_forwarded_for));
+    if let Some(config) = settings.rate_limit {
+        api = api.layer(middleware::from_fn_with_state(Arc::new(RateLimiter::new(config)), rate_limit));
+    }
+
+    let metrics = settings.metrics;
+    let package_accounts = settings.package_accounts;
+    let dependents_depth = settings.dependents_depth;
+
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/metrics", get(metrics_handler))
+        .merge(api)
+        .merge(SwaggerUi::new("/docs").url("/api/openapi.json", ApiDoc::openapi()))
+        .route_layer(middleware::from_fn(track_requests))
+        .fallback(route_not_found)
+        .with_state(AppState { pool, events, health, metrics, package_accounts, dependents_depth, trust_forwarded_for })
 }
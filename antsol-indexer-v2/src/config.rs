@@ -1,3 +1,4 @@
+use solana_sdk::commitment_config::CommitmentConfig;
 use std::env;
 
 #[derive(Clone, Debug)]
@@ -9,17 +10,108 @@ pub struct Config {
     pub port: u16,
     pub start_slot: Option<u64>,
     pub poll_interval_secs: u64,
+    /// How new transactions are discovered
+    pub mode: IndexerMode,
+    /// Websocket endpoint for `logsSubscribe`, derived from the RPC URL by default
+    pub ws_url: String,
+    /// Polling interval in websocket mode, where polling only fills gaps
+    pub gap_fill_interval_secs: u64,
+    /// Commitment events are ingested at; they are marked final separately
+    pub commitment: CommitmentConfig,
+    /// Bearer tokens accepted by `POST /api/ingest`; ingestion is refused when empty
+    pub ingest_api_keys: Vec<String>,
+    /// Bearer tokens accepted by `/api/admin` routes; they are refused when empty
+    pub admin_api_keys: Vec<String>,
+    /// Per-client request budget for `/api` routes; `None` when disabled
+    pub rate_limit: Option<RateLimitConfig>,
+    /// `/health` answers 503 once the indexer is more than this many slots
+    /// behind the RPC node
+    pub health_max_lag_slots: u64,
+    /// Days of per-download rows kept before they are rolled up into daily
+    /// counts; `None` keeps them forever
+    pub download_retention_days: Option<u32>,
+    /// Reading antsol.toml and README.md out of version tarballs; `None` when disabled
+    pub enrichment: Option<EnrichmentConfig>,
+    /// Deepest level `GET /api/packages/{name}/{version}/dependents` follows
+    /// transitive dependents to
+    pub dependents_max_depth: u32,
 }
 
+/// `INDEXER_MODE`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexerMode {
+    /// Poll `getSignaturesForAddress` every `INDEXER_POLL_INTERVAL_SECS`
+    Polling,
+    /// Ingest `logsSubscribe` notifications as they arrive, polling every
+    /// `INDEXER_GAP_FILL_SECS` for anything missed while disconnected
+    Websocket,
+}
+
+/// Token bucket settings for the `/api` rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Sustained requests per minute per client
+    pub per_minute: u32,
+    /// Requests a client may make at once before the rate applies
+    pub burst: u32,
+    /// Identify clients by the last `X-Forwarded-For` hop, the one the proxy
+    /// appended, instead of the peer address; only safe behind a proxy that
+    /// sets the header
+    pub trust_forwarded_for: bool,
+}
+
+/// Settings for the enrichment worker
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnrichmentConfig {
+    /// Gateway tarballs are fetched from, as `<gateway>/ipfs/<cid>`
+    pub gateway: String,
+    /// Tarballs fetched and read at once
+    pub concurrency: usize,
+}
+
+/// Gateway when `ENRICHMENT_GATEWAY` is unset
+pub const DEFAULT_ENRICHMENT_GATEWAY: &str = "https://ipfs.io";
+
+/// Concurrency when `ENRICHMENT_CONCURRENCY` is unset
+pub const DEFAULT_ENRICHMENT_CONCURRENCY: usize = 4;
+
+/// Requests per minute when `RATE_LIMIT_PER_MINUTE` is unset
+pub const DEFAULT_RATE_LIMIT_PER_MINUTE: u32 = 120;
+
+/// Burst when `RATE_LIMIT_BURST` is unset
+pub const DEFAULT_RATE_LIMIT_BURST: u32 = 30;
+
+/// Gap-filling poll interval when `INDEXER_GAP_FILL_SECS` is unset; short
+/// enough that the lag it allows stays under the default health threshold
+pub const DEFAULT_GAP_FILL_INTERVAL_SECS: u64 = 60;
+
+/// Download retention when `DOWNLOAD_RETENTION_DAYS` is unset
+pub const DEFAULT_DOWNLOAD_RETENTION_DAYS: u32 = 180;
+
+/// Trending ranks over raw download rows for windows up to 90 days, so
+/// retention can't be shorter
+pub const MIN_DOWNLOAD_RETENTION_DAYS: u32 = 90;
+
+/// Lag threshold when `HEALTH_MAX_LAG_SLOTS` is unset, about two minutes of slots
+pub const DEFAULT_HEALTH_MAX_LAG_SLOTS: u64 = 300;
+
+/// Transitive dependent depth when `DEPENDENTS_MAX_DEPTH` is unset
+pub const DEFAULT_DEPENDENTS_MAX_DEPTH: u32 = 5;
+
+/// Deepest `DEPENDENTS_MAX_DEPTH` accepted; the recursive query grows with
+/// every level
+pub const MAX_DEPENDENTS_MAX_DEPTH: u32 = 20;
+
 impl Config {
     pub fn from_env() -> Result<Self, String> {
         dotenv::dotenv().ok();
 
+        let solana_rpc_url = env::var("SOLANA_RPC_URL").unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
         Ok(Config {
             database_url: env::var("DATABASE_URL")
                 .map_err(|_| "DATABASE_URL must be set")?,
-            solana_rpc_url: env::var("SOLANA_RPC_URL")
-                .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string()),
+            ws_url: websocket_url(&solana_rpc_url, env::var("INDEXER_WS_URL").ok().as_deref()),
+            solana_rpc_url,
             antsol_program_id: env::var("ANTSOL_PROGRAM_ID")
                 .map_err(|_| "ANTSOL_PROGRAM_ID must be set")?,
             host: env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
@@ -29,6 +121,247 @@ impl Config {
                 .unwrap_or(8080),
             start_slot: env::var("INDEXER_START_SLOT").ok().and_then(|s| s.parse().ok()),
             poll_interval_secs: env::var("INDEXER_POLL_INTERVAL_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(2),
+            mode: parse_indexer_mode(env::var("INDEXER_MODE").ok().as_deref())?,
+            gap_fill_interval_secs: env::var("INDEXER_GAP_FILL_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_GAP_FILL_INTERVAL_SECS),
+            commitment: parse_commitment(env::var("INDEXER_COMMITMENT").ok().as_deref())?,
+            ingest_api_keys: parse_key_list(env::var("INGEST_API_KEYS").ok().as_deref()),
+            admin_api_keys: parse_key_list(env::var("ADMIN_API_KEYS").ok().as_deref()),
+            rate_limit: parse_rate_limit(
+                env::var("RATE_LIMIT_PER_MINUTE").ok().as_deref(),
+                env::var("RATE_LIMIT_BURST").ok().as_deref(),
+                env::var("RATE_LIMIT_TRUST_FORWARDED_FOR").ok().as_deref(),
+            )?,
+            health_max_lag_slots: parse_max_lag(env::var("HEALTH_MAX_LAG_SLOTS").ok().as_deref())?,
+            download_retention_days: parse_download_retention(env::var("DOWNLOAD_RETENTION_DAYS").ok().as_deref())?,
+            enrichment: parse_enrichment(
+                env::var("ENRICHMENT_GATEWAY").ok().as_deref(),
+                env::var("ENRICHMENT_CONCURRENCY").ok().as_deref(),
+            )?,
+            dependents_max_depth: parse_dependents_max_depth(env::var("DEPENDENTS_MAX_DEPTH").ok().as_deref())?,
         })
     }
 }
+
+/// `INGEST_API_KEYS` and `ADMIN_API_KEYS`: comma-separated; list the old and new key while rotating
+pub fn parse_key_list(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// `RATE_LIMIT_PER_MINUTE` (0 disables the limit), `RATE_LIMIT_BURST` and
+/// `RATE_LIMIT_TRUST_FORWARDED_FOR` (`true`/`false`)
+pub fn parse_rate_limit(
+    per_minute: Option<&str>,
+    burst: Option<&str>,
+    trust_forwarded_for: Option<&str>,
+) -> Result<Option<RateLimitConfig>, String> {
+    let number = |name: &str, value: Option<&str>, default: u32| match value.map(str::trim) {
+        None | Some("") => Ok(default),
+        Some(value) => value.parse::<u32>().map_err(|_| format!("{} must be a whole number, got '{}'", name, value)),
+    };
+    let per_minute = number("RATE_LIMIT_PER_MINUTE", per_minute, DEFAULT_RATE_LIMIT_PER_MINUTE)?;
+    if per_minute == 0 {
+        return Ok(None);
+    }
+    let burst = number("RATE_LIMIT_BURST", burst, DEFAULT_RATE_LIMIT_BURST)?.max(1);
+    let trust_forwarded_for = match trust_forwarded_for.map(str::trim) {
+        None | Some("") | Some("false") => false,
+        Some("true") => true,
+        Some(other) => return Err(format!("RATE_LIMIT_TRUST_FORWARDED_FOR must be 'true' or 'false', got '{}'", other)),
+    };
+    Ok(Some(RateLimitConfig { per_minute, burst, trust_forwarded_for }))
+}
+
+/// `INDEXER_MODE`: `polling` (default) or `websocket`
+pub fn parse_indexer_mode(value: Option<&str>) -> Result<IndexerMode, String> {
+    match value.map(str::trim) {
+        None | Some("") | Some("polling") => Ok(IndexerMode::Polling),
+        Some("websocket") => Ok(IndexerMode::Websocket),
+        Some(other) => Err(format!("INDEXER_MODE must be 'polling' or 'websocket', got '{}'", other)),
+    }
+}
+
+/// `INDEXER_WS_URL` when set, else the RPC URL with a websocket scheme. Like
+/// the Solana CLI, a local validator's 8899 becomes its pubsub port 8900.
+pub fn websocket_url(rpc_url: &str, override_url: Option<&str>) -> String {
+    if let Some(url) = override_url.map(str::trim).filter(|url| !url.is_empty()) {
+        return url.to_string();
+    }
+    let url = if let Some(rest) = rpc_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = rpc_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        rpc_url.to_string()
+    };
+    url.replacen(":8899", ":8900", 1)
+}
+
+/// `HEALTH_MAX_LAG_SLOTS`: slots the indexer may trail the chain by before
+/// `/health` reports it unhealthy
+pub fn parse_max_lag(value: Option<&str>) -> Result<u64, String> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(DEFAULT_HEALTH_MAX_LAG_SLOTS),
+        Some(value) => value.parse().map_err(|_| format!("HEALTH_MAX_LAG_SLOTS must be a whole number, got '{}'", value)),
+    }
+}
+
+/// `DOWNLOAD_RETENTION_DAYS`: `0` keeps every download row, otherwise at
+/// least [`MIN_DOWNLOAD_RETENTION_DAYS`]
+pub fn parse_download_retention(value: Option<&str>) -> Result<Option<u32>, String> {
+    let days = match value.map(str::trim) {
+        None | Some("") => DEFAULT_DOWNLOAD_RETENTION_DAYS,
+        Some(value) => value.parse().map_err(|_| format!("DOWNLOAD_RETENTION_DAYS must be a whole number, got '{}'", value))?,
+    };
+    match days {
+        0 => Ok(None),
+        days if days < MIN_DOWNLOAD_RETENTION_DAYS => Err(format!(
+            "DOWNLOAD_RETENTION_DAYS must be 0 or at least {} (the longest trending window), got {}",
+            MIN_DOWNLOAD_RETENTION_DAYS, days
+        )),
+        days => Ok(Some(days)),
+    }
+}
+
+/// `ENRICHMENT_GATEWAY` (an http or https URL) and `ENRICHMENT_CONCURRENCY`
+/// (0 disables the worker)
+pub fn parse_enrichment(gateway: Option<&str>, concurrency: Option<&str>) -> Result<Option<EnrichmentConfig>, String> {
+    let concurrency = match concurrency.map(str::trim) {
+        None | Some("") => DEFAULT_ENRICHMENT_CONCURRENCY,
+        Some(value) => value.parse().map_err(|_| format!("ENRICHMENT_CONCURRENCY must be a whole number, got '{}'", value))?,
+    };
+    if concurrency == 0 {
+        return Ok(None);
+    }
+    let gateway = match gateway.map(str::trim) {
+        None | Some("") => DEFAULT_ENRICHMENT_GATEWAY,
+        Some(url) if url.starts_with("https://") || url.starts_with("http://") => url,
+        Some(other) => return Err(format!("ENRICHMENT_GATEWAY must be an http or https URL, got '{}'", other)),
+    };
+    Ok(Some(EnrichmentConfig { gateway: gateway.trim_end_matches('/').to_string(), concurrency }))
+}
+
+/// `DEPENDENTS_MAX_DEPTH`: 1 (direct dependents only) to [`MAX_DEPENDENTS_MAX_DEPTH`]
+pub fn parse_dependents_max_depth(value: Option<&str>) -> Result<u32, String> {
+    let depth = match value.map(str::trim) {
+        None | Some("") => return Ok(DEFAULT_DEPENDENTS_MAX_DEPTH),
+        Some(value) => value.parse().map_err(|_| format!("DEPENDENTS_MAX_DEPTH must be a whole number, got '{}'", value))?,
+    };
+    if (1..=MAX_DEPENDENTS_MAX_DEPTH).contains(&depth) {
+        Ok(depth)
+    } else {
+        Err(format!("DEPENDENTS_MAX_DEPTH must be between 1 and {}, got {}", MAX_DEPENDENTS_MAX_DEPTH, depth))
+    }
+}
+
+/// `INDEXER_COMMITMENT`: `confirmed` (default) or `finalized`. `getTransaction`
+/// does not serve `processed` transactions, so that level is rejected.
+pub fn parse_commitment(value: Option<&str>) -> Result<CommitmentConfig, String> {
+    match value.map(str::trim) {
+        None | Some("") | Some("confirmed") => Ok(CommitmentConfig::confirmed()),
+        Some("finalized") => Ok(CommitmentConfig::finalized()),
+        Some(other) => Err(format!("INDEXER_COMMITMENT must be 'confirmed' or 'finalized', got '{}'", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commitment_defaults_to_confirmed() {
+        assert_eq!(parse_commitment(None), Ok(CommitmentConfig::confirmed()));
+        assert_eq!(parse_commitment(Some("finalized")), Ok(CommitmentConfig::finalized()));
+        assert!(parse_commitment(Some("processed")).is_err());
+    }
+
+    #[test]
+    fn ingest_keys_are_a_comma_separated_list() {
+        assert!(parse_key_list(None).is_empty());
+        assert_eq!(parse_key_list(Some(" old-key, new-key ,,")), vec!["old-key", "new-key"]);
+    }
+
+    #[test]
+    fn rate_limit_defaults_and_can_be_disabled() {
+        assert_eq!(
+            parse_rate_limit(None, None, None),
+            Ok(Some(RateLimitConfig {
+                per_minute: DEFAULT_RATE_LIMIT_PER_MINUTE,
+                burst: DEFAULT_RATE_LIMIT_BURST,
+                trust_forwarded_for: false,
+            }))
+        );
+        assert_eq!(parse_rate_limit(Some("0"), None, None), Ok(None));
+        assert_eq!(
+            parse_rate_limit(Some("60"), Some("5"), Some("true")),
+            Ok(Some(RateLimitConfig { per_minute: 60, burst: 5, trust_forwarded_for: true }))
+        );
+        assert!(parse_rate_limit(Some("lots"), None, None).is_err());
+        assert!(parse_rate_limit(None, None, Some("yes")).is_err());
+    }
+
+    #[test]
+    fn max_lag_defaults_and_rejects_garbage() {
+        assert_eq!(parse_max_lag(None), Ok(DEFAULT_HEALTH_MAX_LAG_SLOTS));
+        assert_eq!(parse_max_lag(Some(" 50 ")), Ok(50));
+        assert!(parse_max_lag(Some("-1")).is_err());
+    }
+
+    #[test]
+    fn indexer_mode_defaults_to_polling() {
+        assert_eq!(parse_indexer_mode(None), Ok(IndexerMode::Polling));
+        assert_eq!(parse_indexer_mode(Some("websocket")), Ok(IndexerMode::Websocket));
+        assert!(parse_indexer_mode(Some("push")).is_err());
+    }
+
+    #[test]
+    fn websocket_url_follows_the_rpc_url() {
+        assert_eq!(websocket_url("https://api.devnet.solana.com", None), "wss://api.devnet.solana.com");
+        assert_eq!(websocket_url("http://127.0.0.1:8899", None), "ws://127.0.0.1:8900");
+        assert_eq!(websocket_url("https://rpc.example.com", Some("wss://ws.example.com")), "wss://ws.example.com");
+    }
+
+    #[test]
+    fn enrichment_defaults_and_can_be_disabled() {
+        assert_eq!(
+            parse_enrichment(None, None),
+            Ok(Some(EnrichmentConfig {
+                gateway: DEFAULT_ENRICHMENT_GATEWAY.to_string(),
+                concurrency: DEFAULT_ENRICHMENT_CONCURRENCY,
+            }))
+        );
+        assert_eq!(
+            parse_enrichment(Some("https://gw.example.com/"), Some("2")),
+            Ok(Some(EnrichmentConfig { gateway: "https://gw.example.com".to_string(), concurrency: 2 }))
+        );
+        assert_eq!(parse_enrichment(Some("ftp://gw"), Some("0")), Ok(None));
+        assert!(parse_enrichment(Some("gw.example.com"), None).is_err());
+        assert!(parse_enrichment(None, Some("many")).is_err());
+    }
+
+    #[test]
+    fn download_retention_covers_trending_windows() {
+        assert_eq!(parse_download_retention(None), Ok(Some(DEFAULT_DOWNLOAD_RETENTION_DAYS)));
+        assert_eq!(parse_download_retention(Some("0")), Ok(None));
+        assert_eq!(parse_download_retention(Some("365")), Ok(Some(365)));
+        assert!(parse_download_retention(Some("30")).is_err());
+        assert!(parse_download_retention(Some("forever")).is_err());
+    }
+
+    #[test]
+    fn dependents_depth_is_bounded() {
+        assert_eq!(parse_dependents_max_depth(None), Ok(DEFAULT_DEPENDENTS_MAX_DEPTH));
+        assert_eq!(parse_dependents_max_depth(Some(" 1 ")), Ok(1));
+        assert!(parse_dependents_max_depth(Some("0")).is_err());
+        assert!(parse_dependents_max_depth(Some("21")).is_err());
+        assert!(parse_dependents_max_depth(Some("deep")).is_err());
+    }
+}
@@ -1,11 +1,154 @@
 pub mod models;
 pub mod queries;
+pub mod sqlite;
 
-use deadpool_postgres::{Pool, Runtime};
+use deadpool_postgres::Runtime;
+use std::time::Duration;
 use native_tls::TlsConnector;
 use postgres_native_tls::MakeTlsConnector;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+
+use sqlite::{SqliteClient, SqliteDb, SqliteTx};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The indexer's database. `DATABASE_URL` picks the backend: `sqlite:`
+/// URLs open SQLite (see [`sqlite`]), anything else connects to Postgres.
+#[derive(Clone)]
+pub enum Pool {
+    Postgres(deadpool_postgres::Pool),
+    Sqlite(SqliteDb),
+}
+
+impl Pool {
+    /// A connection to run queries on, or to open a transaction on
+    pub async fn get(&self) -> Result<Connection, BoxError> {
+        Ok(match self {
+            Pool::Postgres(pool) => Connection::Postgres(pool.get().await?),
+            Pool::Sqlite(db) => Connection::Sqlite(db.clone()),
+        })
+    }
+}
+
+pub enum Connection {
+    Postgres(deadpool_postgres::Object),
+    Sqlite(SqliteDb),
+}
+
+impl Connection {
+    /// Start a transaction; dropping it uncommitted rolls it back
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>, BoxError> {
+        Ok(match self {
+            Connection::Postgres(client) => Transaction::Postgres(client.transaction().await?),
+            Connection::Sqlite(db) => Transaction::Sqlite(db.begin().await?),
+        })
+    }
+}
+
+pub enum Transaction<'a> {
+    Postgres(deadpool_postgres::Transaction<'a>),
+    Sqlite(SqliteTx),
+}
+
+impl Transaction<'_> {
+    pub async fn commit(self) -> Result<(), BoxError> {
+        match self {
+            Transaction::Postgres(tx) => Ok(tx.commit().await?),
+            Transaction::Sqlite(tx) => tx.commit(),
+        }
+    }
+
+    pub async fn rollback(self) -> Result<(), BoxError> {
+        match self {
+            Transaction::Postgres(tx) => Ok(tx.rollback().await?),
+            Transaction::Sqlite(tx) => tx.rollback(),
+        }
+    }
+}
+
+/// Anything queries in [`queries`] that take a client can run on: a
+/// [`Connection`], a [`Transaction`], or a bare tokio-postgres client
+pub trait GenericClient: Sync {
+    fn client(&self) -> ClientRef<'_>;
+}
+
+/// A client borrowed for one query, per backend
+pub enum ClientRef<'a> {
+    Postgres(PgClient<'a>),
+    Sqlite(SqliteClient<'a>),
+}
+
+impl GenericClient for Connection {
+    fn client(&self) -> ClientRef<'_> {
+        match self {
+            Connection::Postgres(client) => ClientRef::Postgres(PgClient::Client(client)),
+            Connection::Sqlite(db) => ClientRef::Sqlite(SqliteClient::Shared(db)),
+        }
+    }
+}
+
+impl GenericClient for Transaction<'_> {
+    fn client(&self) -> ClientRef<'_> {
+        match self {
+            Transaction::Postgres(tx) => ClientRef::Postgres(PgClient::Transaction(tx)),
+            Transaction::Sqlite(tx) => ClientRef::Sqlite(SqliteClient::Transaction(tx)),
+        }
+    }
+}
+
+impl GenericClient for tokio_postgres::Client {
+    fn client(&self) -> ClientRef<'_> {
+        ClientRef::Postgres(PgClient::Client(self))
+    }
+}
+
+impl GenericClient for tokio_postgres::Transaction<'_> {
+    fn client(&self) -> ClientRef<'_> {
+        ClientRef::Postgres(PgClient::Transaction(self))
+    }
+}
+
+/// A Postgres client or transaction, with the calls [`queries`] makes
+pub enum PgClient<'a> {
+    Client(&'a tokio_postgres::Client),
+    Transaction(&'a tokio_postgres::Transaction<'a>),
+}
+
+impl PgClient<'_> {
+    pub async fn query(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Vec<Row>, tokio_postgres::Error> {
+        match self {
+            PgClient::Client(client) => client.query(sql, params).await,
+            PgClient::Transaction(tx) => tx.query(sql, params).await,
+        }
+    }
+
+    pub async fn query_one(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Row, tokio_postgres::Error> {
+        match self {
+            PgClient::Client(client) => client.query_one(sql, params).await,
+            PgClient::Transaction(tx) => tx.query_one(sql, params).await,
+        }
+    }
+
+    pub async fn query_opt(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<Option<Row>, tokio_postgres::Error> {
+        match self {
+            PgClient::Client(client) => client.query_opt(sql, params).await,
+            PgClient::Transaction(tx) => tx.query_opt(sql, params).await,
+        }
+    }
+
+    pub async fn execute(&self, sql: &str, params: &[&(dyn ToSql + Sync)]) -> Result<u64, tokio_postgres::Error> {
+        match self {
+            PgClient::Client(client) => client.execute(sql, params).await,
+            PgClient::Transaction(tx) => tx.execute(sql, params).await,
+        }
+    }
+}
 
 pub async fn create_pool(database_url: &str) -> Result<Pool, Box<dyn std::error::Error>> {
+    if database_url.starts_with("sqlite:") {
+        return Ok(Pool::Sqlite(SqliteDb::open(database_url)?));
+    }
     let mut cfg = deadpool_postgres::Config::new();
     cfg.dbname = None;
     cfg.user = None;
@@ -16,16 +159,65 @@ pub async fn create_pool(database_url: &str) -> Result<Pool, Box<dyn std::error:
     let tls_connector = TlsConnector::builder().build()?;
     let make_tls = MakeTlsConnector::new(tls_connector);
     let pool = cfg.create_pool(Some(Runtime::Tokio1), make_tls)?;
-    Ok(pool)
+    Ok(Pool::Postgres(pool))
 }
 
 pub async fn run_migrations(pool: &Pool) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = match pool {
+        Pool::Postgres(pool) => pool,
+        Pool::Sqlite(db) => {
+            db.migrate().await?;
+            tracing::info!("Database migrations completed successfully");
+            return Ok(());
+        }
+    };
     let client = pool.get().await?;
     
-    let migration_sql = include_str!("../../migrations/001_init.sql");
+    let migrations = [
+        include_str!("../../migrations/001_init.sql"),
+        include_str!("../../migrations/002_dependency_changes.sql"),
+        include_str!("../../migrations/003_signature_cursor.sql"),
+        include_str!("../../migrations/004_version_block_time.sql"),
+        include_str!("../../migrations/005_download_dedup.sql"),
+        include_str!("../../migrations/006_download_events.sql"),
+        include_str!("../../migrations/007_package_keywords.sql"),
+        include_str!("../../migrations/008_event_finality.sql"),
+        include_str!("../../migrations/009_version_authority.sql"),
+        include_str!("../../migrations/010_package_search.sql"),
+        include_str!("../../migrations/011_package_maintainers.sql"),
+        include_str!("../../migrations/012_download_rollups.sql"),
+        include_str!("../../migrations/013_dependencies.sql"),
+        include_str!("../../migrations/014_event_links.sql"),
+        include_str!("../../migrations/015_version_readme.sql"),
+        include_str!("../../migrations/016_external_dependencies.sql"),
+        include_str!("../../migrations/017_version_ordering.sql"),
+        include_str!("../../migrations/018_event_log_index.sql"),
+        include_str!("../../migrations/019_version_frozen.sql"),
+        include_str!("../../migrations/020_version_enrichment.sql"),
+        include_str!("../../migrations/021_maintainer_authority.sql"),
+    ];
     
-    client.batch_execute(migration_sql).await?;
+    for migration_sql in migrations {
+        client.batch_execute(migration_sql).await?;
+    }
     
     tracing::info!("Database migrations completed successfully");
     Ok(())
 }
+
+/// How often old download events are rolled up
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Roll download events older than `retention_days` into daily counts, now
+/// and then every hour
+pub async fn run_download_rollups(pool: Pool, retention_days: u32) {
+    let mut ticker = tokio::time::interval(ROLLUP_INTERVAL);
+    loop {
+        ticker.tick().await;
+        match queries::roll_up_download_events(&pool, retention_days).await {
+            Ok(0) => {}
+            Ok(moved) => tracing::info!("Rolled {} download events older than {} days into daily counts", moved, retention_days),
+            Err(e) => tracing::warn!("Download rollup failed: {}", e),
+        }
+    }
+}
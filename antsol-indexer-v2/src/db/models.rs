@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Package {
     pub id: i32,
     pub name: String,
@@ -9,12 +10,100 @@ pub struct Package {
     pub description: Option<String>,
     pub repository: Option<String>,
     pub homepage: Option<String>,
+    pub keywords: Vec<String>,
     pub total_downloads: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A search hit with its highest version, so clients don't need a detail fetch per row
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub package: Package,
+    pub latest_version: Option<String>,
+    /// When `latest_version` was published
+    pub latest_published_at: Option<DateTime<Utc>>,
+}
+
+/// One page of a list endpoint, answered with `?envelope=1`
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[aliases(
+    PackagePage = Paginated<Package>,
+    SearchResultPage = Paginated<SearchResult>,
+    EventPage = Paginated<Event>,
+    PackageEventPage = Paginated<PackageEvent>
+)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    /// Rows matching the query across all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Result order for `/api/search`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    /// Exact name first, then text rank blended with downloads
+    #[default]
+    Relevance,
+    /// Most downloaded first
+    Downloads,
+    /// Most recently updated first
+    Recent,
+    /// Alphabetical by name
+    Name,
+}
+
+/// Narrows `/api/search` beyond the text match
+#[derive(Debug, Clone, Default)]
+pub struct SearchFilter {
+    /// Only packages whose authority is this base58 pubkey
+    pub author: Option<String>,
+    /// Only packages with at least one version matching this requirement
+    pub has_version: Option<semver::VersionReq>,
+}
+
+/// Bucket width for `/api/packages/:name/downloads`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadInterval {
+    #[default]
+    Day,
+    /// ISO weeks, starting Monday
+    Week,
+    Month,
+}
+
+impl DownloadInterval {
+    /// Field name for `date_trunc`, which also makes a one-bucket `interval`
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            DownloadInterval::Day => "day",
+            DownloadInterval::Week => "week",
+            DownloadInterval::Month => "month",
+        }
+    }
+}
+
+/// Downloads counted in the bucket starting on `date` (UTC)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DownloadBucket {
+    pub date: chrono::NaiveDate,
+    pub count: i64,
+}
+
+/// A package ranked by the downloads it got within a trending window
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TrendingPackage {
+    #[serde(flatten)]
+    pub package: Package,
+    pub window_downloads: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Version {
     pub id: i32,
     pub package_id: i32,
@@ -22,30 +111,264 @@ pub struct Version {
     pub ipfs_hash: String,
     pub downloads: i64,
     pub published_at: DateTime<Utc>,
+    /// Base58 pubkey that published the version; `None` for versions
+    /// indexed before the authority was recorded
+    pub authority: Option<String>,
+    /// CID of the README.md pinned with the version, for gateway fetches
+    #[serde(default)]
+    pub readme_cid: Option<String>,
+    /// Whether the version's authority was burned; it can't change anymore
+    #[serde(default)]
+    pub frozen: bool,
+    /// License from the tarball's antsol.toml; `None` until the tarball is read
+    #[serde(default)]
+    pub license: Option<String>,
+    /// Regular files in the tarball
+    #[serde(default)]
+    pub file_count: Option<i32>,
+    /// Bytes the tarball's files take once extracted
+    #[serde(default)]
+    pub unpacked_size: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What the enrichment worker read from a version's tarball
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionEnrichment {
+    /// `package.description` from antsol.toml
+    pub description: Option<String>,
+    /// `package.license` from antsol.toml
+    pub license: Option<String>,
+    /// README.md at the package root, cut at the size limit
+    pub readme: Option<String>,
+    pub file_count: i32,
+    pub unpacked_size: i64,
+}
+
+/// A version's README.md, as read from its tarball
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PackageReadme {
+    pub name: String,
+    pub version: String,
+    pub readme: String,
+}
+
+/// One version with the package-level fields inline
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionDetail {
+    #[serde(flatten)]
+    pub version: Version,
+    pub name: String,
+    pub author: String,
+    pub description: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PackageWithVersions {
     #[serde(flatten)]
     pub package: Package,
+    /// Highest release by semver, or the highest prerelease while there is no release
+    pub latest_version: Option<String>,
+    /// Whether the latest version is frozen
+    #[serde(default)]
+    pub frozen: bool,
+    /// Highest semver first; versions that aren't semver last, newest first
     pub versions: Vec<Version>,
+    /// Wallets the latest version's authority allows to publish new
+    /// versions on its behalf
+    #[serde(default)]
+    pub maintainers: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Event {
     pub id: i32,
     pub event_type: String,
     pub package_name: String,
     pub version: Option<String>,
     pub transaction_signature: String,
+    /// Position of the event's log line among its transaction's log messages
+    #[serde(default)]
+    pub log_index: i32,
     pub slot: i64,
     pub block_time: Option<DateTime<Utc>>,
+    /// Whether the event's transaction can still be rolled back; clients grey out `confirmed` rows
+    #[serde(default)]
+    pub finality: EventFinality,
+    /// CID a publish or update stored
+    #[serde(default)]
+    pub ipfs_hash: Option<String>,
+    /// Signer of a publish, update or maintainer change; the new authority of a transfer
+    #[serde(default)]
+    pub authority: Option<String>,
+    /// The `versions` row the event touched, once ingestion resolved it
+    #[serde(default)]
+    pub version_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How settled the transaction behind an event is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EventFinality {
+    /// Seen at the indexer's commitment level; a fork could still drop it
+    #[default]
+    Confirmed,
+    /// Its transaction is in a finalized slot
+    Finalized,
+    /// Its transaction no longer exists on the canonical chain
+    Orphaned,
+}
+
+impl EventFinality {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventFinality::Confirmed => "confirmed",
+            EventFinality::Finalized => "finalized",
+            EventFinality::Orphaned => "orphaned",
+        }
+    }
+
+    /// Read the `events.finality` column; unknown values count as unconfirmed
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "finalized" => EventFinality::Finalized,
+            "orphaned" => EventFinality::Orphaned,
+            _ => EventFinality::Confirmed,
+        }
+    }
+}
+
+/// Dependency names added and removed by an update, from `DependenciesChanged`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DependencyChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// A `name@version` that a version depends on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+}
+
+/// A dependency from another ecosystem, recorded on-chain but never resolved by the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ExternalDependency {
+    pub name: String,
+    /// Version requirement in the ecosystem's own syntax
+    pub version: String,
+    /// `rust`, `npm`, `python` or `go`
+    #[serde(rename = "type")]
+    pub dep_type: String,
+    pub registry: Option<String>,
+}
+
+/// `/api/packages/:name/dependencies`: what one version depends on
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PackageDependencies {
+    pub name: String,
+    /// The requested version, or the latest when none was given
+    pub version: String,
+    pub dependencies: Vec<Dependency>,
+    /// Empty for versions published before the program recorded them
+    #[serde(default)]
+    pub external_dependencies: Vec<ExternalDependency>,
+}
+
+/// A package whose latest version depends on the one asked about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Dependent {
+    pub name: String,
+    /// The dependent's latest version
+    pub version: String,
+    /// Version of the asked-about package it requires
+    pub requires: String,
+}
+
+/// A package that depends on a version, directly or through other packages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VersionDependent {
+    pub name: String,
+    /// The dependent's latest version
+    pub version: String,
+    /// Package it depends on, one level closer to the asked-about version
+    pub via: String,
+    /// Version of `via` it requires
+    pub requires: String,
+    /// 1 for a direct dependent, 2 for a dependent of one, and so on
+    pub depth: i32,
+}
+
+/// Who depends on one version, as `GET /api/packages/{name}/{version}/dependents` answers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VersionDependents {
+    pub name: String,
+    pub version: String,
+    /// Packages whose latest version requires this version
+    pub direct_count: usize,
+    /// Packages that only depend on it through others, within `max_depth`
+    pub transitive_count: usize,
+    /// Levels followed: 1 unless `transitive` was asked for
+    pub max_depth: i32,
+    /// Nearest first, alphabetically within a level; each package once
+    pub dependents: Vec<VersionDependent>,
+}
+
+/// An event as served by the package events endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PackageEvent {
+    #[serde(flatten)]
+    pub event: Event,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_change: Option<DependencyChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Stats {
     pub total_packages: i64,
     pub total_versions: i64,
     pub total_downloads: i64,
     pub total_events: i64,
 }
+
+/// Listener progress from `indexer_state`
+#[derive(Debug, Clone)]
+pub struct IndexerState {
+    pub last_processed_slot: u64,
+    pub error_count: i32,
+    pub last_error: Option<String>,
+    pub seconds_since_update: Option<i64>,
+}
+
+/// `GET /health`: each subsystem's status and how far indexing trails the chain
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Health {
+    /// `ok`, `degraded` (RPC unreachable, so lag is unknown) or `unhealthy`
+    pub status: String,
+    pub database: ComponentHealth,
+    pub rpc: ComponentHealth,
+    pub indexer: IndexerHealth,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ComponentHealth {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct IndexerHealth {
+    /// False when the lag is over `max_lag_slots` or the state can't be read
+    pub ok: bool,
+    pub last_processed_slot: Option<u64>,
+    /// Slot of the RPC node at the indexer's commitment
+    pub current_slot: Option<u64>,
+    /// `current_slot - last_processed_slot`
+    pub lag_slots: Option<u64>,
+    pub max_lag_slots: u64,
+    pub seconds_since_update: Option<i64>,
+    pub error_count: Option<i32>,
+    pub last_error: Option<String>,
+}
@@ -1,107 +1,381 @@
-use deadpool_postgres::Pool;
-use tokio_postgres::Row;
-use chrono::DateTime;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, Row};
+use chrono::{DateTime, Utc};
 
 use super::models::*;
+use super::sqlite::queries as sqlite;
+use super::{ClientRef, GenericClient, Pool};
+use crate::telemetry::QueryTimer;
 
+/// Upsert a package. `authority` is the base58 pubkey of its current
+/// authority; "unknown" keeps whatever authority is already stored, as a
+/// `None` description keeps the stored one.
 pub async fn insert_package(
-    pool: &Pool,
+    client: &impl GenericClient,
     name: &str,
-    author: &str,
+    authority: &str,
     description: Option<&str>,
     repository: Option<&str>,
     homepage: Option<&str>,
 ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("insert_package");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::insert_package(conn, name, authority, description, repository, homepage)).await,
+    };
     
     let row = client.query_one(
         "INSERT INTO packages (name, author, description, repository, homepage)
          VALUES ($1, $2, $3, $4, $5)
          ON CONFLICT (name) DO UPDATE SET
-            author = EXCLUDED.author,
-            description = EXCLUDED.description,
+            author = CASE WHEN EXCLUDED.author = 'unknown' THEN packages.author ELSE EXCLUDED.author END,
+            description = COALESCE(EXCLUDED.description, packages.description),
             repository = EXCLUDED.repository,
             homepage = EXCLUDED.homepage,
             updated_at = NOW()
          RETURNING id",
-        &[&name, &author, &description, &repository, &homepage],
+        &[&name, &authority, &description, &repository, &homepage],
     ).await?;
     
     Ok(row.get(0))
 }
 
+/// Replace a package's repository, homepage and keywords with the metadata
+/// of its latest publish; `None` clears a field
+pub async fn update_package_metadata(
+    client: &impl GenericClient,
+    name: &str,
+    repository: Option<&str>,
+    homepage: Option<&str>,
+    keywords: &[String],
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_package_metadata");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::update_package_metadata(conn, name, repository, homepage, keywords)).await,
+    };
+    
+    let updated = client.execute(
+        "UPDATE packages
+         SET repository = $2, homepage = $3, keywords = $4, updated_at = NOW()
+         WHERE name = $1",
+        &[&name, &repository, &homepage, &keywords],
+    ).await?;
+    
+    Ok(updated > 0)
+}
+
+/// Record the README CID logged with `name@version`'s metadata; `false`
+/// when the version isn't indexed
+pub async fn update_version_readme(
+    client: &impl GenericClient,
+    name: &str,
+    version: &str,
+    readme_cid: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_version_readme");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::update_version_readme(conn, name, version, readme_cid)).await,
+    };
+    
+    let updated = client.execute(
+        "UPDATE versions v SET readme_cid = $3
+         FROM packages p
+         WHERE p.id = v.package_id AND p.name = $1 AND v.version = $2",
+        &[&name, &version, &readme_cid],
+    ).await?;
+    
+    Ok(updated > 0)
+}
+
+/// Upsert a version. `published_at` is the chain time of the publish (the
+/// transaction's `block_time`, or the account's `published_at` when
+/// reconciling from accounts); `None` falls back to the insert time.
+/// An earlier chain time always wins, so replays and backfills correct rows
+/// that were first stored with a later timestamp.
 pub async fn insert_version(
-    pool: &Pool,
+    client: &impl GenericClient,
     package_id: i32,
     version: &str,
     ipfs_hash: &str,
+    authority: Option<&str>,
+    published_at: Option<DateTime<Utc>>,
 ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("insert_version");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::insert_version(conn, package_id, version, ipfs_hash, authority, published_at)).await,
+    };
     
     let row = client.query_one(
-        "INSERT INTO versions (package_id, version, ipfs_hash)
-         VALUES ($1, $2, $3)
+        "INSERT INTO versions (package_id, version, ipfs_hash, authority, published_at)
+         VALUES ($1, $2, $3, $4, COALESCE($5, NOW()))
          ON CONFLICT (package_id, version) DO UPDATE SET
-            ipfs_hash = EXCLUDED.ipfs_hash
-         RETURNING id",
-        &[&package_id, &version, &ipfs_hash],
+            ipfs_hash = EXCLUDED.ipfs_hash,
+            authority = COALESCE(EXCLUDED.authority, versions.authority),
+            published_at = LEAST(versions.published_at, COALESCE($5, versions.published_at))
+         RETURNING id, published_at",
+        &[&package_id, &version, &ipfs_hash, &authority, &published_at],
+    ).await?;
+    
+    // A package is as old as its first published version
+    let version_published_at: DateTime<Utc> = row.get(1);
+    client.execute(
+        "UPDATE packages SET created_at = LEAST(created_at, $2) WHERE id = $1",
+        &[&package_id, &version_published_at],
     ).await?;
     
     Ok(row.get(0))
 }
 
 pub async fn insert_event(
-    pool: &Pool,
+    client: &impl GenericClient,
     event_type: &str,
     package_name: &str,
     version: Option<&str>,
     transaction_signature: &str,
+    log_index: i32,
     slot: i64,
     block_time: Option<i64>,
+    ipfs_hash: Option<&str>,
+    authority: Option<&str>,
 ) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("insert_event");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::insert_event(conn, event_type, package_name, version, transaction_signature, log_index, slot, block_time, ipfs_hash, authority)).await,
+    };
     
     let block_time_dt = block_time.map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default());
     
     let row_opt = client.query_opt(
-        "INSERT INTO events (event_type, package_name, version, transaction_signature, slot, block_time)
-         VALUES ($1, $2, $3, $4, $5, $6)
-         ON CONFLICT (transaction_signature) DO NOTHING
+        "INSERT INTO events (event_type, package_name, version, transaction_signature, log_index, slot, block_time, ipfs_hash, authority)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (transaction_signature, log_index) DO NOTHING
          RETURNING id",
-        &[&event_type, &package_name, &version, &transaction_signature, &slot, &block_time_dt],
+        &[&event_type, &package_name, &version, &transaction_signature, &log_index, &slot, &block_time_dt, &ipfs_hash, &authority],
     ).await?;
     // If conflict occurred, RETURNING yields no row; treat as existing (id unknown -> 0)
     Ok(row_opt.map(|r| r.get(0)).unwrap_or(0))
 }
 
+/// Point event `event_id` at the stored `package_name@version`, returning
+/// the version's id, or `None` when that version isn't indexed
+pub async fn link_event_version(
+    client: &impl GenericClient,
+    event_id: i32,
+    package_name: &str,
+    version: &str,
+) -> Result<Option<i32>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("link_event_version");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::link_event_version(conn, event_id, package_name, version)).await,
+    };
+    
+    let row = client.query_opt(
+        "UPDATE events e SET version_id = v.id
+         FROM versions v
+         JOIN packages p ON p.id = v.package_id
+         WHERE e.id = $1 AND p.name = $2 AND v.version = $3
+         RETURNING v.id",
+        &[&event_id, &package_name, &version],
+    ).await?;
+    
+    Ok(row.map(|r| r.get(0)))
+}
+
+/// Whether an event from `transaction_signature` is already stored, e.g. by
+/// the websocket subscription before the polling gap-filler reached it
+pub async fn event_recorded(pool: &Pool, transaction_signature: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("event_recorded");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::event_recorded(conn, transaction_signature)).await,
+    };
+    
+    let row = client.query_one(
+        "SELECT EXISTS(SELECT 1 FROM events WHERE transaction_signature = $1)",
+        &[&transaction_signature],
+    ).await?;
+    
+    Ok(row.get(0))
+}
+
+/// Queries shorter than this (in characters) are matched with ILIKE, since
+/// a tsquery on one or two letters finds next to nothing
+pub const MIN_FULL_TEXT_QUERY_LEN: usize = 3;
+
+/// `$1` as a tsquery. Separators inside words are blanked to match how names
+/// are indexed (see migration 010); a leading `-` still negates a term.
+const SEARCH_TSQUERY: &str = "(websearch_to_tsquery('simple', regexp_replace($1, '([[:alnum:]])[-_./@]+', '\\1 ', 'g'))
+     || websearch_to_tsquery('english', $1))";
+
+/// Search by name, keywords and description. Each hit carries its latest
+/// version, as [`LATEST_VERSION_ORDER`] picks it.
+///
+/// Queries use `websearch_to_tsquery` (quotes, `or` and `-term` work) against
+/// `packages.search_vector`; very short ones fall back to a substring match.
 pub async fn search_packages(
     pool: &Pool,
     query: &str,
+    filter: &SearchFilter,
+    sort: SearchSort,
     limit: i64,
     offset: i64,
-) -> Result<Vec<Package>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+) -> Result<Paginated<SearchResult>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("search_packages");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::search_packages(conn, query, filter, sort, limit, offset)).await,
+    };
+    let query = query.trim();
+    let full_text = query.chars().count() >= MIN_FULL_TEXT_QUERY_LEN;
     
-    let rows = client.query(
-        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at
-         FROM packages
-         WHERE name ILIKE $1 OR description ILIKE $1
-         ORDER BY total_downloads DESC, name ASC
-         LIMIT $2 OFFSET $3",
-        &[&format!("%{}%", query), &limit, &offset],
-    ).await?;
+    let text_match = if full_text {
+        format!("p.search_vector @@ {}", SEARCH_TSQUERY)
+    } else {
+        "(p.name ILIKE '%' || $1::text || '%' OR p.description ILIKE '%' || $1::text || '%')".to_string()
+    };
+    let has_version = match &filter.has_version {
+        Some(req) => format!(
+            "EXISTS (SELECT 1 FROM versions v WHERE v.package_id = p.id AND {})",
+            version_req_sql(req)
+        ),
+        None => "TRUE".to_string(),
+    };
+    let order = match sort {
+        SearchSort::Relevance if full_text => format!(
+            "lower(p.name) = lower($1) DESC,
+             ts_rank(p.search_vector, {}) * (1 + ln(1 + p.total_downloads::float8) / 10) DESC,
+             p.name ASC",
+            SEARCH_TSQUERY
+        ),
+        SearchSort::Relevance => "lower(p.name) = lower($1) DESC, p.total_downloads DESC, p.name ASC".to_string(),
+        SearchSort::Downloads => "p.total_downloads DESC, p.name ASC".to_string(),
+        SearchSort::Recent => "p.updated_at DESC, p.name ASC".to_string(),
+        SearchSort::Name => "p.name ASC".to_string(),
+    };
     
-    Ok(rows.iter().map(row_to_package).collect())
+    let sql = format!(
+        "SELECT p.id, p.name, p.author, p.description, p.repository, p.homepage, p.total_downloads,
+                p.created_at, p.updated_at, p.keywords, lv.version, lv.published_at, count(*) OVER () AS total
+         FROM packages p
+         LEFT JOIN LATERAL (
+             SELECT v.version, v.published_at
+             FROM versions v
+             WHERE v.package_id = p.id
+             ORDER BY {}
+             LIMIT 1
+         ) lv ON TRUE
+         WHERE {} AND ($2::TEXT IS NULL OR p.author = $2) AND {}
+         ORDER BY {}
+         LIMIT $3 OFFSET $4",
+        LATEST_VERSION_ORDER, text_match, has_version, order
+    );
+    let rows = client.query(&sql, &[&query, &filter.author, &limit, &offset]).await?;
+    let total = window_total(&client, &sql, &[&query, &filter.author], &rows, offset).await?;
+    
+    Ok(Paginated {
+        items: rows.iter().map(|row| SearchResult {
+            package: row_to_package(row),
+            latest_version: row.get(10),
+            latest_published_at: row.get(11),
+        }).collect(),
+        total,
+        limit,
+        offset,
+    })
+}
+
+/// Semver precedence of `v`, highest first, from the columns migration 017
+/// generates. Versions that aren't semver follow, newest first.
+pub(super) const VERSION_ORDER: &str = "v.version_major DESC NULLS LAST, v.version_minor DESC, v.version_patch DESC,
+     (v.version_prerelease IS NULL) DESC, v.version_prerelease DESC,
+     v.published_at DESC, v.id DESC";
+
+/// The version of a package that counts as its latest comes first: the
+/// highest release, or the highest prerelease while there is no release
+pub(super) const LATEST_VERSION_ORDER: &str = "(v.version_major IS NOT NULL AND v.version_prerelease IS NULL) DESC,
+     v.version_major DESC NULLS LAST, v.version_minor DESC, v.version_patch DESC,
+     (v.version_prerelease IS NULL) DESC, v.version_prerelease DESC,
+     v.published_at DESC, v.id DESC";
+
+/// `v.version` as a numeric array when it is a plain `x.y.z`, NULL otherwise,
+/// so comparisons order versions numerically and never match prereleases
+const PLAIN_VERSION_ARRAY: &str =
+    "CASE WHEN v.version ~ '^[0-9]+\\.[0-9]+\\.[0-9]+$' THEN string_to_array(v.version, '.')::numeric[] END";
+
+/// SQL condition on `v.version` for `req`. Each comparator becomes a lower
+/// and/or upper bound on [`PLAIN_VERSION_ARRAY`]; the bounds are integers
+/// from the parsed requirement, so inlining them is safe.
+fn version_req_sql(req: &semver::VersionReq) -> String {
+    let mut conditions = vec![format!("({}) IS NOT NULL", PLAIN_VERSION_ARRAY)];
+    for comparator in &req.comparators {
+        let (lower, upper) = comparator_bounds(comparator);
+        let array = |[major, minor, patch]: [u64; 3]| format!("ARRAY[{}, {}, {}]::numeric[]", major, minor, patch);
+        if let Some((bound, inclusive)) = lower {
+            conditions.push(format!("{} {} {}", PLAIN_VERSION_ARRAY, if inclusive { ">=" } else { ">" }, array(bound)));
+        }
+        if let Some((bound, inclusive)) = upper {
+            conditions.push(format!("{} {} {}", PLAIN_VERSION_ARRAY, if inclusive { "<=" } else { "<" }, array(bound)));
+        }
+    }
+    conditions.join(" AND ")
+}
+
+type VersionBound = Option<([u64; 3], bool)>;
+
+/// Lower and upper bound of one comparator, each with whether it is
+/// inclusive, following Cargo's rules for partial versions
+pub(super) fn comparator_bounds(c: &semver::Comparator) -> (VersionBound, VersionBound) {
+    use semver::Op;
+    let (major, minor, patch) = (c.major, c.minor, c.patch);
+    let floor = [major, minor.unwrap_or(0), patch.unwrap_or(0)];
+    // Just past the last version the partial version covers, e.g. 1.2 -> 1.3.0
+    let past = match (minor, patch) {
+        (Some(minor), Some(patch)) => [major, minor, patch.saturating_add(1)],
+        (Some(minor), None) => [major, minor.saturating_add(1), 0],
+        (None, _) => [major.saturating_add(1), 0, 0],
+    };
+    match c.op {
+        Op::Exact | Op::Wildcard => (Some((floor, true)), Some((past, false))),
+        Op::Greater => (Some((past, true)), None),
+        Op::GreaterEq => (Some((floor, true)), None),
+        Op::Less => (None, Some((floor, false))),
+        Op::LessEq => (None, Some((past, false))),
+        Op::Tilde => {
+            let upper = match minor {
+                Some(minor) => [major, minor.saturating_add(1), 0],
+                None => [major.saturating_add(1), 0, 0],
+            };
+            (Some((floor, true)), Some((upper, false)))
+        }
+        Op::Caret => {
+            let upper = match (major, minor, patch) {
+                (0, Some(0), Some(patch)) => [0, 0, patch.saturating_add(1)],
+                (0, Some(minor), _) => [0, minor.saturating_add(1), 0],
+                _ => [major.saturating_add(1), 0, 0],
+            };
+            (Some((floor, true)), Some((upper, false)))
+        }
+        _ => (Some((floor, true)), None),
+    }
 }
 
 pub async fn get_package_with_versions(
     pool: &Pool,
     name: &str,
 ) -> Result<Option<PackageWithVersions>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_package_with_versions");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_package_with_versions(conn, name)).await,
+    };
     
     let package_row = client.query_opt(
-        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at
+        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords
          FROM packages
          WHERE name = $1",
         &[&name],
@@ -113,38 +387,231 @@ pub async fn get_package_with_versions(
     };
     
     let version_rows = client.query(
-        "SELECT id, package_id, version, ipfs_hash, downloads, published_at
-         FROM versions
-         WHERE package_id = $1
-         ORDER BY published_at DESC",
+        &format!(
+            "SELECT v.id, v.package_id, v.version, v.ipfs_hash, v.downloads, v.published_at, v.authority, v.readme_cid, v.frozen,
+                    v.license, v.file_count, v.unpacked_size
+             FROM versions v
+             WHERE v.package_id = $1
+             ORDER BY {}",
+            VERSION_ORDER
+        ),
         &[&package.id],
     ).await?;
     
-    let versions = version_rows.iter().map(row_to_version).collect();
+    let versions: Vec<Version> = version_rows.iter().map(row_to_version).collect();
+    let latest = latest_of(&versions);
+    let latest_version = latest.map(|v| v.version.clone());
+    let frozen = latest.is_some_and(|v| v.frozen);
+    
+    // Only the list kept by the latest version's authority covers it
+    let authority = latest.and_then(|v| v.authority.clone()).unwrap_or_default();
+    let maintainer_rows = client.query(
+        "SELECT maintainer FROM package_maintainers WHERE package_name = $1 AND authority = $2
+         ORDER BY added_at, maintainer",
+        &[&name, &authority],
+    ).await?;
+    let maintainers = maintainer_rows.iter().map(|row| row.get(0)).collect();
+    
+    Ok(Some(PackageWithVersions { package, latest_version, frozen, versions, maintainers }))
+}
+
+/// The version [`LATEST_VERSION_ORDER`] would pick from `versions`, which
+/// must be in [`VERSION_ORDER`]: the first release, else the first version
+fn latest_of(versions: &[Version]) -> Option<&Version> {
+    versions
+        .iter()
+        .find(|v| semver::Version::parse(&v.version).is_ok_and(|parsed| parsed.pre.is_empty()))
+        .or(versions.first())
+}
+
+/// A single `name@version`; `None` when either the package or the version is unknown
+pub async fn get_version(
+    pool: &Pool,
+    name: &str,
+    version: &str,
+) -> Result<Option<VersionDetail>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_version");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_version(conn, name, version)).await,
+    };
+    
+    let row = client.query_opt(
+        "SELECT v.id, v.package_id, v.version, v.ipfs_hash, v.downloads, v.published_at, v.authority, v.readme_cid, v.frozen,
+                v.license, v.file_count, v.unpacked_size,
+                p.name, p.author, p.description, p.repository, p.homepage
+         FROM versions v
+         JOIN packages p ON p.id = v.package_id
+         WHERE p.name = $1 AND v.version = $2",
+        &[&name, &version],
+    ).await?;
     
-    Ok(Some(PackageWithVersions { package, versions }))
+    Ok(row.as_ref().map(row_to_version_detail))
+}
+
+/// Most recently published versions across all packages, newest first
+pub async fn get_recent_versions(
+    pool: &Pool,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<VersionDetail>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_recent_versions");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_recent_versions(conn, limit, offset)).await,
+    };
+    
+    let rows = client.query(
+        "SELECT v.id, v.package_id, v.version, v.ipfs_hash, v.downloads, v.published_at, v.authority, v.readme_cid, v.frozen,
+                v.license, v.file_count, v.unpacked_size,
+                p.name, p.author, p.description, p.repository, p.homepage
+         FROM versions v
+         JOIN packages p ON p.id = v.package_id
+         ORDER BY v.published_at DESC, v.id DESC
+         LIMIT $1 OFFSET $2",
+        &[&limit, &offset],
+    ).await?;
+    
+    Ok(rows.iter().map(row_to_version_detail).collect())
+}
+
+/// Packages ranked by downloads counted in the last `window_secs`; packages
+/// without downloads in the window are left out
+pub async fn get_trending_packages(
+    pool: &Pool,
+    window_secs: f64,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<TrendingPackage>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_trending_packages");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_trending_packages(conn, window_secs, limit, offset)).await,
+    };
+    
+    let rows = client.query(
+        "SELECT p.id, p.name, p.author, p.description, p.repository, p.homepage, p.total_downloads,
+                p.created_at, p.updated_at, p.keywords, COUNT(d.id) AS window_downloads
+         FROM download_events d
+         JOIN packages p ON p.id = d.package_id
+         WHERE d.downloaded_at >= NOW() - make_interval(secs => $1)
+         GROUP BY p.id
+         ORDER BY window_downloads DESC, p.name ASC
+         LIMIT $2 OFFSET $3",
+        &[&window_secs, &limit, &offset],
+    ).await?;
+    
+    Ok(rows.iter().map(|row| TrendingPackage {
+        package: row_to_package(row),
+        window_downloads: row.get(10),
+    }).collect())
 }
 
 pub async fn list_packages(
     pool: &Pool,
     limit: i64,
     offset: i64,
+) -> Result<Paginated<Package>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("list_packages");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::list_packages(conn, limit, offset)).await,
+    };
+    
+    let sql = "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords,
+                      count(*) OVER () AS total
+               FROM packages
+               ORDER BY created_at DESC
+               LIMIT $1 OFFSET $2";
+    let rows = client.query(sql, &[&limit, &offset]).await?;
+    let total = window_total(&client, sql, &[], &rows, offset).await?;
+    
+    Ok(Paginated { items: rows.iter().map(row_to_package).collect(), total, limit, offset })
+}
+
+/// Packages whose current authority is `authority`, most recently updated first
+pub async fn get_packages_by_author(
+    pool: &Pool,
+    authority: &str,
+    limit: i64,
+    offset: i64,
 ) -> Result<Vec<Package>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_packages_by_author");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_packages_by_author(conn, authority, limit, offset)).await,
+    };
     
     let rows = client.query(
-        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at
+        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords
          FROM packages
-         ORDER BY created_at DESC
-         LIMIT $1 OFFSET $2",
-        &[&limit, &offset],
+         WHERE author = $1
+         ORDER BY updated_at DESC, name ASC
+         LIMIT $2 OFFSET $3",
+        &[&authority, &limit, &offset],
     ).await?;
     
     Ok(rows.iter().map(row_to_package).collect())
 }
 
+/// Record an authority transfer of `name@version`: the version and the
+/// package both move to `new_authority`. Returns whether the package is indexed.
+pub async fn update_package_authority(
+    client: &impl GenericClient,
+    name: &str,
+    version: Option<&str>,
+    new_authority: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_package_authority");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::update_package_authority(conn, name, version, new_authority)).await,
+    };
+    
+    if let Some(version) = version {
+        client.execute(
+            "UPDATE versions v SET authority = $3
+             FROM packages p
+             WHERE p.id = v.package_id AND p.name = $1 AND v.version = $2",
+            &[&name, &version, &new_authority],
+        ).await?;
+    }
+    let updated = client.execute(
+        "UPDATE packages SET author = $2, updated_at = NOW() WHERE name = $1",
+        &[&name, &new_authority],
+    ).await?;
+    
+    Ok(updated > 0)
+}
+
+/// Record that `name@version` was frozen. Returns whether the version is indexed.
+pub async fn freeze_version(
+    client: &impl GenericClient,
+    name: &str,
+    version: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("freeze_version");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::freeze_version(conn, name, version)).await,
+    };
+    
+    let updated = client.execute(
+        "UPDATE versions v SET frozen = TRUE
+         FROM packages p
+         WHERE p.id = v.package_id AND p.name = $1 AND v.version = $2",
+        &[&name, &version],
+    ).await?;
+    
+    Ok(updated > 0)
+}
+
 pub async fn get_stats(pool: &Pool) -> Result<Stats, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_stats");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_stats(conn)).await,
+    };
     
     let row = client.query_one(
         "SELECT 
@@ -163,13 +630,19 @@ pub async fn get_stats(pool: &Pool) -> Result<Stats, Box<dyn std::error::Error +
     })
 }
 
+/// Count one download of a version. Run it inside a transaction, as
+/// `record_download` and ingestion do, so the counters always add up to the
+/// time series.
 pub async fn increment_download(
-    pool: &Pool,
+    client: &impl GenericClient,
     package_id: i32,
     version_id: i32,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
-    
+    let _timer = QueryTimer::start("increment_download");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::increment_download(conn, package_id, version_id)).await,
+    };
     client.execute(
         "UPDATE packages SET total_downloads = total_downloads + 1 WHERE id = $1",
         &[&package_id],
@@ -180,9 +653,151 @@ pub async fn increment_download(
         &[&version_id],
     ).await?;
     
+    client.execute(
+        "INSERT INTO download_events (package_id, version_id) VALUES ($1, $2)",
+        &[&package_id, &version_id],
+    ).await?;
+    
     Ok(())
 }
 
+/// Downloads of a package per `interval` bucket from `from` through `to`
+/// (UTC dates, inclusive), oldest first. Buckets without downloads are
+/// included with a zero count; rolled-up days count like raw events.
+pub async fn get_download_series(
+    pool: &Pool,
+    package_id: i32,
+    interval: DownloadInterval,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+) -> Result<Vec<DownloadBucket>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_download_series");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_download_series(conn, package_id, interval, from, to)).await,
+    };
+    
+    let rows = client.query(
+        "WITH buckets AS (
+             SELECT generate_series(
+                 date_trunc($2, $3::date::timestamp),
+                 date_trunc($2, $4::date::timestamp),
+                 ('1 ' || $2)::interval
+             )::date AS bucket
+         ),
+         counts AS (
+             SELECT date_trunc($2, downloaded_at AT TIME ZONE 'UTC')::date AS bucket, COUNT(*) AS downloads
+             FROM download_events
+             WHERE package_id = $1
+               AND downloaded_at >= $3::date::timestamp AT TIME ZONE 'UTC'
+               AND downloaded_at < ($4::date + 1)::timestamp AT TIME ZONE 'UTC'
+             GROUP BY 1
+             UNION ALL
+             SELECT date_trunc($2, day::timestamp)::date, SUM(downloads)
+             FROM download_rollups
+             WHERE package_id = $1 AND day BETWEEN $3::date AND $4::date
+             GROUP BY 1
+         )
+         SELECT b.bucket, COALESCE(SUM(c.downloads), 0)::BIGINT
+         FROM buckets b
+         LEFT JOIN counts c ON c.bucket = b.bucket
+         GROUP BY b.bucket
+         ORDER BY b.bucket",
+        &[&package_id, &interval.as_sql(), &from, &to],
+    ).await?;
+    
+    Ok(rows.iter().map(|row| DownloadBucket { date: row.get(0), count: row.get(1) }).collect())
+}
+
+/// Move download events from before the last `retention_days` whole UTC
+/// days into daily rollups, in one statement so nothing is counted twice
+/// or lost. Returns how many events were rolled up.
+pub async fn roll_up_download_events(
+    pool: &Pool,
+    retention_days: u32,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("roll_up_download_events");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::roll_up_download_events(conn, retention_days)).await,
+    };
+    
+    let row = client.query_one(
+        "WITH moved AS (
+             DELETE FROM download_events
+             WHERE downloaded_at < (date_trunc('day', NOW() AT TIME ZONE 'UTC') - make_interval(days => $1)) AT TIME ZONE 'UTC'
+             RETURNING package_id, version_id, (downloaded_at AT TIME ZONE 'UTC')::date AS day
+         ),
+         rolled AS (
+             INSERT INTO download_rollups (package_id, version_id, day, downloads)
+             SELECT package_id, version_id, day, COUNT(*) FROM moved GROUP BY 1, 2, 3
+             ON CONFLICT (version_id, day) DO UPDATE SET downloads = download_rollups.downloads + EXCLUDED.downloads
+         )
+         SELECT COUNT(*) FROM moved",
+        &[&(retention_days as i32)],
+    ).await?;
+    
+    let moved: i64 = row.get(0);
+    Ok(moved as u64)
+}
+
+/// Count a client-reported download of `name@version`.
+///
+/// A client seen for the same version within `window_secs` is not counted
+/// again; `client` is hashed before it is stored. Returns `None` when the
+/// version isn't indexed, otherwise whether the download was counted.
+pub async fn record_download(
+    pool: &Pool,
+    name: &str,
+    version: &str,
+    client: Option<&str>,
+    window_secs: f64,
+) -> Result<Option<bool>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("record_download");
+    let mut db = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::record_download(conn, name, version, client, window_secs)).await,
+    };
+    let tx = db.transaction().await?;
+
+    let Some(row) = tx.query_opt(
+        "SELECT v.package_id, v.id
+         FROM versions v
+         JOIN packages p ON p.id = v.package_id
+         WHERE p.name = $1 AND v.version = $2",
+        &[&name, &version],
+    ).await? else {
+        return Ok(None);
+    };
+    let (package_id, version_id): (i32, i32) = (row.get(0), row.get(1));
+
+    if let Some(client) = client {
+        tx.execute(
+            "DELETE FROM download_clients WHERE last_seen < NOW() - make_interval(secs => $1)",
+            &[&window_secs],
+        ).await?;
+
+        // The upsert only returns a row when the client is new or its last
+        // download fell outside the window
+        let fresh = tx.query_opt(
+            "INSERT INTO download_clients (version_id, client_hash, last_seen)
+             VALUES ($1, md5($2), NOW())
+             ON CONFLICT (version_id, client_hash) DO UPDATE SET last_seen = NOW()
+             WHERE download_clients.last_seen < NOW() - make_interval(secs => $3)
+             RETURNING version_id",
+            &[&version_id, &client, &window_secs],
+        ).await?;
+        if fresh.is_none() {
+            tx.commit().await?;
+            return Ok(Some(false));
+        }
+    }
+
+    increment_download(&*tx, package_id, version_id).await?;
+    tx.commit().await?;
+    Ok(Some(true))
+}
+
 fn row_to_package(row: &Row) -> Package {
     Package {
         id: row.get(0),
@@ -191,12 +806,46 @@ fn row_to_package(row: &Row) -> Package {
         description: row.get(3),
         repository: row.get(4),
         homepage: row.get(5),
+        keywords: row.get(9),
         total_downloads: row.get(6),
         created_at: row.get(7),
         updated_at: row.get(8),
     }
 }
 
+/// A version row (unpacked_size last) followed by name, author, description, repository, homepage
+fn row_to_version_detail(row: &Row) -> VersionDetail {
+    VersionDetail {
+        version: row_to_version(row),
+        name: row.get(12),
+        author: row.get(13),
+        description: row.get(14),
+        repository: row.get(15),
+        homepage: row.get(16),
+    }
+}
+
+/// Columns [`row_to_event`] reads, in order, for an `events` table aliased `e`
+const EVENT_COLUMNS: &str =
+    "e.id, e.event_type, e.package_name, e.version, e.transaction_signature, e.slot, e.block_time, e.finality, e.ipfs_hash, e.authority, e.version_id, e.log_index";
+
+fn row_to_event(row: &Row) -> Event {
+    Event {
+        id: row.get(0),
+        event_type: row.get(1),
+        package_name: row.get(2),
+        version: row.get(3),
+        transaction_signature: row.get(4),
+        slot: row.get(5),
+        block_time: row.get(6),
+        finality: EventFinality::from_db(row.get(7)),
+        ipfs_hash: row.get(8),
+        authority: row.get(9),
+        version_id: row.get(10),
+        log_index: row.get(11),
+    }
+}
+
 fn row_to_version(row: &Row) -> Version {
     Version {
         id: row.get(0),
@@ -205,12 +854,22 @@ fn row_to_version(row: &Row) -> Version {
         ipfs_hash: row.get(3),
         downloads: row.get(4),
         published_at: row.get(5),
+        authority: row.get(6),
+        readme_cid: row.get(7),
+        frozen: row.get(8),
+        license: row.get(9),
+        file_count: row.get(10),
+        unpacked_size: row.get(11),
     }
 }
 
 // Indexer state management
 pub async fn get_last_processed_slot(pool: &Pool) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_last_processed_slot");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_last_processed_slot(conn)).await,
+    };
     
     let row = client.query_one(
         "SELECT last_processed_slot FROM indexer_state WHERE id = 1",
@@ -226,7 +885,11 @@ pub async fn update_last_processed_slot(
     slot: u64,
     block_time: Option<i64>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("update_last_processed_slot");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::update_last_processed_slot(conn, slot, block_time)).await,
+    };
     
     let block_time_dt = block_time.map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default());
     
@@ -243,11 +906,234 @@ pub async fn update_last_processed_slot(
     Ok(())
 }
 
+/// Everything up to `slot` has been ingested. Recorded after each complete
+/// poll so an idle program doesn't look like a stalled indexer.
+pub async fn update_synced_slot(pool: &Pool, slot: u64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_synced_slot");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::update_synced_slot(conn, slot)).await,
+    };
+    
+    client.execute(
+        "UPDATE indexer_state
+         SET last_processed_slot = GREATEST(last_processed_slot, $1),
+             updated_at = NOW(),
+             status = 'running'
+         WHERE id = 1",
+        &[&(slot as i64)],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Listener progress and its latest error, for `/health`
+pub async fn get_indexer_state(pool: &Pool) -> Result<IndexerState, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_indexer_state");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_indexer_state(conn)).await,
+    };
+    
+    let row = client.query_one(
+        "SELECT last_processed_slot, error_count, last_error,
+                EXTRACT(EPOCH FROM NOW() - updated_at)::BIGINT
+         FROM indexer_state WHERE id = 1",
+        &[],
+    ).await?;
+    
+    let slot: i64 = row.get(0);
+    let error_count: Option<i32> = row.get(1);
+    Ok(IndexerState {
+        last_processed_slot: slot.max(0) as u64,
+        error_count: error_count.unwrap_or(0),
+        last_error: row.get(2),
+        seconds_since_update: row.get(3),
+    })
+}
+
+/// Newest signature the listener has ingested, if it has run since signature tracking was added
+pub async fn get_last_signature(pool: &Pool) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_last_signature");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_last_signature(conn)).await,
+    };
+    
+    let row = client.query_one(
+        "SELECT last_signature FROM indexer_state WHERE id = 1",
+        &[],
+    ).await?;
+    
+    Ok(row.get(0))
+}
+
+/// Advance the signature cursor together with the slot it belongs to
+pub async fn update_last_signature(
+    pool: &Pool,
+    signature: &str,
+    slot: u64,
+    block_time: Option<i64>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_last_signature");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::update_last_signature(conn, signature, slot, block_time)).await,
+    };
+    
+    let block_time_dt = block_time.map(|ts| DateTime::from_timestamp(ts, 0).unwrap_or_default());
+    
+    client.execute(
+        "UPDATE indexer_state 
+         SET last_signature = $1,
+             last_processed_slot = $2, 
+             last_processed_block_time = $3,
+             updated_at = NOW(),
+             status = 'running'
+         WHERE id = 1",
+        &[&signature, &(slot as i64), &block_time_dt],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Newest slot the finality pass has seen finalized
+pub async fn get_finalized_slot(pool: &Pool) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_finalized_slot");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_finalized_slot(conn)).await,
+    };
+    
+    let row = client.query_one("SELECT finalized_slot FROM indexer_state WHERE id = 1", &[]).await?;
+    let slot: i64 = row.get(0);
+    Ok(slot.max(0) as u64)
+}
+
+pub async fn update_finalized_slot(pool: &Pool, slot: u64) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("update_finalized_slot");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::update_finalized_slot(conn, slot)).await,
+    };
+    
+    client.execute(
+        "UPDATE indexer_state SET finalized_slot = GREATEST(finalized_slot, $1), updated_at = NOW() WHERE id = 1",
+        &[&(slot as i64)],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Signatures (with their recorded slot) of events not yet finalized at or
+/// below `up_to_slot`, oldest first. Only real transaction signatures are
+/// returned; events from manual ingestion can't be checked on-chain.
+pub async fn get_unfinalized_signatures(
+    pool: &Pool,
+    up_to_slot: u64,
+    limit: i64,
+) -> Result<Vec<(String, i64)>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_unfinalized_signatures");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_unfinalized_signatures(conn, up_to_slot, limit)).await,
+    };
+    
+    let rows = client.query(
+        "SELECT transaction_signature, MIN(slot) AS slot
+         FROM events
+         WHERE finality = 'confirmed' AND slot <= $1
+           AND transaction_signature ~ '^[1-9A-HJ-NP-Za-km-z]{86,88}$'
+         GROUP BY transaction_signature
+         ORDER BY slot
+         LIMIT $2",
+        &[&(up_to_slot as i64), &limit],
+    ).await?;
+    
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Mark a transaction's events final, moving them to the slot it landed in
+pub async fn finalize_events(
+    pool: &Pool,
+    transaction_signature: &str,
+    slot: u64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("finalize_events");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::finalize_events(conn, transaction_signature, slot)).await,
+    };
+    
+    client.execute(
+        "UPDATE events SET finality = 'finalized', slot = $2
+         WHERE transaction_signature = $1 AND finality = 'confirmed'",
+        &[&transaction_signature, &(slot as i64)],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Flag a vanished transaction's events as orphaned, returning them
+pub async fn orphan_events(
+    pool: &Pool,
+    transaction_signature: &str,
+) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("orphan_events");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::orphan_events(conn, transaction_signature)).await,
+    };
+    
+    let rows = client.query(
+        &format!(
+            "UPDATE events e SET finality = 'orphaned'
+             WHERE e.transaction_signature = $1 AND e.finality = 'confirmed'
+             RETURNING {}",
+            EVENT_COLUMNS
+        ),
+        &[&transaction_signature],
+    ).await?;
+    
+    Ok(rows.iter().map(row_to_event).collect())
+}
+
+/// Undo the version an orphaned publish or update created, and the package
+/// itself when that left it without versions. Returns whether a version went.
+pub async fn remove_orphaned_version(
+    pool: &Pool,
+    package_name: &str,
+    version: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("remove_orphaned_version");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::remove_orphaned_version(conn, package_name, version)).await,
+    };
+    
+    let removed = client.execute(
+        "DELETE FROM versions v USING packages p
+         WHERE v.package_id = p.id AND p.name = $1 AND v.version = $2",
+        &[&package_name, &version],
+    ).await?;
+    client.execute(
+        "DELETE FROM packages p
+         WHERE p.name = $1 AND NOT EXISTS (SELECT 1 FROM versions v WHERE v.package_id = p.id)",
+        &[&package_name],
+    ).await?;
+    
+    Ok(removed > 0)
+}
+
 pub async fn update_indexer_error(
     pool: &Pool,
     error_msg: &str,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("update_indexer_error");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::update_indexer_error(conn, error_msg)).await,
+    };
     
     client.execute(
         "UPDATE indexer_state 
@@ -261,67 +1147,508 @@ pub async fn update_indexer_error(
     Ok(())
 }
 
+/// Latest events, optionally only those of `event_type`
 pub async fn get_recent_events(
     pool: &Pool,
+    event_type: Option<&str>,
     limit: i64,
-) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    offset: i64,
+) -> Result<Paginated<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_recent_events");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_recent_events(conn, event_type, limit, offset)).await,
+    };
     
-    let rows = client.query(
-        "SELECT id, event_type, package_name, version, transaction_signature, slot, block_time
-         FROM events
-         ORDER BY slot DESC, id DESC
-         LIMIT $1",
-        &[&limit],
-    ).await?;
+    let sql = format!(
+        "SELECT {}, count(*) OVER () AS total
+         FROM events e
+         WHERE $1::TEXT IS NULL OR e.event_type = $1
+         ORDER BY e.slot DESC, e.id DESC
+         LIMIT $2 OFFSET $3",
+        EVENT_COLUMNS
+    );
+    let rows = client.query(&sql, &[&event_type, &limit, &offset]).await?;
+    let total = window_total(&client, &sql, &[&event_type], &rows, offset).await?;
     
-    Ok(rows.iter().map(|row| Event {
-        id: row.get(0),
-        event_type: row.get(1),
-        package_name: row.get(2),
-        version: row.get(3),
-        transaction_signature: row.get(4),
-        slot: row.get(5),
-        block_time: row.get(6),
-    }).collect())
+    Ok(Paginated { items: rows.iter().map(row_to_event).collect(), total, limit, offset })
 }
 
 pub async fn get_package_events(
     pool: &Pool,
     package_name: &str,
+    event_type: Option<&str>,
     limit: i64,
     offset: i64,
-) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+) -> Result<Paginated<PackageEvent>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_package_events");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_package_events(conn, package_name, event_type, limit, offset)).await,
+    };
+    
+    let sql = format!(
+        "SELECT {}, d.dependencies_added, d.dependencies_removed, count(*) OVER () AS total
+         FROM events e
+         LEFT JOIN dependency_changes d ON d.transaction_signature = e.transaction_signature
+         WHERE e.package_name = $1 AND ($2::TEXT IS NULL OR e.event_type = $2)
+         ORDER BY e.slot DESC, e.id DESC
+         LIMIT $3 OFFSET $4",
+        EVENT_COLUMNS
+    );
+    let rows = client.query(&sql, &[&package_name, &event_type, &limit, &offset]).await?;
+    let total = window_total(&client, &sql, &[&package_name, &event_type], &rows, offset).await?;
+    
+    let items = rows.iter().map(|row| {
+        let added: Option<Vec<String>> = row.get(12);
+        let removed: Option<Vec<String>> = row.get(13);
+        PackageEvent {
+            event: row_to_event(row),
+            dependency_change: added.zip(removed).map(|(added, removed)| DependencyChange { added, removed }),
+        }
+    }).collect();
+    Ok(Paginated { items, total, limit, offset })
+}
+
+/// Rows a paginated query matched in all, read from the `total` column its
+/// `count(*) OVER ()` adds to every row. A page past the end has no row to
+/// carry it, so `sql` is re-run for the first row. `params` are the query's
+/// parameters before its trailing `LIMIT` and `OFFSET`.
+async fn window_total(
+    client: &Client,
+    sql: &str,
+    params: &[&(dyn ToSql + Sync)],
+    rows: &[Row],
+    offset: i64,
+) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(row) = rows.first() {
+        return Ok(row.get("total"));
+    }
+    if offset == 0 {
+        return Ok(0);
+    }
+    let (limit, offset) = (1i64, 0i64);
+    let mut first_page = params.to_vec();
+    first_page.extend([&limit as &(dyn ToSql + Sync), &offset]);
+    Ok(client.query_opt(sql, &first_page).await?.map_or(0, |row| row.get("total")))
+}
+
+/// Record the dependency delta of an update; replays of the same transaction are ignored
+pub async fn insert_dependency_change(
+    client: &impl GenericClient,
+    package_name: &str,
+    version: &str,
+    added: &[String],
+    removed: &[String],
+    transaction_signature: &str,
+    slot: i64,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("insert_dependency_change");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::insert_dependency_change(conn, package_name, version, added, removed, transaction_signature, slot)).await,
+    };
+    
+    client.execute(
+        "INSERT INTO dependency_changes
+            (package_name, version, dependencies_added, dependencies_removed, transaction_signature, slot)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (transaction_signature) DO NOTHING",
+        &[&package_name, &version, &added, &removed, &transaction_signature, &slot],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Replace the dependency list of `package_name@version`. Returns false
+/// when the version isn't indexed, in which case nothing is stored.
+pub async fn replace_version_dependencies(
+    client: &impl GenericClient,
+    package_name: &str,
+    version: &str,
+    dependencies: &[(String, String)],
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("replace_version_dependencies");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::replace_version_dependencies(conn, package_name, version, dependencies)).await,
+    };
+    
+    let row = client.query_opt(
+        "SELECT v.id FROM versions v JOIN packages p ON p.id = v.package_id
+         WHERE p.name = $1 AND v.version = $2",
+        &[&package_name, &version],
+    ).await?;
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let version_id: i32 = row.get(0);
+    
+    client.execute("DELETE FROM dependencies WHERE version_id = $1", &[&version_id]).await?;
+    for (dep_name, dep_version) in dependencies {
+        client.execute(
+            "INSERT INTO dependencies (version_id, dep_name, dep_version) VALUES ($1, $2, $3)
+             ON CONFLICT (version_id, dep_name) DO UPDATE SET dep_version = EXCLUDED.dep_version",
+            &[&version_id, dep_name, dep_version],
+        ).await?;
+    }
+    
+    Ok(true)
+}
+
+/// Replace the external dependency list of `package_name@version`. Returns
+/// false when the version isn't indexed, in which case nothing is stored.
+pub async fn replace_version_external_dependencies(
+    client: &impl GenericClient,
+    package_name: &str,
+    version: &str,
+    dependencies: &[ExternalDependency],
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("replace_version_external_dependencies");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::replace_version_external_dependencies(conn, package_name, version, dependencies)).await,
+    };
+    
+    let row = client.query_opt(
+        "SELECT v.id FROM versions v JOIN packages p ON p.id = v.package_id
+         WHERE p.name = $1 AND v.version = $2",
+        &[&package_name, &version],
+    ).await?;
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let version_id: i32 = row.get(0);
+    
+    client.execute("DELETE FROM external_dependencies WHERE version_id = $1", &[&version_id]).await?;
+    for dep in dependencies {
+        client.execute(
+            "INSERT INTO external_dependencies (version_id, dep_type, dep_name, dep_version, registry)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (version_id, dep_type, dep_name)
+             DO UPDATE SET dep_version = EXCLUDED.dep_version, registry = EXCLUDED.registry",
+            &[&version_id, &dep.dep_type, &dep.name, &dep.version, &dep.registry],
+        ).await?;
+    }
+    
+    Ok(true)
+}
+
+/// What `name@version` depends on, or its latest version's dependencies
+/// when `version` is `None`; `None` when the package or version is unknown
+pub async fn get_dependencies(
+    pool: &Pool,
+    name: &str,
+    version: Option<&str>,
+) -> Result<Option<PackageDependencies>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_dependencies");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_dependencies(conn, name, version)).await,
+    };
+    
+    let row = client.query_opt(
+        &format!(
+            "SELECT v.id, v.version
+             FROM versions v
+             JOIN packages p ON p.id = v.package_id
+             WHERE p.name = $1 AND ($2::TEXT IS NULL OR v.version = $2)
+             ORDER BY {}
+             LIMIT 1",
+            LATEST_VERSION_ORDER
+        ),
+        &[&name, &version],
+    ).await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    let version_id: i32 = row.get(0);
     
     let rows = client.query(
-        "SELECT id, event_type, package_name, version, transaction_signature, slot, block_time
-         FROM events
-         WHERE package_name = $1
-         ORDER BY slot DESC, id DESC
-         LIMIT $2 OFFSET $3",
-        &[&package_name, &limit, &offset],
+        "SELECT dep_name, dep_version FROM dependencies WHERE version_id = $1 ORDER BY dep_name",
+        &[&version_id],
+    ).await?;
+    let external = client.query(
+        "SELECT dep_name, dep_version, dep_type, registry FROM external_dependencies
+         WHERE version_id = $1 ORDER BY dep_type, dep_name",
+        &[&version_id],
     ).await?;
     
-    Ok(rows.iter().map(|row| Event {
-        id: row.get(0),
-        event_type: row.get(1),
-        package_name: row.get(2),
-        version: row.get(3),
-        transaction_signature: row.get(4),
-        slot: row.get(5),
-        block_time: row.get(6),
+    Ok(Some(PackageDependencies {
+        name: name.to_string(),
+        version: row.get(1),
+        dependencies: rows.iter().map(|row| Dependency { name: row.get(0), version: row.get(1) }).collect(),
+        external_dependencies: external
+            .iter()
+            .map(|row| ExternalDependency { name: row.get(0), version: row.get(1), dep_type: row.get(2), registry: row.get(3) })
+            .collect(),
+    }))
+}
+
+/// Packages whose latest version depends on `name`, alphabetically
+pub async fn get_dependents(
+    pool: &Pool,
+    name: &str,
+    limit: i64,
+    offset: i64,
+) -> Result<Vec<Dependent>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_dependents");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_dependents(conn, name, limit, offset)).await,
+    };
+    
+    let rows = client.query(
+        &format!(
+            "SELECT p.name, dv.version, d.dep_version
+             FROM dependencies d
+             JOIN versions dv ON dv.id = d.version_id
+             JOIN packages p ON p.id = dv.package_id
+             WHERE d.dep_name = $1
+               AND dv.id = (SELECT v.id FROM versions v
+                            WHERE v.package_id = p.id
+                            ORDER BY {}
+                            LIMIT 1)
+             ORDER BY p.name
+             LIMIT $2 OFFSET $3",
+            LATEST_VERSION_ORDER
+        ),
+        &[&name, &limit, &offset],
+    ).await?;
+    
+    Ok(rows.iter().map(|row| Dependent {
+        name: row.get(0),
+        version: row.get(1),
+        requires: row.get(2),
     }).collect())
 }
 
+/// Packages whose latest version depends on `name@version`, then, up to
+/// `max_depth` levels, packages whose latest version depends on those. Each
+/// package is listed once, at the nearest level it is reached; a path never
+/// revisits a package, so dependency cycles end. `None` when the version
+/// isn't indexed.
+pub async fn get_version_dependents(
+    pool: &Pool,
+    name: &str,
+    version: &str,
+    max_depth: i32,
+) -> Result<Option<Vec<VersionDependent>>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_version_dependents");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_version_dependents(conn, name, version, max_depth)).await,
+    };
+    
+    let indexed = client.query_opt(
+        "SELECT 1 FROM versions v JOIN packages p ON p.id = v.package_id WHERE p.name = $1 AND v.version = $2",
+        &[&name, &version],
+    ).await?;
+    if indexed.is_none() {
+        return Ok(None);
+    }
+    let latest = format!("SELECT v.id FROM versions v WHERE v.package_id = p.id ORDER BY {} LIMIT 1", LATEST_VERSION_ORDER);
+    let rows = client.query(
+        &format!(
+            "WITH RECURSIVE dependents (name, version, via, requires, depth, path) AS (
+                 SELECT p.name, dv.version, d.dep_name, d.dep_version, 1, ARRAY[d.dep_name, p.name]
+                 FROM dependencies d
+                 JOIN versions dv ON dv.id = d.version_id
+                 JOIN packages p ON p.id = dv.package_id
+                 WHERE d.dep_name = $1 AND d.dep_version = $2 AND p.name <> $1
+                   AND dv.id = ({latest})
+               UNION ALL
+                 SELECT p.name, dv.version, d.dep_name, d.dep_version, t.depth + 1, t.path || p.name
+                 FROM dependents t
+                 JOIN dependencies d ON d.dep_name = t.name AND d.dep_version = t.version
+                 JOIN versions dv ON dv.id = d.version_id
+                 JOIN packages p ON p.id = dv.package_id
+                 WHERE t.depth < $3 AND p.name <> ALL(t.path)
+                   AND dv.id = ({latest})
+             )
+             SELECT name, version, via, requires, depth FROM (
+                 SELECT DISTINCT ON (name) name, version, via, requires, depth
+                 FROM dependents
+                 ORDER BY name, depth, via
+             ) nearest
+             ORDER BY depth, name",
+            latest = latest
+        ),
+        &[&name, &version, &max_depth],
+    ).await?;
+    
+    Ok(Some(rows.iter().map(|row| VersionDependent {
+        name: row.get(0),
+        version: row.get(1),
+        via: row.get(2),
+        requires: row.get(3),
+        depth: row.get(4),
+    }).collect()))
+}
+
+/// README.md of `name@version`, or of the latest version when `version` is
+/// `None`: the version and its README, which is `None` until the tarball
+/// was read or when it has none. `None` when the package or version is unknown.
+pub async fn get_readme(
+    pool: &Pool,
+    name: &str,
+    version: Option<&str>,
+) -> Result<Option<(String, Option<String>)>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_readme");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_readme(conn, name, version)).await,
+    };
+    
+    let row = client.query_opt(
+        &format!(
+            "SELECT v.version, v.readme
+             FROM versions v
+             JOIN packages p ON p.id = v.package_id
+             WHERE p.name = $1 AND ($2::TEXT IS NULL OR v.version = $2)
+             ORDER BY {}
+             LIMIT 1",
+            LATEST_VERSION_ORDER
+        ),
+        &[&name, &version],
+    ).await?;
+    
+    Ok(row.map(|row| (row.get(0), row.get(1))))
+}
+
+/// CIDs with versions that weren't enriched yet and are due for an
+/// attempt, oldest first, with the attempts each has had
+pub async fn get_unenriched_cids(
+    pool: &Pool,
+    limit: i64,
+) -> Result<Vec<(String, i32)>, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("get_unenriched_cids");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::get_unenriched_cids(conn, limit)).await,
+    };
+    
+    let rows = client.query(
+        "SELECT ipfs_hash, MAX(enrichment_attempts)
+         FROM versions
+         WHERE enriched_at IS NULL AND NOT enrichment_failed
+           AND (enrichment_retry_at IS NULL OR enrichment_retry_at <= NOW())
+         GROUP BY ipfs_hash
+         ORDER BY MIN(id)
+         LIMIT $1",
+        &[&limit],
+    ).await?;
+    
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Store what was read from the tarball `cid` on every version of it. A
+/// package without a description takes the manifest's. Returns the versions updated.
+pub async fn store_version_enrichment(
+    pool: &Pool,
+    cid: &str,
+    enrichment: &VersionEnrichment,
+) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("store_version_enrichment");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::store_version_enrichment(conn, cid, enrichment)).await,
+    };
+    
+    let updated = client.execute(
+        "UPDATE versions
+         SET description = $2, license = $3, readme = $4, file_count = $5, unpacked_size = $6,
+             enriched_at = NOW(), enrichment_retry_at = NULL, enrichment_error = NULL
+         WHERE ipfs_hash = $1",
+        &[
+            &cid,
+            &enrichment.description,
+            &enrichment.license,
+            &enrichment.readme,
+            &enrichment.file_count,
+            &enrichment.unpacked_size,
+        ],
+    ).await?;
+    if let Some(description) = &enrichment.description {
+        client.execute(
+            "UPDATE packages p SET description = $2
+             FROM versions v
+             WHERE v.package_id = p.id AND v.ipfs_hash = $1 AND COALESCE(p.description, '') = ''",
+            &[&cid, description],
+        ).await?;
+    }
+    
+    Ok(updated)
+}
+
+/// Count a failed attempt at the tarball `cid`: try again at `retry_at`,
+/// or never when it is `None`
+pub async fn record_enrichment_failure(
+    pool: &Pool,
+    cid: &str,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("record_enrichment_failure");
+    let client = match pool {
+        Pool::Postgres(pool) => pool.get().await?,
+        Pool::Sqlite(db) => return db.run(|conn| sqlite::record_enrichment_failure(conn, cid, error, retry_at)).await,
+    };
+    
+    client.execute(
+        "UPDATE versions
+         SET enrichment_attempts = enrichment_attempts + 1, enrichment_error = $2,
+             enrichment_retry_at = $3, enrichment_failed = $3::TIMESTAMPTZ IS NULL
+         WHERE ipfs_hash = $1 AND enriched_at IS NULL",
+        &[&cid, &error, &retry_at],
+    ).await?;
+    
+    Ok(())
+}
+
+/// Add (`listed`) or remove a maintainer on the list `authority` keeps for
+/// `package_name`; replays are no-ops
+pub async fn set_package_maintainer(
+    client: &impl GenericClient,
+    package_name: &str,
+    authority: &str,
+    maintainer: &str,
+    listed: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let _timer = QueryTimer::start("set_package_maintainer");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::set_package_maintainer(conn, package_name, authority, maintainer, listed)).await,
+    };
+    
+    if listed {
+        client.execute(
+            "INSERT INTO package_maintainers (package_name, authority, maintainer) VALUES ($1, $2, $3)
+             ON CONFLICT (package_name, authority, maintainer) DO NOTHING",
+            &[&package_name, &authority, &maintainer],
+        ).await?;
+    } else {
+        client.execute(
+            "DELETE FROM package_maintainers WHERE package_name = $1 AND authority = $2 AND maintainer = $3",
+            &[&package_name, &authority, &maintainer],
+        ).await?;
+    }
+    
+    Ok(())
+}
+
 // --- New helper query functions for indexer ingestion logic ---
 
 /// Return the package id if a package with the given name exists.
 pub async fn get_package_id(
-    pool: &Pool,
+    client: &impl GenericClient,
     name: &str,
 ) -> Result<Option<i32>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_package_id");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::get_package_id(conn, name)).await,
+    };
     let row = client.query_opt(
         "SELECT id FROM packages WHERE name = $1",
         &[&name],
@@ -331,14 +1658,50 @@ pub async fn get_package_id(
 
 /// Return the version id for a given (package_id, version) pair.
 pub async fn get_version_id(
-    pool: &Pool,
+    client: &impl GenericClient,
     package_id: i32,
     version: &str,
 ) -> Result<Option<i32>, Box<dyn std::error::Error + Send + Sync>> {
-    let client = pool.get().await?;
+    let _timer = QueryTimer::start("get_version_id");
+    let client = match client.client() {
+        ClientRef::Postgres(client) => client,
+        ClientRef::Sqlite(db) => return db.run(|conn| sqlite::get_version_id(conn, package_id, version)).await,
+    };
     let row = client.query_opt(
         "SELECT id FROM versions WHERE package_id = $1 AND version = $2",
         &[&package_id, &version],
     ).await?;
     Ok(row.map(|r| r.get(0)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds(req: &str) -> Vec<(VersionBound, VersionBound)> {
+        semver::VersionReq::parse(req).unwrap().comparators.iter().map(comparator_bounds).collect()
+    }
+
+    #[test]
+    fn comparators_become_numeric_bounds() {
+        assert_eq!(bounds("^1.2"), vec![(Some(([1, 2, 0], true)), Some(([2, 0, 0], false)))]);
+        assert_eq!(bounds("^0.2.3"), vec![(Some(([0, 2, 3], true)), Some(([0, 3, 0], false)))]);
+        assert_eq!(bounds("^0.0.3"), vec![(Some(([0, 0, 3], true)), Some(([0, 0, 4], false)))]);
+        assert_eq!(bounds("~1.4.1"), vec![(Some(([1, 4, 1], true)), Some(([1, 5, 0], false)))]);
+        assert_eq!(bounds("=1.2"), vec![(Some(([1, 2, 0], true)), Some(([1, 3, 0], false)))]);
+        assert_eq!(bounds("1.*"), vec![(Some(([1, 0, 0], true)), Some(([2, 0, 0], false)))]);
+        assert_eq!(
+            bounds(">1.2.3, <=2"),
+            vec![(Some(([1, 2, 4], true)), None), (None, Some(([3, 0, 0], false)))]
+        );
+    }
+
+    #[test]
+    fn version_conditions_only_match_plain_versions() {
+        let sql = version_req_sql(&semver::VersionReq::parse(">=1.0.0").unwrap());
+        assert_eq!(
+            sql,
+            format!("({0}) IS NOT NULL AND {0} >= ARRAY[1, 0, 0]::numeric[]", PLAIN_VERSION_ARRAY)
+        );
+    }
+}
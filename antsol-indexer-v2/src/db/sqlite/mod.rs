@@ -0,0 +1,167 @@
+//! SQLite backend, for running the indexer without a Postgres server:
+//! `DATABASE_URL=sqlite://indexer.db`, or `sqlite::memory:` for a database
+//! that lives as long as the process.
+//!
+//! There is a single connection. Statements take turns on it, and a
+//! transaction has it to itself until it commits, so writes are serialized
+//! and API reads wait while an ingestion transaction is open. Statements
+//! run on the calling task's thread rather than a blocking pool.
+
+pub mod queries;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::Connection;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::sync::{OwnedRwLockWriteGuard, RwLock};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Schema files, in order; each may be re-run on an up-to-date database
+const MIGRATIONS: [&str; 1] = [include_str!("../../../migrations/sqlite/001_init.sql")];
+
+/// Rebuilds an `events` table created before events were keyed on their
+/// log index; run only when the column is missing
+const EVENT_LOG_INDEX: &str = include_str!("../../../migrations/sqlite/002_event_log_index.sql");
+
+/// Adds `versions.frozen` to a database created before versions could be
+/// frozen; run only when the column is missing
+const VERSION_FROZEN: &str = include_str!("../../../migrations/sqlite/003_version_frozen.sql");
+
+/// Adds the enrichment columns to `versions`; run only when they are missing
+const VERSION_ENRICHMENT: &str = include_str!("../../../migrations/sqlite/004_version_enrichment.sql");
+
+/// Rebuilds `package_maintainers` keyed by authority; run only when the
+/// column is missing
+const MAINTAINER_AUTHORITY: &str = include_str!("../../../migrations/sqlite/005_maintainer_authority.sql");
+
+/// How long a statement waits on a database another process has locked
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct SqliteDb {
+    conn: Arc<Mutex<Connection>>,
+    /// Held shared around single statements and exclusively by a
+    /// transaction, so nothing else runs on the connection inside one
+    turn: Arc<RwLock<()>>,
+}
+
+impl SqliteDb {
+    /// Open the database a `sqlite:` URL names: `sqlite://path`,
+    /// `sqlite:path` or `sqlite::memory:`
+    pub fn open(url: &str) -> Result<Self, BoxError> {
+        let path = url.trim_start_matches("sqlite:").trim_start_matches("//");
+        let path = path.split('?').next().unwrap_or_default();
+        let conn = if path.is_empty() || path == ":memory:" {
+            Connection::open_in_memory()?
+        } else {
+            let conn = Connection::open(path)?;
+            conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+            conn
+        };
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        Ok(Self { conn: Arc::new(Mutex::new(conn)), turn: Arc::new(RwLock::new(())) })
+    }
+
+    pub async fn migrate(&self) -> Result<(), BoxError> {
+        self.run(|conn| {
+            MIGRATIONS.iter().try_for_each(|sql| conn.execute_batch(sql))?;
+            if !has_column(conn, "events", "log_index")? {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute_batch(EVENT_LOG_INDEX)?;
+                tx.commit()?;
+            }
+            if !has_column(conn, "versions", "frozen")? {
+                conn.execute_batch(VERSION_FROZEN)?;
+            }
+            if !has_column(conn, "versions", "enriched_at")? {
+                conn.execute_batch(VERSION_ENRICHMENT)?;
+            }
+            if !has_column(conn, "package_maintainers", "authority")? {
+                let tx = conn.unchecked_transaction()?;
+                tx.execute_batch(MAINTAINER_AUTHORITY)?;
+                tx.commit()?;
+            }
+            Ok::<_, rusqlite::Error>(())
+        }).await
+    }
+
+    /// Run `f` on the connection once no transaction holds it
+    pub async fn run<T, E: Into<BoxError>>(&self, f: impl FnOnce(&Connection) -> Result<T, E>) -> Result<T, BoxError> {
+        let _turn = self.turn.read().await;
+        f(&self.connection()).map_err(Into::into)
+    }
+
+    /// Start a transaction, waiting for the one in progress to finish
+    pub async fn begin(&self) -> Result<SqliteTx, BoxError> {
+        let turn = self.turn.clone().write_owned().await;
+        self.connection().execute_batch("BEGIN IMMEDIATE")?;
+        Ok(SqliteTx { db: self.clone(), _turn: turn, done: false })
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> rusqlite::Result<bool> {
+    let mut stmt = conn.prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?;
+    stmt.exists([column])
+}
+
+/// An open transaction; dropping it uncommitted rolls it back
+pub struct SqliteTx {
+    db: SqliteDb,
+    _turn: OwnedRwLockWriteGuard<()>,
+    done: bool,
+}
+
+impl SqliteTx {
+    pub fn commit(mut self) -> Result<(), BoxError> {
+        self.db.connection().execute_batch("COMMIT")?;
+        self.done = true;
+        Ok(())
+    }
+
+    pub fn rollback(mut self) -> Result<(), BoxError> {
+        self.done = true;
+        self.db.connection().execute_batch("ROLLBACK")?;
+        Ok(())
+    }
+}
+
+impl Drop for SqliteTx {
+    fn drop(&mut self) {
+        if !self.done {
+            if let Err(e) = self.db.connection().execute_batch("ROLLBACK") {
+                tracing::warn!("Failed to roll back SQLite transaction: {}", e);
+            }
+        }
+    }
+}
+
+/// The connection, outside or inside a transaction
+pub enum SqliteClient<'a> {
+    Shared(&'a SqliteDb),
+    Transaction(&'a SqliteTx),
+}
+
+impl SqliteClient<'_> {
+    pub async fn run<T, E: Into<BoxError>>(&self, f: impl FnOnce(&Connection) -> Result<T, E>) -> Result<T, BoxError> {
+        match self {
+            SqliteClient::Shared(db) => db.run(f).await,
+            SqliteClient::Transaction(tx) => f(&tx.db.connection()).map_err(Into::into),
+        }
+    }
+}
+
+/// How timestamp columns store `at`
+pub fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// How date columns store `day`
+pub fn date(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
@@ -0,0 +1,1225 @@
+//! The statements behind [`crate::db::queries`] on SQLite. Each function
+//! does what the one of the same name there does on Postgres; search is a
+//! substring match per term instead of ranked full-text search.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use md5::{Digest, Md5};
+use rusqlite::types::{Type, Value};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row};
+use std::collections::BTreeMap;
+
+use super::{date, timestamp};
+use crate::db::models::*;
+use crate::db::queries::{comparator_bounds, LATEST_VERSION_ORDER, MIN_FULL_TEXT_QUERY_LEN, VERSION_ORDER};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+pub fn insert_package(
+    conn: &Connection,
+    name: &str,
+    authority: &str,
+    description: Option<&str>,
+    repository: Option<&str>,
+    homepage: Option<&str>,
+) -> Result<i32> {
+    Ok(conn.query_row(
+        "INSERT INTO packages (name, author, description, repository, homepage)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (name) DO UPDATE SET
+            author = CASE WHEN excluded.author = 'unknown' THEN packages.author ELSE excluded.author END,
+            description = COALESCE(excluded.description, packages.description),
+            repository = excluded.repository,
+            homepage = excluded.homepage,
+            updated_at = ?6
+         RETURNING id",
+        params![name, authority, description, repository, homepage, now()],
+        |row| row.get(0),
+    )?)
+}
+
+pub fn update_package_metadata(
+    conn: &Connection,
+    name: &str,
+    repository: Option<&str>,
+    homepage: Option<&str>,
+    keywords: &[String],
+) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE packages SET repository = ?2, homepage = ?3, keywords = ?4, updated_at = ?5 WHERE name = ?1",
+        params![name, repository, homepage, serde_json::to_string(keywords)?, now()],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn update_version_readme(conn: &Connection, name: &str, version: &str, readme_cid: &str) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE versions SET readme_cid = ?3
+         WHERE version = ?2 AND package_id = (SELECT id FROM packages WHERE name = ?1)",
+        params![name, version, readme_cid],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn insert_version(
+    conn: &Connection,
+    package_id: i32,
+    version: &str,
+    ipfs_hash: &str,
+    authority: Option<&str>,
+    published_at: Option<DateTime<Utc>>,
+) -> Result<i32> {
+    let (major, minor, patch, prerelease) = semver_columns(version);
+    let (id, version_published_at): (i32, String) = conn.query_row(
+        "INSERT INTO versions (package_id, version, ipfs_hash, authority, published_at,
+                               version_major, version_minor, version_patch, version_prerelease)
+         VALUES (?1, ?2, ?3, ?4, COALESCE(?5, ?6), ?7, ?8, ?9, ?10)
+         ON CONFLICT (package_id, version) DO UPDATE SET
+            ipfs_hash = excluded.ipfs_hash,
+            authority = COALESCE(excluded.authority, versions.authority),
+            published_at = min(versions.published_at, COALESCE(?5, versions.published_at))
+         RETURNING id, published_at",
+        params![package_id, version, ipfs_hash, authority, published_at.map(timestamp), now(), major, minor, patch, prerelease],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    // A package is as old as its first published version
+    conn.execute(
+        "UPDATE packages SET created_at = min(COALESCE(created_at, ?2), ?2) WHERE id = ?1",
+        params![package_id, version_published_at],
+    )?;
+    Ok(id)
+}
+
+/// Sort columns of `version` as migration 017 generates them on Postgres:
+/// major, minor and patch, and the prerelease key; all `None` unless it is
+/// `MAJOR.MINOR.PATCH[-PRE][+BUILD]`
+fn semver_columns(version: &str) -> (Option<i64>, Option<i64>, Option<i64>, Option<String>) {
+    let identifier_chars = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    let none = (None, None, None, None);
+
+    let (rest, build) = match version.split_once('+') {
+        Some((rest, build)) => (rest, Some(build)),
+        None => (version, None),
+    };
+    if build.is_some_and(|build| !identifier_chars(build)) {
+        return none;
+    }
+    let (core, prerelease) = match rest.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (rest, None),
+    };
+    if prerelease.is_some_and(|pre| !identifier_chars(pre)) {
+        return none;
+    }
+    let parts: Vec<Option<i64>> = core
+        .split('.')
+        .map(|part| if part.bytes().all(|b| b.is_ascii_digit()) { part.parse().ok() } else { None })
+        .collect();
+    let [Some(major), Some(minor), Some(patch)] = parts[..] else {
+        return none;
+    };
+
+    // Numeric identifiers as '0' plus their length in two digits plus the
+    // digits, alphanumeric ones as '1' plus the identifier
+    let key = prerelease.map(|pre| {
+        pre.split('.')
+            .map(|ident| {
+                if !ident.is_empty() && ident.bytes().all(|b| b.is_ascii_digit()) {
+                    let length = format!("{:02}", ident.len());
+                    format!("0{}{}", &length[..2], ident)
+                } else {
+                    format!("1{}", ident)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    });
+    (Some(major), Some(minor), Some(patch), key)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn insert_event(
+    conn: &Connection,
+    event_type: &str,
+    package_name: &str,
+    version: Option<&str>,
+    transaction_signature: &str,
+    log_index: i32,
+    slot: i64,
+    block_time: Option<i64>,
+    ipfs_hash: Option<&str>,
+    authority: Option<&str>,
+) -> Result<i32> {
+    let id = conn.query_row(
+        "INSERT INTO events (event_type, package_name, version, transaction_signature, log_index, slot, block_time, ipfs_hash, authority)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT (transaction_signature, log_index) DO NOTHING
+         RETURNING id",
+        params![event_type, package_name, version, transaction_signature, log_index, slot, block_time.map(chain_time), ipfs_hash, authority],
+        |row| row.get(0),
+    ).optional()?;
+    Ok(id.unwrap_or(0))
+}
+
+pub fn link_event_version(conn: &Connection, event_id: i32, package_name: &str, version: &str) -> Result<Option<i32>> {
+    let Some(version_id) = version_id_of(conn, package_name, version)? else {
+        return Ok(None);
+    };
+    let updated = conn.execute("UPDATE events SET version_id = ?2 WHERE id = ?1", params![event_id, version_id])?;
+    Ok((updated > 0).then_some(version_id))
+}
+
+pub fn event_recorded(conn: &Connection, transaction_signature: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM events WHERE transaction_signature = ?1)",
+        params![transaction_signature],
+        |row| row.get(0),
+    )?)
+}
+
+/// Every positive term of the query has to turn up in the name, a keyword
+/// or the description, where it is matched without an English suffix; a
+/// `-term` must not. Names and keywords outrank descriptions.
+pub fn search_packages(
+    conn: &Connection,
+    query: &str,
+    filter: &SearchFilter,
+    sort: SearchSort,
+    limit: i64,
+    offset: i64,
+) -> Result<Paginated<SearchResult>> {
+    let query = query.trim();
+    let full_text = query.chars().count() >= MIN_FULL_TEXT_QUERY_LEN;
+    // ?1 is the query and ?2 the author; patterns follow
+    let mut values = vec![Value::Text(query.to_string()), filter.author.clone().map_or(Value::Null, Value::Text)];
+    let mut pattern = |text: &str| {
+        values.push(Value::Text(format!("%{}%", escape_like(text))));
+        format!("?{}", values.len())
+    };
+
+    let (text_match, score) = if full_text {
+        let mut conditions = Vec::new();
+        let mut scores = Vec::new();
+        for (word, excluded) in search_words(query) {
+            let terms: Vec<&str> = if excluded {
+                vec![word]
+            } else {
+                word.split(['-', '_', '.', '/', '@']).filter(|term| !term.is_empty()).collect()
+            };
+            let mut matches = Vec::new();
+            for term in terms {
+                let (name, prose) = (pattern(term), pattern(stem(term)));
+                let in_name = format!("p.name LIKE {} ESCAPE '\\'", name);
+                let in_keywords = format!("EXISTS (SELECT 1 FROM json_each(p.keywords) k WHERE k.value LIKE {} ESCAPE '\\')", name);
+                let in_description = format!("p.description LIKE {} ESCAPE '\\'", prose);
+                matches.push(format!("({} OR {} OR {})", in_name, in_keywords, in_description));
+                if !excluded {
+                    scores.push(format!(
+                        "(CASE WHEN {} THEN 4 WHEN {} THEN 2 WHEN {} THEN 1 ELSE 0 END)",
+                        in_name, in_keywords, in_description
+                    ));
+                }
+            }
+            if matches.is_empty() {
+                continue;
+            }
+            let all = matches.join(" AND ");
+            conditions.push(if excluded { format!("NOT ({})", all) } else { all });
+        }
+        if conditions.is_empty() {
+            conditions.push("FALSE".to_string());
+        }
+        let score = (!scores.is_empty()).then(|| scores.join(" + "));
+        (conditions.join(" AND "), score)
+    } else {
+        let any = pattern(query);
+        (format!("(p.name LIKE {0} ESCAPE '\\' OR p.description LIKE {0} ESCAPE '\\')", any), None)
+    };
+    let has_version = match &filter.has_version {
+        Some(req) => format!(
+            "EXISTS (SELECT 1 FROM versions v WHERE v.package_id = p.id AND {})",
+            version_req_sql(req)
+        ),
+        None => "TRUE".to_string(),
+    };
+    let order = match sort {
+        SearchSort::Relevance => match score {
+            Some(score) => format!("lower(p.name) = lower(?1) DESC, {} DESC, p.total_downloads DESC, p.name ASC", score),
+            None => "lower(p.name) = lower(?1) DESC, p.total_downloads DESC, p.name ASC".to_string(),
+        },
+        SearchSort::Downloads => "p.total_downloads DESC, p.name ASC".to_string(),
+        SearchSort::Recent => "p.updated_at DESC, p.name ASC".to_string(),
+        SearchSort::Name => "p.name ASC".to_string(),
+    };
+
+    let latest = |column: &str| format!(
+        "(SELECT v.{} FROM versions v WHERE v.package_id = p.id ORDER BY {} LIMIT 1)",
+        column, LATEST_VERSION_ORDER
+    );
+    let sql = format!(
+        "SELECT p.id, p.name, p.author, p.description, p.repository, p.homepage, p.total_downloads,
+                p.created_at, p.updated_at, p.keywords, {}, {}, count(*) OVER () AS total
+         FROM packages p
+         WHERE {} AND (?2 IS NULL OR p.author = ?2) AND {}
+         ORDER BY {}
+         LIMIT ?{} OFFSET ?{}",
+        latest("version"), latest("published_at"), text_match, has_version, order, values.len() + 1, values.len() + 2
+    );
+    let (items, total) = paginate(conn, &sql, values, limit, offset, |row| {
+        Ok(SearchResult {
+            package: row_to_package(row)?,
+            latest_version: row.get(10)?,
+            latest_published_at: opt_time(row, 11)?,
+        })
+    })?;
+    Ok(Paginated { items, total, limit, offset })
+}
+
+/// Words of a search query with quotes dropped, each with whether it is
+/// excluded (`-word`)
+fn search_words(query: &str) -> Vec<(&str, bool)> {
+    query
+        .split_whitespace()
+        .map(|word| word.trim_matches('"'))
+        .filter_map(|word| match word.strip_prefix('-') {
+            Some(excluded) => (!excluded.is_empty()).then_some((excluded, true)),
+            None => (!word.is_empty()).then_some((word, false)),
+        })
+        .collect()
+}
+
+/// `word` without a common English suffix, so "parse" finds "parsing"
+fn stem(word: &str) -> &str {
+    for suffix in ["ing", "ed", "es", "e", "s"] {
+        if word.len() > suffix.len() + 2 {
+            if let Some(stem) = word.strip_suffix(suffix) {
+                return stem;
+            }
+        }
+    }
+    word
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// SQL condition on `v` for `req`, the bounds of
+/// [`comparator_bounds`] on the version columns of plain versions
+fn version_req_sql(req: &semver::VersionReq) -> String {
+    let mut conditions = vec![
+        "v.version_major IS NOT NULL AND v.version_prerelease IS NULL AND instr(v.version, '+') = 0".to_string(),
+    ];
+    let tuple = |[major, minor, patch]: [u64; 3]| format!("({}, {}, {})", major, minor, patch);
+    for comparator in &req.comparators {
+        let (lower, upper) = comparator_bounds(comparator);
+        if let Some((bound, inclusive)) = lower {
+            conditions.push(format!("{} {} {}", VERSION_TUPLE, if inclusive { ">=" } else { ">" }, tuple(bound)));
+        }
+        if let Some((bound, inclusive)) = upper {
+            conditions.push(format!("{} {} {}", VERSION_TUPLE, if inclusive { "<=" } else { "<" }, tuple(bound)));
+        }
+    }
+    conditions.join(" AND ")
+}
+
+const VERSION_TUPLE: &str = "(v.version_major, v.version_minor, v.version_patch)";
+
+pub fn get_package_with_versions(conn: &Connection, name: &str) -> Result<Option<PackageWithVersions>> {
+    let package = conn.query_row(
+        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords
+         FROM packages WHERE name = ?1",
+        params![name],
+        row_to_package,
+    ).optional()?;
+    let Some(package) = package else {
+        return Ok(None);
+    };
+
+    let versions = conn
+        .prepare(&format!(
+            "SELECT v.id, v.package_id, v.version, v.ipfs_hash, v.downloads, v.published_at, v.authority, v.readme_cid, v.frozen,
+                    v.license, v.file_count, v.unpacked_size
+             FROM versions v
+             WHERE v.package_id = ?1
+             ORDER BY {}",
+            VERSION_ORDER
+        ))?
+        .query_map(params![package.id], row_to_version)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let latest_version: Option<String> = conn.query_row(
+        &format!("SELECT v.version FROM versions v WHERE v.package_id = ?1 ORDER BY {} LIMIT 1", LATEST_VERSION_ORDER),
+        params![package.id],
+        |row| row.get(0),
+    ).optional()?;
+    let frozen = versions.iter().any(|v| v.frozen && latest_version.as_ref() == Some(&v.version));
+    // Only the list kept by the latest version's authority covers it
+    let authority = versions.iter()
+        .find(|v| latest_version.as_ref() == Some(&v.version))
+        .and_then(|v| v.authority.clone())
+        .unwrap_or_default();
+    let maintainers = conn
+        .prepare(
+            "SELECT maintainer FROM package_maintainers WHERE package_name = ?1 AND authority = ?2
+             ORDER BY added_at, maintainer",
+        )?
+        .query_map(params![name, authority], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Some(PackageWithVersions { package, latest_version, frozen, versions, maintainers }))
+}
+
+pub fn get_version(conn: &Connection, name: &str, version: &str) -> Result<Option<VersionDetail>> {
+    Ok(conn.query_row(
+        &format!("{} WHERE p.name = ?1 AND v.version = ?2", VERSION_DETAIL_SELECT),
+        params![name, version],
+        row_to_version_detail,
+    ).optional()?)
+}
+
+pub fn get_recent_versions(conn: &Connection, limit: i64, offset: i64) -> Result<Vec<VersionDetail>> {
+    let rows = conn
+        .prepare(&format!(
+            "{} ORDER BY v.published_at DESC, v.id DESC LIMIT ?1 OFFSET ?2",
+            VERSION_DETAIL_SELECT
+        ))?
+        .query_map(params![limit, offset], row_to_version_detail)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn get_trending_packages(conn: &Connection, window_secs: f64, limit: i64, offset: i64) -> Result<Vec<TrendingPackage>> {
+    let since = timestamp(Utc::now() - Duration::milliseconds((window_secs * 1000.0) as i64));
+    let rows = conn
+        .prepare(
+            "SELECT p.id, p.name, p.author, p.description, p.repository, p.homepage, p.total_downloads,
+                    p.created_at, p.updated_at, p.keywords, COUNT(d.id) AS window_downloads
+             FROM download_events d
+             JOIN packages p ON p.id = d.package_id
+             WHERE d.downloaded_at >= ?1
+             GROUP BY p.id
+             ORDER BY window_downloads DESC, p.name ASC
+             LIMIT ?2 OFFSET ?3",
+        )?
+        .query_map(params![since, limit, offset], |row| {
+            Ok(TrendingPackage { package: row_to_package(row)?, window_downloads: row.get(10)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn list_packages(conn: &Connection, limit: i64, offset: i64) -> Result<Paginated<Package>> {
+    let (items, total) = paginate(
+        conn,
+        "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords,
+                count(*) OVER () AS total
+         FROM packages
+         ORDER BY created_at DESC
+         LIMIT ?1 OFFSET ?2",
+        Vec::new(),
+        limit,
+        offset,
+        row_to_package,
+    )?;
+    Ok(Paginated { items, total, limit, offset })
+}
+
+pub fn get_packages_by_author(conn: &Connection, authority: &str, limit: i64, offset: i64) -> Result<Vec<Package>> {
+    let rows = conn
+        .prepare(
+            "SELECT id, name, author, description, repository, homepage, total_downloads, created_at, updated_at, keywords
+             FROM packages
+             WHERE author = ?1
+             ORDER BY updated_at DESC, name ASC
+             LIMIT ?2 OFFSET ?3",
+        )?
+        .query_map(params![authority, limit, offset], row_to_package)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn update_package_authority(conn: &Connection, name: &str, version: Option<&str>, new_authority: &str) -> Result<bool> {
+    if let Some(version) = version {
+        conn.execute(
+            "UPDATE versions SET authority = ?3
+             WHERE version = ?2 AND package_id = (SELECT id FROM packages WHERE name = ?1)",
+            params![name, version, new_authority],
+        )?;
+    }
+    let updated = conn.execute(
+        "UPDATE packages SET author = ?2, updated_at = ?3 WHERE name = ?1",
+        params![name, new_authority, now()],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn freeze_version(conn: &Connection, name: &str, version: &str) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE versions SET frozen = 1
+         WHERE version = ?2 AND package_id = (SELECT id FROM packages WHERE name = ?1)",
+        params![name, version],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn get_stats(conn: &Connection) -> Result<Stats> {
+    Ok(conn.query_row(
+        "SELECT
+            (SELECT COUNT(*) FROM packages),
+            (SELECT COUNT(*) FROM versions),
+            (SELECT COALESCE(SUM(total_downloads), 0) FROM packages),
+            (SELECT COUNT(*) FROM events)",
+        [],
+        |row| Ok(Stats {
+            total_packages: row.get(0)?,
+            total_versions: row.get(1)?,
+            total_downloads: row.get(2)?,
+            total_events: row.get(3)?,
+        }),
+    )?)
+}
+
+pub fn increment_download(conn: &Connection, package_id: i32, version_id: i32) -> Result<()> {
+    conn.execute("UPDATE packages SET total_downloads = total_downloads + 1 WHERE id = ?1", params![package_id])?;
+    conn.execute("UPDATE versions SET downloads = downloads + 1 WHERE id = ?1", params![version_id])?;
+    conn.execute(
+        "INSERT INTO download_events (package_id, version_id, downloaded_at) VALUES (?1, ?2, ?3)",
+        params![package_id, version_id, now()],
+    )?;
+    Ok(())
+}
+
+/// Daily counts are read from the database and bucketed here
+pub fn get_download_series(
+    conn: &Connection,
+    package_id: i32,
+    interval: DownloadInterval,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DownloadBucket>> {
+    let mut counts = BTreeMap::new();
+    let mut bucket = bucket_start(interval, from);
+    while bucket <= to {
+        counts.insert(bucket, 0);
+        bucket = match interval {
+            DownloadInterval::Day => bucket + Duration::days(1),
+            DownloadInterval::Week => bucket + Duration::days(7),
+            DownloadInterval::Month => bucket.checked_add_months(chrono::Months::new(1)).ok_or("date out of range")?,
+        };
+    }
+
+    let start = timestamp(from.and_time(NaiveTime::MIN).and_utc());
+    let end = timestamp((to + Duration::days(1)).and_time(NaiveTime::MIN).and_utc());
+    let days = conn
+        .prepare(
+            "SELECT substr(downloaded_at, 1, 10), COUNT(*)
+             FROM download_events
+             WHERE package_id = ?1 AND downloaded_at >= ?2 AND downloaded_at < ?3
+             GROUP BY 1
+             UNION ALL
+             SELECT day, SUM(downloads)
+             FROM download_rollups
+             WHERE package_id = ?1 AND day BETWEEN ?4 AND ?5
+             GROUP BY 1",
+        )?
+        .query_map(params![package_id, start, end, date(from), date(to)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    for (day, downloads) in days {
+        let day = NaiveDate::parse_from_str(&day, "%Y-%m-%d")?;
+        *counts.entry(bucket_start(interval, day)).or_insert(0) += downloads;
+    }
+
+    Ok(counts.into_iter().map(|(date, count)| DownloadBucket { date, count }).collect())
+}
+
+/// First day of the bucket `day` falls in, as `date_trunc` picks it
+fn bucket_start(interval: DownloadInterval, day: NaiveDate) -> NaiveDate {
+    match interval {
+        DownloadInterval::Day => day,
+        DownloadInterval::Week => day - Duration::days(day.weekday().num_days_from_monday().into()),
+        DownloadInterval::Month => day.with_day(1).unwrap_or(day),
+    }
+}
+
+pub fn roll_up_download_events(conn: &Connection, retention_days: u32) -> Result<u64> {
+    let cutoff = (Utc::now().date_naive() - Duration::days(retention_days.into())).and_time(NaiveTime::MIN).and_utc();
+    let cutoff = timestamp(cutoff);
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO download_rollups (package_id, version_id, day, downloads)
+         SELECT package_id, version_id, substr(downloaded_at, 1, 10), COUNT(*)
+         FROM download_events
+         WHERE downloaded_at < ?1
+         GROUP BY 1, 2, 3
+         ON CONFLICT (version_id, day) DO UPDATE SET downloads = download_rollups.downloads + excluded.downloads",
+        params![cutoff],
+    )?;
+    let moved = tx.execute("DELETE FROM download_events WHERE downloaded_at < ?1", params![cutoff])?;
+    tx.commit()?;
+    Ok(moved as u64)
+}
+
+pub fn record_download(
+    conn: &Connection,
+    name: &str,
+    version: &str,
+    client: Option<&str>,
+    window_secs: f64,
+) -> Result<Option<bool>> {
+    let tx = conn.unchecked_transaction()?;
+    let row = tx.query_row(
+        "SELECT v.package_id, v.id FROM versions v JOIN packages p ON p.id = v.package_id
+         WHERE p.name = ?1 AND v.version = ?2",
+        params![name, version],
+        |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?)),
+    ).optional()?;
+    let Some((package_id, version_id)) = row else {
+        return Ok(None);
+    };
+
+    if let Some(client) = client {
+        let now = Utc::now();
+        let window_start = timestamp(now - Duration::milliseconds((window_secs * 1000.0) as i64));
+        tx.execute("DELETE FROM download_clients WHERE last_seen < ?1", params![window_start])?;
+
+        // The upsert only returns a row when the client is new or its last
+        // download fell outside the window
+        let client_hash: String = Md5::digest(client.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect();
+        let fresh = tx.query_row(
+            "INSERT INTO download_clients (version_id, client_hash, last_seen)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT (version_id, client_hash) DO UPDATE SET last_seen = excluded.last_seen
+             WHERE download_clients.last_seen < ?4
+             RETURNING version_id",
+            params![version_id, client_hash, timestamp(now), window_start],
+            |row| row.get::<_, i32>(0),
+        ).optional()?;
+        if fresh.is_none() {
+            tx.commit()?;
+            return Ok(Some(false));
+        }
+    }
+
+    increment_download(&tx, package_id, version_id)?;
+    tx.commit()?;
+    Ok(Some(true))
+}
+
+pub fn get_last_processed_slot(conn: &Connection) -> Result<u64> {
+    let slot: i64 = conn.query_row("SELECT last_processed_slot FROM indexer_state WHERE id = 1", [], |row| row.get(0))?;
+    Ok(slot.max(0) as u64)
+}
+
+pub fn update_last_processed_slot(conn: &Connection, slot: u64, block_time: Option<i64>) -> Result<()> {
+    conn.execute(
+        "UPDATE indexer_state
+         SET last_processed_slot = ?1, last_processed_block_time = ?2, updated_at = ?3, status = 'running'
+         WHERE id = 1",
+        params![slot as i64, block_time.map(chain_time), now()],
+    )?;
+    Ok(())
+}
+
+pub fn update_synced_slot(conn: &Connection, slot: u64) -> Result<()> {
+    conn.execute(
+        "UPDATE indexer_state
+         SET last_processed_slot = max(last_processed_slot, ?1), updated_at = ?2, status = 'running'
+         WHERE id = 1",
+        params![slot as i64, now()],
+    )?;
+    Ok(())
+}
+
+pub fn get_indexer_state(conn: &Connection) -> Result<IndexerState> {
+    let (slot, error_count, last_error, updated_at) = conn.query_row(
+        "SELECT last_processed_slot, error_count, last_error, updated_at FROM indexer_state WHERE id = 1",
+        [],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i32>>(1)?, row.get::<_, Option<String>>(2)?, opt_time(row, 3)?)),
+    )?;
+    Ok(IndexerState {
+        last_processed_slot: slot.max(0) as u64,
+        error_count: error_count.unwrap_or(0),
+        last_error,
+        seconds_since_update: updated_at.map(|at| (Utc::now() - at).num_seconds()),
+    })
+}
+
+pub fn get_last_signature(conn: &Connection) -> Result<Option<String>> {
+    Ok(conn.query_row("SELECT last_signature FROM indexer_state WHERE id = 1", [], |row| row.get(0))?)
+}
+
+pub fn update_last_signature(conn: &Connection, signature: &str, slot: u64, block_time: Option<i64>) -> Result<()> {
+    conn.execute(
+        "UPDATE indexer_state
+         SET last_signature = ?1, last_processed_slot = ?2, last_processed_block_time = ?3,
+             updated_at = ?4, status = 'running'
+         WHERE id = 1",
+        params![signature, slot as i64, block_time.map(chain_time), now()],
+    )?;
+    Ok(())
+}
+
+pub fn get_finalized_slot(conn: &Connection) -> Result<u64> {
+    let slot: i64 = conn.query_row("SELECT finalized_slot FROM indexer_state WHERE id = 1", [], |row| row.get(0))?;
+    Ok(slot.max(0) as u64)
+}
+
+pub fn update_finalized_slot(conn: &Connection, slot: u64) -> Result<()> {
+    conn.execute(
+        "UPDATE indexer_state SET finalized_slot = max(finalized_slot, ?1), updated_at = ?2 WHERE id = 1",
+        params![slot as i64, now()],
+    )?;
+    Ok(())
+}
+
+/// Base58 signatures are 86 to 88 characters of the Bitcoin alphabet
+pub fn get_unfinalized_signatures(conn: &Connection, up_to_slot: u64, limit: i64) -> Result<Vec<(String, i64)>> {
+    let rows = conn
+        .prepare(
+            "SELECT transaction_signature, MIN(slot) AS slot
+             FROM events
+             WHERE finality = 'confirmed' AND slot <= ?1
+               AND length(transaction_signature) BETWEEN 86 AND 88
+               AND transaction_signature NOT GLOB '*[^1-9A-HJ-NP-Za-km-z]*'
+             GROUP BY transaction_signature
+             ORDER BY slot
+             LIMIT ?2",
+        )?
+        .query_map(params![up_to_slot as i64, limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn finalize_events(conn: &Connection, transaction_signature: &str, slot: u64) -> Result<()> {
+    conn.execute(
+        "UPDATE events SET finality = 'finalized', slot = ?2
+         WHERE transaction_signature = ?1 AND finality = 'confirmed'",
+        params![transaction_signature, slot as i64],
+    )?;
+    Ok(())
+}
+
+pub fn orphan_events(conn: &Connection, transaction_signature: &str) -> Result<Vec<Event>> {
+    // RETURNING can't name the table, so the columns are listed bare
+    let rows = conn
+        .prepare(&format!(
+            "UPDATE events SET finality = 'orphaned'
+             WHERE transaction_signature = ?1 AND finality = 'confirmed'
+             RETURNING {}",
+            EVENT_COLUMNS.replace("e.", "")
+        ))?
+        .query_map(params![transaction_signature], row_to_event)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn remove_orphaned_version(conn: &Connection, package_name: &str, version: &str) -> Result<bool> {
+    let removed = conn.execute(
+        "DELETE FROM versions WHERE version = ?2 AND package_id = (SELECT id FROM packages WHERE name = ?1)",
+        params![package_name, version],
+    )?;
+    conn.execute(
+        "DELETE FROM packages
+         WHERE name = ?1 AND NOT EXISTS (SELECT 1 FROM versions v WHERE v.package_id = packages.id)",
+        params![package_name],
+    )?;
+    Ok(removed > 0)
+}
+
+pub fn update_indexer_error(conn: &Connection, error_msg: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE indexer_state SET error_count = error_count + 1, last_error = ?1, updated_at = ?2 WHERE id = 1",
+        params![error_msg, now()],
+    )?;
+    Ok(())
+}
+
+pub fn get_recent_events(conn: &Connection, event_type: Option<&str>, limit: i64, offset: i64) -> Result<Paginated<Event>> {
+    let sql = format!(
+        "SELECT {}, count(*) OVER () AS total
+         FROM events e
+         WHERE ?1 IS NULL OR e.event_type = ?1
+         ORDER BY e.slot DESC, e.id DESC
+         LIMIT ?2 OFFSET ?3",
+        EVENT_COLUMNS
+    );
+    let values = vec![event_type.map_or(Value::Null, |t| Value::Text(t.to_string()))];
+    let (items, total) = paginate(conn, &sql, values, limit, offset, row_to_event)?;
+    Ok(Paginated { items, total, limit, offset })
+}
+
+pub fn get_package_events(
+    conn: &Connection,
+    package_name: &str,
+    event_type: Option<&str>,
+    limit: i64,
+    offset: i64,
+) -> Result<Paginated<PackageEvent>> {
+    let sql = format!(
+        "SELECT {}, d.dependencies_added, d.dependencies_removed, count(*) OVER () AS total
+         FROM events e
+         LEFT JOIN dependency_changes d ON d.transaction_signature = e.transaction_signature
+         WHERE e.package_name = ?1 AND (?2 IS NULL OR e.event_type = ?2)
+         ORDER BY e.slot DESC, e.id DESC
+         LIMIT ?3 OFFSET ?4",
+        EVENT_COLUMNS
+    );
+    let values = vec![
+        Value::Text(package_name.to_string()),
+        event_type.map_or(Value::Null, |t| Value::Text(t.to_string())),
+    ];
+    let (items, total) = paginate(conn, &sql, values, limit, offset, |row| {
+        let added = opt_list(row, 12)?;
+        let removed = opt_list(row, 13)?;
+        Ok(PackageEvent {
+            event: row_to_event(row)?,
+            dependency_change: added.zip(removed).map(|(added, removed)| DependencyChange { added, removed }),
+        })
+    })?;
+    Ok(Paginated { items, total, limit, offset })
+}
+
+/// Rows of a paginated query, and the `total` column its `count(*) OVER ()`
+/// adds, re-reading the first row for a page past the end as `window_total`
+/// does on Postgres. `values` are the parameters before `LIMIT` and `OFFSET`.
+fn paginate<T>(
+    conn: &Connection,
+    sql: &str,
+    mut values: Vec<Value>,
+    limit: i64,
+    offset: i64,
+    map: impl Fn(&Row) -> rusqlite::Result<T>,
+) -> Result<(Vec<T>, i64)> {
+    let mut statement = conn.prepare(sql)?;
+    let mut page = values.clone();
+    page.extend([Value::Integer(limit), Value::Integer(offset)]);
+
+    let mut items = Vec::new();
+    let mut total = None;
+    let mut rows = statement.query(params_from_iter(page))?;
+    while let Some(row) = rows.next()? {
+        total.get_or_insert(row.get::<_, i64>("total")?);
+        items.push(map(row)?);
+    }
+    drop(rows);
+
+    let total = match total {
+        Some(total) => total,
+        None if offset == 0 => 0,
+        None => {
+            values.extend([Value::Integer(1), Value::Integer(0)]);
+            statement.query_row(params_from_iter(values), |row| row.get("total")).optional()?.unwrap_or(0)
+        }
+    };
+    Ok((items, total))
+}
+
+pub fn insert_dependency_change(
+    conn: &Connection,
+    package_name: &str,
+    version: &str,
+    added: &[String],
+    removed: &[String],
+    transaction_signature: &str,
+    slot: i64,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO dependency_changes
+            (package_name, version, dependencies_added, dependencies_removed, transaction_signature, slot)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (transaction_signature) DO NOTHING",
+        params![package_name, version, serde_json::to_string(added)?, serde_json::to_string(removed)?, transaction_signature, slot],
+    )?;
+    Ok(())
+}
+
+pub fn replace_version_dependencies(
+    conn: &Connection,
+    package_name: &str,
+    version: &str,
+    dependencies: &[(String, String)],
+) -> Result<bool> {
+    let Some(version_id) = version_id_of(conn, package_name, version)? else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM dependencies WHERE version_id = ?1", params![version_id])?;
+    for (dep_name, dep_version) in dependencies {
+        conn.execute(
+            "INSERT INTO dependencies (version_id, dep_name, dep_version) VALUES (?1, ?2, ?3)
+             ON CONFLICT (version_id, dep_name) DO UPDATE SET dep_version = excluded.dep_version",
+            params![version_id, dep_name, dep_version],
+        )?;
+    }
+    Ok(true)
+}
+
+pub fn replace_version_external_dependencies(
+    conn: &Connection,
+    package_name: &str,
+    version: &str,
+    dependencies: &[ExternalDependency],
+) -> Result<bool> {
+    let Some(version_id) = version_id_of(conn, package_name, version)? else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM external_dependencies WHERE version_id = ?1", params![version_id])?;
+    for dep in dependencies {
+        conn.execute(
+            "INSERT INTO external_dependencies (version_id, dep_type, dep_name, dep_version, registry)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (version_id, dep_type, dep_name)
+             DO UPDATE SET dep_version = excluded.dep_version, registry = excluded.registry",
+            params![version_id, dep.dep_type, dep.name, dep.version, dep.registry],
+        )?;
+    }
+    Ok(true)
+}
+
+pub fn get_dependencies(conn: &Connection, name: &str, version: Option<&str>) -> Result<Option<PackageDependencies>> {
+    let row = conn.query_row(
+        &format!(
+            "SELECT v.id, v.version
+             FROM versions v
+             JOIN packages p ON p.id = v.package_id
+             WHERE p.name = ?1 AND (?2 IS NULL OR v.version = ?2)
+             ORDER BY {}
+             LIMIT 1",
+            LATEST_VERSION_ORDER
+        ),
+        params![name, version],
+        |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)),
+    ).optional()?;
+    let Some((version_id, version)) = row else {
+        return Ok(None);
+    };
+
+    let dependencies = conn
+        .prepare("SELECT dep_name, dep_version FROM dependencies WHERE version_id = ?1 ORDER BY dep_name")?
+        .query_map(params![version_id], |row| Ok(Dependency { name: row.get(0)?, version: row.get(1)? }))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let external_dependencies = conn
+        .prepare(
+            "SELECT dep_name, dep_version, dep_type, registry FROM external_dependencies
+             WHERE version_id = ?1 ORDER BY dep_type, dep_name",
+        )?
+        .query_map(params![version_id], |row| {
+            Ok(ExternalDependency { name: row.get(0)?, version: row.get(1)?, dep_type: row.get(2)?, registry: row.get(3)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    Ok(Some(PackageDependencies { name: name.to_string(), version, dependencies, external_dependencies }))
+}
+
+pub fn get_dependents(conn: &Connection, name: &str, limit: i64, offset: i64) -> Result<Vec<Dependent>> {
+    let rows = conn
+        .prepare(&format!(
+            "SELECT p.name, dv.version, d.dep_version
+             FROM dependencies d
+             JOIN versions dv ON dv.id = d.version_id
+             JOIN packages p ON p.id = dv.package_id
+             WHERE d.dep_name = ?1
+               AND dv.id = (SELECT v.id FROM versions v
+                            WHERE v.package_id = p.id
+                            ORDER BY {}
+                            LIMIT 1)
+             ORDER BY p.name
+             LIMIT ?2 OFFSET ?3",
+            LATEST_VERSION_ORDER
+        ))?
+        .query_map(params![name, limit, offset], |row| {
+            Ok(Dependent { name: row.get(0)?, version: row.get(1)?, requires: row.get(2)? })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn get_version_dependents(conn: &Connection, name: &str, version: &str, max_depth: i32) -> Result<Option<Vec<VersionDependent>>> {
+    let indexed = conn.query_row(
+        "SELECT 1 FROM versions v JOIN packages p ON p.id = v.package_id WHERE p.name = ?1 AND v.version = ?2",
+        params![name, version],
+        |_| Ok(()),
+    ).optional()?;
+    if indexed.is_none() {
+        return Ok(None);
+    }
+    let latest = format!("SELECT v.id FROM versions v WHERE v.package_id = p.id ORDER BY {} LIMIT 1", LATEST_VERSION_ORDER);
+    // Paths are comma-delimited names, which never contain a comma. With
+    // MIN(), SQLite takes the bare columns from the row at the nearest depth.
+    let rows = conn
+        .prepare(&format!(
+            "WITH RECURSIVE dependents (name, version, via, requires, depth, path) AS (
+                 SELECT p.name, dv.version, d.dep_name, d.dep_version, 1, ',' || d.dep_name || ',' || p.name || ','
+                 FROM dependencies d
+                 JOIN versions dv ON dv.id = d.version_id
+                 JOIN packages p ON p.id = dv.package_id
+                 WHERE d.dep_name = ?1 AND d.dep_version = ?2 AND p.name <> ?1
+                   AND dv.id = ({latest})
+               UNION ALL
+                 SELECT p.name, dv.version, d.dep_name, d.dep_version, t.depth + 1, t.path || p.name || ','
+                 FROM dependents t
+                 JOIN dependencies d ON d.dep_name = t.name AND d.dep_version = t.version
+                 JOIN versions dv ON dv.id = d.version_id
+                 JOIN packages p ON p.id = dv.package_id
+                 WHERE t.depth < ?3 AND instr(t.path, ',' || p.name || ',') = 0
+                   AND dv.id = ({latest})
+             )
+             SELECT name, version, via, requires, MIN(depth) AS nearest
+             FROM dependents
+             GROUP BY name
+             ORDER BY nearest, name",
+            latest = latest
+        ))?
+        .query_map(params![name, version, max_depth], |row| {
+            Ok(VersionDependent {
+                name: row.get(0)?,
+                version: row.get(1)?,
+                via: row.get(2)?,
+                requires: row.get(3)?,
+                depth: row.get(4)?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(Some(rows))
+}
+
+pub fn get_readme(conn: &Connection, name: &str, version: Option<&str>) -> Result<Option<(String, Option<String>)>> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT v.version, v.readme
+             FROM versions v
+             JOIN packages p ON p.id = v.package_id
+             WHERE p.name = ?1 AND (?2 IS NULL OR v.version = ?2)
+             ORDER BY {}
+             LIMIT 1",
+            LATEST_VERSION_ORDER
+        ),
+        params![name, version],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    ).optional()?)
+}
+
+pub fn get_unenriched_cids(conn: &Connection, limit: i64) -> Result<Vec<(String, i32)>> {
+    let rows = conn
+        .prepare(
+            "SELECT ipfs_hash, MAX(enrichment_attempts)
+             FROM versions
+             WHERE enriched_at IS NULL AND NOT enrichment_failed
+               AND (enrichment_retry_at IS NULL OR enrichment_retry_at <= ?1)
+             GROUP BY ipfs_hash
+             ORDER BY MIN(id)
+             LIMIT ?2",
+        )?
+        .query_map(params![now(), limit], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(rows)
+}
+
+pub fn store_version_enrichment(conn: &Connection, cid: &str, enrichment: &VersionEnrichment) -> Result<u64> {
+    let updated = conn.execute(
+        "UPDATE versions
+         SET description = ?2, license = ?3, readme = ?4, file_count = ?5, unpacked_size = ?6,
+             enriched_at = ?7, enrichment_retry_at = NULL, enrichment_error = NULL
+         WHERE ipfs_hash = ?1",
+        params![
+            cid,
+            enrichment.description,
+            enrichment.license,
+            enrichment.readme,
+            enrichment.file_count,
+            enrichment.unpacked_size,
+            now(),
+        ],
+    )?;
+    if let Some(description) = &enrichment.description {
+        conn.execute(
+            "UPDATE packages SET description = ?2
+             WHERE COALESCE(description, '') = ''
+               AND id IN (SELECT package_id FROM versions WHERE ipfs_hash = ?1)",
+            params![cid, description],
+        )?;
+    }
+    Ok(updated as u64)
+}
+
+pub fn record_enrichment_failure(conn: &Connection, cid: &str, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<()> {
+    conn.execute(
+        "UPDATE versions
+         SET enrichment_attempts = enrichment_attempts + 1, enrichment_error = ?2,
+             enrichment_retry_at = ?3, enrichment_failed = ?3 IS NULL
+         WHERE ipfs_hash = ?1 AND enriched_at IS NULL",
+        params![cid, error, retry_at.map(timestamp)],
+    )?;
+    Ok(())
+}
+
+pub fn set_package_maintainer(conn: &Connection, package_name: &str, authority: &str, maintainer: &str, listed: bool) -> Result<()> {
+    if listed {
+        conn.execute(
+            "INSERT INTO package_maintainers (package_name, authority, maintainer, added_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (package_name, authority, maintainer) DO NOTHING",
+            params![package_name, authority, maintainer, now()],
+        )?;
+    } else {
+        conn.execute(
+            "DELETE FROM package_maintainers WHERE package_name = ?1 AND authority = ?2 AND maintainer = ?3",
+            params![package_name, authority, maintainer],
+        )?;
+    }
+    Ok(())
+}
+
+pub fn get_package_id(conn: &Connection, name: &str) -> Result<Option<i32>> {
+    Ok(conn.query_row("SELECT id FROM packages WHERE name = ?1", params![name], |row| row.get(0)).optional()?)
+}
+
+pub fn get_version_id(conn: &Connection, package_id: i32, version: &str) -> Result<Option<i32>> {
+    Ok(conn.query_row(
+        "SELECT id FROM versions WHERE package_id = ?1 AND version = ?2",
+        params![package_id, version],
+        |row| row.get(0),
+    ).optional()?)
+}
+
+fn version_id_of(conn: &Connection, package_name: &str, version: &str) -> rusqlite::Result<Option<i32>> {
+    conn.query_row(
+        "SELECT v.id FROM versions v JOIN packages p ON p.id = v.package_id WHERE p.name = ?1 AND v.version = ?2",
+        params![package_name, version],
+        |row| row.get(0),
+    ).optional()
+}
+
+fn now() -> String {
+    timestamp(Utc::now())
+}
+
+/// A chain `block_time` as a timestamp column
+fn chain_time(seconds: i64) -> String {
+    timestamp(DateTime::from_timestamp(seconds, 0).unwrap_or_default())
+}
+
+fn conversion_error(index: usize, e: impl std::error::Error + Send + Sync + 'static) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e))
+}
+
+fn time(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    opt_time(row, index)?.ok_or_else(|| rusqlite::Error::InvalidColumnType(index, "timestamp".to_string(), Type::Null))
+}
+
+fn opt_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    let Some(text) = row.get::<_, Option<String>>(index)? else {
+        return Ok(None);
+    };
+    DateTime::parse_from_rfc3339(&text).map(|at| Some(at.with_timezone(&Utc))).map_err(|e| conversion_error(index, e))
+}
+
+fn opt_list(row: &Row, index: usize) -> rusqlite::Result<Option<Vec<String>>> {
+    let Some(text) = row.get::<_, Option<String>>(index)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&text).map(Some).map_err(|e| conversion_error(index, e))
+}
+
+fn row_to_package(row: &Row) -> rusqlite::Result<Package> {
+    Ok(Package {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        author: row.get(2)?,
+        description: row.get(3)?,
+        repository: row.get(4)?,
+        homepage: row.get(5)?,
+        keywords: opt_list(row, 9)?.unwrap_or_default(),
+        total_downloads: row.get(6)?,
+        created_at: time(row, 7)?,
+        updated_at: time(row, 8)?,
+    })
+}
+
+fn row_to_version(row: &Row) -> rusqlite::Result<Version> {
+    Ok(Version {
+        id: row.get(0)?,
+        package_id: row.get(1)?,
+        version: row.get(2)?,
+        ipfs_hash: row.get(3)?,
+        downloads: row.get(4)?,
+        published_at: time(row, 5)?,
+        authority: row.get(6)?,
+        readme_cid: row.get(7)?,
+        frozen: row.get(8)?,
+        license: row.get(9)?,
+        file_count: row.get(10)?,
+        unpacked_size: row.get(11)?,
+    })
+}
+
+/// Version columns in [`row_to_version`]'s order, then the package's
+const VERSION_DETAIL_SELECT: &str =
+    "SELECT v.id, v.package_id, v.version, v.ipfs_hash, v.downloads, v.published_at, v.authority, v.readme_cid, v.frozen,
+            v.license, v.file_count, v.unpacked_size,
+            p.name, p.author, p.description, p.repository, p.homepage
+     FROM versions v
+     JOIN packages p ON p.id = v.package_id";
+
+fn row_to_version_detail(row: &Row) -> rusqlite::Result<VersionDetail> {
+    Ok(VersionDetail {
+        version: row_to_version(row)?,
+        name: row.get(12)?,
+        author: row.get(13)?,
+        description: row.get(14)?,
+        repository: row.get(15)?,
+        homepage: row.get(16)?,
+    })
+}
+
+/// Columns [`row_to_event`] reads, in order, for an `events` table aliased `e`
+const EVENT_COLUMNS: &str =
+    "e.id, e.event_type, e.package_name, e.version, e.transaction_signature, e.slot, e.block_time, e.finality, e.ipfs_hash, e.authority, e.version_id, e.log_index";
+
+fn row_to_event(row: &Row) -> rusqlite::Result<Event> {
+    Ok(Event {
+        id: row.get(0)?,
+        event_type: row.get(1)?,
+        package_name: row.get(2)?,
+        version: row.get(3)?,
+        transaction_signature: row.get(4)?,
+        slot: row.get(5)?,
+        block_time: opt_time(row, 6)?,
+        finality: EventFinality::from_db(&row.get::<_, String>(7)?),
+        ipfs_hash: row.get(8)?,
+        authority: row.get(9)?,
+        version_id: row.get(10)?,
+        log_index: row.get(11)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn semver_columns_follow_migration_017() {
+        assert_eq!(semver_columns("1.10.0"), (Some(1), Some(10), Some(0), None));
+        assert_eq!(semver_columns("1.0.0+build.5"), (Some(1), Some(0), Some(0), None));
+        assert_eq!(
+            semver_columns("2.0.0-beta.11"),
+            (Some(2), Some(0), Some(0), Some("1beta 00211".to_string()))
+        );
+        assert!(semver_columns("2.0.0-beta.2").3 < semver_columns("2.0.0-beta.11").3);
+        assert!(semver_columns("2.0.0-alpha").3 < semver_columns("2.0.0-alpha.1").3);
+        for invalid in ["1.0", "1.0.0.0", "v1.0.0", "1.0.0-", "1.0.0-a_b", "1.x.0"] {
+            assert_eq!(semver_columns(invalid), (None, None, None, None), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn search_words_split_exclusions_and_quotes() {
+        assert_eq!(search_words(" \"math utils\" -slow parsed "), vec![("math", false), ("utils", false), ("slow", true), ("parsed", false)]);
+        assert_eq!(stem("parsed"), "pars");
+        assert_eq!(stem("parse"), "pars");
+        assert_eq!(stem("utils"), "util");
+        assert_eq!(stem("dns"), "dns");
+        assert_eq!(escape_like("100%_a"), "100\\%\\_a");
+    }
+}
@@ -0,0 +1,349 @@
+use metrics::counter;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::{Pubkey, MAX_SEED_LEN};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+use crate::telemetry::RPC_ERRORS;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Anchor discriminator of `Package` accounts: `sha256("account:Package")[..8]`
+const PACKAGE_DISCRIMINATOR: [u8; 8] = [203, 12, 246, 255, 184, 43, 206, 39];
+
+/// Fetches before giving up on an account that isn't visible yet
+const FETCH_ATTEMPTS: u32 = 4;
+
+/// First delay between fetches; doubles per attempt
+const FETCH_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// The fields of an on-chain `Package` account the indexer stores.
+/// Log lines carry only what fits; the rest is read from here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageAccount {
+    pub name: String,
+    pub version: String,
+    pub authority: String,
+    pub ipfs_cid: String,
+    pub published_at: i64,
+    pub description: String,
+    /// `(name, version)` of each registry dependency
+    pub dependencies: Vec<(String, String)>,
+    /// Empty when not given, or in accounts created before it existed
+    pub repository: String,
+    pub homepage: String,
+    /// Downloads counted on-chain by `record_download`; 0 in accounts that
+    /// end before the counter
+    pub downloads: u64,
+}
+
+/// Address of the `Package` account holding `name@version`
+pub fn package_address(program_id: &Pubkey, name: &str, version: &str) -> Pubkey {
+    Pubkey::find_program_address(&[b"package", name.as_bytes(), version.as_bytes()], program_id).0
+}
+
+/// Borsh reader over account data; every read fails past the end
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn option_string(&mut self) -> Option<Option<String>> {
+        match self.take(1)?[0] {
+            0 => Some(None),
+            1 => Some(Some(self.string()?)),
+            _ => None,
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn pubkey(&mut self) -> Option<Pubkey> {
+        Some(Pubkey::new_from_array(self.take(32)?.try_into().ok()?))
+    }
+}
+
+/// Decode a `Package` account, `None` when `data` isn't one. Fields the
+/// program appended later are read as empty when an account ends before them.
+pub fn decode_package(data: &[u8]) -> Option<PackageAccount> {
+    let mut reader = Reader(data);
+    if reader.take(8)? != PACKAGE_DISCRIMINATOR {
+        return None;
+    }
+    let name = reader.string()?;
+    let version = reader.string()?;
+    let authority = reader.pubkey()?.to_string();
+    let ipfs_cid = reader.string()?;
+    let published_at = reader.i64()?;
+    let description = reader.string()?;
+    let count = reader.u32()?;
+    let dependencies = (0..count)
+        .map(|_| Some((reader.string()?, reader.string()?)))
+        .collect::<Option<Vec<_>>>()?;
+    // bump and content_sha256
+    reader.take(1 + 32)?;
+    let repository = reader.string().unwrap_or_default();
+    let homepage = reader.string().unwrap_or_default();
+    let downloads = skip_to_downloads(&mut reader).and_then(|()| reader.u64()).unwrap_or_default();
+    Some(PackageAccount { name, version, authority, ipfs_cid, published_at, description, dependencies, repository, homepage, downloads })
+}
+
+/// Skip keywords, the README CID and external dependencies, which sit
+/// between the homepage and the download counter
+fn skip_to_downloads(reader: &mut Reader) -> Option<()> {
+    for _ in 0..reader.u32()? {
+        reader.string()?;
+    }
+    reader.option_string()?;
+    for _ in 0..reader.u32()? {
+        // name, version, type, registry
+        reader.string()?;
+        reader.string()?;
+        reader.string()?;
+        reader.option_string()?;
+    }
+    Some(())
+}
+
+/// Where account data comes from; an RPC node, or a stub in tests
+pub trait AccountFetcher: Send + Sync {
+    /// Data of the account at `address`, `None` when it doesn't exist
+    fn fetch<'a>(&'a self, address: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, BoxError>> + Send + 'a>>;
+}
+
+/// Reads at the client's commitment, which should match the listener's
+impl AccountFetcher for RpcClient {
+    fn fetch<'a>(&'a self, address: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, BoxError>> + Send + 'a>> {
+        Box::pin(async move {
+            let response = self.get_account_with_commitment(address, self.commitment()).await?;
+            Ok(response.value.map(|account| account.data))
+        })
+    }
+}
+
+/// Why [`PackageAccounts::read`] has no package to return
+#[derive(Debug, thiserror::Error)]
+pub enum AccountError {
+    #[error("No package account exists for {0}")]
+    NotFound(String),
+    #[error("Account {0} is not a decodable package")]
+    Undecodable(Pubkey),
+    #[error("Failed to fetch account {0}: {1}")]
+    Rpc(Pubkey, BoxError),
+}
+
+/// Looks up the `Package` account behind a publish or update
+#[derive(Clone)]
+pub struct PackageAccounts {
+    fetcher: Arc<dyn AccountFetcher>,
+    program_id: Pubkey,
+    retry_delay: Duration,
+}
+
+impl std::fmt::Debug for PackageAccounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PackageAccounts").field("program_id", &self.program_id).finish_non_exhaustive()
+    }
+}
+
+impl PackageAccounts {
+    pub fn new(fetcher: Arc<dyn AccountFetcher>, program_id: Pubkey) -> Self {
+        Self { fetcher, program_id, retry_delay: FETCH_RETRY_DELAY }
+    }
+
+    /// Wait `delay` before the first retry instead of the default
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// The decoded account of `name@version`. A node behind the one that
+    /// served the transaction may not have the account yet, so a missing
+    /// account or an RPC error is retried with backoff; `None` once that
+    /// runs out, or when the data doesn't decode.
+    pub async fn fetch(&self, name: &str, version: &str) -> Option<PackageAccount> {
+        let address = package_address(&self.program_id, name, version);
+        let mut delay = self.retry_delay;
+        for attempt in 1..=FETCH_ATTEMPTS {
+            match self.fetcher.fetch(&address).await {
+                Ok(Some(data)) => {
+                    let account = decode_package(&data);
+                    if account.is_none() {
+                        tracing::warn!("Account {} of {}@{} is not a decodable package", address, name, version);
+                    }
+                    return account;
+                }
+                Ok(None) => tracing::debug!("Account {} of {}@{} not visible yet (attempt {})", address, name, version, attempt),
+                Err(e) => {
+                    counter!(RPC_ERRORS, "operation" => "get_account").increment(1);
+                    tracing::debug!("Failed to fetch account {} of {}@{} (attempt {}): {}", address, name, version, attempt, e);
+                }
+            }
+            if attempt < FETCH_ATTEMPTS {
+                sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        tracing::warn!("Gave up fetching account {} of {}@{}; storing what the logs carry", address, name, version);
+        None
+    }
+
+    /// The decoded account of `name@version`, fetched once. For re-syncing
+    /// a version after the fact, when the account should long be visible.
+    pub async fn read(&self, name: &str, version: &str) -> Result<PackageAccount, AccountError> {
+        // Longer seeds have no address, so the program can't have created one
+        if name.len() > MAX_SEED_LEN || version.len() > MAX_SEED_LEN {
+            return Err(AccountError::NotFound(format!("{}@{}", name, version)));
+        }
+        let address = package_address(&self.program_id, name, version);
+        match self.fetcher.fetch(&address).await {
+            Ok(Some(data)) => decode_package(&data).ok_or(AccountError::Undecodable(address)),
+            Ok(None) => Err(AccountError::NotFound(format!("{}@{}", name, version))),
+            Err(e) => {
+                counter!(RPC_ERRORS, "operation" => "get_account").increment(1);
+                Err(AccountError::Rpc(address, e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Serializes like the program did before `repository` was appended
+    fn package_data(description: &str, dependencies: &[(&str, &str)]) -> Vec<u8> {
+        fn string(out: &mut Vec<u8>, value: &str) {
+            out.extend((value.len() as u32).to_le_bytes());
+            out.extend(value.as_bytes());
+        }
+        let mut data = PACKAGE_DISCRIMINATOR.to_vec();
+        string(&mut data, "foo");
+        string(&mut data, "1.0.0");
+        data.extend([7u8; 32]);
+        string(&mut data, "bafkreigxsby7rxtodbuj3tehs2g7ztede2raoqsqjiz74s647i7wg3l7wu");
+        data.extend(1_700_000_000i64.to_le_bytes());
+        string(&mut data, description);
+        data.extend((dependencies.len() as u32).to_le_bytes());
+        for (name, version) in dependencies {
+            string(&mut data, name);
+            string(&mut data, version);
+        }
+        data.push(254);
+        data.extend([0u8; 32]);
+        data
+    }
+
+    #[test]
+    fn decodes_package_accounts_with_and_without_appended_fields() {
+        let mut data = package_data("A foo", &[("bar", "2.0.0")]);
+        let old = decode_package(&data).unwrap();
+        assert_eq!(old.description, "A foo");
+        assert_eq!(old.authority, Pubkey::new_from_array([7; 32]).to_string());
+        assert_eq!(old.published_at, 1_700_000_000);
+        assert_eq!(old.dependencies, vec![("bar".to_string(), "2.0.0".to_string())]);
+        assert_eq!(old.repository, "");
+
+        data.extend(20u32.to_le_bytes());
+        data.extend(b"https://github.com/x");
+        assert_eq!(decode_package(&data).unwrap().repository, "https://github.com/x");
+
+        assert_eq!(decode_package(&data).unwrap().downloads, 0);
+
+        // homepage, keywords, README CID, external dependencies, then the counter
+        data.extend(0u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data.extend(4u32.to_le_bytes());
+        data.extend(b"defi");
+        data.push(0);
+        data.extend(1u32.to_le_bytes());
+        for field in ["serde", "^1.0", "rust"] {
+            data.extend((field.len() as u32).to_le_bytes());
+            data.extend(field.as_bytes());
+        }
+        data.push(0);
+        assert_eq!(decode_package(&data).unwrap().downloads, 0);
+        data.extend(42u64.to_le_bytes());
+        assert_eq!(decode_package(&data).unwrap().downloads, 42);
+
+        data[0] ^= 1;
+        assert_eq!(decode_package(&data), None);
+        assert_eq!(decode_package(&package_data("A foo", &[])[..60]), None);
+    }
+
+    /// Answers from `responses` in order, then with the last one
+    struct Stub {
+        responses: Mutex<Vec<Option<Vec<u8>>>>,
+        calls: Mutex<Vec<Pubkey>>,
+    }
+
+    impl AccountFetcher for Stub {
+        fn fetch<'a>(&'a self, address: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, BoxError>> + Send + 'a>> {
+            self.calls.lock().unwrap().push(*address);
+            let mut responses = self.responses.lock().unwrap();
+            let response = if responses.len() > 1 { responses.remove(0) } else { responses[0].clone() };
+            Box::pin(async move { Ok(response) })
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_account_is_visible() {
+        let program_id = Pubkey::new_unique();
+        let stub = Arc::new(Stub {
+            responses: Mutex::new(vec![None, None, Some(package_data("A foo", &[]))]),
+            calls: Mutex::new(Vec::new()),
+        });
+        let accounts = PackageAccounts::new(stub.clone(), program_id).with_retry_delay(Duration::ZERO);
+
+        assert_eq!(accounts.fetch("foo", "1.0.0").await.unwrap().description, "A foo");
+        let calls = stub.calls.lock().unwrap().clone();
+        assert_eq!(calls, vec![package_address(&program_id, "foo", "1.0.0"); 3]);
+
+        *stub.responses.lock().unwrap() = vec![None];
+        assert_eq!(accounts.fetch("foo", "1.0.0").await, None);
+        assert_eq!(stub.calls.lock().unwrap().len(), 3 + FETCH_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn read_fetches_once() {
+        let stub = Arc::new(Stub { responses: Mutex::new(vec![None]), calls: Mutex::new(Vec::new()) });
+        let accounts = PackageAccounts::new(stub.clone(), Pubkey::new_unique());
+
+        assert!(matches!(accounts.read("foo", "1.0.0").await, Err(AccountError::NotFound(_))));
+        assert_eq!(stub.calls.lock().unwrap().len(), 1);
+        // No address exists for seeds past 32 bytes, so nothing is asked
+        assert!(matches!(accounts.read(&"x".repeat(33), "1.0.0").await, Err(AccountError::NotFound(_))));
+        assert_eq!(stub.calls.lock().unwrap().len(), 1);
+
+        *stub.responses.lock().unwrap() = vec![Some(vec![0; 16])];
+        assert!(matches!(accounts.read("foo", "1.0.0").await, Err(AccountError::Undecodable(_))));
+        *stub.responses.lock().unwrap() = vec![Some(package_data("A foo", &[]))];
+        assert_eq!(accounts.read("foo", "1.0.0").await.unwrap().description, "A foo");
+    }
+}
@@ -0,0 +1,239 @@
+use crate::db::Pool;
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::accounts::PackageAccounts;
+use super::broadcast::EventBus;
+use super::listener::{collect_new_signatures, ingest_transaction, transaction_config, SIGNATURE_PAGE_LIMIT};
+
+/// Transactions fetched at once when `--concurrency` is not given
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Log progress every this many transactions
+const PROGRESS_EVERY: usize = 100;
+
+/// A one-shot catch-up over a slot range, from `antsol-indexer-v2 backfill`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillArgs {
+    pub from_slot: u64,
+    /// Last slot to include; the newest signature when unset
+    pub to_slot: Option<u64>,
+    pub concurrency: usize,
+}
+
+impl BackfillArgs {
+    /// Parse `backfill --from-slot X [--to-slot Y] [--concurrency N]` from the
+    /// process arguments (without the binary name). `Ok(None)` when the first
+    /// argument is not `backfill`, so the server starts as usual.
+    pub fn parse(args: &[String]) -> Result<Option<Self>, String> {
+        let Some((command, flags)) = args.split_first() else {
+            return Ok(None);
+        };
+        if command != "backfill" {
+            return Ok(None);
+        }
+
+        let mut from_slot = None;
+        let mut to_slot = None;
+        let mut concurrency = DEFAULT_CONCURRENCY;
+        let mut flags = flags.iter();
+        while let Some(flag) = flags.next() {
+            let value = flags.next().ok_or_else(|| format!("{} needs a value", flag))?;
+            let number = || value.parse::<u64>().map_err(|_| format!("{} expects a number, got '{}'", flag, value));
+            match flag.as_str() {
+                "--from-slot" => from_slot = Some(number()?),
+                "--to-slot" => to_slot = Some(number()?),
+                "--concurrency" => concurrency = number()?.max(1) as usize,
+                other => return Err(format!("Unknown backfill option '{}'", other)),
+            }
+        }
+
+        let from_slot = from_slot.ok_or("backfill needs --from-slot")?;
+        if to_slot.is_some_and(|to| to < from_slot) {
+            return Err("--to-slot must not be below --from-slot".to_string());
+        }
+        Ok(Some(Self { from_slot, to_slot, concurrency }))
+    }
+}
+
+/// What a backfill did
+#[derive(Debug, Default)]
+pub struct BackfillReport {
+    pub transactions: usize,
+    pub events: usize,
+    pub failed: usize,
+    /// Newest slot of the contiguous range that completed, if any
+    pub completed_through: Option<u64>,
+}
+
+/// Ingest every program transaction in the requested slot range.
+///
+/// Signatures are paged with `getSignaturesForAddress` and transactions are
+/// fetched `concurrency` at a time. Re-running a range is safe: events are
+/// deduplicated by signature. `indexer_state` only moves forward, and only
+/// to the end of the oldest-first run of transactions that all succeeded.
+pub async fn run_backfill(
+    pool: &Pool,
+    rpc_url: &str,
+    program_id: &str,
+    commitment: CommitmentConfig,
+    args: &BackfillArgs,
+) -> Result<BackfillReport, anyhow::Error> {
+    let program_id = Pubkey::from_str(program_id)?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.to_string(), commitment);
+
+    let mut signatures = collect_new_signatures(|before| {
+        rpc_client.get_signatures_for_address_with_config(
+            &program_id,
+            GetConfirmedSignaturesForAddress2Config {
+                before,
+                until: None,
+                limit: Some(SIGNATURE_PAGE_LIMIT),
+                commitment: Some(commitment),
+            },
+        ).map_err(anyhow::Error::from)
+    }, Some(args.from_slot))?;
+    signatures.retain(|status| args.to_slot.map_or(true, |to| status.slot <= to));
+
+    let total = signatures.len();
+    tracing::info!(
+        "Backfilling {} program transactions from slot {} to {}",
+        total,
+        args.from_slot,
+        args.to_slot.map_or("the latest slot".to_string(), |to| to.to_string())
+    );
+
+    // Nothing subscribes in a one-shot run; the bus only satisfies ingestion
+    let events = EventBus::new();
+    let async_client = Arc::new(AsyncRpcClient::new_with_commitment(rpc_url.to_string(), commitment));
+    let accounts = PackageAccounts::new(async_client.clone(), program_id);
+    let permits = Arc::new(Semaphore::new(args.concurrency));
+    let mut tasks = JoinSet::new();
+    for (index, status) in signatures.iter().enumerate() {
+        // Failed transactions emit no events; count them as done
+        if status.err.is_some() {
+            continue;
+        }
+        let (client, permits, pool, events) = (async_client.clone(), permits.clone(), pool.clone(), events.clone());
+        let accounts = accounts.clone();
+        let signature = status.signature.clone();
+        tasks.spawn(async move {
+            let result = async {
+                let _permit = permits.acquire_owned().await?;
+                let tx = client.get_transaction_with_config(&Signature::from_str(&signature)?, transaction_config(commitment)).await?;
+                ingest_transaction(&pool, &events, Some(&accounts), &signature, tx, &program_id).await
+            }.await;
+            (index, result)
+        });
+    }
+
+    let mut succeeded = vec![true; total];
+    let mut report = BackfillReport { transactions: total, ..Default::default() };
+    let mut done = total - tasks.len();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((_, Ok(events))) => report.events += events,
+            Ok((index, Err(e))) => {
+                tracing::warn!("Backfill failed for transaction {}: {}", signatures[index].signature, e);
+                succeeded[index] = false;
+                report.failed += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        done += 1;
+        if done % PROGRESS_EVERY == 0 {
+            tracing::info!("Backfill progress: {}/{} transactions, {} events", done, total, report.events);
+        }
+    }
+
+    let contiguous = contiguous_prefix(&succeeded);
+    if let Some(last) = contiguous.checked_sub(1).map(|i| &signatures[i]) {
+        report.completed_through = Some(last.slot);
+        advance_cursor(pool, args.from_slot, &last.signature, last.slot, last.block_time).await?;
+    }
+    Ok(report)
+}
+
+/// Length of the leading run of successes: the range that is safe to mark done
+fn contiguous_prefix(succeeded: &[bool]) -> usize {
+    succeeded.iter().take_while(|ok| **ok).count()
+}
+
+/// Move the live listener's cursor to `signature` when the backfilled range
+/// (starting at `from_slot`) joins up with the stored state and extends it
+async fn advance_cursor(
+    pool: &Pool,
+    from_slot: u64,
+    signature: &str,
+    slot: u64,
+    block_time: Option<i64>,
+) -> Result<(), anyhow::Error> {
+    let current = crate::db::queries::get_last_processed_slot(pool).await.map_err(|e| anyhow::anyhow!(e))?;
+    if !extends_state(current, from_slot, slot) {
+        tracing::info!("Indexer state (slot {}) is ahead of or not contiguous with the backfill; leaving it unchanged", current);
+        return Ok(());
+    }
+    crate::db::queries::update_last_signature(pool, signature, slot, block_time)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    tracing::info!("Indexer state advanced to slot {} ({})", slot, signature);
+    Ok(())
+}
+
+/// Whether a completed `from_slot..=to_slot` range may become the new state.
+/// A state of 0 means nothing was indexed yet.
+fn extends_state(current: u64, from_slot: u64, to_slot: u64) -> bool {
+    to_slot > current && (current == 0 || from_slot <= current + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_backfill_arguments() {
+        assert_eq!(BackfillArgs::parse(&[]), Ok(None));
+        assert_eq!(BackfillArgs::parse(&args("serve")), Ok(None));
+        assert_eq!(
+            BackfillArgs::parse(&args("backfill --from-slot 100 --to-slot 250 --concurrency 4")),
+            Ok(Some(BackfillArgs { from_slot: 100, to_slot: Some(250), concurrency: 4 }))
+        );
+        assert_eq!(
+            BackfillArgs::parse(&args("backfill --from-slot 100")),
+            Ok(Some(BackfillArgs { from_slot: 100, to_slot: None, concurrency: DEFAULT_CONCURRENCY }))
+        );
+        assert!(BackfillArgs::parse(&args("backfill")).is_err());
+        assert!(BackfillArgs::parse(&args("backfill --from-slot ten")).is_err());
+        assert!(BackfillArgs::parse(&args("backfill --from-slot 10 --to-slot 5")).is_err());
+        assert!(BackfillArgs::parse(&args("backfill --from-slot 10 --to-slot")).is_err());
+        assert!(BackfillArgs::parse(&args("backfill --from 10")).is_err());
+    }
+
+    #[test]
+    fn only_the_leading_successful_run_counts_as_complete() {
+        assert_eq!(contiguous_prefix(&[true, true, false, true]), 2);
+        assert_eq!(contiguous_prefix(&[false, true]), 0);
+        assert_eq!(contiguous_prefix(&[true, true]), 2);
+        assert_eq!(contiguous_prefix(&[]), 0);
+    }
+
+    #[test]
+    fn state_advances_only_over_contiguous_ranges() {
+        assert!(extends_state(0, 500, 900));
+        assert!(extends_state(499, 500, 900));
+        assert!(extends_state(700, 500, 900));
+        // A gap between the stored state and the range would be skipped for good
+        assert!(!extends_state(400, 500, 900));
+        // Never move the live cursor backwards
+        assert!(!extends_state(1000, 500, 900));
+    }
+}
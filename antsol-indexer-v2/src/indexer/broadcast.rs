@@ -0,0 +1,67 @@
+use tokio::sync::broadcast;
+
+use crate::db::models::Event;
+
+/// Events buffered per subscriber; a subscriber further behind misses events
+const CAPACITY: usize = 256;
+
+/// Fan-out of newly stored events to live subscribers such as
+/// `/api/events/stream`. Cloning shares the channel.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+
+    /// Announce a newly stored event; a no-op when nobody is listening
+    pub fn publish(&self, event: Event) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::EventFinality;
+
+    fn event(id: i32) -> Event {
+        Event {
+            id,
+            event_type: "PackagePublished".to_string(),
+            package_name: "pkg".to_string(),
+            version: Some("1.0.0".to_string()),
+            transaction_signature: format!("sig{}", id),
+            log_index: 0,
+            slot: 1,
+            block_time: None,
+            finality: EventFinality::Confirmed,
+            ipfs_hash: None,
+            authority: None,
+            version_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_events_published_after_they_joined() {
+        let bus = EventBus::new();
+        bus.publish(event(1));
+        let mut receiver = bus.clone().subscribe();
+        bus.publish(event(2));
+        assert_eq!(receiver.recv().await.unwrap().id, 2);
+    }
+}
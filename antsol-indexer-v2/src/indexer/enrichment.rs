@@ -0,0 +1,424 @@
+use crate::config::EnrichmentConfig;
+use crate::db::models::VersionEnrichment;
+use crate::db::{queries, Pool};
+use crate::ipfs::GatewayOutcome;
+use crate::telemetry::ENRICHMENT_FETCHES;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use metrics::counter;
+use serde::Deserialize;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tar::{Archive, EntryType};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// CIDs picked up per pass
+const BATCH: i64 = 64;
+
+/// Wait after a pass that found nothing due
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// To connect to the gateway
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// For one whole tarball download
+const FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Largest tarball downloaded, the CLI's default `max_package_size`
+pub const MAX_ARCHIVE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Sum of the file sizes a tarball may expand to, the CLI's default `max_extracted_size`
+pub const MAX_UNPACKED_SIZE: u64 = 500 * 1024 * 1024;
+
+/// Largest single file, the CLI's default `max_extracted_file_size`
+pub const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Files, directories and links together, like the CLI's extraction
+pub const MAX_ENTRIES: usize = 10_000;
+
+/// README text kept per version; longer READMEs are cut here
+pub const MAX_README_SIZE: usize = 256 * 1024;
+
+/// An antsol.toml larger than this isn't parsed
+const MAX_MANIFEST_SIZE: u64 = 64 * 1024;
+
+/// Failed attempts at a CID before it is given up on
+pub const MAX_ATTEMPTS: i32 = 8;
+
+/// Wait before the first retry; it doubles with every failure after that
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Longest wait between retries
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(6 * 3600);
+
+/// Why a tarball couldn't be read
+#[derive(Debug, thiserror::Error)]
+pub enum EnrichmentError {
+    /// The gateway failed or timed out; worth another try
+    #[error("{0}")]
+    Transient(String),
+    /// The tarball itself is unusable; trying again won't help
+    #[error("{0}")]
+    Permanent(String),
+}
+
+/// Read antsol.toml and README.md out of the tarball of every version that
+/// lacks them, `config.concurrency` tarballs at a time, forever.
+///
+/// Work is tracked per CID, so versions sharing a tarball are read once.
+/// A failed CID is retried with exponential backoff and given up on after
+/// [`MAX_ATTEMPTS`], or straight away when the tarball is unusable.
+pub async fn run_enrichment(pool: Pool, config: EnrichmentConfig) {
+    let client = match reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT).timeout(FETCH_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Enrichment disabled: cannot build an HTTP client: {}", e);
+            return;
+        }
+    };
+    let gateway: Arc<str> = config.gateway.into();
+    let permits = Arc::new(Semaphore::new(config.concurrency));
+
+    loop {
+        let due = match queries::get_unenriched_cids(&pool, BATCH).await {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::warn!("Could not list versions to enrich: {}", e);
+                Vec::new()
+            }
+        };
+        if due.is_empty() {
+            tokio::time::sleep(IDLE_INTERVAL).await;
+            continue;
+        }
+
+        let mut tasks = JoinSet::new();
+        for (cid, attempts) in due {
+            let permit = permits.clone().acquire_owned().await.expect("the semaphore is never closed");
+            let (pool, client, gateway) = (pool.clone(), client.clone(), gateway.clone());
+            tasks.spawn(async move {
+                enrich(&pool, &client, &gateway, &cid, attempts).await;
+                drop(permit);
+            });
+        }
+        while tasks.join_next().await.is_some() {}
+    }
+}
+
+/// Fetch and read one tarball, then store the result or the failure
+async fn enrich(pool: &Pool, client: &reqwest::Client, gateway: &str, cid: &str, attempts: i32) {
+    let result = match fetch_archive(client, gateway, cid).await {
+        Ok(archive) => tokio::task::spawn_blocking(move || inspect_archive(&archive))
+            .await
+            .unwrap_or_else(|e| Err(EnrichmentError::Permanent(format!("Reading the tarball panicked: {}", e)))),
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(enrichment) => match queries::store_version_enrichment(pool, cid, &enrichment).await {
+            Ok(versions) => tracing::info!(
+                "Enriched {} version(s) from {}: {} files, README {}",
+                versions,
+                cid,
+                enrichment.file_count,
+                if enrichment.readme.is_some() { "found" } else { "missing" }
+            ),
+            Err(e) => tracing::warn!("Could not store the enrichment of {}: {}", cid, e),
+        },
+        Err(error) => {
+            let retry_at = retry_at(attempts, &error, Utc::now());
+            match retry_at {
+                Some(at) => tracing::warn!("Could not read tarball {}: {}; retrying after {}", cid, error, at),
+                None => tracing::warn!("Giving up on tarball {}: {}", cid, error),
+            }
+            if let Err(e) = queries::record_enrichment_failure(pool, cid, &error.to_string(), retry_at).await {
+                tracing::warn!("Could not record the enrichment failure of {}: {}", cid, e);
+            }
+        }
+    }
+}
+
+/// Download `<gateway>/ipfs/<cid>`, refusing anything over [`MAX_ARCHIVE_SIZE`]
+async fn fetch_archive(client: &reqwest::Client, gateway: &str, cid: &str) -> Result<Vec<u8>, EnrichmentError> {
+    let url = format!("{}/ipfs/{}", gateway, cid);
+    let failed = |e: reqwest::Error| {
+        record_fetch(if e.is_timeout() { GatewayOutcome::Timeout } else { GatewayOutcome::ConnectError });
+        EnrichmentError::Transient(format!("{}: {}", url, e))
+    };
+
+    let mut response = client.get(&url).send().await.map_err(failed)?;
+    let status = response.status();
+    if !status.is_success() {
+        record_fetch(GatewayOutcome::HttpError);
+        let message = format!("{} answered {}", url, status);
+        // A gateway that can't find the CID yet, or is busy, may serve it later
+        return Err(match status.as_u16() {
+            404 | 408 | 429 | 500..=599 => EnrichmentError::Transient(message),
+            _ => EnrichmentError::Permanent(message),
+        });
+    }
+    if response.content_length().is_some_and(|length| length > MAX_ARCHIVE_SIZE) {
+        record_fetch(GatewayOutcome::VerificationFailed);
+        return Err(too_large(&url));
+    }
+
+    let mut archive = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(failed)? {
+        if (archive.len() + chunk.len()) as u64 > MAX_ARCHIVE_SIZE {
+            record_fetch(GatewayOutcome::VerificationFailed);
+            return Err(too_large(&url));
+        }
+        archive.extend_from_slice(&chunk);
+    }
+    record_fetch(GatewayOutcome::Success);
+    Ok(archive)
+}
+
+fn record_fetch(outcome: GatewayOutcome) {
+    counter!(ENRICHMENT_FETCHES, "outcome" => outcome.as_str()).increment(1);
+}
+
+fn too_large(url: &str) -> EnrichmentError {
+    EnrichmentError::Permanent(format!("{} is larger than {} bytes", url, MAX_ARCHIVE_SIZE))
+}
+
+/// When to try a CID again after a failure, given the attempts it had
+/// before; `None` gives up on it
+pub fn retry_at(attempts: i32, error: &EnrichmentError, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match error {
+        EnrichmentError::Permanent(_) => None,
+        EnrichmentError::Transient(_) if attempts + 1 >= MAX_ATTEMPTS => None,
+        EnrichmentError::Transient(_) => Some(now + retry_delay(attempts)),
+    }
+}
+
+/// Backoff after the failure of attempt `attempts` (counting from 0)
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let delay = FIRST_RETRY_DELAY.saturating_mul(1 << attempts.clamp(0, 16) as u32).min(MAX_RETRY_DELAY);
+    chrono::Duration::seconds(delay.as_secs() as i64)
+}
+
+/// Read a package tarball in memory, by the rules the CLI extracts it with:
+/// absolute paths, `..` escapes, hard links, device files and symlinks
+/// pointing out of the package reject it, as do more than [`MAX_ENTRIES`]
+/// entries or files past [`MAX_FILE_SIZE`] or [`MAX_UNPACKED_SIZE`].
+/// Nothing is written to disk. Only regular files are read, so a README
+/// that is a link is ignored.
+pub fn inspect_archive(archive: &[u8]) -> Result<VersionEnrichment, EnrichmentError> {
+    let mut archive = Archive::new(GzDecoder::new(archive));
+    let mut enrichment = VersionEnrichment::default();
+    let mut manifest = None;
+    let mut entries = 0usize;
+    let mut unpacked_size = 0u64;
+
+    for entry in archive.entries().map_err(unreadable)? {
+        let mut entry = entry.map_err(unreadable)?;
+        let entry_type = entry.header().entry_type();
+        if matches!(entry_type, EntryType::XGlobalHeader | EntryType::XHeader | EntryType::GNULongName | EntryType::GNULongLink) {
+            // Metadata for the next entry, not an entry of its own
+            continue;
+        }
+
+        entries += 1;
+        if entries > MAX_ENTRIES {
+            return Err(rejected(format!("it has more than {} entries", MAX_ENTRIES)));
+        }
+        let path = entry.path().map_err(unreadable)?.into_owned();
+        let relative = contained_path(&path)?;
+
+        match entry_type {
+            EntryType::Directory => {}
+            EntryType::Regular | EntryType::Continuous => {
+                let size = entry.size();
+                if size > MAX_FILE_SIZE {
+                    return Err(rejected(format!("{} is over the {} byte per-file limit", path.display(), MAX_FILE_SIZE)));
+                }
+                unpacked_size += size;
+                if unpacked_size > MAX_UNPACKED_SIZE {
+                    return Err(rejected(format!("it would extract to more than {} bytes", MAX_UNPACKED_SIZE)));
+                }
+                enrichment.file_count += 1;
+
+                if relative == Path::new("antsol.toml") && size <= MAX_MANIFEST_SIZE {
+                    let mut text = String::new();
+                    // A manifest that isn't UTF-8 is treated as missing
+                    if entry.read_to_string(&mut text).is_ok() {
+                        manifest = Some(text);
+                    }
+                } else if relative.to_str().is_some_and(|name| name.eq_ignore_ascii_case("README.md")) {
+                    enrichment.readme = Some(read_readme(&mut entry).map_err(unreadable)?);
+                }
+            }
+            EntryType::Symlink => {
+                let target = entry
+                    .link_name()
+                    .map_err(unreadable)?
+                    .ok_or_else(|| rejected(format!("symlink {} has no target", path.display())))?;
+                let resolved = relative.parent().unwrap_or(Path::new("")).join(&target);
+                if target.is_absolute() || contained_path(&resolved).is_err() {
+                    return Err(rejected(format!("symlink {} points outside the package ({})", path.display(), target.display())));
+                }
+            }
+            EntryType::Link => return Err(rejected(format!("it contains a hard link ({})", path.display()))),
+            other => return Err(rejected(format!("entry {} has unsupported type {:?}", path.display(), other))),
+        }
+    }
+
+    enrichment.unpacked_size = unpacked_size as i64;
+    if let Some(manifest) = manifest.as_deref().and_then(|text| toml::from_str::<Manifest>(text).ok()) {
+        enrichment.description = non_empty(manifest.package.description);
+        enrichment.license = non_empty(manifest.package.license);
+    }
+    Ok(enrichment)
+}
+
+/// The fields of antsol.toml the indexer keeps
+#[derive(Deserialize)]
+struct Manifest {
+    package: ManifestPackage,
+}
+
+#[derive(Deserialize)]
+struct ManifestPackage {
+    description: Option<String>,
+    license: Option<String>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Up to [`MAX_README_SIZE`] bytes of README, without a character split by the cut
+fn read_readme(entry: &mut impl Read) -> std::io::Result<String> {
+    let mut bytes = Vec::new();
+    entry.take(MAX_README_SIZE as u64).read_to_end(&mut bytes)?;
+    Ok(match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).unwrap_or_default()
+        }
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    })
+}
+
+fn unreadable(e: std::io::Error) -> EnrichmentError {
+    EnrichmentError::Permanent(format!("Unreadable tarball: {}", e))
+}
+
+fn rejected(reason: String) -> EnrichmentError {
+    EnrichmentError::Permanent(format!("Unsafe tarball rejected: {}", reason))
+}
+
+/// `path` with `.` components dropped, rejected when it is absolute or
+/// climbs out with `..` at any point
+fn contained_path(path: &Path) -> Result<PathBuf, EnrichmentError> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            Component::ParentDir if normalized.pop() => {}
+            _ => return Err(rejected(format!("{} escapes the package directory", path.display()))),
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tar::Header;
+
+    /// A tar.gz of `(path, type, data, link target)` entries. Names are
+    /// written into the headers raw, bypassing `tar::Builder`'s checks.
+    fn archive(entries: &[(&str, EntryType, &[u8], Option<&str>)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, kind, data, link) in entries {
+            let mut header = Header::new_gnu();
+            let gnu = header.as_gnu_mut().unwrap();
+            gnu.name[..path.len()].copy_from_slice(path.as_bytes());
+            if let Some(target) = link {
+                gnu.linkname[..target.len()].copy_from_slice(target.as_bytes());
+            }
+            header.set_entry_type(*kind);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    fn file<'a>(path: &'a str, data: &'a [u8]) -> (&'a str, EntryType, &'a [u8], Option<&'a str>) {
+        (path, EntryType::Regular, data, None)
+    }
+
+    #[test]
+    fn reads_the_manifest_readme_and_sizes() {
+        let manifest = b"[package]\nname = \"math\"\nversion = \"1.0.0\"\ndescription = \"Fixed-point math\"\nlicense = \"MIT\"\n";
+        let tarball = archive(&[
+            file("antsol.toml", manifest),
+            file("README.md", b"# math\n"),
+            ("src", EntryType::Directory, b"", None),
+            file("src/lib.rs", b"pub fn one() {}\n"),
+            ("docs/readme", EntryType::Symlink, b"", Some("../README.md")),
+        ]);
+
+        let enrichment = inspect_archive(&tarball).unwrap();
+        assert_eq!(enrichment.description.as_deref(), Some("Fixed-point math"));
+        assert_eq!(enrichment.license.as_deref(), Some("MIT"));
+        assert_eq!(enrichment.readme.as_deref(), Some("# math\n"));
+        assert_eq!(enrichment.file_count, 3);
+        assert_eq!(enrichment.unpacked_size, (manifest.len() + 7 + 16) as i64);
+    }
+
+    #[test]
+    fn a_package_without_manifest_or_readme_is_still_read() {
+        let enrichment = inspect_archive(&archive(&[file("antsol.toml", b"not toml ["), file("lib.rs", b"")])).unwrap();
+        assert_eq!(enrichment, VersionEnrichment { file_count: 2, unpacked_size: 10, ..Default::default() });
+    }
+
+    #[test]
+    fn long_readmes_are_cut_on_a_character_boundary() {
+        let readme = "é".repeat(MAX_README_SIZE);
+        let enrichment = inspect_archive(&archive(&[file("README.md", readme.as_bytes())])).unwrap();
+        let kept = enrichment.readme.unwrap();
+        assert_eq!(kept.len(), MAX_README_SIZE);
+        assert!(kept.chars().all(|c| c == 'é'));
+    }
+
+    #[test]
+    fn unsafe_tarballs_are_rejected_for_good() {
+        let hostile = [
+            archive(&[file("../escape.txt", b"x")]),
+            archive(&[file("/etc/cron.d/x", b"x")]),
+            archive(&[("lib", EntryType::Link, b"", Some("/etc/passwd"))]),
+            archive(&[("lib", EntryType::Symlink, b"", Some("../../outside"))]),
+            archive(&[("dev", EntryType::Char, b"", None)]),
+            b"not a tarball".to_vec(),
+        ];
+        for tarball in &hostile {
+            let error = inspect_archive(tarball).unwrap_err();
+            assert!(matches!(error, EnrichmentError::Permanent(_)), "{}", error);
+            assert_eq!(retry_at(0, &error, Utc::now()), None);
+        }
+    }
+
+    #[test]
+    fn transient_failures_back_off_until_the_attempts_run_out() {
+        let now = Utc::now();
+        let error = EnrichmentError::Transient("gateway timeout".to_string());
+        assert_eq!(retry_at(0, &error, now), Some(now + chrono::Duration::seconds(60)));
+        assert_eq!(retry_at(2, &error, now), Some(now + chrono::Duration::seconds(240)));
+        assert_eq!(retry_delay(20), chrono::Duration::hours(6));
+        assert_eq!(retry_at(MAX_ATTEMPTS - 1, &error, now), None);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::db::Pool;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature};
+use solana_transaction_status::TransactionStatus;
+use std::str::FromStr;
+
+/// Signatures checked per finality pass
+const PASS_LIMIT: i64 = 512;
+
+/// `getSignatureStatuses` accepts at most this many signatures per call
+const STATUS_BATCH: usize = 256;
+
+/// What re-checking a not-yet-final transaction found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Landed in a finalized slot, possibly a different one than first seen
+    Finalized { slot: u64 },
+    /// Gone from the ledger (or failed on the canonical fork) although its slot is finalized
+    Orphaned,
+    /// Not settled yet; look again on the next pass
+    Pending,
+}
+
+/// Classify a transaction first seen at `event_slot` from its current status
+pub fn classify(status: Option<&TransactionStatus>, event_slot: u64, finalized_slot: u64) -> Verdict {
+    match status {
+        Some(status) if status.err.is_some() => Verdict::Orphaned,
+        Some(status) if status.slot <= finalized_slot => Verdict::Finalized { slot: status.slot },
+        Some(_) => Verdict::Pending,
+        None if event_slot <= finalized_slot => Verdict::Orphaned,
+        None => Verdict::Pending,
+    }
+}
+
+/// Counts from one finality pass
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct FinalityReport {
+    pub finalized: usize,
+    pub orphaned: usize,
+}
+
+/// Settle events whose slot is now finalized.
+///
+/// Every not-yet-final transaction at or below the finalized slot is looked
+/// up with `getSignatureStatuses` (searching history). Its events are marked
+/// final when it is there; when it vanished with a fork they are flagged
+/// orphaned and the versions they created are removed again.
+pub async fn reconcile(rpc_client: &RpcClient, pool: &Pool) -> Result<FinalityReport, anyhow::Error> {
+    let finalized_slot = rpc_client.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    let pending = crate::db::queries::get_unfinalized_signatures(pool, finalized_slot, PASS_LIMIT)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+
+    let mut report = FinalityReport::default();
+    for batch in pending.chunks(STATUS_BATCH) {
+        let signatures = batch
+            .iter()
+            .map(|(signature, _)| Signature::from_str(signature))
+            .collect::<Result<Vec<_>, _>>()?;
+        let statuses = rpc_client.get_signature_statuses_with_history(&signatures)?.value;
+
+        for ((signature, slot), status) in batch.iter().zip(statuses) {
+            match classify(status.as_ref(), (*slot).max(0) as u64, finalized_slot) {
+                Verdict::Finalized { slot } => {
+                    crate::db::queries::finalize_events(pool, signature, slot).await.map_err(|e| anyhow::anyhow!(e))?;
+                    report.finalized += 1;
+                }
+                Verdict::Orphaned => {
+                    orphan(pool, signature).await?;
+                    report.orphaned += 1;
+                }
+                Verdict::Pending => {}
+            }
+        }
+    }
+
+    crate::db::queries::update_finalized_slot(pool, finalized_slot).await.map_err(|e| anyhow::anyhow!(e))?;
+    Ok(report)
+}
+
+/// Flag a vanished transaction's events and undo the versions they added
+async fn orphan(pool: &Pool, signature: &str) -> Result<(), anyhow::Error> {
+    let events = crate::db::queries::orphan_events(pool, signature).await.map_err(|e| anyhow::anyhow!(e))?;
+    for event in events {
+        tracing::warn!(
+            "Transaction {} was rolled back; {} for {} is orphaned",
+            signature, event.event_type, event.package_name
+        );
+        let creates_version = matches!(event.event_type.as_str(), "PackagePublished" | "PackageUpdated");
+        let Some(version) = event.version.as_deref().filter(|_| creates_version) else {
+            continue;
+        };
+        let removed = crate::db::queries::remove_orphaned_version(pool, &event.package_name, version)
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        if removed {
+            tracing::warn!("Removed phantom version {}@{}", event.package_name, version);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::transaction::TransactionError;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn status(slot: u64, err: Option<TransactionError>) -> TransactionStatus {
+        TransactionStatus {
+            slot,
+            confirmations: None,
+            status: err.clone().map_or(Ok(()), Err),
+            err,
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        }
+    }
+
+    #[test]
+    fn finalized_transactions_take_their_landing_slot() {
+        assert_eq!(classify(Some(&status(100, None)), 100, 150), Verdict::Finalized { slot: 100 });
+        // Re-landed on the surviving fork after the slot it was first seen in
+        assert_eq!(classify(Some(&status(104, None)), 100, 150), Verdict::Finalized { slot: 104 });
+    }
+
+    #[test]
+    fn unsettled_transactions_wait() {
+        assert_eq!(classify(Some(&status(160, None)), 160, 150), Verdict::Pending);
+        assert_eq!(classify(None, 160, 150), Verdict::Pending);
+    }
+
+    #[test]
+    fn vanished_or_failed_transactions_are_orphaned() {
+        assert_eq!(classify(None, 100, 150), Verdict::Orphaned);
+        assert_eq!(classify(Some(&status(100, Some(TransactionError::AccountInUse))), 100, 150), Verdict::Orphaned);
+    }
+}
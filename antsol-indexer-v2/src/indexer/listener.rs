@@ -1,11 +1,22 @@
-use deadpool_postgres::Pool;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use crate::db::Pool;
+use metrics::{counter, gauge};
+use solana_client::nonblocking::rpc_client::RpcClient as AsyncRpcClient;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{EncodedConfirmedTransactionWithStatusMeta, UiTransactionEncoding};
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
+use super::accounts::{PackageAccount, PackageAccounts};
+use super::broadcast::EventBus;
 use super::parser::parse_transaction;
+use crate::db::models::Event;
+use crate::db::GenericClient;
+use crate::telemetry::{EVENTS_INGESTED, INDEXER_LAG_SLOTS, LAST_PROCESSED_SLOT, RPC_ERRORS, SIGNATURES_PROCESSED};
 /// Attempt to extract a probable IPFS hash / CID from a log line.
 /// Heuristics:
 ///  - Look for "ipfs" followed by common separators and take next token
@@ -39,87 +50,359 @@ pub fn extract_ipfs_hash(log: &str) -> Option<String> {
     None
 }
 
-/// Shared ingestion logic used by the blockchain listener and the manual API ingestion endpoint.
-pub async fn ingest_event(
+/// Store a parsed event, returning its id, or 0 when its signature and log
+/// index were already recorded. Nothing is announced until the transaction commits.
+pub async fn record_event(
+    client: &impl GenericClient,
+    event: &Event,
+    block_time: Option<i64>,
+) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+    crate::db::queries::insert_event(
+        client,
+        &event.event_type,
+        &event.package_name,
+        event.version.as_deref(),
+        &event.transaction_signature,
+        event.log_index,
+        event.slot,
+        block_time,
+        event.ipfs_hash.as_deref(),
+        event.authority.as_deref(),
+    ).await
+}
+
+/// Record the events parsed from one program transaction and apply each to
+/// packages and versions in a single database transaction, so a failure
+/// part-way leaves no rows behind. Each stored event is then linked to the
+/// version row it touched. Returns how many events were stored.
+/// Publishes and updates read their package account through `accounts`
+/// when given (see [`ingest_event`]).
+///
+/// Each event gets its own row, keyed on its signature and the index of its
+/// log line. When the first event is already recorded (an earlier run, or
+/// the other of the websocket and polling paths, got there first) the
+/// transaction is rolled back and 0 returned. Events committed here are
+/// then counted and announced on `events`.
+pub async fn ingest_events(
     pool: &Pool,
-    event: &crate::db::models::Event,
+    events: &EventBus,
+    accounts: Option<&PackageAccounts>,
+    parsed: &[(Event, &str)],
+    block_time: Option<i64>,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let mut db = pool.get().await?;
+    let tx = db.transaction().await?;
+    let mut stored = Vec::new();
+    for (index, (event, log)) in parsed.iter().enumerate() {
+        let id = record_event(&tx, event, block_time).await?;
+        if id == 0 && index == 0 {
+            tx.rollback().await?;
+            return Ok(0);
+        }
+        ingest_event(&tx, event, log, accounts).await?;
+        if id != 0 {
+            let version_id = match &event.version {
+                Some(version) => crate::db::queries::link_event_version(&tx, id, &event.package_name, version).await?,
+                None => None,
+            };
+            stored.push(Event { id, version_id, ..event.clone() });
+        }
+    }
+    tx.commit().await?;
+
+    let count = stored.len();
+    for event in stored {
+        counter!(EVENTS_INGESTED, "type" => event.event_type.clone()).increment(1);
+        events.publish(event);
+    }
+    Ok(count)
+}
+
+/// Apply one event to packages and versions. Used by the blockchain
+/// listener and the manual API ingestion endpoint through [`ingest_events`];
+/// errors are returned so the caller's transaction rolls back.
+///
+/// Log lines don't carry a publish's description, so with `accounts` the
+/// new version's package account is fetched and decoded for it, along with
+/// anything else the logs left out. Without it, or when the account can't
+/// be read, only what the logs carry is stored.
+pub async fn ingest_event(
+    client: &impl GenericClient,
+    event: &Event,
     log: &str,
+    accounts: Option<&PackageAccounts>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     match event.event_type.as_str() {
         "PackagePublished" => {
-            let ipfs = extract_ipfs_hash(log).unwrap_or_else(|| "unknown".to_string());
             if let Some(ver) = &event.version {
-                match crate::db::queries::insert_package(
-                    pool,
-                    &event.package_name,
-                    "unknown",
-                    None,
-                    None,
-                    None,
-                ).await {
-                    Ok(pkg_id) => {
-                        if ipfs != "unknown" {
-                            if let Err(e) = crate::db::queries::insert_version(pool, pkg_id, ver, &ipfs).await {
-                                tracing::warn!("Failed to insert version {} for {}: {}", ver, event.package_name, e);
-                            } else {
-                                tracing::info!("Stored published version {}@{} (ipfs={})", event.package_name, ver, &ipfs[..8.min(ipfs.len())]);
-                            }
-                        } else {
-                            tracing::debug!("No IPFS hash detected for published package {}@{}", event.package_name, ver);
-                        }
-                    }
-                    Err(e) => tracing::warn!("Failed upsert package {}: {}", event.package_name, e),
+                let account = fetch_account(accounts, &event.package_name, ver).await;
+                let (ipfs, authority) = version_details(event, account.as_ref());
+                let pkg_id = upsert_package(client, &event.package_name, authority.as_deref(), account.as_ref()).await?;
+                if ipfs != "unknown" {
+                    store_version(client, pkg_id, event, ver, &ipfs, authority.as_deref(), account.as_ref()).await?;
+                    tracing::info!("Stored published version {}@{} (ipfs={})", event.package_name, ver, &ipfs[..8.min(ipfs.len())]);
+                } else {
+                    tracing::debug!("No IPFS hash detected for published package {}@{}", event.package_name, ver);
                 }
             } else {
                 tracing::warn!("Publish event missing version for package {}", event.package_name);
             }
         }
         "PackageUpdated" => {
-            let ipfs = extract_ipfs_hash(log).unwrap_or_else(|| "unknown".to_string());
             if let Some(ver) = &event.version {
-                let pkg_id = match crate::db::queries::get_package_id(pool, &event.package_name).await {
-                    Ok(Some(id)) => id,
-                    _ => match crate::db::queries::insert_package(pool, &event.package_name, "unknown", None, None, None).await {
-                        Ok(id) => id,
-                        Err(e) => { tracing::warn!("Failed create package on update {}: {}", event.package_name, e); return Ok(()); }
-                    },
+                let account = fetch_account(accounts, &event.package_name, ver).await;
+                let (ipfs, authority) = version_details(event, account.as_ref());
+                // The new version's account refreshes the description; without it the row is left alone
+                let pkg_id = match (&account, crate::db::queries::get_package_id(client, &event.package_name).await?) {
+                    (None, Some(id)) => id,
+                    _ => upsert_package(client, &event.package_name, authority.as_deref(), account.as_ref()).await?,
                 };
                 if ipfs != "unknown" {
-                    if let Err(e) = crate::db::queries::insert_version(pool, pkg_id, ver, &ipfs).await {
-                        tracing::warn!("Failed upsert updated version {} for {}: {}", ver, event.package_name, e);
-                    } else {
-                        tracing::info!("Updated version {}@{} (ipfs={})", event.package_name, ver, &ipfs[..8.min(ipfs.len())]);
-                    }
+                    store_version(client, pkg_id, event, ver, &ipfs, authority.as_deref(), account.as_ref()).await?;
+                    tracing::info!("Updated version {}@{} (ipfs={})", event.package_name, ver, &ipfs[..8.min(ipfs.len())]);
                 } else {
                     tracing::debug!("Update event without IPFS for {}@{}", event.package_name, ver);
                 }
             }
         }
-        "PackageDownloaded" => {
+        "PackageMetadata" => {
+            let Some(metadata) = super::parser::parse_package_metadata(log) else {
+                tracing::warn!("Malformed package metadata for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            let updated = crate::db::queries::update_package_metadata(
+                client,
+                &event.package_name,
+                metadata.repository.as_deref(),
+                metadata.homepage.as_deref(),
+                &metadata.keywords,
+            ).await?;
+            if updated {
+                tracing::info!("Stored metadata for {} ({} keyword(s))", event.package_name, metadata.keywords.len());
+            } else {
+                tracing::debug!("Metadata for unknown package {}", event.package_name);
+            }
+            if let (Some(ver), Some(readme_cid)) = (&event.version, metadata.readme_cid.as_deref()) {
+                crate::db::queries::update_version_readme(client, &event.package_name, ver, readme_cid).await?;
+            }
+        }
+        "DependenciesChanged" => {
+            let (Some(ver), Some((added, removed))) = (&event.version, super::parser::parse_dependency_delta(log)) else {
+                tracing::warn!("Malformed dependency change for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            crate::db::queries::insert_dependency_change(
+                client,
+                &event.package_name,
+                ver,
+                &added,
+                &removed,
+                &event.transaction_signature,
+                event.slot,
+            ).await?;
+            if !added.is_empty() {
+                tracing::warn!("{}@{} adds new dependencies: {}", event.package_name, ver, added.join(", "));
+            }
+            tracing::info!("Stored dependency change for {}@{} (+{} -{})", event.package_name, ver, added.len(), removed.len());
+        }
+        "PackageDependencies" => {
+            let (Some(ver), Some(dependencies)) = (&event.version, super::parser::parse_package_dependencies(log)) else {
+                tracing::warn!("Malformed dependency list for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            let stored = crate::db::queries::replace_version_dependencies(client, &event.package_name, ver, &dependencies).await?;
+            if stored {
+                tracing::info!("Stored {} dependencies for {}@{}", dependencies.len(), event.package_name, ver);
+            } else {
+                tracing::debug!("Dependencies for unknown version {}@{}", event.package_name, ver);
+            }
+        }
+        "PackageExternalDependencies" => {
+            let (Some(ver), Some(dependencies)) = (&event.version, super::parser::parse_external_dependencies(log)) else {
+                tracing::warn!("Malformed external dependency list for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            let stored = crate::db::queries::replace_version_external_dependencies(client, &event.package_name, ver, &dependencies).await?;
+            if stored {
+                tracing::info!("Stored {} external dependencies for {}@{}", dependencies.len(), event.package_name, ver);
+            } else {
+                tracing::debug!("External dependencies for unknown version {}@{}", event.package_name, ver);
+            }
+        }
+        "AuthorityTransferred" => {
+            let Some((old, new)) = super::parser::parse_authority_transfer(log) else {
+                tracing::warn!("Malformed authority transfer for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            let updated = crate::db::queries::update_package_authority(
+                client,
+                &event.package_name,
+                event.version.as_deref(),
+                &new,
+            ).await?;
+            if updated {
+                tracing::info!("Authority of {} transferred from {} to {}", event.package_name, old, new);
+            } else {
+                tracing::debug!("Authority transfer for unknown package {}", event.package_name);
+            }
+        }
+        "MaintainerAdded" | "MaintainerRemoved" => {
+            let Some((maintainer, authority)) = super::parser::extract_maintainer(log) else {
+                tracing::warn!("Malformed maintainer change for {}: {}", event.package_name, log);
+                return Ok(());
+            };
+            let listed = event.event_type == "MaintainerAdded";
+            crate::db::queries::set_package_maintainer(client, &event.package_name, &authority, &maintainer, listed).await?;
+            tracing::info!(
+                "{} {} as a maintainer of {} for {}",
+                if listed { "Added" } else { "Removed" },
+                maintainer,
+                event.package_name,
+                authority
+            );
+        }
+        "PackageFrozen" => {
             if let Some(ver) = &event.version {
-                if let Ok(Some(pkg_id)) = crate::db::queries::get_package_id(pool, &event.package_name).await {
-                    if let Ok(Some(ver_id)) = crate::db::queries::get_version_id(pool, pkg_id, ver).await {
-                        if let Err(e) = crate::db::queries::increment_download(pool, pkg_id, ver_id).await {
-                            tracing::warn!("Failed increment download for {}@{}: {}", event.package_name, ver, e);
-                        } else {
-                            tracing::info!("Incremented downloads for {}@{}", event.package_name, ver);
-                        }
-                    } else {
-                        tracing::debug!("Download event version not found {}@{} (maybe publish not processed yet)", event.package_name, ver);
-                    }
+                if crate::db::queries::freeze_version(client, &event.package_name, ver).await? {
+                    tracing::info!("Froze {}@{}", event.package_name, ver);
                 } else {
-                    tracing::debug!("Download event package not found {} (maybe publish not processed yet)", event.package_name);
+                    tracing::debug!("Freeze event for unknown version {}@{}", event.package_name, ver);
                 }
             }
         }
+        "PackageDownloaded" => {
+            if let Some(ver) = &event.version {
+                let Some(pkg_id) = crate::db::queries::get_package_id(client, &event.package_name).await? else {
+                    tracing::debug!("Download event package not found {} (maybe publish not processed yet)", event.package_name);
+                    return Ok(());
+                };
+                let Some(ver_id) = crate::db::queries::get_version_id(client, pkg_id, ver).await? else {
+                    tracing::debug!("Download event version not found {}@{} (maybe publish not processed yet)", event.package_name, ver);
+                    return Ok(());
+                };
+                crate::db::queries::increment_download(client, pkg_id, ver_id).await?;
+                tracing::info!("Incremented downloads for {}@{}", event.package_name, ver);
+            }
+        }
         _ => {}
     }
     Ok(())
 }
 
+async fn fetch_account(accounts: Option<&PackageAccounts>, name: &str, version: &str) -> Option<PackageAccount> {
+    match accounts {
+        Some(accounts) => accounts.fetch(name, version).await,
+        None => None,
+    }
+}
+
+/// CID and signer of a publish or update, from its log line or else its account
+fn version_details(event: &Event, account: Option<&PackageAccount>) -> (String, Option<String>) {
+    let ipfs = event.ipfs_hash.clone()
+        .or_else(|| account.map(|a| a.ipfs_cid.clone()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let authority = event.authority.clone().or_else(|| account.map(|a| a.authority.clone()));
+    (ipfs, authority)
+}
+
+/// Insert or refresh the package row, taking its description, repository
+/// and homepage from `account` when there is one
+async fn upsert_package(
+    client: &impl GenericClient,
+    name: &str,
+    authority: Option<&str>,
+    account: Option<&PackageAccount>,
+) -> Result<i32, Box<dyn std::error::Error + Send + Sync>> {
+    let field = |value: fn(&PackageAccount) -> &str| account.map(value).filter(|v| !v.is_empty());
+    crate::db::queries::insert_package(
+        client,
+        name,
+        authority.unwrap_or("unknown"),
+        field(|a| a.description.as_str()),
+        field(|a| a.repository.as_str()),
+        field(|a| a.homepage.as_str()),
+    ).await
+}
+
+/// Insert the version row, then the dependencies its account lists. The
+/// dependency log line that follows stores the same list when present.
+async fn store_version(
+    client: &impl GenericClient,
+    package_id: i32,
+    event: &Event,
+    version: &str,
+    ipfs: &str,
+    authority: Option<&str>,
+    account: Option<&PackageAccount>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let published_at = event.block_time
+        .or_else(|| account.and_then(|a| chrono::DateTime::from_timestamp(a.published_at, 0)));
+    crate::db::queries::insert_version(client, package_id, version, ipfs, authority, published_at).await?;
+    if let Some(account) = account {
+        crate::db::queries::replace_version_dependencies(client, &event.package_name, version, &account.dependencies).await?;
+    }
+    Ok(())
+}
+
+/// Store a package version straight from its account, for a publish the
+/// listener missed: the package row is refreshed and the version row is
+/// inserted or corrected with the account's CID, authority and publish time.
+pub async fn resync_version(pool: &Pool, account: &PackageAccount) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut db = pool.get().await?;
+    let tx = db.transaction().await?;
+    let package_id = upsert_package(&tx, &account.name, Some(account.authority.as_str()), Some(account)).await?;
+    let published_at = chrono::DateTime::from_timestamp(account.published_at, 0);
+    crate::db::queries::insert_version(&tx, package_id, &account.version, &account.ipfs_cid, Some(account.authority.as_str()), published_at).await?;
+    crate::db::queries::replace_version_dependencies(&tx, &account.name, &account.version, &account.dependencies).await?;
+    tx.commit().await?;
+    tracing::info!("Re-synced {}@{} from its account (ipfs={})", account.name, account.version, &account.ipfs_cid[..8.min(account.ipfs_cid.len())]);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
-    use super::extract_ipfs_hash;
+    use super::*;
+    use solana_transaction_status::TransactionConfirmationStatus;
+
+    fn status(slot: u64) -> RpcConfirmedTransactionStatusWithSignature {
+        RpcConfirmedTransactionStatusWithSignature {
+            signature: Signature::new_unique().to_string(),
+            slot,
+            err: None,
+            memo: None,
+            block_time: None,
+            confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+        }
+    }
+
+    #[test]
+    fn pages_signatures_back_and_returns_oldest_first() {
+        // Newest first, like getSignaturesForAddress: one full page then a partial one
+        let history: Vec<_> = (0..SIGNATURE_PAGE_LIMIT + 5).rev().map(|i| status(100 + i as u64)).collect();
+        let mut befores = Vec::new();
+        let collected = collect_new_signatures(|before| {
+            befores.push(before);
+            let start = match before {
+                Some(before) => history.iter().position(|s| s.signature == before.to_string()).unwrap() + 1,
+                None => 0,
+            };
+            Ok(history[start..].iter().take(SIGNATURE_PAGE_LIMIT).cloned().collect())
+        }, None).unwrap();
+
+        assert_eq!(collected.len(), SIGNATURE_PAGE_LIMIT + 5);
+        assert_eq!(collected.first().unwrap().slot, 100);
+        assert_eq!(collected.last().unwrap().slot, 100 + SIGNATURE_PAGE_LIMIT as u64 + 4);
+        assert_eq!(befores.len(), 2);
+        assert_eq!(befores[1].unwrap().to_string(), history[SIGNATURE_PAGE_LIMIT - 1].signature);
+    }
+
+    #[test]
+    fn backfill_stops_below_min_slot() {
+        let history: Vec<_> = [50, 40, 30, 20].into_iter().map(status).collect();
+        let collected = collect_new_signatures(|_| Ok(history.clone()), Some(30)).unwrap();
+        let slots: Vec<u64> = collected.iter().map(|s| s.slot).collect();
+        assert_eq!(slots, vec![30, 40, 50]);
+    }
 
     #[test]
     fn test_extract_ipfs_basic_patterns() {
@@ -133,12 +416,53 @@ mod tests {
     }
 }
 
+/// Page size for `getSignaturesForAddress` (the RPC maximum)
+pub(crate) const SIGNATURE_PAGE_LIMIT: usize = 1000;
+
+/// Page back through the program's signatures, newest first, until a page
+/// comes back short (the RPC stops at the `until` cursor baked into
+/// `fetch_page`) or, when backfilling without a cursor, until a signature
+/// older than `min_slot`. Returns them oldest first, ready for ingestion.
+pub(crate) fn collect_new_signatures<F>(
+    mut fetch_page: F,
+    min_slot: Option<u64>,
+) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, anyhow::Error>
+where
+    F: FnMut(Option<Signature>) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>, anyhow::Error>,
+{
+    let mut collected = Vec::new();
+    let mut before = None;
+    
+    'pages: loop {
+        let page = fetch_page(before)?;
+        let full_page = page.len() >= SIGNATURE_PAGE_LIMIT;
+        let oldest = page.last().map(|s| s.signature.clone());
+        
+        for status in page {
+            if min_slot.is_some_and(|min| status.slot < min) {
+                break 'pages;
+            }
+            collected.push(status);
+        }
+        
+        match oldest {
+            Some(oldest) if full_page => before = Some(Signature::from_str(&oldest)?),
+            _ => break,
+        }
+    }
+    
+    collected.reverse();
+    Ok(collected)
+}
+
 pub async fn start_indexer(
     pool: Pool,
     rpc_url: String,
     program_id_str: String,
     start_slot_override: Option<u64>,
     poll_interval_secs: u64,
+    commitment: CommitmentConfig,
+    events: EventBus,
 ) {
     tracing::info!("Starting indexer for program: {}", program_id_str);
     
@@ -150,27 +474,42 @@ pub async fn start_indexer(
         }
     };
     
-    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+    let rpc_client = RpcClient::new_with_commitment(rpc_url.clone(), commitment);
+    let accounts = PackageAccounts::new(Arc::new(AsyncRpcClient::new_with_commitment(rpc_url.clone(), commitment)), program_id);
     
-    // Determine starting slot: existing state > override > current slot
-    let mut last_slot = match crate::db::queries::get_last_processed_slot(&pool).await {
-        Ok(slot) if slot > 0 => {
-            tracing::info!("Resuming from last processed slot: {}", slot);
-            slot
+    // Resume from the stored signature when there is one. Otherwise backfill
+    // from a slot: the last processed slot (state from the block-scanning
+    // listener) > override (0 = the program's whole history) > current slot.
+    let mut cursor = match crate::db::queries::get_last_signature(&pool).await {
+        Ok(cursor) => cursor,
+        Err(e) => {
+            tracing::warn!("Failed to read signature cursor: {}", e);
+            None
         }
-        _ => {
-            if let Some(override_slot) = start_slot_override {
-                tracing::info!("Indexer initial state empty; backfilling from override start slot {}", override_slot);
-                override_slot
-            } else {
-                match rpc_client.get_slot() {
-                    Ok(slot) => {
-                        tracing::info!("Indexer initial state empty; starting from current slot {} (no historical backfill override provided)", slot);
-                        slot
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to get initial slot: {}", e);
-                        return;
+    };
+    let backfill_from = if let Some(signature) = &cursor {
+        tracing::info!("Resuming after signature {}", signature);
+        0
+    } else {
+        match crate::db::queries::get_last_processed_slot(&pool).await {
+            Ok(slot) if slot > 0 => {
+                tracing::info!("No signature cursor; resuming after last processed slot: {}", slot);
+                slot + 1
+            }
+            _ => {
+                if let Some(override_slot) = start_slot_override {
+                    tracing::info!("Indexer initial state empty; backfilling program signatures from slot {}", override_slot);
+                    override_slot
+                } else {
+                    match rpc_client.get_slot() {
+                        Ok(slot) => {
+                            tracing::info!("Indexer initial state empty; starting from current slot {} (no historical backfill override provided)", slot);
+                            slot
+                        }
+                        Err(e) => {
+                            tracing::error!("Failed to get initial slot: {}", e);
+                            return;
+                        }
                     }
                 }
             }
@@ -180,55 +519,93 @@ pub async fn start_indexer(
     let mut retry_count = 0;
     let max_retries = 5;
     let mut error_backoff = Duration::from_secs(2);
+    let mut last_slot: Option<u64> = None;
     
     loop {
-        match rpc_client.get_slot() {
-            Ok(current_slot) => {
+        // Read before listing signatures, so a complete poll covers at least this slot
+        let head = match rpc_client.get_slot() {
+            Ok(slot) => Some(slot),
+            Err(e) => {
+                counter!(RPC_ERRORS, "operation" => "get_slot").increment(1);
+                tracing::debug!("Failed to read current slot: {}", e);
+                None
+            }
+        };
+        let until = cursor.as_deref().and_then(|s| Signature::from_str(s).ok());
+        let min_slot = if cursor.is_some() { None } else { Some(backfill_from) };
+        let signatures = collect_new_signatures(|before| {
+            rpc_client.get_signatures_for_address_with_config(
+                &program_id,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(SIGNATURE_PAGE_LIMIT),
+                    commitment: Some(commitment),
+                },
+            ).map_err(anyhow::Error::from)
+        }, min_slot);
+        
+        match signatures {
+            Ok(signatures) => {
                 retry_count = 0;
                 error_backoff = Duration::from_secs(2); // Reset backoff
                 
-                if current_slot > last_slot {
-                    let slots_to_process = current_slot - last_slot;
-                    
-                    if slots_to_process > 100 {
-                        tracing::info!("Processing {} slots ({} to {}), this may take a while...", 
-                            slots_to_process, last_slot + 1, current_slot);
-                    } else {
-                        tracing::debug!("Processing slots {} to {}", last_slot + 1, current_slot);
-                    }
-                    
-                    for slot in last_slot + 1..=current_slot {
-                        match process_slot(&rpc_client, &pool, slot, &program_id).await {
-                            Ok(_) => {
-                                // Update database state every 10 slots or on last slot
-                                if slot % 10 == 0 || slot == current_slot {
-                                    if let Err(e) = crate::db::queries::update_last_processed_slot(
-                                        &pool, 
-                                        slot, 
-                                        None
-                                    ).await {
-                                        tracing::warn!("Failed to update last processed slot: {}", e);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                tracing::warn!("Error processing slot {}: {}", slot, e);
-                                if let Err(db_err) = crate::db::queries::update_indexer_error(
-                                    &pool,
-                                    &format!("Slot {}: {}", slot, e)
-                                ).await {
-                                    tracing::error!("Failed to log error to database: {}", db_err);
-                                }
+                if signatures.len() > 100 {
+                    tracing::info!("Processing {} program transactions, this may take a while...", signatures.len());
+                } else if !signatures.is_empty() {
+                    tracing::debug!("Processing {} program transactions", signatures.len());
+                }
+                
+                let mut caught_up = true;
+                for status in signatures {
+                    // Failed transactions emit no events, but still advance the cursor, as
+                    // do ones the websocket subscription has already stored
+                    let recorded = crate::db::queries::event_recorded(&pool, &status.signature).await.unwrap_or(false);
+                    if status.err.is_none() && !recorded {
+                        if let Err(e) = process_transaction(&rpc_client, &pool, &events, &accounts, &status.signature, &program_id, commitment).await {
+                            tracing::warn!("Error processing transaction {}: {}", status.signature, e);
+                            if let Err(db_err) = crate::db::queries::update_indexer_error(
+                                &pool,
+                                &format!("Transaction {}: {}", status.signature, e)
+                            ).await {
+                                tracing::error!("Failed to log error to database: {}", db_err);
                             }
+                            // Keep the cursor before this transaction so the next poll retries it
+                            caught_up = false;
+                            break;
                         }
                     }
                     
-                    last_slot = current_slot;
+                    if let Err(e) = crate::db::queries::update_last_signature(
+                        &pool,
+                        &status.signature,
+                        status.slot,
+                        status.block_time,
+                    ).await {
+                        tracing::warn!("Failed to update signature cursor: {}", e);
+                    }
+                    counter!(SIGNATURES_PROCESSED).increment(1);
+                    last_slot = Some(status.slot);
+                    cursor = Some(status.signature);
+                }
+                
+                if let Some(head) = head.filter(|_| caught_up) {
+                    if let Err(e) = crate::db::queries::update_synced_slot(&pool, head).await {
+                        tracing::warn!("Failed to record synced slot: {}", e);
+                    }
+                    last_slot = Some(last_slot.map_or(head, |slot| slot.max(head)));
+                }
+                if let Some(slot) = last_slot {
+                    gauge!(LAST_PROCESSED_SLOT).set(slot as f64);
+                    if let Some(head) = head {
+                        gauge!(INDEXER_LAG_SLOTS).set(head.saturating_sub(slot) as f64);
+                    }
                 }
             }
             Err(e) => {
+                counter!(RPC_ERRORS, "operation" => "get_signatures").increment(1);
                 retry_count += 1;
-                tracing::error!("Failed to get current slot (attempt {}/{}): {}", 
+                tracing::error!("Failed to fetch program signatures (attempt {}/{}): {}", 
                     retry_count, max_retries, e);
                 
                 if retry_count >= max_retries {
@@ -252,127 +629,123 @@ pub async fn start_indexer(
             }
         }
         
+        // Settle events whose slots have been finalized since the last poll
+        match super::finality::reconcile(&rpc_client, &pool).await {
+            Ok(report) if report.orphaned > 0 => {
+                tracing::warn!("Finality pass: {} transaction(s) finalized, {} orphaned by a fork", report.finalized, report.orphaned);
+            }
+            Ok(report) if report.finalized > 0 => {
+                tracing::debug!("Finality pass: {} transaction(s) finalized", report.finalized);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Finality pass failed: {}", e),
+        }
+        
         // Normal polling interval (configurable)
         sleep(Duration::from_secs(poll_interval_secs)).await;
     }
 }
 
-async fn process_slot(
+/// Fetch one program transaction and ingest the events in its logs
+async fn process_transaction(
     rpc_client: &RpcClient,
     pool: &Pool,
-    slot: u64,
+    events: &EventBus,
+    accounts: &PackageAccounts,
+    signature: &str,
     program_id: &Pubkey,
+    commitment: CommitmentConfig,
 ) -> Result<(), anyhow::Error> {
-    let block = match rpc_client.get_block_with_config(
-        slot,
-        solana_client::rpc_config::RpcBlockConfig {
-            encoding: Some(solana_transaction_status::UiTransactionEncoding::Json),
-            transaction_details: Some(solana_transaction_status::TransactionDetails::Full),
-            rewards: Some(false),
-            commitment: Some(CommitmentConfig::confirmed()),
-            max_supported_transaction_version: Some(0),
-        },
-    ) {
-        Ok(block) => block,
-        Err(e) => {
-            // Slot might not be available yet or skipped
-            let err_str = e.to_string();
-            if err_str.contains("skipped") || err_str.contains("not available") {
-                tracing::debug!("Slot {} skipped or not available: {}", slot, e);
-                return Ok(());
-            }
-            return Err(anyhow::Error::from(e));
-        }
+    let tx = rpc_client
+        .get_transaction_with_config(&Signature::from_str(signature)?, transaction_config(commitment))
+        .inspect_err(|_| counter!(RPC_ERRORS, "operation" => "get_transaction").increment(1))?;
+    ingest_transaction(pool, events, Some(accounts), signature, tx, program_id).await?;
+    Ok(())
+}
+
+/// How program transactions are fetched for ingestion
+pub(crate) fn transaction_config(commitment: CommitmentConfig) -> RpcTransactionConfig {
+    RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(commitment),
+        max_supported_transaction_version: Some(0),
+    }
+}
+
+/// Ingest the events in a fetched transaction's logs, returning how many
+/// were recorded. A transaction whose signature is already stored is
+/// skipped as a whole, so re-ingesting is harmless; new events are
+/// announced on `events`.
+pub(crate) async fn ingest_transaction(
+    pool: &Pool,
+    events: &EventBus,
+    accounts: Option<&PackageAccounts>,
+    signature: &str,
+    tx: EncodedConfirmedTransactionWithStatusMeta,
+    program_id: &Pubkey,
+) -> Result<usize, anyhow::Error> {
+    let Some(meta) = tx.transaction.meta else {
+        return Ok(0);
     };
+    // Check if transaction was successful
+    if meta.err.is_some() {
+        tracing::trace!("Skipping failed transaction: {}", signature);
+        return Ok(0);
+    }
     
-    let transactions = match block.transactions {
-        Some(txs) => txs,
-        None => return Ok(()),
+    let solana_transaction_status::option_serializer::OptionSerializer::Some(logs) = &meta.log_messages else {
+        return Ok(0);
     };
-    
-    let mut events_found = 0;
-    
-    for tx_with_meta in transactions {
-        // Extract signature from transaction
-        let signature = match &tx_with_meta.transaction {
-            solana_transaction_status::EncodedTransaction::Json(ui_tx) => {
-                ui_tx.signatures.get(0).cloned().unwrap_or_default()
-            }
-            _ => String::new(),
-        };
-
-        if signature.is_empty() {
-            continue;
-        }
+    ingest_logs(pool, events, accounts, signature, tx.slot, tx.block_time, logs, program_id)
+        .await
+        .map_err(|e| anyhow::anyhow!(e))
+}
 
-        if let Some(meta) = tx_with_meta.meta {
-            // Check if transaction was successful
-            if meta.err.is_some() {
-                tracing::trace!("Skipping failed transaction: {}", signature);
-                continue;
-            }
-            
-            if let solana_transaction_status::option_serializer::OptionSerializer::Some(logs) = &meta.log_messages {
-                let mut has_program_invocation = false;
-                
-                // Check if our program was invoked
-                for log in logs {
-tracing::debug!("Indexer saw log: {}", log);
-                    if log.contains(&program_id.to_string()) {
-                        has_program_invocation = true;
-                        break;
-                    }
-                }
-                
-                if !has_program_invocation {
-                    continue;
-                }
-                
-                // Parse all logs for this transaction
-                for log in logs {
-tracing::debug!("Indexer saw log: {}", log);
-                    if let Some(event) = parse_transaction(&log, &signature, slot as i64, block.block_time) {
-                        match crate::db::queries::insert_event(
-                            pool,
-                            &event.event_type,
-                            &event.package_name,
-                            event.version.as_deref(),
-                            &event.transaction_signature,
-                            event.slot,
-                            block.block_time,
-                        ).await {
-                            Ok(_) => {
-                                events_found += 1;
-                                tracing::info!(
-                                    "Indexed event: {} for package {} (slot: {}, tx: {})", 
-                                    event.event_type, 
-                                    event.package_name,
-                                    slot,
-                                    &signature[..8]
-                                );
-                                // If this is a publish event, attempt to upsert package + version metadata
-                                // Ingestion logic based on event type
-                                // Delegate ingestion work to helper
-                                if let Err(e) = ingest_event(pool, &event, &log).await {
-                                    tracing::warn!("Ingestion helper failed for {}: {}", event.event_type, e);
-                                }
-                            }
-                            Err(e) => {
-                                // Ignore duplicate key errors (transaction signature already exists)
-                                if !e.to_string().contains("duplicate") {
-                                    tracing::warn!("Failed to insert event: {}", e);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Record the events in a successful transaction's logs, returning how many
+/// were stored. Shared by the polling listener and the websocket subscription;
+/// whichever sees a transaction second stores nothing (see [`ingest_events`]).
+#[allow(clippy::too_many_arguments)]
+pub async fn ingest_logs(
+    pool: &Pool,
+    events: &EventBus,
+    accounts: Option<&PackageAccounts>,
+    signature: &str,
+    slot: u64,
+    block_time: Option<i64>,
+    logs: &[String],
+    program_id: &Pubkey,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    // Check if our program was invoked (not just referenced as an account)
+    if !logs.iter().any(|log| log.contains(&program_id.to_string())) {
+        return Ok(0);
     }
     
-    if events_found > 0 {
-        tracing::info!("Found {} events in slot {}", events_found, slot);
+    // Parse all logs for this transaction
+    let mut parsed = Vec::new();
+    for (log_index, log) in logs.iter().enumerate() {
+        tracing::debug!("Indexer saw log: {}", log);
+        if let Some(event) = parse_transaction(log, signature, log_index as i32, slot as i64, block_time) {
+            parsed.push((event, log.as_str()));
+        }
+    }
+    if parsed.is_empty() {
+        return Ok(0);
     }
     
-    Ok(())
+    let stored = ingest_events(pool, events, accounts, &parsed, block_time).await?;
+    if stored == 0 {
+        tracing::debug!("Transaction {} was already indexed", &signature[..8.min(signature.len())]);
+        return Ok(0);
+    }
+    for (event, _) in &parsed {
+        tracing::info!(
+            "Indexed event: {} for package {} (slot: {}, tx: {})", 
+            event.event_type, 
+            event.package_name,
+            slot,
+            &signature[..8.min(signature.len())]
+        );
+    }
+    tracing::info!("Found {} events in transaction {} (slot {})", parsed.len(), &signature[..8.min(signature.len())], slot);
+    Ok(stored)
 }
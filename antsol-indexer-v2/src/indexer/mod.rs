@@ -1,4 +1,10 @@
+pub mod accounts;
+pub mod backfill;
+pub mod broadcast;
+pub mod enrichment;
+pub mod finality;
 pub mod listener;
 pub mod parser;
+pub mod websocket;
 
 pub use listener::start_indexer;
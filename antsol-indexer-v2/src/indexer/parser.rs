@@ -1,14 +1,75 @@
-use crate::db::models::Event;
+use crate::db::models::{Event, EventFinality, ExternalDependency};
+use super::listener::extract_ipfs_hash;
 
 pub fn parse_transaction(
     log: &str,
     signature: &str,
+    log_index: i32,
     slot: i64,
     block_time: Option<i64>,
 ) -> Option<Event> {
     let log_lower = log.to_lowercase();
     // Try to parse different event types
     
+    // Pattern 0: package metadata, checked first because its URLs can contain
+    // anything the patterns below look for
+    if let Some((package_name, version)) = extract_prefixed_package(log, METADATA_PREFIX) {
+        tracing::debug!("Parsed PackageMetadata: {} v{}", package_name, version);
+        return Some(Event {
+            id: 0,
+            event_type: "PackageMetadata".to_string(),
+            package_name,
+            version: Some(version),
+            transaction_signature: signature.to_string(),
+            log_index,
+            slot,
+            block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+            finality: EventFinality::Confirmed,
+            ipfs_hash: None,
+            authority: None,
+            version_id: None,
+        });
+    }
+    
+    // Pattern 0b: a version's full dependency list, checked before the
+    // patterns below since dependency names can contain their keywords
+    if let Some((package_name, version)) = extract_prefixed_package(log, DEPENDENCIES_PREFIX) {
+        tracing::debug!("Parsed PackageDependencies: {} v{}", package_name, version);
+        return Some(Event {
+            id: 0,
+            event_type: "PackageDependencies".to_string(),
+            package_name,
+            version: Some(version),
+            transaction_signature: signature.to_string(),
+            log_index,
+            slot,
+            block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+            finality: EventFinality::Confirmed,
+            ipfs_hash: None,
+            authority: None,
+            version_id: None,
+        });
+    }
+    
+    // Pattern 0c: a version's external dependency list, for the same reason
+    if let Some((package_name, version)) = extract_prefixed_package(log, EXTERNAL_DEPENDENCIES_PREFIX) {
+        tracing::debug!("Parsed PackageExternalDependencies: {} v{}", package_name, version);
+        return Some(Event {
+            id: 0,
+            event_type: "PackageExternalDependencies".to_string(),
+            package_name,
+            version: Some(version),
+            transaction_signature: signature.to_string(),
+            log_index,
+            slot,
+            block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+            finality: EventFinality::Confirmed,
+            ipfs_hash: None,
+            authority: None,
+            version_id: None,
+        });
+    }
+    
     // Pattern 1: PackagePublished or Publish instruction
     if log_lower.contains("packagepublished") || log_lower.contains("instruction: publish") || log_lower.contains("program log: publish") || log_lower.contains("package published:") {
         if let Some((package_name, version)) = extract_package_info(log) {
@@ -19,14 +80,19 @@ pub fn parse_transaction(
                 package_name,
                 version,
                 transaction_signature: signature.to_string(),
+                log_index,
                 slot,
                 block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: extract_ipfs_hash(log),
+                authority: extract_authority(log),
+                version_id: None,
             });
         }
     }
     
     // Pattern 2: PackageUpdated or Update instruction
-    if log_lower.contains("packageupdated") || log_lower.contains("instruction: update") || log_lower.contains("program log: update") {
+    if log_lower.contains("packageupdated") || log_lower.contains("instruction: update") || log_lower.contains("program log: update") || log_lower.contains("package updated:") {
         if let Some((package_name, version)) = extract_package_info(log) {
             tracing::debug!("Parsed PackageUpdated: {} v{}", package_name, version.as_ref().unwrap_or(&"unknown".to_string()));
             return Some(Event {
@@ -35,14 +101,102 @@ pub fn parse_transaction(
                 package_name,
                 version,
                 transaction_signature: signature.to_string(),
+                log_index,
+                slot,
+                block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: extract_ipfs_hash(log),
+                authority: extract_authority(log),
+                version_id: None,
+            });
+        }
+    }
+    
+    // Pattern 2b: DependenciesChanged (emitted by update_package when the dependency set changes)
+    if log_lower.contains("dependencieschanged") || log_lower.contains("dependencies changed:") {
+        if let Some((package_name, version)) = extract_package_info(log) {
+            tracing::debug!("Parsed DependenciesChanged: {} v{}", package_name, version.as_ref().unwrap_or(&"unknown".to_string()));
+            return Some(Event {
+                id: 0,
+                event_type: "DependenciesChanged".to_string(),
+                package_name,
+                version,
+                transaction_signature: signature.to_string(),
+                log_index,
                 slot,
                 block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: None,
+                authority: None,
+                version_id: None,
             });
         }
     }
     
-    // Pattern 3: PackageDownloaded or Download instruction
-    if log_lower.contains("packagedownloaded") || log_lower.contains("instruction: download") || log_lower.contains("program log: download") {
+    // Pattern 2c: AuthorityTransferred (emitted by transfer_authority)
+    if log_lower.contains("authoritytransferred") || log_lower.contains("authority transferred:") {
+        if let Some((package_name, version)) = extract_package_info(log) {
+            tracing::debug!("Parsed AuthorityTransferred: {} v{}", package_name, version.as_ref().unwrap_or(&"unknown".to_string()));
+            return Some(Event {
+                id: 0,
+                event_type: "AuthorityTransferred".to_string(),
+                package_name,
+                version,
+                transaction_signature: signature.to_string(),
+                log_index,
+                slot,
+                block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: None,
+                authority: parse_authority_transfer(log).map(|(_, new)| new),
+                version_id: None,
+            });
+        }
+    }
+    
+    // Pattern 2e: PackageFrozen (emitted by freeze_package)
+    if log_lower.contains("packagefrozen") || log_lower.contains("package frozen:") {
+        if let Some((package_name, version)) = extract_package_info(log) {
+            tracing::debug!("Parsed PackageFrozen: {} v{}", package_name, version.as_ref().unwrap_or(&"unknown".to_string()));
+            return Some(Event {
+                id: 0,
+                event_type: "PackageFrozen".to_string(),
+                package_name,
+                version,
+                transaction_signature: signature.to_string(),
+                log_index,
+                slot,
+                block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: None,
+                authority: extract_authority(log),
+                version_id: None,
+            });
+        }
+    }
+    
+    // Pattern 2d: MaintainerAdded / MaintainerRemoved (maintainer lists apply to every version)
+    for (marker, event_type) in [(MAINTAINER_ADDED_PREFIX, "MaintainerAdded"), (MAINTAINER_REMOVED_PREFIX, "MaintainerRemoved")] {
+        let Some(package_name) = extract_maintainer_package(log, marker) else { continue };
+        tracing::debug!("Parsed {}: {}", event_type, package_name);
+        return Some(Event {
+            id: 0,
+            event_type: event_type.to_string(),
+            package_name,
+            version: None,
+            transaction_signature: signature.to_string(),
+            log_index,
+            slot,
+            block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+            finality: EventFinality::Confirmed,
+            ipfs_hash: None,
+            authority: extract_authority(log),
+            version_id: None,
+        });
+    }
+    
+    // Pattern 3: PackageDownloaded (emitted by record_download) or Download instruction
+    if log_lower.contains("packagedownloaded") || log_lower.contains("instruction: download") || log_lower.contains("program log: download") || log_lower.contains("package downloaded:") {
         if let Some((package_name, version)) = extract_package_info(log) {
             tracing::debug!("Parsed PackageDownloaded: {} v{}", package_name, version.as_ref().unwrap_or(&"unknown".to_string()));
             return Some(Event {
@@ -51,8 +205,13 @@ pub fn parse_transaction(
                 package_name,
                 version,
                 transaction_signature: signature.to_string(),
+                log_index,
                 slot,
                 block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: None,
+                authority: None,
+                version_id: None,
             });
         }
     }
@@ -67,8 +226,13 @@ pub fn parse_transaction(
                 package_name,
                 version,
                 transaction_signature: signature.to_string(),
+                log_index,
                 slot,
                 block_time: block_time.map(|ts| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default()),
+                finality: EventFinality::Confirmed,
+                ipfs_hash: None,
+                authority: None,
+                version_id: None,
             });
         }
     }
@@ -76,6 +240,140 @@ pub fn parse_transaction(
     None
 }
 
+/// Extract the added and removed dependency names from a
+/// "Dependencies changed: name@version added=a,b removed=c" log line
+pub fn parse_dependency_delta(log: &str) -> Option<(Vec<String>, Vec<String>)> {
+    let added = extract_name_list(log, "added=")?;
+    let removed = extract_name_list(log, "removed=")?;
+    Some((added, removed))
+}
+
+/// The publishing authority from a
+/// "Package published: name@version by <pubkey>" (or "Package updated:") log line
+pub fn extract_authority(log: &str) -> Option<String> {
+    extract_token(log, " by ").filter(|a| !a.is_empty()).map(|a| a.to_string())
+}
+
+/// The old and new authority from an
+/// "Authority transferred: name@version from <pubkey> to <pubkey>" log line
+pub fn parse_authority_transfer(log: &str) -> Option<(String, String)> {
+    let old = extract_token(log, " from ").filter(|a| !a.is_empty())?;
+    let new = extract_token(log, " to ").filter(|a| !a.is_empty())?;
+    Some((old.to_string(), new.to_string()))
+}
+
+const MAINTAINER_ADDED_PREFIX: &str = "Maintainer added:";
+const MAINTAINER_REMOVED_PREFIX: &str = "Maintainer removed:";
+
+/// The maintainer and the authority whose list changed, from a
+/// "Maintainer added: name maintainer=<pubkey> by <pubkey>" (or "Maintainer removed:") log line
+pub fn extract_maintainer(log: &str) -> Option<(String, String)> {
+    let maintainer = extract_token(log, " maintainer=").filter(|m| !m.is_empty())?;
+    let authority = extract_token(log, " by ").filter(|a| !a.is_empty())?;
+    Some((maintainer.to_string(), authority.to_string()))
+}
+
+fn extract_maintainer_package(log: &str, prefix: &str) -> Option<String> {
+    let start = log.find(prefix)? + prefix.len();
+    log[start..].split_whitespace().next().map(|name| name.to_string())
+}
+
+/// Repository, homepage and keywords from a package's latest publish, and
+/// the README CID of that version
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackageMetadata {
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    pub keywords: Vec<String>,
+    pub readme_cid: Option<String>,
+}
+
+const METADATA_PREFIX: &str = "Package metadata:";
+
+/// Extract the metadata from a
+/// "Package metadata: name@version repository=url homepage=url keywords=a,b readme=cid" log line.
+/// Values never contain whitespace; an empty value means the field is unset.
+/// Programs from before README CIDs omit `readme=`.
+pub fn parse_package_metadata(log: &str) -> Option<PackageMetadata> {
+    extract_prefixed_package(log, METADATA_PREFIX)?;
+    let non_empty = |value: &str| Some(value.to_string()).filter(|v| !v.is_empty());
+    Some(PackageMetadata {
+        repository: extract_token(log, " repository=").and_then(non_empty),
+        homepage: extract_token(log, " homepage=").and_then(non_empty),
+        keywords: extract_token(log, " keywords=")
+            .map(|list| list.split(',').filter(|k| !k.is_empty()).map(|k| k.to_string()).collect())
+            .unwrap_or_default(),
+        readme_cid: extract_token(log, " readme=").and_then(non_empty),
+    })
+}
+
+const DEPENDENCIES_PREFIX: &str = "Package dependencies:";
+
+/// Extract the `(name, version)` pairs from a
+/// "Package dependencies: name@version deps=a@1.0.0,b@2.1.0" log line
+pub fn parse_package_dependencies(log: &str) -> Option<Vec<(String, String)>> {
+    extract_prefixed_package(log, DEPENDENCIES_PREFIX)?;
+    let list = extract_token(log, " deps=")?;
+    list.split(',')
+        .filter(|dep| !dep.is_empty())
+        .map(|dep| {
+            let (name, version) = dep.split_once('@')?;
+            (!name.is_empty() && !version.is_empty()).then(|| (name.to_string(), version.to_string()))
+        })
+        .collect()
+}
+
+const EXTERNAL_DEPENDENCIES_PREFIX: &str = "Package external dependencies:";
+
+/// Extract the dependencies from a "Package external dependencies: name@version
+/// deps=type|name|version|registry;..." log line, where an empty registry means none
+pub fn parse_external_dependencies(log: &str) -> Option<Vec<ExternalDependency>> {
+    extract_prefixed_package(log, EXTERNAL_DEPENDENCIES_PREFIX)?;
+    let list = extract_token(log, " deps=")?;
+    list.split(';')
+        .filter(|dep| !dep.is_empty())
+        .map(|dep| {
+            let mut fields = dep.split('|');
+            let (dep_type, name, version, registry) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+            if fields.next().is_some() || dep_type.is_empty() || name.is_empty() || version.is_empty() {
+                return None;
+            }
+            Some(ExternalDependency {
+                name: name.to_string(),
+                version: version.to_string(),
+                dep_type: dep_type.to_string(),
+                registry: Some(registry.to_string()).filter(|r| !r.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// The `name@version` that follows `prefix`
+fn extract_prefixed_package(log: &str, prefix: &str) -> Option<(String, String)> {
+    let start = log.find(prefix)? + prefix.len();
+    let (name, version) = log[start..].split_whitespace().next()?.split_once('@')?;
+    if name.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), version.to_string()))
+}
+
+/// The value after `key` up to the next whitespace; empty when nothing follows the key
+fn extract_token<'a>(log: &'a str, key: &str) -> Option<&'a str> {
+    let start = log.find(key)? + key.len();
+    log[start..].split(char::is_whitespace).next()
+}
+
+fn extract_name_list(log: &str, key: &str) -> Option<Vec<String>> {
+    let start = log.find(key)? + key.len();
+    let value = log[start..].split_whitespace().next().unwrap_or("");
+    // An empty list is logged as "added=" directly followed by the next key
+    if value.contains('=') {
+        return Some(Vec::new());
+    }
+    Some(value.split(',').filter(|n| !n.is_empty()).map(|n| n.to_string()).collect())
+}
+
 fn extract_package_info(log: &str) -> Option<(String, Option<String>)> {
     // Try to extract "package@version" format first (e.g., "Package published: awesome-math-utils@1.0.0")
     if let Some(at_format) = try_extract_at_format(log) {
@@ -295,7 +593,7 @@ mod tests {
     #[test]
     fn test_parse_publish_event() {
         let log = r#"Program log: PackagePublished {"package":"my-pkg","version":"1.0.0"}"#;
-        let event = parse_transaction(log, "sig123", 12345, Some(1699900000));
+        let event = parse_transaction(log, "sig123", 0, 12345, Some(1699900000));
         assert!(event.is_some());
         let event = event.unwrap();
         assert_eq!(event.event_type, "PackagePublished");
@@ -306,7 +604,7 @@ mod tests {
     #[test]
     fn test_parse_publish_event_case_insensitive() {
         let log = "program log: packagepublished {\"package\":\"my-pkg\",\"version\":\"1.0.0\"}";
-        let event = parse_transaction(log, "sig123", 12345, Some(1699900000));
+        let event = parse_transaction(log, "sig123", 0, 12345, Some(1699900000));
         assert!(event.is_some());
         let event = event.unwrap();
         assert_eq!(event.event_type, "PackagePublished");
@@ -317,20 +615,164 @@ mod tests {
     #[test]
     fn test_parse_update_and_download_variants() {
         let log = "Instruction: Update {\"package\":\"foo\",\"version\":\"2.0.0\"}";
-        let event = parse_transaction(log, "sigU", 1, None);
+        let event = parse_transaction(log, "sigU", 0, 1, None);
         assert!(event.is_some());
         assert_eq!(event.unwrap().event_type, "PackageUpdated");
 
         let log = "program log: download {\"package\":\"foo\",\"version\":\"2.0.0\"}";
-        let event = parse_transaction(log, "sigD", 2, None);
+        let event = parse_transaction(log, "sigD", 0, 2, None);
         assert!(event.is_some());
         assert_eq!(event.unwrap().event_type, "PackageDownloaded");
     }
 
+    #[test]
+    fn test_parse_dependencies_changed() {
+        let log = "Program log: 🔗 Dependencies changed: my-pkg@1.0.1 added=new-dep,other-dep removed=";
+        let event = parse_transaction(log, "sigC", 0, 4, None).unwrap();
+        assert_eq!(event.event_type, "DependenciesChanged");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, Some("1.0.1".to_string()));
+        assert_eq!(
+            parse_dependency_delta(log),
+            Some((vec!["new-dep".to_string(), "other-dep".to_string()], vec![]))
+        );
+
+        let log = "Program log: 🔗 Dependencies changed: my-pkg@2.0.0 added= removed=old-dep";
+        assert_eq!(parse_dependency_delta(log), Some((vec![], vec!["old-dep".to_string()])));
+        assert_eq!(parse_dependency_delta("Program log: unrelated"), None);
+    }
+
+    #[test]
+    fn test_parse_authority() {
+        let log = "Program log: 📦 Package published: my-pkg@1.0.0 by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+        let event = parse_transaction(log, "sigA", 0, 6, None).unwrap();
+        assert_eq!(event.event_type, "PackagePublished");
+        assert_eq!(event.version, Some("1.0.0".to_string()));
+        assert_eq!(extract_authority(log), Some("4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T".to_string()));
+        assert_eq!(event.authority, extract_authority(log));
+        assert_eq!(event.ipfs_hash, None);
+
+        let log = "Program log: 🔄 Package updated: my-pkg@1.1.0 by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+        let event = parse_transaction(log, "sigB", 0, 7, None).unwrap();
+        assert_eq!(event.event_type, "PackageUpdated");
+        assert_eq!(event.version, Some("1.1.0".to_string()));
+        assert_eq!(extract_authority("Program log: 📦 Package published: my-pkg@1.0.0"), None);
+
+        let log = "Program log: 🔑 Authority transferred: my-pkg@1.1.0 from 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T to 9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+        let event = parse_transaction(log, "sigT", 0, 8, None).unwrap();
+        assert_eq!(event.event_type, "AuthorityTransferred");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, Some("1.1.0".to_string()));
+        assert_eq!(
+            parse_authority_transfer(log),
+            Some((
+                "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T".to_string(),
+                "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin".to_string()
+            ))
+        );
+        assert_eq!(event.authority, Some("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin".to_string()));
+
+        let cid = format!("Qm{}", "c".repeat(44));
+        let log = format!("Program log: ipfs={} 📦 Package published: my-pkg@1.2.0 by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T", cid);
+        assert_eq!(parse_transaction(&log, "sigI", 0, 9, None).unwrap().ipfs_hash, Some(cid));
+    }
+
+    #[test]
+    fn test_parse_maintainer_changes() {
+        let log = "Program log: 👥 Maintainer added: my-pkg maintainer=9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+        let event = parse_transaction(log, "sigM", 0, 9, None).unwrap();
+        assert_eq!(event.event_type, "MaintainerAdded");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, None);
+        assert_eq!(
+            extract_maintainer(log),
+            Some((
+                "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin".to_string(),
+                "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T".to_string()
+            ))
+        );
+
+        let log = "Program log: 👥 Maintainer removed: alice/tools maintainer=9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+        let event = parse_transaction(log, "sigR", 0, 10, None).unwrap();
+        assert_eq!(event.event_type, "MaintainerRemoved");
+        assert_eq!(event.package_name, "alice/tools");
+        assert_eq!(extract_maintainer("Program log: 👥 Maintainer added: my-pkg"), None);
+        assert_eq!(extract_maintainer("Program log: 👥 Maintainer added: my-pkg maintainer=9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin"), None);
+    }
+
+    #[test]
+    fn test_parse_package_metadata() {
+        let log = "Program log: 📇 Package metadata: my-pkg@1.2.0 repository=https://github.com/a/published?x=1 homepage= keywords=solana,math";
+        let event = parse_transaction(log, "sigM", 0, 5, None).unwrap();
+        assert_eq!(event.event_type, "PackageMetadata");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, Some("1.2.0".to_string()));
+        assert_eq!(
+            parse_package_metadata(log),
+            Some(PackageMetadata {
+                repository: Some("https://github.com/a/published?x=1".to_string()),
+                homepage: None,
+                keywords: vec!["solana".to_string(), "math".to_string()],
+                readme_cid: None,
+            })
+        );
+        let log = "Program log: 📇 Package metadata: my-pkg@1.3.0 repository= homepage= keywords= readme=QmReadme123";
+        assert_eq!(parse_package_metadata(log).unwrap().readme_cid, Some("QmReadme123".to_string()));
+        assert_eq!(parse_package_metadata("Program log: 📦 Package published: my-pkg@1.2.0"), None);
+    }
+
+    #[test]
+    fn test_parse_package_dependencies() {
+        let log = "Program log: 🧩 Package dependencies: my-pkg@1.2.0 deps=math-utils@1.0.0,alice/publish-helpers@0.3.1";
+        let event = parse_transaction(log, "sigD", 0, 11, None).unwrap();
+        assert_eq!(event.event_type, "PackageDependencies");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, Some("1.2.0".to_string()));
+        assert_eq!(
+            parse_package_dependencies(log),
+            Some(vec![
+                ("math-utils".to_string(), "1.0.0".to_string()),
+                ("alice/publish-helpers".to_string(), "0.3.1".to_string()),
+            ])
+        );
+
+        assert_eq!(parse_package_dependencies("Program log: 🧩 Package dependencies: my-pkg@1.2.0 deps=broken"), None);
+        assert_eq!(parse_package_dependencies("Program log: 📦 Package published: my-pkg@1.2.0"), None);
+    }
+
+    #[test]
+    fn test_parse_external_dependencies() {
+        let log = "Program log: 📚 Package external dependencies: my-pkg@1.2.0 deps=rust|serde|^1.0|;npm|left-pad|1.3.0|https://registry.npmjs.org";
+        let event = parse_transaction(log, "sigE", 0, 12, None).unwrap();
+        assert_eq!(event.event_type, "PackageExternalDependencies");
+        assert_eq!(event.package_name, "my-pkg");
+        assert_eq!(event.version, Some("1.2.0".to_string()));
+        assert_eq!(
+            parse_external_dependencies(log),
+            Some(vec![
+                ExternalDependency {
+                    name: "serde".to_string(),
+                    version: "^1.0".to_string(),
+                    dep_type: "rust".to_string(),
+                    registry: None,
+                },
+                ExternalDependency {
+                    name: "left-pad".to_string(),
+                    version: "1.3.0".to_string(),
+                    dep_type: "npm".to_string(),
+                    registry: Some("https://registry.npmjs.org".to_string()),
+                },
+            ])
+        );
+
+        assert_eq!(parse_external_dependencies("Program log: 📚 Package external dependencies: my-pkg@1.2.0 deps=rust|serde"), None);
+        assert_eq!(parse_package_dependencies(log), None);
+    }
+
     #[test]
     fn test_parse_publish_missing_version() {
         let log = "Program log: PackagePublished {\"package\":\"nover\"}";
-        let event = parse_transaction(log, "sigX", 3, None);
+        let event = parse_transaction(log, "sigX", 0, 3, None);
         assert!(event.is_some());
         let event = event.unwrap();
         assert_eq!(event.package_name, "nover");
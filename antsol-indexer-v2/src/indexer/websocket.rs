@@ -0,0 +1,114 @@
+use crate::db::Pool;
+use metrics::counter;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+use tokio_stream::StreamExt;
+
+use super::accounts::PackageAccounts;
+use super::broadcast::EventBus;
+use super::listener::ingest_logs;
+use crate::telemetry::RPC_ERRORS;
+
+/// First reconnect delay; doubles per failed attempt up to [`MAX_BACKOFF`]
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Ingest the program's transactions from `logsSubscribe` notifications as
+/// they arrive, reconnecting with backoff whenever the socket drops.
+///
+/// This never touches the signature cursor: the polling listener keeps
+/// running as a gap-filler and walks every signature after its cursor, so
+/// anything missed while disconnected is still picked up. Events the
+/// subscription stored first are skipped there.
+pub async fn start_log_subscription(
+    pool: Pool,
+    ws_url: String,
+    rpc_url: String,
+    program_id_str: String,
+    commitment: CommitmentConfig,
+    events: EventBus,
+) {
+    let program_id = match Pubkey::from_str(&program_id_str) {
+        Ok(pk) => pk,
+        Err(e) => {
+            tracing::error!("Invalid program ID: {}", e);
+            return;
+        }
+    };
+    // Notifications carry no block time, so it is looked up per transaction
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(rpc_url, commitment));
+    let accounts = PackageAccounts::new(rpc_client.clone(), program_id);
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        match subscribe(&pool, &ws_url, &rpc_client, &accounts, &program_id, commitment, &events).await {
+            Ok(received) => {
+                tracing::warn!("Log subscription closed after {} notifications; reconnecting", received);
+                if received > 0 {
+                    backoff = INITIAL_BACKOFF;
+                }
+            }
+            Err(e) => {
+                counter!(RPC_ERRORS, "operation" => "logs_subscribe").increment(1);
+                tracing::error!("Log subscription to {} failed: {}", ws_url, e);
+            }
+        }
+        tracing::info!("Reconnecting log subscription in {:?}", backoff);
+        sleep(backoff).await;
+        backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+    }
+}
+
+/// One connection: subscribe and ingest until the stream ends, returning
+/// how many notifications arrived
+async fn subscribe(
+    pool: &Pool,
+    ws_url: &str,
+    rpc_client: &RpcClient,
+    accounts: &PackageAccounts,
+    program_id: &Pubkey,
+    commitment: CommitmentConfig,
+    events: &EventBus,
+) -> Result<usize, anyhow::Error> {
+    let client = PubsubClient::new(ws_url).await?;
+    let (mut notifications, unsubscribe) = client
+        .logs_subscribe(
+            RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+            RpcTransactionLogsConfig { commitment: Some(commitment) },
+        )
+        .await?;
+    tracing::info!("Subscribed to logs for program {} via {}", program_id, ws_url);
+
+    let mut received = 0;
+    while let Some(notification) = notifications.next().await {
+        received += 1;
+        let slot = notification.context.slot;
+        let logs = notification.value;
+        // Failed transactions emit no events
+        if logs.err.is_some() {
+            continue;
+        }
+        let block_time = match rpc_client.get_block_time(slot).await {
+            Ok(block_time) => Some(block_time),
+            Err(e) => {
+                // Close enough for a block that was just confirmed
+                tracing::debug!("Block time for slot {} unavailable ({}); using the current time", slot, e);
+                Some(chrono::Utc::now().timestamp())
+            }
+        };
+        // The gap-filler retries anything that failed here
+        if let Err(e) = ingest_logs(pool, events, Some(accounts), &logs.signature, slot, block_time, &logs.logs, program_id).await {
+            tracing::warn!("Failed to ingest transaction {}: {}", logs.signature, e);
+        }
+    }
+
+    drop(notifications);
+    unsubscribe().await;
+    Ok(received)
+}
@@ -0,0 +1,114 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (ms) of the gateway latency histogram buckets
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+/// Result of a single gateway request.
+///
+/// Serialized names match the CLI's `GatewayOutcome` (`antsol gateways stats`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayOutcome {
+    Success,
+    HttpError,
+    Timeout,
+    ConnectError,
+    VerificationFailed,
+}
+
+impl GatewayOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GatewayOutcome::Success => "success",
+            GatewayOutcome::HttpError => "http_error",
+            GatewayOutcome::Timeout => "timeout",
+            GatewayOutcome::ConnectError => "connect_error",
+            GatewayOutcome::VerificationFailed => "verification_failed",
+        }
+    }
+}
+
+/// Counters and latency histogram for one gateway
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GatewaySnapshot {
+    pub gateway: String,
+    /// Requests per outcome, keyed by `GatewayOutcome::as_str`
+    pub outcomes: BTreeMap<&'static str, u64>,
+    /// Cumulative counts per bucket in `LATENCY_BUCKETS_MS`, plus a final +Inf bucket
+    pub latency_buckets: Vec<u64>,
+    pub latency_sum_ms: u64,
+    pub latency_count: u64,
+}
+
+impl GatewaySnapshot {
+    pub fn successes(&self) -> u64 {
+        self.outcomes.get(GatewayOutcome::Success.as_str()).copied().unwrap_or(0)
+    }
+
+    pub fn attempts(&self) -> u64 {
+        self.outcomes.values().sum()
+    }
+
+    /// Success rate with add-one smoothing (0.5 for an unused gateway),
+    /// the same score the CLI ranks gateways by
+    pub fn score(&self) -> f64 {
+        (self.successes() as f64 + 1.0) / (self.attempts() as f64 + 2.0)
+    }
+}
+
+/// Process-wide per-gateway metrics for IPFS fetches made by the indexer
+#[derive(Debug, Default)]
+pub struct GatewayMetrics {
+    gateways: Mutex<BTreeMap<String, GatewaySnapshot>>,
+}
+
+impl GatewayMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, gateway: &str, outcome: GatewayOutcome, latency: Duration) {
+        let mut gateways = self.gateways.lock().unwrap();
+        let entry = gateways.entry(gateway.to_string()).or_insert_with(|| GatewaySnapshot {
+            gateway: gateway.to_string(),
+            latency_buckets: vec![0; LATENCY_BUCKETS_MS.len() + 1],
+            ..Default::default()
+        });
+        *entry.outcomes.entry(outcome.as_str()).or_insert(0) += 1;
+
+        let ms = latency.as_millis() as u64;
+        for (i, bound) in LATENCY_BUCKETS_MS.iter().enumerate() {
+            if ms <= *bound {
+                entry.latency_buckets[i] += 1;
+            }
+        }
+        entry.latency_buckets[LATENCY_BUCKETS_MS.len()] += 1;
+        entry.latency_sum_ms += ms;
+        entry.latency_count += 1;
+    }
+
+    pub fn snapshot(&self) -> Vec<GatewaySnapshot> {
+        self.gateways.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Best gateway by smoothed success rate, then mean latency
+    pub fn preferred(&self) -> Option<String> {
+        let snapshot = self.snapshot();
+        snapshot
+            .iter()
+            .max_by(|a, b| {
+                a.score()
+                    .partial_cmp(&b.score())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then_with(|| {
+                        let la = a.latency_sum_ms as f64 / a.latency_count.max(1) as f64;
+                        let lb = b.latency_sum_ms as f64 / b.latency_count.max(1) as f64;
+                        lb.partial_cmp(&la).unwrap_or(std::cmp::Ordering::Equal)
+                    })
+            })
+            .map(|s| s.gateway.clone())
+    }
+}
@@ -2,3 +2,5 @@ pub mod config;
 pub mod db;
 pub mod api;
 pub mod indexer;
+pub mod ipfs;
+pub mod telemetry;
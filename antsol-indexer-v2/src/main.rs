@@ -2,6 +2,7 @@ mod config;
 mod db;
 mod api;
 mod indexer;
+mod telemetry;
 
 use std::net::SocketAddr;
 use tower_http::cors::{Any, CorsLayer};
@@ -19,6 +20,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("Starting AntSol Indexer v2");
 
+    // Prometheus recorder; metrics recorded before this point are dropped
+    let metrics = telemetry::install_recorder();
+
     // Load configuration
     let config = config::Config::from_env()?;
     tracing::info!("Configuration loaded successfully");
@@ -31,18 +35,72 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     db::run_migrations(&pool).await?;
     tracing::info!("Database migrations completed");
 
+    // One-shot catch-up: `antsol-indexer-v2 backfill --from-slot X [--to-slot Y]`
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(backfill) = indexer::backfill::BackfillArgs::parse(&args)? {
+        let report = indexer::backfill::run_backfill(
+            &pool,
+            &config.solana_rpc_url,
+            &config.antsol_program_id,
+            config.commitment,
+            &backfill,
+        ).await.map_err(|e| e.to_string())?;
+        tracing::info!(
+            "Backfill complete: {} transactions, {} events ingested, {} failed",
+            report.transactions, report.events, report.failed
+        );
+        match report.completed_through {
+            Some(slot) => tracing::info!("Contiguous range completed through slot {}", slot),
+            None if report.transactions > 0 => tracing::warn!("No contiguous range completed; re-run the backfill"),
+            None => {}
+        }
+        return Ok(());
+    }
+
+    // New events fan out from the indexer to `/api/events/stream`
+    let events = indexer::broadcast::EventBus::new();
+
     // Start blockchain indexer in background
     let indexer_pool = pool.clone();
+    let indexer_events = events.clone();
     let rpc_url = config.solana_rpc_url.clone();
     let program_id = config.antsol_program_id.clone();
     
     let start_slot_override = config.start_slot;
-    let poll_interval = config.poll_interval_secs;
+    let commitment = config.commitment;
+    // In websocket mode polling only fills gaps left by disconnects
+    let poll_interval = match config.mode {
+        config::IndexerMode::Polling => config.poll_interval_secs,
+        config::IndexerMode::Websocket => {
+            tokio::spawn(indexer::websocket::start_log_subscription(
+                pool.clone(),
+                config.ws_url.clone(),
+                config.solana_rpc_url.clone(),
+                config.antsol_program_id.clone(),
+                commitment,
+                events.clone(),
+            ));
+            tracing::info!("Log subscription started; gap-filling every {}s", config.gap_fill_interval_secs);
+            config.gap_fill_interval_secs
+        }
+    };
     tokio::spawn(async move {
-        indexer::start_indexer(indexer_pool, rpc_url, program_id, start_slot_override, poll_interval).await;
+        indexer::start_indexer(indexer_pool, rpc_url, program_id, start_slot_override, poll_interval, commitment, indexer_events).await;
     });
     tracing::info!("Blockchain indexer started");
 
+    if let Some(days) = config.download_retention_days {
+        tokio::spawn(db::run_download_rollups(pool.clone(), days));
+    }
+
+    match config.enrichment.clone() {
+        Some(enrichment) => {
+            tracing::info!("Enriching versions from {} ({} at a time)", enrichment.gateway, enrichment.concurrency);
+            tokio::spawn(indexer::enrichment::run_enrichment(pool.clone(), enrichment));
+        }
+        None => tracing::info!("ENRICHMENT_CONCURRENCY is 0; version tarballs are not read"),
+    }
+
     // Create CORS layer
     let cors = CorsLayer::new()
         .allow_origin(Any)
@@ -50,7 +108,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .allow_headers(Any);
 
     // Create router with CORS
-    let app = api::routes::create_router(pool)
+    if config.ingest_api_keys.is_empty() {
+        tracing::info!("INGEST_API_KEYS is not set; POST /api/ingest is disabled");
+    }
+    let settings = api::access::ApiSettings {
+        metrics: Some(metrics),
+        ..api::access::ApiSettings::from_config(&config)
+    };
+    let app = api::routes::create_router_with(pool, events, settings)
         .layer(cors)
         .layer(tower_http::compression::CompressionLayer::new());
 
@@ -60,7 +125,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Start server
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -0,0 +1,85 @@
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Events stored, labelled by `type`
+pub const EVENTS_INGESTED: &str = "antsol_events_ingested_total";
+/// Program transaction signatures the listener has moved past
+pub const SIGNATURES_PROCESSED: &str = "antsol_signatures_processed_total";
+/// Newest slot the listener has fully processed
+pub const LAST_PROCESSED_SLOT: &str = "antsol_indexer_last_processed_slot";
+/// Slots between the RPC node's head and the last processed slot
+pub const INDEXER_LAG_SLOTS: &str = "antsol_indexer_lag_slots";
+/// Failed RPC calls, labelled by `operation`
+pub const RPC_ERRORS: &str = "antsol_rpc_errors_total";
+/// Tarball fetches by the enrichment worker, labelled by `outcome`
+pub const ENRICHMENT_FETCHES: &str = "antsol_enrichment_fetches_total";
+/// Time spent in each `db::queries` function, labelled by `query`
+pub const DB_QUERY_SECONDS: &str = "antsol_db_query_duration_seconds";
+/// Requests answered, labelled by `method`, `route` and `status`
+pub const HTTP_REQUESTS: &str = "antsol_http_requests_total";
+/// Time to the response head, labelled by `method` and `route`
+pub const HTTP_REQUEST_SECONDS: &str = "antsol_http_request_duration_seconds";
+
+/// Histogram buckets for every `*_seconds` metric, 1ms to 10s
+const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+static RECORDER: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the global Prometheus recorder; later calls return the same
+/// handle, so tests sharing a process can each ask for it
+pub fn install_recorder() -> PrometheusHandle {
+    RECORDER
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+                .expect("latency buckets are not empty")
+                .install_recorder()
+                .expect("no other metrics recorder is installed")
+        })
+        .clone()
+}
+
+/// Records [`DB_QUERY_SECONDS`] for `query` when dropped, so early returns
+/// and errors are timed too
+pub struct QueryTimer {
+    query: &'static str,
+    started: Instant,
+}
+
+impl QueryTimer {
+    pub fn start(query: &'static str) -> Self {
+        Self { query, started: Instant::now() }
+    }
+}
+
+impl Drop for QueryTimer {
+    fn drop(&mut self) {
+        histogram!(DB_QUERY_SECONDS, "query" => self.query).record(self.started.elapsed().as_secs_f64());
+    }
+}
+
+/// Count and time each request under its route template (`/api/packages/:name`),
+/// keeping one series per route rather than per URL
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    counter!(HTTP_REQUESTS, "method" => method.clone(), "route" => route.clone(), "status" => status).increment(1);
+    histogram!(HTTP_REQUEST_SECONDS, "method" => method, "route" => route).record(started.elapsed().as_secs_f64());
+    response
+}
@@ -0,0 +1,224 @@
+#![allow(dead_code)]
+
+use antsol_indexer_v2::api::access::ApiSettings;
+use antsol_indexer_v2::api::routes::create_router_with;
+use antsol_indexer_v2::db::{self, Pool};
+use antsol_indexer_v2::indexer::broadcast::EventBus;
+use axum::body::{to_bytes, Body};
+use axum::extract::ConnectInfo;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use chrono::{DateTime, Utc};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+/// Pool that is never connected; enough for routes that don't touch the database
+pub async fn offline_pool() -> Pool {
+    db::create_pool("postgresql://antsol@127.0.0.1:1/antsol").await.unwrap()
+}
+
+/// Migrated pool for `TEST_DATABASE_URL`, or a fresh in-memory SQLite database
+pub async fn test_pool() -> Pool {
+    let url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".to_string());
+    let pool = db::create_pool(&url).await.unwrap();
+    db::run_migrations(&pool).await.unwrap();
+    pool
+}
+
+/// Record `count` downloads of a version at `at`, bypassing deduplication
+pub async fn insert_download_events(pool: &Pool, package_id: i32, version_id: i32, at: DateTime<Utc>, count: i32) {
+    match pool {
+        Pool::Postgres(pool) => {
+            pool.get().await.unwrap().execute(
+                "INSERT INTO download_events (package_id, version_id, downloaded_at)
+                 SELECT $1, $2, $3 FROM generate_series(1, $4)",
+                &[&package_id, &version_id, &at, &count],
+            ).await.unwrap();
+        }
+        Pool::Sqlite(sqlite) => {
+            sqlite.run(|conn| {
+                let mut insert = conn.prepare("INSERT INTO download_events (package_id, version_id, downloaded_at) VALUES (?1, ?2, ?3)")?;
+                for _ in 0..count {
+                    insert.execute(rusqlite::params![package_id, version_id, db::sqlite::timestamp(at)])?;
+                }
+                Ok::<_, rusqlite::Error>(())
+            }).await.unwrap();
+        }
+    }
+}
+
+/// Issue a GET against the router and return status, headers and JSON body
+pub async fn get(router: Router, uri: &str) -> (StatusCode, axum::http::HeaderMap, serde_json::Value) {
+    let response = router
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, headers, body)
+}
+
+/// Issue a bodiless POST with the given headers and return status and JSON body
+pub async fn post(router: Router, uri: &str, headers: &[(&str, &str)]) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder().method("POST").uri(uri);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    send(router, request).await
+}
+
+/// Like [`post`], arriving over a connection from `peer`
+pub async fn post_from(router: Router, uri: &str, peer: SocketAddr, headers: &[(&str, &str)]) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder().method("POST").uri(uri).extension(ConnectInfo(peer));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    send(router, request).await
+}
+
+async fn send(router: Router, request: axum::http::request::Builder) -> (StatusCode, serde_json::Value) {
+    let response = router
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, body)
+}
+
+/// Router like [`ingest_router`] that also serves `/metrics` from the process-wide recorder
+pub fn metrics_router(pool: Pool) -> Router {
+    let settings = ApiSettings {
+        ingest_api_keys: vec![INGEST_KEY.to_string()],
+        metrics: Some(antsol_indexer_v2::telemetry::install_recorder()),
+        ..Default::default()
+    };
+    create_router_with(pool, EventBus::new(), settings)
+}
+
+/// Sum of the samples of `name` whose labels contain every one of `labels`
+/// (e.g. `route="/api/ingest"`). Tests share the recorder, so compare before and after.
+pub async fn metric_value(router: Router, name: &str, labels: &[&str]) -> f64 {
+    let response = router
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec())
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with(&format!("{}{{", name)) || line.starts_with(&format!("{} ", name)))
+        .filter(|line| labels.iter().all(|label| line.contains(label)))
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .sum()
+}
+
+/// Key accepted by [`ingest_router`]
+pub const INGEST_KEY: &str = "test-ingest-key";
+
+/// Router that accepts [`INGEST_KEY`] on `POST /api/ingest`
+pub fn ingest_router(pool: Pool) -> Router {
+    let settings = ApiSettings { ingest_api_keys: vec![INGEST_KEY.to_string()], ..Default::default() };
+    create_router_with(pool, EventBus::new(), settings)
+}
+
+/// POST a JSON body with the given headers and return status and JSON body
+pub async fn post_json(
+    router: Router,
+    uri: &str,
+    headers: &[(&str, &str)],
+    json: serde_json::Value,
+) -> (StatusCode, serde_json::Value) {
+    let mut request = Request::builder().method("POST").uri(uri).header("content-type", "application/json");
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = router.oneshot(request.body(Body::from(json.to_string())).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+    (status, body)
+}
+
+/// The OpenAPI document served by the router
+pub async fn openapi_spec() -> serde_json::Value {
+    let router = antsol_indexer_v2::api::routes::create_router(offline_pool().await);
+    let (status, _, spec) = get(router, "/api/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+    spec
+}
+
+/// Assert `body` matches the JSON schema documented for `method path` answering `status`
+pub fn assert_conforms(spec: &serde_json::Value, method: &str, path: &str, status: StatusCode, body: &serde_json::Value) {
+    let schema = &spec["paths"][path][method]["responses"][status.as_str()]["content"]["application/json"]["schema"];
+    assert!(!schema.is_null(), "no documented {} response for {} {}", status, method, path);
+    if let Err(problem) = check_schema(spec, schema, body, "$") {
+        panic!("{} {} {} does not match its schema: {}\n{}", method, path, status, problem, body);
+    }
+}
+
+/// Validate the OpenAPI 3.0 schema subset utoipa emits
+fn check_schema(spec: &serde_json::Value, schema: &serde_json::Value, value: &serde_json::Value, at: &str) -> Result<(), String> {
+    use serde_json::Value;
+
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        return check_schema(spec, &spec["components"]["schemas"][name], value, at);
+    }
+    if value.is_null() && schema["nullable"] == true {
+        return Ok(());
+    }
+    if let Some(all) = schema["allOf"].as_array() {
+        for part in all {
+            check_schema(spec, part, value, at)?;
+        }
+    }
+    if let Some(any) = schema["oneOf"].as_array().or(schema["anyOf"].as_array()) {
+        if !any.iter().any(|option| check_schema(spec, option, value, at).is_ok()) {
+            return Err(format!("{} matches none of the alternatives", at));
+        }
+    }
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            return Err(format!("{} = {} is not one of {:?}", at, value, allowed));
+        }
+    }
+    let kind_ok = match schema["type"].as_str() {
+        None => true,
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("string") => value.is_string(),
+        Some("integer") => value.is_i64() || value.is_u64(),
+        Some("number") => value.is_number(),
+        Some("boolean") => value.is_boolean(),
+        Some(_) => true,
+    };
+    if !kind_ok {
+        return Err(format!("{} = {} is not a {}", at, value, schema["type"]));
+    }
+    if let Value::Object(fields) = value {
+        for required in schema["required"].as_array().into_iter().flatten() {
+            let key = required.as_str().unwrap_or_default();
+            if !fields.contains_key(key) {
+                return Err(format!("{} is missing required field '{}'", at, key));
+            }
+        }
+        if let Some(properties) = schema["properties"].as_object() {
+            for (key, property) in properties {
+                if let Some(field) = fields.get(key) {
+                    check_schema(spec, property, field, &format!("{}.{}", at, key))?;
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            check_schema(spec, item_schema, item, &format!("{}[{}]", at, i))?;
+        }
+    }
+    Ok(())
+}
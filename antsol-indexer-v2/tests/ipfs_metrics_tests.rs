@@ -0,0 +1,53 @@
+use antsol_indexer_v2::ipfs::{GatewayMetrics, GatewayOutcome, LATENCY_BUCKETS_MS};
+use std::time::Duration;
+
+#[test]
+fn test_gateway_counters_and_histogram() {
+    let metrics = GatewayMetrics::new();
+    metrics.record("https://ipfs.io", GatewayOutcome::Success, Duration::from_millis(40));
+    metrics.record("https://ipfs.io", GatewayOutcome::Success, Duration::from_millis(300));
+    metrics.record("https://ipfs.io", GatewayOutcome::Timeout, Duration::from_millis(20000));
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.len(), 1);
+    let gw = &snapshot[0];
+    assert_eq!(gw.outcomes["success"], 2);
+    assert_eq!(gw.outcomes["timeout"], 1);
+    assert_eq!(gw.attempts(), 3);
+    assert_eq!(gw.latency_count, 3);
+    assert_eq!(gw.latency_sum_ms, 20340);
+
+    // Cumulative buckets: <=50 has 1, <=500 has 2, only +Inf has all 3
+    assert_eq!(gw.latency_buckets.len(), LATENCY_BUCKETS_MS.len() + 1);
+    assert_eq!(gw.latency_buckets[0], 1);
+    assert_eq!(gw.latency_buckets[3], 2);
+    assert_eq!(gw.latency_buckets[LATENCY_BUCKETS_MS.len() - 1], 2);
+    assert_eq!(gw.latency_buckets[LATENCY_BUCKETS_MS.len()], 3);
+}
+
+#[test]
+fn test_preferred_gateway() {
+    let metrics = GatewayMetrics::new();
+    assert_eq!(metrics.preferred(), None);
+
+    metrics.record("https://flaky", GatewayOutcome::HttpError, Duration::from_millis(10));
+    metrics.record("https://slow", GatewayOutcome::Success, Duration::from_millis(900));
+    metrics.record("https://fast", GatewayOutcome::Success, Duration::from_millis(90));
+
+    assert_eq!(metrics.preferred(), Some("https://fast".to_string()));
+}
+
+#[test]
+fn test_outcome_names_match_cli() {
+    let names: Vec<String> = [
+        GatewayOutcome::Success,
+        GatewayOutcome::HttpError,
+        GatewayOutcome::Timeout,
+        GatewayOutcome::ConnectError,
+        GatewayOutcome::VerificationFailed,
+    ]
+    .iter()
+    .map(|o| serde_json::to_value(o).unwrap().as_str().unwrap().to_string())
+    .collect();
+    assert_eq!(names, vec!["success", "http_error", "timeout", "connect_error", "verification_failed"]);
+}
@@ -3,7 +3,7 @@ use antsol_indexer_v2::indexer::parser::parse_transaction;
 #[test]
 fn test_parse_json_format_publish() {
     let log = r#"Program log: PackagePublished {"package":"test-pkg","version":"1.0.0"}"#;
-    let event = parse_transaction(log, "sig123abc", 12345, Some(1699900000));
+    let event = parse_transaction(log, "sig123abc", 0, 12345, Some(1699900000));
     
     assert!(event.is_some());
     let event = event.unwrap();
@@ -17,7 +17,7 @@ fn test_parse_json_format_publish() {
 #[test]
 fn test_parse_kv_format_update() {
     let log = "Program log: Instruction: Update package=my-package version=2.0.0";
-    let event = parse_transaction(log, "sig456def", 67890, Some(1699900000));
+    let event = parse_transaction(log, "sig456def", 0, 67890, Some(1699900000));
     
     assert!(event.is_some());
     let event = event.unwrap();
@@ -29,7 +29,7 @@ fn test_parse_kv_format_update() {
 #[test]
 fn test_parse_colon_format_download() {
     let log = "Program log: Download package: awesome-lib, version: 3.5.1";
-    let event = parse_transaction(log, "sig789ghi", 11111, Some(1699900000));
+    let event = parse_transaction(log, "sig789ghi", 0, 11111, Some(1699900000));
     
     assert!(event.is_some());
     let event = event.unwrap();
@@ -41,7 +41,7 @@ fn test_parse_colon_format_download() {
 #[test]
 fn test_parse_no_version() {
     let log = r#"Program log: PackagePublished {"package":"no-version-pkg"}"#;
-    let event = parse_transaction(log, "sigXYZ", 22222, None);
+    let event = parse_transaction(log, "sigXYZ", 0, 22222, None);
     
     assert!(event.is_some());
     let event = event.unwrap();
@@ -52,7 +52,7 @@ fn test_parse_no_version() {
 #[test]
 fn test_parse_invalid_log() {
     let log = "Program log: Some random log message";
-    let event = parse_transaction(log, "sigABC", 33333, None);
+    let event = parse_transaction(log, "sigABC", 0, 33333, None);
     
     assert!(event.is_none());
 }
@@ -66,7 +66,7 @@ fn test_parse_multiple_formats() {
     ];
     
     for (i, (log, expected_name, expected_version)) in logs.iter().enumerate() {
-        let event = parse_transaction(log, &format!("sig{}", i), i as i64, None);
+        let event = parse_transaction(log, &format!("sig{}", i), 0, i as i64, None);
         assert!(event.is_some(), "Failed to parse: {}", log);
         let event = event.unwrap();
         assert_eq!(event.package_name, *expected_name);
@@ -77,7 +77,7 @@ fn test_parse_multiple_formats() {
 #[test]
 fn test_parse_quoted_values() {
     let log = r#"Program log: Update package="quoted-pkg" version="1.2.3""#;
-    let event = parse_transaction(log, "sig999", 44444, None);
+    let event = parse_transaction(log, "sig999", 0, 44444, None);
     
     assert!(event.is_some());
     let event = event.unwrap();
@@ -89,10 +89,60 @@ fn test_parse_quoted_values() {
 fn test_parse_edge_cases() {
     // Package name with special characters
     let log = r#"Program log: PackagePublished {"package":"@scope/my-pkg","version":"1.0.0-beta.1"}"#;
-    let event = parse_transaction(log, "sigEDGE", 55555, None);
+    let event = parse_transaction(log, "sigEDGE", 0, 55555, None);
     
     assert!(event.is_some());
     let event = event.unwrap();
     assert_eq!(event.package_name, "@scope/my-pkg");
     assert_eq!(event.version, Some("1.0.0-beta.1".to_string()));
 }
+
+#[test]
+fn test_parse_metadata_with_url_lookalikes() {
+    // URLs may contain ':' and '@', which must not confuse the name@version lookup
+    let log = "Program log: 📇 Package metadata: url-pkg@0.2.0 repository=https://git@example.com:22/x homepage=http://a.dev keywords=";
+    let event = parse_transaction(log, "sigURL", 0, 8, None).unwrap();
+    assert_eq!(event.event_type, "PackageMetadata");
+    assert_eq!(event.package_name, "url-pkg");
+    assert_eq!(event.version, Some("0.2.0".to_string()));
+}
+
+#[test]
+fn test_parse_namespace_scoped_publish() {
+    let log = "Program log: 📦 Package published: alice/token-utils@1.0.0 by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+    let event = parse_transaction(log, "sigNS", 0, 9, None).unwrap();
+    assert_eq!(event.event_type, "PackagePublished");
+    assert_eq!(event.package_name, "alice/token-utils");
+    assert_eq!(event.version, Some("1.0.0".to_string()));
+
+    let log = "Program log: 📇 Package metadata: alice/token-utils@1.0.0 repository= homepage= keywords=";
+    assert_eq!(parse_transaction(log, "sigNS", 0, 9, None).unwrap().package_name, "alice/token-utils");
+
+    // Registering a namespace is not a package event
+    let log = "Program log: 🏷️ Namespace registered: alice by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+    assert!(parse_transaction(log, "sigNS", 0, 10, None).is_none());
+}
+
+#[test]
+fn test_parse_record_download() {
+    let log = "Program log: 📥 Package downloaded: alice/token-utils@1.2.0 downloads=7";
+    let event = parse_transaction(log, "sigDL", 0, 12, Some(1699900000)).unwrap();
+    assert_eq!(event.event_type, "PackageDownloaded");
+    assert_eq!(event.package_name, "alice/token-utils");
+    assert_eq!(event.version, Some("1.2.0".to_string()));
+
+    // Anchor's own instruction log names no package
+    assert!(parse_transaction("Program log: Instruction: RecordDownload", "sigDL", 0, 12, None).is_none());
+}
+
+#[test]
+fn test_parse_package_frozen() {
+    let log = "Program log: 🔒 Package frozen: alice/token-utils@1.2.0 by 4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+    let event = parse_transaction(log, "sigFZ", 0, 14, Some(1699900000)).unwrap();
+    assert_eq!(event.event_type, "PackageFrozen");
+    assert_eq!(event.package_name, "alice/token-utils");
+    assert_eq!(event.version, Some("1.2.0".to_string()));
+    assert_eq!(event.authority, Some("4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T".to_string()));
+
+    assert!(parse_transaction("Program log: Instruction: FreezePackage", "sigFZ", 0, 14, None).is_none());
+}
@@ -0,0 +1,1465 @@
+mod common;
+
+use antsol_indexer_v2::api::handlers::{encode_path_segment, parse_window, DependentsDepth};
+use antsol_indexer_v2::api::access::ApiSettings;
+use antsol_indexer_v2::api::routes::{create_router, create_router_with};
+use antsol_indexer_v2::config::RateLimitConfig;
+use antsol_indexer_v2::db::models::VersionEnrichment;
+use antsol_indexer_v2::db::{queries, Pool};
+use antsol_indexer_v2::indexer::accounts::{AccountFetcher, PackageAccounts};
+use antsol_indexer_v2::indexer::broadcast::EventBus;
+use antsol_indexer_v2::indexer::listener::{ingest_event, ingest_events, ingest_logs};
+use antsol_indexer_v2::indexer::parser::parse_transaction;
+use axum::http::{header, StatusCode};
+use solana_sdk::pubkey::Pubkey;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+#[test]
+fn test_encode_path_segment() {
+    assert_eq!(encode_path_segment("plain-pkg"), "plain-pkg");
+    assert_eq!(encode_path_segment("@scope/pkg"), "%40scope%2Fpkg");
+}
+
+#[test]
+fn test_parse_trending_window() {
+    assert_eq!(parse_window("24h"), Some(86400.0));
+    assert_eq!(parse_window("7d"), Some(7.0 * 86400.0));
+    assert_eq!(parse_window("2w"), Some(14.0 * 86400.0));
+    assert_eq!(parse_window("90d"), Some(90.0 * 86400.0));
+    for invalid in ["", "d", "0d", "91d", "7", "7m", "-1d", "1.5d"] {
+        assert_eq!(parse_window(invalid), None, "{}", invalid);
+    }
+}
+
+#[tokio::test]
+async fn test_invalid_trending_window_is_rejected() {
+    let router = create_router(common::offline_pool().await);
+    let (status, _, body) = common::get(router, "/api/stats/trending?window=1y").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("1y"));
+}
+
+#[tokio::test]
+async fn test_unencoded_scoped_package_redirects() {
+    let router = create_router(common::offline_pool().await);
+    let (status, headers, body) = common::get(router, "/api/packages/@scope/pkg").await;
+
+    assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(headers[header::LOCATION], "/api/packages/%40scope%2Fpkg");
+    assert!(body["error"].as_str().unwrap().contains("percent-encoded"));
+}
+
+#[tokio::test]
+async fn test_unencoded_scoped_events_redirects() {
+    let router = create_router(common::offline_pool().await);
+    let (status, headers, _) = common::get(router, "/api/events/@scope/pkg").await;
+
+    assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(headers[header::LOCATION], "/api/events/%40scope%2Fpkg");
+}
+
+#[tokio::test]
+async fn test_unencoded_namespace_name_redirects() {
+    let router = create_router(common::offline_pool().await);
+    let (status, headers, _) = common::get(router, "/api/packages/alice/token-utils").await;
+
+    assert_eq!(status, StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(headers[header::LOCATION], "/api/packages/alice%2Ftoken-utils");
+}
+
+#[tokio::test]
+async fn test_two_segment_path_without_a_valid_scope_is_not_found() {
+    for uri in ["/api/packages/Plain/extra", "/api/packages/-plain/extra"] {
+        let router = create_router(common::offline_pool().await);
+        let (status, _, body) = common::get(router, uri).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND, "{}", uri);
+        assert!(body["error"].as_str().is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_scoped_and_unscoped_lookups() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    queries::insert_package(&db, "@scope/pkg", "author1", None, None, None).await.unwrap();
+    queries::insert_package(&db, "plain-pkg", "author1", None, None, None).await.unwrap();
+
+    for uri in [
+        "/api/packages/%40scope%2Fpkg",
+        "/api/packages/@scope%2Fpkg",
+        "/api/packages?name=%40scope%2Fpkg",
+    ] {
+        let (status, _, body) = common::get(create_router(pool.clone()), uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(body["data"]["name"], "@scope/pkg", "{}", uri);
+    }
+
+    for uri in ["/api/packages/plain-pkg", "/api/packages?name=plain-pkg"] {
+        let (status, _, body) = common::get(create_router(pool.clone()), uri).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+        assert_eq!(body["data"]["name"], "plain-pkg", "{}", uri);
+    }
+
+    let (status, _, _) = common::get(create_router(pool.clone()), "/api/events/%40scope%2Fpkg").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _, _) = common::get(create_router(pool), "/api/packages?name=%40scope%2Fmissing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_package_events_include_dependency_changes() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let sig = format!("depsig-{}", std::process::id());
+    queries::insert_event(&db, "PackageUpdated", "dep-delta-pkg", Some("1.0.1"), &sig, 0, 42, None, None, None).await.unwrap();
+    queries::insert_dependency_change(
+        &db,
+        "dep-delta-pkg",
+        "1.0.1",
+        &["new-dep".to_string()],
+        &["old-dep".to_string()],
+        &sig,
+        42,
+    ).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool), "/api/events/dep-delta-pkg").await;
+    assert_eq!(status, StatusCode::OK);
+    let event = body["data"].as_array().unwrap().iter()
+        .find(|e| e["transaction_signature"] == sig.as_str())
+        .expect("event listed");
+    assert_eq!(event["dependency_change"]["added"][0], "new-dep");
+    assert_eq!(event["dependency_change"]["removed"][0], "old-dep");
+}
+
+#[tokio::test]
+async fn test_backfilled_version_keeps_chain_publish_time() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    // 2023-11-14T22:13:20Z, long before this test runs
+    let block_time = 1_700_000_000;
+    let cid = format!("Qm{}", "b".repeat(44));
+    let log = format!("Program log: ipfs={} 📦 Package published: old-pkg@0.9.0", cid);
+    let sig = format!("oldsig-{}", std::process::id());
+    let event = parse_transaction(&log, &sig, 0, 7, Some(block_time)).expect("publish event");
+    ingest_event(&db, &event, &log, None).await.unwrap();
+
+    // Replaying without a block time must not move the date forward
+    let package_id = queries::get_package_id(&db, "old-pkg").await.unwrap().unwrap();
+    queries::insert_version(&db, package_id, "0.9.0", &cid, None, None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool), "/api/packages/old-pkg").await;
+    assert_eq!(status, StatusCode::OK);
+    let version = body["data"]["versions"].as_array().unwrap().iter()
+        .find(|v| v["version"] == "0.9.0")
+        .expect("version listed");
+    let published_at: chrono::DateTime<chrono::Utc> = version["published_at"].as_str().unwrap().parse().unwrap();
+    assert_eq!(published_at.timestamp(), block_time);
+    let created_at: chrono::DateTime<chrono::Utc> = body["data"]["created_at"].as_str().unwrap().parse().unwrap();
+    assert!(created_at.timestamp() <= block_time);
+}
+
+/// Serves one package account, as the program serializes it, for any address
+struct OneAccount(Vec<u8>);
+
+impl AccountFetcher for OneAccount {
+    fn fetch<'a>(&'a self, _: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>>> + Send + 'a>> {
+        Box::pin(async move { Ok(Some(self.0.clone())) })
+    }
+}
+
+fn package_account(name: &str, version: &str, description: &str, dependencies: &[(&str, &str)]) -> Vec<u8> {
+    fn string(out: &mut Vec<u8>, value: &str) {
+        out.extend((value.len() as u32).to_le_bytes());
+        out.extend(value.as_bytes());
+    }
+    // sha256("account:Package")[..8]
+    let mut data = vec![203, 12, 246, 255, 184, 43, 206, 39];
+    string(&mut data, name);
+    string(&mut data, version);
+    data.extend([9u8; 32]);
+    string(&mut data, &format!("Qm{}", "d".repeat(44)));
+    data.extend(1_700_000_000i64.to_le_bytes());
+    string(&mut data, description);
+    data.extend((dependencies.len() as u32).to_le_bytes());
+    for (name, version) in dependencies {
+        string(&mut data, name);
+        string(&mut data, version);
+    }
+    data.push(255);
+    data.extend([0u8; 32]);
+    string(&mut data, "https://github.com/acct/pkg");
+    string(&mut data, "");
+    data
+}
+
+#[tokio::test]
+async fn test_publish_reads_description_from_package_account() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let account = package_account("acct-pkg", "1.0.0", "Read from the account", &[("acct-dep", "2.0.0")]);
+    let accounts = PackageAccounts::new(Arc::new(OneAccount(account)), Pubkey::new_unique());
+    let log = "Program log: 📦 Package published: acct-pkg@1.0.0 by 11111111111111111111111111111111";
+    let event = parse_transaction(log, &format!("acctsig-{}", std::process::id()), 0, 8, None).expect("publish event");
+    ingest_event(&db, &event, log, Some(&accounts)).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), "/api/packages/acct-pkg").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["description"], "Read from the account");
+    assert_eq!(body["data"]["repository"], "https://github.com/acct/pkg");
+    let version = &body["data"]["versions"][0];
+    assert_eq!(version["ipfs_hash"], format!("Qm{}", "d".repeat(44)));
+    let published_at: chrono::DateTime<chrono::Utc> = version["published_at"].as_str().unwrap().parse().unwrap();
+    assert_eq!(published_at.timestamp(), 1_700_000_000);
+
+    // A later publish whose account can't be read keeps the description
+    let log = "Program log: 📦 Package published: acct-pkg@1.0.0 by 11111111111111111111111111111111";
+    let event = parse_transaction(log, &format!("acctsig2-{}", std::process::id()), 0, 9, None).unwrap();
+    ingest_event(&db, &event, log, None).await.unwrap();
+    let (_, _, body) = common::get(create_router(pool.clone()), "/api/packages/acct-pkg").await;
+    assert_eq!(body["data"]["description"], "Read from the account");
+
+    let (_, _, body) = common::get(create_router(pool), "/api/packages/acct-pkg/dependencies").await;
+    assert_eq!(body["data"]["dependencies"][0]["name"], "acct-dep", "{}", body);
+}
+
+/// An RPC node without the account
+struct NoAccount;
+
+impl AccountFetcher for NoAccount {
+    fn fetch<'a>(&'a self, _: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<Option<Vec<u8>>, Box<dyn std::error::Error + Send + Sync>>> + Send + 'a>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+const ADMIN_KEY: &str = "test-admin-key";
+
+fn resync_router(pool: Pool, fetcher: Option<Arc<dyn AccountFetcher>>) -> axum::Router {
+    let settings = ApiSettings {
+        admin_api_keys: vec![ADMIN_KEY.to_string()],
+        package_accounts: fetcher.map(|fetcher| PackageAccounts::new(fetcher, Pubkey::new_unique())),
+        ..Default::default()
+    };
+    create_router_with(pool, EventBus::new(), settings)
+}
+
+#[tokio::test]
+async fn test_resync_stores_a_missed_publish_from_its_account() {
+    let pool = common::test_pool().await;
+    let spec = common::openapi_spec().await;
+    let name = format!("missed-pkg-{}", std::process::id());
+    let account = package_account(&name, "1.2.0", "Published while the indexer was down", &[("acct-dep", "2.0.0")]);
+    let router = resync_router(pool.clone(), Some(Arc::new(OneAccount(account))));
+    let auth = format!("Bearer {}", ADMIN_KEY);
+
+    let uri = format!("/api/admin/resync/{}/1.2.0", name);
+    let (status, body) = common::post(router.clone(), &uri, &[("authorization", &auth)]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    common::assert_conforms(&spec, "post", "/api/admin/resync/{name}/{version}", status, &body);
+    assert_eq!(body["data"]["name"], name);
+    assert_eq!(body["data"]["version"], "1.2.0");
+    assert_eq!(body["data"]["description"], "Published while the indexer was down");
+    assert_eq!(body["data"]["ipfs_hash"], format!("Qm{}", "d".repeat(44)));
+    assert_eq!(body["data"]["author"], Pubkey::new_from_array([9; 32]).to_string());
+    let published_at: chrono::DateTime<chrono::Utc> = body["data"]["published_at"].as_str().unwrap().parse().unwrap();
+    assert_eq!(published_at.timestamp(), 1_700_000_000);
+
+    // Re-syncing again changes nothing, and the version is served as usual
+    let (status, _) = common::post(router, &uri, &[("authorization", &auth)]).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependencies", name)).await;
+    assert_eq!(body["data"]["dependencies"][0]["name"], "acct-dep", "{}", body);
+
+    let (status, body) = common::post(resync_router(pool.clone(), Some(Arc::new(NoAccount))), &uri.replace("1.2.0", "9.9.9"), &[("authorization", &auth)]).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "account_not_found");
+
+    let (status, body) = common::post(resync_router(pool, None), &uri, &[("authorization", &auth)]).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "resync_unavailable");
+}
+
+#[tokio::test]
+async fn test_resync_requires_an_admin_key() {
+    let router = resync_router(common::offline_pool().await, Some(Arc::new(NoAccount)));
+    let uri = "/api/admin/resync/foo/1.0.0";
+    let ingest_auth = format!("Bearer {}", common::INGEST_KEY);
+    for headers in [&[][..], &[("authorization", "Bearer stolen-key")][..], &[("authorization", ingest_auth.as_str())][..]] {
+        let (status, body) = common::post(router.clone(), uri, headers).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "unauthorized");
+    }
+
+    // Without configured keys admin routes are off entirely
+    let (status, body) = common::post(create_router(common::offline_pool().await), uri, &[("authorization", "Bearer ")]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"].as_str().unwrap().contains("ADMIN_API_KEYS"));
+}
+
+#[tokio::test]
+async fn test_search_includes_latest_version() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("search-latest-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", Some("searchable"), None, None).await.unwrap();
+    let cid = format!("Qm{}", "c".repeat(44));
+    // Published out of order: a 1.x backport after 2.0.0, and 1.10.0 beats 1.9.0 numerically
+    for version in ["1.9.0", "2.0.0", "1.10.0"] {
+        queries::insert_version(&db, package_id, version, &cid, None, None).await.unwrap();
+    }
+    queries::insert_package(&db, &format!("{}-empty", name), "author1", None, None, None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool), &format!("/api/search?q={}&limit=5", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    let hits = body["data"].as_array().unwrap();
+    let hit = |n: &str| hits.iter().find(|h| h["name"] == n).expect("hit listed");
+    assert_eq!(hit(&name)["latest_version"], "2.0.0");
+    assert!(hit(&format!("{}-empty", name))["latest_version"].is_null());
+}
+
+#[tokio::test]
+async fn test_package_versions_follow_semver_precedence() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("semver-order-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", None, None, None).await.unwrap();
+    let cid = format!("Qm{}", "o".repeat(44));
+    // Publish order, oldest first: a 1.2.4 hotfix after 2.0.0, prereleases of
+    // 2.0.0 and 3.0.0 around it, and 1.10.0 after 1.9.0's backport
+    let published = [
+        "1.0.0", "2.0.0-alpha", "2.0.0-alpha.1", "2.0.0-beta.11", "2.0.0-beta.2", "2.0.0-rc.1",
+        "2.0.0", "1.10.0", "1.9.0", "1.2.4", "3.0.0-alpha.beta", "1.0.0+build.5",
+    ];
+    for (i, version) in published.iter().enumerate() {
+        let at = chrono::DateTime::from_timestamp(1_700_000_000 + i as i64 * 60, 0);
+        queries::insert_version(&db, package_id, version, &cid, None, at).await.unwrap();
+    }
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    let versions: Vec<&str> = body["data"]["versions"].as_array().unwrap().iter().map(|v| v["version"].as_str().unwrap()).collect();
+    assert_eq!(versions, [
+        "3.0.0-alpha.beta", "2.0.0", "2.0.0-rc.1", "2.0.0-beta.11", "2.0.0-beta.2", "2.0.0-alpha.1", "2.0.0-alpha",
+        "1.10.0", "1.9.0", "1.2.4",
+        // Build metadata doesn't count; the later publish goes first
+        "1.0.0+build.5", "1.0.0",
+    ]);
+    // Prereleases are listed but never latest once there is a release
+    assert_eq!(body["data"]["latest_version"], "2.0.0");
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}", name)).await;
+    assert_eq!(body["data"][0]["latest_version"], "2.0.0");
+
+    let prerelease_only = format!("{}-pre", name);
+    let pre_id = queries::insert_package(&db, &prerelease_only, "author1", None, None, None).await.unwrap();
+    for version in ["0.1.0-rc.10", "0.1.0-rc.9"] {
+        queries::insert_version(&db, pre_id, version, &cid, None, None).await.unwrap();
+    }
+    let (_, _, body) = common::get(create_router(pool), &format!("/api/packages/{}", prerelease_only)).await;
+    assert_eq!(body["data"]["latest_version"], "0.1.0-rc.10");
+}
+
+#[tokio::test]
+async fn test_list_endpoints_report_totals_in_the_envelope() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let term = format!("pageterm{}", std::process::id());
+    for i in 0..3 {
+        queries::insert_package(&db, &format!("{}-{}", term, i), "author1", None, None, None).await.unwrap();
+    }
+
+    // Bare array by default, for clients that predate the envelope
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&limit=2", term)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&limit=2&offset=2&envelope=1", term)).await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"]["total"], 3);
+    assert_eq!(body["data"]["limit"], 2);
+    assert_eq!(body["data"]["offset"], 2);
+
+    // Past the last page the total is still known
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&offset=10&envelope=1", term)).await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 0);
+    assert_eq!(body["data"]["total"], 3);
+
+    let (_, _, body) = common::get(create_router(pool), "/api/packages?limit=1&envelope=1").await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 1);
+    assert!(body["data"]["total"].as_i64().unwrap() >= 3);
+}
+
+#[tokio::test]
+async fn test_search_ranks_matches_and_honours_sort() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let term = format!("rankterm{}", std::process::id());
+    let exact = queries::insert_package(&db, &term, "author1", None, None, None).await.unwrap();
+    let helper = queries::insert_package(&db, &format!("{}-helpers", term), "author1", None, None, None).await.unwrap();
+    let tagged = format!("tagged-{}", std::process::id());
+    queries::insert_package(&db, &tagged, "author1", Some("Utilities for parsing things"), None, None).await.unwrap();
+    queries::update_package_metadata(&db, &tagged, None, None, &[term.clone()]).await.unwrap();
+    queries::insert_version(&db, exact, "1.0.0", &format!("Qm{}", "s".repeat(44)), None, None).await.unwrap();
+    let helper_version = queries::insert_version(&db, helper, "1.0.0", &format!("Qm{}", "t".repeat(44)), None, None).await.unwrap();
+    for _ in 0..3 {
+        queries::increment_download(&db, helper, helper_version).await.unwrap();
+    }
+
+    let names = |body: &serde_json::Value| -> Vec<String> {
+        body["data"].as_array().unwrap().iter().map(|h| h["name"].as_str().unwrap().to_string()).collect()
+    };
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}", term)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&body), vec![term.clone(), format!("{}-helpers", term), tagged.clone()]);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&sort=downloads", term)).await;
+    assert_eq!(names(&body)[0], format!("{}-helpers", term));
+
+    // Stemmed description match, and a hyphenated name prefix
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q=parsed+{}", term)).await;
+    assert_eq!(names(&body), vec![tagged.clone()]);
+    let (_, _, body) = common::get(create_router(pool), &format!("/api/search?q={}-helpers", term)).await;
+    assert_eq!(names(&body), vec![format!("{}-helpers", term)]);
+}
+
+#[tokio::test]
+async fn test_search_uses_the_full_text_index() {
+    let Pool::Postgres(pool) = common::test_pool().await else {
+        eprintln!("Full-text search needs Postgres, skipping");
+        return;
+    };
+    let client = pool.get().await.unwrap();
+    // The test table is tiny, so rule out the sequential scan the planner would prefer
+    client.batch_execute("SET enable_seqscan = off").await.unwrap();
+    let plan: Vec<String> = client
+        .query("EXPLAIN SELECT id FROM packages WHERE search_vector @@ websearch_to_tsquery('simple', 'solana')", &[])
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    client.batch_execute("RESET enable_seqscan").await.unwrap();
+    assert!(plan.iter().any(|line| line.contains("idx_packages_search")), "{}", plan.join("\n"));
+}
+
+#[tokio::test]
+async fn test_unknown_search_sort_is_rejected() {
+    let router = create_router(common::offline_pool().await);
+    let (status, _, body) = common::get(router, "/api/search?q=math&sort=stars").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn test_search_filters_by_author_and_version() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let term = format!("filterterm{}", std::process::id());
+    let old = queries::insert_package(&db, &format!("{}-old", term), "author-a", None, None, None).await.unwrap();
+    let new = queries::insert_package(&db, &format!("{}-new", term), "author-b", None, None, None).await.unwrap();
+    queries::insert_version(&db, old, "0.9.0", &format!("Qm{}", "f".repeat(44)), None, None).await.unwrap();
+    queries::insert_version(&db, new, "1.4.0", &format!("Qm{}", "g".repeat(44)), None, None).await.unwrap();
+    queries::insert_version(&db, new, "2.0.0-rc.1", &format!("Qm{}", "h".repeat(44)), None, None).await.unwrap();
+
+    let names = |body: &serde_json::Value| -> Vec<String> {
+        body["data"].as_array().unwrap().iter().map(|h| h["name"].as_str().unwrap().to_string()).collect()
+    };
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&sort=name", term)).await;
+    assert_eq!(names(&body), vec![format!("{}-new", term), format!("{}-old", term)]);
+    assert_eq!(body["data"][0]["latest_version"], "1.4.0");
+    assert!(body["data"][0]["latest_published_at"].is_string());
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&author=author-a", term)).await;
+    assert_eq!(names(&body), vec![format!("{}-old", term)]);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&has_version=%5E1.2", term)).await;
+    assert_eq!(names(&body), vec![format!("{}-new", term)]);
+    // Prereleases never match, like in Cargo requirements without one
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/search?q={}&has_version=%3E%3D2", term)).await;
+    assert_eq!(names(&body), Vec::<String>::new());
+    let (_, _, body) = common::get(
+        create_router(pool),
+        &format!("/api/search?q={}&has_version=%3C1&author=author-a&envelope=1", term),
+    ).await;
+    assert_eq!(body["data"]["total"], 1);
+}
+
+#[tokio::test]
+async fn test_malformed_has_version_is_rejected() {
+    let router = create_router(common::offline_pool().await);
+    let (status, _, body) = common::get(router, "/api/search?q=math&has_version=not-a-req").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "invalid_version_req");
+}
+
+#[tokio::test]
+async fn test_single_version_lookup() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("one-version-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", Some("just one"), None, None).await.unwrap();
+    let cid = format!("Qm{}", "d".repeat(44));
+    queries::insert_version(&db, package_id, "1.0.0", &format!("Qm{}", "e".repeat(44)), None, None).await.unwrap();
+    queries::insert_version(&db, package_id, "1.1.0", &cid, None, None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/1.1.0", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], name.as_str());
+    assert_eq!(body["data"]["version"], "1.1.0");
+    assert_eq!(body["data"]["ipfs_hash"], cid.as_str());
+    assert_eq!(body["data"]["author"], "author1");
+    assert_eq!(body["data"]["description"], "just one");
+    assert_eq!(body["data"]["downloads"], 0);
+    assert!(body["data"]["published_at"].is_string());
+
+    let (status, _, _) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/9.9.9", name)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _, _) = common::get(create_router(pool), "/api/packages/no-such-package-xyz/versions/1.0.0").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Router behind a proxy: clients are told apart by `X-Forwarded-For`
+fn proxied_router(pool: Pool) -> axum::Router {
+    let settings = ApiSettings {
+        rate_limit: Some(RateLimitConfig { per_minute: 6000, burst: 100, trust_forwarded_for: true }),
+        ..Default::default()
+    };
+    create_router_with(pool, EventBus::new(), settings)
+}
+
+#[tokio::test]
+async fn test_download_reports_dedup_per_client() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("downloaded-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", None, None, None).await.unwrap();
+    queries::insert_version(&db, package_id, "1.0.0", &format!("Qm{}", "f".repeat(44)), None, None).await.unwrap();
+    let uri = format!("/api/packages/{}/1.0.0/download", name);
+
+    let first = [("x-forwarded-for", "203.0.113.7")];
+    let (status, body) = common::post(proxied_router(pool.clone()), &uri, &first).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["counted"], true);
+
+    let (_, body) = common::post(proxied_router(pool.clone()), &uri, &first).await;
+    assert_eq!(body["data"]["counted"], false);
+    // Hops the client sends ahead of the one the proxy appends don't make it a new client
+    let spoofed = [("x-forwarded-for", "192.0.2.99, 203.0.113.7")];
+    let (_, body) = common::post(proxied_router(pool.clone()), &uri, &spoofed).await;
+    assert_eq!(body["data"]["counted"], false);
+
+    let (_, body) = common::post(proxied_router(pool.clone()), &uri, &[("x-forwarded-for", "198.51.100.2")]).await;
+    assert_eq!(body["data"]["counted"], true);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/1.0.0", name)).await;
+    assert_eq!(body["data"]["downloads"], 2);
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["total_downloads"], 2);
+
+    let (status, _) = common::post(create_router(pool), &format!("/api/packages/{}/9.9.9/download", name), &first).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_download_dedup_ignores_spoofed_forwarded_for_by_default() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("spoofed-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", None, None, None).await.unwrap();
+    queries::insert_version(&db, package_id, "1.0.0", &format!("Qm{}", "f".repeat(44)), None, None).await.unwrap();
+    let uri = format!("/api/packages/{}/1.0.0/download", name);
+    let peer = "192.0.2.10:40000".parse().unwrap();
+
+    let (_, body) = common::post_from(create_router(pool.clone()), &uri, peer, &[("x-forwarded-for", "203.0.113.7")]).await;
+    assert_eq!(body["data"]["counted"], true);
+    // A fresh header from the same peer is still the same client
+    let (_, body) = common::post_from(create_router(pool.clone()), &uri, peer, &[("x-forwarded-for", "198.51.100.2")]).await;
+    assert_eq!(body["data"]["counted"], false);
+
+    let other = "192.0.2.11:40000".parse().unwrap();
+    let (_, body) = common::post_from(create_router(pool), &uri, other, &[("x-forwarded-for", "203.0.113.7")]).await;
+    assert_eq!(body["data"]["counted"], true);
+}
+
+#[tokio::test]
+async fn test_download_series_is_zero_filled_and_survives_rollup() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("series-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", None, None, None).await.unwrap();
+    let version_id = queries::insert_version(&db, package_id, "1.0.0", &format!("Qm{}", "s".repeat(44)), None, None).await.unwrap();
+    for _ in 0..3 {
+        queries::increment_download(&db, package_id, version_id).await.unwrap();
+    }
+    let today = chrono::Utc::now().date_naive();
+    let old_day = today - chrono::Duration::days(200);
+    let noon = old_day.and_hms_opt(12, 0, 0).unwrap().and_utc();
+    common::insert_download_events(&pool, package_id, version_id, noon, 2).await;
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/downloads", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    let days = body["data"].as_array().unwrap();
+    assert_eq!(days.len(), 30);
+    assert_eq!(days[29]["date"], today.to_string());
+    assert_eq!(days[29]["count"], 3);
+    assert!(days[..29].iter().all(|day| day["count"] == 0));
+
+    // Rolled-up days still count, and the totals are untouched
+    assert!(queries::roll_up_download_events(&pool, 90).await.unwrap() >= 2);
+    let uri = format!("/api/packages/{}/downloads?interval=month&from={}&to={}", name, old_day, today);
+    let (_, _, body) = common::get(create_router(pool.clone()), &uri).await;
+    let months = body["data"].as_array().unwrap();
+    assert_eq!(months.iter().map(|month| month["count"].as_i64().unwrap()).sum::<i64>(), 5);
+    assert_eq!(months[0]["count"], 2);
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["total_downloads"], 3);
+
+    let (status, _, body) = common::get(create_router(pool), "/api/packages/no-such-package-anywhere/downloads").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "package_not_found");
+}
+
+#[tokio::test]
+async fn test_download_series_rejects_bad_ranges() {
+    let router = create_router(common::offline_pool().await);
+    let (status, _, body) = common::get(router.clone(), "/api/packages/pkg/downloads?from=2024-02-01&to=2024-01-01").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "invalid_range");
+    let (_, _, body) = common::get(router.clone(), "/api/packages/pkg/downloads?from=2000-01-01&to=2024-01-01").await;
+    assert_eq!(body["code"], "invalid_range");
+    let (_, _, body) = common::get(router, "/api/packages/pkg/downloads?interval=hour").await;
+    assert_eq!(body["code"], "invalid_query");
+}
+
+#[tokio::test]
+async fn test_trending_counts_only_downloads_in_window() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let hot = format!("trending-hot-{}", std::process::id());
+    let old = format!("trending-old-{}", std::process::id());
+    let hot_id = queries::insert_package(&db, &hot, "author1", None, None, None).await.unwrap();
+    let old_id = queries::insert_package(&db, &old, "author1", None, None, None).await.unwrap();
+    let hot_version = queries::insert_version(&db, hot_id, "1.0.0", &format!("Qm{}", "g".repeat(44)), None, None).await.unwrap();
+    let old_version = queries::insert_version(&db, old_id, "1.0.0", &format!("Qm{}", "h".repeat(44)), None, None).await.unwrap();
+
+    for _ in 0..2 {
+        queries::increment_download(&db, hot_id, hot_version).await.unwrap();
+    }
+    queries::increment_download(&db, old_id, old_version).await.unwrap();
+    let month_ago = chrono::Utc::now() - chrono::Duration::days(30);
+    common::insert_download_events(&pool, old_id, old_version, month_ago, 5).await;
+
+    let window_downloads = |body: &serde_json::Value, name: &str| {
+        body["data"].as_array().unwrap().iter()
+            .find(|p| p["name"] == name)
+            .map(|p| p["window_downloads"].as_i64().unwrap())
+    };
+
+    let (status, _, body) = common::get(create_router(pool.clone()), "/api/stats/trending?limit=1000").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(window_downloads(&body, &hot), Some(2));
+    assert_eq!(window_downloads(&body, &old), Some(1));
+    assert!(body["data"].as_array().unwrap().len() <= 100);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), "/api/stats/trending?window=90d&limit=1000").await;
+    assert_eq!(window_downloads(&body, &old), Some(6));
+    assert_eq!(window_downloads(&body, &hot), Some(2));
+
+    // Rankings live under /api/stats, so a package can be named after them
+    queries::insert_package(&db, "trending", "author1", None, None, None).await.unwrap();
+    let (status, _, body) = common::get(create_router(pool), "/api/packages/trending").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "trending");
+}
+
+#[tokio::test]
+async fn test_recent_versions_newest_first() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("recent-{}", std::process::id());
+    let package_id = queries::insert_package(&db, &name, "author1", Some("fresh"), None, None).await.unwrap();
+    let future = |days: i64| Some(chrono::Utc::now() + chrono::Duration::days(days));
+    queries::insert_version(&db, package_id, "1.0.0", &format!("Qm{}", "i".repeat(44)), None, future(1)).await.unwrap();
+    queries::insert_version(&db, package_id, "1.1.0", &format!("Qm{}", "j".repeat(44)), None, future(2)).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), "/api/stats/recent?limit=2").await;
+    assert_eq!(status, StatusCode::OK);
+    let versions = body["data"].as_array().unwrap();
+    assert_eq!(versions.len(), 2);
+    assert_eq!(versions[0]["name"], name.as_str());
+    assert_eq!(versions[0]["version"], "1.1.0");
+    assert_eq!(versions[0]["description"], "fresh");
+    assert_eq!(versions[1]["version"], "1.0.0");
+
+    let (_, _, body) = common::get(create_router(pool), "/api/stats/recent?limit=1&offset=1").await;
+    assert_eq!(body["data"][0]["version"], "1.0.0");
+}
+
+#[tokio::test]
+async fn test_publish_logs_populate_package_metadata() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("meta-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "d".repeat(44));
+    let sig = format!("metasig-{}", std::process::id());
+    let logs = [
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0", cid, name),
+        format!("Program log: 📇 Package metadata: {}@1.0.0 repository=https://github.com/a/b homepage= keywords=solana,math", name),
+    ];
+    for log in &logs {
+        let event = parse_transaction(log, &sig, 0, 9, None).expect("event");
+        ingest_event(&db, &event, log, None).await.unwrap();
+    }
+
+    let (status, _, body) = common::get(create_router(pool), &format!("/api/packages/{}", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["repository"], "https://github.com/a/b");
+    assert!(body["data"]["homepage"].is_null());
+    assert_eq!(body["data"]["keywords"], serde_json::json!(["solana", "math"]));
+}
+
+#[tokio::test]
+async fn test_metadata_logs_store_the_readme_cid_on_the_version() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("readme-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "e".repeat(44));
+    let readme = format!("Qm{}", "f".repeat(44));
+    let sig = format!("readmesig-{}", std::process::id());
+    let logs = [
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0", cid, name),
+        format!("Program log: 📇 Package metadata: {}@1.0.0 repository= homepage= keywords= readme={}", name, readme),
+    ];
+    for log in &logs {
+        let event = parse_transaction(log, &sig, 0, 9, None).expect("event");
+        ingest_event(&db, &event, log, None).await.unwrap();
+    }
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/1.0.0", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["readme_cid"], readme.as_str());
+    let (_, _, body) = common::get(create_router(pool), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["versions"][0]["readme_cid"], readme.as_str());
+}
+
+#[tokio::test]
+async fn test_enrichment_serves_the_readme_and_retries_failed_cids() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("enriched-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "n".repeat(44));
+    let log = format!("Program log: ipfs={} 📦 Package published: {}@1.0.0", cid, name);
+    let event = parse_transaction(&log, &format!("enrichsig-{}", std::process::id()), 0, 9, None).expect("event");
+    ingest_event(&db, &event, &log, None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/readme", name)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "readme_not_found");
+    assert!(queries::get_unenriched_cids(&pool, 1000).await.unwrap().contains(&(cid.clone(), 0)));
+
+    // A failed attempt is retried later, not on the next pass
+    let later = chrono::Utc::now() + chrono::Duration::minutes(1);
+    queries::record_enrichment_failure(&pool, &cid, "gateway timeout", Some(later)).await.unwrap();
+    assert!(!queries::get_unenriched_cids(&pool, 1000).await.unwrap().iter().any(|(due, _)| *due == cid));
+
+    let enrichment = VersionEnrichment {
+        description: Some("Read from antsol.toml".to_string()),
+        license: Some("MIT".to_string()),
+        readme: Some("# Enriched\n".to_string()),
+        file_count: 3,
+        unpacked_size: 1024,
+    };
+    assert_eq!(queries::store_version_enrichment(&pool, &cid, &enrichment).await.unwrap(), 1);
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/readme", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["version"], "1.0.0");
+    assert_eq!(body["data"]["readme"], "# Enriched\n");
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/1.0.0", name)).await;
+    assert_eq!(body["data"]["license"], "MIT");
+    assert_eq!(body["data"]["file_count"], 3);
+    assert_eq!(body["data"]["unpacked_size"], 1024);
+    let (status, _, body) = common::get(create_router(pool), &format!("/api/packages/{}/readme?version=9.9.9", name)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "version_not_found");
+}
+
+#[tokio::test]
+async fn test_events_report_finality_and_orphans_drop_phantom_versions() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("reorg-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "r".repeat(44));
+    let kept = solana_sdk::signature::Signature::new_unique().to_string();
+    let forked = solana_sdk::signature::Signature::new_unique().to_string();
+    for (sig, version) in [(&kept, "1.0.0"), (&forked, "1.1.0")] {
+        let log = format!("Program log: ipfs={} 📦 Package published: {}@{}", cid, name, version);
+        let event = parse_transaction(&log, sig, 0, 500, None).expect("publish event");
+        queries::insert_event(&db, &event.event_type, &event.package_name, event.version.as_deref(), sig, 0, 500, None, event.ipfs_hash.as_deref(), event.authority.as_deref()).await.unwrap();
+        ingest_event(&db, &event, &log, None).await.unwrap();
+    }
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/events/{}", name)).await;
+    assert!(body["data"].as_array().unwrap().iter().all(|e| e["finality"] == "confirmed"));
+
+    queries::finalize_events(&pool, &kept, 501).await.unwrap();
+    let orphaned = queries::orphan_events(&pool, &forked).await.unwrap();
+    assert_eq!(orphaned.len(), 1);
+    assert!(queries::remove_orphaned_version(&pool, &name, "1.1.0").await.unwrap());
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/events/{}", name)).await;
+    let finality = |sig: &str| body["data"].as_array().unwrap().iter()
+        .find(|e| e["transaction_signature"] == sig)
+        .map(|e| (e["finality"].clone(), e["slot"].clone()))
+        .expect("event listed");
+    assert_eq!(finality(&kept), ("finalized".into(), 501.into()));
+    assert_eq!(finality(&forked).0, "orphaned");
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    let versions: Vec<_> = body["data"]["versions"].as_array().unwrap().iter().map(|v| v["version"].clone()).collect();
+    assert_eq!(versions, vec!["1.0.0"]);
+
+    // Orphaning the only remaining version removes the phantom package too
+    assert!(queries::remove_orphaned_version(&pool, &name, "1.0.0").await.unwrap());
+    assert_eq!(queries::get_package_id(&db, &name).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_publish_and_transfer_record_authority() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("owned-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "o".repeat(44));
+    let publisher = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let successor = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let logs = [
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0 by {}", cid, name, publisher),
+        format!("Program log: 🔑 Authority transferred: {}@1.0.0 from {} to {}", name, publisher, successor),
+    ];
+
+    let event = parse_transaction(&logs[0], &format!("authsig-{}", std::process::id()), 0, 10, None).expect("publish event");
+    ingest_event(&db, &event, &logs[0], None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/authors/{}/packages", publisher)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["name"], name.as_str());
+    assert_eq!(body["data"][0]["author"], publisher.as_str());
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/versions/1.0.0", name)).await;
+    assert_eq!(body["data"]["authority"], publisher.as_str());
+
+    let event = parse_transaction(&logs[1], &format!("authsig2-{}", std::process::id()), 0, 11, None).expect("transfer event");
+    assert_eq!(event.event_type, "AuthorityTransferred");
+    ingest_event(&db, &event, &logs[1], None).await.unwrap();
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/authors/{}/packages", publisher)).await;
+    assert_eq!(body["data"], serde_json::json!([]));
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/authors/{}/packages", successor)).await;
+    assert_eq!(body["data"][0]["name"], name.as_str());
+    let (_, _, body) = common::get(create_router(pool), &format!("/api/packages/{}/versions/1.0.0", name)).await;
+    assert_eq!(body["data"]["authority"], successor.as_str());
+}
+
+#[tokio::test]
+async fn test_maintainer_changes_show_on_the_package() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let name = format!("team-pkg-{}", std::process::id());
+    let cid = format!("Qm{}", "m".repeat(44));
+    let owner = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let maintainer = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let stranger = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let logs = [
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0 by {}", cid, name, owner),
+        format!("Program log: 👥 Maintainer added: {} maintainer={} by {}", name, maintainer, owner),
+        // A list kept by a wallet that owns no version shown here
+        format!("Program log: 👥 Maintainer added: {} maintainer={} by {}", name, stranger, stranger),
+        format!("Program log: 👥 Maintainer removed: {} maintainer={} by {}", name, maintainer, owner),
+    ];
+    let ingest = |i: usize| {
+        let pool = pool.clone();
+        let log = logs[i].clone();
+        async move {
+            let event = parse_transaction(&log, &format!("maintsig{}-{}", i, std::process::id()), 0, 20 + i as i64, None).unwrap();
+            ingest_event(&db, &event, &log, None).await.unwrap();
+            event
+        }
+    };
+
+    ingest(0).await;
+    assert_eq!(ingest(1).await.event_type, "MaintainerAdded");
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["maintainers"], serde_json::json!([maintainer]));
+
+    ingest(2).await;
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["maintainers"], serde_json::json!([maintainer]));
+
+    ingest(3).await;
+    let (_, _, body) = common::get(create_router(pool), &format!("/api/packages/{}", name)).await;
+    assert_eq!(body["data"]["maintainers"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_ingested_events_arrive_on_the_event_stream() {
+    use axum::body::Body;
+    use axum::http::Request;
+    use tokio_stream::StreamExt;
+    use tower::ServiceExt;
+
+    let pool = common::test_pool().await;
+    let router = common::ingest_router(pool);
+    let response = router
+        .clone()
+        .oneshot(Request::builder().uri("/api/events/stream?replay=0").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    let mut frames = response.into_body().into_data_stream();
+
+    let name = format!("streamed-pkg-{}", std::process::id());
+    let auth = format!("Bearer {}", common::INGEST_KEY);
+    let (status, _) = common::post_json(router, "/api/ingest", &[("authorization", &auth)], serde_json::json!({
+        "log": format!("Program log: 📦 Package published: {}@1.0.0", name),
+        "signature": format!("streamsig-{}", uuid::Uuid::new_v4()),
+        "slot": 42,
+    })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let mut received = String::new();
+    while !received.contains(&name) {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), frames.next())
+            .await
+            .expect("event streamed within 5s")
+            .expect("stream open")
+            .unwrap();
+        received.push_str(&String::from_utf8_lossy(&frame));
+    }
+    let data = received.lines().find(|line| line.starts_with("data:") && line.contains(&name)).unwrap();
+    let event: serde_json::Value = serde_json::from_str(data.trim_start_matches("data:").trim()).unwrap();
+    assert_eq!(event["event_type"], "PackagePublished");
+    assert_eq!(event["version"], "1.0.0");
+    assert!(event["id"].as_i64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn test_openapi_documents_every_route() {
+    let spec = common::openapi_spec().await;
+    let documented = |method: &str, path: &str| !spec["paths"][path][method].is_null();
+    for (method, path) in [
+        ("get", "/health"),
+        ("get", "/metrics"),
+        ("get", "/api/search"),
+        ("get", "/api/packages"),
+        ("get", "/api/stats/trending"),
+        ("get", "/api/stats/recent"),
+        ("get", "/api/packages/{name}"),
+        ("get", "/api/packages/{scope}/{name}"),
+        ("get", "/api/packages/{name}/versions/{version}"),
+        ("get", "/api/packages/{name}/downloads"),
+        ("post", "/api/packages/{name}/{version}/download"),
+        ("get", "/api/authors/{pubkey}/packages"),
+        ("get", "/api/stats"),
+        ("get", "/api/events/recent"),
+        ("get", "/api/events/stream"),
+        ("get", "/api/events/{package}"),
+        ("get", "/api/events/{scope}/{name}"),
+        ("post", "/api/ingest"),
+        ("post", "/api/admin/resync/{name}/{version}"),
+    ] {
+        assert!(documented(method, path), "{} {} missing from the OpenAPI document", method, path);
+    }
+
+    let router = create_router(common::offline_pool().await);
+    let (status, headers, _) = common::get(router, "/docs/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+}
+
+#[tokio::test]
+async fn test_errors_use_the_response_envelope() {
+    let spec = common::openapi_spec().await;
+    let router = common::ingest_router(common::offline_pool().await);
+    let auth = format!("Bearer {}", common::INGEST_KEY);
+
+    // The offline pool can't connect, so database routes fail
+    let (status, _, body) = common::get(router.clone(), "/api/stats").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "internal_error");
+    common::assert_conforms(&spec, "get", "/api/stats", status, &body);
+
+    let (status, _, body) = common::get(router.clone(), "/api/stats/trending?window=1y").await;
+    assert_eq!(body["code"], "invalid_window");
+    common::assert_conforms(&spec, "get", "/api/stats/trending", status, &body);
+
+    let (status, _, body) = common::get(router.clone(), "/api/search?q=math&limit=many").await;
+    assert_eq!(body["code"], "invalid_query");
+    common::assert_conforms(&spec, "get", "/api/search", status, &body);
+
+    let (status, body) = common::post_json(router.clone(), "/api/ingest", &[("authorization", &auth)], serde_json::json!({"slot": 1})).await;
+    assert_eq!(body["code"], "invalid_body");
+    common::assert_conforms(&spec, "post", "/api/ingest", status, &body);
+
+    let (status, _, body) = common::get(router.clone(), "/api/packages/@scope/pkg").await;
+    common::assert_conforms(&spec, "get", "/api/packages/{scope}/{name}", status, &body);
+
+    let (status, _, body) = common::get(router.clone(), "/api/nothing/here").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "route_not_found");
+
+    let (status, _, body) = common::get(router, "/health").await;
+    common::assert_conforms(&spec, "get", "/health", status, &body);
+}
+
+#[tokio::test]
+async fn test_responses_match_the_openapi_schema() {
+    let pool = common::test_pool().await;
+    let db = pool.get().await.unwrap();
+    let spec = common::openapi_spec().await;
+    let name = format!("schema-pkg-{}", std::process::id());
+    let authority = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let log = format!("Program log: ipfs=Qm{} 📦 Package published: {}@1.0.0 by {}", "k".repeat(44), name, authority);
+    let event = parse_transaction(&log, &format!("schemasig-{}", uuid::Uuid::new_v4()), 0, 12, Some(1_700_000_000)).unwrap();
+    queries::insert_event(&db, &event.event_type, &event.package_name, event.version.as_deref(), &event.transaction_signature, event.log_index, 12, Some(1_700_000_000), event.ipfs_hash.as_deref(), event.authority.as_deref()).await.unwrap();
+    ingest_event(&db, &event, &log, None).await.unwrap();
+
+    let router = common::ingest_router(pool);
+    let auth = format!("Bearer {}", common::INGEST_KEY);
+    for (uri, path) in [
+        (format!("/api/packages/{}", name), "/api/packages/{name}"),
+        (format!("/api/packages/{}/versions/1.0.0", name), "/api/packages/{name}/versions/{version}"),
+        (format!("/api/packages/{}/versions/9.9.9", name), "/api/packages/{name}/versions/{version}"),
+        (format!("/api/packages/{}/downloads?interval=week", name), "/api/packages/{name}/downloads"),
+        ("/api/packages/no-such-package-anywhere".to_string(), "/api/packages/{name}"),
+        ("/api/packages?limit=5&envelope=1".to_string(), "/api/packages"),
+        ("/api/stats/recent?limit=5".to_string(), "/api/stats/recent"),
+        ("/api/stats/trending".to_string(), "/api/stats/trending"),
+        (format!("/api/search?q={}&envelope=1", name), "/api/search"),
+        (format!("/api/authors/{}/packages", authority), "/api/authors/{pubkey}/packages"),
+        ("/api/stats".to_string(), "/api/stats"),
+        ("/api/events/recent?limit=5&envelope=1".to_string(), "/api/events/recent"),
+        (format!("/api/events/{}?envelope=1", name), "/api/events/{package}"),
+    ] {
+        let (status, _, body) = common::get(router.clone(), &uri).await;
+        common::assert_conforms(&spec, "get", path, status, &body);
+    }
+
+    let (status, body) = common::post(router.clone(), &format!("/api/packages/{}/1.0.0/download", name), &[]).await;
+    common::assert_conforms(&spec, "post", "/api/packages/{name}/{version}/download", status, &body);
+    let (status, body) = common::post_json(router, "/api/ingest", &[("authorization", &auth)], serde_json::json!({"log": "Program log: nothing to see"})).await;
+    common::assert_conforms(&spec, "post", "/api/ingest", status, &body);
+}
+
+#[tokio::test]
+async fn test_ingest_requires_one_of_the_configured_keys() {
+    let settings = ApiSettings {
+        ingest_api_keys: vec!["retiring-key".to_string(), "current-key".to_string()],
+        ..Default::default()
+    };
+    let router = create_router_with(common::offline_pool().await, EventBus::new(), settings);
+    let spec = common::openapi_spec().await;
+    // A body without `log` gets past authentication but fails to parse
+    let malformed = serde_json::json!({"slot": 1});
+
+    for key in ["retiring-key", "current-key"] {
+        let auth = format!("Bearer {}", key);
+        let (status, body) = common::post_json(router.clone(), "/api/ingest", &[("authorization", &auth)], malformed.clone()).await;
+        assert_eq!(body["code"], "invalid_body", "{} should be accepted", key);
+        assert_ne!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    for headers in [&[][..], &[("authorization", "Bearer stolen-key")][..], &[("authorization", "current-key")][..]] {
+        let (status, body) = common::post_json(router.clone(), "/api/ingest", headers, malformed.clone()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "unauthorized");
+        common::assert_conforms(&spec, "post", "/api/ingest", status, &body);
+    }
+
+    // Without configured keys ingestion is off entirely
+    let router = create_router(common::offline_pool().await);
+    let (status, body) = common::post_json(router, "/api/ingest", &[("authorization", "Bearer ")], malformed).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"].as_str().unwrap().contains("INGEST_API_KEYS"));
+}
+
+#[tokio::test]
+async fn test_api_routes_are_rate_limited_per_client() {
+    let settings = ApiSettings {
+        rate_limit: Some(RateLimitConfig { per_minute: 60, burst: 2, trust_forwarded_for: true }),
+        ..Default::default()
+    };
+    let router = create_router_with(common::offline_pool().await, EventBus::new(), settings);
+    // Rejected before touching the database, so the offline pool is enough
+    let uri = "/api/stats/trending?window=nope";
+
+    for _ in 0..2 {
+        let (status, _, _) = common::get(router.clone(), uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (status, headers, body) = common::get(router.clone(), uri).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(headers[header::RETRY_AFTER], "1");
+    assert_eq!(body["code"], "rate_limited");
+
+    // Other clients have their own budget, and health checks are not limited
+    let (status, _) = common::post_json(router.clone(), "/api/ingest", &[("x-forwarded-for", "198.51.100.1")], serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _, _) = common::get(router.clone(), "/health").await;
+    assert_ne!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // The bucket refills at one request per second
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    let (status, _, _) = common::get(router, uri).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_rate_limit_ignores_client_supplied_forwarded_for_hops() {
+    let settings = ApiSettings {
+        rate_limit: Some(RateLimitConfig { per_minute: 60, burst: 2, trust_forwarded_for: true }),
+        ..Default::default()
+    };
+    let router = create_router_with(common::offline_pool().await, EventBus::new(), settings);
+
+    // The client rotates the hop it sends; the proxy appends the same address each time
+    for spoofed in ["203.0.113.1", "203.0.113.2"] {
+        let forwarded = format!("{}, 198.51.100.7", spoofed);
+        let (status, _) = common::post_json(router.clone(), "/api/ingest", &[("x-forwarded-for", forwarded.as_str())], serde_json::json!({})).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let spoofed = [("x-forwarded-for", "203.0.113.3, 198.51.100.7")];
+    let (status, _) = common::post_json(router.clone(), "/api/ingest", &spoofed, serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // A client reached through the same proxy from elsewhere has its own budget
+    let (status, _) = common::post_json(router, "/api/ingest", &[("x-forwarded-for", "198.51.100.8")], serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_health_is_unavailable_without_a_database() {
+    let router = create_router(common::offline_pool().await);
+    let (status, _, body) = common::get(router, "/health").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["code"], "unhealthy");
+    assert_eq!(body["data"]["status"], "unhealthy");
+    assert_eq!(body["data"]["database"]["ok"], false);
+    assert!(body["data"]["indexer"]["lag_slots"].is_null());
+}
+
+#[tokio::test]
+async fn test_health_reports_indexer_state() {
+    let pool = common::test_pool().await;
+    queries::update_synced_slot(&pool, 42).await.unwrap();
+
+    // Without an RPC node the lag is unknown, which degrades but doesn't fail the check
+    let (status, _, body) = common::get(create_router(pool), "/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "degraded");
+    assert_eq!(body["data"]["database"]["ok"], true);
+    assert_eq!(body["data"]["rpc"]["ok"], false);
+    assert!(body["data"]["indexer"]["last_processed_slot"].as_u64().unwrap() >= 42);
+    assert!(body["data"]["indexer"]["seconds_since_update"].as_i64().unwrap() < 60);
+}
+
+#[tokio::test]
+async fn test_metrics_count_api_requests_per_route() {
+    let router = common::metrics_router(common::offline_pool().await);
+    let auth = format!("Bearer {}", common::INGEST_KEY);
+    let ingest_ok = ["antsol_http_requests_total", "route=\"/api/ingest\"", "status=\"200\""];
+    let before = common::metric_value(router.clone(), ingest_ok[0], &ingest_ok[1..]).await;
+
+    // A log without an event is answered without touching the database
+    for _ in 0..2 {
+        let (status, _) = common::post_json(router.clone(), "/api/ingest", &[("authorization", &auth)], serde_json::json!({"log": "Program log: nothing to see"})).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let after = common::metric_value(router.clone(), ingest_ok[0], &ingest_ok[1..]).await;
+    assert!(after >= before + 2.0, "{} -> {}", before, after);
+    let timed = common::metric_value(router, "antsol_http_request_duration_seconds_count", &["route=\"/api/ingest\""]).await;
+    assert!(timed >= 2.0);
+
+    // Without a recorder there is nothing to serve
+    let (status, _, body) = common::get(create_router(common::offline_pool().await), "/metrics").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "route_not_found");
+}
+
+#[tokio::test]
+async fn test_metrics_count_ingested_events() {
+    let pool = common::test_pool().await;
+    let router = common::metrics_router(pool);
+    let auth = format!("Bearer {}", common::INGEST_KEY);
+    let published = ["antsol_events_ingested_total", "type=\"PackagePublished\""];
+    let before = common::metric_value(router.clone(), published[0], &published[1..]).await;
+
+    let name = format!("metrics-pkg-{}", std::process::id());
+    let log = format!("Program log: ipfs=Qm{} 📦 Package published: {}@1.0.0 by {}", "m".repeat(44), name, solana_sdk::pubkey::Pubkey::new_unique());
+    let signature = format!("metricssig-{}", uuid::Uuid::new_v4());
+    let (status, _) = common::post_json(router.clone(), "/api/ingest", &[("authorization", &auth)], serde_json::json!({"log": log, "signature": signature, "slot": 7})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let after = common::metric_value(router.clone(), published[0], &published[1..]).await;
+    assert!(after >= before + 1.0, "{} -> {}", before, after);
+    let inserts = common::metric_value(router, "antsol_db_query_duration_seconds_count", &["query=\"insert_event\""]).await;
+    assert!(inserts >= 1.0);
+}
+
+#[tokio::test]
+async fn test_websocket_and_polling_ingest_a_transaction_once() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let name = format!("dual-source-pkg-{}", std::process::id());
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    let logs = vec![
+        format!("Program {} invoke [1]", program_id),
+        format!("Program log: ipfs=Qm{} 📦 Package published: {}@1.0.0 by {}", "d".repeat(44), name, solana_sdk::pubkey::Pubkey::new_unique()),
+    ];
+    let events = EventBus::new();
+    let mut subscriber = events.subscribe();
+
+    // The subscription and the gap-filler race on the same transaction
+    let (first, second) = tokio::join!(
+        ingest_logs(&pool, &events, None, &signature, 30, Some(1_700_000_000), &logs, &program_id),
+        ingest_logs(&pool, &events, None, &signature, 30, Some(1_700_000_000), &logs, &program_id),
+    );
+    // The loser sees the winner's row and rolls back
+    assert_eq!(first.unwrap() + second.unwrap(), 1);
+
+    let stored = queries::get_package_events(&pool, &name, None, 10, 0).await.unwrap();
+    assert_eq!(stored.items.len(), 1);
+    assert_eq!(stored.total, 1);
+    assert!(queries::event_recorded(&pool, &signature).await.unwrap());
+    // Only the insert that won is announced
+    assert!(subscriber.try_recv().is_ok());
+    assert!(subscriber.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_events_sharing_a_signature_are_all_stored() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let names = [format!("batch-a-pkg-{}", std::process::id()), format!("batch-b-pkg-{}", std::process::id())];
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    let mut logs = vec![format!("Program {} invoke [1]", program_id)];
+    for (name, cid) in names.iter().zip(["e", "f"]) {
+        logs.push(format!("Program log: ipfs=Qm{} 📦 Package published: {}@1.0.0", cid.repeat(44), name));
+    }
+    let events = EventBus::new();
+
+    // One transaction publishing two packages stores an event for each
+    let stored = ingest_logs(&pool, &events, None, &signature, 32, Some(1_700_000_000), &logs, &program_id).await.unwrap();
+    assert_eq!(stored, 2);
+    for (name, log_index) in names.iter().zip([1, 2]) {
+        let page = queries::get_package_events(&pool, name, None, 10, 0).await.unwrap();
+        assert_eq!(page.items.len(), 1, "{}", name);
+        assert_eq!(page.items[0].transaction_signature, signature);
+        assert_eq!(page.items[0].log_index, log_index);
+    }
+
+    // Seeing the transaction again still stores nothing
+    let again = ingest_logs(&pool, &events, None, &signature, 32, Some(1_700_000_000), &logs, &program_id).await.unwrap();
+    assert_eq!(again, 0);
+}
+
+#[tokio::test]
+async fn test_failed_ingest_leaves_no_partial_rows() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let name = format!("half-ingested-pkg-{}", std::process::id());
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    let publish = |cid: String| vec![
+        format!("Program {} invoke [1]", program_id),
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0", cid, name),
+    ];
+    let events = EventBus::new();
+    let mut subscriber = events.subscribe();
+
+    // Postgres rejects the NUL in the CID, so the version insert fails after
+    // the event and package rows were written
+    let broken = publish(format!("Qm{}\0x", "n".repeat(44)));
+    assert!(ingest_logs(&pool, &events, None, &signature, 31, Some(1_700_000_000), &broken, &program_id).await.is_err());
+    assert!(!queries::event_recorded(&pool, &signature).await.unwrap());
+    let db = pool.get().await.unwrap();
+    assert_eq!(queries::get_package_id(&db, &name).await.unwrap(), None);
+    assert!(subscriber.try_recv().is_err());
+
+    // Nothing blocks a retry of the same signature
+    let fixed = publish(format!("Qm{}", "n".repeat(44)));
+    assert_eq!(ingest_logs(&pool, &events, None, &signature, 31, Some(1_700_000_000), &fixed, &program_id).await.unwrap(), 1);
+    let package_id = queries::get_package_id(&db, &name).await.unwrap().expect("package stored");
+    assert!(queries::get_version_id(&db, package_id, "1.0.0").await.unwrap().is_some());
+    assert!(subscriber.try_recv().is_ok());
+}
+
+#[tokio::test]
+async fn test_dependency_graph_endpoints() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let base = format!("graph-base-{}", std::process::id());
+    let app = format!("graph-app-{}", std::process::id());
+    let events = EventBus::new();
+    let publish = |version: &str, deps: &str, cid_char: &str| vec![
+        format!("Program {} invoke [1]", program_id),
+        format!("Program log: ipfs=Qm{} 📦 Package published: {}@{}", cid_char.repeat(44), app, version),
+        format!("Program log: 🧩 Package dependencies: {}@{} deps={}", app, version, deps),
+    ];
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    let mut logs = publish("1.0.0", &format!("{}@1.0.0,other-dep@0.1.0", base), "g");
+    logs.push(format!("Program log: 📚 Package external dependencies: {}@1.0.0 deps=rust|serde|^1.0|;npm|left-pad|1.3.0|https://registry.npmjs.org", app));
+    ingest_logs(&pool, &events, None, &signature, 40, Some(1_700_000_000), &logs, &program_id).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependencies", app)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["version"], "1.0.0");
+    assert_eq!(body["data"]["dependencies"][0]["name"], base.as_str());
+    assert_eq!(body["data"]["dependencies"][1]["name"], "other-dep");
+    // External dependencies are listed by type, and never count as dependents
+    assert_eq!(body["data"]["external_dependencies"][0]["type"], "npm");
+    assert_eq!(body["data"]["external_dependencies"][0]["registry"], "https://registry.npmjs.org");
+    assert_eq!(body["data"]["external_dependencies"][1]["name"], "serde");
+    assert!(body["data"]["external_dependencies"][1]["registry"].is_null());
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependents", base)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["name"], app.as_str());
+    assert_eq!(body["data"][0]["requires"], "1.0.0");
+
+    // Once the latest version drops the dependency it no longer counts
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    let logs = publish("2.0.0", "other-dep@0.2.0", "h");
+    ingest_logs(&pool, &events, None, &signature, 41, Some(1_700_000_100), &logs, &program_id).await.unwrap();
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependents", base)).await;
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependencies?version=1.0.0", app)).await;
+    assert_eq!(body["data"]["dependencies"].as_array().unwrap().len(), 2);
+    assert_eq!(body["data"]["external_dependencies"].as_array().unwrap().len(), 2);
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/packages/{}/dependencies", app)).await;
+    assert!(body["data"]["external_dependencies"].as_array().unwrap().is_empty());
+    let (status, _, body) = common::get(create_router(pool), &format!("/api/packages/{}/dependencies?version=9.9.9", app)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "version_not_found");
+}
+
+#[tokio::test]
+async fn test_version_dependents_follow_the_graph() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let events = EventBus::new();
+    let name = |short: &str| format!("impact-{}-{}", short, std::process::id());
+    // lib and util need core, app needs both, tool needs app, and core
+    // needs app back; stale needs an older core
+    let graph = [
+        ("core", "0.9.0", vec![]),
+        ("core", "1.0.0", vec!["app"]),
+        ("lib", "1.0.0", vec!["core"]),
+        ("util", "1.0.0", vec!["core"]),
+        ("app", "1.0.0", vec!["lib", "util"]),
+        ("tool", "1.0.0", vec!["app"]),
+        ("stale", "1.0.0", vec![]),
+    ];
+    for (slot, (package, version, deps)) in graph.iter().enumerate() {
+        let mut deps: Vec<String> = deps.iter().map(|dep| format!("{}@1.0.0", name(dep))).collect();
+        if *package == "stale" {
+            deps.push(format!("{}@0.9.0", name("core")));
+        }
+        let mut logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            format!("Program log: ipfs=Qm{} 📦 Package published: {}@{}", "i".repeat(44), name(package), version),
+        ];
+        if !deps.is_empty() {
+            logs.push(format!("Program log: 🧩 Package dependencies: {}@{} deps={}", name(package), version, deps.join(",")));
+        }
+        let signature = solana_sdk::signature::Signature::new_unique().to_string();
+        ingest_logs(&pool, &events, None, &signature, 60 + slot as u64, Some(1_700_000_000 + slot as i64), &logs, &program_id)
+            .await
+            .unwrap();
+    }
+    let uri = |query: &str| format!("/api/packages/{}/1.0.0/dependents{}", name("core"), query);
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &uri("")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["direct_count"], 2);
+    assert_eq!(body["data"]["transitive_count"], 0);
+    assert_eq!(body["data"]["dependents"][0]["name"], name("lib").as_str());
+    assert_eq!(body["data"]["dependents"][1]["name"], name("util").as_str());
+
+    // app is reached through lib and util but listed once; the cycle back to
+    // core ends there
+    let (_, _, body) = common::get(create_router(pool.clone()), &uri("?transitive=true")).await;
+    assert_eq!(body["data"]["direct_count"], 2);
+    assert_eq!(body["data"]["transitive_count"], 2);
+    let dependents = body["data"]["dependents"].as_array().unwrap();
+    assert_eq!(dependents[2]["name"], name("app").as_str());
+    assert_eq!(dependents[2]["depth"], 2);
+    assert_eq!(dependents[2]["via"], name("lib").as_str());
+    assert_eq!(dependents[3]["name"], name("tool").as_str());
+    assert_eq!(dependents[3]["depth"], 3);
+
+    let (_, _, body) = common::get(create_router(pool.clone()), &uri("?transitive=true&depth=2")).await;
+    assert_eq!(body["data"]["max_depth"], 2);
+    assert_eq!(body["data"]["transitive_count"], 1);
+
+    // The depth asked for is capped by the configured one
+    let settings = ApiSettings { dependents_depth: DependentsDepth(1), ..Default::default() };
+    let router = create_router_with(pool.clone(), EventBus::new(), settings);
+    let (_, _, body) = common::get(router, &uri("?transitive=true&depth=10")).await;
+    assert_eq!(body["data"]["max_depth"], 1);
+    assert_eq!(body["data"]["transitive_count"], 0);
+
+    let stale = queries::get_version_dependents(&pool, &name("core"), "0.9.0", 5).await.unwrap().unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].name, name("stale"));
+    assert!(queries::get_version_dependents(&pool, &name("tool"), "1.0.0", 5).await.unwrap().unwrap().is_empty());
+
+    let (status, _, body) = common::get(create_router(pool), &format!("/api/packages/{}/9.9.9/dependents", name("core"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "version_not_found");
+}
+
+#[tokio::test]
+async fn test_events_carry_cid_authority_and_version() {
+    let pool = common::test_pool().await;
+    let program_id = solana_sdk::pubkey::Pubkey::new_unique();
+    let name = format!("linked-events-pkg-{}", std::process::id());
+    let authority = solana_sdk::pubkey::Pubkey::new_unique().to_string();
+    let cid = format!("Qm{}", "l".repeat(44));
+    let events = EventBus::new();
+    let logs = vec![
+        format!("Program {} invoke [1]", program_id),
+        format!("Program log: ipfs={} 📦 Package published: {}@1.0.0 by {}", cid, name, authority),
+    ];
+    let signature = solana_sdk::signature::Signature::new_unique().to_string();
+    ingest_logs(&pool, &events, None, &signature, 50, Some(1_700_000_000), &logs, &program_id).await.unwrap();
+    let download = format!("Program log: Download package: {}, version: 1.0.0", name);
+    let event = parse_transaction(&download, &solana_sdk::signature::Signature::new_unique().to_string(), 0, 51, None).unwrap();
+    ingest_events(&pool, &events, None, &[(event, download.as_str())], None).await.unwrap();
+
+    let (status, _, body) = common::get(create_router(pool.clone()), &format!("/api/events/{}?event_type=PackagePublished", name)).await;
+    assert_eq!(status, StatusCode::OK);
+    let published = body["data"].as_array().unwrap();
+    assert_eq!(published.len(), 1);
+    assert_eq!(published[0]["ipfs_hash"], cid.as_str());
+    assert_eq!(published[0]["authority"], authority.as_str());
+    let version_id = published[0]["version_id"].as_i64().unwrap();
+
+    // The download has no CID of its own but points at the same version
+    let (_, _, body) = common::get(create_router(pool.clone()), &format!("/api/events/{}?event_type=PackageDownloaded", name)).await;
+    assert_eq!(body["data"][0]["version_id"].as_i64(), Some(version_id));
+    assert!(body["data"][0]["ipfs_hash"].is_null());
+
+    let (_, _, body) = common::get(create_router(pool), "/api/events/recent?event_type=PackagePublished&limit=100").await;
+    let recent = body["data"].as_array().unwrap();
+    assert!(recent.iter().all(|event| event["event_type"] == "PackagePublished"));
+    assert!(recent.iter().any(|event| event["package_name"] == name.as_str()));
+}
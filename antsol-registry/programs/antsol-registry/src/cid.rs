@@ -0,0 +1,180 @@
+//! IPFS CID syntax, shared by the instructions
+//!
+//! Both CID versions are decoded and their multihash checked for shape, with
+//! fixed-size buffers and no allocation:
+//! - CIDv0: 46 base58btc characters encoding a sha2-256 multihash (`Qm...`)
+//! - CIDv1: a multibase prefix, `b` (base32, lowercase) or `z` (base58btc),
+//!   then varints for the version, the content codec and the multihash.
+//!   Any codec is accepted, so raw (`bafk...`) and dag-pb (`bafy...`) both pass.
+
+use crate::state::MAX_CID_LENGTH;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+/// Multihash code of sha2-256, the only hash CIDv0 allows
+const SHA2_256: u64 = 0x12;
+/// Length of a CIDv0 string
+const CID_V0_LENGTH: usize = 46;
+/// Longest digest accepted; sha2-512 and blake2b-512 are 64 bytes
+const MAX_DIGEST_LENGTH: u64 = 64;
+/// Room for any decoded CID of at most `MAX_CID_LENGTH` characters
+const MAX_DECODED_LENGTH: usize = MAX_CID_LENGTH;
+
+/// Whether `cid` is a well-formed CIDv0 or CIDv1 of at most `MAX_CID_LENGTH` characters
+pub fn is_valid_cid(cid: &str) -> bool {
+	if cid.is_empty() || cid.len() > MAX_CID_LENGTH { return false; }
+	let mut buf = [0u8; MAX_DECODED_LENGTH];
+	if cid.len() == CID_V0_LENGTH && cid.starts_with("Qm") {
+		return match decode_base58(cid.as_bytes(), &mut buf) {
+			Some(len) => matches!(parse_multihash(&buf[..len]), Some(SHA2_256)),
+			None => false,
+		};
+	}
+
+	let (prefix, rest) = cid.as_bytes().split_at(1);
+	let len = match prefix[0] {
+		b'b' => decode_base32(rest, &mut buf),
+		b'z' => decode_base58(rest, &mut buf),
+		_ => None,
+	};
+	let Some(len) = len else { return false };
+	let mut bytes = &buf[..len];
+	if read_varint(&mut bytes) != Some(1) { return false; }
+	if read_varint(&mut bytes).is_none() { return false; }
+	parse_multihash(bytes).is_some()
+}
+
+/// The hash code of a multihash that fills `bytes` exactly, if it does.
+/// sha2-256 digests must be 32 bytes.
+fn parse_multihash(mut bytes: &[u8]) -> Option<u64> {
+	let code = read_varint(&mut bytes)?;
+	let len = read_varint(&mut bytes)?;
+	if len == 0 || len > MAX_DIGEST_LENGTH || len as usize != bytes.len() { return None; }
+	if code == SHA2_256 && len != 32 { return None; }
+	Some(code)
+}
+
+/// Unsigned LEB128, as multiformats uses it: at most 9 bytes, minimally encoded
+fn read_varint(bytes: &mut &[u8]) -> Option<u64> {
+	let mut value = 0u64;
+	for i in 0..9 {
+		let byte = *bytes.get(i)?;
+		value |= u64::from(byte & 0x7f) << (7 * i);
+		if byte & 0x80 == 0 {
+			if byte == 0 && i > 0 { return None; }
+			*bytes = &bytes[i + 1..];
+			return Some(value);
+		}
+	}
+	None
+}
+
+/// Decode base58btc into `out`, returning the decoded length
+fn decode_base58(input: &[u8], out: &mut [u8; MAX_DECODED_LENGTH]) -> Option<usize> {
+	if input.is_empty() { return None; }
+	// Big-endian digits are accumulated little-endian in `out`, then reversed
+	let mut len = 0;
+	for &c in input {
+		let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+		for byte in out[..len].iter_mut() {
+			carry += u32::from(*byte) * 58;
+			*byte = carry as u8;
+			carry >>= 8;
+		}
+		while carry > 0 {
+			if len == out.len() { return None; }
+			out[len] = carry as u8;
+			len += 1;
+			carry >>= 8;
+		}
+	}
+	// Each leading '1' stands for a leading zero byte
+	for _ in input.iter().take_while(|&&c| c == b'1') {
+		if len == out.len() { return None; }
+		out[len] = 0;
+		len += 1;
+	}
+	out[..len].reverse();
+	Some(len)
+}
+
+/// Decode unpadded lowercase RFC 4648 base32 into `out`, returning the decoded length
+fn decode_base32(input: &[u8], out: &mut [u8; MAX_DECODED_LENGTH]) -> Option<usize> {
+	if input.is_empty() { return None; }
+	let (mut buffer, mut bits, mut len) = (0u32, 0u32, 0);
+	for &c in input {
+		let value = match c {
+			b'a'..=b'z' => c - b'a',
+			b'2'..=b'7' => c - b'2' + 26,
+			_ => return None,
+		};
+		buffer = (buffer << 5) | u32::from(value);
+		bits += 5;
+		if bits >= 8 {
+			bits -= 8;
+			if len == out.len() { return None; }
+			out[len] = (buffer >> bits) as u8;
+			len += 1;
+		}
+		buffer &= (1 << bits) - 1;
+	}
+	// Leftover bits are padding and must be zero
+	if bits >= 5 || buffer != 0 { return None; }
+	Some(len)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn accepts_well_formed_cids_of_both_versions() {
+		for cid in [
+			// CIDv0, sha2-256 of "hello world"
+			"QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L4",
+			"QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG",
+			// CIDv1 base32: raw, dag-pb and dag-cbor
+			"bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+			"bafybeifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+			"bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi",
+			"bafyreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+			// CIDv1 raw with a blake2b-256 multihash
+			"bafk2bzaceaswza5ss4iu2ia3galz6pyo6dfm5f4dmiw2lf2de22dmf4k533ba",
+			// CIDv1 raw with an identity multihash
+			"bafkqablimvwgy3y",
+			// CIDv1 base58btc
+			"zb2rhj7crUKTQYRGCRATFaQ6YFLTde2YzdqbbhAASkL9uRDXn",
+		] {
+			assert!(is_valid_cid(cid), "{} should be valid", cid);
+		}
+	}
+
+	#[test]
+	fn rejects_malformed_cids() {
+		for cid in [
+			"",
+			"Qmxxxx",
+			// CIDv0 length with characters outside base58
+			"Qm0OIl00000000000000000000000000000000000000000",
+			"QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L0",
+			// Too large for a 34-byte multihash
+			"Qmzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz",
+			// Base58 of a 34-byte multihash with the wrong hash code
+			"S5db5FV3f8XV5jaiw5y2rXPP2stxaF2Nm2i842CZi7etLg",
+			// sha2-256 digest one byte short
+			"bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n",
+			// CIDv1 with version 2
+			"bajkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+			// Uppercase base32 and unknown multibase prefixes
+			"BAFKREIFZJUT3TE2NHYEKKLSS27NH3K72YSCO7Y32KOAO5EEI66WOF36N5E",
+			"fafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e",
+			"bafy",
+			"bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi1",
+			"b",
+			"z",
+		] {
+			assert!(!is_valid_cid(cid), "{} should be invalid", cid);
+		}
+		let too_long = format!("bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e{}", "a".repeat(8));
+		assert!(!is_valid_cid(&too_long));
+	}
+}
@@ -2,23 +2,23 @@ use anchor_lang::prelude::*;
 
 #[error_code]
 pub enum RegistryError {
-    #[msg("Package name is too long (max 64 characters)")] 
+    #[msg("Package name is too long (max 32 characters)")] 
     NameTooLong,
     #[msg("Package name is empty")] 
     NameEmpty,
-    #[msg("Package name contains invalid characters (use lowercase alphanumeric and hyphens only)")] 
+    #[msg("Package name is invalid (lowercase alphanumeric and hyphens, optionally prefixed by one scope/)")] 
     InvalidNameFormat,
-    #[msg("Version is too long (max 16 characters)")] 
+    #[msg("Version is too long (max 32 characters)")] 
     VersionTooLong,
     #[msg("Version is empty")] 
     VersionEmpty,
-    #[msg("Version format is invalid (use semantic versioning: X.Y.Z)")] 
+    #[msg("Version format is invalid (use semantic versioning: X.Y.Z[-prerelease][+build])")] 
     InvalidVersionFormat,
     #[msg("IPFS CID is too long (max 64 characters)")] 
     CidTooLong,
     #[msg("IPFS CID is empty")] 
     CidEmpty,
-    #[msg("IPFS CID format is invalid (must start with 'Qm' or 'bafy')")] 
+    #[msg("IPFS CID is invalid (a base58 CIDv0 or a base32 or base58 CIDv1 with a well-formed multihash)")] 
     InvalidCidFormat,
     #[msg("Description is too long (max 256 characters)")] 
     DescriptionTooLong,
@@ -38,4 +38,46 @@ pub enum RegistryError {
     UnauthorizedAuthority,
     #[msg("Arithmetic overflow occurred")] 
     ArithmeticOverflow,
+    #[msg("Content checksum must be the sha256 of the package archive, not all zeros")] 
+    InvalidContentChecksum,
+    #[msg("Repository URL is too long (max 128 characters)")] 
+    RepositoryTooLong,
+    #[msg("Homepage URL is too long (max 128 characters)")] 
+    HomepageTooLong,
+    #[msg("Repository and homepage must be http:// or https:// URLs without spaces")] 
+    InvalidUrl,
+    #[msg("Too many keywords (max 5)")] 
+    TooManyKeywords,
+    #[msg("Keyword is invalid (1-20 lowercase alphanumeric characters and hyphens)")] 
+    InvalidKeyword,
+    #[msg("Namespace scope is invalid (1-32 lowercase alphanumeric characters and hyphens)")] 
+    InvalidScope,
+    #[msg("Scoped package names must be published with their namespace account")] 
+    NamespaceRequired,
+    #[msg("Only the namespace owner or its publishers can publish under this scope")] 
+    UnauthorizedNamespacePublisher,
+    #[msg("Only the namespace owner can change its publishers")] 
+    NotNamespaceOwner,
+    #[msg("Too many namespace publishers (max 10)")] 
+    TooManyPublishers,
+    #[msg("Too many maintainers (max 5)")] 
+    TooManyMaintainers,
+    #[msg("Wallet is already a maintainer of this package")] 
+    MaintainerAlreadyListed,
+    #[msg("Wallet is not a maintainer of this package")] 
+    MaintainerNotListed,
+    #[msg("README CID is invalid (a CIDv0 or CIDv1, max 64 characters)")] 
+    InvalidReadmeCid,
+    #[msg("Too many external dependencies (max 10)")] 
+    TooManyExternalDependencies,
+    #[msg("External dependency name is invalid (1-64 printable characters, no spaces, ';' or '|')")] 
+    InvalidExternalDependencyName,
+    #[msg("External dependency version is invalid (1-32 printable characters, no spaces, ';' or '|')")] 
+    InvalidExternalDependencyVersion,
+    #[msg("External dependency type is invalid (must be rust, npm, python or go)")] 
+    InvalidExternalDependencyType,
+    #[msg("External dependency registry is invalid (1-64 printable characters, no spaces, ';' or '|')")] 
+    InvalidExternalDependencyRegistry,
+    #[msg("Package version is frozen; it can no longer be updated or transferred")] 
+    PackageFrozen,
 }
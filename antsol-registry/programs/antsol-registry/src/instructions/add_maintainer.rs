@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::RegistryError;
+
+/// Let another wallet publish new versions of a package on the signer's
+/// behalf. Signed by the current authority of the given version; the list is
+/// that authority's own, created on first use.
+#[derive(Accounts)]
+#[instruction(name: String, version: String)]
+pub struct AddMaintainer<'info> {
+	#[account(mut)]
+	pub authority: Signer<'info>,
+	#[account(
+		seeds = [b"package", name.as_bytes(), version.as_bytes()],
+		bump = package.bump,
+		constraint = package.authority == authority.key() @ RegistryError::UnauthorizedAuthority
+	)]
+	pub package: Account<'info, Package>,
+	#[account(
+		init_if_needed,
+		payer = authority,
+		space = Maintainers::SPACE,
+		seeds = [b"maintainers", name.as_bytes(), authority.key().as_ref()],
+		bump
+	)]
+	pub maintainers: Account<'info, Maintainers>,
+	pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<AddMaintainer>, maintainer: Pubkey) -> Result<()> {
+	let authority = ctx.accounts.authority.key();
+	let list = &mut ctx.accounts.maintainers;
+	require!(!list.contains(&maintainer), RegistryError::MaintainerAlreadyListed);
+	require!(list.maintainers.len() < MAX_MAINTAINERS, RegistryError::TooManyMaintainers);
+
+	if list.name.is_empty() {
+		list.name = ctx.accounts.package.name.clone();
+		list.bump = ctx.bumps.maintainers;
+		list.authority = authority;
+	}
+	list.maintainers.push(maintainer);
+
+	emit!(MaintainerAdded {
+		name: list.name.clone(),
+		maintainer,
+		authority,
+		timestamp: Clock::get()?.unix_timestamp,
+	});
+	msg!("👥 Maintainer added: {} maintainer={} by {}", list.name, maintainer, authority);
+	Ok(())
+}
+
+#[event]
+pub struct MaintainerAdded {
+	pub name: String,
+	pub maintainer: Pubkey,
+	pub authority: Pubkey,
+	pub timestamp: i64,
+}
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use crate::state::*;
+use crate::errors::RegistryError;
+
+/// Freeze a package version for good. Signed by its authority, which is
+/// replaced by the system program so nobody can sign for it again. Only
+/// this version's account is frozen; other versions of the name keep their
+/// own authorities.
+#[derive(Accounts)]
+#[instruction(name: String, version: String)]
+pub struct FreezePackage<'info> {
+	/// Pays the rent for the flag on accounts sized before it
+	#[account(mut)]
+	pub authority: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [b"package", name.as_bytes(), version.as_bytes()],
+		bump = package.bump,
+		constraint = !package.frozen.0 @ RegistryError::PackageFrozen,
+		constraint = package.authority == authority.key() @ RegistryError::UnauthorizedAuthority,
+		realloc = package.to_account_info().data_len().max(package.current_space()),
+		realloc::payer = authority,
+		realloc::zero = false
+	)]
+	pub package: Account<'info, Package>,
+	pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<FreezePackage>) -> Result<()> {
+	let package = &mut ctx.accounts.package;
+	let authority = package.authority;
+	package.authority = system_program::ID;
+	package.frozen = Frozen(true);
+	emit!(PackageFrozen {
+		name: package.name.clone(),
+		version: package.version.clone(),
+		authority,
+		timestamp: Clock::get()?.unix_timestamp,
+	});
+	msg!("🔒 Package frozen: {}@{} by {}", package.name, package.version, authority);
+	Ok(())
+}
+
+#[event]
+pub struct PackageFrozen {
+	pub name: String,
+	pub version: String,
+	/// The authority that froze it, the last there will be
+	pub authority: Pubkey,
+	pub timestamp: i64,
+}
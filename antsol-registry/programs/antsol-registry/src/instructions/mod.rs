@@ -1,7 +1,19 @@
 pub mod publish_package;
 pub mod update_package;
 pub mod transfer_authority;
+pub mod register_namespace;
+pub mod set_namespace_publishers;
+pub mod add_maintainer;
+pub mod remove_maintainer;
+pub mod record_download;
+pub mod freeze_package;
 
 pub use publish_package::*;
 pub use update_package::*;
 pub use transfer_authority::*;
+pub use register_namespace::*;
+pub use set_namespace_publishers::*;
+pub use add_maintainer::*;
+pub use remove_maintainer::*;
+pub use record_download::*;
+pub use freeze_package::*;
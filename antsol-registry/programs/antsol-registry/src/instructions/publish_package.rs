@@ -1,22 +1,43 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::RegistryError;
+use crate::version::is_valid_semver;
+use crate::name::{is_valid_name_segment, is_valid_package_name, package_scope};
+use crate::cid::is_valid_cid;
 
 /// Publish a new package to the registry
 #[derive(Accounts)]
-#[instruction(name: String, version: String)]
+#[instruction(
+	name: String,
+	version: String,
+	ipfs_cid: String,
+	description: String,
+	dependencies: Vec<PackageDependency>,
+	content_sha256: [u8; 32],
+	repository: String,
+	homepage: String,
+	keywords: Vec<String>,
+	readme_cid: Option<String>,
+	external_dependencies: Vec<ExternalDependency>,
+)]
 pub struct PublishPackage<'info> {
 	#[account(mut)]
 	pub authority: Signer<'info>,
 	#[account(
 		init,
 		payer = authority,
-		space = Package::MAX_SPACE,
+		space = Package::space_for(&name, &version, &ipfs_cid, &description, &dependencies, &repository, &homepage, &keywords, readme_cid.as_deref(), &external_dependencies),
 		seeds = [b"package", name.as_bytes(), version.as_bytes()],
 		bump
 	)]
 	pub package: Account<'info, Package>,
 	pub system_program: Program<'info, System>,
+	/// The scope's namespace; required for scoped names, omitted otherwise
+	#[account(
+		seeds = [b"namespace", package_scope(&name).unwrap_or_default().as_bytes()],
+		bump = namespace.bump
+	)]
+	pub namespace: Option<Account<'info, Namespace>>,
 }
 
 pub fn handler(
@@ -26,10 +47,23 @@ pub fn handler(
 	ipfs_cid: String,
 	description: String,
 	dependencies: Vec<PackageDependency>,
+	content_sha256: [u8; 32],
+	repository: String,
+	homepage: String,
+	keywords: Vec<String>,
+	readme_cid: Option<String>,
+	external_dependencies: Vec<ExternalDependency>,
 ) -> Result<()> {
 	require!(!name.is_empty(), RegistryError::NameEmpty);
 	require!(name.len() <= MAX_NAME_LENGTH, RegistryError::NameTooLong);
 	require!(is_valid_package_name(&name), RegistryError::InvalidNameFormat);
+	if package_scope(&name).is_some() {
+		let namespace = ctx.accounts.namespace.as_ref().ok_or(RegistryError::NamespaceRequired)?;
+		require!(
+			namespace.can_publish(&ctx.accounts.authority.key()),
+			RegistryError::UnauthorizedNamespacePublisher
+		);
+	}
 
 	require!(!version.is_empty(), RegistryError::VersionEmpty);
 	require!(version.len() <= MAX_VERSION_LENGTH, RegistryError::VersionTooLong);
@@ -37,10 +71,12 @@ pub fn handler(
 
 	require!(!ipfs_cid.is_empty(), RegistryError::CidEmpty);
 	require!(ipfs_cid.len() <= MAX_CID_LENGTH, RegistryError::CidTooLong);
-	require!(is_valid_ipfs_cid(&ipfs_cid), RegistryError::InvalidCidFormat);
+	require!(is_valid_cid(&ipfs_cid), RegistryError::InvalidCidFormat);
 
 	require!(description.len() <= MAX_DESCRIPTION_LENGTH, RegistryError::DescriptionTooLong);
 
+	require!(content_sha256 != [0u8; 32], RegistryError::InvalidContentChecksum);
+
 	require!(dependencies.len() <= MAX_DEPENDENCIES, RegistryError::TooManyDependencies);
 	for dep in &dependencies {
 		require!(!dep.name.is_empty(), RegistryError::InvalidDependencyName);
@@ -51,6 +87,10 @@ pub fn handler(
 		require!(is_valid_semver(&dep.version), RegistryError::InvalidDependencyVersion);
 	}
 
+	validate_metadata(&repository, &homepage, &keywords)?;
+	validate_readme_cid(readme_cid.as_deref())?;
+	validate_external_dependencies(&external_dependencies)?;
+
 	let clock = Clock::get()?;
 	let current_timestamp = clock.unix_timestamp;
 
@@ -63,32 +103,127 @@ pub fn handler(
 	package.description = description;
 	package.dependencies = dependencies;
 	package.bump = ctx.bumps.package;
+	package.content_sha256 = content_sha256;
+	package.repository = repository;
+	package.homepage = homepage;
+	package.keywords = keywords;
+	package.readme_cid = ReadmeCid(readme_cid);
+	package.external_dependencies = ExternalDependencies(external_dependencies);
 
 	emit!(PackagePublished {
 		name: package.name.clone(),
 		version: package.version.clone(),
 		authority: package.authority,
 		ipfs_cid: ipfs_cid,
+		content_sha256,
+		repository: package.repository.clone(),
+		homepage: package.homepage.clone(),
+		keywords: package.keywords.clone(),
+		readme_cid: package.readme_cid.0.clone(),
+		dependencies: package.dependencies.clone(),
+		external_dependencies: package.external_dependencies.0.clone(),
 		timestamp: current_timestamp,
 	});
 
-	msg!("📦 Package published: {}@{}", package.name, package.version);
+	msg!("📦 Package published: {}@{} by {}", package.name, package.version, package.authority);
+	log_metadata(package);
+	log_dependencies(package);
+	log_external_dependencies(package);
+	Ok(())
+}
+
+/// Optional package metadata: http(s) URLs within their length limits, and
+/// keywords formatted like package names
+pub(crate) fn validate_metadata(repository: &str, homepage: &str, keywords: &[String]) -> Result<()> {
+	require!(repository.len() <= MAX_REPOSITORY_LENGTH, RegistryError::RepositoryTooLong);
+	require!(repository.is_empty() || is_valid_url(repository), RegistryError::InvalidUrl);
+	require!(homepage.len() <= MAX_HOMEPAGE_LENGTH, RegistryError::HomepageTooLong);
+	require!(homepage.is_empty() || is_valid_url(homepage), RegistryError::InvalidUrl);
+
+	require!(keywords.len() <= MAX_KEYWORDS, RegistryError::TooManyKeywords);
+	for keyword in keywords {
+		require!(keyword.len() <= MAX_KEYWORD_LENGTH, RegistryError::InvalidKeyword);
+		require!(is_valid_name_segment(keyword), RegistryError::InvalidKeyword);
+	}
+	Ok(())
+}
+
+/// An optional README CID has the same format as the package CID
+pub(crate) fn validate_readme_cid(readme_cid: Option<&str>) -> Result<()> {
+	if let Some(cid) = readme_cid {
+		require!(is_valid_cid(cid), RegistryError::InvalidReadmeCid);
+	}
+	Ok(())
+}
+
+/// External dependencies: a known ecosystem, and a name, version and
+/// registry that fit their limits and the log line below
+pub(crate) fn validate_external_dependencies(dependencies: &[ExternalDependency]) -> Result<()> {
+	require!(dependencies.len() <= MAX_EXTERNAL_DEPENDENCIES, RegistryError::TooManyExternalDependencies);
+	for dep in dependencies {
+		require!(is_loggable(&dep.name, MAX_EXTERNAL_NAME_LENGTH), RegistryError::InvalidExternalDependencyName);
+		require!(is_loggable(&dep.version, MAX_EXTERNAL_VERSION_LENGTH), RegistryError::InvalidExternalDependencyVersion);
+		require!(
+			EXTERNAL_DEPENDENCY_TYPES.contains(&dep.dep_type.as_str()),
+			RegistryError::InvalidExternalDependencyType
+		);
+		if let Some(registry) = &dep.registry {
+			require!(is_loggable(registry, MAX_REGISTRY_LENGTH), RegistryError::InvalidExternalDependencyRegistry);
+		}
+	}
 	Ok(())
 }
 
-fn is_valid_package_name(name: &str) -> bool {
-	if name.is_empty() || name.len() > MAX_NAME_LENGTH { return false; }
-	if name.starts_with('-') || name.ends_with('-') { return false; }
-	name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+/// 1 to `max` printable ASCII characters other than the log line's separators
+fn is_loggable(value: &str, max: usize) -> bool {
+	!value.is_empty() && value.len() <= max && value.bytes().all(|b| b.is_ascii_graphic() && b != b';' && b != b'|')
+}
+
+/// Log the metadata as one line for log-based indexers; values never contain spaces
+pub(crate) fn log_metadata(package: &Package) {
+	let readme = package.readme_cid.0.as_deref().unwrap_or_default();
+	if package.repository.is_empty() && package.homepage.is_empty() && package.keywords.is_empty() && readme.is_empty() {
+		return;
+	}
+	msg!(
+		"📇 Package metadata: {}@{} repository={} homepage={} keywords={} readme={}",
+		package.name,
+		package.version,
+		package.repository,
+		package.homepage,
+		package.keywords.join(","),
+		readme
+	);
+}
+
+/// Log the full dependency list as one line for log-based indexers, as
+/// `name@version` pairs; at most `MAX_DEPENDENCIES` entries
+pub(crate) fn log_dependencies(package: &Package) {
+	if package.dependencies.is_empty() {
+		return;
+	}
+	let deps: Vec<String> = package.dependencies.iter()
+		.map(|d| format!("{}@{}", d.name, d.version))
+		.collect();
+	msg!("🧩 Package dependencies: {}@{} deps={}", package.name, package.version, deps.join(","));
 }
-fn is_valid_semver(version: &str) -> bool {
-	let parts: Vec<&str> = version.split('.').collect();
-	if parts.len() != 3 { return false; }
-	parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+
+/// Log the external dependencies as one line for log-based indexers, as
+/// `type|name|version|registry` entries separated by `;`, with an empty
+/// registry when there is none
+pub(crate) fn log_external_dependencies(package: &Package) {
+	if package.external_dependencies.0.is_empty() {
+		return;
+	}
+	let deps: Vec<String> = package.external_dependencies.0.iter()
+		.map(|d| format!("{}|{}|{}|{}", d.dep_type, d.name, d.version, d.registry.as_deref().unwrap_or_default()))
+		.collect();
+	msg!("📚 Package external dependencies: {}@{} deps={}", package.name, package.version, deps.join(";"));
 }
-fn is_valid_ipfs_cid(cid: &str) -> bool {
-	if cid.is_empty() || cid.len() > MAX_CID_LENGTH { return false; }
-	cid.starts_with("Qm") || cid.starts_with("bafy")
+
+fn is_valid_url(url: &str) -> bool {
+	let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"));
+	matches!(rest, Some(rest) if !rest.is_empty() && !url.chars().any(|c| c.is_whitespace() || c.is_control()))
 }
 
 #[event]
@@ -97,5 +232,12 @@ pub struct PackagePublished {
 	pub version: String,
 	pub authority: Pubkey,
 	pub ipfs_cid: String,
+	pub content_sha256: [u8; 32],
+	pub repository: String,
+	pub homepage: String,
+	pub keywords: Vec<String>,
+	pub readme_cid: Option<String>,
+	pub dependencies: Vec<PackageDependency>,
+	pub external_dependencies: Vec<ExternalDependency>,
 	pub timestamp: i64,
 }
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::RegistryError;
+
+/// Count one download of a package version. Anyone can call it.
+#[derive(Accounts)]
+#[instruction(name: String, version: String)]
+pub struct RecordDownload<'info> {
+	/// Pays the fee, and the rent for the counter on accounts sized before it
+	#[account(mut)]
+	pub payer: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [b"package", name.as_bytes(), version.as_bytes()],
+		bump = package.bump,
+		realloc = package.to_account_info().data_len().max(package.current_space()),
+		realloc::payer = payer,
+		realloc::zero = false
+	)]
+	pub package: Account<'info, Package>,
+	pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RecordDownload>) -> Result<()> {
+	let package = &mut ctx.accounts.package;
+	package.downloads.0 = package.downloads.0.checked_add(1).ok_or(RegistryError::ArithmeticOverflow)?;
+	emit!(PackageDownloaded {
+		name: package.name.clone(),
+		version: package.version.clone(),
+		downloads: package.downloads.0,
+		timestamp: Clock::get()?.unix_timestamp,
+	});
+	msg!("📥 Package downloaded: {}@{} downloads={}", package.name, package.version, package.downloads.0);
+	Ok(())
+}
+
+#[event]
+pub struct PackageDownloaded {
+	pub name: String,
+	pub version: String,
+	/// The counter after this download
+	pub downloads: u64,
+	pub timestamp: i64,
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::RegistryError;
+use crate::name::is_valid_scope;
+
+/// Claim a scope so that only its owner (and publishers the owner lists)
+/// can publish `scope/...` package names
+#[derive(Accounts)]
+#[instruction(scope: String)]
+pub struct RegisterNamespace<'info> {
+	#[account(mut)]
+	pub owner: Signer<'info>,
+	#[account(
+		init,
+		payer = owner,
+		space = Namespace::SPACE,
+		seeds = [b"namespace", scope.as_bytes()],
+		bump
+	)]
+	pub namespace: Account<'info, Namespace>,
+	pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RegisterNamespace>, scope: String) -> Result<()> {
+	require!(is_valid_scope(&scope), RegistryError::InvalidScope);
+
+	let timestamp = Clock::get()?.unix_timestamp;
+	let namespace = &mut ctx.accounts.namespace;
+	namespace.scope = scope;
+	namespace.owner = ctx.accounts.owner.key();
+	namespace.publishers = Vec::new();
+	namespace.created_at = timestamp;
+	namespace.bump = ctx.bumps.namespace;
+
+	emit!(NamespaceRegistered {
+		scope: namespace.scope.clone(),
+		owner: namespace.owner,
+		timestamp,
+	});
+	msg!("🏷️ Namespace registered: {} by {}", namespace.scope, namespace.owner);
+	Ok(())
+}
+
+#[event]
+pub struct NamespaceRegistered {
+	pub scope: String,
+	pub owner: Pubkey,
+	pub timestamp: i64,
+}
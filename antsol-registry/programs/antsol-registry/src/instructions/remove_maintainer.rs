@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::RegistryError;
+
+/// Stop a wallet from publishing new versions of a package. Signed by the
+/// current authority of the given version, from its own list.
+#[derive(Accounts)]
+#[instruction(name: String, version: String)]
+pub struct RemoveMaintainer<'info> {
+	pub authority: Signer<'info>,
+	#[account(
+		seeds = [b"package", name.as_bytes(), version.as_bytes()],
+		bump = package.bump,
+		constraint = package.authority == authority.key() @ RegistryError::UnauthorizedAuthority
+	)]
+	pub package: Account<'info, Package>,
+	#[account(
+		mut,
+		seeds = [b"maintainers", name.as_bytes(), authority.key().as_ref()],
+		bump = maintainers.bump
+	)]
+	pub maintainers: Account<'info, Maintainers>,
+}
+
+pub fn handler(ctx: Context<RemoveMaintainer>, maintainer: Pubkey) -> Result<()> {
+	let authority = ctx.accounts.authority.key();
+	let list = &mut ctx.accounts.maintainers;
+	let position = list.maintainers.iter().position(|m| *m == maintainer).ok_or(RegistryError::MaintainerNotListed)?;
+	list.maintainers.remove(position);
+
+	emit!(MaintainerRemoved {
+		name: list.name.clone(),
+		maintainer,
+		authority,
+		timestamp: Clock::get()?.unix_timestamp,
+	});
+	msg!("👥 Maintainer removed: {} maintainer={} by {}", list.name, maintainer, authority);
+	Ok(())
+}
+
+#[event]
+pub struct MaintainerRemoved {
+	pub name: String,
+	pub maintainer: Pubkey,
+	pub authority: Pubkey,
+	pub timestamp: i64,
+}
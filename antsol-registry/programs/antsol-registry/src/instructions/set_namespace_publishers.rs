@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use crate::state::*;
+use crate::errors::RegistryError;
+
+/// Replace the wallets, besides the owner, allowed to publish under a scope
+#[derive(Accounts)]
+#[instruction(scope: String)]
+pub struct SetNamespacePublishers<'info> {
+	pub owner: Signer<'info>,
+	#[account(
+		mut,
+		seeds = [b"namespace", scope.as_bytes()],
+		bump = namespace.bump,
+		has_one = owner @ RegistryError::NotNamespaceOwner
+	)]
+	pub namespace: Account<'info, Namespace>,
+}
+
+pub fn handler(ctx: Context<SetNamespacePublishers>, publishers: Vec<Pubkey>) -> Result<()> {
+	require!(publishers.len() <= MAX_NAMESPACE_PUBLISHERS, RegistryError::TooManyPublishers);
+
+	let namespace = &mut ctx.accounts.namespace;
+	namespace.publishers = publishers;
+
+	emit!(NamespacePublishersChanged {
+		scope: namespace.scope.clone(),
+		publishers: namespace.publishers.clone(),
+		timestamp: Clock::get()?.unix_timestamp,
+	});
+	msg!("🏷️ Namespace publishers changed: {} now has {} publisher(s)", namespace.scope, namespace.publishers.len());
+	Ok(())
+}
+
+#[event]
+pub struct NamespacePublishersChanged {
+	pub scope: String,
+	pub publishers: Vec<Pubkey>,
+	pub timestamp: i64,
+}
@@ -6,17 +6,23 @@ use crate::errors::RegistryError;
 #[derive(Accounts)]
 #[instruction(name: String, version: String)]
 pub struct TransferAuthority<'info> {
+	/// Pays the rent for fields added since the account was sized
 	#[account(mut)]
 	pub current_authority: Signer<'info>,
 	#[account(
 		mut,
 		seeds = [b"package", name.as_bytes(), version.as_bytes()],
 		bump = package.bump,
-		constraint = package.authority == current_authority.key() @ RegistryError::UnauthorizedAuthority
+		constraint = !package.frozen.0 @ RegistryError::PackageFrozen,
+		constraint = package.authority == current_authority.key() @ RegistryError::UnauthorizedAuthority,
+		realloc = package.to_account_info().data_len().max(package.current_space()),
+		realloc::payer = current_authority,
+		realloc::zero = false
 	)]
 	pub package: Account<'info, Package>,
 	/// CHECK: New authority (doesn't need to sign)
 	pub new_authority: AccountInfo<'info>,
+	pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<TransferAuthority>) -> Result<()> {
@@ -31,7 +37,13 @@ pub fn handler(ctx: Context<TransferAuthority>) -> Result<()> {
 		new_authority,
 		timestamp: Clock::get()?.unix_timestamp,
 	});
-	msg!("🔑 Authority transferred: {} -> {}", old_authority, new_authority);
+	msg!(
+		"🔑 Authority transferred: {}@{} from {} to {}",
+		package.name,
+		package.version,
+		old_authority,
+		new_authority
+	);
 	Ok(())
 }
 
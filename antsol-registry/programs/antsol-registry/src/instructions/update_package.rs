@@ -1,31 +1,62 @@
 use anchor_lang::prelude::*;
 use crate::state::*;
 use crate::errors::RegistryError;
+use crate::version::{is_valid_semver, is_version_greater};
+use crate::name::is_valid_package_name;
+use crate::cid::is_valid_cid;
+use super::publish_package::{
+	log_dependencies, log_external_dependencies, log_metadata, validate_external_dependencies, validate_metadata,
+	validate_readme_cid,
+};
 
 /// Update an existing package with a new version
 #[derive(Accounts)]
-#[instruction(name: String, new_version: String)]
+#[instruction(
+	name: String,
+	new_version: String,
+	ipfs_cid: String,
+	description: String,
+	dependencies: Vec<PackageDependency>,
+	content_sha256: [u8; 32],
+	repository: String,
+	homepage: String,
+	keywords: Vec<String>,
+	readme_cid: Option<String>,
+	external_dependencies: Vec<ExternalDependency>,
+)]
 pub struct UpdatePackage<'info> {
 	#[account(mut)]
 	pub authority: Signer<'info>,
 
+	/// Signed for by its authority or, with `maintainers`, a wallet its
+	/// authority listed.
+	/// Freezing is per version: a frozen version can't be updated from, but
+	/// an older unfrozen one still can, as any version of the name can be
+	/// published by anyone outside a namespace
 	#[account(
 		seeds = [b"package", existing_package.name.as_bytes(), existing_package.version.as_bytes()],
-		bump = existing_package.bump,
-		constraint = existing_package.authority == authority.key() @ RegistryError::UnauthorizedAuthority
+		bump = existing_package.bump
 	)]
 	pub existing_package: Account<'info, Package>,
 
 	#[account(
 		init,
 		payer = authority,
-		space = Package::MAX_SPACE,
+		space = Package::space_for(&name, &new_version, &ipfs_cid, &description, &dependencies, &repository, &homepage, &keywords, readme_cid.as_deref(), &external_dependencies),
 		seeds = [b"package", name.as_bytes(), new_version.as_bytes()],
 		bump
 	)]
 	pub new_package: Account<'info, Package>,
 
 	pub system_program: Program<'info, System>,
+
+	/// The maintainer list of `existing_package`'s authority; only needed
+	/// when a maintainer signs
+	#[account(
+		seeds = [b"maintainers", existing_package.name.as_bytes(), existing_package.authority.as_ref()],
+		bump = maintainers.bump
+	)]
+	pub maintainers: Option<Account<'info, Maintainers>>,
 }
 
 pub fn handler(
@@ -35,8 +66,18 @@ pub fn handler(
 	ipfs_cid: String,
 	description: String,
 	dependencies: Vec<PackageDependency>,
+	content_sha256: [u8; 32],
+	repository: String,
+	homepage: String,
+	keywords: Vec<String>,
+	readme_cid: Option<String>,
+	external_dependencies: Vec<ExternalDependency>,
 ) -> Result<()> {
 	let existing = &ctx.accounts.existing_package;
+	require!(!existing.frozen.0, RegistryError::PackageFrozen);
+	let signer = ctx.accounts.authority.key();
+	let is_maintainer = ctx.accounts.maintainers.as_ref().is_some_and(|list| list.contains(&signer));
+	require!(existing.authority == signer || is_maintainer, RegistryError::UnauthorizedAuthority);
 
 	require!(name == existing.name, RegistryError::UnauthorizedPackageName);
 
@@ -47,11 +88,13 @@ pub fn handler(
 
 	require!(!ipfs_cid.is_empty(), RegistryError::CidEmpty);
 	require!(ipfs_cid.len() <= MAX_CID_LENGTH, RegistryError::CidTooLong);
-	require!(is_valid_ipfs_cid(&ipfs_cid), RegistryError::InvalidCidFormat);
+	require!(is_valid_cid(&ipfs_cid), RegistryError::InvalidCidFormat);
 	require!(ipfs_cid != existing.ipfs_cid, RegistryError::SameCidAsExisting);
 
 	require!(description.len() <= MAX_DESCRIPTION_LENGTH, RegistryError::DescriptionTooLong);
 
+	require!(content_sha256 != [0u8; 32], RegistryError::InvalidContentChecksum);
+
 	require!(dependencies.len() <= MAX_DEPENDENCIES, RegistryError::TooManyDependencies);
 	for dep in &dependencies {
 		require!(!dep.name.is_empty(), RegistryError::InvalidDependencyName);
@@ -62,9 +105,23 @@ pub fn handler(
 		require!(is_valid_semver(&dep.version), RegistryError::InvalidDependencyVersion);
 	}
 
+	validate_metadata(&repository, &homepage, &keywords)?;
+	validate_readme_cid(readme_cid.as_deref())?;
+	validate_external_dependencies(&external_dependencies)?;
+
 	let clock = Clock::get()?;
 	let current_timestamp = clock.unix_timestamp;
 
+	// Dependency names added or dropped relative to the version being replaced
+	let added: Vec<String> = dependencies.iter()
+		.filter(|d| !existing.dependencies.iter().any(|e| e.name == d.name))
+		.map(|d| d.name.clone())
+		.collect();
+	let removed: Vec<String> = existing.dependencies.iter()
+		.filter(|e| !dependencies.iter().any(|d| d.name == e.name))
+		.map(|e| e.name.clone())
+		.collect();
+
 	let new_package = &mut ctx.accounts.new_package;
 	new_package.name = name.clone();
 	new_package.version = new_version.clone();
@@ -74,50 +131,81 @@ pub fn handler(
 	new_package.description = description;
 	new_package.dependencies = dependencies;
 	new_package.bump = ctx.bumps.new_package;
+	new_package.content_sha256 = content_sha256;
+	new_package.repository = repository;
+	new_package.homepage = homepage;
+	new_package.keywords = keywords;
+	new_package.readme_cid = ReadmeCid(readme_cid);
+	new_package.external_dependencies = ExternalDependencies(external_dependencies);
 
 	emit!(PackageUpdated {
 		name,
 		old_version: existing.version.clone(),
 		new_version,
 		authority: existing.authority,
+		signer,
+		repository: new_package.repository.clone(),
+		homepage: new_package.homepage.clone(),
+		keywords: new_package.keywords.clone(),
+		readme_cid: new_package.readme_cid.0.clone(),
+		dependencies: new_package.dependencies.clone(),
+		external_dependencies: new_package.external_dependencies.0.clone(),
 		timestamp: current_timestamp,
 	});
 
-	msg!("🔄 Package updated: {}@{}", new_package.name, new_package.version);
-	Ok(())
-}
-
-fn is_version_greater(version1: &str, version2: &str) -> bool {
-	let v1_parts: Vec<u32> = version1.split('.').filter_map(|s| s.parse().ok()).collect();
-	let v2_parts: Vec<u32> = version2.split('.').filter_map(|s| s.parse().ok()).collect();
-	if v1_parts.len() != 3 || v2_parts.len() != 3 { return false; }
-	for i in 0..3 {
-		if v1_parts[i] > v2_parts[i] { return true; }
-		else if v1_parts[i] < v2_parts[i] { return false; }
+	msg!("🔄 Package updated: {}@{} by {}", new_package.name, new_package.version, new_package.authority);
+	if signer != existing.authority {
+		msg!("👥 Signed by maintainer: {}@{} maintainer={}", new_package.name, new_package.version, signer);
+	}
+	log_metadata(new_package);
+	log_dependencies(new_package);
+	log_external_dependencies(new_package);
+
+	if !added.is_empty() || !removed.is_empty() {
+		msg!(
+			"🔗 Dependencies changed: {}@{} added={} removed={}",
+			new_package.name,
+			new_package.version,
+			added.join(","),
+			removed.join(",")
+		);
+		emit!(DependenciesChanged {
+			name: new_package.name.clone(),
+			version: new_package.version.clone(),
+			added,
+			removed,
+			timestamp: current_timestamp,
+		});
 	}
-	false
+	Ok(())
 }
 
-fn is_valid_package_name(name: &str) -> bool {
-	if name.is_empty() || name.len() > MAX_NAME_LENGTH { return false; }
-	if name.starts_with('-') || name.ends_with('-') { return false; }
-	name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
-}
-fn is_valid_semver(version: &str) -> bool {
-	let parts: Vec<&str> = version.split('.').collect();
-	if parts.len() != 3 { return false; }
-	parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
-}
-fn is_valid_ipfs_cid(cid: &str) -> bool {
-	if cid.is_empty() || cid.len() > MAX_CID_LENGTH { return false; }
-	cid.starts_with("Qm") || cid.starts_with("bafy")
-}
 
 #[event]
 pub struct PackageUpdated {
 	pub name: String,
 	pub old_version: String,
 	pub new_version: String,
+	/// Authority of the new version, carried over from the old one
 	pub authority: Pubkey,
+	/// Wallet that signed: the authority, or a maintainer it listed
+	pub signer: Pubkey,
+	pub repository: String,
+	pub homepage: String,
+	pub keywords: Vec<String>,
+	pub readme_cid: Option<String>,
+	pub dependencies: Vec<PackageDependency>,
+	pub external_dependencies: Vec<ExternalDependency>,
+	pub timestamp: i64,
+}
+
+/// Emitted alongside `PackageUpdated` when the new version's dependency set
+/// differs by name from the version it replaces
+#[event]
+pub struct DependenciesChanged {
+	pub name: String,
+	pub version: String,
+	pub added: Vec<String>,
+	pub removed: Vec<String>,
 	pub timestamp: i64,
 }
@@ -3,6 +3,9 @@ use anchor_lang::prelude::*;
 pub mod instructions;
 pub mod state;
 pub mod errors;
+pub mod version;
+pub mod name;
+pub mod cid;
 
 use instructions::*;
 use state::*;
@@ -21,6 +24,12 @@ pub mod antsol_registry {
         ipfs_cid: String,
         description: String,
         dependencies: Vec<PackageDependency>,
+        content_sha256: [u8; 32],
+        repository: String,
+        homepage: String,
+        keywords: Vec<String>,
+        readme_cid: Option<String>,
+        external_dependencies: Vec<ExternalDependency>,
     ) -> Result<()> {
         instructions::publish_package::handler(
             ctx,
@@ -29,6 +38,12 @@ pub mod antsol_registry {
             ipfs_cid,
             description,
             dependencies,
+            content_sha256,
+            repository,
+            homepage,
+            keywords,
+            readme_cid,
+            external_dependencies,
         )
     }
 
@@ -40,6 +55,12 @@ pub mod antsol_registry {
         ipfs_cid: String,
         description: String,
         dependencies: Vec<PackageDependency>,
+        content_sha256: [u8; 32],
+        repository: String,
+        homepage: String,
+        keywords: Vec<String>,
+        readme_cid: Option<String>,
+        external_dependencies: Vec<ExternalDependency>,
     ) -> Result<()> {
         instructions::update_package::handler(
             ctx,
@@ -48,6 +69,12 @@ pub mod antsol_registry {
             ipfs_cid,
             description,
             dependencies,
+            content_sha256,
+            repository,
+            homepage,
+            keywords,
+            readme_cid,
+            external_dependencies,
         )
     }
 
@@ -59,4 +86,59 @@ pub mod antsol_registry {
     ) -> Result<()> {
         instructions::transfer_authority::handler(ctx)
     }
+
+    /// Claim a namespace scope for scoped package names (`scope/name`)
+    pub fn register_namespace(ctx: Context<RegisterNamespace>, scope: String) -> Result<()> {
+        instructions::register_namespace::handler(ctx, scope)
+    }
+
+    /// Replace the wallets allowed to publish under a namespace besides its owner
+    pub fn set_namespace_publishers(
+        ctx: Context<SetNamespacePublishers>,
+        _scope: String,
+        publishers: Vec<Pubkey>,
+    ) -> Result<()> {
+        instructions::set_namespace_publishers::handler(ctx, publishers)
+    }
+
+    /// Let another wallet publish new versions of a package on the signing
+    /// authority's behalf
+    pub fn add_maintainer(
+        ctx: Context<AddMaintainer>,
+        _name: String,
+        _version: String,
+        maintainer: Pubkey,
+    ) -> Result<()> {
+        instructions::add_maintainer::handler(ctx, maintainer)
+    }
+
+    /// Remove a wallet from the signing authority's maintainers of a package
+    pub fn remove_maintainer(
+        ctx: Context<RemoveMaintainer>,
+        _name: String,
+        _version: String,
+        maintainer: Pubkey,
+    ) -> Result<()> {
+        instructions::remove_maintainer::handler(ctx, maintainer)
+    }
+
+    /// Count a download of a package version; anyone may call it
+    pub fn record_download(
+        ctx: Context<RecordDownload>,
+        _name: String,
+        _version: String,
+    ) -> Result<()> {
+        instructions::record_download::handler(ctx)
+    }
+
+    /// Make a package version final: burn its authority so it can never be
+    /// updated from or transferred again. Other versions of the name are
+    /// unaffected
+    pub fn freeze_package(
+        ctx: Context<FreezePackage>,
+        _name: String,
+        _version: String,
+    ) -> Result<()> {
+        instructions::freeze_package::handler(ctx)
+    }
 }
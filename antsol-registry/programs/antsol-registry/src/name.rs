@@ -0,0 +1,30 @@
+//! Package and namespace name rules, shared by the instructions
+
+use crate::state::{MAX_NAME_LENGTH, MAX_SCOPE_LENGTH};
+
+/// Lowercase alphanumerics and hyphens, not starting or ending with a
+/// hyphen: an unscoped package name, a scope, or a keyword
+pub fn is_valid_name_segment(segment: &str) -> bool {
+	if segment.is_empty() { return false; }
+	if segment.starts_with('-') || segment.ends_with('-') { return false; }
+	segment.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A namespace scope: a name segment short enough to be a PDA seed
+pub fn is_valid_scope(scope: &str) -> bool {
+	scope.len() <= MAX_SCOPE_LENGTH && is_valid_name_segment(scope)
+}
+
+/// `name` or `scope/name`: exactly one `/` at most, with both sides valid
+pub fn is_valid_package_name(name: &str) -> bool {
+	if name.is_empty() || name.len() > MAX_NAME_LENGTH { return false; }
+	match name.split_once('/') {
+		Some((scope, rest)) => is_valid_scope(scope) && is_valid_name_segment(rest),
+		None => is_valid_name_segment(name),
+	}
+}
+
+/// The scope of a scoped name (`alice` for `alice/token-utils`)
+pub fn package_scope(name: &str) -> Option<&str> {
+	name.split_once('/').map(|(scope, _)| scope)
+}
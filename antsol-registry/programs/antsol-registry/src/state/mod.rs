@@ -1,15 +1,41 @@
 use anchor_lang::prelude::*;
 
-/// Maximum length for package name
-pub const MAX_NAME_LENGTH: usize = 64;
-/// Maximum length for version string (semver)
-pub const MAX_VERSION_LENGTH: usize = 16;
+/// Maximum length for package name, scope included (it is a PDA seed)
+pub const MAX_NAME_LENGTH: usize = 32;
+/// Maximum length for version string (semver, including prerelease and build metadata)
+pub const MAX_VERSION_LENGTH: usize = 32;
 /// Maximum length for IPFS CID
 pub const MAX_CID_LENGTH: usize = 64;
 /// Maximum length for description
 pub const MAX_DESCRIPTION_LENGTH: usize = 256;
 /// Maximum number of dependencies
 pub const MAX_DEPENDENCIES: usize = 10;
+/// Maximum length for the repository URL
+pub const MAX_REPOSITORY_LENGTH: usize = 128;
+/// Maximum length for the homepage URL
+pub const MAX_HOMEPAGE_LENGTH: usize = 128;
+/// Maximum number of keywords
+pub const MAX_KEYWORDS: usize = 5;
+/// Maximum length for one keyword
+pub const MAX_KEYWORD_LENGTH: usize = 20;
+/// Maximum length for a namespace scope (it is a PDA seed)
+pub const MAX_SCOPE_LENGTH: usize = 32;
+/// Maximum number of publishers a namespace lists besides its owner
+pub const MAX_NAMESPACE_PUBLISHERS: usize = 10;
+/// Maximum number of maintainers a package lists besides its authority
+pub const MAX_MAINTAINERS: usize = 5;
+/// Maximum number of dependencies from other ecosystems
+pub const MAX_EXTERNAL_DEPENDENCIES: usize = 10;
+/// Maximum length for an external dependency's name
+pub const MAX_EXTERNAL_NAME_LENGTH: usize = 64;
+/// Maximum length for an external dependency's version requirement
+pub const MAX_EXTERNAL_VERSION_LENGTH: usize = 32;
+/// Maximum length for an external dependency's registry
+pub const MAX_REGISTRY_LENGTH: usize = 64;
+/// Ecosystems an external dependency can come from
+pub const EXTERNAL_DEPENDENCY_TYPES: [&str; 4] = ["rust", "npm", "python", "go"];
+/// Length of the longest `EXTERNAL_DEPENDENCY_TYPES` entry
+pub const MAX_DEPENDENCY_TYPE_LENGTH: usize = 6;
 
 /// Package account structure stored on-chain
 /// Each version of a package gets its own account
@@ -31,36 +57,245 @@ pub struct Package {
 	pub dependencies: Vec<PackageDependency>,
 	/// PDA bump seed
 	pub bump: u8,
+	/// sha256 of the exact tar.gz uploaded to IPFS.
+	/// Kept last so accounts created before it existed read as all zeros.
+	pub content_sha256: [u8; 32],
+	/// Source repository URL, empty when not given.
+	/// This and the fields below sit in what older accounts left as zero
+	/// padding, so those read as empty.
+	pub repository: String,
+	/// Project homepage URL, empty when not given
+	pub homepage: String,
+	/// Search keywords, formatted like package names
+	pub keywords: Vec<String>,
+	/// CID of the package's README.md, pinned on its own.
+	/// Accounts sized before it existed end right before it and read as none.
+	pub readme_cid: ReadmeCid,
+	/// Dependencies from other ecosystems (crates, npm packages, ...).
+	/// Accounts sized before it existed end right before it and read as none.
+	pub external_dependencies: ExternalDependencies,
+	/// Downloads counted on-chain by `record_download`.
+	/// Accounts sized before it existed end right before it and read as 0
+	/// until their first recorded download grows them.
+	pub downloads: DownloadCount,
+	/// Set for good by `freeze_package`, which also burns the authority.
+	/// Accounts sized before it existed end right before it and read as
+	/// not frozen.
+	pub frozen: Frozen,
 }
 
 impl Package {
-	/// Calculate space needed for account
+	/// Calculate space needed for account.
+	/// `deps_len`, `keywords_len` and `external_deps_len` are the serialized
+	/// sizes of the items, without the Vec length prefix; `readme_cid_len` is
+	/// the size of the CID string with its prefix, 0 when there is none.
+	#[allow(clippy::too_many_arguments)]
 	pub const fn space(
 		name_len: usize,
 		version_len: usize,
+		cid_len: usize,
 		description_len: usize,
-		deps_count: usize,
+		deps_len: usize,
+		repository_len: usize,
+		homepage_len: usize,
+		keywords_len: usize,
+		readme_cid_len: usize,
+		external_deps_len: usize,
 	) -> usize {
 		8 + // discriminator
 		4 + name_len + // String prefix + data
 		4 + version_len +
 		32 + // Pubkey
-		4 + MAX_CID_LENGTH +
+		4 + cid_len +
 		8 + // i64
 		4 + description_len +
-		4 + (deps_count * PackageDependency::LEN) + // Vec prefix + data
-		1 // bump
+		4 + deps_len + // Vec prefix + data
+		1 + // bump
+		32 + // content_sha256
+		4 + repository_len +
+		4 + homepage_len +
+		4 + keywords_len +
+		1 + readme_cid_len + // Option tag + String
+		4 + external_deps_len +
+		8 + // downloads
+		1 // frozen
+	}
+
+	/// Exact space for a package built from these instruction arguments.
+	/// Packages only grow to fit fields added after they were created, so
+	/// nothing is reserved beyond that.
+	#[allow(clippy::too_many_arguments)]
+	pub fn space_for(
+		name: &str,
+		version: &str,
+		ipfs_cid: &str,
+		description: &str,
+		dependencies: &[PackageDependency],
+		repository: &str,
+		homepage: &str,
+		keywords: &[String],
+		readme_cid: Option<&str>,
+		external_dependencies: &[ExternalDependency],
+	) -> usize {
+		Self::space(
+			name.len(),
+			version.len(),
+			ipfs_cid.len(),
+			description.len(),
+			dependencies.iter().map(PackageDependency::size).sum(),
+			repository.len(),
+			homepage.len(),
+			keywords.iter().map(|k| 4 + k.len()).sum(),
+			readme_cid.map_or(0, |cid| 4 + cid.len()),
+			external_dependencies.iter().map(ExternalDependency::size).sum(),
+		)
+	}
+
+	/// Exact space for this package, download counter and frozen flag included
+	pub fn current_space(&self) -> usize {
+		Self::space_for(
+			&self.name,
+			&self.version,
+			&self.ipfs_cid,
+			&self.description,
+			&self.dependencies,
+			&self.repository,
+			&self.homepage,
+			&self.keywords,
+			self.readme_cid.0.as_deref(),
+			&self.external_dependencies.0,
+		)
 	}
 
 	/// Maximum possible space for a package account
 	pub const MAX_SPACE: usize = Self::space(
 		MAX_NAME_LENGTH,
 		MAX_VERSION_LENGTH,
+		MAX_CID_LENGTH,
 		MAX_DESCRIPTION_LENGTH,
-		MAX_DEPENDENCIES,
+		MAX_DEPENDENCIES * PackageDependency::LEN,
+		MAX_REPOSITORY_LENGTH,
+		MAX_HOMEPAGE_LENGTH,
+		MAX_KEYWORDS * (4 + MAX_KEYWORD_LENGTH),
+		4 + MAX_CID_LENGTH,
+		MAX_EXTERNAL_DEPENDENCIES * ExternalDependency::LEN,
 	);
 }
 
+/// Optional README CID, serialized as an `Option<String>`.
+/// Reading it tolerates a missing tag byte, which is where exactly sized
+/// accounts from before the field end.
+#[derive(AnchorSerialize, Clone, Debug, Default, PartialEq)]
+pub struct ReadmeCid(pub Option<String>);
+
+impl AnchorDeserialize for ReadmeCid {
+	fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+		let mut tag = [0u8; 1];
+		if reader.read(&mut tag)? == 0 {
+			return Ok(Self(None));
+		}
+		match tag[0] {
+			0 => Ok(Self(None)),
+			1 => Ok(Self(Some(String::deserialize_reader(reader)?))),
+			other => Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("invalid README CID tag {other}"),
+			)),
+		}
+	}
+}
+
+/// External dependencies, serialized as a `Vec<ExternalDependency>`.
+/// Reading it tolerates a missing length prefix, which is where exactly
+/// sized accounts from before the field end.
+#[derive(AnchorSerialize, Clone, Debug, Default, PartialEq)]
+pub struct ExternalDependencies(pub Vec<ExternalDependency>);
+
+impl AnchorDeserialize for ExternalDependencies {
+	fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+		let mut len = [0u8; 4];
+		if reader.read(&mut len[..1])? == 0 {
+			return Ok(Self(Vec::new()));
+		}
+		reader.read_exact(&mut len[1..])?;
+		// The length comes from account data, so it isn't trusted for an allocation
+		let mut deps = Vec::new();
+		for _ in 0..u32::from_le_bytes(len) {
+			deps.push(ExternalDependency::deserialize_reader(reader)?);
+		}
+		Ok(Self(deps))
+	}
+}
+
+/// Download counter, serialized as a `u64`.
+/// Reading it tolerates the counter's bytes being missing, which is where
+/// exactly sized accounts from before the field end.
+#[derive(AnchorSerialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct DownloadCount(pub u64);
+
+impl AnchorDeserialize for DownloadCount {
+	fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+		let mut bytes = [0u8; 8];
+		if reader.read(&mut bytes[..1])? == 0 {
+			return Ok(Self(0));
+		}
+		reader.read_exact(&mut bytes[1..])?;
+		Ok(Self(u64::from_le_bytes(bytes)))
+	}
+}
+
+/// Whether the package version is frozen, serialized as a `bool`.
+/// Reading it tolerates the flag's byte being missing, which is where
+/// exactly sized accounts from before the field end.
+#[derive(AnchorSerialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Frozen(pub bool);
+
+impl AnchorDeserialize for Frozen {
+	fn deserialize_reader<R: std::io::Read>(reader: &mut R) -> std::io::Result<Self> {
+		let mut byte = [0u8; 1];
+		if reader.read(&mut byte)? == 0 {
+			return Ok(Self(false));
+		}
+		match byte[0] {
+			0 => Ok(Self(false)),
+			1 => Ok(Self(true)),
+			other => Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("invalid frozen flag {other}"),
+			)),
+		}
+	}
+}
+
+/// A dependency outside the registry, recorded for consumers to install themselves
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ExternalDependency {
+	/// Name in its own registry (e.g., "serde", "@types/node")
+	pub name: String,
+	/// Version requirement in the ecosystem's own syntax (e.g., "^1.0")
+	pub version: String,
+	/// One of `EXTERNAL_DEPENDENCY_TYPES`
+	pub dep_type: String,
+	/// Registry it comes from (e.g., "crates.io"), when not the default
+	pub registry: Option<String>,
+}
+
+impl ExternalDependency {
+	/// Fixed length for an external dependency (max sizes)
+	pub const LEN: usize = 4 + MAX_EXTERNAL_NAME_LENGTH
+		+ 4 + MAX_EXTERNAL_VERSION_LENGTH
+		+ 4 + MAX_DEPENDENCY_TYPE_LENGTH
+		+ 1 + 4 + MAX_REGISTRY_LENGTH;
+
+	/// Serialized size of this dependency
+	pub fn size(&self) -> usize {
+		4 + self.name.len()
+			+ 4 + self.version.len()
+			+ 4 + self.dep_type.len()
+			+ 1 + self.registry.as_ref().map_or(0, |registry| 4 + registry.len())
+	}
+}
+
 /// Dependency structure
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub struct PackageDependency {
@@ -73,4 +308,123 @@ pub struct PackageDependency {
 impl PackageDependency {
 	/// Fixed length for dependency (max sizes)
 	pub const LEN: usize = 4 + MAX_NAME_LENGTH + 4 + MAX_VERSION_LENGTH;
+
+	/// Serialized size of this dependency
+	pub fn size(&self) -> usize {
+		4 + self.name.len() + 4 + self.version.len()
+	}
+}
+
+/// A scope claimed by a wallet (`alice` in `alice/token-utils`).
+/// Seeds: `["namespace", scope]`
+#[account]
+pub struct Namespace {
+	/// The scope, formatted like an unscoped package name
+	pub scope: String,
+	/// Wallet that registered the scope and manages its publishers
+	pub owner: Pubkey,
+	/// Other wallets allowed to publish under the scope
+	pub publishers: Vec<Pubkey>,
+	/// Unix timestamp when registered
+	pub created_at: i64,
+	/// PDA bump seed
+	pub bump: u8,
+}
+
+impl Namespace {
+	/// Fixed space, enough for the longest scope and a full publisher list
+	pub const SPACE: usize =
+		8 + // discriminator
+		4 + MAX_SCOPE_LENGTH +
+		32 + // owner
+		4 + MAX_NAMESPACE_PUBLISHERS * 32 +
+		8 + // i64
+		1; // bump
+
+	/// Whether `wallet` may publish package names under this scope
+	pub fn can_publish(&self, wallet: &Pubkey) -> bool {
+		self.owner == *wallet || self.publishers.contains(wallet)
+	}
+}
+
+/// Wallets allowed to publish new versions of a package on behalf of one
+/// authority. Keyed by name and authority, so a list only covers versions
+/// that authority holds, and stops applying to a version once it is
+/// transferred. Seeds: `["maintainers", name, authority]`
+#[account]
+pub struct Maintainers {
+	/// Package name the list applies to
+	pub name: String,
+	/// Listed wallets, in the order they were added
+	pub maintainers: Vec<Pubkey>,
+	/// PDA bump seed
+	pub bump: u8,
+	/// Authority whose versions the listed wallets may update from; only it
+	/// can change the list
+	pub authority: Pubkey,
+}
+
+impl Maintainers {
+	/// Fixed space, enough for the longest name and a full list
+	pub const SPACE: usize =
+		8 + // discriminator
+		4 + MAX_NAME_LENGTH +
+		4 + MAX_MAINTAINERS * 32 +
+		1 + // bump
+		32; // authority
+
+	/// Whether `wallet` is listed
+	pub fn contains(&self, wallet: &Pubkey) -> bool {
+		self.maintainers.contains(wallet)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sample_package() -> Package {
+		Package {
+			name: "legacy-pkg".to_string(),
+			version: "1.0.0".to_string(),
+			authority: Pubkey::new_unique(),
+			ipfs_cid: "QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L4".to_string(),
+			published_at: 1_700_000_000,
+			description: "A package sized before the newer fields".to_string(),
+			dependencies: vec![PackageDependency { name: "dep".to_string(), version: "1.0.0".to_string() }],
+			bump: 255,
+			content_sha256: [7; 32],
+			repository: String::new(),
+			homepage: String::new(),
+			keywords: vec!["solana".to_string()],
+			readme_cid: ReadmeCid(None),
+			external_dependencies: ExternalDependencies(Vec::new()),
+			downloads: DownloadCount(0),
+			frozen: Frozen(false),
+		}
+	}
+
+	#[test]
+	fn legacy_sized_accounts_grow_to_current_space_to_be_written_back() {
+		let mut current = Vec::new();
+		sample_package().try_serialize(&mut current).unwrap();
+		assert_eq!(current.len(), sample_package().current_space());
+
+		// Accounts created before readme_cid, external_dependencies, downloads and frozen
+		for missing in [1 + 4 + 8 + 1, 4 + 8 + 1, 8 + 1, 1] {
+			let legacy = &current[..current.len() - missing];
+			let mut package = Package::try_deserialize(&mut &legacy[..]).unwrap();
+			assert_eq!(package.readme_cid, ReadmeCid(None));
+			assert!(!package.frozen.0);
+
+			package.authority = Pubkey::new_unique();
+			let mut same_size = vec![0u8; legacy.len()];
+			assert!(package.try_serialize(&mut &mut same_size[..]).is_err(), "{} bytes short", missing);
+
+			// What the `realloc` constraints on mutating instructions grow the account to
+			let mut grown = vec![0u8; legacy.len().max(package.current_space())];
+			package.try_serialize(&mut &mut grown[..]).unwrap();
+			assert_eq!(grown.len(), current.len());
+		}
+	}
 }
@@ -0,0 +1,95 @@
+//! Semantic Versioning 2.0 parsing and precedence, shared by the instructions
+
+use std::cmp::Ordering;
+
+/// A parsed version. Build metadata is validated but never affects precedence.
+struct Version<'a> {
+	core: [u64; 3],
+	prerelease: Vec<&'a str>,
+}
+
+/// `MAJOR.MINOR.PATCH`, optionally followed by `-prerelease` and `+build`
+pub fn is_valid_semver(version: &str) -> bool {
+	parse(version).is_some()
+}
+
+/// Semver precedence of `a` against `b`; `None` if either is not a valid version
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+	let (a, b) = (parse(a)?, parse(b)?);
+	Some(a.core.cmp(&b.core).then_with(|| compare_prerelease(&a.prerelease, &b.prerelease)))
+}
+
+/// Whether `a` has strictly higher precedence than `b`
+pub fn is_version_greater(a: &str, b: &str) -> bool {
+	compare_versions(a, b) == Some(Ordering::Greater)
+}
+
+fn parse(version: &str) -> Option<Version<'_>> {
+	let (rest, build) = match version.split_once('+') {
+		Some((rest, build)) => (rest, Some(build)),
+		None => (version, None),
+	};
+	if let Some(build) = build {
+		if !build.split('.').all(is_identifier) { return None; }
+	}
+
+	let (core, prerelease) = match rest.split_once('-') {
+		Some((core, prerelease)) => (core, Some(prerelease)),
+		None => (rest, None),
+	};
+	let mut parts = core.split('.');
+	let mut numbers = [0u64; 3];
+	for number in numbers.iter_mut() {
+		let part = parts.next()?;
+		if !is_numeric(part) { return None; }
+		*number = part.parse().ok()?;
+	}
+	if parts.next().is_some() { return None; }
+
+	let prerelease: Vec<&str> = match prerelease {
+		Some(prerelease) => prerelease.split('.').collect(),
+		None => Vec::new(),
+	};
+	// Numeric identifiers may not have leading zeros
+	if !prerelease.iter().all(|id| is_identifier(id) && (!is_digits(id) || is_numeric(id))) {
+		return None;
+	}
+	Some(Version { core: numbers, prerelease })
+}
+
+/// A release sorts above its prereleases; otherwise identifiers compare left
+/// to right and a longer list wins when one is a prefix of the other
+fn compare_prerelease(a: &[&str], b: &[&str]) -> Ordering {
+	match (a.is_empty(), b.is_empty()) {
+		(true, true) => Ordering::Equal,
+		(true, false) => Ordering::Greater,
+		(false, true) => Ordering::Less,
+		(false, false) => a
+			.iter()
+			.zip(b)
+			.map(|(x, y)| compare_identifier(x, y))
+			.find(|ordering| ordering.is_ne())
+			.unwrap_or_else(|| a.len().cmp(&b.len())),
+	}
+}
+
+/// Numeric identifiers compare by value and sort below alphanumeric ones
+fn compare_identifier(a: &str, b: &str) -> Ordering {
+	match (is_digits(a), is_digits(b)) {
+		// No leading zeros, so the longer number is the larger one
+		(true, true) => a.len().cmp(&b.len()).then_with(|| a.cmp(b)),
+		(true, false) => Ordering::Less,
+		(false, true) => Ordering::Greater,
+		(false, false) => a.cmp(b),
+	}
+}
+
+fn is_identifier(id: &str) -> bool {
+	!id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+fn is_digits(id: &str) -> bool {
+	!id.is_empty() && id.chars().all(|c| c.is_ascii_digit())
+}
+fn is_numeric(id: &str) -> bool {
+	is_digits(id) && (id == "0" || !id.starts_with('0'))
+}
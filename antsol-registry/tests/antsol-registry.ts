@@ -12,6 +12,25 @@ interface PackageAccount {
   description: string;
   dependencies: { name: string; version: string }[];
   bump: number;
+  contentSha256: number[];
+  repository: string;
+  homepage: string;
+  keywords: string[];
+  // ReadmeCid is a tuple struct around Option<String>
+  readmeCid: { 0: string | null };
+  // ExternalDependencies is a tuple struct around Vec<ExternalDependency>
+  externalDependencies: { 0: ExternalDependency[] };
+  // DownloadCount is a tuple struct around u64
+  downloads: { 0: anchor.BN };
+  // Frozen is a tuple struct around bool
+  frozen: { 0: boolean };
+}
+
+interface ExternalDependency {
+  name: string;
+  version: string;
+  depType: string;
+  registry: string | null;
 }
 
 describe("antsol-registry", () => {
@@ -27,9 +46,15 @@ describe("antsol-registry", () => {
   const basePackage = {
     name: uniqueName("pkg"),
     version: "1.0.0",
-    ipfsCid: "QmTest123456789abcdefghijklmnopqrstuvwxyz",
+    ipfsCid: "QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L4",
     description: "A test package for AntSol registry",
     dependencies: [] as { name: string; version: string }[],
+    contentSha256: Array.from({ length: 32 }, (_, i) => i + 1),
+    repository: "",
+    homepage: "",
+    keywords: [] as string[],
+    readmeCid: null as string | null,
+    externalDependencies: [] as ExternalDependency[],
   };
 
   function getPackagePDA(name: string, version: string) {
@@ -42,7 +67,7 @@ describe("antsol-registry", () => {
   async function publish(pkg: typeof basePackage, authorityOverride?: anchor.web3.PublicKey) {
     const [pda] = getPackagePDA(pkg.name, pkg.version);
     return (program.methods as any)
-      .publishPackage(pkg.name, pkg.version, pkg.ipfsCid, pkg.description, pkg.dependencies)
+      .publishPackage(pkg.name, pkg.version, pkg.ipfsCid, pkg.description, pkg.dependencies, pkg.contentSha256, pkg.repository, pkg.homepage, pkg.keywords, pkg.readmeCid, pkg.externalDependencies)
       .accounts({
         authority: authorityOverride ?? authority.publicKey,
         package: pda,
@@ -51,19 +76,170 @@ describe("antsol-registry", () => {
       .rpc();
   }
 
+  function getNamespacePDA(scope: string) {
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("namespace"), Buffer.from(scope)],
+      program.programId
+    );
+  }
+
+  async function registerNamespace(scope: string) {
+    const [namespace] = getNamespacePDA(scope);
+    return (program.methods as any)
+      .registerNamespace(scope)
+      .accounts({
+        owner: authority.publicKey,
+        namespace,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  // Publish a scoped name, passing the scope's namespace unless told not to
+  async function publishScoped(pkg: typeof basePackage, signer?: anchor.web3.Keypair, withNamespace = true) {
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const [namespace] = getNamespacePDA(pkg.name.split("/")[0]);
+    const builder = (program.methods as any)
+      .publishPackage(pkg.name, pkg.version, pkg.ipfsCid, pkg.description, pkg.dependencies, pkg.contentSha256, pkg.repository, pkg.homepage, pkg.keywords, pkg.readmeCid, pkg.externalDependencies)
+      .accounts({
+        authority: signer?.publicKey ?? authority.publicKey,
+        package: pda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        namespace: withNamespace ? namespace : null,
+      });
+    return signer ? builder.signers([signer]).rpc() : builder.rpc();
+  }
+
+  // A new wallet funded from the provider wallet (devnet airdrops are unreliable)
+  async function fundedKeypair(): Promise<anchor.web3.Keypair> {
+    const keypair = anchor.web3.Keypair.generate();
+    const tx = new anchor.web3.Transaction().add(anchor.web3.SystemProgram.transfer({
+      fromPubkey: authority.publicKey,
+      toPubkey: keypair.publicKey,
+      lamports: 0.05 * anchor.web3.LAMPORTS_PER_SOL,
+    }));
+    await provider.sendAndConfirm(tx);
+    return keypair;
+  }
+
+  async function update(name: string, fromVersion: string, toVersion: string) {
+    const [existingPda] = getPackagePDA(name, fromVersion);
+    const [newPda] = getPackagePDA(name, toVersion);
+    return (program.methods as any)
+      .updatePackage(name, toVersion, basePackage.ipfsCid, basePackage.description, [], basePackage.contentSha256, "", "", [], null, [])
+      .accounts({
+        authority: authority.publicKey,
+        existingPackage: existingPda,
+        newPackage: newPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  // The maintainer list `owner` keeps for `name`
+  function getMaintainersPDA(name: string, owner: anchor.web3.PublicKey = authority.publicKey) {
+    return anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("maintainers"), Buffer.from(name), owner.toBuffer()],
+      program.programId
+    );
+  }
+
+  async function changeMaintainer(method: "addMaintainer" | "removeMaintainer", name: string, version: string, maintainer: anchor.web3.PublicKey, signer?: anchor.web3.Keypair) {
+    const [pkg] = getPackagePDA(name, version);
+    const signerKey = signer?.publicKey ?? authority.publicKey;
+    const [maintainers] = getMaintainersPDA(name, signerKey);
+    const accounts: Record<string, anchor.web3.PublicKey> = { authority: signerKey, package: pkg, maintainers };
+    if (method === "addMaintainer") accounts.systemProgram = anchor.web3.SystemProgram.programId;
+    const builder = (program.methods as any)[method](name, version, maintainer).accounts(accounts);
+    return signer ? builder.signers([signer]).rpc() : builder.rpc();
+  }
+
+  // Update signed by `signer`, passing the maintainer list `listOwner` keeps
+  async function updateAsMaintainer(signer: anchor.web3.Keypair, name: string, fromVersion: string, toVersion: string, listOwner = authority.publicKey) {
+    const [existingPda] = getPackagePDA(name, fromVersion);
+    const [newPda] = getPackagePDA(name, toVersion);
+    const [maintainers] = getMaintainersPDA(name, listOwner);
+    return (program.methods as any)
+      .updatePackage(name, toVersion, "QmbiWpYcp6srgwWeTadXu9fXAndDZRR2khEEAANYauMJ1G", basePackage.description, [], basePackage.contentSha256, "", "", [], null, [])
+      .accounts({
+        authority: signer.publicKey,
+        existingPackage: existingPda,
+        newPackage: newPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+        maintainers,
+      })
+      .signers([signer])
+      .rpc();
+  }
+
   async function fetchPackage(name: string, version: string): Promise<PackageAccount> {
     const [pda] = getPackagePDA(name, version);
     return (await (program.account as any)["package"].fetch(pda)) as PackageAccount;
   }
 
+  // Mirrors Package::space_for: exact bytes for an account built from these arguments
+  const PACKAGE_MAX_SPACE = 3505;
+  function packageSpace(pkg: typeof basePackage): number {
+    const str = (s: string) => 4 + Buffer.byteLength(s);
+    return 8 + str(pkg.name) + str(pkg.version) + 32 + str(pkg.ipfsCid) + 8 + str(pkg.description)
+      + 4 + pkg.dependencies.reduce((n, d) => n + str(d.name) + str(d.version), 0)
+      + 1 + 32 + str(pkg.repository) + str(pkg.homepage)
+      + 4 + pkg.keywords.reduce((n, k) => n + str(k), 0)
+      + 1 + (pkg.readmeCid === null ? 0 : str(pkg.readmeCid))
+      + 4 + pkg.externalDependencies.reduce(
+        (n, d) => n + str(d.name) + str(d.version) + str(d.depType) + 1 + (d.registry === null ? 0 : str(d.registry)),
+        0
+      )
+      + 8 + 1;
+  }
+
+  async function recordDownload(name: string, version: string, payer?: anchor.web3.Keypair) {
+    const [pda] = getPackagePDA(name, version);
+    const builder = (program.methods as any)
+      .recordDownload(name, version)
+      .accounts({
+        payer: payer?.publicKey ?? authority.publicKey,
+        package: pda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      });
+    return payer ? builder.signers([payer]).rpc() : builder.rpc();
+  }
+
+  async function freeze(name: string, version: string, signer?: anchor.web3.Keypair) {
+    const [pda] = getPackagePDA(name, version);
+    const builder = (program.methods as any)
+      .freezePackage(name, version)
+      .accounts({
+        authority: signer?.publicKey ?? authority.publicKey,
+        package: pda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      });
+    return signer ? builder.signers([signer]).rpc() : builder.rpc();
+  }
+
+  async function expectFrozen(action: () => Promise<unknown>, what: string) {
+    try {
+      await action();
+      assert.fail(`${what} should fail once the version is frozen`);
+    } catch (e: any) {
+      assert.include(e.toString(), "PackageFrozen");
+    }
+  }
+
   async function expectPublishFail(pkg: Partial<typeof basePackage>, expectSubstring?: string) {
     // Fill required fields with defaults if not provided
     const attempt = {
       name: pkg.name ?? uniqueName("bad"),
       version: pkg.version ?? "1.0.0",
-      ipfsCid: pkg.ipfsCid ?? "QmTest123456789abcdefghijklmnopqrstuvwxyz",
+      ipfsCid: pkg.ipfsCid ?? "QmaozNR7DZHQK1ZcU9p7QdrshMvXqWK6gpu5rmrkPdT3L4",
       description: pkg.description ?? "desc",
       dependencies: pkg.dependencies ?? [],
+      contentSha256: pkg.contentSha256 ?? basePackage.contentSha256,
+      repository: pkg.repository ?? "",
+      homepage: pkg.homepage ?? "",
+      keywords: pkg.keywords ?? [],
+      readmeCid: pkg.readmeCid ?? null,
+      externalDependencies: pkg.externalDependencies ?? [],
     };
     try {
       await publish(attempt);
@@ -84,6 +260,7 @@ describe("antsol-registry", () => {
     assert.equal(acct.name, basePackage.name);
     assert.equal(acct.version, basePackage.version);
     assert.equal(acct.ipfsCid, basePackage.ipfsCid);
+    assert.deepEqual(Array.from(acct.contentSha256), basePackage.contentSha256);
   });
 
   // Validation error cases (grouped)
@@ -107,8 +284,8 @@ describe("antsol-registry", () => {
     await expectPublishFail({ version: "" }, "VersionEmpty");
   });
   it("6 rejects version too long", async () => {
-    // Valid semver but exceeds MAX_VERSION_LENGTH (16)
-    await expectPublishFail({ version: "1234567890123456.0.0" }, "VersionTooLong");
+    // Valid semver but exceeds MAX_VERSION_LENGTH (32), which is also the PDA seed limit
+    await expectPublishFail({ version: `1.0.0-${"a".repeat(27)}` }, "Max seed length exceeded");
   });
   it("7 rejects invalid version format", async () => {
     await expectPublishFail({ version: "1.0" }, "InvalidVersionFormat");
@@ -136,12 +313,12 @@ describe("antsol-registry", () => {
   });
 
   // Update flows
-  const updatePkg = { ...basePackage, newVersion: "1.1.0", newCid: "QmNewCid123456789abcdefghijklmnopqrstuvwxyz" };
+  const updatePkg = { ...basePackage, newVersion: "1.1.0", newCid: "QmPWEpC8ikaHVXCGDuJ8ztDGBbJDL3QxMXmekjCZ4vwSog" };
   it("15 updates package with greater version", async () => {
     const [existingPda] = getPackagePDA(basePackage.name, basePackage.version);
     const [newPda] = getPackagePDA(basePackage.name, updatePkg.newVersion);
     const tx = await (program.methods as any)
-      .updatePackage(basePackage.name, updatePkg.newVersion, updatePkg.newCid, basePackage.description, basePackage.dependencies)
+      .updatePackage(basePackage.name, updatePkg.newVersion, updatePkg.newCid, basePackage.description, basePackage.dependencies, basePackage.contentSha256, "", "", [], null, [])
       .accounts({
         authority: authority.publicKey,
         existingPackage: existingPda,
@@ -161,7 +338,7 @@ describe("antsol-registry", () => {
     const [newPda] = getPackagePDA(basePackage.name, lowerVersion);
     try {
       await (program.methods as any)
-        .updatePackage(basePackage.name, lowerVersion, "QmAnotherCid123456789abcdefghijklmnopqrstuvwxyz", basePackage.description, basePackage.dependencies)
+        .updatePackage(basePackage.name, lowerVersion, "Qma4vGVdPBiZTCNVCSm8kYZkefxbAHNDFvDCQG18X9h7Sc", basePackage.description, basePackage.dependencies, basePackage.contentSha256, "", "", [], null, [])
         .accounts({
           authority: authority.publicKey,
           existingPackage: existingPda,
@@ -185,6 +362,7 @@ describe("antsol-registry", () => {
         currentAuthority: authority.publicKey,
         package: existingPda,
         newAuthority: newAuthority.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
       })
       .signers([])
       .rpc();
@@ -202,6 +380,7 @@ describe("antsol-registry", () => {
           currentAuthority: rogue.publicKey, // not the actual authority
           package: existingPda,
           newAuthority: anchor.web3.Keypair.generate().publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
         })
         // fee payer is provider (has funds); rogue is added as required signer
         .signers([rogue])
@@ -211,4 +390,430 @@ describe("antsol-registry", () => {
       assert.include(e.toString(), "UnauthorizedAuthority");
     }
   });
+
+  // Dependency delta reporting on update
+  it("19 reports added and removed dependencies on update", async () => {
+    const pkg = { ...basePackage, name: uniqueName("deps"), dependencies: [{ name: "old-dep", version: "1.0.0" }] };
+    await publish(pkg);
+    const [existingPda] = getPackagePDA(pkg.name, pkg.version);
+    const [newPda] = getPackagePDA(pkg.name, "1.0.1");
+    const tx = await (program.methods as any)
+      .updatePackage(pkg.name, "1.0.1", updatePkg.newCid, pkg.description, [{ name: "new-dep", version: "2.0.0" }], pkg.contentSha256, "", "", [], null, [])
+      .accounts({
+        authority: authority.publicKey,
+        existingPackage: existingPda,
+        newPackage: newPda,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    const txInfo = await provider.connection.getTransaction(tx, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+    const logs = (txInfo?.meta?.logMessages ?? []).join("\n");
+    assert.include(logs, `Dependencies changed: ${pkg.name}@1.0.1 added=new-dep removed=old-dep`);
+  });
+
+  // Content checksum
+  it("20 rejects an all-zero content checksum", async () => {
+    await expectPublishFail({ contentSha256: new Array(32).fill(0) }, "InvalidContentChecksum");
+  });
+
+  // Optional metadata
+  it("21 stores repository, homepage and keywords", async () => {
+    const pkg = {
+      ...basePackage,
+      name: uniqueName("meta"),
+      repository: "https://github.com/antsol/example",
+      homepage: "https://antsol.dev",
+      keywords: ["solana", "anchor-utils"],
+    };
+    await publish(pkg);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.repository, pkg.repository);
+    assert.equal(acct.homepage, pkg.homepage);
+    assert.deepEqual(acct.keywords, pkg.keywords);
+  });
+  it("22 rejects too many keywords", async () => {
+    await expectPublishFail({ keywords: ["a", "b", "c", "d", "e", "f"] }, "TooManyKeywords");
+  });
+  it("23 rejects invalid keyword", async () => {
+    await expectPublishFail({ keywords: ["Not Valid"] }, "InvalidKeyword");
+  });
+  it("24 rejects non-http repository URL", async () => {
+    await expectPublishFail({ repository: "git@github.com:antsol/example.git" }, "InvalidUrl");
+  });
+
+  // Account sizing
+  it("25 sizes a minimal package to its contents", async () => {
+    const pkg = { ...basePackage, name: uniqueName("min"), description: "" };
+    await publish(pkg);
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+    const maxRent = await provider.connection.getMinimumBalanceForRentExemption(PACKAGE_MAX_SPACE);
+    assert.isBelow(info!.lamports, maxRent / 3, "minimal package should cost well under a max-size account");
+  });
+  it("26 fits maximum-length fields", async () => {
+    // Strings at their limits; names are PDA seeds, so at most 32 bytes.
+    // One dependency keeps the transaction under 1232 bytes.
+    const long = (n: number) => Array.from({ length: n }, (_, i) => "abcdefghijklmnopqrstuvwxyz"[i % 26]).join("");
+    const name = `${uniqueName("max")}-${long(32)}`.slice(0, 32);
+    const pkg = {
+      ...basePackage,
+      name,
+      version: `1.0.0-${long(26)}`,
+      description: "d".repeat(256),
+      dependencies: [{ name: long(32), version: `2.0.0+${long(26)}` }],
+      repository: `https://${long(120)}`,
+      homepage: `https://${long(120)}`,
+      keywords: ["k1", "k2", "k3", "k4", long(20)],
+    };
+    await publish(pkg);
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.description, pkg.description);
+    assert.deepEqual(acct.keywords, pkg.keywords);
+  });
+
+  // Semver prereleases and build metadata
+  it("27 orders prereleases by semver precedence on update", async () => {
+    const name = uniqueName("pre");
+    // The precedence example from semver.org section 11, plus build metadata
+    const chain = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2", "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1-0+build.5"];
+    await publish({ ...basePackage, name, version: chain[0] });
+    for (let i = 1; i < chain.length; i++) {
+      await update(name, chain[i - 1], chain[i]);
+    }
+    const acct = await fetchPackage(name, "1.0.0-rc.1");
+    assert.equal(acct.version, "1.0.0-rc.1");
+
+    // Lower or equal precedence is refused, even when the strings differ
+    for (const lower of ["1.0.0-rc.2", "1.0.0", "1.0.1-0+other"]) {
+      try {
+        await update(name, "1.0.1-0+build.5", lower);
+        assert.fail(`update to ${lower} should have failed`);
+      } catch (e: any) {
+        assert.include(e.toString(), "VersionNotGreater");
+      }
+    }
+  });
+  it("28 rejects malformed prereleases", async () => {
+    for (const version of ["1.0.0-", "1.0.0-rc..1", "1.0.0-01", "01.0.0", "1.0.0+"]) {
+      await expectPublishFail({ version }, "InvalidVersionFormat");
+    }
+  });
+
+  // Scoped names and namespaces
+  it("29 publishes a scoped name as the namespace owner", async () => {
+    const scope = uniqueName("ns").slice(0, 20);
+    await registerNamespace(scope);
+    const [namespacePda] = getNamespacePDA(scope);
+    const namespace = await (program.account as any)["namespace"].fetch(namespacePda);
+    assert.equal(namespace.scope, scope);
+    assert.ok(namespace.owner.equals(authority.publicKey));
+
+    const pkg = { ...basePackage, name: `${scope}/utils` };
+    await publishScoped(pkg);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.name, pkg.name);
+  });
+  it("30 rejects a scoped name without its namespace", async () => {
+    const scope = uniqueName("ns").slice(0, 20);
+    try {
+      await publishScoped({ ...basePackage, name: `${scope}/utils` }, undefined, false);
+      assert.fail("publish without a namespace should have failed");
+    } catch (e: any) {
+      assert.include(e.toString(), "NamespaceRequired");
+    }
+  });
+  it("31 only lets the owner and listed publishers use a scope", async () => {
+    const scope = uniqueName("ns").slice(0, 20);
+    await registerNamespace(scope);
+    const outsider = await fundedKeypair();
+    const pkg = { ...basePackage, name: `${scope}/shared` };
+    try {
+      await publishScoped(pkg, outsider);
+      assert.fail("publish by a non-publisher should have failed");
+    } catch (e: any) {
+      assert.include(e.toString(), "UnauthorizedNamespacePublisher");
+    }
+
+    const [namespace] = getNamespacePDA(scope);
+    await (program.methods as any)
+      .setNamespacePublishers(scope, [outsider.publicKey])
+      .accounts({ owner: authority.publicKey, namespace })
+      .rpc();
+    await publishScoped(pkg, outsider);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.ok(acct.authority.equals(outsider.publicKey));
+  });
+  it("32 rejects malformed scoped names", async () => {
+    for (const name of ["a/b/c", "/pkg", "scope/", "Scope/pkg", "-scope/pkg"]) {
+      await expectPublishFail({ name }, "InvalidNameFormat");
+    }
+  });
+
+  // Maintainers
+  it("33 lets a listed maintainer publish updates", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    const [maintainersPda] = getMaintainersPDA(pkg.name);
+    const list = await (program.account as any)["maintainers"].fetch(maintainersPda);
+    assert.equal(list.name, pkg.name);
+    assert.ok(list.authority.equals(authority.publicKey));
+    assert.equal(list.maintainers.length, 1);
+
+    await updateAsMaintainer(maintainer, pkg.name, "1.0.0", "1.1.0");
+    const acct = await fetchPackage(pkg.name, "1.1.0");
+    // Ownership stays with the authority
+    assert.ok(acct.authority.equals(authority.publicKey));
+
+    try {
+      await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+      assert.fail("adding a listed maintainer twice should have failed");
+    } catch (e: any) {
+      assert.include(e.toString(), "MaintainerAlreadyListed");
+    }
+  });
+  it("34 stops removed maintainers and outsiders", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    try {
+      await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey, maintainer);
+      assert.fail("a non-authority should not be able to add maintainers");
+    } catch (e: any) {
+      assert.include(e.toString(), "UnauthorizedAuthority");
+    }
+
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    await changeMaintainer("removeMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    try {
+      await updateAsMaintainer(maintainer, pkg.name, "1.0.0", "1.1.0");
+      assert.fail("a removed maintainer should not be able to update");
+    } catch (e: any) {
+      assert.include(e.toString(), "UnauthorizedAuthority");
+    }
+  });
+  it("35 caps the maintainer list", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    for (let i = 0; i < 5; i++) {
+      await changeMaintainer("addMaintainer", pkg.name, "1.0.0", anchor.web3.Keypair.generate().publicKey);
+    }
+    try {
+      await changeMaintainer("addMaintainer", pkg.name, "1.0.0", anchor.web3.Keypair.generate().publicKey);
+      assert.fail("a sixth maintainer should have been rejected");
+    } catch (e: any) {
+      assert.include(e.toString(), "TooManyMaintainers");
+    }
+  });
+  it("36 keeps transfer_authority owner-only", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    const [pda] = getPackagePDA(pkg.name, "1.0.0");
+    try {
+      await (program.methods as any)
+        .transferAuthority(pkg.name, "1.0.0")
+        .accounts({ currentAuthority: maintainer.publicKey, package: pda, newAuthority: maintainer.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+        .signers([maintainer])
+        .rpc();
+      assert.fail("a maintainer should not be able to transfer authority");
+    } catch (e: any) {
+      assert.include(e.toString(), "UnauthorizedAuthority");
+    }
+  });
+
+  // README CID
+  it("37 stores a README CID and sizes the account for it", async () => {
+    const pkg = { ...basePackage, name: uniqueName("readme"), readmeCid: "QmVxA7HQNcDfk9Ncm5LcPShjqFGSzFdRV4wMb3Y8jkTsx1" };
+    await publish(pkg);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.readmeCid[0], pkg.readmeCid);
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+  });
+  it("38 rejects an invalid README CID", async () => {
+    await expectPublishFail({ readmeCid: "not-a-cid" }, "InvalidReadmeCid");
+  });
+
+  // CID syntax
+  it("39 accepts a CIDv1 with the raw codec", async () => {
+    const pkg = { ...basePackage, name: uniqueName("cidv1"), ipfsCid: "bafkreigxion64jdxhpf7uliks6kh5y3ce6yq2ebcwgsvqr7jfclfxnv73y" };
+    await publish(pkg);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.ipfsCid, pkg.ipfsCid);
+  });
+  it("40 rejects CIDs with a valid prefix that don't decode", async () => {
+    await expectPublishFail({ ipfsCid: "Qmxxxx" }, "InvalidCidFormat");
+    await expectPublishFail({ ipfsCid: "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzd" }, "InvalidCidFormat");
+  });
+
+  // External dependencies
+  it("41 stores external dependencies and sizes the account for them", async () => {
+    const pkg = {
+      ...basePackage,
+      name: uniqueName("extdeps"),
+      externalDependencies: [
+        { name: "serde", version: "^1.0", depType: "rust", registry: "crates.io" },
+        { name: "@types/node", version: ">=18", depType: "npm", registry: null },
+      ],
+    };
+    await publish(pkg);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.deepEqual(acct.externalDependencies[0], pkg.externalDependencies);
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+  });
+  it("42 rejects invalid external dependencies", async () => {
+    const dep = { name: "serde", version: "^1.0", depType: "rust", registry: null };
+    await expectPublishFail({ externalDependencies: Array(11).fill(dep) }, "TooManyExternalDependencies");
+    await expectPublishFail({ externalDependencies: [{ ...dep, depType: "cobol" }] }, "InvalidExternalDependencyType");
+    await expectPublishFail({ externalDependencies: [{ ...dep, name: "" }] }, "InvalidExternalDependencyName");
+    await expectPublishFail({ externalDependencies: [{ ...dep, version: ">=1.2 <2" }] }, "InvalidExternalDependencyVersion");
+    await expectPublishFail({ externalDependencies: [{ ...dep, registry: "crates|io" }] }, "InvalidExternalDependencyRegistry");
+  });
+
+  // On-chain download counter
+  it("43 counts downloads from any wallet without resizing new accounts", async () => {
+    const pkg = { ...basePackage, name: uniqueName("dl") };
+    await publish(pkg);
+    await recordDownload(pkg.name, pkg.version);
+    await recordDownload(pkg.name, pkg.version, await fundedKeypair());
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.equal(acct.downloads[0].toNumber(), 2);
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+  });
+  it("44 rejects recording a download of an unpublished version", async () => {
+    try {
+      await recordDownload(uniqueName("nope"), "1.0.0");
+      assert.fail("recording a download of a missing package should fail");
+    } catch (e: any) {
+      assert.include(e.toString(), "AccountNotInitialized");
+    }
+  });
+
+  // Frozen packages
+  it("45 freezes a version by burning its authority", async () => {
+    const pkg = { ...basePackage, name: uniqueName("frz") };
+    await publish(pkg);
+    const outsider = await fundedKeypair();
+    try {
+      await freeze(pkg.name, pkg.version, outsider);
+      assert.fail("only the authority should be able to freeze");
+    } catch (e: any) {
+      assert.include(e.toString(), "UnauthorizedAuthority");
+    }
+    assert.isFalse((await fetchPackage(pkg.name, pkg.version)).frozen[0]);
+
+    await freeze(pkg.name, pkg.version);
+    const acct = await fetchPackage(pkg.name, pkg.version);
+    assert.isTrue(acct.frozen[0]);
+    assert.ok(acct.authority.equals(anchor.web3.SystemProgram.programId));
+    const [pda] = getPackagePDA(pkg.name, pkg.version);
+    const info = await provider.connection.getAccountInfo(pda);
+    assert.equal(info!.data.length, packageSpace(pkg));
+  });
+  it("46 keeps a frozen version frozen", async () => {
+    const pkg = { ...basePackage, name: uniqueName("frz") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    await freeze(pkg.name, "1.0.0");
+    const [pda] = getPackagePDA(pkg.name, "1.0.0");
+
+    await expectFrozen(() => update(pkg.name, "1.0.0", "1.1.0"), "update_package");
+    await expectFrozen(() => updateAsMaintainer(maintainer, pkg.name, "1.0.0", "1.1.0"), "a maintainer's update");
+    await expectFrozen(
+      () => (program.methods as any)
+        .transferAuthority(pkg.name, "1.0.0")
+        .accounts({ currentAuthority: authority.publicKey, package: pda, newAuthority: authority.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+        .rpc(),
+      "transfer_authority"
+    );
+    // Freezing again can't be used to take the account back either
+    await expectFrozen(() => freeze(pkg.name, "1.0.0"), "freeze_package");
+
+    const acct = await fetchPackage(pkg.name, "1.0.0");
+    assert.isTrue(acct.frozen[0]);
+    assert.ok(acct.authority.equals(anchor.web3.SystemProgram.programId));
+    assert.isNull(await provider.connection.getAccountInfo(getPackagePDA(pkg.name, "1.1.0")[0]));
+  });
+  it("47 keeps other authorities' maintainers off a package's versions", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+
+    // Anyone can publish another version of an unscoped name and list maintainers for it
+    const stranger = await fundedKeypair();
+    const [strangerPda] = getPackagePDA(pkg.name, "9.9.9");
+    await (program.methods as any)
+      .publishPackage(pkg.name, "9.9.9", pkg.ipfsCid, pkg.description, [], pkg.contentSha256, "", "", [], null, [])
+      .accounts({ authority: stranger.publicKey, package: strangerPda, systemProgram: anchor.web3.SystemProgram.programId })
+      .signers([stranger])
+      .rpc();
+    await changeMaintainer("addMaintainer", pkg.name, "9.9.9", stranger.publicKey, stranger);
+
+    // That list only covers the stranger's own versions
+    for (const [listOwner, expected] of [[stranger.publicKey, "ConstraintSeeds"], [authority.publicKey, "UnauthorizedAuthority"]] as const) {
+      try {
+        await updateAsMaintainer(stranger, pkg.name, "1.0.0", "1.1.0", listOwner);
+        assert.fail("the stranger should not be able to update the author's version");
+      } catch (e: any) {
+        assert.include(e.toString(), expected);
+      }
+    }
+
+    const list = await (program.account as any)["maintainers"].fetch(getMaintainersPDA(pkg.name)[0]);
+    assert.equal(list.maintainers.length, 1);
+    assert.ok(list.maintainers[0].equals(maintainer.publicKey));
+    assert.isNull(await provider.connection.getAccountInfo(getPackagePDA(pkg.name, "1.1.0")[0]));
+  });
+  it("48 freezes only the version it was asked to", async () => {
+    // A CID of its own, so updates from 1.0.0 don't repeat it
+    const pkg = { ...basePackage, name: uniqueName("frz"), ipfsCid: "QmPWEpC8ikaHVXCGDuJ8ztDGBbJDL3QxMXmekjCZ4vwSog" };
+    await publish(pkg);
+    await update(pkg.name, "1.0.0", "1.1.0");
+    await freeze(pkg.name, "1.1.0");
+
+    await expectFrozen(() => update(pkg.name, "1.1.0", "1.2.0"), "updating from the frozen version");
+    // The older version kept its authority, so it can still be updated from
+    await update(pkg.name, "1.0.0", "1.0.1");
+    const older = await fetchPackage(pkg.name, "1.0.1");
+    assert.isFalse(older.frozen[0]);
+    assert.ok(older.authority.equals(authority.publicKey));
+    assert.isTrue((await fetchPackage(pkg.name, "1.1.0")).frozen[0]);
+  });
+  it("49 leaves the old authority's maintainers behind on transfer", async () => {
+    const pkg = { ...basePackage, name: uniqueName("team") };
+    await publish(pkg);
+    const maintainer = await fundedKeypair();
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey);
+    const newOwner = await fundedKeypair();
+    await (program.methods as any)
+      .transferAuthority(pkg.name, "1.0.0")
+      .accounts({ currentAuthority: authority.publicKey, package: getPackagePDA(pkg.name, "1.0.0")[0], newAuthority: newOwner.publicKey, systemProgram: anchor.web3.SystemProgram.programId })
+      .rpc();
+
+    try {
+      await updateAsMaintainer(maintainer, pkg.name, "1.0.0", "1.1.0");
+      assert.fail("the old authority's maintainer should not be able to update");
+    } catch (e: any) {
+      assert.include(e.toString(), "ConstraintSeeds");
+    }
+
+    // The new authority lists its own maintainers
+    await changeMaintainer("addMaintainer", pkg.name, "1.0.0", maintainer.publicKey, newOwner);
+    await updateAsMaintainer(maintainer, pkg.name, "1.0.0", "1.1.0", newOwner.publicKey);
+    assert.ok((await fetchPackage(pkg.name, "1.1.0")).authority.equals(newOwner.publicKey));
+  });
 });